@@ -0,0 +1,105 @@
+use serde::Serialize;
+use serde_json::Value;
+use sha2::{Digest, Sha256};
+
+// Canonical JSON bytes and hashes of Nautilus payloads, exactly as the enclave signs them. The
+// enclave, sui-vktool and verifiers all link this crate instead of re-serializing on their own,
+// which is where byte-for-byte mismatches (key order, whitespace, escaping) used to creep in.
+//
+// "json-sorted-keys-v1":
+//   - object members sorted by the UTF-8 bytes of their keys, at every depth;
+//   - no whitespace between tokens;
+//   - strings escaped as serde_json does: `"` `\` and control characters only, with the short
+//     forms \b \f \n \r \t and \u00XX (lowercase hex) for the rest; other characters as UTF-8;
+//   - numbers as serde_json prints them: integers in plain decimal, other numbers in their
+//     shortest round-trip form. Signed payloads only carry integers.
+
+pub const CANONICALIZATION: &str = "json-sorted-keys-v1";
+
+pub fn to_canonical_json(value: &Value) -> Vec<u8> {
+    let mut out = Vec::new();
+    write(value, &mut out);
+    out
+}
+
+// Canonical bytes of anything serializable, via its JSON value.
+pub fn canonical_bytes<T: Serialize + ?Sized>(value: &T) -> Result<Vec<u8>, serde_json::Error> {
+    Ok(to_canonical_json(&serde_json::to_value(value)?))
+}
+
+// SHA-256 of the canonical bytes, hex encoded.
+pub fn canonical_sha256_hex(value: &Value) -> String {
+    hex::encode(Sha256::digest(to_canonical_json(value)))
+}
+
+// The bytes an attestation envelope's signatures are over: the canonical form of its `data`.
+// None for an envelope that isn't an object with `data`, or predates canonical signing (v1
+// formats were signed over the service's own field order and can't be reproduced from JSON).
+pub fn signed_bytes(envelope: &Value) -> Option<Vec<u8>> {
+    let canonicalization = envelope.get("canonicalization")?.as_str()?;
+    if canonicalization != CANONICALIZATION {
+        return None;
+    }
+    Some(to_canonical_json(envelope.get("data")?))
+}
+
+fn write(value: &Value, out: &mut Vec<u8>) {
+    match value {
+        Value::Object(map) => {
+            let mut members: Vec<(&String, &Value)> = map.iter().collect();
+            members.sort_by(|a, b| a.0.as_bytes().cmp(b.0.as_bytes()));
+            out.push(b'{');
+            for (i, (key, value)) in members.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                scalar(&Value::String(key.clone()), out);
+                out.push(b':');
+                write(value, out);
+            }
+            out.push(b'}');
+        }
+        Value::Array(items) => {
+            out.push(b'[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(b',');
+                }
+                write(item, out);
+            }
+            out.push(b']');
+        }
+        _ => scalar(value, out),
+    }
+}
+
+fn scalar(value: &Value, out: &mut Vec<u8>) {
+    serde_json::to_writer(out, value).expect("writing JSON to a Vec can't fail");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_canonical_form() {
+        let value = json!({ "b": [3, { "z": null, "a": true }], "a": "tab\there \"é\" \u{1}", "B": -1 });
+        let canonical = String::from_utf8(to_canonical_json(&value)).unwrap();
+        assert_eq!(canonical, r#"{"B":-1,"a":"tab\there \"é\" \u0001","b":[3,{"a":true,"z":null}]}"#);
+        // Parsing and re-serializing the canonical form is a fixed point.
+        let reparsed: Value = serde_json::from_str(&canonical).unwrap();
+        assert_eq!(to_canonical_json(&reparsed), canonical.as_bytes());
+        assert_eq!(canonical_sha256_hex(&json!({})), "44136fa355b3678a1146ad16f7e8649e94fb4fc21fe77e8310c060f61caaff8a");
+
+        #[derive(Serialize)]
+        struct Data {
+            z: u8,
+            a: u8,
+        }
+        assert_eq!(canonical_bytes(&Data { z: 1, a: 2 }).unwrap(), br#"{"a":2,"z":1}"#);
+        let envelope = json!({ "canonicalization": CANONICALIZATION, "data": { "z": 1, "a": 2 } });
+        assert_eq!(signed_bytes(&envelope).unwrap(), br#"{"a":2,"z":1}"#);
+        assert!(signed_bytes(&json!({ "format": "ed25519-v1", "data": {} })).is_none());
+    }
+}
@@ -0,0 +1,118 @@
+// Generated from the Rust types by `zkdatavault-nautilus schemas`; do not edit.
+
+export interface AttestationEnvelope {
+  canonicalization?: string | null;
+  cosignature?: Cosignature | null;
+  data: AttestationData;
+  format: string;
+  key_id?: string | null;
+  nsm_document_b64?: string | null;
+  payload_sha256?: string | null;
+  platform_evidence_b64?: string | null;
+  public_key_b64?: string | null;
+  signature_b64?: string | null;
+}
+
+export type Aggregate = "weighted_mean" | "weighted_geometric" | "minimum";
+
+export interface AttestationData {
+  blob_id: string;
+  category?: string;
+  config_hash?: string;
+  content_sha256?: string | null;
+  degradations?: string[];
+  drift?: DriftReport | null;
+  enclave_measurement: string;
+  evidence?: Record<string, string>;
+  expires_at?: number | null;
+  nonce?: string | null;
+  pcrs?: Record<string, string>;
+  plaintext_sha256?: string | null;
+  poisoning_risk?: number | null;
+  quality_score: number;
+  report_sha256?: string | null;
+  sampling?: SampleInfo | null;
+  scoring?: ScoringSummary | null;
+  screening?: ScreeningReport | null;
+  source_type?: string;
+  sui_object_id?: string | null;
+  timestamp: number;
+  walrus_profile?: string;
+}
+
+export interface Cosignature {
+  digest_hex: string;
+  key_id: string;
+  public_key_b64: string;
+  signature_b64: string;
+}
+
+export interface DriftReport {
+  drift_score: number;
+  features: Record<string, FeatureDrift>;
+  level: string;
+  reference_blob_id: string;
+  reference_content_sha256: string;
+  reference_job_id: string;
+}
+
+export interface FeatureDrift {
+  kl_ppm: number;
+  psi_ppm: number;
+}
+
+export interface Interval {
+  estimate: number;
+  high: number;
+  low: number;
+}
+
+export interface RandomnessBeacon {
+  committed_round: number;
+  epoch: number;
+  object_id: string;
+  object_version: number;
+  random_bytes: string;
+  round: number;
+  source: string;
+}
+
+export interface SampleInfo {
+  beacon?: RandomnessBeacon | null;
+  estimate?: ScoreEstimate | null;
+  method: string;
+  records_sampled: number;
+  records_seen: number;
+  sample_size: number;
+  seed: number;
+}
+
+export interface ScoreEstimate {
+  checks: Record<string, Interval>;
+  chunk_bytes: number;
+  chunks_scored: number;
+  confidence_pct: number;
+  score: Interval;
+}
+
+export interface ScoringSummary {
+  aggregate: Aggregate;
+  below_minimum?: string[];
+  declared_parameters?: number | null;
+  json_schema_sha256?: string | null;
+  label_column?: string | null;
+  minimums?: Record<string, number>;
+  overridden?: boolean;
+  plugins?: Record<string, string>;
+  timestamp_column?: string | null;
+  weights: Record<string, number>;
+}
+
+export type ScreeningMode = "off" | "flag" | "fail";
+
+export interface ScreeningReport {
+  findings?: Record<string, number>;
+  flagged: boolean;
+  known_bad_hash?: boolean;
+  mode: ScreeningMode;
+}
@@ -0,0 +1,279 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ring::rand::{SecureRandom, SystemRandom};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::listing::Listed;
+
+// Tenant API keys. Tokens look like `zvk_<key_id>_<secret>`; the store (NAUTILUS_API_KEYS_FILE)
+// keeps only a per-key salt and SHA-256(salt || secret), so a leaked store cannot be replayed.
+// Keys are managed offline with `zkdatavault-nautilus keys ...` (see `run_cli`); the server
+// re-reads the file when it changes, so revocations apply without a restart.
+
+const TOKEN_PREFIX: &str = "zvk";
+pub const SCOPES: [&str; 3] = ["verify", "policy", "admin"];
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KeyRecord {
+    pub key_id: String,
+    pub tenant: String,
+    pub scopes: Vec<String>,
+    pub created_ms: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub revoked_ms: Option<u64>,
+    // Key this one replaced when minted by `rotate`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub rotated_from: Option<String>,
+    salt: String,
+    hash: String,
+}
+
+// Public view of a key for audit exports and GET /admin/keys: no salt, no hash.
+#[derive(Debug, Serialize)]
+pub struct PublicKeyEntry {
+    pub key_id: String,
+    pub tenant: String,
+    pub scopes: Vec<String>,
+    // "active" or "revoked".
+    pub status: &'static str,
+    pub created_ms: u64,
+    pub revoked_ms: Option<u64>,
+    pub rotated_from: Option<String>,
+}
+
+impl Listed for PublicKeyEntry {
+    fn id(&self) -> &str {
+        &self.key_id
+    }
+
+    fn status(&self) -> &str {
+        self.status
+    }
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct KeyFile {
+    pub keys: Vec<KeyRecord>,
+}
+
+impl KeyFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        match std::fs::read(path) {
+            Ok(raw) => serde_json::from_slice(&raw).with_context(|| format!("parse {}", path.display())),
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => Ok(Self::default()),
+            Err(err) => Err(err).with_context(|| format!("read {}", path.display())),
+        }
+    }
+
+    // Write via a temp file and rename so the server never reads a half-written store.
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let tmp = path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_vec_pretty(self)?).with_context(|| format!("write {}", tmp.display()))?;
+        std::fs::rename(&tmp, path).with_context(|| format!("replace {}", path.display()))
+    }
+
+    // Returns the new record and the plaintext token, which is shown once and never stored.
+    pub fn mint(&mut self, tenant: &str, scopes: &[String]) -> Result<(KeyRecord, String)> {
+        if tenant.is_empty() {
+            bail!("tenant must not be empty");
+        }
+        if let Some(bad) = scopes.iter().find(|s| !SCOPES.contains(&s.as_str())) {
+            bail!("unknown scope '{}' (expected one of {:?})", bad, SCOPES);
+        }
+        let key_id = hex::encode(random_bytes::<6>()?);
+        let secret = URL_SAFE_NO_PAD.encode(random_bytes::<32>()?);
+        let salt = random_bytes::<16>()?;
+        let rec = KeyRecord {
+            key_id: key_id.clone(),
+            tenant: tenant.to_string(),
+            scopes: scopes.to_vec(),
+            created_ms: now_ms(),
+            revoked_ms: None,
+            rotated_from: None,
+            salt: hex::encode(salt),
+            hash: salted_hash(&salt, &secret),
+        };
+        self.keys.push(rec.clone());
+        Ok((rec, format!("{}_{}_{}", TOKEN_PREFIX, key_id, secret)))
+    }
+
+    pub fn revoke(&mut self, key_id: &str) -> Result<()> {
+        let rec = self.keys.iter_mut().find(|k| k.key_id == key_id).ok_or_else(|| anyhow!("no key {}", key_id))?;
+        rec.revoked_ms.get_or_insert_with(now_ms);
+        Ok(())
+    }
+
+    // Mint a replacement with the same tenant and scopes, then revoke the old key.
+    pub fn rotate(&mut self, key_id: &str) -> Result<(KeyRecord, String)> {
+        let old = self.find_active(key_id).ok_or_else(|| anyhow!("no active key {}", key_id))?.clone();
+        let (_, token) = self.mint(&old.tenant, &old.scopes)?;
+        let rec = self.keys.last_mut().expect("just minted");
+        rec.rotated_from = Some(old.key_id.clone());
+        let rec = rec.clone();
+        self.revoke(&old.key_id)?;
+        Ok((rec, token))
+    }
+
+    pub fn registry(&self) -> Vec<PublicKeyEntry> {
+        self.keys
+            .iter()
+            .map(|k| PublicKeyEntry {
+                key_id: k.key_id.clone(),
+                tenant: k.tenant.clone(),
+                scopes: k.scopes.clone(),
+                status: if k.revoked_ms.is_some() { "revoked" } else { "active" },
+                created_ms: k.created_ms,
+                revoked_ms: k.revoked_ms,
+                rotated_from: k.rotated_from.clone(),
+            })
+            .collect()
+    }
+
+    // The active key a token belongs to, if the token is genuine.
+    pub fn authenticate(&self, token: &str) -> Option<&KeyRecord> {
+        let rest = token.strip_prefix(TOKEN_PREFIX)?.strip_prefix('_')?;
+        let (key_id, secret) = rest.split_once('_')?;
+        let rec = self.find_active(key_id)?;
+        let salt = hex::decode(&rec.salt).ok()?;
+        constant_time_eq(salted_hash(&salt, secret).as_bytes(), rec.hash.as_bytes()).then_some(rec)
+    }
+
+    fn find_active(&self, key_id: &str) -> Option<&KeyRecord> {
+        self.keys.iter().find(|k| k.key_id == key_id && k.revoked_ms.is_none())
+    }
+}
+
+// Server-side view of the key file, reloaded whenever its mtime changes.
+pub struct ApiKeyStore {
+    path: PathBuf,
+    cached: Mutex<(Option<SystemTime>, KeyFile)>,
+}
+
+impl ApiKeyStore {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let keys = KeyFile::load(&path)?;
+        info!(path = %path.display(), keys = keys.keys.len(), "API key enforcement enabled");
+        let mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        Ok(Self { path, cached: Mutex::new((mtime, keys)) })
+    }
+
+    // Tenant of the token if it is valid and carries `scope`.
+    pub fn authorize(&self, token: &str, scope: &str) -> Option<String> {
+        let cached = self.current();
+        let rec = cached.1.authenticate(token)?;
+        rec.scopes.iter().any(|s| s == scope).then(|| rec.tenant.clone())
+    }
+
+    // Every key, revoked ones included, for GET /admin/keys.
+    pub fn registry(&self) -> Vec<PublicKeyEntry> {
+        self.current().1.registry()
+    }
+
+    fn current(&self) -> MutexGuard<'_, (Option<SystemTime>, KeyFile)> {
+        let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        let mtime = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if mtime != cached.0 {
+            match KeyFile::load(&self.path) {
+                Ok(keys) => *cached = (mtime, keys),
+                // Keep serving the last good copy rather than locking everyone out.
+                Err(err) => warn!(%err, "Failed to reload API key file"),
+            }
+        }
+        cached
+    }
+}
+
+// `zkdatavault-nautilus keys <mint|list|rotate|revoke|export> ...` against NAUTILUS_API_KEYS_FILE.
+pub fn run_cli(args: &[String]) -> Result<()> {
+    let path = PathBuf::from(std::env::var("NAUTILUS_API_KEYS_FILE").unwrap_or_else(|_| "api_keys.json".into()));
+    let mut file = KeyFile::load(&path)?;
+    let flag = |name: &str| -> Vec<String> {
+        args.windows(2).filter(|w| w[0] == name).map(|w| w[1].clone()).collect()
+    };
+    match args.first().map(String::as_str) {
+        Some("mint") => {
+            let tenant = flag("--tenant").pop().ok_or_else(|| anyhow!("--tenant is required"))?;
+            let mut scopes = flag("--scope");
+            if scopes.is_empty() {
+                scopes.push("verify".into());
+            }
+            let (rec, token) = file.mint(&tenant, &scopes)?;
+            file.save(&path)?;
+            println!("key_id: {}\ntenant: {}\nscopes: {}\ntoken:  {}", rec.key_id, rec.tenant, rec.scopes.join(","), token);
+            println!("Store the token now; only its salted hash is kept.");
+        }
+        Some("rotate") => {
+            let key_id = args.get(1).ok_or_else(|| anyhow!("usage: keys rotate <key_id>"))?;
+            let (rec, token) = file.rotate(key_id)?;
+            file.save(&path)?;
+            println!("key_id: {} (replaces {})\ntoken:  {}", rec.key_id, key_id, token);
+        }
+        Some("revoke") => {
+            let key_id = args.get(1).ok_or_else(|| anyhow!("usage: keys revoke <key_id>"))?;
+            file.revoke(key_id)?;
+            file.save(&path)?;
+            println!("revoked {}", key_id);
+        }
+        Some("list") => {
+            for k in &file.keys {
+                let state = if k.revoked_ms.is_some() { "revoked" } else { "active" };
+                println!("{}  {:<8} {:<20} {}", k.key_id, state, k.tenant, k.scopes.join(","));
+            }
+        }
+        Some("export") => println!("{}", serde_json::to_string_pretty(&file.registry())?),
+        _ => bail!("usage: keys <mint --tenant T [--scope S]... | list | rotate ID | revoke ID | export>"),
+    }
+    Ok(())
+}
+
+fn salted_hash(salt: &[u8], secret: &str) -> String {
+    let mut h = Sha256::new();
+    h.update(salt);
+    h.update(secret.as_bytes());
+    hex::encode(h.finalize())
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0u8, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+fn random_bytes<const N: usize>() -> Result<[u8; N]> {
+    let mut buf = [0u8; N];
+    SystemRandom::new().fill(&mut buf).map_err(|_| anyhow!("OS randomness unavailable"))?;
+    Ok(buf)
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mint_authenticate_rotate_revoke() {
+        let mut file = KeyFile::default();
+        let (rec, token) = file.mint("acme", &["verify".to_string()]).unwrap();
+        assert_eq!(file.authenticate(&token).unwrap().key_id, rec.key_id);
+        // The secret itself may contain '_', so split off only the prefix and key id.
+        let secret = token.splitn(3, '_').nth(2).unwrap();
+        assert!(!serde_json::to_string(&file).unwrap().contains(secret));
+        assert!(file.authenticate(&format!("{}x", token)).is_none());
+        assert!(file.mint("acme", &["root".to_string()]).is_err());
+
+        let (new, new_token) = file.rotate(&rec.key_id).unwrap();
+        assert!(file.authenticate(&token).is_none());
+        assert_eq!(file.authenticate(&new_token).unwrap().rotated_from.as_deref(), Some(rec.key_id.as_str()));
+
+        file.revoke(&new.key_id).unwrap();
+        assert!(file.authenticate(&new_token).is_none());
+        assert_eq!(file.registry().len(), 2);
+    }
+}
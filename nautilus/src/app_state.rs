@@ -0,0 +1,138 @@
+use anyhow::{Context, Result};
+use std::sync::Arc;
+
+use crate::api_keys::ApiKeyStore;
+use crate::arbitration::Arbitration;
+use crate::archive::Archiver;
+use crate::audit::AuditLog;
+use crate::blob_source::{BlobSource, RoutedSource};
+use crate::config::Config;
+use crate::content_policy::ContentPolicies;
+use crate::cosign::OperatorSigner;
+use crate::dedupe::DedupeIndex;
+use crate::drift::DriftStore;
+use crate::escrow::Escrow;
+use crate::evidence::EvidenceStore;
+use crate::http_source::HttpSource;
+use crate::ipfs_source::IpfsSource;
+use crate::s3_source::S3Source;
+use crate::screening::BadHashes;
+use crate::submission::Submitter;
+use crate::jobs::JobRegistry;
+use crate::key_ring::KeyRing;
+use crate::key_usage::KeyUsageMonitor;
+use crate::tee_attestation::{self, Attester, TeeAttester};
+use crate::transcript::Transcript;
+use crate::walrus_client::WalrusClient;
+use crate::watchdog::Watchdog;
+
+// Everything request handlers need, built once at startup and shared across connections.
+// Collaborators are trait objects so tests can inject fakes.
+pub struct AppState {
+    pub config: Config,
+    // `config.hash()`, computed once; attested with every verification.
+    pub config_hash: String,
+    pub blobs: Arc<dyn BlobSource>,
+    pub attester: Arc<dyn Attester>,
+    // Service signing keys, shared with the attester: `current()` signs attestations, badges and
+    // submissions; retired keys stay listed for verification through their grace window.
+    pub keys: Arc<KeyRing>,
+    // Per-key signature accounting; every use of a signing key goes through it first.
+    pub key_usage: Arc<KeyUsageMonitor>,
+    pub jobs: JobRegistry,
+    pub audit: AuditLog,
+    pub dedupe: DedupeIndex,
+    // Distribution profiles of verified datasets, for drift against a reference.
+    pub drift: DriftStore,
+    pub archive: Archiver,
+    // Hash-chained record of every attestation issued.
+    pub transcript: Transcript,
+    // Results held back until their release time or on-chain event.
+    pub escrow: Escrow,
+    // Sealed per-check evidence behind published scores.
+    pub evidence: EvidenceStore,
+    // Delivered reports kept for dispute exports.
+    pub arbitration: Arbitration,
+    // Records completed verifications on chain in the background (no-op unless configured).
+    pub submitter: Submitter,
+    // None disables API key checks (e.g. a single-tenant deployment behind its own gateway).
+    pub api_keys: Option<ApiKeyStore>,
+    // Per-tenant content policies; nothing is refused by content when unset.
+    pub content_policies: Option<ContentPolicies>,
+    pub bad_hashes: BadHashes,
+    // Drains and restarts the instance past its RSS/descriptor limits.
+    pub watchdog: Watchdog,
+    // Concrete Walrus client kept for capability reporting (pool size, cache status).
+    pub walrus: Option<Arc<WalrusClient>>,
+}
+
+impl AppState {
+    pub async fn build(config: Config) -> Result<Self> {
+        let walrus = Arc::new(WalrusClient::new(&config.walrus)?);
+        tee_attestation::tpm::init(&config.tpm);
+        let keypair = tee_attestation::load_signing_key(&config.kms, config.sealed_dir.as_deref()).await;
+        let keypair = keypair.context("Failed to initialize signing key")?;
+        let keys = Arc::new(KeyRing::new(
+            keypair,
+            config.key_rotation.clone(),
+            config.sealed_dir.as_deref(),
+            config.kms.clone(),
+        ));
+        let key_usage = Arc::new(KeyUsageMonitor::new(config.key_usage.clone()));
+        let jobs = JobRegistry::new(config.job_memory_cap);
+        let audit = AuditLog::new(config.audit_capacity);
+        let config_hash = config.hash();
+        let dedupe = DedupeIndex::open(config.sealed_dir.as_deref(), config.dedupe.clone())
+            .context("Failed to open dedupe index")?;
+        let drift = DriftStore::open(config.sealed_dir.as_deref(), &config.dedupe).context("Failed to open drift store")?;
+        let archive = Archiver::open(config.sealed_dir.as_deref(), config.archive.clone())
+            .context("Failed to open audit archive index")?;
+        let transcript =
+            Transcript::open(config.sealed_dir.as_deref()).context("Failed to open attestation transcript")?;
+        let escrow = Escrow::open(config.sealed_dir.as_deref(), config.escrow.clone())
+            .context("Failed to open escrow store")?;
+        let evidence = EvidenceStore::open(config.sealed_dir.as_deref(), &config.evidence)
+            .context("Failed to open evidence store")?;
+        let arbitration = Arbitration::open(config.sealed_dir.as_deref(), &config.arbitration)
+            .context("Failed to open arbitration store")?;
+        let submitter = Submitter::new(config.submission.clone());
+        let api_keys = config.api_keys_file.clone().map(ApiKeyStore::open).transpose()?;
+        let content_policies = config.content_policy_file.clone().map(ContentPolicies::open).transpose()?;
+        let bad_hashes = BadHashes::load(&config.screening).context("Failed to load known-bad hash list")?;
+        let watchdog = Watchdog::new(config.watchdog.clone());
+        let cosigner = OperatorSigner::from_config(&config.cosign)?.map(Arc::new);
+        let attester = TeeAttester::new(
+            keys.clone(),
+            config.signature_scheme,
+            config.payload_encoding,
+            key_usage.clone(),
+            cosigner,
+        );
+        let http = HttpSource::new(&config.http_source, &config.walrus, walrus.egress_proxy())?.map(Arc::new);
+        let s3 = S3Source::new(&config.s3, &config.walrus, walrus.egress_proxy())?.map(Arc::new);
+        let ipfs = IpfsSource::new(&config.ipfs, &config.walrus, walrus.egress_proxy())?.map(Arc::new);
+        Ok(Self {
+            config,
+            config_hash,
+            blobs: Arc::new(RoutedSource { walrus: walrus.clone(), http, s3, ipfs }),
+            attester: Arc::new(attester),
+            keys,
+            key_usage,
+            jobs,
+            audit,
+            dedupe,
+            drift,
+            archive,
+            transcript,
+            escrow,
+            evidence,
+            arbitration,
+            submitter,
+            api_keys,
+            content_policies,
+            bad_hashes,
+            watchdog,
+            walrus: Some(walrus),
+        })
+    }
+}
@@ -0,0 +1,289 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{PublicKey, Signature, Signer, Verifier};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+use crate::app_state::AppState;
+use crate::archive::ArchiveEntry;
+use crate::audit::AuditRecord;
+use crate::evidence::Evidence;
+use crate::metrics;
+use crate::sealed;
+use crate::tee_attestation::key_id;
+
+// Dispute bundles for marketplace arbitration. Every delivered verification keeps its full
+// response (attestation included) in arbitration.sqlite in the sealed dir, with the ed25519 keys
+// of the parties named in the request. POST /disputes/export hands out a signed bundle of the
+// response, the sealed evidence artifacts (see evidence), the sampling proof and the audit
+// entries for the job, when the request is signed by every party or by an arbiter key
+// (NAUTILUS_ARBITER_PUBLIC_KEYS).
+//
+// Request signatures are over "nautilus-dispute-export-v1\n<job_id>\n<expires_ms>" and are
+// accepted until `expires_ms`, at most an hour ahead.
+
+const FORMAT: &str = "nautilus-dispute-bundle-v1";
+const REQUEST_DOMAIN: &str = "nautilus-dispute-export-v1";
+const MAX_REQUEST_TTL_MS: u64 = 3_600_000;
+
+#[derive(Debug, thiserror::Error)]
+#[error("UNAUTHORIZED: {0}")]
+pub struct ExportDenied(pub String);
+
+#[derive(Debug, Clone, Default)]
+pub struct ArbitrationConfig {
+    // Base64 ed25519 public keys of arbiters who may export any job's bundle on their own.
+    pub arbiter_keys: Vec<String>,
+}
+
+impl ArbitrationConfig {
+    pub fn from_env() -> Self {
+        let arbiter_keys = env::var("NAUTILUS_ARBITER_PUBLIC_KEYS")
+            .unwrap_or_default()
+            .split(',')
+            .map(str::trim)
+            .filter(|k| !k.is_empty())
+            .map(String::from)
+            .collect();
+        Self { arbiter_keys }
+    }
+}
+
+pub fn parse_public_key(b64: &str) -> Result<PublicKey> {
+    STANDARD
+        .decode(b64)
+        .ok()
+        .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+        .with_context(|| format!("'{}' is not a base64 ed25519 public key", b64))
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ExportRequest {
+    pub job_id: String,
+    pub expires_ms: u64,
+    pub signatures: Vec<RequestSignature>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct RequestSignature {
+    pub public_key_b64: String,
+    pub signature_b64: String,
+}
+
+pub fn request_message(job_id: &str, expires_ms: u64) -> Vec<u8> {
+    format!("{}\n{}\n{}", REQUEST_DOMAIN, job_id, expires_ms).into_bytes()
+}
+
+#[derive(Serialize)]
+struct DisputeBundle<'a> {
+    format: &'static str,
+    created_ms: u64,
+    job_id: &'a str,
+    blob_id: &'a str,
+    // "arbiter" or "parties", with the key IDs that signed the request.
+    authorized_by: &'static str,
+    signers: Vec<String>,
+    parties: &'a [String],
+    config_hash: &'a str,
+    // The verification response as delivered, attestation included.
+    report: &'a serde_json::Value,
+    // Seed (and randomness round, when drawn from Sui) of the record sample the score used.
+    sampling: Option<&'a serde_json::Value>,
+    evidence: &'a [Evidence],
+    audit: Option<&'a AuditRecord>,
+    archive: Option<&'a ArchiveEntry>,
+}
+
+// What is returned. `bundle` is the exact JSON text that was signed.
+#[derive(Serialize)]
+pub struct SignedBundle {
+    pub bundle: String,
+    pub key_id: String,
+    pub public_key_b64: String,
+    pub signature_b64: String,
+}
+
+pub struct Arbitration {
+    conn: Mutex<Connection>,
+    arbiters: Vec<PublicKey>,
+}
+
+impl Arbitration {
+    pub fn open(sealed_dir: Option<&Path>, config: &ArbitrationConfig) -> Result<Self> {
+        let arbiters = config
+            .arbiter_keys
+            .iter()
+            .map(|k| parse_public_key(k).context("NAUTILUS_ARBITER_PUBLIC_KEYS"))
+            .collect::<Result<Vec<_>>>()?;
+        let conn = sealed::open_db(sealed_dir, "arbitration.sqlite")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS reports (
+                 job_id TEXT PRIMARY KEY,
+                 blob_id TEXT NOT NULL,
+                 parties TEXT NOT NULL,
+                 report TEXT NOT NULL,
+                 created_ms INTEGER NOT NULL
+             );",
+        )
+        .context("initialize arbitration store")?;
+        Ok(Self { conn: Mutex::new(conn), arbiters })
+    }
+
+    // Keep a delivered verification for later disputes.
+    pub fn record(&self, job_id: &str, blob_id: &str, parties: &[String], report: &serde_json::Value) -> Result<()> {
+        self.conn
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .execute(
+                "INSERT OR REPLACE INTO reports (job_id, blob_id, parties, report, created_ms) VALUES (?1, ?2, ?3, ?4, ?5)",
+                params![job_id, blob_id, serde_json::to_string(parties)?, report.to_string(), now_ms() as i64],
+            )
+            .context("store report for arbitration")?;
+        Ok(())
+    }
+
+    // (blob ID, parties, report) of a recorded job.
+    fn stored(&self, job_id: &str) -> Result<Option<(String, Vec<String>, serde_json::Value)>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let row = conn
+            .query_row("SELECT blob_id, parties, report FROM reports WHERE job_id = ?1", params![job_id], |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, String>(2)?))
+            })
+            .optional()?;
+        row.map(|(blob_id, parties, report)| {
+            Ok((blob_id, serde_json::from_str(&parties)?, serde_json::from_str(&report).context("decode stored report")?))
+        })
+        .transpose()
+    }
+
+    // Key IDs of the valid signers, and whether an arbiter or every party signed.
+    fn authorize(&self, req: &ExportRequest, parties: &[String]) -> Result<(&'static str, Vec<String>), ExportDenied> {
+        let now = now_ms();
+        if req.expires_ms < now || req.expires_ms > now + MAX_REQUEST_TTL_MS {
+            return Err(ExportDenied("expires_ms must be in the future and at most an hour ahead".into()));
+        }
+        let message = request_message(&req.job_id, req.expires_ms);
+        let signed: Vec<PublicKey> = req
+            .signatures
+            .iter()
+            .filter_map(|s| {
+                let key = parse_public_key(&s.public_key_b64).ok()?;
+                let sig = STANDARD.decode(&s.signature_b64).ok().and_then(|b| Signature::from_bytes(&b).ok())?;
+                key.verify(&message, &sig).is_ok().then_some(key)
+            })
+            .collect();
+        let signers = signed.iter().map(key_id).collect();
+        if signed.iter().any(|k| self.arbiters.contains(k)) {
+            return Ok(("arbiter", signers));
+        }
+        let party_keys: Vec<PublicKey> = parties.iter().filter_map(|p| parse_public_key(p).ok()).collect();
+        if party_keys.len() >= 2 && party_keys.iter().all(|p| signed.contains(p)) {
+            return Ok(("parties", signers));
+        }
+        // One answer whether the job is unknown, names too few parties or wasn't signed for, so
+        // callers without the keys learn nothing about it.
+        Err(ExportDenied("the request needs a signature from an arbiter or from every party, at least two".into()))
+    }
+
+    // None when the job was never delivered (or is still held in escrow). The request is
+    // authorized first, so only an arbiter or the parties find out which.
+    pub fn export(&self, req: &ExportRequest, state: &AppState) -> Result<Option<SignedBundle>> {
+        let stored = self.stored(&req.job_id)?;
+        let parties = stored.as_ref().map_or(&[][..], |(_, parties, _)| parties);
+        let (authorized_by, signers) = self.authorize(req, parties)?;
+        let Some((blob_id, parties, report)) = stored else {
+            return Ok(None);
+        };
+        if state.escrow.is_held(&req.job_id)? {
+            return Ok(None);
+        }
+        let evidence = state.evidence.get(&req.job_id)?;
+        let audit = state.audit.get(&req.job_id);
+        let archive = state.archive.find_job(&req.job_id)?;
+        let bundle = DisputeBundle {
+            format: FORMAT,
+            created_ms: now_ms(),
+            job_id: &req.job_id,
+            blob_id: &blob_id,
+            authorized_by,
+            signers,
+            parties: &parties,
+            config_hash: &state.config_hash,
+            report: &report,
+            sampling: report.get("sampling"),
+            evidence: &evidence,
+            audit: audit.as_ref(),
+            archive: archive.as_ref(),
+        };
+        let bundle = serde_json::to_string(&bundle).context("serialize dispute bundle")?;
+        let key = state.keys.current();
+        let kid = key_id(&key.public);
+        // Counted, but kept out of the alarm windows: export requests come from outside.
+        state.key_usage.record(&kid, "dispute")?;
+        info!(job_id = %req.job_id, authorized_by, "Exported dispute bundle");
+        metrics::inc_counter("nautilus_dispute_exports_total", "Dispute bundles exported", &[("authorized_by", authorized_by)]);
+        Ok(Some(SignedBundle {
+            key_id: kid,
+            public_key_b64: STANDARD.encode(key.public.to_bytes()),
+            signature_b64: STANDARD.encode(key.sign(bundle.as_bytes()).to_bytes()),
+            bundle,
+        }))
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::{Keypair, SecretKey};
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        Keypair { public: (&secret).into(), secret }
+    }
+
+    fn signed(job_id: &str, expires_ms: u64, signers: &[&Keypair]) -> ExportRequest {
+        let message = request_message(job_id, expires_ms);
+        let signatures = signers
+            .iter()
+            .map(|k| RequestSignature {
+                public_key_b64: STANDARD.encode(k.public.to_bytes()),
+                signature_b64: STANDARD.encode(k.sign(&message).to_bytes()),
+            })
+            .collect();
+        ExportRequest { job_id: job_id.into(), expires_ms, signatures }
+    }
+
+    #[test]
+    fn test_parties_or_arbiter_authorize() {
+        let (seller, buyer, arbiter, stranger) = (keypair(1), keypair(2), keypair(3), keypair(4));
+        let config = ArbitrationConfig { arbiter_keys: vec![STANDARD.encode(arbiter.public.to_bytes())] };
+        let arb = Arbitration::open(None, &config).unwrap();
+        let parties = [&seller, &buyer].map(|k| STANDARD.encode(k.public.to_bytes())).to_vec();
+        let expires = now_ms() + 60_000;
+
+        assert_eq!(arb.authorize(&signed("job", expires, &[&seller, &buyer]), &parties).unwrap().0, "parties");
+        assert_eq!(arb.authorize(&signed("job", expires, &[&arbiter]), &parties).unwrap().0, "arbiter");
+        assert!(arb.authorize(&signed("job", expires, &[&seller, &stranger]), &parties).is_err());
+        // Signatures are bound to the job and the expiry.
+        let mut other_job = signed("other", expires, &[&seller, &buyer]);
+        other_job.job_id = "job".into();
+        assert!(arb.authorize(&other_job, &parties).is_err());
+        assert!(arb.authorize(&signed("job", now_ms() - 1, &[&arbiter]), &parties).is_err());
+        // One named party can't stand in for both sides.
+        assert!(arb.authorize(&signed("job", expires, &[&seller]), &parties[..1]).is_err());
+        // A stranger is told the same for a job that doesn't exist (no parties) as for one that does.
+        let denied = |parties: &[String]| arb.authorize(&signed("job", expires, &[&stranger]), parties).unwrap_err().0;
+        assert_eq!(denied(&parties), denied(&[]));
+        assert_eq!(arb.authorize(&signed("job", expires, &[&arbiter]), &[]).unwrap().0, "arbiter");
+    }
+}
@@ -0,0 +1,299 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Keypair, Signer};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::env;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::info;
+
+use crate::audit::{AuditLog, AuditRecord};
+use crate::blob_source::BlobSource;
+use crate::integrity::sha256_hex;
+use crate::key_usage::KeyUsageMonitor;
+use crate::metrics;
+use crate::sealed;
+use crate::tee_attestation::key_id;
+
+// Archival of old audit records to Walrus. Records older than NAUTILUS_ARCHIVE_AFTER_SECS are
+// bundled, signed with the service key and stored as one Walrus blob (whose ID is derived from
+// the content). A local index (archive.sqlite in the sealed dir) maps every archived job to its
+// bundle, so history stays resolvable while the in-memory audit log only holds recent records.
+
+const FORMAT: &str = "nautilus-audit-archive-v1";
+
+#[derive(Debug, Clone)]
+pub struct ArchiveConfig {
+    // Age at which records are archived; zero disables archival.
+    pub after: Duration,
+    pub interval: Duration,
+}
+
+impl ArchiveConfig {
+    pub fn from_env() -> Self {
+        let parse = |key: &str, default: u64| env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            after: Duration::from_secs(parse("NAUTILUS_ARCHIVE_AFTER_SECS", 7 * 86_400)),
+            interval: Duration::from_secs(parse("NAUTILUS_ARCHIVE_INTERVAL_SECS", 3600)),
+        }
+    }
+}
+
+#[derive(Serialize)]
+struct ArchiveBundle<'a> {
+    format: &'static str,
+    created_ms: u64,
+    from_ms: u64,
+    to_ms: u64,
+    config_hash: &'a str,
+    records: &'a [AuditRecord],
+}
+
+// What is uploaded. `bundle` is the exact JSON text that was signed.
+#[derive(Serialize)]
+struct SignedArchive {
+    bundle: String,
+    key_id: String,
+    public_key_b64: String,
+    signature_b64: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ArchiveEntry {
+    pub archive_blob_id: String,
+    // SHA-256 of the uploaded bytes, for checking what Walrus returns.
+    pub sha256: String,
+    pub from_ms: u64,
+    pub to_ms: u64,
+    pub records: u64,
+    pub created_ms: u64,
+}
+
+pub struct Archiver {
+    conn: Mutex<Connection>,
+    config: ArchiveConfig,
+}
+
+impl Archiver {
+    pub fn open(sealed_dir: Option<&Path>, config: ArchiveConfig) -> Result<Self> {
+        let conn = sealed::open_db(sealed_dir, "archive.sqlite")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS archives (
+                 archive_blob_id TEXT PRIMARY KEY,
+                 sha256 TEXT NOT NULL,
+                 from_ms INTEGER NOT NULL,
+                 to_ms INTEGER NOT NULL,
+                 records INTEGER NOT NULL,
+                 created_ms INTEGER NOT NULL
+             );
+             CREATE TABLE IF NOT EXISTS archived_jobs (
+                 job_id TEXT PRIMARY KEY,
+                 archive_blob_id TEXT NOT NULL REFERENCES archives(archive_blob_id)
+             );",
+        )
+        .context("initialize archive index")?;
+        Ok(Self { conn: Mutex::new(conn), config })
+    }
+
+    pub fn config(&self) -> &ArchiveConfig {
+        &self.config
+    }
+
+    // Archive everything older than the configured age. Records leave the audit log only after
+    // the upload succeeded and the index was updated, so a failed run loses nothing.
+    pub async fn run_once(
+        &self,
+        audit: &AuditLog,
+        store: &dyn BlobSource,
+        signing_key: &Keypair,
+        usage: &KeyUsageMonitor,
+        config_hash: &str,
+    ) -> Result<Option<ArchiveEntry>> {
+        let now = now_ms();
+        let cutoff = now.saturating_sub(self.config.after.as_millis() as u64);
+        let records = audit.older_than(cutoff);
+        let (Some(first), Some(last)) = (records.first(), records.last()) else {
+            return Ok(None);
+        };
+        let bundle = ArchiveBundle {
+            format: FORMAT,
+            created_ms: now,
+            from_ms: first.timestamp_ms,
+            to_ms: last.timestamp_ms,
+            config_hash,
+            records: &records,
+        };
+        let bundle = serde_json::to_string(&bundle).context("serialize archive bundle")?;
+        let kid = key_id(&signing_key.public);
+        usage.authorize(&kid, "archive")?;
+        let signed = SignedArchive {
+            key_id: kid,
+            public_key_b64: STANDARD.encode(signing_key.public.to_bytes()),
+            signature_b64: STANDARD.encode(signing_key.sign(bundle.as_bytes()).to_bytes()),
+            bundle,
+        };
+        let bytes = serde_json::to_vec(&signed)?;
+        let entry = ArchiveEntry {
+            sha256: sha256_hex(&bytes),
+            archive_blob_id: store.store_blob(bytes).await.context("upload audit archive")?,
+            from_ms: first.timestamp_ms,
+            to_ms: last.timestamp_ms,
+            records: records.len() as u64,
+            created_ms: now,
+        };
+        self.index(&entry, &records)?;
+        audit.prune_before(cutoff);
+        info!(archive = %entry.archive_blob_id, records = entry.records, "Archived audit records to Walrus");
+        metrics::inc_counter("nautilus_audit_archives_total", "Audit archive bundles uploaded", &[]);
+        metrics::add_counter(
+            "nautilus_audit_archived_records_total",
+            "Audit records moved to Walrus archives",
+            &[],
+            entry.records as f64,
+        );
+        Ok(Some(entry))
+    }
+
+    fn index(&self, entry: &ArchiveEntry, records: &[AuditRecord]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let tx = conn.transaction()?;
+        tx.execute(
+            "INSERT OR REPLACE INTO archives (archive_blob_id, sha256, from_ms, to_ms, records, created_ms)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            params![
+                entry.archive_blob_id,
+                entry.sha256,
+                entry.from_ms as i64,
+                entry.to_ms as i64,
+                entry.records as i64,
+                entry.created_ms as i64
+            ],
+        )?;
+        for rec in records {
+            tx.execute(
+                "INSERT OR REPLACE INTO archived_jobs (job_id, archive_blob_id) VALUES (?1, ?2)",
+                params![rec.job_id, entry.archive_blob_id],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    // All archives, newest first.
+    pub fn list(&self) -> Result<Vec<ArchiveEntry>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare(
+            "SELECT archive_blob_id, sha256, from_ms, to_ms, records, created_ms FROM archives ORDER BY created_ms DESC",
+        )?;
+        let rows = stmt.query_map([], entry_from_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // The archive holding `job_id`'s audit record, if it has been archived.
+    pub fn find_job(&self, job_id: &str) -> Result<Option<ArchiveEntry>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        Ok(conn
+            .query_row(
+                "SELECT a.archive_blob_id, a.sha256, a.from_ms, a.to_ms, a.records, a.created_ms
+                 FROM archived_jobs j JOIN archives a ON a.archive_blob_id = j.archive_blob_id WHERE j.job_id = ?1",
+                params![job_id],
+                entry_from_row,
+            )
+            .optional()?)
+    }
+}
+
+fn entry_from_row(r: &rusqlite::Row) -> rusqlite::Result<ArchiveEntry> {
+    Ok(ArchiveEntry {
+        archive_blob_id: r.get(0)?,
+        sha256: r.get(1)?,
+        from_ms: r.get::<_, i64>(2)? as u64,
+        to_ms: r.get::<_, i64>(3)? as u64,
+        records: r.get::<_, i64>(4)? as u64,
+        created_ms: r.get::<_, i64>(5)? as u64,
+    })
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality_validator::QualityBreakdown;
+    use crate::walrus_client::{BlobMetadata, BlobRange};
+
+    struct Store(Mutex<Vec<Vec<u8>>>);
+
+    #[async_trait::async_trait]
+    impl BlobSource for Store {
+        async fn fetch_blob(&self, _blob_id: &str, _limit: u64) -> Result<Vec<u8>> {
+            unimplemented!()
+        }
+        async fn fetch_blob_range(&self, _blob_id: &str, _offset: u64, _len: u64) -> Result<BlobRange> {
+            unimplemented!()
+        }
+        async fn blob_metadata(&self, _blob_id: &str) -> Result<BlobMetadata> {
+            unimplemented!()
+        }
+        async fn store_blob(&self, data: Vec<u8>) -> Result<String> {
+            let id = format!("archive-{}", sha256_hex(&data));
+            self.0.lock().unwrap().push(data);
+            Ok(id)
+        }
+    }
+
+    fn record(job_id: &str, timestamp_ms: u64) -> AuditRecord {
+        let breakdown = QualityBreakdown {
+            diversity: Some(50),
+            bias: Some(50),
+            authenticity: Some(50),
+            completeness: Some(50),
+            consistency: Some(50),
+            ..Default::default()
+        };
+        AuditRecord {
+            job_id: job_id.into(),
+            blob_id: "b".into(),
+            timestamp_ms,
+            category: Default::default(),
+            threshold: 10,
+            score: 50,
+            breakdown,
+            degradations: vec![],
+            evidence: Default::default(),
+        }
+    }
+
+    #[tokio::test]
+    async fn test_archives_old_records_and_indexes_them() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[3u8; 32]).unwrap();
+        let kp = Keypair { public: (&secret).into(), secret };
+        let audit = AuditLog::new(16);
+        audit.record(record("old-1", 1));
+        audit.record(record("old-2", 2));
+        audit.record(record("fresh", now_ms()));
+        let store = Store(Mutex::new(vec![]));
+        let usage = KeyUsageMonitor::new(crate::key_usage::KeyUsagePolicy::from_env());
+        let archiver = Archiver::open(None, ArchiveConfig { after: Duration::from_secs(60), ..ArchiveConfig::from_env() })
+            .unwrap();
+
+        let entry = archiver.run_once(&audit, &store, &kp, &usage, "cfg").await.unwrap().expect("archived");
+        assert_eq!((entry.records, entry.from_ms, entry.to_ms), (2, 1, 2));
+        assert_eq!(audit.since(0).len(), 1);
+        assert_eq!(archiver.find_job("old-2").unwrap(), Some(entry.clone()));
+        assert_eq!(archiver.find_job("fresh").unwrap(), None);
+        assert_eq!(archiver.list().unwrap(), vec![entry]);
+        assert!(archiver.run_once(&audit, &store, &kp, &usage, "cfg").await.unwrap().is_none());
+
+        let uploaded: serde_json::Value = serde_json::from_slice(&store.0.lock().unwrap()[0]).unwrap();
+        let bundle = uploaded["bundle"].as_str().unwrap();
+        let sig = ed25519_dalek::Signature::from_bytes(&STANDARD.decode(uploaded["signature_b64"].as_str().unwrap()).unwrap())
+            .unwrap();
+        assert!(ed25519_dalek::Verifier::verify(&kp.public, bundle.as_bytes(), &sig).is_ok());
+    }
+}
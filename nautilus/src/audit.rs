@@ -0,0 +1,70 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, VecDeque};
+use std::sync::Mutex;
+
+use crate::category::Category;
+use crate::quality_validator::QualityBreakdown;
+
+// Audit store of completed verifications: the per-check breakdown behind every score, so
+// policies can be replayed later (POST /policy/simulate) without re-fetching any data.
+// Bounded and in-memory; the oldest records are dropped past NAUTILUS_AUDIT_CAPACITY, and
+// records past the archive age are moved to Walrus (see archive).
+
+#[derive(Debug, Clone, Serialize)]
+pub struct AuditRecord {
+    pub job_id: String,
+    pub blob_id: String,
+    pub timestamp_ms: u64,
+    pub category: Category,
+    pub threshold: u8,
+    pub score: u8,
+    pub breakdown: QualityBreakdown,
+    pub degradations: Vec<String>,
+    // SHA-256 of each check's sealed evidence artifact, by check (see evidence).
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub evidence: BTreeMap<String, String>,
+}
+
+pub struct AuditLog {
+    records: Mutex<VecDeque<AuditRecord>>,
+    capacity: usize,
+}
+
+impl AuditLog {
+    pub fn new(capacity: usize) -> Self {
+        Self { records: Mutex::new(VecDeque::new()), capacity }
+    }
+
+    pub fn record(&self, rec: AuditRecord) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        while records.len() >= self.capacity {
+            records.pop_front();
+        }
+        records.push_back(rec);
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<AuditRecord> {
+        let records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        records.iter().rev().find(|r| r.job_id == job_id).cloned()
+    }
+
+    // Records at or after `since_ms`, oldest first.
+    pub fn since(&self, since_ms: u64) -> Vec<AuditRecord> {
+        let records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        records.iter().filter(|r| r.timestamp_ms >= since_ms).cloned().collect()
+    }
+
+    // Records strictly before `cutoff_ms`, oldest first.
+    pub fn older_than(&self, cutoff_ms: u64) -> Vec<AuditRecord> {
+        let records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        records.iter().filter(|r| r.timestamp_ms < cutoff_ms).cloned().collect()
+    }
+
+    pub fn prune_before(&self, cutoff_ms: u64) {
+        let mut records = self.records.lock().unwrap_or_else(|e| e.into_inner());
+        records.retain(|r| r.timestamp_ms >= cutoff_ms);
+    }
+}
@@ -0,0 +1,85 @@
+use anyhow::{anyhow, Context, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use ed25519_dalek::{Keypair, PublicKey, Signature, Signer, Verifier};
+use qrcode::render::svg;
+use qrcode::QrCode;
+use serde::{Deserialize, Serialize};
+
+use crate::tee_attestation::key_id;
+
+// Scannable verification badges for marketplace UIs.
+// A badge is a compact, signed reference to a verification: `ZV1.<claims>.<signature>`, both parts
+// base64url. It fits comfortably in a QR code and is appended to the verify URL as `?t=` so a scan
+// lands on GET /badge/verify, which checks the signature and echoes the claims.
+
+const PREFIX: &str = "ZV1";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BadgeClaims {
+    // Verification (job) ID the badge refers to.
+    pub jti: String,
+    // Signing key ID (see `tee_attestation::key_id`).
+    pub kid: String,
+    pub score: u8,
+    pub iat: u64,
+}
+
+pub fn encode(kp: &Keypair, claims: &BadgeClaims) -> Result<String> {
+    let payload = serde_json::to_vec(claims).context("serialize badge claims")?;
+    let sig = kp.sign(&payload);
+    Ok(format!("{}.{}.{}", PREFIX, URL_SAFE_NO_PAD.encode(&payload), URL_SAFE_NO_PAD.encode(sig.to_bytes())))
+}
+
+// SDK helper: check a short form against the issuing public key and return its claims.
+pub fn verify(short: &str, pk: &PublicKey) -> Result<BadgeClaims> {
+    let mut parts = short.trim().split('.');
+    let (Some(PREFIX), Some(payload), Some(sig), None) = (parts.next(), parts.next(), parts.next(), parts.next()) else {
+        return Err(anyhow!("Malformed badge"));
+    };
+    let payload = URL_SAFE_NO_PAD.decode(payload).context("badge payload encoding")?;
+    let sig = Signature::from_bytes(&URL_SAFE_NO_PAD.decode(sig).context("badge signature encoding")?)
+        .map_err(|_| anyhow!("Malformed badge signature"))?;
+    pk.verify(&payload, &sig).map_err(|_| anyhow!("Badge signature is invalid"))?;
+    let claims: BadgeClaims = serde_json::from_slice(&payload).context("badge claims")?;
+    if claims.kid != key_id(pk) {
+        return Err(anyhow!("Badge was issued by another key"));
+    }
+    Ok(claims)
+}
+
+pub fn deep_link(verify_url: &str, short: &str) -> String {
+    format!("{}?t={}", verify_url, short)
+}
+
+pub fn qr_svg(data: &str) -> Result<String> {
+    let code = QrCode::new(data.as_bytes()).context("encode QR")?;
+    Ok(code.render::<svg::Color>().min_dimensions(200, 200).build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[seed; 32]).unwrap();
+        let public: PublicKey = (&secret).into();
+        Keypair { secret, public }
+    }
+
+    #[test]
+    fn test_badge_roundtrip_and_tamper() {
+        let kp = keypair(1);
+        let claims = BadgeClaims { jti: "job-1".into(), kid: key_id(&kp.public), score: 87, iat: 1 };
+        let short = encode(&kp, &claims).unwrap();
+        assert!(short.starts_with("ZV1."));
+        assert_eq!(verify(&short, &kp.public).unwrap(), claims);
+
+        assert!(verify(&short, &keypair(2).public).is_err());
+        let forged = BadgeClaims { score: 99, ..claims };
+        let forged_payload = URL_SAFE_NO_PAD.encode(serde_json::to_vec(&forged).unwrap());
+        let sig = short.rsplit('.').next().unwrap();
+        assert!(verify(&format!("ZV1.{}.{}", forged_payload, sig), &kp.public).is_err());
+        assert!(qr_svg(&deep_link("https://v.example/badge/verify", &short)).unwrap().contains("<svg"));
+    }
+}
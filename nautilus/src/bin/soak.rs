@@ -0,0 +1,274 @@
+// Soak-test harness: drives sustained /verify load at a nautilus instance and reports
+// latency percentiles and error rates.
+//
+// The harness also serves a mock Walrus aggregator so blob sizes are under its control; point the
+// target at it with WALRUS_AGGREGATOR_URL=http://<SOAK_AGGREGATOR_ADDR>. Blob IDs encode their size
+// (`soak-<bytes>-<n>`), and every request uses a fresh ID so caches don't flatter the numbers.
+//
+// Settings (env):
+//   SOAK_TARGET           nautilus base URL (default http://127.0.0.1:3000)
+//   SOAK_AGGREGATOR_ADDR  mock aggregator listen address (default 127.0.0.1:3100; empty disables it)
+//   SOAK_RPS              requests started per second (default 10)
+//   SOAK_DURATION_SECS    how long to generate load (default 60)
+//   SOAK_SIZE_MIX         weighted blob sizes, e.g. "32k:70,1m:25,16m:5" (default)
+//   SOAK_BATCH_RATIO      fraction (0..=1) of ticks that fire a burst instead of one request (default 0.1)
+//   SOAK_BATCH_SIZE       requests per burst (default 10)
+//   SOAK_THRESHOLD        min_quality_threshold sent with each request (default 50)
+
+use anyhow::{Context, Result};
+use http_body_util::Full;
+use hyper::body::{Bytes, Incoming};
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::collections::{BTreeMap, HashMap};
+use std::env;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+struct Settings {
+    target: String,
+    aggregator_addr: Option<String>,
+    rps: u32,
+    duration: Duration,
+    size_mix: Vec<(u64, u32)>,
+    batch_ratio: f64,
+    batch_size: u32,
+    threshold: u8,
+}
+
+impl Settings {
+    fn from_env() -> Result<Self> {
+        let size_mix = parse_size_mix(&env::var("SOAK_SIZE_MIX").unwrap_or_else(|_| "32k:70,1m:25,16m:5".to_string()))?;
+        Ok(Self {
+            target: env::var("SOAK_TARGET").unwrap_or_else(|_| "http://127.0.0.1:3000".to_string()),
+            aggregator_addr: match env::var("SOAK_AGGREGATOR_ADDR") {
+                Ok(addr) if addr.is_empty() => None,
+                Ok(addr) => Some(addr),
+                Err(_) => Some("127.0.0.1:3100".to_string()),
+            },
+            rps: env_parse("SOAK_RPS", 10u32).max(1),
+            duration: Duration::from_secs(env_parse("SOAK_DURATION_SECS", 60)),
+            size_mix,
+            batch_ratio: env_parse("SOAK_BATCH_RATIO", 0.1f64).clamp(0.0, 1.0),
+            batch_size: env_parse("SOAK_BATCH_SIZE", 10u32).max(1),
+            threshold: env_parse("SOAK_THRESHOLD", 50u8),
+        })
+    }
+}
+
+#[derive(Default)]
+struct Stats {
+    latencies: Vec<Duration>,
+    // "200", "503", "transport", ...
+    outcomes: BTreeMap<String, u64>,
+}
+
+#[tokio::main]
+async fn main() -> Result<()> {
+    dotenvy::dotenv().ok();
+    let filter = tracing_subscriber::EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| tracing_subscriber::EnvFilter::new("info"));
+    tracing_subscriber::fmt().with_env_filter(filter).init();
+    let settings = Settings::from_env()?;
+
+    if let Some(addr) = &settings.aggregator_addr {
+        let listener = tokio::net::TcpListener::bind(addr).await.context("bind mock aggregator")?;
+        info!(%addr, "Mock Walrus aggregator listening");
+        tokio::spawn(serve_mock_aggregator(listener));
+    }
+
+    info!(
+        target = %settings.target,
+        rps = settings.rps,
+        duration = ?settings.duration,
+        batch_ratio = settings.batch_ratio,
+        batch_size = settings.batch_size,
+        "Starting soak run"
+    );
+    let http = reqwest::Client::new();
+    let stats = Arc::new(Mutex::new(Stats::default()));
+    let seq = Arc::new(AtomicU64::new(0));
+    let mut ticker = tokio::time::interval(Duration::from_secs(1) / settings.rps);
+    let started = Instant::now();
+    let mut inflight = Vec::new();
+
+    while started.elapsed() < settings.duration {
+        ticker.tick().await;
+        let burst = if random_unit() < settings.batch_ratio { settings.batch_size } else { 1 };
+        for _ in 0..burst {
+            let n = seq.fetch_add(1, Ordering::Relaxed);
+            let size = pick_size(&settings.size_mix);
+            let blob_id = format!("soak-{}-{}", size, n);
+            let url = format!("{}/verify", settings.target.trim_end_matches('/'));
+            let body = serde_json::json!({ "blob_id": blob_id, "min_quality_threshold": settings.threshold });
+            let (http, stats) = (http.clone(), stats.clone());
+            inflight.push(tokio::spawn(async move {
+                let sent = Instant::now();
+                let outcome = match http.post(&url).json(&body).send().await {
+                    Ok(resp) => {
+                        let status = resp.status().as_u16().to_string();
+                        let _ = resp.bytes().await;
+                        status
+                    }
+                    Err(_) => "transport".to_string(),
+                };
+                let mut s = stats.lock().unwrap_or_else(|e| e.into_inner());
+                s.latencies.push(sent.elapsed());
+                *s.outcomes.entry(outcome).or_default() += 1;
+            }));
+        }
+        inflight.retain(|h: &tokio::task::JoinHandle<()>| !h.is_finished());
+    }
+    info!(pending = inflight.len(), "Load phase done, waiting for in-flight requests");
+    for handle in inflight {
+        let _ = handle.await;
+    }
+
+    let stats = stats.lock().unwrap_or_else(|e| e.into_inner());
+    report(&stats, started.elapsed());
+    Ok(())
+}
+
+fn report(stats: &Stats, elapsed: Duration) {
+    let mut lat = stats.latencies.clone();
+    lat.sort();
+    let total = lat.len() as u64;
+    let ok = stats.outcomes.get("200").copied().unwrap_or(0);
+    println!("requests:   {} in {:.1}s ({:.1} req/s)", total, elapsed.as_secs_f64(), total as f64 / elapsed.as_secs_f64());
+    println!("error rate: {:.2}%", if total == 0 { 0.0 } else { (total - ok) as f64 * 100.0 / total as f64 });
+    for (outcome, count) in &stats.outcomes {
+        println!("  {:<10} {}", outcome, count);
+    }
+    for (label, q) in [("p50", 0.50), ("p90", 0.90), ("p99", 0.99), ("max", 1.0)] {
+        println!("{}:        {:?}", label, percentile(&lat, q));
+    }
+}
+
+fn percentile(sorted: &[Duration], q: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::ZERO;
+    }
+    let idx = ((sorted.len() as f64 * q).ceil() as usize).clamp(1, sorted.len()) - 1;
+    sorted[idx]
+}
+
+// Serves GET/HEAD /v1/blobs/soak-<bytes>-<n> with deterministic text-like content of that size.
+async fn serve_mock_aggregator(listener: tokio::net::TcpListener) {
+    let bodies: Arc<Mutex<HashMap<u64, Bytes>>> = Arc::default();
+    loop {
+        let (stream, _) = match listener.accept().await {
+            Ok(conn) => conn,
+            Err(err) => {
+                error!(%err, "Mock aggregator accept failed");
+                continue;
+            }
+        };
+        let bodies = bodies.clone();
+        tokio::spawn(async move {
+            let svc = service_fn(move |req: Request<Incoming>| {
+                let bodies = bodies.clone();
+                async move { Ok::<_, hyper::Error>(mock_blob_response(&req, &bodies)) }
+            });
+            if let Err(err) = http1::Builder::new().serve_connection(TokioIo::new(stream), svc).await {
+                error!(%err, "Mock aggregator connection error");
+            }
+        });
+    }
+}
+
+fn mock_blob_response(req: &Request<Incoming>, bodies: &Mutex<HashMap<u64, Bytes>>) -> Response<Full<Bytes>> {
+    let size = req
+        .uri()
+        .path()
+        .strip_prefix("/v1/blobs/soak-")
+        .and_then(|rest| rest.split('-').next())
+        .and_then(|s| s.parse::<u64>().ok());
+    let (status, body) = match (req.method(), size) {
+        (&Method::GET | &Method::HEAD, Some(size)) => {
+            let mut cache = bodies.lock().unwrap_or_else(|e| e.into_inner());
+            (StatusCode::OK, cache.entry(size).or_insert_with(|| synth_blob(size)).clone())
+        }
+        _ => (StatusCode::NOT_FOUND, Bytes::new()),
+    };
+    let mut resp = Response::new(Full::new(body));
+    *resp.status_mut() = status;
+    resp
+}
+
+fn synth_blob(size: u64) -> Bytes {
+    let line = b"soak dataset row: the quick brown fox jumps over the lazy dog ";
+    let mut out = Vec::with_capacity(size as usize);
+    let mut i: u32 = 0;
+    while (out.len() as u64) < size {
+        out.extend_from_slice(line);
+        out.extend_from_slice(i.to_string().as_bytes());
+        out.push(b'\n');
+        i = i.wrapping_add(1);
+    }
+    out.truncate(size as usize);
+    Bytes::from(out)
+}
+
+// "32k:70,1m:25" -> [(32768, 70), (1048576, 25)]
+fn parse_size_mix(raw: &str) -> Result<Vec<(u64, u32)>> {
+    let mut mix = Vec::new();
+    for part in raw.split(',').map(str::trim).filter(|p| !p.is_empty()) {
+        let (size, weight) = part.split_once(':').unwrap_or((part, "1"));
+        let size = size.trim().to_ascii_lowercase();
+        let (digits, mult) = match size.chars().last() {
+            Some('k') => (&size[..size.len() - 1], 1024),
+            Some('m') => (&size[..size.len() - 1], 1024 * 1024),
+            Some('g') => (&size[..size.len() - 1], 1024 * 1024 * 1024),
+            _ => (size.as_str(), 1),
+        };
+        let bytes: u64 = digits.parse().with_context(|| format!("bad size in SOAK_SIZE_MIX: {}", part))?;
+        let weight: u32 = weight.trim().parse().with_context(|| format!("bad weight in SOAK_SIZE_MIX: {}", part))?;
+        mix.push((bytes * mult, weight));
+    }
+    anyhow::ensure!(mix.iter().any(|(_, w)| *w > 0), "SOAK_SIZE_MIX has no positive weights");
+    Ok(mix)
+}
+
+fn pick_size(mix: &[(u64, u32)]) -> u64 {
+    let total: u32 = mix.iter().map(|(_, w)| w).sum();
+    let mut roll = (random_unit() * total as f64) as u32;
+    for (size, weight) in mix {
+        if roll < *weight {
+            return *size;
+        }
+        roll -= weight;
+    }
+    mix[mix.len() - 1].0
+}
+
+fn random_unit() -> f64 {
+    let mut buf = [0u8; 4];
+    let _ = SystemRandom::new().fill(&mut buf);
+    u32::from_le_bytes(buf) as f64 / (u32::MAX as f64 + 1.0)
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_size_mix_and_percentiles() {
+        let mix = parse_size_mix("32k:70, 1m:25,16m:5").unwrap();
+        assert_eq!(mix, vec![(32 * 1024, 70), (1024 * 1024, 25), (16 * 1024 * 1024, 5)]);
+        assert!(parse_size_mix("1m:0").is_err());
+
+        let lat: Vec<Duration> = (1..=100).map(Duration::from_millis).collect();
+        assert_eq!(percentile(&lat, 0.50), Duration::from_millis(50));
+        assert_eq!(percentile(&lat, 0.99), Duration::from_millis(99));
+        assert_eq!(percentile(&lat, 1.0), Duration::from_millis(100));
+    }
+}
@@ -0,0 +1,229 @@
+use anyhow::Result;
+use bytes::Bytes;
+use futures_util::stream::{self, BoxStream};
+use futures_util::StreamExt;
+use std::sync::Arc;
+
+use crate::http_source::HttpSource;
+use crate::ipfs_source::IpfsSource;
+use crate::s3_source::S3Source;
+use crate::walrus_client::{BlobMetadata, BlobRange, MoveCall, RegisteredBlob, SuiRandomness, WalrusClient, WalrusError};
+
+pub const WALRUS: &str = "walrus";
+pub const HTTP: &str = "http";
+pub const S3: &str = "s3";
+pub const IPFS: &str = "ipfs";
+
+// Which backend serves `blob_id`; attested with every verification.
+pub fn source_type(blob_id: &str) -> &'static str {
+    match blob_id.split_once("://") {
+        Some((scheme, _)) if scheme.eq_ignore_ascii_case("https") || scheme.eq_ignore_ascii_case("http") => HTTP,
+        Some((scheme, _)) if scheme.eq_ignore_ascii_case("s3") => S3,
+        Some((scheme, _)) if scheme.eq_ignore_ascii_case("ipfs") => IPFS,
+        _ => WALRUS,
+    }
+}
+
+// The request names an external source this deployment won't fetch.
+#[derive(Debug, thiserror::Error)]
+#[error("SOURCE_NOT_ALLOWED: {0}")]
+pub struct SourceNotAllowed(pub String);
+
+// Where dataset bytes come from. Handlers only see this trait so storage backends
+// (and test doubles) can be swapped without touching the verification flow.
+#[async_trait::async_trait]
+pub trait BlobSource: Send + Sync {
+    // Whole blob, failing once more than `limit` bytes arrive.
+    async fn fetch_blob(&self, blob_id: &str, limit: u64) -> Result<Vec<u8>>;
+
+    // The blob as a stream of chunks, failing once more than `limit` bytes arrive. Backends that
+    // can't stream hand back the buffered blob as a single chunk.
+    async fn fetch_blob_stream(&self, blob_id: &str, limit: u64) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let data = self.fetch_blob(blob_id, limit).await?;
+        Ok(stream::once(async move { Ok(Bytes::from(data)) }).boxed())
+    }
+
+    // `len` bytes from `offset`, plus the blob length when the backend knows it.
+    async fn fetch_blob_range(&self, blob_id: &str, offset: u64, len: u64) -> Result<BlobRange>;
+
+    // Cheap existence/size probe; fails with `WalrusError::NotFound` for missing blobs.
+    async fn blob_metadata(&self, blob_id: &str) -> Result<BlobMetadata>;
+
+    // Fetch several blobs with at most `parallelism` downloads in flight.
+    // Results come back in input order, one per blob, so a failure doesn't sink the rest.
+    async fn fetch_blobs(&self, blob_ids: &[String], limit: u64, parallelism: usize) -> Vec<Result<Vec<u8>>> {
+        // Build the (lazy) futures up front; mapping inside the stream trips async_trait's Send bound.
+        let fetches: Vec<_> = blob_ids.iter().map(|id| self.fetch_blob(id, limit)).collect();
+        stream::iter(fetches).buffered(parallelism.max(1)).collect().await
+    }
+
+    // Walrus' registration of the blob on Sui, when the backend can find one.
+    async fn registered_blob(&self, _blob_id: &str) -> Result<Option<RegisteredBlob>> {
+        Ok(None)
+    }
+
+    // Walrus blob ID registered on a Sui dataset object.
+    async fn resolve_sui_object(&self, object_id: &str) -> Result<String> {
+        anyhow::bail!("Sui object {} cannot be resolved by this blob source", object_id)
+    }
+
+    // Whether a Sui object exists on chain.
+    async fn sui_object_exists(&self, object_id: &str) -> Result<bool> {
+        anyhow::bail!("Sui object {} cannot be looked up by this blob source", object_id)
+    }
+
+    // Latest on-chain randomness round, for unbiasable sample seeds.
+    async fn sui_randomness(&self) -> Result<SuiRandomness> {
+        anyhow::bail!("Sui randomness is not available from this blob source")
+    }
+
+    // Unsigned transaction bytes for a Move call.
+    async fn sui_build_move_call(&self, call: &MoveCall) -> Result<Vec<u8>> {
+        anyhow::bail!("Move call {}::{} cannot be submitted through this blob source", call.module, call.function)
+    }
+
+    // Execute a signed transaction, returning its digest.
+    async fn sui_execute(&self, _tx_bytes: &[u8], _signature: &str) -> Result<String> {
+        anyhow::bail!("transactions cannot be submitted through this blob source")
+    }
+
+    // Store `data` and return its blob ID.
+    async fn store_blob(&self, _data: Vec<u8>) -> Result<String> {
+        anyhow::bail!("this blob source is read-only")
+    }
+
+    async fn blob_exists(&self, blob_id: &str) -> Result<bool> {
+        match self.blob_metadata(blob_id).await {
+            Ok(_) => Ok(true),
+            // Expired and uncertified blobs can't be read either.
+            Err(err)
+                if matches!(
+                    err.downcast_ref::<WalrusError>(),
+                    Some(WalrusError::NotFound | WalrusError::Expired { .. } | WalrusError::NotCertified { .. })
+                ) =>
+            {
+                Ok(false)
+            }
+            Err(err) => Err(err),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobSource for WalrusClient {
+    async fn fetch_blob(&self, blob_id: &str, limit: u64) -> Result<Vec<u8>> {
+        WalrusClient::fetch_blob(self, blob_id, limit).await
+    }
+
+    async fn fetch_blob_stream(&self, blob_id: &str, limit: u64) -> Result<BoxStream<'static, Result<Bytes>>> {
+        WalrusClient::fetch_blob_stream(self, blob_id, limit).await
+    }
+
+    async fn fetch_blob_range(&self, blob_id: &str, offset: u64, len: u64) -> Result<BlobRange> {
+        WalrusClient::fetch_blob_range(self, blob_id, offset, len).await
+    }
+
+    async fn blob_metadata(&self, blob_id: &str) -> Result<BlobMetadata> {
+        WalrusClient::blob_metadata(self, blob_id).await
+    }
+
+    async fn registered_blob(&self, blob_id: &str) -> Result<Option<RegisteredBlob>> {
+        WalrusClient::registered_blob(self, blob_id).await
+    }
+
+    async fn resolve_sui_object(&self, object_id: &str) -> Result<String> {
+        WalrusClient::resolve_sui_object(self, object_id).await
+    }
+
+    async fn sui_object_exists(&self, object_id: &str) -> Result<bool> {
+        WalrusClient::sui_object_exists(self, object_id).await
+    }
+
+    async fn sui_randomness(&self) -> Result<SuiRandomness> {
+        WalrusClient::sui_randomness(self).await
+    }
+
+    async fn sui_build_move_call(&self, call: &MoveCall) -> Result<Vec<u8>> {
+        WalrusClient::sui_build_move_call(self, call).await
+    }
+
+    async fn sui_execute(&self, tx_bytes: &[u8], signature: &str) -> Result<String> {
+        WalrusClient::sui_execute(self, tx_bytes, signature).await
+    }
+
+    async fn store_blob(&self, data: Vec<u8>) -> Result<String> {
+        WalrusClient::store_blob(self, data).await
+    }
+}
+
+// Sends URL, s3:// and ipfs:// blob IDs to their backends and everything else to Walrus.
+pub struct RoutedSource {
+    pub walrus: Arc<dyn BlobSource>,
+    // None when no HTTP hosts are allowlisted.
+    pub http: Option<Arc<HttpSource>>,
+    // None without S3 credentials.
+    pub s3: Option<Arc<S3Source>>,
+    // None without IPFS gateways.
+    pub ipfs: Option<Arc<IpfsSource>>,
+}
+
+impl RoutedSource {
+    fn route(&self, blob_id: &str) -> Result<&dyn BlobSource> {
+        let backend: Option<&dyn BlobSource> = match source_type(blob_id) {
+            HTTP => self.http.as_deref().map(|s| s as &dyn BlobSource),
+            S3 => self.s3.as_deref().map(|s| s as &dyn BlobSource),
+            IPFS => self.ipfs.as_deref().map(|s| s as &dyn BlobSource),
+            _ => return Ok(self.walrus.as_ref()),
+        };
+        backend.ok_or_else(|| {
+            SourceNotAllowed(format!("{} sources are not enabled on this deployment", source_type(blob_id))).into()
+        })
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobSource for RoutedSource {
+    async fn fetch_blob(&self, blob_id: &str, limit: u64) -> Result<Vec<u8>> {
+        self.route(blob_id)?.fetch_blob(blob_id, limit).await
+    }
+
+    async fn fetch_blob_stream(&self, blob_id: &str, limit: u64) -> Result<BoxStream<'static, Result<Bytes>>> {
+        self.route(blob_id)?.fetch_blob_stream(blob_id, limit).await
+    }
+
+    async fn fetch_blob_range(&self, blob_id: &str, offset: u64, len: u64) -> Result<BlobRange> {
+        self.route(blob_id)?.fetch_blob_range(blob_id, offset, len).await
+    }
+
+    async fn blob_metadata(&self, blob_id: &str) -> Result<BlobMetadata> {
+        self.route(blob_id)?.blob_metadata(blob_id).await
+    }
+
+    async fn registered_blob(&self, blob_id: &str) -> Result<Option<RegisteredBlob>> {
+        self.route(blob_id)?.registered_blob(blob_id).await
+    }
+
+    async fn resolve_sui_object(&self, object_id: &str) -> Result<String> {
+        self.walrus.resolve_sui_object(object_id).await
+    }
+
+    async fn sui_object_exists(&self, object_id: &str) -> Result<bool> {
+        self.walrus.sui_object_exists(object_id).await
+    }
+
+    async fn sui_randomness(&self) -> Result<SuiRandomness> {
+        self.walrus.sui_randomness().await
+    }
+
+    async fn sui_build_move_call(&self, call: &MoveCall) -> Result<Vec<u8>> {
+        self.walrus.sui_build_move_call(call).await
+    }
+
+    async fn sui_execute(&self, tx_bytes: &[u8], signature: &str) -> Result<String> {
+        self.walrus.sui_execute(tx_bytes, signature).await
+    }
+
+    async fn store_blob(&self, data: Vec<u8>) -> Result<String> {
+        self.walrus.store_blob(data).await
+    }
+}
@@ -0,0 +1,144 @@
+use serde::Serialize;
+use std::path::Path;
+use tracing::info;
+
+use crate::app_state::AppState;
+use crate::category::Category;
+use crate::errors::{self, ErrorCategory};
+use crate::quality_validator::REGISTRY;
+use crate::screening::ScreeningMode;
+use crate::tee_attestation::{self, SignatureScheme};
+
+// Feature discovery for client SDKs (GET /capabilities).
+// Everything here describes this deployment as configured, so clients can feature-detect
+// instead of assuming what a given Nautilus instance supports.
+
+#[derive(Serialize)]
+pub struct Capabilities {
+    pub service: &'static str,
+    pub version: &'static str,
+    pub api_versions: Vec<&'static str>,
+    pub formats: Vec<&'static str>,
+    pub checks: Vec<&'static str>,
+    pub categories: Vec<&'static str>,
+    pub attestation_backends: Vec<&'static str>,
+    pub active_attestation_backend: String,
+    // Scheme of the attestation signature ("ed25519", "secp256k1" or "bls12381"; see tee_attestation).
+    pub signature_scheme: &'static str,
+    // Canonicalization of signed payloads: "json-sorted-keys-v1", or "bcs-v1" for Move contracts.
+    pub payload_encoding: &'static str,
+    // Envelope encodings a request can ask for with `envelope_encoding`; no "cose" under bls12381.
+    pub envelope_encodings: Vec<&'static str>,
+    pub max_blob_bytes: u64,
+    // Walrus/Sui network profile ("testnet", "mainnet" or "custom"); attested with every result.
+    pub walrus_profile: &'static str,
+    pub walrus_aggregators: usize,
+    // Blob sources verifications can read from; "http", "s3" and "ipfs" only when configured.
+    pub sources: Vec<&'static str>,
+    pub disk_cache: bool,
+    pub memory_cache: bool,
+    pub load_shedding: bool,
+    pub zk_prover: bool,
+    // Whether mutating endpoints require a tenant API key (see api_keys).
+    pub api_key_auth: bool,
+    // Whether `co_sign` verifications can get an operator co-signature (see cosign).
+    pub operator_cosign: bool,
+    // Whether completed verifications are recorded on chain (see submission).
+    pub onchain_submission: bool,
+    // Whether the enclave signing key is sealed with KMS and so stable across restarts (see kms_seal).
+    pub sealed_signing_key: bool,
+    pub endpoints: Vec<&'static str>,
+    // Every error code with its status and whether a retry can succeed (see errors).
+    pub error_codes: Vec<ErrorCode>,
+}
+
+#[derive(Serialize)]
+pub struct ErrorCode {
+    pub code: &'static str,
+    pub status: u16,
+    pub category: ErrorCategory,
+}
+
+pub fn current(state: &AppState) -> Capabilities {
+    let nitro = Path::new("/dev/nsm").exists();
+    let walrus = state.walrus.as_deref();
+    Capabilities {
+        service: "nautilus",
+        version: env!("CARGO_PKG_VERSION"),
+        api_versions: vec!["v1"],
+        // Formats with their own checks; anything else is scored as raw bytes.
+        formats: {
+            let mut formats = vec!["binary"];
+            for format in REGISTRY.iter().flat_map(|e| e.formats) {
+                if !formats.contains(&format.as_str()) {
+                    formats.push(format.as_str());
+                }
+            }
+            formats
+        },
+        checks: {
+            let mut checks = vec!["diversity", "bias", "authenticity", "completeness", "consistency", "privacy", "secrets", "complexity", "poisoning", "freshness", "finite_weights"];
+            if state.config.checks.lexicon.is_some() {
+                checks.push("content_safety");
+            }
+            if state.config.screening.mode != ScreeningMode::Off {
+                checks.push("screening");
+            }
+            checks
+        },
+        categories: Category::ALL.iter().map(|c| c.as_str()).collect(),
+        attestation_backends: vec!["ed25519-v2", "secp256k1-v2", "bls12381-v2", "nsm-document-v2", "sev-snp-v2", "tpm-quote-weak-v2"],
+        active_attestation_backend: format!(
+            "{}-v2",
+            tee_attestation::provider().map_or(state.config.signature_scheme.as_str(), |p| p.format())
+        ),
+        signature_scheme: state.config.signature_scheme.as_str(),
+        payload_encoding: state.config.payload_encoding.canonicalization(),
+        envelope_encodings: match state.config.signature_scheme {
+            SignatureScheme::Bls12381 => vec!["json"],
+            _ => vec!["json", "cose"],
+        },
+        max_blob_bytes: state.config.walrus.max_blob_bytes,
+        walrus_profile: state.config.walrus.profile.as_str(),
+        walrus_aggregators: walrus.map(|w| w.aggregator_count()).unwrap_or(0),
+        sources: [
+            Some("walrus"),
+            (!state.config.http_source.allowlist.is_empty()).then_some("http"),
+            state.config.s3.enabled().then_some("s3"),
+            (!state.config.ipfs.gateways.is_empty()).then_some("ipfs"),
+        ]
+        .into_iter()
+        .flatten()
+        .collect(),
+        disk_cache: walrus.map(|w| w.disk_cache_enabled()).unwrap_or(false),
+        memory_cache: state.config.walrus.memory_cache_max_bytes > 0,
+        load_shedding: true,
+        // Proofs are produced by the backend/sui-vktool, not inside the enclave.
+        zk_prover: false,
+        api_key_auth: state.api_keys.is_some(),
+        operator_cosign: state.config.cosign.signer_url.is_some(),
+        onchain_submission: state.submitter.config().enabled(),
+        sealed_signing_key: nitro && state.config.kms.enabled(),
+        endpoints: vec!["GET /health", "GET /capabilities", "GET /build-info", "GET /schemas", "GET /schemas/{file}", "GET /metrics", "GET /jobs/{id}", "HEAD /blobs/{id}", "POST /verify", "POST /verify/batch", "POST /policy/simulate", "GET /policies", "POST /disputes/export", "GET /badge/{job_id}", "GET /badge/verify", "GET /attestation", "POST /attestation/verify", "POST /attestation/aggregate", "GET /keys", "GET /audit/archives", "GET /attestations", "GET /escrow", "GET /escrow/{job_id}"],
+        error_codes: errors::CODES
+            .iter()
+            .map(|s| ErrorCode { code: s.code, status: s.status.as_u16(), category: s.category })
+            .collect(),
+    }
+}
+
+// One-line summary logged at startup so operators can see what this instance will do.
+pub fn log_banner(state: &AppState) {
+    let c = current(state);
+    info!(
+        version = c.version,
+        attestation = c.active_attestation_backend.as_str(),
+        max_blob_bytes = c.max_blob_bytes,
+        profile = c.walrus_profile,
+        aggregators = c.walrus_aggregators,
+        disk_cache = c.disk_cache,
+        formats = ?c.formats,
+        config_hash = %state.config_hash,
+        "Nautilus capabilities"
+    );
+}
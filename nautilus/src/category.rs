@@ -0,0 +1,103 @@
+use serde::{Deserialize, Serialize};
+
+use crate::quality_validator::CheckWeights;
+
+// Dataset category taxonomy. The byte-level checks behave very differently across kinds of data
+// (plain text has low byte variance, imagery is near-uniform), so each category carries its own
+// check weights and a calibration range that maps raw scores onto a common 0..=100 scale.
+// Scores are only comparable within a category; the category is part of the signed attestation.
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Category {
+    #[default]
+    Generic,
+    TextCorpus,
+    TabularFinance,
+    ImagerySatellite,
+    AudioSpeech,
+}
+
+#[derive(Debug, Clone)]
+pub struct CategoryPreset {
+    pub weights: CheckWeights,
+    // Raw scores at or below `.0` map to 0, at or above `.1` to 100, linearly in between.
+    pub calibration: (u8, u8),
+}
+
+impl CategoryPreset {
+    pub fn calibrate(&self, raw: u8) -> u8 {
+        let (lo, hi) = self.calibration;
+        if hi <= lo {
+            return raw;
+        }
+        let clamped = raw.clamp(lo, hi);
+        ((clamped - lo) as u32 * 100 / (hi - lo) as u32) as u8
+    }
+}
+
+impl Category {
+    pub const ALL: [Category; 5] = [
+        Category::Generic,
+        Category::TextCorpus,
+        Category::TabularFinance,
+        Category::ImagerySatellite,
+        Category::AudioSpeech,
+    ];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Category::Generic => "generic",
+            Category::TextCorpus => "text-corpus",
+            Category::TabularFinance => "tabular-finance",
+            Category::ImagerySatellite => "imagery-satellite",
+            Category::AudioSpeech => "audio-speech",
+        }
+    }
+
+    // Presets are provisional and should be re-fit as audited breakdowns accumulate
+    // (see POST /policy/simulate).
+    pub fn preset(&self) -> CategoryPreset {
+        let (weights, calibration) = match self {
+            Category::Generic => (CheckWeights::default(), (0, 100)),
+            // Byte variance says little about prose; lean on repetition and completeness.
+            Category::TextCorpus => (
+                CheckWeights { diversity: 25, bias: 5, authenticity: 35, completeness: 20, consistency: 15, ..Default::default() },
+                (5, 85),
+            ),
+            // Rows of delimited numbers: structure and completeness matter most.
+            Category::TabularFinance => (
+                CheckWeights { diversity: 15, bias: 15, authenticity: 30, completeness: 20, consistency: 20, ..Default::default() },
+                (10, 90),
+            ),
+            // Encoded pixels are high-entropy; low diversity means flat or corrupt tiles.
+            Category::ImagerySatellite => (
+                CheckWeights { diversity: 35, bias: 25, authenticity: 20, completeness: 15, consistency: 5, ..Default::default() },
+                (20, 95),
+            ),
+            Category::AudioSpeech => (
+                CheckWeights { diversity: 30, bias: 25, authenticity: 25, completeness: 15, consistency: 5, ..Default::default() },
+                (15, 95),
+            ),
+        };
+        CategoryPreset { weights, calibration }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_category_names_and_calibration() {
+        for c in Category::ALL {
+            let parsed: Category = serde_json::from_str(&format!("\"{}\"", c.as_str())).unwrap();
+            assert_eq!(parsed, c);
+        }
+        let text = Category::TextCorpus.preset();
+        assert_eq!(text.calibrate(0), 0);
+        assert_eq!(text.calibrate(45), 50);
+        assert_eq!(text.calibrate(100), 100);
+        assert_eq!(Category::Generic.preset().calibrate(42), 42);
+    }
+}
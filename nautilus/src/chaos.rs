@@ -0,0 +1,127 @@
+// Fault injection for resilience testing (cargo feature "chaos").
+// Randomly delays or fails Walrus fetches, NSM calls and signing so retry, failover and
+// job-failure handling can be exercised under failure storms. Without the feature every
+// hook compiles to a no-op.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FaultPoint {
+    WalrusFetch,
+    Nsm,
+    Signing,
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("chaos: injected {0:?} failure")]
+pub struct InjectedFault(pub FaultPoint);
+
+#[cfg(not(feature = "chaos"))]
+pub async fn inject(_point: FaultPoint) -> Result<(), InjectedFault> {
+    Ok(())
+}
+
+#[cfg(feature = "chaos")]
+pub use enabled::*;
+
+#[cfg(feature = "chaos")]
+mod enabled {
+    use super::{FaultPoint, InjectedFault};
+    use anyhow::{Context, Result};
+    use ring::rand::{SecureRandom, SystemRandom};
+    use serde::{Deserialize, Serialize};
+    use std::env;
+    use std::sync::{OnceLock, RwLock};
+    use std::time::Duration;
+    use tracing::warn;
+
+    use crate::metrics;
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct FaultSpec {
+        // Probability (0..=1) that the call fails outright.
+        #[serde(default)]
+        pub fail: f64,
+        // Probability (0..=1) that the call is delayed by `delay_ms` first.
+        #[serde(default)]
+        pub delay: f64,
+        #[serde(default)]
+        pub delay_ms: u64,
+    }
+
+    #[derive(Debug, Clone, Default, Serialize, Deserialize)]
+    pub struct ChaosConfig {
+        #[serde(default)]
+        pub walrus_fetch: FaultSpec,
+        #[serde(default)]
+        pub nsm: FaultSpec,
+        #[serde(default)]
+        pub signing: FaultSpec,
+    }
+
+    fn config() -> &'static RwLock<ChaosConfig> {
+        static CONFIG: OnceLock<RwLock<ChaosConfig>> = OnceLock::new();
+        CONFIG.get_or_init(|| {
+            // NAUTILUS_CHAOS holds the initial config as JSON, e.g. {"walrus_fetch":{"fail":0.2}}
+            let initial = env::var("NAUTILUS_CHAOS")
+                .ok()
+                .and_then(|raw| serde_json::from_str(&raw).ok())
+                .unwrap_or_default();
+            RwLock::new(initial)
+        })
+    }
+
+    pub fn current() -> ChaosConfig {
+        config().read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    // Replace the active fault config (admin endpoint).
+    pub fn update(raw: &[u8]) -> Result<ChaosConfig> {
+        let next: ChaosConfig = serde_json::from_slice(raw).context("Invalid chaos config")?;
+        *config().write().unwrap_or_else(|e| e.into_inner()) = next.clone();
+        warn!(?next, "Chaos fault injection config updated");
+        Ok(next)
+    }
+
+    pub async fn inject(point: FaultPoint) -> Result<(), InjectedFault> {
+        let cfg = current();
+        let spec = match point {
+            FaultPoint::WalrusFetch => cfg.walrus_fetch,
+            FaultPoint::Nsm => cfg.nsm,
+            FaultPoint::Signing => cfg.signing,
+        };
+        let label = format!("{:?}", point);
+        if spec.delay_ms > 0 && roll(spec.delay) {
+            metrics::inc_counter("nautilus_chaos_injected_total", "Injected faults by point and kind", &[("point", &label), ("kind", "delay")]);
+            tokio::time::sleep(Duration::from_millis(spec.delay_ms)).await;
+        }
+        if roll(spec.fail) {
+            metrics::inc_counter("nautilus_chaos_injected_total", "Injected faults by point and kind", &[("point", &label), ("kind", "fail")]);
+            warn!(?point, "Chaos: injecting failure");
+            return Err(InjectedFault(point));
+        }
+        Ok(())
+    }
+
+    fn roll(probability: f64) -> bool {
+        if probability <= 0.0 {
+            return false;
+        }
+        let mut buf = [0u8; 4];
+        if SystemRandom::new().fill(&mut buf).is_err() {
+            return false;
+        }
+        (u32::from_le_bytes(buf) as f64 / u32::MAX as f64) < probability
+    }
+}
+
+#[cfg(all(test, feature = "chaos"))]
+mod tests {
+    use super::enabled::ChaosConfig;
+
+    #[test]
+    fn test_partial_config_defaults_to_no_faults() {
+        let cfg: ChaosConfig = serde_json::from_str(r#"{"nsm":{"fail":0.5}}"#).unwrap();
+        assert_eq!(cfg.nsm.fail, 0.5);
+        assert_eq!(cfg.walrus_fetch.fail, 0.0);
+        assert_eq!(cfg.signing.delay_ms, 0);
+    }
+}
@@ -0,0 +1,378 @@
+use anyhow::{anyhow, bail, ensure, Result};
+use arrow_array::{Array, Float64Array, RecordBatch};
+use arrow_cast::display::{ArrayFormatter, FormatOptions};
+use arrow_schema::{DataType, SchemaRef};
+use bytes::Bytes;
+use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+use parquet::file::metadata::ParquetMetaDataReader;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Cursor;
+
+use crate::column_stats::{ColumnSummarizer, ColumnSummary};
+use crate::content_policy::DetectedType;
+use crate::distribution::{self, DistributionReport, NumericColumn};
+use crate::quality_validator::SharedProfile;
+
+// Parquet and Arrow IPC datasets are scored from their columns. The bytes of a compressed,
+// dictionary-encoded file say little about the data in it: byte entropy is high for any of them.
+// The file is decoded through the arrow/parquet readers instead and each column profiled for null
+// counts (completeness), cardinality (diversity), how much of it one value takes up (bias) and,
+// for numeric columns, whether the values look made up (authenticity, see distribution). Each
+// decoded column is also summarized for the report (see column_stats).
+// Decoding needs the whole file, so it is buffered up to MAX_DECODE_BYTES; past that only the
+// tail is kept and a Parquet file is judged on the null counts in its footer statistics. Whatever
+// the columns can't answer falls back to the byte-level check of the same name.
+
+const MAX_DECODE_BYTES: usize = 256 * 1024 * 1024;
+// Tail kept once the file is too large to decode, for the Parquet footer.
+const TAIL_BYTES: usize = 16 * 1024 * 1024;
+const BATCH_ROWS: usize = 8192;
+// Distinct values tracked per column; beyond it cardinality is a lower bound.
+const MAX_TRACKED_VALUES: usize = 65_536;
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnStats {
+    pub name: String,
+    pub data_type: String,
+    pub values: u64,
+    pub nulls: u64,
+    // Distinct non-null values and the share the most common one takes; absent for footer stats.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distinct: Option<u64>,
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub distinct_capped: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub top_share: Option<f64>,
+    // Distribution tests, for numeric columns.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub distribution: Option<DistributionReport>,
+    // Aggregates of the decoded values; absent for footer stats.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ColumnSummary>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ColumnarReport {
+    // "data" when the values were decoded, "footer" for Parquet footer statistics only.
+    pub source: &'static str,
+    pub rows: u64,
+    pub columns: Vec<ColumnStats>,
+    // Why the file couldn't be decoded, when it couldn't.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl ColumnarReport {
+    fn authenticity(&self) -> Option<u32> {
+        let reports: Vec<DistributionReport> = self.columns.iter().filter_map(|c| c.distribution.clone()).collect();
+        distribution::score(&reports)
+    }
+
+    fn completeness(&self) -> Option<u32> {
+        let values: u64 = self.columns.iter().map(|c| c.values).sum();
+        let nulls: u64 = self.columns.iter().map(|c| c.nulls).sum();
+        (values > 0).then(|| ((1.0 - nulls as f64 / values as f64) * 100.0).round() as u32)
+    }
+
+    // Log-scaled cardinality per column: 0 for a constant column, 1 when every value differs.
+    fn diversity(&self) -> Option<u32> {
+        let per_column = self.columns.iter().filter_map(|c| {
+            let present = c.values - c.nulls;
+            let distinct = c.distinct?;
+            (present > 1).then(|| ((distinct.max(1) as f64).ln() / (present as f64).ln()).clamp(0.0, 1.0))
+        });
+        mean(per_column).map(|d| (d * 100.0).round() as u32)
+    }
+
+    // How far each column's most common value is over its fair share (1/distinct), inverted.
+    fn bias(&self) -> Option<u32> {
+        let per_column = self.columns.iter().filter_map(|c| {
+            let (distinct, top) = (c.distinct.filter(|&d| d > 1)? as f64, c.top_share?);
+            let fair = 1.0 / distinct;
+            Some(1.0 - ((top - fair) / (1.0 - fair)).clamp(0.0, 1.0))
+        });
+        mean(per_column).map(|b| (b * 100.0).round() as u32)
+    }
+}
+
+fn mean(values: impl Iterator<Item = f64>) -> Option<f64> {
+    let (sum, n) = values.fold((0.0, 0usize), |(sum, n), v| (sum + v, n + 1));
+    (n > 0).then(|| sum / n as f64)
+}
+
+// Hashes formatted values without allocating them, counting their characters.
+struct FnvWriter {
+    hash: u64,
+    chars: u64,
+}
+
+impl fmt::Write for FnvWriter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        for &b in s.as_bytes() {
+            self.hash = (self.hash ^ b as u64).wrapping_mul(FNV_PRIME);
+        }
+        self.chars += s.chars().count() as u64;
+        Ok(())
+    }
+}
+
+struct ColumnAcc {
+    name: String,
+    data_type: String,
+    values: u64,
+    nulls: u64,
+    counts: HashMap<u64, u64>,
+    capped: bool,
+    numbers: Option<NumericColumn>,
+    summary: ColumnSummarizer,
+    // Whether values are strings, whose lengths are summarized.
+    text: bool,
+}
+
+impl ColumnAcc {
+    fn add(&mut self, array: &dyn Array) -> Result<()> {
+        self.values += array.len() as u64;
+        self.nulls += array.null_count() as u64;
+        let floats = match self.numbers.as_mut() {
+            Some(numbers) => {
+                let floats = arrow_cast::cast(array, &DataType::Float64)?;
+                let floats =
+                    floats.as_any().downcast_ref::<Float64Array>().ok_or_else(|| anyhow!("cast to Float64 failed"))?.clone();
+                floats.iter().flatten().for_each(|v| numbers.add(v));
+                Some(floats)
+            }
+            None => None,
+        };
+        let formatter = ArrayFormatter::try_new(array, &FormatOptions::default())?;
+        for i in 0..array.len() {
+            if !array.is_valid(i) {
+                self.summary.null();
+                continue;
+            }
+            let mut value = FnvWriter { hash: FNV_OFFSET, chars: 0 };
+            fmt::Write::write_fmt(&mut value, format_args!("{}", formatter.value(i)))?;
+            let hash = value.hash;
+            match &floats {
+                Some(floats) => self.summary.number(hash, floats.value(i)),
+                None if self.text => self.summary.text(hash, value.chars),
+                None => self.summary.other(hash),
+            }
+            let tracked = self.counts.len() < MAX_TRACKED_VALUES;
+            match self.counts.get_mut(&hash) {
+                Some(n) => *n += 1,
+                None if tracked => {
+                    self.counts.insert(hash, 1);
+                }
+                None => self.capped = true,
+            }
+        }
+        Ok(())
+    }
+
+    fn stats(self, index: usize) -> ColumnStats {
+        let present = self.values - self.nulls;
+        let top = self.counts.values().copied().max().unwrap_or(0);
+        ColumnStats {
+            name: self.name,
+            data_type: self.data_type,
+            values: self.values,
+            nulls: self.nulls,
+            distinct: Some(self.counts.len() as u64),
+            distinct_capped: self.capped,
+            top_share: (present > 0).then(|| (top as f64 * 1000.0 / present as f64).round() / 1000.0),
+            distribution: self.numbers.map(|n| n.report(index, None)),
+            stats: Some(self.summary.summary()),
+        }
+    }
+}
+
+fn profile_batches(schema: SchemaRef, batches: impl Iterator<Item = Result<RecordBatch>>) -> Result<ColumnarReport> {
+    let mut columns: Vec<ColumnAcc> = schema
+        .fields()
+        .iter()
+        .map(|f| ColumnAcc {
+            name: f.name().clone(),
+            data_type: f.data_type().to_string(),
+            values: 0,
+            nulls: 0,
+            counts: HashMap::new(),
+            capped: false,
+            numbers: f.data_type().is_numeric().then(NumericColumn::default),
+            summary: ColumnSummarizer::default(),
+            text: matches!(f.data_type(), DataType::Utf8 | DataType::LargeUtf8 | DataType::Utf8View),
+        })
+        .collect();
+    let mut rows = 0u64;
+    for batch in batches {
+        let batch = batch?;
+        rows += batch.num_rows() as u64;
+        for (acc, array) in columns.iter_mut().zip(batch.columns()) {
+            acc.add(array.as_ref())?;
+        }
+    }
+    Ok(ColumnarReport { source: "data", rows, columns: columns.into_iter().enumerate().map(|(i, c)| c.stats(i)).collect(), error: None })
+}
+
+fn decode_parquet(data: Bytes) -> Result<ColumnarReport> {
+    let reader = ParquetRecordBatchReaderBuilder::try_new(data)?.with_batch_size(BATCH_ROWS).build()?;
+    let schema = arrow_array::RecordBatchReader::schema(&reader);
+    profile_batches(schema, reader.map(|b| b.map_err(Into::into)))
+}
+
+fn decode_arrow(data: Bytes) -> Result<ColumnarReport> {
+    let reader = arrow_ipc::reader::FileReader::try_new(Cursor::new(data), None)?;
+    let schema = reader.schema();
+    profile_batches(schema, reader.map(|b| b.map_err(Into::into)))
+}
+
+// Null counts from the footer of a Parquet file, given (at least) its last bytes.
+fn parquet_footer(tail: &[u8]) -> Result<ColumnarReport> {
+    let n = tail.len();
+    ensure!(n >= 8 && tail.ends_with(b"PAR1"), "no Parquet footer");
+    let len = u32::from_le_bytes([tail[n - 8], tail[n - 7], tail[n - 6], tail[n - 5]]) as usize;
+    if len + 8 > n {
+        bail!("Parquet footer is larger than the {} bytes kept", n);
+    }
+    let metadata = ParquetMetaDataReader::decode_metadata(&tail[n - 8 - len..n - 8])?;
+    let schema = metadata.file_metadata().schema_descr();
+    let columns = (0..schema.num_columns())
+        .map(|i| {
+            let chunks = metadata.row_groups().iter().map(|rg| rg.column(i));
+            let (values, nulls) = chunks.fold((0u64, 0u64), |(values, nulls), chunk| {
+                let chunk_nulls = chunk.statistics().and_then(|s| s.null_count_opt()).unwrap_or(0);
+                (values + chunk.num_values().max(0) as u64, nulls + chunk_nulls)
+            });
+            ColumnStats {
+                name: schema.column(i).path().string(),
+                data_type: format!("{:?}", schema.column(i).physical_type()),
+                values,
+                nulls,
+                distinct: None,
+                distinct_capped: false,
+                top_share: None,
+                distribution: None,
+                stats: None,
+            }
+        })
+        .collect();
+    Ok(ColumnarReport {
+        source: "footer",
+        rows: metadata.file_metadata().num_rows().max(0) as u64,
+        columns,
+        error: None,
+    })
+}
+
+// The file as it streams in, decoded once at `finish` for every check that shares it.
+pub struct ColumnarProfile {
+    format: DetectedType,
+    buf: Vec<u8>,
+    // Too large to decode; `buf` holds only the tail.
+    overflowed: bool,
+    report: Option<ColumnarReport>,
+}
+
+impl ColumnarProfile {
+    pub fn new(format: DetectedType) -> Self {
+        Self { format, buf: Vec::new(), overflowed: false, report: None }
+    }
+}
+
+impl SharedProfile for ColumnarProfile {
+    fn update(&mut self, data: &[u8]) {
+        self.buf.extend_from_slice(data);
+        let keep = if self.overflowed { 2 * TAIL_BYTES } else { MAX_DECODE_BYTES };
+        if self.buf.len() > keep {
+            self.overflowed = true;
+            self.buf.drain(..self.buf.len() - TAIL_BYTES);
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.report.is_some() {
+            return;
+        }
+        let data = Bytes::from(std::mem::take(&mut self.buf));
+        let decoded = match self.format {
+            _ if self.overflowed => Err(anyhow!("larger than the {} bytes decoded", MAX_DECODE_BYTES)),
+            DetectedType::Parquet => decode_parquet(data.clone()),
+            _ => decode_arrow(data.clone()),
+        };
+        self.report = Some(decoded.unwrap_or_else(|err| {
+            let footer = (self.format == DetectedType::Parquet).then(|| parquet_footer(&data).ok()).flatten();
+            let empty = ColumnarReport { source: "data", rows: 0, columns: Vec::new(), error: None };
+            ColumnarReport { error: Some(err.to_string()), ..footer.unwrap_or(empty) }
+        }));
+    }
+
+    fn score(&self, check: &str) -> Option<u32> {
+        let report = self.report.as_ref()?;
+        match check {
+            "completeness" => report.completeness(),
+            "diversity" => report.diversity(),
+            "bias" => report.bias(),
+            "authenticity" => report.authenticity(),
+            _ => None,
+        }
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.buf.capacity() as u64
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        serde_json::to_value(self.report.as_ref()?).ok().map(|v| ("columns", v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::quality_validator::{validate_dataset_quality, ValidationOptions};
+    use arrow_array::{Int64Array, StringArray};
+    use parquet::arrow::ArrowWriter;
+    use std::sync::Arc;
+
+    #[test]
+    fn test_scored_from_columns() {
+        // A unique id, a 90/10 label and a note that is half null, with five values otherwise.
+        let ids = Int64Array::from_iter_values(0..100);
+        let labels = StringArray::from_iter_values((0..100).map(|i| if i < 90 { "a" } else { "b" }));
+        let notes = StringArray::from_iter((0..100).map(|i| (i % 2 == 0).then(|| format!("n{}", i % 5))));
+        let batch = arrow_array::RecordBatch::try_from_iter([
+            ("id", Arc::new(ids) as Arc<dyn arrow_array::Array>),
+            ("label", Arc::new(labels) as _),
+            ("note", Arc::new(notes) as _),
+        ])
+        .unwrap();
+        let mut parquet = Vec::new();
+        let mut writer = ArrowWriter::try_new(&mut parquet, batch.schema(), None).unwrap();
+        writer.write(&batch).unwrap();
+        writer.close().unwrap();
+        let mut arrow = Vec::new();
+        let mut writer = arrow_ipc::writer::FileWriter::try_new(&mut arrow, &batch.schema()).unwrap();
+        writer.write(&batch).unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        // Footer statistics alone still carry the null counts, but nothing about cardinality.
+        let footer = super::parquet_footer(&parquet).unwrap();
+        assert_eq!((footer.source, footer.rows, footer.columns[2].nulls), ("footer", 100, 50));
+        assert_eq!((footer.completeness(), footer.diversity()), (Some(83), None));
+
+        for data in [parquet, arrow] {
+            let report = validate_dataset_quality(&data, &ValidationOptions::default()).unwrap();
+            let b = &report.breakdown;
+            // 50 nulls in 300 values; cardinality ln(d)/ln(n) of 1, 0.15 and 0.41; label 80% skewed.
+            assert_eq!((b.completeness, b.diversity, b.bias, b.consistency), (Some(83), Some(52), Some(73), Some(100)));
+            assert_eq!(report.details["columns"]["rows"], 100);
+            assert_eq!(report.details["columns"]["columns"][2]["nulls"], 50);
+            let stats = &report.details["columns"]["columns"];
+            assert_eq!((stats[0]["stats"]["numeric"]["max"].as_f64(), stats[0]["stats"]["cardinality"].as_u64()), (Some(99.0), Some(100)));
+            assert_eq!((stats[2]["stats"]["null_ratio"].as_f64(), stats[2]["stats"]["text_length"]["mean"].as_f64()), (Some(0.5), Some(2.0)));
+        }
+    }
+}
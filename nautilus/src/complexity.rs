@@ -0,0 +1,183 @@
+use crate::category::Category;
+use crate::quality_validator::QualityCheck;
+
+// How far the data compresses, as a structure signal. Byte entropy only sees how often each
+// value occurs, so a few records repeated or templated over and over score like varied data;
+// a general-purpose compressor (zstd, level 1) sees the repetition and shrinks it to almost
+// nothing. The "complexity" check compresses blocks of the data on their own and scores the
+// compressed size over the raw size: real records keep well above MIN_RATIO, repetitive
+// synthetic data falls below it. Blocks are sampled at a stride that doubles whenever
+// 2 * MAX_SAMPLES have been kept, dropping every other one, so the work and the state stay
+// bounded and the samples stay spread over the whole blob.
+
+// Weight in every category unless NAUTILUS_CHECK_WEIGHTS overrides it.
+const COMPLEXITY_WEIGHT: u32 = 10;
+const BLOCK: usize = 64 * 1024;
+const MAX_SAMPLES: usize = 16;
+const LEVEL: i32 = 1;
+// A trailing partial block shorter than this is only compressed when nothing else was.
+const MIN_TAIL: usize = 4096;
+// Compressed over raw size at or below which the check scores 0, and from which it scores 100.
+const MIN_RATIO: f64 = 0.02;
+const FULL_RATIO: f64 = 0.08;
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    index: u64,
+    raw: u64,
+    compressed: u64,
+}
+
+pub struct ComplexityCheck {
+    // The block being filled, when it is sampled.
+    block: Vec<u8>,
+    filled: usize,
+    index: u64,
+    stride: u64,
+    samples: Vec<Sample>,
+    freq: [u64; 256],
+}
+
+impl Default for ComplexityCheck {
+    fn default() -> Self {
+        Self { block: Vec::new(), filled: 0, index: 0, stride: 1, samples: Vec::new(), freq: [0; 256] }
+    }
+}
+
+impl ComplexityCheck {
+    fn sampled(&self) -> bool {
+        self.index.is_multiple_of(self.stride)
+    }
+
+    fn compress_block(&mut self) {
+        let compressed = zstd::bulk::compress(&self.block, LEVEL).map_or(self.block.len(), |c| c.len());
+        self.samples.push(Sample { index: self.index, raw: self.block.len() as u64, compressed: compressed as u64 });
+        if self.samples.len() == 2 * MAX_SAMPLES {
+            self.stride *= 2;
+            let stride = self.stride;
+            self.samples.retain(|s| s.index.is_multiple_of(stride));
+        }
+    }
+
+    fn ratio(&self) -> Option<f64> {
+        let raw: u64 = self.samples.iter().map(|s| s.raw).sum();
+        let compressed: u64 = self.samples.iter().map(|s| s.compressed).sum();
+        (raw > 0).then(|| compressed as f64 / raw as f64)
+    }
+
+    // Shannon entropy of the byte values over 8 bits: the ratio an order-0 coder would reach.
+    fn entropy_ratio(&self) -> f64 {
+        let n: u64 = self.freq.iter().sum();
+        if n == 0 {
+            return 0.0;
+        }
+        let bits: f64 = self.freq.iter().filter(|&&c| c > 0).map(|&c| c as f64 / n as f64).map(|p| -p * p.log2()).sum();
+        bits / 8.0
+    }
+}
+
+impl QualityCheck for ComplexityCheck {
+    fn name(&self) -> &'static str {
+        "complexity"
+    }
+
+    fn weight(&self, _category: Category) -> u32 {
+        COMPLEXITY_WEIGHT
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        for &b in data {
+            self.freq[b as usize] += 1;
+        }
+        while !data.is_empty() {
+            let take = data.len().min(BLOCK - self.filled);
+            if self.sampled() {
+                self.block.extend_from_slice(&data[..take]);
+            }
+            self.filled += take;
+            data = &data[take..];
+            if self.filled == BLOCK {
+                if self.sampled() {
+                    self.compress_block();
+                }
+                self.block.clear();
+                self.filled = 0;
+                self.index += 1;
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        let tail = self.block.len() >= MIN_TAIL || self.samples.is_empty();
+        if !self.block.is_empty() && tail {
+            self.compress_block();
+        }
+        self.block = Vec::new();
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        match self.ratio() {
+            Some(ratio) => ((ratio - MIN_RATIO) / (FULL_RATIO - MIN_RATIO) * 100.0).clamp(0.0, 100.0) as u32,
+            None => 100,
+        }
+    }
+
+    fn state_bytes(&self) -> u64 {
+        (self.block.capacity() + self.samples.capacity() * std::mem::size_of::<Sample>()) as u64
+    }
+
+    fn measures(&self, _total_len: u64) -> Vec<(&'static str, f64)> {
+        let mut out = vec![("entropy_ratio", self.entropy_ratio())];
+        out.extend(self.ratio().map(|r| ("compression_ratio", r)));
+        out
+    }
+
+    // The sampled blocks, by offset, with their compressed sizes.
+    fn evidence(&self) -> Option<serde_json::Value> {
+        let samples: Vec<_> = self
+            .samples
+            .iter()
+            .map(|s| serde_json::json!({ "offset": s.index * BLOCK as u64, "length": s.raw, "compressed": s.compressed }))
+            .collect();
+        Some(serde_json::json!({ "codec": "zstd", "level": LEVEL, "samples": samples }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(data: &[u8]) -> ComplexityCheck {
+        let mut check = ComplexityCheck::default();
+        data.chunks(10_000).for_each(|c| check.update(c));
+        check.finish();
+        check
+    }
+
+    #[test]
+    fn test_repetition_caught_where_entropy_is_fooled() {
+        // Varied records and the same handful of records over and over.
+        let mut x = 0x2545_f491u64;
+        let varied: Vec<u8> = (0..20_000)
+            .flat_map(|i| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                format!("{},user{},{}.{:02},{}\n", i, x % 9973, x % 5000, x % 100, ["red", "green", "blue"][(x % 3) as usize])
+                    .into_bytes()
+            })
+            .collect();
+        let repeated: Vec<u8> = varied[..varied.len() / 400].repeat(400);
+        let (varied, repeated) = (run(&varied), run(&repeated));
+        assert!((varied.entropy_ratio() - repeated.entropy_ratio()).abs() < 0.02);
+        assert_eq!(varied.score(0), 100);
+        assert!(repeated.score(0) < 20, "{}", repeated.score(0));
+
+        // Sampling stays bounded and spread out.
+        assert!(varied.samples.len() < 2 * MAX_SAMPLES && varied.samples.last().unwrap().index > 0);
+        let big = run(&vec![7u8; 40 * BLOCK]);
+        assert!(big.samples.len() < 2 * MAX_SAMPLES && big.stride > 1);
+        assert_eq!(big.score(0), 0);
+        assert_eq!(run(b"").score(0), 100);
+    }
+}
@@ -0,0 +1,309 @@
+use anyhow::{bail, Context, Result};
+use serde::Deserialize;
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::content_policy::DetectedType;
+use crate::quality_validator::{
+    round3, ChecksConfig, CheckReport, QualityAccumulator, QualityBreakdown, QualityReport, ValidationOptions,
+};
+use crate::sampling::SampleInfo;
+use crate::scoring::{Aggregate, ScoringSummary};
+
+// Composite blobs: a JSON manifest line followed by sections of different kinds, e.g. a JSON
+// description and a Parquet payload. Sniffed as such, and only as such, when the first line is
+// an object whose first member is "format": "nautilus-composite-v1":
+//
+//   {"format": "nautilus-composite-v1", "sections": [
+//     {"name": "meta", "offset": 0, "length": 512, "type": "json", "weight": 1},
+//     {"name": "rows", "offset": 512, "length": 1048576, "weight": 4}]}\n<sections...>
+//
+// Offsets count from the byte after the manifest's newline. Each section is sniffed and scored
+// on its own by the checks registered for its format; a declared `type` is only compared with
+// what was sniffed. The section scores combine with the manifest's weights under the configured
+// aggregate formula, and per check likewise. Bytes outside every section aren't scored, only
+// counted. A manifest that doesn't parse leaves the blob scored as a whole, with the error noted.
+
+const FORMAT: &str = "nautilus-composite-v1";
+const MAX_MANIFEST_BYTES: usize = 64 * 1024;
+const MAX_SECTIONS: usize = 64;
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct Manifest {
+    format: String,
+    sections: Vec<SectionSpec>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct SectionSpec {
+    name: String,
+    offset: u64,
+    length: u64,
+    #[serde(default, rename = "type")]
+    declared: Option<DetectedType>,
+    #[serde(default = "default_weight")]
+    weight: u32,
+}
+
+fn default_weight() -> u32 {
+    1
+}
+
+// Whether `head` opens with a composite manifest.
+pub fn is_manifest(head: &[u8]) -> bool {
+    let prefix = format!("{{\"format\":\"{}\"", FORMAT);
+    let compact = head.iter().copied().filter(|b| !b.is_ascii_whitespace()).take(prefix.len());
+    compact.eq(prefix.bytes())
+}
+
+// The manifest has started but its line hasn't ended yet.
+pub fn awaiting_manifest(head: &[u8]) -> bool {
+    head.len() < MAX_MANIFEST_BYTES && !head.contains(&b'\n') && is_manifest(head)
+}
+
+struct Section {
+    spec: SectionSpec,
+    acc: QualityAccumulator,
+    received: u64,
+}
+
+pub struct Composite {
+    sections: Vec<Section>,
+    // Payload bytes seen, i.e. the offset of the next one.
+    offset: u64,
+    uncovered: u64,
+    config: ChecksConfig,
+}
+
+impl Composite {
+    // Parse the manifest at the start of `head`; returns the composite and the manifest's length.
+    pub fn start(head: &[u8], opts: &ValidationOptions, config: &ChecksConfig) -> Result<(Self, usize)> {
+        let end = head.iter().position(|&b| b == b'\n').context("manifest line doesn't end")?;
+        let manifest: Manifest = serde_json::from_slice(&head[..end]).context("invalid manifest")?;
+        if manifest.format != FORMAT {
+            bail!("unknown manifest format '{}'", manifest.format);
+        }
+        let mut specs = manifest.sections;
+        if specs.is_empty() || specs.len() > MAX_SECTIONS {
+            bail!("a manifest lists 1 to {} sections", MAX_SECTIONS);
+        }
+        specs.sort_by_key(|s| s.offset);
+        let mut names = BTreeSet::new();
+        for (i, spec) in specs.iter().enumerate() {
+            if spec.name.is_empty() || !names.insert(spec.name.as_str()) {
+                bail!("section names must be present and unique");
+            }
+            if spec.length == 0 {
+                bail!("section '{}' is empty", spec.name);
+            }
+            let overlaps = specs.get(i + 1).is_some_and(|next| spec.offset.saturating_add(spec.length) > next.offset);
+            if overlaps {
+                bail!("section '{}' overlaps the next one", spec.name);
+            }
+        }
+        if specs.iter().all(|s| s.weight == 0) {
+            bail!("every section has weight 0");
+        }
+        let sections = specs
+            .into_iter()
+            .map(|spec| Section { spec, acc: QualityAccumulator::section(opts, config), received: 0 })
+            .collect();
+        Ok((Self { sections, offset: 0, uncovered: 0, config: config.clone() }, end + 1))
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let (start, end) = (self.offset, self.offset + data.len() as u64);
+        let mut covered = 0;
+        for section in self.sections.iter_mut() {
+            let from = section.spec.offset.max(start);
+            let to = section.spec.offset.saturating_add(section.spec.length).min(end);
+            if from < to {
+                section.acc.update(&data[(from - start) as usize..(to - start) as usize]);
+                section.received += to - from;
+                covered += to - from;
+            }
+        }
+        self.uncovered += data.len() as u64 - covered;
+        self.offset = end;
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        self.sections.iter().map(|s| s.acc.state_bytes()).sum()
+    }
+
+    pub fn finish(self) -> Result<QualityReport> {
+        let aggregate = self.config.aggregate;
+        let mut scores = Vec::new();
+        let mut listed = Vec::new();
+        let mut reports = Vec::new();
+        for section in self.sections {
+            let spec = &section.spec;
+            let truncated = section.received < spec.length;
+            let mut entry = serde_json::json!({
+                "name": spec.name,
+                "offset": spec.offset,
+                "length": spec.length,
+                "weight": spec.weight,
+            });
+            // A section the blob doesn't fully contain, or that can't be scored (e.g. it is still
+            // encrypted), scores 0.
+            let report = match section.acc.finish() {
+                _ if truncated => None,
+                Ok(report) => Some(report),
+                Err(err) => {
+                    entry["error"] = format!("{:#}", err).into();
+                    None
+                }
+            };
+            match &report {
+                Some(report) => {
+                    entry["type"] = report.format.as_str().into();
+                    entry["score"] = report.score.into();
+                    entry["breakdown"] = serde_json::to_value(&report.breakdown)?;
+                    if !report.details.is_empty() {
+                        entry["details"] = serde_json::to_value(&report.details)?;
+                    }
+                    if spec.declared.is_some_and(|d| d != report.format) {
+                        entry["declared_type"] = spec.declared.map(DetectedType::as_str).into();
+                    }
+                }
+                None if truncated => {
+                    entry["score"] = 0.into();
+                    entry["truncated"] = true.into();
+                    entry["received"] = section.received.into();
+                }
+                None => entry["score"] = 0.into(),
+            }
+            scores.push((report.as_ref().map_or(0, |r| r.score as u32), spec.weight));
+            listed.push(entry);
+            reports.extend(report.map(|r| (spec.name.clone(), spec.weight, r)));
+        }
+        let score = aggregate.combine(scores.into_iter());
+
+        let mut breakdown = QualityBreakdown::default();
+        let mut names: Vec<String> = reports.iter().flat_map(|(_, _, r)| r.breakdown.scores().map(|(n, _)| n.to_string())).collect();
+        names.sort();
+        names.dedup();
+        for name in &names {
+            let per_section = reports.iter().filter_map(|(_, w, r)| Some((r.breakdown.check(name)?, *w)));
+            breakdown.set(name, Aggregate::WeightedMean.combine(per_section) as u32);
+        }
+
+        let mut scoring = ScoringSummary { aggregate, overridden: self.config.overridden, ..Default::default() };
+        let mut evidence: BTreeMap<&'static str, serde_json::Value> = BTreeMap::new();
+        let mut encrypted_fields = Vec::new();
+        let mut sampling: Option<SampleInfo> = None;
+        for (name, _, report) in reports {
+            scoring.weights.extend(report.scoring.weights);
+            scoring.minimums.extend(report.scoring.minimums);
+            scoring.below_minimum.extend(report.scoring.below_minimum);
+            scoring.label_column = scoring.label_column.or(report.scoring.label_column);
+            scoring.timestamp_column = scoring.timestamp_column.or(report.scoring.timestamp_column);
+            scoring.declared_parameters = scoring.declared_parameters.or(report.scoring.declared_parameters);
+            scoring.json_schema_sha256 = scoring.json_schema_sha256.or(report.scoring.json_schema_sha256);
+            scoring.plugins.extend(report.scoring.plugins);
+            for (check, artifact) in report.evidence {
+                evidence.entry(check).or_insert_with(|| serde_json::json!({ "sections": {} }))["sections"][&name] = artifact;
+            }
+            encrypted_fields.extend(report.encrypted_fields);
+            if let Some(info) = report.sampling {
+                match sampling.as_mut() {
+                    Some(all) => {
+                        all.records_sampled += info.records_sampled;
+                        all.records_seen += info.records_seen;
+                    }
+                    None => sampling = Some(info),
+                }
+            }
+        }
+        scoring.below_minimum.sort();
+        scoring.below_minimum.dedup();
+
+        let total_weight = names.iter().map(|n| scoring.weights.get(n).copied().unwrap_or(0)).sum::<u32>().max(1) as f64;
+        let checks = breakdown
+            .scores()
+            .filter_map(|(name, score)| {
+                let name = self.config.check_name(name)?;
+                let weight = scoring.weights.get(name).copied().unwrap_or(0);
+                Some((name, CheckReport {
+                    score,
+                    weight,
+                    share: round3(weight as f64 / total_weight),
+                    minimum: scoring.minimums.get(name).copied(),
+                    measures: BTreeMap::new(),
+                    details: BTreeMap::new(),
+                }))
+            })
+            .collect();
+        let details = BTreeMap::from([(
+            "composite",
+            serde_json::json!({ "sections": listed, "uncovered_bytes": self.uncovered }),
+        )]);
+        Ok(QualityReport {
+            score,
+            format: DetectedType::Composite,
+            breakdown,
+            sampling,
+            encrypted_fields,
+            details,
+            evidence,
+            scoring,
+            checks,
+        })
+    }
+}
+
+// The manifest couldn't be used; the blob is scored as a whole.
+pub fn manifest_error(err: &anyhow::Error) -> serde_json::Value {
+    serde_json::json!({ "error": format!("{:#}", err) })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_policy;
+    use crate::quality_validator::validate_dataset_quality;
+
+    fn blob(manifest: &str, sections: &[&[u8]]) -> Vec<u8> {
+        let mut out = format!("{}\n", manifest).into_bytes();
+        sections.iter().for_each(|s| out.extend_from_slice(s));
+        out
+    }
+
+    #[test]
+    fn test_sections_scored_and_weighted() {
+        let json: Vec<u8> = (0..300).flat_map(|i| format!("{{\"id\": {}, \"v\": \"x{}\"}}\n", i, i * 7).into_bytes()).collect();
+        let zeros = vec![0u8; 4000];
+        let manifest = format!(
+            r#"{{ "format": "nautilus-composite-v1", "sections": [
+                {{"name": "rows", "offset": 0, "length": {}, "type": "jsonl", "weight": 3}},
+                {{"name": "pad", "offset": {}, "length": 4000, "type": "csv"}}]}}"#,
+            json.len(),
+            json.len()
+        )
+        .replace('\n', " ");
+        let data = blob(&manifest, &[&json, &zeros, b"trailer"]);
+        assert_eq!(content_policy::sniff(&data[..content_policy::SNIFF_LEN]), DetectedType::Composite);
+
+        let report = validate_dataset_quality(&data, &ValidationOptions::default()).unwrap();
+        let alone = |bytes: &[u8]| validate_dataset_quality(bytes, &ValidationOptions::default()).unwrap().score as u32;
+        assert_eq!(report.format, DetectedType::Composite);
+        assert_eq!(report.score as u32, (alone(&json) * 3 + alone(&zeros)) / 4);
+        let composite = &report.details["composite"];
+        assert_eq!(composite["uncovered_bytes"], 7);
+        assert_eq!((composite["sections"][0]["type"].as_str(), composite["sections"][1]["declared_type"].as_str()), (Some("jsonl"), Some("csv")));
+        assert!(report.evidence["authenticity"]["sections"]["pad"].is_object());
+
+        // A section the blob ends inside of scores 0.
+        let cut = validate_dataset_quality(&data[..data.len() - 100], &ValidationOptions::default()).unwrap();
+        assert_eq!(cut.score as u32, alone(&json) * 3 / 4);
+        assert_eq!(cut.details["composite"]["sections"][1]["truncated"], true);
+
+        // A bad manifest leaves the blob scored whole.
+        let bad = blob(r#"{"format": "nautilus-composite-v1", "sections": []}"#, &[&json]);
+        let report = validate_dataset_quality(&bad, &ValidationOptions::default()).unwrap();
+        assert!(report.details["composite"]["error"].as_str().unwrap().contains("sections"));
+        assert!(report.breakdown.diversity.is_some());
+    }
+}
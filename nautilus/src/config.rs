@@ -0,0 +1,297 @@
+use anyhow::{Context, Result};
+use std::env;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::arbitration::ArbitrationConfig;
+use crate::archive::ArchiveConfig;
+use crate::cosign::CosignConfig;
+use crate::dedupe::DedupeConfig;
+use crate::escrow::EscrowConfig;
+use crate::evidence::EvidenceConfig;
+use crate::http_source::HttpSourceConfig;
+use crate::integrity::sha256_hex;
+use crate::key_ring::RotationPolicy;
+use crate::key_usage::KeyUsagePolicy;
+use crate::kms_seal::KmsConfig;
+use crate::load_shed::LoadShedPolicy;
+use crate::ipfs_source::IpfsConfig;
+use crate::quality_validator::ChecksConfig;
+use crate::s3_source::S3Config;
+use crate::sampling::SeedSource;
+use crate::screening::ScreeningConfig;
+use crate::submission::SubmissionConfig;
+use crate::tee_attestation::tpm::TpmConfig;
+use crate::tee_attestation::{PayloadEncoding, SignatureScheme};
+use crate::walrus_client::WalrusConfig;
+use crate::watchdog::WatchdogConfig;
+
+// 4 GiB default per-job budget; override with NAUTILUS_JOB_MEMORY_CAP_BYTES.
+const DEFAULT_JOB_MEMORY_CAP: u64 = 4 * 1024 * 1024 * 1024;
+// Verifications retained for policy simulation; override with NAUTILUS_AUDIT_CAPACITY.
+const DEFAULT_AUDIT_CAPACITY: usize = 10_000;
+// 30 days; override with NAUTILUS_ATTESTATION_VALIDITY_SECS, 0 for attestations that never expire.
+const DEFAULT_ATTESTATION_VALIDITY_SECS: u64 = 30 * 24 * 3600;
+
+// Effective service configuration. Read from the environment once at startup so
+// request paths never consult env vars directly.
+#[derive(Debug, Clone)]
+pub struct Config {
+    pub listen_addr: SocketAddr,
+    // Externally reachable base URL, used in links handed to clients (badges).
+    pub public_url: String,
+    pub walrus: WalrusConfig,
+    pub load_shed: LoadShedPolicy,
+    pub job_memory_cap: u64,
+    pub audit_capacity: usize,
+    // Concurrent downloads per batch and the largest batch accepted.
+    pub fetch_parallelism: usize,
+    pub batch_max_items: usize,
+    // Reject verifications that don't carry an expected content digest (see integrity). Off unless
+    // NAUTILUS_REQUIRE_CONTENT_DIGEST=1.
+    pub require_content_digest: bool,
+    // Tenant API key store (see api_keys); bearer auth is enforced only when set, and admin
+    // endpoints are refused when it isn't.
+    pub api_keys_file: Option<PathBuf>,
+    // Per-tenant content policies (see content_policy).
+    pub content_policy_file: Option<PathBuf>,
+    // Persistent sealed volume for enclave state (dedupe index); in-memory fallbacks when unset.
+    pub sealed_dir: Option<PathBuf>,
+    pub dedupe: DedupeConfig,
+    pub archive: ArchiveConfig,
+    pub key_usage: KeyUsagePolicy,
+    pub cosign: CosignConfig,
+    pub http_source: HttpSourceConfig,
+    pub escrow: EscrowConfig,
+    pub submission: SubmissionConfig,
+    // Sealing key for per-check evidence (see evidence); a secret, so not in `effective()`.
+    pub evidence: EvidenceConfig,
+    pub arbitration: ArbitrationConfig,
+    pub s3: S3Config,
+    // Seals the enclave signing key so it survives restarts; off without a key ID.
+    pub kms: KmsConfig,
+    // Signing key rotation schedule and how long retired keys keep verifying (see key_ring).
+    pub key_rotation: RotationPolicy,
+    // Attestation signature scheme; secp256k1 for on-chain verification in Move.
+    pub signature_scheme: SignatureScheme,
+    // Serialization of the signed payload; BCS for Move contracts.
+    pub payload_encoding: PayloadEncoding,
+    // Validity period of attestations (their `expires_at`, rounded up to a minute under bls12381);
+    // None when they never expire.
+    pub attestation_validity: Option<Duration>,
+    // TPM quote fallback for bare-metal hosts; off without an AK handle.
+    pub tpm: TpmConfig,
+    pub ipfs: IpfsConfig,
+    pub screening: ScreeningConfig,
+    pub checks: ChecksConfig,
+    pub watchdog: WatchdogConfig,
+    // Where sample seeds come from (see sampling).
+    pub sample_seed_source: SeedSource,
+}
+
+impl Config {
+    pub fn from_env() -> Result<Self> {
+        let listen_addr = env::var("NAUTILUS_LISTEN_ADDR").unwrap_or_else(|_| "0.0.0.0:3000".into());
+        let listen_addr: SocketAddr = listen_addr
+            .parse()
+            .with_context(|| format!("Invalid NAUTILUS_LISTEN_ADDR '{}'", listen_addr))?;
+        let public_url = env::var("NAUTILUS_PUBLIC_URL")
+            .unwrap_or_else(|_| format!("http://{}", listen_addr))
+            .trim_end_matches('/')
+            .to_string();
+        Ok(Self {
+            listen_addr,
+            public_url,
+            walrus: WalrusConfig::from_env()?,
+            load_shed: LoadShedPolicy::from_env(),
+            job_memory_cap: env::var("NAUTILUS_JOB_MEMORY_CAP_BYTES")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_JOB_MEMORY_CAP),
+            audit_capacity: env::var("NAUTILUS_AUDIT_CAPACITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_AUDIT_CAPACITY),
+            fetch_parallelism: env::var("NAUTILUS_FETCH_PARALLELISM")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(4),
+            batch_max_items: env::var("NAUTILUS_BATCH_MAX_ITEMS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(32),
+            require_content_digest: env::var("NAUTILUS_REQUIRE_CONTENT_DIGEST")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            api_keys_file: env::var("NAUTILUS_API_KEYS_FILE").ok().filter(|v| !v.is_empty()).map(PathBuf::from),
+            content_policy_file: env::var("NAUTILUS_CONTENT_POLICY_FILE").ok().filter(|v| !v.is_empty()).map(PathBuf::from),
+            sealed_dir: env::var("NAUTILUS_SEALED_DIR").ok().filter(|v| !v.is_empty()).map(PathBuf::from),
+            dedupe: DedupeConfig::from_env(),
+            archive: ArchiveConfig::from_env(),
+            key_usage: KeyUsagePolicy::from_env(),
+            cosign: CosignConfig::from_env(),
+            http_source: HttpSourceConfig::from_env(),
+            escrow: EscrowConfig::from_env(),
+            submission: SubmissionConfig::from_env(),
+            evidence: EvidenceConfig::from_env()?,
+            arbitration: ArbitrationConfig::from_env(),
+            s3: S3Config::from_env(),
+            kms: KmsConfig::from_env(),
+            key_rotation: RotationPolicy::from_env(),
+            signature_scheme: SignatureScheme::from_env()?,
+            payload_encoding: PayloadEncoding::from_env()?,
+            attestation_validity: match env::var("NAUTILUS_ATTESTATION_VALIDITY_SECS")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(DEFAULT_ATTESTATION_VALIDITY_SECS)
+            {
+                0 => None,
+                secs => Some(Duration::from_secs(secs)),
+            },
+            tpm: TpmConfig::from_env()?,
+            ipfs: IpfsConfig::from_env(),
+            screening: ScreeningConfig::from_env(),
+            checks: ChecksConfig::from_env()?,
+            watchdog: WatchdogConfig::from_env(),
+            sample_seed_source: SeedSource::from_env()?,
+        })
+    }
+
+    // Operationally relevant settings (policies, allow-lists, backend endpoints) as canonical
+    // JSON with sorted keys. Listen addresses, cache locations and secrets are left out: they
+    // don't change what a verification means. Served at /build-info so the hash can be recomputed.
+    pub fn effective(&self) -> serde_json::Value {
+        let w = &self.walrus;
+        let shed = &self.load_shed;
+        serde_json::json!({
+            "walrus": {
+                "profile": w.profile.as_str(),
+                "aggregator_urls": w.aggregator_urls,
+                "selection": format!("{:?}", w.selection),
+                "max_blob_bytes": w.max_blob_bytes,
+                "request_timeout_ms": w.request_timeout.as_millis() as u64,
+                "retry_max_attempts": w.retry.max_attempts,
+                "retry_statuses": w.retry.retryable.iter().map(|r| format!("{:?}", r)).collect::<Vec<_>>(),
+                "proxy_url": w.proxy_url,
+                "vsock_proxy": w.vsock_proxy.map(|(cid, port)| format!("{}:{}", cid, port)),
+                "download_rate_limit": w.download_rate_limit,
+                "allow_mock": w.allow_mock,
+                "sui_rpc_url": w.sui_rpc_url,
+                "sui_dataset_type": w.sui_dataset_type,
+                "sui_blob_field": w.sui_blob_field,
+                "sui_object_cache_entries": w.sui_object_cache_entries,
+                "sui_checkpoint_poll_ms": w.sui_checkpoint_poll.as_millis() as u64,
+                "status_node_url": w.status_node_url,
+                "publisher_url": w.publisher_url,
+                "seal_key_servers": w.seal_key_servers,
+            },
+            "load_shed": {
+                "mem_pct": [shed.mem_soft_pct, shed.mem_high_pct, shed.mem_hard_pct],
+                "cpu": [shed.cpu_soft, shed.cpu_high, shed.cpu_hard],
+                "sample_rate_pct": shed.sample_rate_pct,
+                "sample_records": shed.sample_records,
+                "seed_source": self.sample_seed_source,
+            },
+            "job_memory_cap": self.job_memory_cap,
+            "batch_max_items": self.batch_max_items,
+            "require_content_digest": self.require_content_digest,
+            "dedupe_similarity": self.dedupe.similarity,
+            "api_key_auth": self.api_keys_file.is_some(),
+            "content_policy": self.content_policy_file.is_some(),
+            "screening": {
+                "mode": self.screening.mode,
+                "bad_hashes": self.screening.bad_hashes_file.is_some(),
+            },
+            "quality_checks": {
+                "disabled": self.checks.disabled,
+                "weights": self.checks.weights,
+                "minimums": self.checks.minimums,
+                "aggregate": self.checks.aggregate,
+                "completeness_thresholds": self.checks.completeness_thresholds,
+                "override_bounds": self.checks.override_bounds,
+                "ngrams": self.checks.ngrams,
+                "plugins": self.checks.plugins.iter().map(|p| (p.name, &p.sha256)).collect::<std::collections::BTreeMap<_, _>>(),
+                "plugin_limits": self.checks.plugins.first().map(|p| {
+                    serde_json::json!({
+                        "fuel": p.limits.fuel,
+                        "memory_bytes": p.limits.memory_bytes,
+                        "max_input_bytes": p.limits.max_input_bytes,
+                    })
+                }),
+                "content_safety": self.checks.lexicon.as_ref().map(|l| serde_json::json!({ "terms": l.len(), "sha256": l.sha256 })),
+            },
+            "cosign": {
+                "signer_url": self.cosign.signer_url,
+                "operator_public_key": self.cosign.operator_public_key,
+                "always": self.cosign.always,
+            },
+            "submission": {
+                "package": self.submission.package,
+                "module": self.submission.module,
+                "function": self.submission.function,
+                "gas_budget": self.submission.gas_budget,
+            },
+            "arbitration": {
+                "arbiter_keys": self.arbitration.arbiter_keys,
+            },
+            "watchdog": {
+                "max_rss_bytes": self.watchdog.max_rss_bytes,
+                "max_fds": self.watchdog.max_fds,
+                "interval_secs": self.watchdog.interval.as_secs(),
+                "drain_secs": self.watchdog.drain_timeout.as_secs(),
+            },
+            "http_source": {
+                "allowlist": self.http_source.allowlist,
+                "allow_plain_http": self.http_source.allow_plain_http,
+            },
+            "s3": {
+                "enabled": self.s3.enabled(),
+                "endpoint": self.s3.endpoint(),
+                "region": self.s3.region,
+                "buckets": self.s3.buckets,
+                "virtual_hosted": self.s3.virtual_hosted,
+            },
+            "ipfs": {
+                "gateways": self.ipfs.gateways,
+            },
+            "kms": {
+                "key_id": self.kms.key_id,
+                "endpoint": self.kms.key_id.as_ref().map(|_| self.kms.endpoint()),
+            },
+            "key_rotation": {
+                "interval_secs": self.key_rotation.interval.map(|i| i.as_secs()),
+                "grace_secs": self.key_rotation.grace.as_secs(),
+            },
+            "signature_scheme": self.signature_scheme.as_str(),
+            "payload_encoding": self.payload_encoding.canonicalization(),
+            "attestation_validity_secs": self.attestation_validity.map(|v| v.as_secs()),
+            "tpm": {
+                "device": self.tpm.device,
+                "ak_handle": self.tpm.ak_handle.map(|h| format!("{:#010x}", h)),
+            },
+            "chaos": cfg!(feature = "chaos"),
+        })
+    }
+
+    // SHA-256 (hex) of `effective()`; bound into every attestation.
+    pub fn hash(&self) -> String {
+        sha256_hex(self.effective().to_string().as_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config_hash_tracks_operational_settings() {
+        let config = Config::from_env().unwrap();
+        let mut moved = config.clone();
+        moved.listen_addr = "127.0.0.1:1".parse().unwrap();
+        assert_eq!(config.hash(), moved.hash());
+        let mut stricter = config.clone();
+        stricter.require_content_digest = !config.require_content_digest;
+        assert_ne!(config.hash(), stricter.hash());
+    }
+}
@@ -0,0 +1,371 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, MutexGuard};
+use std::time::SystemTime;
+use tracing::{info, warn};
+
+use crate::composite;
+use crate::listing::Listed;
+use crate::metrics;
+use crate::model_artifact;
+use crate::screening::ScreeningMode;
+
+// Per-tenant content policies: what a tenant is willing to have verified (e.g. no executables,
+// no archives over 1 GB). Policies live in NAUTILUS_CONTENT_POLICY_FILE, keyed by the tenant of
+// the request's API key with an optional `default` for everyone else, and are re-read when the
+// file changes. They are enforced on the decrypted bytes as they stream: the type is sniffed
+// from the first bytes, then sizes are checked as the blob arrives, so a violating blob is
+// refused without downloading the rest of it.
+//
+// {"default": {"banned_magic": ["4d5a"]},
+//  "tenants": {"acme": {"allowed_types": ["text", "json", "csv"], "max_bytes_by_type": {"archive": 1073741824}}}}
+
+// Bytes sniffed before the type is decided.
+pub const SNIFF_LEN: usize = 512;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DetectedType {
+    Executable,
+    Archive,
+    Image,
+    Pdf,
+    Parquet,
+    Arrow,
+    Json,
+    Jsonl,
+    Csv,
+    Text,
+    Binary,
+    // JSON manifest followed by sections of other types (see composite).
+    Composite,
+    // Safetensors, GGUF or ONNX model weights (see model_artifact).
+    Model,
+}
+
+impl DetectedType {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Executable => "executable",
+            Self::Archive => "archive",
+            Self::Image => "image",
+            Self::Pdf => "pdf",
+            Self::Parquet => "parquet",
+            Self::Arrow => "arrow",
+            Self::Json => "json",
+            Self::Jsonl => "jsonl",
+            Self::Csv => "csv",
+            Self::Text => "text",
+            Self::Binary => "binary",
+            Self::Composite => "composite",
+            Self::Model => "model",
+        }
+    }
+}
+
+// Magic numbers at offset 0, most specific first.
+const MAGIC: &[(&[u8], DetectedType)] = &[
+    (b"\x7fELF", DetectedType::Executable),
+    (b"MZ", DetectedType::Executable),
+    (b"\xcf\xfa\xed\xfe", DetectedType::Executable),
+    (b"\xce\xfa\xed\xfe", DetectedType::Executable),
+    (b"\xca\xfe\xba\xbe", DetectedType::Executable),
+    (b"\0asm", DetectedType::Executable),
+    (b"#!", DetectedType::Executable),
+    (b"PK\x03\x04", DetectedType::Archive),
+    (b"\x1f\x8b", DetectedType::Archive),
+    (b"7z\xbc\xaf\x27\x1c", DetectedType::Archive),
+    (b"Rar!\x1a\x07", DetectedType::Archive),
+    (b"\xfd7zXZ\0", DetectedType::Archive),
+    (b"\x28\xb5\x2f\xfd", DetectedType::Archive),
+    (b"BZh", DetectedType::Archive),
+    (b"\x89PNG\r\n\x1a\n", DetectedType::Image),
+    (b"\xff\xd8\xff", DetectedType::Image),
+    (b"GIF8", DetectedType::Image),
+    (b"%PDF-", DetectedType::Pdf),
+    (b"PAR1", DetectedType::Parquet),
+    (b"ARROW1", DetectedType::Arrow),
+];
+
+pub fn sniff(head: &[u8]) -> DetectedType {
+    if let Some((_, ty)) = MAGIC.iter().find(|(magic, _)| head.starts_with(magic)) {
+        return *ty;
+    }
+    if head.len() > 262 && &head[257..262] == b"ustar" {
+        return DetectedType::Archive;
+    }
+    if model_artifact::sniff(head) {
+        return DetectedType::Model;
+    }
+    // A composite's sections may be binary; only its manifest has to be text.
+    if composite::is_manifest(head) {
+        return DetectedType::Composite;
+    }
+    // Text unless it has control bytes; a multi-byte character cut at the sniff boundary is fine.
+    let text = match std::str::from_utf8(head) {
+        Ok(s) => s,
+        Err(err) if err.error_len().is_none() => std::str::from_utf8(&head[..err.valid_up_to()]).unwrap_or_default(),
+        Err(_) => return DetectedType::Binary,
+    };
+    if text.chars().any(|c| c.is_control() && !c.is_whitespace()) {
+        return DetectedType::Binary;
+    }
+    let text = text.trim_start();
+    match text.chars().next() {
+        // One object per line: the line after the first starts another object.
+        Some('{') if text.split_once('\n').is_some_and(|(_, rest)| rest.trim_start().starts_with('{')) => {
+            DetectedType::Jsonl
+        }
+        Some('{') | Some('[') => DetectedType::Json,
+        _ if looks_like_csv(text) => DetectedType::Csv,
+        _ => DetectedType::Text,
+    }
+}
+
+// Comma-separated, and every complete line in the head has as many (unquoted) commas as the
+// first; prose has commas too, but rarely the same number line after line.
+fn looks_like_csv(text: &str) -> bool {
+    let commas = |line: &str| line.split('"').step_by(2).map(|part| part.matches(',').count()).sum::<usize>();
+    let mut lines = text.split('\n');
+    let header = lines.next().map(commas).unwrap_or(0);
+    // The last piece may be cut off by the sniff limit.
+    let complete: Vec<&str> = lines.collect();
+    let complete = &complete[..complete.len().saturating_sub(1)];
+    header > 0 && complete.iter().filter(|l| !l.trim().is_empty()).take(4).all(|l| commas(l) == header)
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ContentPolicy {
+    // Types that may be verified; any type when empty.
+    #[serde(default)]
+    pub allowed_types: Vec<DetectedType>,
+    #[serde(default)]
+    pub max_bytes: Option<u64>,
+    // Tighter caps for particular types, e.g. archives.
+    #[serde(default)]
+    pub max_bytes_by_type: HashMap<DetectedType, u64>,
+    // Hex prefixes refused outright, whatever type they sniff as.
+    #[serde(default)]
+    pub banned_magic: Vec<String>,
+    // Overrides NAUTILUS_SCREENING for this tenant (see screening).
+    #[serde(default)]
+    pub screening: Option<ScreeningMode>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct PolicyFile {
+    #[serde(default)]
+    default: Option<ContentPolicy>,
+    #[serde(default)]
+    tenants: HashMap<String, ContentPolicy>,
+}
+
+impl PolicyFile {
+    fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        let file: Self = serde_json::from_slice(&raw).with_context(|| format!("parse {}", path.display()))?;
+        for policy in file.default.iter().chain(file.tenants.values()) {
+            for magic in &policy.banned_magic {
+                hex::decode(magic).with_context(|| format!("banned_magic '{}' is not hex", magic))?;
+            }
+        }
+        Ok(file)
+    }
+}
+
+// The blob breaks its tenant's content policy.
+#[derive(Debug, thiserror::Error)]
+#[error("CONTENT_POLICY_VIOLATION: {rule}: {detail}")]
+pub struct ContentPolicyViolation {
+    pub tenant: String,
+    pub rule: &'static str,
+    pub detail: String,
+}
+
+// Server-side view of the policy file, reloaded whenever its mtime changes.
+pub struct ContentPolicies {
+    path: PathBuf,
+    cached: Mutex<(Option<SystemTime>, PolicyFile)>,
+}
+
+impl ContentPolicies {
+    pub fn open(path: PathBuf) -> Result<Self> {
+        let file = PolicyFile::load(&path)?;
+        info!(path = %path.display(), tenants = file.tenants.len(), "Content policies enabled");
+        let mtime = std::fs::metadata(&path).and_then(|m| m.modified()).ok();
+        Ok(Self { path, cached: Mutex::new((mtime, file)) })
+    }
+
+    // The tenant's own policy, else the default; None when neither exists.
+    pub fn for_tenant(&self, tenant: Option<&str>) -> Option<ContentPolicy> {
+        let cached = self.current();
+        tenant.and_then(|t| cached.1.tenants.get(t)).or(cached.1.default.as_ref()).cloned()
+    }
+
+    // Every policy in the file, for GET /policies.
+    pub fn entries(&self) -> Vec<PolicyEntry> {
+        let cached = self.current();
+        let tenants = cached.1.tenants.iter().map(|(t, p)| PolicyEntry { id: t.clone(), status: "tenant", policy: p.clone() });
+        let default = cached.1.default.iter().map(|p| PolicyEntry { id: DEFAULT_POLICY_ID.into(), status: "default", policy: p.clone() });
+        tenants.chain(default).collect()
+    }
+
+    fn current(&self) -> MutexGuard<'_, (Option<SystemTime>, PolicyFile)> {
+        let mut cached = self.cached.lock().unwrap_or_else(|e| e.into_inner());
+        let mtime = std::fs::metadata(&self.path).and_then(|m| m.modified()).ok();
+        if mtime != cached.0 {
+            match PolicyFile::load(&self.path) {
+                Ok(file) => *cached = (mtime, file),
+                // Keep enforcing the last good copy.
+                Err(err) => warn!(%err, "Failed to reload content policy file"),
+            }
+        }
+        cached
+    }
+}
+
+// Listed under "*" so it can't collide with a tenant name.
+const DEFAULT_POLICY_ID: &str = "*";
+
+// One policy of the file: a tenant's, or the default for tenants without one.
+#[derive(Debug, Serialize)]
+pub struct PolicyEntry {
+    pub id: String,
+    // "tenant" or "default".
+    pub status: &'static str,
+    pub policy: ContentPolicy,
+}
+
+impl Listed for PolicyEntry {
+    fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn status(&self) -> &str {
+        self.status
+    }
+}
+
+// Enforces one policy over a blob's plaintext as it streams past.
+pub struct PolicyCheck {
+    policy: ContentPolicy,
+    tenant: String,
+    head: Vec<u8>,
+    detected: Option<DetectedType>,
+    seen: u64,
+}
+
+impl PolicyCheck {
+    pub fn new(policy: ContentPolicy, tenant: Option<&str>) -> Self {
+        let tenant = tenant.unwrap_or("default").to_string();
+        Self { policy, tenant, head: Vec::new(), detected: None, seen: 0 }
+    }
+
+    pub fn update(&mut self, plaintext: &[u8]) -> Result<(), ContentPolicyViolation> {
+        self.seen += plaintext.len() as u64;
+        if self.detected.is_none() {
+            let take = plaintext.len().min(SNIFF_LEN - self.head.len());
+            self.head.extend_from_slice(&plaintext[..take]);
+            if self.head.len() >= SNIFF_LEN {
+                self.classify()?;
+            }
+        }
+        self.check_size()
+    }
+
+    // Decides on blobs shorter than the sniff window and returns the detected type. `total_len`
+    // is the full blob size when only a sample streamed past.
+    pub fn finish(mut self, total_len: Option<u64>) -> Result<DetectedType, ContentPolicyViolation> {
+        if self.detected.is_none() {
+            self.classify()?;
+        }
+        self.seen = self.seen.max(total_len.unwrap_or(0));
+        self.check_size()?;
+        Ok(self.detected.unwrap_or(DetectedType::Binary))
+    }
+
+    fn classify(&mut self) -> Result<(), ContentPolicyViolation> {
+        if let Some(magic) = self
+            .policy
+            .banned_magic
+            .iter()
+            .find(|m| hex::decode(m).is_ok_and(|bytes| self.head.starts_with(&bytes)))
+        {
+            return Err(self.violation("banned_magic", format!("content starts with banned bytes {}", magic)));
+        }
+        let ty = sniff(&self.head);
+        self.detected = Some(ty);
+        if !self.policy.allowed_types.is_empty() && !self.policy.allowed_types.contains(&ty) {
+            return Err(self.violation("type_not_allowed", format!("{} content is not accepted", ty.as_str())));
+        }
+        Ok(())
+    }
+
+    fn check_size(&self) -> Result<(), ContentPolicyViolation> {
+        let by_type = self.detected.and_then(|ty| self.policy.max_bytes_by_type.get(&ty).copied());
+        match by_type.into_iter().chain(self.policy.max_bytes).min() {
+            Some(limit) if self.seen > limit => {
+                let ty = self.detected.map(DetectedType::as_str).unwrap_or("blob");
+                Err(self.violation("max_bytes", format!("{} content exceeds {} bytes", ty, limit)))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn violation(&self, rule: &'static str, detail: String) -> ContentPolicyViolation {
+        warn!(tenant = %self.tenant, rule, %detail, "Content policy violation");
+        metrics::inc_counter(
+            "nautilus_content_policy_violations_total",
+            "Blobs refused by a tenant content policy",
+            &[("rule", rule)],
+        );
+        ContentPolicyViolation { tenant: self.tenant.clone(), rule, detail }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_enforced_while_streaming() {
+        assert_eq!(sniff(b"\x7fELF\x02\x01"), DetectedType::Executable);
+        assert_eq!(sniff(b"PK\x03\x04rest"), DetectedType::Archive);
+        assert_eq!(sniff(b"  {\"a\": 1}"), DetectedType::Json);
+        assert_eq!(sniff(b"{\"a\": 1}\n{\"a\": 2}\n"), DetectedType::Jsonl);
+        assert_eq!(sniff(b"{\n  \"a\": 1\n}"), DetectedType::Json);
+        assert_eq!(sniff(b"id,name\n1,x\n"), DetectedType::Csv);
+        assert_eq!(sniff(b"plain words\n"), DetectedType::Text);
+        assert_eq!(sniff(b"Well, it rained.\nThe roads, fields and barns flooded.\nNo one left.\n"), DetectedType::Text);
+        assert_eq!(sniff(&[0u8, 159, 146, 150]), DetectedType::Binary);
+
+        let policy = ContentPolicy {
+            allowed_types: vec![DetectedType::Text, DetectedType::Archive],
+            max_bytes_by_type: HashMap::from([(DetectedType::Archive, 1000)]),
+            banned_magic: vec!["cafe".into()],
+            ..Default::default()
+        };
+        let mut check = PolicyCheck::new(policy.clone(), Some("acme"));
+        check.update(b"PK\x03\x04").unwrap();
+        check.update(&[1u8; 600]).unwrap();
+        assert_eq!(check.update(&[1u8; 600]).unwrap_err().rule, "max_bytes");
+
+        let mut check = PolicyCheck::new(policy.clone(), None);
+        check.update(&[0xca, 0xfe, 0x00]).unwrap();
+        assert_eq!(check.finish(None).unwrap_err().rule, "banned_magic");
+
+        let mut check = PolicyCheck::new(policy.clone(), None);
+        check.update(b"\x7fELF").unwrap();
+        assert_eq!(check.finish(None).unwrap_err().rule, "type_not_allowed");
+
+        let mut check = PolicyCheck::new(policy.clone(), None);
+        check.update(&b"hello world ".repeat(100)).unwrap();
+        assert_eq!(check.finish(None).unwrap(), DetectedType::Text);
+        // A sample is held to the size of the whole blob.
+        let mut check = PolicyCheck::new(policy, None);
+        check.update(b"PK\x03\x04").unwrap();
+        assert_eq!(check.finish(Some(5000)).unwrap_err().rule, "max_bytes");
+    }
+}
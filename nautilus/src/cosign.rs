@@ -0,0 +1,156 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use reqwest::Client;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::time::Duration;
+use tracing::info;
+
+use crate::tee_attestation::key_id;
+
+// Split-trust co-signing. For high-value attestations the enclave signs as usual, then forwards
+// SHA-256 of the signed payload to an operator-held signer (an HSM or KMS behind a small HTTP
+// bridge) and embeds its signature next to its own, marking the envelope "-cosigned". POST
+// /attestation/verify fails such an envelope unless both signatures check out; relying parties
+// that insist on the cosigned format are then safe from either key being compromised alone.
+//
+// Bridge contract: POST {"key_id", "digest_hex"} -> {"signature_b64"}, an ed25519 signature over
+// the 32 digest bytes by the key pinned in NAUTILUS_COSIGNER_PUBLIC_KEY.
+
+#[derive(Debug, Clone)]
+pub struct CosignConfig {
+    pub signer_url: Option<String>,
+    // Base64 ed25519 public key the signer must sign with.
+    pub operator_public_key: Option<String>,
+    // Co-sign every attestation, not only those that request it.
+    pub always: bool,
+    pub timeout: Duration,
+}
+
+impl CosignConfig {
+    pub fn from_env() -> Self {
+        Self {
+            signer_url: env::var("NAUTILUS_COSIGNER_URL").ok().filter(|u| !u.is_empty()),
+            operator_public_key: env::var("NAUTILUS_COSIGNER_PUBLIC_KEY").ok().filter(|k| !k.is_empty()),
+            always: env::var("NAUTILUS_COSIGN_ALL")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+            timeout: Duration::from_secs(
+                env::var("NAUTILUS_COSIGNER_TIMEOUT_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(10),
+            ),
+        }
+    }
+}
+
+// A co-signature could not be obtained; the attestation is refused rather than issued with the
+// enclave signature alone.
+#[derive(Debug, thiserror::Error)]
+#[error("COSIGN_UNAVAILABLE: {0}")]
+pub struct CosignUnavailable(pub String);
+
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Cosignature {
+    // SHA-256 of the serialized attestation payload; what the operator key signed.
+    pub digest_hex: String,
+    pub key_id: String,
+    pub public_key_b64: String,
+    pub signature_b64: String,
+}
+
+#[derive(Serialize)]
+struct SignRequest<'a> {
+    key_id: &'a str,
+    digest_hex: &'a str,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature_b64: String,
+}
+
+pub struct OperatorSigner {
+    http: Client,
+    url: String,
+    public_key: PublicKey,
+    timeout: Duration,
+}
+
+impl OperatorSigner {
+    // None when no signer is configured.
+    pub fn from_config(config: &CosignConfig) -> Result<Option<Self>> {
+        let Some(url) = &config.signer_url else {
+            return Ok(None);
+        };
+        let raw = config
+            .operator_public_key
+            .as_deref()
+            .context("NAUTILUS_COSIGNER_URL requires NAUTILUS_COSIGNER_PUBLIC_KEY")?;
+        let public_key = STANDARD
+            .decode(raw)
+            .ok()
+            .and_then(|bytes| PublicKey::from_bytes(&bytes).ok())
+            .context("NAUTILUS_COSIGNER_PUBLIC_KEY is not a base64 ed25519 public key")?;
+        info!(url = %url, key_id = %key_id(&public_key), always = config.always, "Operator co-signing enabled");
+        Ok(Some(Self { http: Client::new(), url: url.clone(), public_key, timeout: config.timeout }))
+    }
+
+    pub async fn cosign(&self, digest: &[u8; 32]) -> Result<Cosignature> {
+        let kid = key_id(&self.public_key);
+        let digest_hex = hex::encode(digest);
+        let resp: SignResponse = self
+            .http
+            .post(&self.url)
+            .timeout(self.timeout)
+            .json(&SignRequest { key_id: &kid, digest_hex: &digest_hex })
+            .send()
+            .await
+            .map_err(|err| CosignUnavailable(format!("operator signer unreachable: {}", err)))?
+            .error_for_status()
+            .map_err(|err| CosignUnavailable(format!("operator signer refused: {}", err)))?
+            .json()
+            .await
+            .map_err(|err| CosignUnavailable(format!("invalid operator signer response: {}", err)))?;
+        let sig = STANDARD
+            .decode(&resp.signature_b64)
+            .ok()
+            .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+            .ok_or_else(|| CosignUnavailable("operator signature is not a base64 ed25519 signature".into()))?;
+        // Never embed a signature the chain would reject.
+        self.public_key
+            .verify(digest, &sig)
+            .map_err(|_| CosignUnavailable(format!("operator signature does not verify against key {}", kid)))?;
+        Ok(Cosignature {
+            digest_hex,
+            key_id: kid,
+            public_key_b64: STANDARD.encode(self.public_key.to_bytes()),
+            signature_b64: resp.signature_b64,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signer_requires_pinned_key() {
+        let config = CosignConfig {
+            signer_url: None,
+            operator_public_key: None,
+            always: false,
+            timeout: Duration::from_secs(1),
+        };
+        assert!(OperatorSigner::from_config(&config).unwrap().is_none());
+        let with_url = CosignConfig { signer_url: Some("http://127.0.0.1:1/sign".into()), ..config };
+        assert!(OperatorSigner::from_config(&with_url).is_err());
+        let bad_key = CosignConfig { operator_public_key: Some("not-a-key".into()), ..with_url.clone() };
+        assert!(OperatorSigner::from_config(&bad_key).is_err());
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[7u8; 32]).unwrap();
+        let public: PublicKey = (&secret).into();
+        let pinned = CosignConfig { operator_public_key: Some(STANDARD.encode(public.to_bytes())), ..with_url };
+        assert!(OperatorSigner::from_config(&pinned).unwrap().is_some());
+    }
+}
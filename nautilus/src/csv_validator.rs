@@ -0,0 +1,627 @@
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+
+use crate::category::Category;
+use crate::column_stats::{ColumnSummarizer, ColumnSummary};
+use crate::distribution::{DistributionReport, NumericColumn};
+use crate::field_encryption::{sniff_delimiter, MAX_HEADER_BYTES};
+use crate::labels::LabelCounts;
+use crate::timeseries::{parse_timestamp, TimeSeries};
+use crate::quality_validator::QualityCheck;
+
+// Structured validation of CSV/TSV datasets. Rows are tokenized as they stream (quoted fields,
+// doubled quotes and CRLF handled), and per-row and per-column statistics replace the byte-level
+// heuristics for two dimensions: completeness is the share of cells that aren't missing, and
+// consistency combines column-count stability, per-column type agreement, the duplicate-row
+// ratio and whether the header is usable. Cells are never kept, only their inferred types and a
+// 64-bit hash per row for duplicate detection. The consistency instance also summarizes each
+// column (see column_stats).
+
+// Columns beyond this are counted but not typed.
+const MAX_COLUMNS: usize = 256;
+// Bytes of a cell looked at for type inference.
+const MAX_CELL_BYTES: usize = 128;
+// Distinct row hashes remembered; rows after that are still checked against the set.
+const MAX_TRACKED_ROWS: usize = 1 << 20;
+// Cell values read as missing, besides empty ones (compared case-insensitively).
+const MISSING: &[&str] = &["na", "n/a", "null", "none", "nan", "-"];
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+// Mixed into the row hash between cells, so "a,bc" and "ab,c" differ.
+const CELL_SEPARATOR: u64 = 0x1f;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CellType {
+    Integer,
+    Float,
+    Boolean,
+    Date,
+    Text,
+}
+
+const CELL_TYPES: [CellType; 5] = [CellType::Integer, CellType::Float, CellType::Boolean, CellType::Date, CellType::Text];
+
+// None for a missing value.
+fn classify(cell: &[u8]) -> Option<CellType> {
+    let text = String::from_utf8_lossy(cell);
+    let text = text.trim();
+    if text.is_empty() || MISSING.iter().any(|m| text.eq_ignore_ascii_case(m)) {
+        return None;
+    }
+    let digits = |s: &[u8]| !s.is_empty() && s.iter().all(u8::is_ascii_digit);
+    let b = text.as_bytes();
+    Some(if digits(text.strip_prefix(['-', '+']).unwrap_or(text).as_bytes()) {
+        CellType::Integer
+    } else if text.parse::<f64>().is_ok_and(f64::is_finite) {
+        CellType::Float
+    } else if ["true", "false", "yes", "no"].iter().any(|v| text.eq_ignore_ascii_case(v)) {
+        CellType::Boolean
+    } else if b.len() >= 10 && digits(&b[..4]) && b[4] == b'-' && digits(&b[5..7]) && b[7] == b'-' && digits(&b[8..10]) {
+        CellType::Date
+    } else {
+        CellType::Text
+    })
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnProfile {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // Most common type among present values; None when the column is all missing.
+    pub inferred: Option<CellType>,
+    // Share of present values of the inferred type.
+    pub type_consistency: f64,
+    pub missing_ratio: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub stats: Option<ColumnSummary>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CsvReport {
+    pub delimiter: String,
+    pub header: bool,
+    // Problems with the header row: "empty_names", "duplicate_names", "width_mismatch".
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub header_issues: Vec<&'static str>,
+    // Data rows, not counting the header.
+    pub rows: u64,
+    // Fields per row in most rows, and rows with any other count.
+    pub columns: usize,
+    pub ragged_rows: u64,
+    pub missing_ratio: f64,
+    pub duplicate_rows: u64,
+    pub duplicate_ratio: f64,
+    pub column_profiles: Vec<ColumnProfile>,
+}
+
+impl CsvReport {
+    pub fn completeness(&self) -> u32 {
+        if self.rows == 0 {
+            return 0;
+        }
+        ((1.0 - self.missing_ratio) * 100.0).round() as u32
+    }
+
+    // Column-count stability 40%, type agreement 30%, distinct rows 20%, usable header 10%.
+    pub fn consistency(&self) -> u32 {
+        if self.rows == 0 {
+            return 0;
+        }
+        let stable = 1.0 - self.ragged_rows as f64 / self.rows as f64;
+        let typed: Vec<f64> =
+            self.column_profiles.iter().filter(|c| c.inferred.is_some()).map(|c| c.type_consistency).collect();
+        let types = if typed.is_empty() { 0.0 } else { typed.iter().sum::<f64>() / typed.len() as f64 };
+        let header = if self.header && self.header_issues.is_empty() { 1.0 } else { 0.5 };
+        ((0.4 * stable + 0.3 * types + 0.2 * (1.0 - self.duplicate_ratio) + 0.1 * header) * 100.0).round() as u32
+    }
+}
+
+#[derive(Default, Clone, Copy)]
+struct Column {
+    missing: u64,
+    types: [u64; 5],
+}
+
+// A cell as the column summaries see it: its hash, and its number or length in characters.
+#[derive(Clone, Copy)]
+enum CellValue {
+    Missing,
+    Number(u64, f64),
+    Text(u64, u64),
+    Other(u64),
+}
+
+struct Row {
+    cells: Vec<Option<CellType>>,
+    // Filled only when columns are summarized.
+    values: Vec<CellValue>,
+    width: usize,
+    hash: u64,
+    // Value in the label column, when one is tracked, and the hash of every other cell.
+    label: Option<String>,
+    features: u64,
+    // Value of the timestamp column, when one is tracked.
+    timestamp: Option<i64>,
+}
+
+impl Row {
+    fn new() -> Self {
+        Self { cells: Vec::new(), values: Vec::new(), width: 0, hash: FNV_OFFSET, label: None, features: FNV_OFFSET, timestamp: None }
+    }
+
+    fn mix(&mut self, b: u64) {
+        self.hash = (self.hash ^ b).wrapping_mul(FNV_PRIME);
+    }
+
+    fn mix_features(&mut self, b: u64) {
+        self.features = (self.features ^ b).wrapping_mul(FNV_PRIME);
+    }
+
+    fn is_blank(&self) -> bool {
+        let mut empty = Row::new();
+        empty.mix(CELL_SEPARATOR);
+        self.width == 1 && self.hash == empty.hash
+    }
+}
+
+// Streaming CSV statistics; input is the dataset in order, in chunks of any size.
+pub struct CsvStats {
+    track_duplicates: bool,
+    // Buffered first line until the delimiter is known.
+    head: Option<Vec<u8>>,
+    delimiter: u8,
+    in_quotes: bool,
+    // A quote inside a quoted field: either its end or the first half of a doubled quote.
+    quote_pending: bool,
+    cell: Vec<u8>,
+    row: Row,
+    // The first row and its cell text, judged as header or data at `finish`.
+    first: Option<(Row, Vec<String>)>,
+    names: Vec<String>,
+    header: Option<Vec<String>>,
+    widths: HashMap<usize, u64>,
+    columns: Vec<Column>,
+    rows: u64,
+    cells: u64,
+    missing: u64,
+    seen: HashSet<u64>,
+    duplicates: u64,
+    // Label column by header name, its index once the header is read, and the class counts.
+    label: Option<(String, Option<usize>, LabelCounts)>,
+    // Timestamp column by header name, its index once the header is read, and the series.
+    timestamps: Option<(String, Option<usize>, TimeSeries)>,
+    // Numeric values per column, for the distribution tests (see distribution).
+    numbers: Option<Vec<NumericColumn>>,
+    // Per-column aggregates, and the hash and length of the cell being read for them.
+    summaries: Option<Vec<ColumnSummarizer>>,
+    cell_hash: u64,
+    cell_chars: u64,
+}
+
+impl CsvStats {
+    // Duplicate detection keeps a hash per distinct row; it can be left to one instance.
+    pub fn new(track_duplicates: bool) -> Self {
+        Self {
+            track_duplicates,
+            head: Some(Vec::new()),
+            delimiter: b',',
+            in_quotes: false,
+            quote_pending: false,
+            cell: Vec::new(),
+            row: Row::new(),
+            first: None,
+            names: Vec::new(),
+            header: None,
+            widths: HashMap::new(),
+            columns: Vec::new(),
+            rows: 0,
+            cells: 0,
+            missing: 0,
+            seen: HashSet::new(),
+            duplicates: 0,
+            label: None,
+            timestamps: None,
+            numbers: None,
+            summaries: None,
+            cell_hash: FNV_OFFSET,
+            cell_chars: 0,
+        }
+    }
+
+    // Also collect numeric cells for the distribution tests. The first row's are left out even
+    // when it turns out to be data.
+    pub fn with_numbers(mut self) -> Self {
+        self.numbers = Some(Vec::new());
+        self
+    }
+
+    // Also summarize every column (see column_stats).
+    pub fn with_column_stats(mut self) -> Self {
+        self.summaries = Some(Vec::new());
+        self
+    }
+
+    // Distribution tests on the columns that held numbers.
+    pub fn distributions(&self) -> Vec<DistributionReport> {
+        let Some(numbers) = &self.numbers else { return Vec::new() };
+        numbers
+            .iter()
+            .enumerate()
+            .filter(|(_, col)| col.values() > 0)
+            .map(|(idx, col)| col.report(idx, self.header.as_ref().and_then(|h| h.get(idx).cloned())))
+            .collect()
+    }
+
+    // Also count the classes of the named column (see labels).
+    pub fn with_label(mut self, column: &str) -> Self {
+        self.label = Some((column.to_string(), None, LabelCounts::default()));
+        self
+    }
+
+    // Also read the named column as a time series (see timeseries).
+    pub fn with_timestamps(mut self, column: &str, now_ms: i64) -> Self {
+        self.timestamps = Some((column.to_string(), None, TimeSeries::new(now_ms)));
+        self
+    }
+
+    // The timestamp column's series, when the header has it.
+    pub fn timestamps(&self) -> Option<&TimeSeries> {
+        self.timestamps.as_ref().filter(|(_, index, _)| index.is_some()).map(|(_, _, series)| series)
+    }
+
+    // Class counts of the label column, when the header has it.
+    pub fn labels(&self) -> Option<&LabelCounts> {
+        self.label.as_ref().filter(|(_, index, _)| index.is_some()).map(|(_, _, counts)| counts)
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        if let Some(head) = self.head.as_mut() {
+            head.extend_from_slice(data);
+            if !head.contains(&b'\n') && head.len() < MAX_HEADER_BYTES {
+                return;
+            }
+            self.start();
+            return;
+        }
+        self.scan(data);
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        self.seen.capacity() as u64 * 9
+            + (self.columns.len() * std::mem::size_of::<Column>()) as u64
+            + self.head.as_ref().map_or(0, |h| h.capacity() as u64)
+            + self.numbers.as_ref().map_or(0, |n| n.iter().map(NumericColumn::state_bytes).sum())
+            + self.summaries.as_ref().map_or(0, |s| s.iter().map(ColumnSummarizer::state_bytes).sum())
+    }
+
+    // Closes a last row without a trailing newline and decides about the header.
+    pub fn finish(&mut self) {
+        if self.head.is_some() {
+            self.start();
+        }
+        if self.row.width > 0 || !self.cell.is_empty() || self.in_quotes {
+            self.end_row();
+        }
+        let Some((first, names)) = self.first.take() else { return };
+        // A header is text (or blank) throughout; anything typed means the first row is data.
+        let header = first.cells.iter().all(|c| matches!(c, None | Some(CellType::Text)))
+            && first.cells.iter().any(|c| c.is_some());
+        if header {
+            self.header = Some(names);
+        } else {
+            // Without a header there's no column by that name.
+            if let Some((_, index, _)) = self.label.as_mut() {
+                *index = None;
+            }
+            if let Some((_, index, _)) = self.timestamps.as_mut() {
+                *index = None;
+            }
+            self.count(first);
+        }
+    }
+
+    pub fn report(&self) -> CsvReport {
+        let rows = self.rows;
+        let (columns, modal) = self.widths.iter().map(|(&w, &n)| (w, n)).max_by_key(|&(w, n)| (n, w)).unwrap_or_default();
+        let mut header_issues = Vec::new();
+        if let Some(names) = &self.header {
+            if names.iter().any(String::is_empty) {
+                header_issues.push("empty_names");
+            }
+            let distinct: HashSet<&String> = names.iter().filter(|n| !n.is_empty()).collect();
+            if distinct.len() < names.iter().filter(|n| !n.is_empty()).count() {
+                header_issues.push("duplicate_names");
+            }
+            if rows > 0 && names.len() != columns {
+                header_issues.push("width_mismatch");
+            }
+        }
+        let ratio = |n: u64, d: u64| if d == 0 { 0.0 } else { (n as f64 / d as f64 * 10_000.0).round() / 10_000.0 };
+        let column_profiles = self
+            .columns
+            .iter()
+            .enumerate()
+            .map(|(idx, col)| {
+                let present: u64 = col.types.iter().sum();
+                let (ty, n) = CELL_TYPES.iter().zip(col.types).max_by_key(|&(_, n)| n).map(|(t, n)| (*t, n)).unwrap_or((CellType::Text, 0));
+                ColumnProfile {
+                    name: self.header.as_ref().and_then(|h| h.get(idx).cloned()),
+                    inferred: (present > 0).then_some(ty),
+                    type_consistency: ratio(n, present),
+                    missing_ratio: ratio(col.missing, present + col.missing),
+                    stats: self.summaries.as_ref().and_then(|s| s.get(idx)).map(ColumnSummarizer::summary),
+                }
+            })
+            .collect();
+        CsvReport {
+            delimiter: (self.delimiter as char).to_string(),
+            header: self.header.is_some(),
+            header_issues,
+            rows,
+            columns,
+            ragged_rows: rows - modal,
+            missing_ratio: ratio(self.missing, self.cells),
+            duplicate_rows: self.duplicates,
+            duplicate_ratio: ratio(self.duplicates, rows),
+            column_profiles,
+        }
+    }
+
+    fn start(&mut self) {
+        let head = self.head.take().unwrap_or_default();
+        let line = &head[..head.iter().position(|&b| b == b'\n').unwrap_or(head.len())];
+        self.delimiter = sniff_delimiter(line).unwrap_or(b',');
+        self.scan(&head);
+    }
+
+    fn scan(&mut self, data: &[u8]) {
+        for &b in data {
+            if self.in_quotes {
+                if !self.quote_pending {
+                    if b == b'"' {
+                        self.quote_pending = true;
+                    } else {
+                        self.push_byte(b);
+                    }
+                    continue;
+                }
+                self.quote_pending = false;
+                if b == b'"' {
+                    self.push_byte(b);
+                    continue;
+                }
+                self.in_quotes = false;
+            }
+            match b {
+                b'"' if self.cell.is_empty() => self.in_quotes = true,
+                b'\n' => self.end_row(),
+                b'\r' => {}
+                _ if b == self.delimiter => self.end_cell(),
+                _ => self.push_byte(b),
+            }
+        }
+    }
+
+    fn label_index(&self) -> Option<usize> {
+        self.label.as_ref().and_then(|(_, index, _)| *index)
+    }
+
+    fn push_byte(&mut self, b: u8) {
+        self.row.mix(b as u64);
+        if self.label_index() != Some(self.row.width) {
+            self.row.mix_features(b as u64);
+        }
+        if self.cell.len() < MAX_CELL_BYTES {
+            self.cell.push(b);
+        }
+        if self.summaries.is_some() {
+            self.cell_hash = (self.cell_hash ^ b as u64).wrapping_mul(FNV_PRIME);
+            // UTF-8 continuation bytes don't start a character.
+            self.cell_chars += u64::from(b & 0xc0 != 0x80);
+        }
+    }
+
+    fn end_cell(&mut self) {
+        self.row.mix(CELL_SEPARATOR);
+        self.row.mix_features(CELL_SEPARATOR);
+        let cell = std::mem::take(&mut self.cell);
+        let is_label = self.label_index() == Some(self.row.width);
+        if is_label && self.first.is_some() && classify(&cell).is_some() {
+            self.row.label = Some(String::from_utf8_lossy(&cell).into_owned());
+        }
+        let is_timestamp = self.timestamps.as_ref().and_then(|(_, index, _)| *index) == Some(self.row.width);
+        if is_timestamp && self.first.is_some() {
+            self.row.timestamp = std::str::from_utf8(&cell).ok().and_then(parse_timestamp);
+        }
+        let (hash, chars) = (std::mem::replace(&mut self.cell_hash, FNV_OFFSET), std::mem::take(&mut self.cell_chars));
+        if self.row.width < MAX_COLUMNS {
+            let kind = classify(&cell);
+            let number = matches!(kind, Some(CellType::Integer | CellType::Float))
+                .then(|| std::str::from_utf8(&cell).ok().and_then(|c| c.trim().parse::<f64>().ok()).unwrap_or(f64::NAN));
+            if let (Some(numbers), Some(_), Some(value)) = (self.numbers.as_mut(), self.first.as_ref(), number) {
+                if numbers.len() <= self.row.width {
+                    numbers.resize_with(self.row.width + 1, NumericColumn::default);
+                }
+                numbers[self.row.width].add(value);
+            }
+            if self.summaries.is_some() {
+                self.row.values.push(match (kind, number) {
+                    (None, _) => CellValue::Missing,
+                    (_, Some(value)) => CellValue::Number(hash, value),
+                    (Some(CellType::Text), None) => CellValue::Text(hash, chars),
+                    _ => CellValue::Other(hash),
+                });
+            }
+            self.row.cells.push(kind);
+            if self.first.is_none() {
+                self.names.push(String::from_utf8_lossy(&cell[..cell.len().min(64)]).trim().to_string());
+            }
+        }
+        self.row.width += 1;
+    }
+
+    fn end_row(&mut self) {
+        self.end_cell();
+        self.in_quotes = false;
+        self.quote_pending = false;
+        let row = std::mem::replace(&mut self.row, Row::new());
+        let names = std::mem::take(&mut self.names);
+        if row.is_blank() {
+            return;
+        }
+        if self.first.is_none() && self.header.is_none() && self.rows == 0 {
+            if let Some((column, index, _)) = self.label.as_mut() {
+                *index = names.iter().position(|n| n == column);
+            }
+            if let Some((column, index, _)) = self.timestamps.as_mut() {
+                *index = names.iter().position(|n| n == column);
+            }
+            self.first = Some((row, names));
+            return;
+        }
+        self.count(row);
+    }
+
+    fn count(&mut self, row: Row) {
+        self.rows += 1;
+        if let Some((_, Some(_), counts)) = self.label.as_mut() {
+            counts.add(row.label.as_deref(), row.features);
+        }
+        if let Some((_, Some(_), series)) = self.timestamps.as_mut() {
+            series.add(row.timestamp);
+        }
+        *self.widths.entry(row.width).or_default() += 1;
+        if self.columns.len() < row.cells.len() {
+            self.columns.resize(row.cells.len(), Column::default());
+        }
+        for (column, cell) in self.columns.iter_mut().zip(&row.cells) {
+            match cell {
+                None => column.missing += 1,
+                Some(ty) => column.types[*ty as usize] += 1,
+            }
+        }
+        if let Some(summaries) = self.summaries.as_mut() {
+            if summaries.len() < row.values.len() {
+                summaries.resize_with(row.values.len(), ColumnSummarizer::default);
+            }
+            for (summary, value) in summaries.iter_mut().zip(&row.values) {
+                match *value {
+                    CellValue::Missing => summary.null(),
+                    CellValue::Number(hash, value) => summary.number(hash, value),
+                    CellValue::Text(hash, chars) => summary.text(hash, chars),
+                    CellValue::Other(hash) => summary.other(hash),
+                }
+            }
+        }
+        self.cells += row.cells.len() as u64;
+        self.missing += row.cells.iter().filter(|c| c.is_none()).count() as u64;
+        if self.track_duplicates {
+            if self.seen.contains(&row.hash) {
+                self.duplicates += 1;
+            } else if self.seen.len() < MAX_TRACKED_ROWS {
+                self.seen.insert(row.hash);
+            }
+        }
+    }
+}
+
+// CSV completeness or consistency, by `name`. Both parse the rows; only the consistency
+// instance tracks duplicates and reports the details.
+pub struct CsvCheck {
+    name: &'static str,
+    stats: CsvStats,
+}
+
+impl CsvCheck {
+    pub fn completeness() -> Self {
+        Self { name: "completeness", stats: CsvStats::new(false) }
+    }
+
+    pub fn consistency() -> Self {
+        Self { name: "consistency", stats: CsvStats::new(true).with_column_stats() }
+    }
+}
+
+impl QualityCheck for CsvCheck {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        category.preset().weights.get(self.name)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.stats.update(data);
+    }
+
+    fn finish(&mut self) {
+        self.stats.finish();
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        let report = self.stats.report();
+        if self.name == "completeness" {
+            report.completeness()
+        } else {
+            report.consistency()
+        }
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        (self.name == "consistency").then(|| serde_json::to_value(self.stats.report()).ok()).flatten().map(|v| ("csv", v))
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.stats.state_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stats(data: &[u8]) -> CsvReport {
+        let mut stats = CsvStats::new(true).with_column_stats();
+        for chunk in data.chunks(5) {
+            stats.update(chunk);
+        }
+        stats.finish();
+        stats.report()
+    }
+
+    #[test]
+    fn test_csv_structure() {
+        let mut csv = String::from("id,name,amount,joined\r\n");
+        for i in 0..100 {
+            let amount = if i % 10 == 0 { "NA".to_string() } else { format!("{}.5", i) };
+            csv.push_str(&format!("{},\"Doe, \"\"J{}\"\"\",{},2024-01-{:02}\r\n", i, i, amount, i % 28 + 1));
+        }
+        csv.push_str("5,\"Doe, \"\"J5\"\"\",5.5,2024-01-06\r\n");
+        csv.push_str("101,short\n\n");
+        let report = stats(csv.as_bytes());
+        assert!(report.header && report.header_issues.is_empty(), "{:?}", report.header_issues);
+        assert_eq!((report.rows, report.columns, report.ragged_rows), (102, 4, 1));
+        assert_eq!(report.duplicate_rows, 1);
+        let types: Vec<_> = report.column_profiles.iter().map(|c| c.inferred).collect();
+        assert_eq!(types, [Some(CellType::Integer), Some(CellType::Text), Some(CellType::Float), Some(CellType::Date)]);
+        assert_eq!(report.column_profiles[2].missing_ratio, 0.099);
+        let ids = report.column_profiles[0].stats.as_ref().unwrap();
+        assert_eq!((ids.cardinality, ids.numeric.as_ref().map(|r| (r.min, r.max))), (101, Some((0.0, 101.0))));
+        let amounts = report.column_profiles[2].stats.as_ref().unwrap();
+        assert_eq!((amounts.null_ratio, amounts.numeric.as_ref().map(|r| r.max)), (0.099, Some(99.5)));
+        // From `short` to `Doe, "J42"`.
+        let names = report.column_profiles[1].stats.as_ref().unwrap().text_length.as_ref().unwrap();
+        assert_eq!((names.min, names.max), (5.0, 10.0));
+        assert_eq!(report.completeness(), 98);
+        assert!(report.consistency() > 90);
+
+        // Typed first row: no header. Tabs win over commas.
+        let tsv = stats(b"1\t2,5\tx\n3\t4\ty\n");
+        assert_eq!((tsv.header, tsv.rows, tsv.delimiter.as_str()), (false, 2, "\t"));
+        let dup = stats(b"a,a,\n1,2,3\n");
+        assert_eq!(dup.header_issues, ["empty_names", "duplicate_names"]);
+    }
+}
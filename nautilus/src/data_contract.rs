@@ -0,0 +1,318 @@
+use anyhow::{bail, Context, Result};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+use crate::category::Category;
+use crate::quality_validator::QualityCheck;
+
+// Data contracts: a JSON Schema the request carries inline (`json_schema`) or names by blob ID
+// (`json_schema_blob_id`, any source a dataset can come from, stored in the clear). JSON and
+// JSON Lines records are validated against it as they stream past: each line of JSON Lines, each
+// element of a top-level JSON array, or the JSON document itself. The share of conforming
+// records is averaged into the consistency score with the structural indicator, and the schema's
+// canonical SHA-256 (see nautilus-canonical) is attested with the scoring summary, so a buyer can
+// require both the contract and the conformance. Violations are reported by schema keyword and
+// record location only, never with the offending values. Remote `$ref`s aren't fetched.
+
+// Serialized size a schema may have, inline or fetched.
+pub const MAX_SCHEMA_BYTES: u64 = 256 * 1024;
+// A JSON Lines record longer than this isn't parsed; it doesn't conform.
+const MAX_RECORD_BYTES: usize = 1024 * 1024;
+// A JSON document is held whole to be validated; a larger one doesn't conform.
+const MAX_DOCUMENT_BYTES: usize = 32 * 1024 * 1024;
+// Errors counted per failing record, distinct schema locations reported, failing records listed.
+const MAX_ERRORS_PER_RECORD: usize = 8;
+const MAX_VIOLATION_KINDS: usize = 32;
+const MAX_LISTED_FAILURES: usize = 100;
+
+// A compiled schema and its canonical hash.
+#[derive(Debug, Clone)]
+pub struct DataContract {
+    validator: Arc<jsonschema::Validator>,
+    sha256: String,
+}
+
+impl DataContract {
+    pub fn compile(schema: &Value) -> Result<Self> {
+        let canonical = nautilus_canonical::to_canonical_json(schema);
+        if canonical.len() as u64 > MAX_SCHEMA_BYTES {
+            bail!("json_schema exceeds {} bytes", MAX_SCHEMA_BYTES);
+        }
+        let validator = jsonschema::validator_for(schema).map_err(|err| anyhow::anyhow!("invalid json_schema: {}", err))?;
+        Ok(Self { validator: Arc::new(validator), sha256: nautilus_canonical::canonical_sha256_hex(schema) })
+    }
+
+    pub fn parse(bytes: &[u8]) -> Result<Self> {
+        let schema: Value = serde_json::from_slice(bytes).context("json_schema blob isn't JSON")?;
+        Self::compile(&schema)
+    }
+
+    pub fn sha256(&self) -> &str {
+        &self.sha256
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ContractReport {
+    pub schema_sha256: String,
+    pub records: u64,
+    pub conforming: u64,
+    // Percentage of records that conform, when there were any.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conformance: Option<f64>,
+    // Failing records per schema location (JSON pointer to the keyword).
+    pub violations: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub other_violations: Option<u64>,
+    // Records that couldn't be parsed, or were too long to be.
+    pub unparsed: u64,
+}
+
+enum Records {
+    Lines { line: Vec<u8>, overflow: bool },
+    Document { bytes: Vec<u8>, overflow: bool },
+}
+
+// Consistency from the structural indicator (`fallback`) and conformance to the contract.
+pub struct SchemaConformance {
+    contract: DataContract,
+    records: Records,
+    seen: u64,
+    conforming: u64,
+    unparsed: u64,
+    violations: BTreeMap<String, u64>,
+    other_violations: u64,
+    // 1-based numbers of the first failing records, as evidence.
+    failures: Vec<u64>,
+    fallback: Box<dyn QualityCheck>,
+}
+
+impl SchemaConformance {
+    pub fn jsonl(contract: DataContract, fallback: Box<dyn QualityCheck>) -> Self {
+        Self::new(contract, Records::Lines { line: Vec::new(), overflow: false }, fallback)
+    }
+
+    pub fn json(contract: DataContract, fallback: Box<dyn QualityCheck>) -> Self {
+        Self::new(contract, Records::Document { bytes: Vec::new(), overflow: false }, fallback)
+    }
+
+    fn new(contract: DataContract, records: Records, fallback: Box<dyn QualityCheck>) -> Self {
+        Self {
+            contract,
+            records,
+            seen: 0,
+            conforming: 0,
+            unparsed: 0,
+            violations: BTreeMap::new(),
+            other_violations: 0,
+            failures: Vec::new(),
+            fallback,
+        }
+    }
+
+    fn conformance(&self) -> Option<f64> {
+        (self.seen > 0).then(|| self.conforming as f64 / self.seen as f64 * 100.0)
+    }
+
+    fn check(&mut self, record: Option<&Value>) {
+        self.seen += 1;
+        let Some(record) = record else {
+            self.unparsed += 1;
+            self.fail();
+            return;
+        };
+        if self.contract.validator.is_valid(record) {
+            self.conforming += 1;
+            return;
+        }
+        let mut locations: Vec<String> = self
+            .contract
+            .validator
+            .iter_errors(record)
+            .take(MAX_ERRORS_PER_RECORD)
+            .map(|err| err.schema_path.to_string())
+            .collect();
+        locations.sort();
+        locations.dedup();
+        for location in locations {
+            if let Some(count) = self.violations.get_mut(&location) {
+                *count += 1;
+            } else if self.violations.len() < MAX_VIOLATION_KINDS {
+                self.violations.insert(location, 1);
+            } else {
+                self.other_violations += 1;
+            }
+        }
+        self.fail();
+    }
+
+    fn fail(&mut self) {
+        if self.failures.len() < MAX_LISTED_FAILURES {
+            self.failures.push(self.seen);
+        }
+    }
+
+    fn end_line(&mut self) {
+        let Records::Lines { line, overflow } = &mut self.records else { return };
+        if line.iter().all(u8::is_ascii_whitespace) && !*overflow {
+            line.clear();
+            return;
+        }
+        let record = (!*overflow).then(|| serde_json::from_slice::<Value>(line).ok()).flatten();
+        line.clear();
+        *overflow = false;
+        self.check(record.as_ref());
+    }
+
+    pub fn report(&self) -> ContractReport {
+        ContractReport {
+            schema_sha256: self.contract.sha256.clone(),
+            records: self.seen,
+            conforming: self.conforming,
+            conformance: self.conformance().map(|c| (c * 100.0).round() / 100.0),
+            violations: self.violations.clone(),
+            other_violations: (self.other_violations > 0).then_some(self.other_violations),
+            unparsed: self.unparsed,
+        }
+    }
+}
+
+impl QualityCheck for SchemaConformance {
+    fn name(&self) -> &'static str {
+        self.fallback.name()
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        self.fallback.weight(category)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.fallback.update(data);
+        if let Records::Document { bytes, overflow } = &mut self.records {
+            if bytes.len() + data.len() > MAX_DOCUMENT_BYTES {
+                *overflow = true;
+                *bytes = Vec::new();
+            } else if !*overflow {
+                bytes.extend_from_slice(data);
+            }
+            return;
+        }
+        for piece in data.split_inclusive(|&b| b == b'\n') {
+            if let Records::Lines { line, overflow } = &mut self.records {
+                if line.len() + piece.len() > MAX_RECORD_BYTES {
+                    *overflow = true;
+                    line.clear();
+                } else if !*overflow {
+                    line.extend_from_slice(piece);
+                }
+            }
+            if piece.ends_with(b"\n") {
+                self.end_line();
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        self.fallback.finish();
+        let document = match &mut self.records {
+            Records::Lines { .. } => return self.end_line(),
+            Records::Document { bytes, overflow } => {
+                let parsed = (!*overflow).then(|| serde_json::from_slice::<Value>(bytes).ok()).flatten();
+                *bytes = Vec::new();
+                parsed
+            }
+        };
+        match document {
+            Some(Value::Array(items)) => items.iter().for_each(|item| self.check(Some(item))),
+            other => self.check(other.as_ref()),
+        }
+    }
+
+    fn score(&self, total_len: u64) -> u32 {
+        let structure = self.fallback.score(total_len);
+        match self.conformance() {
+            Some(conformance) => (structure + conformance.floor() as u32) / 2,
+            None => structure,
+        }
+    }
+
+    fn exclude(&mut self, freq: &[u64; 256]) {
+        self.fallback.exclude(freq);
+    }
+
+    fn state_bytes(&self) -> u64 {
+        let held = match &self.records {
+            Records::Lines { line, .. } => line.capacity(),
+            Records::Document { bytes, .. } => bytes.capacity(),
+        };
+        held as u64 + self.fallback.state_bytes()
+    }
+
+    // The structural findings, with the contract's under "schema".
+    fn details(&self) -> Option<(&'static str, Value)> {
+        let (key, mut value) = self.fallback.details().unwrap_or(("json", Value::Object(Default::default())));
+        value["schema"] = serde_json::to_value(self.report()).ok()?;
+        Some((key, value))
+    }
+
+    fn measures(&self, total_len: u64) -> Vec<(&'static str, f64)> {
+        let mut out = self.fallback.measures(total_len);
+        out.extend(self.conformance().map(|c| ("schema_conformance", c)));
+        out
+    }
+
+    // The first failing records, by number.
+    fn evidence(&self) -> Option<Value> {
+        let mut artifact = self.fallback.evidence().unwrap_or_else(|| serde_json::json!({}));
+        artifact["schema_failures"] = serde_json::json!({ "schema_sha256": self.contract.sha256, "records": self.failures });
+        Some(artifact)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality_validator::{validate_dataset_quality, ValidationOptions};
+    use serde_json::json;
+
+    #[test]
+    fn test_conformance_scored_and_reported() {
+        let schema = json!({
+            "type": "object",
+            "required": ["id", "price"],
+            "properties": { "id": { "type": "integer" }, "price": { "type": "number", "minimum": 0 } }
+        });
+        let contract = DataContract::compile(&schema).unwrap();
+        assert_eq!(contract.sha256(), nautilus_canonical::canonical_sha256_hex(&schema));
+        assert!(DataContract::compile(&json!({ "type": 12 })).is_err());
+        assert!(DataContract::parse(b"not json").is_err());
+
+        // Every fourth record has a negative price, every tenth no id.
+        let jsonl: String = (0..400)
+            .map(|i| match i {
+                _ if i % 10 == 0 => format!("{{\"price\": {}}}\n", i),
+                _ if i % 4 == 0 => format!("{{\"id\": {}, \"price\": -{}}}\n", i, i),
+                _ => format!("{{\"id\": {}, \"price\": {}.5}}\n", i, i),
+            })
+            .collect();
+        let plain = validate_dataset_quality(jsonl.as_bytes(), &ValidationOptions::default()).unwrap();
+        let opts = ValidationOptions { contract: Some(contract.clone()), ..Default::default() };
+        let report = validate_dataset_quality(jsonl.as_bytes(), &opts).unwrap();
+        let schema_report = &report.details["json"]["schema"];
+        assert_eq!((schema_report["records"].as_u64(), schema_report["conforming"].as_u64()), (Some(400), Some(280)));
+        assert_eq!(schema_report["violations"]["/required"], 40);
+        assert_eq!(schema_report["violations"]["/properties/price/minimum"], 80);
+        let structure = plain.breakdown.consistency.unwrap();
+        assert_eq!(report.breakdown.consistency, Some((structure + 70) / 2));
+        assert_eq!(report.scoring.json_schema_sha256.as_deref(), Some(contract.sha256()));
+        assert_eq!(report.evidence["consistency"]["schema_failures"]["records"][0], 1);
+
+        // A JSON array is validated element by element.
+        let array = br#"[{"id": 1, "price": 2}, {"id": "x", "price": 2}]"#;
+        let report = validate_dataset_quality(array, &opts).unwrap();
+        let schema_report = &report.details["json"]["schema"];
+        assert_eq!((schema_report["records"].as_u64(), schema_report["conformance"].as_f64()), (Some(2), Some(50.0)));
+    }
+}
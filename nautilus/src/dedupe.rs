@@ -0,0 +1,326 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::env;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use crate::metrics;
+use crate::sealed;
+
+// Cross-dataset near-duplicate index. Every fully fetched dataset gets a MinHash signature;
+// signatures are persisted to SQLite (dedupe.sqlite in NAUTILUS_SEALED_DIR, the enclave's
+// persistent sealed volume) and bucketed with LSH bands so a relisted or lightly edited dataset
+// is found without scanning the whole index. A periodic compaction drops entries past the
+// retention window or above the entry cap and returns freed pages to the filesystem.
+
+// One-permutation MinHash: each shingle is hashed once and lands in one of SIG_LEN bins.
+pub const SIG_LEN: usize = 64;
+const SHINGLE: usize = 16;
+// LSH: BANDS x ROWS = SIG_LEN. Pairs with Jaccard ~0.7+ share a band with high probability.
+const BANDS: usize = 16;
+const ROWS: usize = SIG_LEN / BANDS;
+const EMPTY: u64 = u64::MAX;
+
+#[derive(Debug, Clone)]
+pub struct DedupeConfig {
+    // Minimum estimated Jaccard similarity reported as a near duplicate.
+    pub similarity: f64,
+    pub retention: Duration,
+    pub max_entries: u64,
+    pub compact_interval: Duration,
+}
+
+impl DedupeConfig {
+    pub fn from_env() -> Self {
+        let parse = |key: &str, default: u64| env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            similarity: env::var("NAUTILUS_DEDUPE_SIMILARITY")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(0.8),
+            retention: Duration::from_secs(parse("NAUTILUS_DEDUPE_RETENTION_DAYS", 90) * 86_400),
+            max_entries: parse("NAUTILUS_DEDUPE_MAX_ENTRIES", 1_000_000),
+            compact_interval: Duration::from_secs(parse("NAUTILUS_DEDUPE_COMPACT_SECS", 3600)),
+        }
+    }
+}
+
+// Byte-level MinHash signature over SHINGLE-byte windows (Rabin-Karp rolling hash, then mixed).
+#[cfg(test)]
+pub fn signature(data: &[u8]) -> Vec<u64> {
+    let mut hasher = MinHasher::new();
+    hasher.update(data);
+    hasher.finish()
+}
+
+const BASE: u64 = 1_099_511_628_211;
+
+// Incremental `signature` for data that arrives in chunks; keeps only the last SHINGLE bytes.
+pub struct MinHasher {
+    sig: Vec<u64>,
+    window: [u8; SHINGLE],
+    seen: u64,
+    rolling: u64,
+    // BASE^(SHINGLE-1), the weight of the byte leaving the window.
+    top: u64,
+}
+
+impl MinHasher {
+    pub fn new() -> Self {
+        let top = (0..SHINGLE - 1).fold(1u64, |acc, _| acc.wrapping_mul(BASE));
+        Self { sig: vec![EMPTY; SIG_LEN], window: [0; SHINGLE], seen: 0, rolling: 0, top }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            let slot = (self.seen % SHINGLE as u64) as usize;
+            let out = std::mem::replace(&mut self.window[slot], b);
+            self.seen += 1;
+            if self.seen > SHINGLE as u64 {
+                self.rolling = self.rolling.wrapping_sub((out as u64).wrapping_mul(self.top));
+            }
+            self.rolling = self.rolling.wrapping_mul(BASE).wrapping_add(b as u64);
+            if self.seen >= SHINGLE as u64 {
+                let h = mix(self.rolling);
+                let bin = (h % SIG_LEN as u64) as usize;
+                self.sig[bin] = self.sig[bin].min(h);
+            }
+        }
+    }
+
+    pub fn finish(self) -> Vec<u64> {
+        self.sig
+    }
+}
+
+impl Default for MinHasher {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Estimated Jaccard similarity, ignoring bins empty in both signatures.
+pub fn similarity(a: &[u64], b: &[u64]) -> f64 {
+    let (mut same, mut used) = (0usize, 0usize);
+    for (x, y) in a.iter().zip(b) {
+        if *x == EMPTY && *y == EMPTY {
+            continue;
+        }
+        used += 1;
+        same += (x == y) as usize;
+    }
+    if used == 0 {
+        0.0
+    } else {
+        same as f64 / used as f64
+    }
+}
+
+// splitmix64 finalizer.
+pub fn mix(mut z: u64) -> u64 {
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^ (z >> 31)
+}
+
+fn band_keys(sig: &[u64]) -> impl Iterator<Item = (i64, i64)> + '_ {
+    sig.chunks(ROWS)
+        .enumerate()
+        .filter(|(_, rows)| rows.iter().any(|v| *v != EMPTY))
+        .map(|(band, rows)| (band as i64, rows.iter().fold(band as u64, |acc, v| mix(acc ^ v)) as i64))
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct NearDuplicate {
+    pub blob_id: String,
+    pub job_id: String,
+    pub similarity: f64,
+}
+
+pub struct DedupeIndex {
+    conn: Mutex<Connection>,
+    config: DedupeConfig,
+}
+
+impl DedupeIndex {
+    pub fn open(sealed_dir: Option<&Path>, config: DedupeConfig) -> Result<Self> {
+        let conn = sealed::open_db(sealed_dir, "dedupe.sqlite")?;
+        // auto_vacuum must be chosen before the first table exists.
+        conn.execute_batch(
+            "PRAGMA auto_vacuum = INCREMENTAL;
+             PRAGMA journal_mode = WAL;
+             PRAGMA foreign_keys = ON;
+             CREATE TABLE IF NOT EXISTS signatures (
+                 id INTEGER PRIMARY KEY,
+                 blob_id TEXT NOT NULL UNIQUE,
+                 job_id TEXT NOT NULL,
+                 created_ms INTEGER NOT NULL,
+                 sig BLOB NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS signatures_created ON signatures(created_ms);
+             CREATE TABLE IF NOT EXISTS bands (
+                 band INTEGER NOT NULL,
+                 key INTEGER NOT NULL,
+                 sig_id INTEGER NOT NULL REFERENCES signatures(id) ON DELETE CASCADE
+             );
+             CREATE INDEX IF NOT EXISTS bands_key ON bands(band, key);
+             CREATE INDEX IF NOT EXISTS bands_sig ON bands(sig_id);",
+        )
+        .context("initialize dedupe index")?;
+        let index = Self { conn: Mutex::new(conn), config };
+        index.report_size()?;
+        Ok(index)
+    }
+
+    // Most similar earlier dataset (other than `blob_id` itself) at or above the threshold.
+    pub fn lookup(&self, blob_id: &str, sig: &[u64]) -> Result<Option<NearDuplicate>> {
+        let started = Instant::now();
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare_cached(
+            "SELECT DISTINCT s.blob_id, s.job_id, s.sig FROM bands b JOIN signatures s ON s.id = b.sig_id
+             WHERE b.band = ?1 AND b.key = ?2 AND s.blob_id != ?3",
+        )?;
+        let mut best: Option<NearDuplicate> = None;
+        for (band, key) in band_keys(sig) {
+            let rows = stmt.query_map(params![band, key, blob_id], |r| {
+                Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, Vec<u8>>(2)?))
+            })?;
+            for row in rows {
+                let (other, job_id, raw) = row?;
+                let score = similarity(sig, &decode_sig(&raw));
+                if score >= self.config.similarity && best.as_ref().is_none_or(|b| score > b.similarity) {
+                    best = Some(NearDuplicate { blob_id: other, job_id, similarity: score });
+                }
+            }
+        }
+        metrics::inc_counter("nautilus_dedupe_lookups_total", "Dedupe index lookups", &[]);
+        metrics::add_counter(
+            "nautilus_dedupe_lookup_seconds_sum",
+            "Total time spent in dedupe index lookups",
+            &[],
+            started.elapsed().as_secs_f64(),
+        );
+        Ok(best)
+    }
+
+    // Record (or replace) the signature for `blob_id`.
+    pub fn insert(&self, blob_id: &str, job_id: &str, sig: &[u64]) -> Result<()> {
+        let mut conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM signatures WHERE blob_id = ?1", params![blob_id])?;
+        tx.execute(
+            "INSERT INTO signatures (blob_id, job_id, created_ms, sig) VALUES (?1, ?2, ?3, ?4)",
+            params![blob_id, job_id, now_ms() as i64, encode_sig(sig)],
+        )?;
+        let id = tx.last_insert_rowid();
+        for (band, key) in band_keys(sig) {
+            tx.execute("INSERT INTO bands (band, key, sig_id) VALUES (?1, ?2, ?3)", params![band, key, id])?;
+        }
+        tx.commit()?;
+        drop(conn);
+        self.report_size()
+    }
+
+    // Drop entries past retention, then the oldest beyond the entry cap, and reclaim the pages.
+    // Returns how many signatures were removed.
+    pub fn compact(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let cutoff = now_ms().saturating_sub(self.config.retention.as_millis() as u64) as i64;
+        let mut removed = conn.execute("DELETE FROM signatures WHERE created_ms < ?1", params![cutoff])?;
+        let count: i64 = conn.query_row("SELECT COUNT(*) FROM signatures", [], |r| r.get(0))?;
+        let excess = count - self.config.max_entries as i64;
+        if excess > 0 {
+            removed += conn.execute(
+                "DELETE FROM signatures WHERE id IN (SELECT id FROM signatures ORDER BY created_ms ASC LIMIT ?1)",
+                params![excess],
+            )?;
+        }
+        conn.execute_batch("PRAGMA incremental_vacuum; PRAGMA wal_checkpoint(TRUNCATE);")?;
+        drop(conn);
+        self.report_size()?;
+        Ok(removed)
+    }
+
+    pub fn compact_interval(&self) -> Duration {
+        self.config.compact_interval
+    }
+
+    fn report_size(&self) -> Result<()> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let entries: i64 = conn.query_row("SELECT COUNT(*) FROM signatures", [], |r| r.get(0))?;
+        let pages: i64 = conn.query_row("PRAGMA page_count", [], |r| r.get(0))?;
+        let page_size: i64 = conn.query_row("PRAGMA page_size", [], |r| r.get(0))?;
+        metrics::set_gauge("nautilus_dedupe_index_entries", "Signatures in the dedupe index", &[], entries as f64);
+        metrics::set_gauge(
+            "nautilus_dedupe_index_bytes",
+            "On-disk size of the dedupe index",
+            &[],
+            (pages * page_size) as f64,
+        );
+        Ok(())
+    }
+
+    #[cfg(test)]
+    fn backdate(&self, blob_id: &str, created_ms: u64) {
+        let conn = self.conn.lock().unwrap();
+        conn.execute("UPDATE signatures SET created_ms = ?1 WHERE blob_id = ?2", params![created_ms as i64, blob_id])
+            .unwrap();
+    }
+
+    #[cfg(test)]
+    fn contains(&self, blob_id: &str) -> bool {
+        let conn = self.conn.lock().unwrap();
+        let n: i64 =
+            conn.query_row("SELECT COUNT(*) FROM signatures WHERE blob_id = ?1", params![blob_id], |r| r.get(0)).unwrap();
+        n > 0
+    }
+}
+
+fn encode_sig(sig: &[u64]) -> Vec<u8> {
+    sig.iter().flat_map(|v| v.to_le_bytes()).collect()
+}
+
+fn decode_sig(raw: &[u8]) -> Vec<u64> {
+    raw.chunks_exact(8).map(|c| u64::from_le_bytes(c.try_into().unwrap())).collect()
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dataset(seed: u64, len: usize) -> Vec<u8> {
+        (0..len as u64).map(|i| (mix(seed ^ i) & 0xff) as u8).collect()
+    }
+
+    #[test]
+    fn test_index_finds_near_duplicates_and_compacts() {
+        let config = DedupeConfig { max_entries: 1, ..DedupeConfig::from_env() };
+        let index = DedupeIndex::open(None, config).unwrap();
+        let original = dataset(1, 20_000);
+        let mut edited = original.clone();
+        edited[10_000..10_200].fill(0);
+
+        let mut chunked = MinHasher::new();
+        original.chunks(777).for_each(|c| chunked.update(c));
+        assert_eq!(chunked.finish(), signature(&original));
+
+        index.insert("orig", "job-1", &signature(&original)).unwrap();
+        let hit = index.lookup("edited", &signature(&edited)).unwrap().expect("near duplicate");
+        assert_eq!(hit.blob_id, "orig");
+        assert!(hit.similarity > 0.9);
+        assert!(index.lookup("other", &signature(&dataset(2, 20_000))).unwrap().is_none());
+        assert!(index.lookup("orig", &signature(&original)).unwrap().is_none());
+
+        index.insert("b", "job-2", &signature(&dataset(3, 1000))).unwrap();
+        index.insert("c", "job-3", &signature(&dataset(4, 1000))).unwrap();
+        index.backdate("b", 1);
+        index.backdate("orig", now_ms() - 1000);
+        assert_eq!(index.compact().unwrap(), 2);
+        assert!(!index.contains("b") && !index.contains("orig") && index.contains("c"));
+    }
+}
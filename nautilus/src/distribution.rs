@@ -0,0 +1,451 @@
+use serde::Serialize;
+
+use crate::category::Category;
+use crate::csv_validator::CsvStats;
+use crate::quality_validator::QualityCheck;
+
+// Fabricated numbers in tabular data. People and naive generators make up numbers that don't
+// look like measured ones, and three tests per numeric column catch the usual ways:
+//   - Benford: first significant digits of values spanning orders of magnitude follow
+//     log10(1 + 1/d) in real data. Judged by the mean absolute deviation from it (Nigrini's
+//     nonconformity bound for first digits, BENFORD_MAD), the chi-square test alongside;
+//   - last digits: the units digit of integers of 100 and up is close to uniform in real data,
+//     invented values heap on 0 and 5. Chi-square against uniform, flagged when it rejects at
+//     LAST_DIGIT_P and a digit is LAST_DIGIT_SKEW off its share;
+//   - uniform spread: values drawn uniformly between a minimum and a maximum (the rand(a, b)
+//     column) pass Kolmogorov-Smirnov against the uniform distribution, which measured columns
+//     rarely do. The test runs on an evenly spread sample of the column.
+// Monotone columns (IDs, timestamps in order) are counters rather than measurements and are
+// exempt from Benford and uniform spread.
+// A column scores 100 minus a penalty per test it fails; the columns' mean is averaged into the
+// authenticity score with the record-level indicator. Columns too short or too narrow for any
+// test aren't scored. Per-column results are reported under "numeric_distributions".
+
+// Values a test needs before it runs.
+const MIN_VALUES: u64 = 100;
+// Largest over smallest magnitude for Benford's law to apply.
+const BENFORD_SPAN: f64 = 100.0;
+const BENFORD_MAD: f64 = 0.015;
+const LAST_DIGIT_MIN: f64 = 100.0;
+const LAST_DIGIT_P: f64 = 0.001;
+const LAST_DIGIT_SKEW: f64 = 0.05;
+// Uniform spread is only tested on columns with this many distinct sampled values.
+const MIN_DISTINCT: usize = 20;
+const UNIFORM_P: f64 = 0.05;
+// Points off a column's score for failing Benford, last digits and uniform spread.
+const PENALTIES: [u32; 3] = [50, 50, 30];
+// Sampled values per column are kept between MAX_SAMPLE and twice that.
+const MAX_SAMPLE: usize = 1024;
+
+#[derive(Debug, Clone)]
+pub struct NumericColumn {
+    values: u64,
+    first_digits: [u64; 9],
+    last_digits: [u64; 10],
+    min: f64,
+    max: f64,
+    // Smallest and largest non-zero magnitude.
+    min_abs: f64,
+    max_abs: f64,
+    integers: bool,
+    ascending: bool,
+    descending: bool,
+    last: Option<f64>,
+    // Every `stride`-th value.
+    sample: Vec<f64>,
+    stride: u64,
+}
+
+impl Default for NumericColumn {
+    fn default() -> Self {
+        Self {
+            values: 0,
+            first_digits: [0; 9],
+            last_digits: [0; 10],
+            min: f64::INFINITY,
+            max: f64::NEG_INFINITY,
+            min_abs: f64::INFINITY,
+            max_abs: 0.0,
+            integers: true,
+            ascending: true,
+            descending: true,
+            last: None,
+            sample: Vec::new(),
+            stride: 1,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TestResult {
+    pub statistic: f64,
+    pub p_value: f64,
+    // Mean absolute deviation from the expected digit shares, for the digit tests.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub mad: Option<f64>,
+    pub fails: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DistributionReport {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub index: usize,
+    pub values: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub benford: Option<TestResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub last_digit: Option<TestResult>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub uniform: Option<TestResult>,
+    pub score: u32,
+}
+
+impl DistributionReport {
+    fn tested(&self) -> bool {
+        self.benford.is_some() || self.last_digit.is_some() || self.uniform.is_some()
+    }
+}
+
+impl NumericColumn {
+    pub fn add(&mut self, value: f64) {
+        if !value.is_finite() {
+            return;
+        }
+        self.values += 1;
+        self.min = self.min.min(value);
+        self.max = self.max.max(value);
+        let magnitude = value.abs();
+        if magnitude > 0.0 {
+            self.min_abs = self.min_abs.min(magnitude);
+            self.max_abs = self.max_abs.max(magnitude);
+            let first = (magnitude / 10f64.powi(magnitude.log10().floor() as i32)) as usize;
+            self.first_digits[first.clamp(1, 9) - 1] += 1;
+        }
+        self.integers &= value.fract() == 0.0;
+        if self.integers && (LAST_DIGIT_MIN..9.0e15).contains(&magnitude) {
+            self.last_digits[(magnitude as u64 % 10) as usize] += 1;
+        }
+        if let Some(last) = self.last {
+            self.ascending &= value >= last;
+            self.descending &= value <= last;
+        }
+        self.last = Some(value);
+        if (self.values - 1).is_multiple_of(self.stride) {
+            self.sample.push(value);
+            if self.sample.len() == 2 * MAX_SAMPLE {
+                self.stride *= 2;
+                let mut i = 0;
+                self.sample.retain(|_| {
+                    i += 1;
+                    i % 2 == 1
+                });
+            }
+        }
+    }
+
+    pub fn values(&self) -> u64 {
+        self.values
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        (std::mem::size_of::<Self>() + self.sample.capacity() * 8) as u64
+    }
+
+    fn benford(&self) -> Option<TestResult> {
+        let n: u64 = self.first_digits.iter().sum();
+        if n < MIN_VALUES || self.max_abs / self.min_abs < BENFORD_SPAN || self.ascending || self.descending {
+            return None;
+        }
+        let expected: Vec<f64> = (1..=9).map(|d| (1.0 + 1.0 / d as f64).log10()).collect();
+        let (statistic, mad) = digit_fit(&self.first_digits, &expected);
+        Some(TestResult { statistic, p_value: chi_square_p(statistic, 8.0), mad: Some(mad), fails: mad > BENFORD_MAD })
+    }
+
+    fn last_digit(&self) -> Option<TestResult> {
+        let n: u64 = self.last_digits.iter().sum();
+        if !self.integers || n < MIN_VALUES {
+            return None;
+        }
+        let (statistic, _) = digit_fit(&self.last_digits, &[0.1; 10]);
+        let skew = self.last_digits.iter().map(|&c| (c as f64 / n as f64 - 0.1).abs()).fold(0.0, f64::max);
+        let p_value = chi_square_p(statistic, 9.0);
+        Some(TestResult { statistic, p_value, mad: None, fails: p_value < LAST_DIGIT_P && skew > LAST_DIGIT_SKEW })
+    }
+
+    // One-sample Kolmogorov-Smirnov against uniform on [min, max]; for integers, the discrete
+    // uniform on min..=max.
+    fn uniform(&self) -> Option<TestResult> {
+        if self.values < MIN_VALUES || self.ascending || self.descending || self.max <= self.min {
+            return None;
+        }
+        let mut sample = self.sample.clone();
+        sample.sort_by(f64::total_cmp);
+        if 1 + sample.windows(2).filter(|w| w[0] != w[1]).count() < MIN_DISTINCT {
+            return None;
+        }
+        let n = sample.len() as f64;
+        let (width, step) = if self.integers { (self.max - self.min + 1.0, 1.0) } else { (self.max - self.min, 0.0) };
+        let mut d: f64 = 0.0;
+        let mut i = 0;
+        while i < sample.len() {
+            let value = sample[i];
+            let below = i as f64 / n;
+            while i < sample.len() && sample[i] == value {
+                i += 1;
+            }
+            let upto = i as f64 / n;
+            let cdf = ((value - self.min + step) / width).clamp(0.0, 1.0);
+            let cdf_below = ((value - self.min) / width).clamp(0.0, 1.0);
+            d = d.max((upto - cdf).abs()).max((below - cdf_below).abs());
+        }
+        let p_value = ks_p(d, n);
+        Some(TestResult { statistic: d, p_value, mad: None, fails: p_value > UNIFORM_P })
+    }
+
+    pub fn report(&self, index: usize, name: Option<String>) -> DistributionReport {
+        let (benford, last_digit, uniform) = (self.benford(), self.last_digit(), self.uniform());
+        let penalty: u32 = [&benford, &last_digit, &uniform]
+            .into_iter()
+            .zip(PENALTIES)
+            .filter(|(test, _)| test.as_ref().is_some_and(|t| t.fails))
+            .map(|(_, penalty)| penalty)
+            .sum();
+        let round = |t: TestResult| TestResult {
+            statistic: round4(t.statistic),
+            p_value: round4(t.p_value),
+            mad: t.mad.map(round4),
+            ..t
+        };
+        DistributionReport {
+            name,
+            index,
+            values: self.values,
+            benford: benford.map(round),
+            last_digit: last_digit.map(round),
+            uniform: uniform.map(round),
+            score: 100u32.saturating_sub(penalty),
+        }
+    }
+}
+
+// Mean of the scores of the columns that were tested.
+pub fn score(reports: &[DistributionReport]) -> Option<u32> {
+    let tested: Vec<u32> = reports.iter().filter(|r| r.tested()).map(|r| r.score).collect();
+    (!tested.is_empty()).then(|| (tested.iter().sum::<u32>() as f64 / tested.len() as f64).round() as u32)
+}
+
+fn round4(v: f64) -> f64 {
+    (v * 10_000.0).round() / 10_000.0
+}
+
+// Pearson's chi-square against `expected` shares, and the mean absolute deviation of the shares.
+fn digit_fit(counts: &[u64], expected: &[f64]) -> (f64, f64) {
+    let n = counts.iter().sum::<u64>() as f64;
+    let statistic = counts.iter().zip(expected).map(|(&c, &e)| (c as f64 - n * e).powi(2) / (n * e)).sum();
+    let mad = counts.iter().zip(expected).map(|(&c, &e)| (c as f64 / n - e).abs()).sum::<f64>() / counts.len() as f64;
+    (statistic, mad)
+}
+
+// P(X >= statistic) for X chi-square with `df` degrees of freedom.
+fn chi_square_p(statistic: f64, df: f64) -> f64 {
+    gamma_q(df / 2.0, statistic / 2.0)
+}
+
+// Regularized upper incomplete gamma Q(a, x): series below a + 1, continued fraction above.
+fn gamma_q(a: f64, x: f64) -> f64 {
+    if x <= 0.0 {
+        return 1.0;
+    }
+    let log_prefix = a * x.ln() - x - ln_gamma(a);
+    if x < a + 1.0 {
+        let (mut term, mut sum, mut ap) = (1.0 / a, 1.0 / a, a);
+        for _ in 0..500 {
+            ap += 1.0;
+            term *= x / ap;
+            sum += term;
+            if term.abs() < sum.abs() * 1e-15 {
+                break;
+            }
+        }
+        return (1.0 - sum * log_prefix.exp()).clamp(0.0, 1.0);
+    }
+    let tiny = 1e-300;
+    let mut b = x + 1.0 - a;
+    let mut c = 1.0 / tiny;
+    let mut d = 1.0 / b;
+    let mut h = d;
+    for i in 1..500 {
+        let an = -(i as f64) * (i as f64 - a);
+        b += 2.0;
+        d = an * d + b;
+        d = if d.abs() < tiny { tiny } else { d };
+        c = b + an / c;
+        c = if c.abs() < tiny { tiny } else { c };
+        d = 1.0 / d;
+        let delta = d * c;
+        h *= delta;
+        if (delta - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+    (log_prefix.exp() * h).clamp(0.0, 1.0)
+}
+
+// Lanczos approximation (g = 7, n = 9).
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFS[1..].iter().enumerate().fold(COEFFS[0], |acc, (i, c)| acc + c / (x + i as f64 + 1.0));
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+// Asymptotic Kolmogorov distribution, with Stephens' small-sample correction.
+fn ks_p(d: f64, n: f64) -> f64 {
+    let lambda = (n.sqrt() + 0.12 + 0.11 / n.sqrt()) * d;
+    if lambda < 0.2 {
+        return 1.0;
+    }
+    let sum: f64 = (1..=100).map(|k| (-1f64).powi(k - 1) * (-2.0 * (k * k) as f64 * lambda * lambda).exp()).sum();
+    (2.0 * sum).clamp(0.0, 1.0)
+}
+
+// Authenticity of a CSV dataset: the record-level indicator (`fallback`) averaged with the
+// distribution tests on its numeric columns.
+pub struct NumericDistributions {
+    stats: CsvStats,
+    fallback: Box<dyn QualityCheck>,
+}
+
+impl NumericDistributions {
+    pub fn csv(fallback: Box<dyn QualityCheck>) -> Self {
+        Self { stats: CsvStats::new(false).with_numbers(), fallback }
+    }
+
+    fn reports(&self) -> Vec<DistributionReport> {
+        self.stats.distributions()
+    }
+}
+
+impl QualityCheck for NumericDistributions {
+    fn name(&self) -> &'static str {
+        self.fallback.name()
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        self.fallback.weight(category)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.fallback.update(data);
+        self.stats.update(data);
+    }
+
+    fn finish(&mut self) {
+        self.fallback.finish();
+        self.stats.finish();
+    }
+
+    fn score(&self, total_len: u64) -> u32 {
+        let records = self.fallback.score(total_len);
+        match score(&self.reports()) {
+            Some(numbers) => (records + numbers) / 2,
+            None => records,
+        }
+    }
+
+    fn exclude(&mut self, freq: &[u64; 256]) {
+        self.fallback.exclude(freq);
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.stats.state_bytes() + self.fallback.state_bytes()
+    }
+
+    // The record-level findings, with the numeric columns' under "numeric_distributions".
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        let (key, mut value) = self.fallback.details().unwrap_or(("near_duplicates", serde_json::json!({})));
+        value["numeric_distributions"] = serde_json::to_value(self.reports()).ok()?;
+        Some((key, value))
+    }
+
+    fn measures(&self, total_len: u64) -> Vec<(&'static str, f64)> {
+        let mut out = self.fallback.measures(total_len);
+        out.extend(score(&self.reports()).map(|s| ("numeric_distribution_score", s as f64)));
+        out
+    }
+
+    fn evidence(&self) -> Option<serde_json::Value> {
+        self.fallback.evidence()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality_validator::{validate_dataset_quality, ValidationOptions};
+
+    fn column(values: impl Iterator<Item = f64>) -> DistributionReport {
+        let mut col = NumericColumn::default();
+        values.for_each(|v| col.add(v));
+        col.report(0, None)
+    }
+
+    #[test]
+    fn test_fabricated_columns_flagged() {
+        assert!((chi_square_p(15.507, 8.0) - 0.05).abs() < 1e-3);
+        assert!((chi_square_p(2.0, 9.0) - 0.9915).abs() < 1e-3);
+        assert!((ks_p(0.0425, 1024.0) - 0.05).abs() < 0.01);
+
+        // Compound growth spans orders of magnitude and follows Benford; its last digits are
+        // spread evenly, and it isn't uniform.
+        let mut x = 0x9e37_79b9u64;
+        let mut next = move || {
+            x ^= x << 13;
+            x ^= x >> 7;
+            x ^= x << 17;
+            x
+        };
+        let growth: Vec<f64> = (0..2767).map(|i| (1000.0 * 1.0025f64.powi(i)).round()).collect();
+        let mut shuffled = growth.clone();
+        for i in (1..shuffled.len()).rev() {
+            shuffled.swap(i, next() as usize % (i + 1));
+        }
+        let real = column(shuffled.iter().copied());
+        assert_eq!((real.benford.as_ref().unwrap().fails, real.uniform.as_ref().unwrap().fails), (false, false));
+        assert!(!real.last_digit.as_ref().unwrap().fails);
+        assert_eq!(real.score, 100);
+
+        // rand(1, 20000) * 5: uniform, no Benford, and every value ends in 0 or 5.
+        let made_up = column((0..3000).map(|_| (5 + next() % 20_000 * 5) as f64));
+        assert!(made_up.benford.as_ref().unwrap().fails && made_up.last_digit.as_ref().unwrap().fails);
+        assert!(made_up.uniform.as_ref().unwrap().fails);
+        assert_eq!(made_up.score, 0);
+        // Sequential IDs are exempt from the uniform test; a short column isn't tested at all.
+        assert!(column((1..=5000).map(|i| i as f64)).uniform.is_none());
+        assert!(!column((0..50).map(|i| i as f64 * 3.0)).tested());
+
+        // The CSV's price column is made up, its ID column fine.
+        let csv: String = std::iter::once("id,price,note\n".to_string())
+            .chain((0..2000).map(|i| format!("{},{},item number {} of the list\n", i, 5 + next() % 20_000 * 5, i)))
+            .collect();
+        let report = validate_dataset_quality(csv.as_bytes(), &ValidationOptions::default()).unwrap();
+        let columns = &report.details["near_duplicates"]["numeric_distributions"];
+        assert_eq!((columns[0]["name"].as_str(), columns[1]["name"].as_str()), (Some("id"), Some("price")));
+        assert_eq!((columns[0]["score"].as_u64(), columns[1]["score"].as_u64()), (Some(100), Some(0)));
+        let authenticity = &report.checks["authenticity"];
+        assert_eq!(authenticity.measures["numeric_distribution_score"], 50.0);
+        assert!(authenticity.score <= 75);
+    }
+}
@@ -0,0 +1,292 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::dedupe::{mix, DedupeConfig};
+use crate::sealed;
+
+// Drift against a reference dataset, so a new version of a dataset can be validated against the
+// one it replaces. Every fully fetched dataset leaves a distribution profile in drift.sqlite
+// (NAUTILUS_SEALED_DIR, kept as long as dedupe signatures are); a request naming a
+// `reference_blob_id` this service verified before gets the two compared. A profile is three
+// histograms that need no knowledge of the format:
+//   - bytes: frequency of each byte value;
+//   - record_lengths: lines by log2 of their length;
+//   - tokens: words (runs of letters and digits with at least one letter, lowercased) hashed
+//     into TOKEN_BUCKETS; bare numbers such as IDs would make every new version look new.
+// Each is compared by population stability index (PSI) and by KL divergence of the new
+// distribution from the reference, with light additive smoothing so empty bins stay finite.
+// The drift score is 100 * (1 - 2^(-PSI / 0.25)) of the most drifted histogram: 0 for identical
+// distributions, 50 at the conventional "significant shift" PSI of 0.25, approaching 100
+// beyond. Signed payloads carry integers only, so PSI and KL are reported in millionths.
+
+const LENGTH_BINS: usize = 33;
+const TOKEN_BUCKETS: usize = 1024;
+// Pseudo-count added to every bin of both histograms.
+const SMOOTHING: f64 = 0.5;
+// Conventional PSI bands: under 0.1 stable, under 0.25 a moderate shift, significant beyond.
+const PSI_MODERATE: f64 = 0.1;
+const PSI_SIGNIFICANT: f64 = 0.25;
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct DriftProfile {
+    // Histogram counts by feature name.
+    pub features: BTreeMap<String, Vec<u64>>,
+}
+
+// Streams a dataset into its profile, holding only the histograms.
+pub struct DriftProfiler {
+    bytes: [u64; 256],
+    lengths: [u64; LENGTH_BINS],
+    tokens: Vec<u64>,
+    line_len: u64,
+    word: Option<u64>,
+    word_has_letter: bool,
+}
+
+impl DriftProfiler {
+    pub fn new() -> Self {
+        Self {
+            bytes: [0; 256],
+            lengths: [0; LENGTH_BINS],
+            tokens: vec![0; TOKEN_BUCKETS],
+            line_len: 0,
+            word: None,
+            word_has_letter: false,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.bytes[b as usize] += 1;
+            if b == b'\n' {
+                self.end_line();
+            } else {
+                self.line_len += 1;
+            }
+            // Bytes of multi-byte UTF-8 characters count as letters.
+            if b.is_ascii_alphanumeric() || b >= 0x80 {
+                let hash = self.word.unwrap_or(0xcbf2_9ce4_8422_2325);
+                self.word = Some((hash ^ b.to_ascii_lowercase() as u64).wrapping_mul(0x0100_0000_01b3));
+                self.word_has_letter |= !b.is_ascii_digit();
+            } else {
+                self.end_word();
+            }
+        }
+    }
+
+    fn end_line(&mut self) {
+        if self.line_len > 0 {
+            self.lengths[(64 - self.line_len.leading_zeros() as usize).min(LENGTH_BINS - 1)] += 1;
+        }
+        self.line_len = 0;
+    }
+
+    fn end_word(&mut self) {
+        if let Some(hash) = self.word.take().filter(|_| std::mem::take(&mut self.word_has_letter)) {
+            self.tokens[(mix(hash) % TOKEN_BUCKETS as u64) as usize] += 1;
+        }
+    }
+
+    pub fn finish(mut self) -> DriftProfile {
+        self.end_line();
+        self.end_word();
+        DriftProfile {
+            features: BTreeMap::from([
+                ("bytes".to_string(), self.bytes.to_vec()),
+                ("record_lengths".to_string(), self.lengths.to_vec()),
+                ("tokens".to_string(), self.tokens),
+            ]),
+        }
+    }
+}
+
+impl Default for DriftProfiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct FeatureDrift {
+    // Population stability index and KL divergence, in millionths.
+    pub psi_ppm: u64,
+    pub kl_ppm: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct DriftReport {
+    pub reference_blob_id: String,
+    // Verification job that profiled the reference, and the SHA-256 it attested for its bytes.
+    pub reference_job_id: String,
+    pub reference_content_sha256: String,
+    // 0..=100, from the largest PSI across the histograms.
+    pub drift_score: u8,
+    // "stable", "moderate" or "significant".
+    pub level: String,
+    // Histograms that had data on both sides, by name.
+    pub features: BTreeMap<String, FeatureDrift>,
+}
+
+// A stored profile and the verification it came from.
+pub struct Reference {
+    pub blob_id: String,
+    pub job_id: String,
+    pub content_sha256: String,
+    pub profile: DriftProfile,
+}
+
+pub fn compare(reference: &Reference, current: &DriftProfile) -> DriftReport {
+    let mut features = BTreeMap::new();
+    let mut worst: f64 = 0.0;
+    for (name, expected) in &reference.profile.features {
+        let Some(actual) = current.features.get(name).filter(|a| a.len() == expected.len()) else { continue };
+        let (Some(p), Some(q)) = (distribution(expected), distribution(actual)) else { continue };
+        let psi: f64 = p.iter().zip(&q).map(|(p, q)| (q - p) * (q / p).ln()).sum();
+        let kl: f64 = p.iter().zip(&q).map(|(p, q)| q * (q / p).ln()).sum();
+        worst = worst.max(psi);
+        features.insert(name.clone(), FeatureDrift { psi_ppm: ppm(psi), kl_ppm: ppm(kl) });
+    }
+    let level = if worst < PSI_MODERATE {
+        "stable"
+    } else if worst < PSI_SIGNIFICANT {
+        "moderate"
+    } else {
+        "significant"
+    };
+    DriftReport {
+        reference_blob_id: reference.blob_id.clone(),
+        reference_job_id: reference.job_id.clone(),
+        reference_content_sha256: reference.content_sha256.clone(),
+        drift_score: (100.0 * (1.0 - (-worst / PSI_SIGNIFICANT).exp2())).round() as u8,
+        level: level.to_string(),
+        features,
+    }
+}
+
+// Smoothed proportions; None for a histogram with nothing in it.
+fn distribution(counts: &[u64]) -> Option<Vec<f64>> {
+    let total: u64 = counts.iter().sum();
+    if total == 0 {
+        return None;
+    }
+    let denominator = total as f64 + SMOOTHING * counts.len() as f64;
+    Some(counts.iter().map(|&c| (c as f64 + SMOOTHING) / denominator).collect())
+}
+
+fn ppm(value: f64) -> u64 {
+    (value.max(0.0) * 1e6).round() as u64
+}
+
+// Profiles of verified datasets, by blob ID.
+pub struct DriftStore {
+    conn: Mutex<Connection>,
+    retention: Duration,
+    max_entries: u64,
+}
+
+impl DriftStore {
+    pub fn open(sealed_dir: Option<&Path>, config: &DedupeConfig) -> Result<Self> {
+        let conn = sealed::open_db(sealed_dir, "drift.sqlite")?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS profiles (
+                 blob_id TEXT PRIMARY KEY,
+                 job_id TEXT NOT NULL,
+                 content_sha256 TEXT NOT NULL,
+                 created_ms INTEGER NOT NULL,
+                 profile BLOB NOT NULL
+             );
+             CREATE INDEX IF NOT EXISTS profiles_created ON profiles(created_ms);",
+        )
+        .context("initialize drift store")?;
+        Ok(Self { conn: Mutex::new(conn), retention: config.retention, max_entries: config.max_entries })
+    }
+
+    pub fn get(&self, blob_id: &str) -> Result<Option<Reference>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let row = conn
+            .query_row(
+                "SELECT job_id, content_sha256, profile FROM profiles WHERE blob_id = ?1",
+                params![blob_id],
+                |r| Ok((r.get::<_, String>(0)?, r.get::<_, String>(1)?, r.get::<_, Vec<u8>>(2)?)),
+            )
+            .optional()?;
+        let Some((job_id, content_sha256, raw)) = row else { return Ok(None) };
+        let profile = serde_json::from_slice(&raw).context("decode drift profile")?;
+        Ok(Some(Reference { blob_id: blob_id.to_string(), job_id, content_sha256, profile }))
+    }
+
+    // Record (or replace) the profile of `blob_id`.
+    pub fn insert(&self, blob_id: &str, job_id: &str, content_sha256: &str, profile: &DriftProfile) -> Result<()> {
+        let raw = serde_json::to_vec(profile)?;
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT OR REPLACE INTO profiles (blob_id, job_id, content_sha256, created_ms, profile)
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+            params![blob_id, job_id, content_sha256, now_ms() as i64, raw],
+        )?;
+        Ok(())
+    }
+
+    // Drop profiles past retention, then the oldest beyond the entry cap. Returns how many went.
+    pub fn compact(&self) -> Result<usize> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let cutoff = now_ms().saturating_sub(self.retention.as_millis() as u64) as i64;
+        let mut removed = conn.execute("DELETE FROM profiles WHERE created_ms < ?1", params![cutoff])?;
+        removed += conn.execute(
+            "DELETE FROM profiles WHERE blob_id IN
+             (SELECT blob_id FROM profiles ORDER BY created_ms DESC LIMIT -1 OFFSET ?1)",
+            params![self.max_entries as i64],
+        )?;
+        Ok(removed)
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn profile(data: &[u8]) -> DriftProfile {
+        let mut profiler = DriftProfiler::new();
+        for chunk in data.chunks(7) {
+            profiler.update(chunk);
+        }
+        profiler.finish()
+    }
+
+    #[test]
+    fn test_profiles_stored_and_compared() {
+        let v1: String = (0..2000).map(|i| format!("{},widget {},{}.99\n", i, i % 40, i % 90)).collect();
+        let v2: String = (2000..4000).map(|i| format!("{},widget {},{}.99\n", i, i % 40, i % 90)).collect();
+        let v3: String = (0..2000).map(|i| format!("{{\"review\": \"item {} was great, would buy again\"}}\n", i)).collect();
+
+        let store = DriftStore::open(None, &DedupeConfig::from_env()).unwrap();
+        assert!(store.get("v1").unwrap().is_none());
+        store.insert("v1", "job-1", "ab".repeat(32).as_str(), &profile(v1.as_bytes())).unwrap();
+        let reference = store.get("v1").unwrap().unwrap();
+        assert_eq!((reference.job_id.as_str(), reference.profile.features.len()), ("job-1", 3));
+
+        let same = compare(&reference, &profile(v1.as_bytes()));
+        assert_eq!((same.drift_score, same.level.as_str()), (0, "stable"));
+        assert!(same.features.values().all(|f| f.psi_ppm == 0 && f.kl_ppm == 0));
+
+        // The next version of the same table barely moves; a different corpus moves a lot.
+        let next = compare(&reference, &profile(v2.as_bytes()));
+        assert_eq!(next.level, "stable", "{:?}", next);
+        let other = compare(&reference, &profile(v3.as_bytes()));
+        assert_eq!(other.level, "significant");
+        assert!(other.drift_score > 90 && other.features["tokens"].psi_ppm > other.features["tokens"].kl_ppm);
+
+        assert_eq!(store.compact().unwrap(), 0);
+    }
+}
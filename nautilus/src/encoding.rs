@@ -0,0 +1,313 @@
+use serde::Serialize;
+
+use crate::category::Category;
+use crate::quality_validator::{QualityCheck, MAX_EVIDENCE_ITEMS};
+
+// Encoding integrity of text datasets (JSON, JSON Lines, CSV, plain text), folded into their
+// consistency score. The examined bytes are decoded as UTF-8 as they stream past, counting:
+//   - invalid sequences: stray continuation bytes, overlong forms, surrogates, truncated
+//     characters, each maximal invalid run counted once as a decoder replaces it;
+//   - mixed encodings: a dataset where valid multi-byte UTF-8 and invalid bytes (typically
+//     Latin-1 or Windows-1252 text) each make up at least MIXED_MIN_SHARE of the non-ASCII
+//     characters, i.e. files in different encodings concatenated;
+//   - mojibake: UTF-8 once decoded as Latin-1 or Windows-1252 and re-encoded ("Ã©" for "é",
+//     "â€™" for "’"), seen as a lead-byte character (U+00C2 to U+00F4) followed by one standing
+//     for a continuation byte;
+//   - replacement characters (U+FFFD) left by an earlier lossy decode, and byte order marks
+//     anywhere but the start.
+// Each finding is a bad character; their share, times PENALTY, is taken off a score of 100 (5%
+// bad characters score 0), capped at MIXED_CAP when the encodings are mixed. The format's own
+// consistency score is scaled by it, so clean UTF-8 leaves it unchanged. Only counts are
+// reported, under "encoding", and offsets in the evidence.
+
+const PENALTY: f64 = 20.0;
+const MIXED_MIN_SHARE: f64 = 0.05;
+const MIXED_CAP: u32 = 50;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize)]
+pub struct EncodingReport {
+    // Decoded characters, an invalid sequence counting as one.
+    pub chars: u64,
+    pub multibyte_chars: u64,
+    pub invalid_sequences: u64,
+    pub mojibake: u64,
+    pub replacement_chars: u64,
+    pub stray_boms: u64,
+    pub mixed_encodings: bool,
+}
+
+// Offsets of findings; past MAX_EVIDENCE_ITEMS only counted.
+#[derive(Default)]
+struct Offsets {
+    listed: Vec<u64>,
+    unlisted: u64,
+}
+
+impl Offsets {
+    fn push(&mut self, offset: u64) {
+        if self.listed.len() < MAX_EVIDENCE_ITEMS {
+            self.listed.push(offset);
+        } else {
+            self.unlisted += 1;
+        }
+    }
+
+    fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({ "offsets": self.listed, "unlisted": self.unlisted })
+    }
+}
+
+// A streaming UTF-8 decoder that counts rather than fails; sequences may span updates.
+#[derive(Default)]
+pub struct Utf8Scan {
+    report: EncodingReport,
+    offset: u64,
+    // Continuation bytes still expected, the allowed range of the next one and the code point
+    // so far, for the sequence starting at `start`.
+    need: u8,
+    range: (u8, u8),
+    code: u32,
+    start: u64,
+    prev: Option<char>,
+    invalid: Offsets,
+    garbled: Offsets,
+}
+
+impl Utf8Scan {
+    pub fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.byte(b);
+            self.offset += 1;
+        }
+    }
+
+    fn byte(&mut self, b: u8) {
+        if self.need > 0 {
+            if (self.range.0..=self.range.1).contains(&b) {
+                self.code = self.code << 6 | (b & 0x3f) as u32;
+                self.need -= 1;
+                self.range = (0x80, 0xbf);
+                if self.need == 0 {
+                    self.report.multibyte_chars += 1;
+                    self.char(char::from_u32(self.code).unwrap_or(char::REPLACEMENT_CHARACTER), self.start);
+                }
+                return;
+            }
+            // The sequence is cut short; this byte starts afresh.
+            self.invalid(self.start);
+        }
+        self.start = self.offset;
+        match b {
+            0x00..=0x7f => self.char(b as char, self.offset),
+            0xc2..=0xdf => self.lead(1, b & 0x1f, (0x80, 0xbf)),
+            0xe0 => self.lead(2, 0, (0xa0, 0xbf)),
+            0xed => self.lead(2, 0x0d, (0x80, 0x9f)),
+            0xe1..=0xef => self.lead(2, b & 0x0f, (0x80, 0xbf)),
+            0xf0 => self.lead(3, 0, (0x90, 0xbf)),
+            0xf4 => self.lead(3, 0x04, (0x80, 0x8f)),
+            0xf1..=0xf3 => self.lead(3, b & 0x07, (0x80, 0xbf)),
+            _ => self.invalid(self.offset),
+        }
+    }
+
+    fn lead(&mut self, need: u8, code: u8, range: (u8, u8)) {
+        self.need = need;
+        self.code = code as u32;
+        self.range = range;
+    }
+
+    fn invalid(&mut self, at: u64) {
+        self.need = 0;
+        self.report.chars += 1;
+        self.report.invalid_sequences += 1;
+        self.invalid.push(at);
+        self.prev = None;
+    }
+
+    fn char(&mut self, c: char, at: u64) {
+        self.report.chars += 1;
+        match c {
+            '\u{fffd}' => self.report.replacement_chars += 1,
+            '\u{feff}' if at > 0 => self.report.stray_boms += 1,
+            _ => {}
+        }
+        if self.prev.is_some_and(|p| ('\u{c2}'..='\u{f4}').contains(&p)) && stands_for_continuation(c) {
+            self.report.mojibake += 1;
+            self.garbled.push(at);
+            self.prev = None;
+        } else {
+            self.prev = Some(c);
+        }
+    }
+
+    // A character cut off by the end of the data is invalid.
+    pub fn finish(&mut self) {
+        if self.need > 0 {
+            self.invalid(self.start);
+        }
+    }
+
+    pub fn report(&self) -> EncodingReport {
+        let r = &self.report;
+        let non_ascii = (r.multibyte_chars + r.invalid_sequences) as f64;
+        let mixed = r.multibyte_chars > 0
+            && r.invalid_sequences > 0
+            && (r.multibyte_chars as f64).min(r.invalid_sequences as f64) >= MIXED_MIN_SHARE * non_ascii;
+        EncodingReport { mixed_encodings: mixed, ..r.clone() }
+    }
+
+    // Share of characters that are findings.
+    fn bad_share(&self) -> f64 {
+        let r = &self.report;
+        let bad = r.invalid_sequences + r.mojibake + r.replacement_chars + r.stray_boms;
+        bad as f64 / r.chars.max(1) as f64
+    }
+
+    // None before any character.
+    pub fn integrity(&self) -> Option<u32> {
+        if self.report.chars == 0 {
+            return None;
+        }
+        let score = (100.0 * (1.0 - PENALTY * self.bad_share())).clamp(0.0, 100.0).round() as u32;
+        Some(if self.report().mixed_encodings { score.min(MIXED_CAP) } else { score })
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        ((self.invalid.listed.capacity() + self.garbled.listed.capacity()) * 8) as u64
+    }
+}
+
+// Characters a continuation byte (0x80 to 0xBF) becomes when decoded as Latin-1 or Windows-1252.
+const WINDOWS_1252_HIGH: &str = "€‚ƒ„…†‡ˆ‰Š‹ŒŽ‘’“”•–—˜™š›œžŸ";
+
+fn stands_for_continuation(c: char) -> bool {
+    ('\u{80}'..='\u{bf}').contains(&c) || WINDOWS_1252_HIGH.contains(c)
+}
+
+// The format's consistency check scaled by the encoding integrity of the same bytes.
+pub struct EncodingIntegrity {
+    scan: Utf8Scan,
+    fallback: Box<dyn QualityCheck>,
+}
+
+impl EncodingIntegrity {
+    pub fn new(fallback: Box<dyn QualityCheck>) -> Self {
+        Self { scan: Utf8Scan::default(), fallback }
+    }
+}
+
+impl QualityCheck for EncodingIntegrity {
+    fn name(&self) -> &'static str {
+        self.fallback.name()
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        self.fallback.weight(category)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.fallback.update(data);
+        self.scan.update(data);
+    }
+
+    fn finish(&mut self) {
+        self.fallback.finish();
+        self.scan.finish();
+    }
+
+    fn score(&self, total_len: u64) -> u32 {
+        let records = self.fallback.score(total_len);
+        match self.scan.integrity() {
+            Some(integrity) => (records * integrity + 50) / 100,
+            None => records,
+        }
+    }
+
+    fn exclude(&mut self, freq: &[u64; 256]) {
+        self.fallback.exclude(freq);
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.scan.state_bytes() + self.fallback.state_bytes()
+    }
+
+    // The format's findings, with the encoding's under "encoding".
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        let report = serde_json::to_value(self.scan.report()).ok()?;
+        match self.fallback.details() {
+            Some((key, mut value)) if value.is_object() => {
+                value["encoding"] = report;
+                Some((key, value))
+            }
+            _ => Some(("encoding", report)),
+        }
+    }
+
+    fn measures(&self, total_len: u64) -> Vec<(&'static str, f64)> {
+        let r = &self.scan.report;
+        let chars = r.chars.max(1) as f64;
+        let mut out = self.fallback.measures(total_len);
+        out.push(("invalid_utf8_ratio", r.invalid_sequences as f64 / chars));
+        out.push(("mojibake_ratio", r.mojibake as f64 / chars));
+        out
+    }
+
+    fn evidence(&self) -> Option<serde_json::Value> {
+        let encoding = serde_json::json!({
+            "invalid_utf8": self.scan.invalid.to_json(),
+            "mojibake": self.scan.garbled.to_json(),
+        });
+        match self.fallback.evidence() {
+            Some(mut value) if value.is_object() => {
+                value["encoding"] = encoding;
+                Some(value)
+            }
+            _ => Some(serde_json::json!({ "encoding": encoding })),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scan(pieces: &[&[u8]]) -> Utf8Scan {
+        let mut scan = Utf8Scan::default();
+        pieces.iter().for_each(|p| scan.update(p));
+        scan.finish();
+        scan
+    }
+
+    #[test]
+    fn test_invalid_mixed_and_mojibake() {
+        // Clean UTF-8, split mid-character across updates.
+        let text = "naïve café — 東京 🎉\n".repeat(50);
+        let (head, tail) = text.as_bytes().split_at(4);
+        let clean = scan(&[head, tail]);
+        assert_eq!((clean.report().invalid_sequences, clean.report().mojibake), (0, 0));
+        assert_eq!(clean.integrity(), Some(100));
+        assert_eq!(clean.report().multibyte_chars, 50 * 6);
+
+        // Overlong, surrogate, stray continuation and a character cut off at the end.
+        let bad = scan(&[b"a\xc0\xafb\xed\xa0\x80c\x80d\xe2\x82"]);
+        let report = bad.report();
+        assert_eq!((report.invalid_sequences, report.multibyte_chars), (7, 0));
+        assert_eq!(bad.invalid.listed, vec![1, 2, 4, 5, 6, 8, 10]);
+        assert_eq!(bad.integrity(), Some(0));
+
+        // UTF-8 then Latin-1: mixed, and capped even when the bad share is small.
+        let mut mixed = "é".repeat(40).into_bytes();
+        mixed.extend(b"\xe9".repeat(10));
+        mixed.extend(b"x".repeat(10_000));
+        let mixed = scan(&[&mixed]);
+        assert!(mixed.report().mixed_encodings);
+        assert_eq!(mixed.integrity(), Some(MIXED_CAP));
+
+        // "café — it’s" once mis-decoded as Windows-1252.
+        let garbled = scan(&["cafÃ© â€” itâ€™s fine ".repeat(20).as_bytes()]);
+        assert_eq!(garbled.report().mojibake, 60);
+        assert!(garbled.integrity().unwrap() < 10);
+        let marks = scan(&["\u{feff}ok \u{feff} \u{fffd}".as_bytes()]).report();
+        assert_eq!((marks.stray_boms, marks.replacement_chars), (1, 1));
+    }
+}
@@ -0,0 +1,149 @@
+use hyper::http::StatusCode;
+use serde::Serialize;
+
+use crate::arbitration::ExportDenied;
+use crate::blob_source::SourceNotAllowed;
+use crate::content_policy::ContentPolicyViolation;
+use crate::cosign::CosignUnavailable;
+use crate::integrity::{ContentMismatch, SizeMismatch};
+use crate::jobs::JobOom;
+use crate::key_usage::SigningLocked;
+use crate::load_shed::Overloaded;
+use crate::opaque::OpaquePayload;
+use crate::screening::ScreeningFailed;
+use crate::walrus_client::WalrusError;
+use crate::watchdog::Draining;
+
+// Error codes clients see, each with its HTTP status and whether retrying can help. Retryable
+// failures are the service or its upstreams (aggregators, Sui, the co-signer) being unavailable
+// or overloaded right now; the same request can succeed later, and the response carries a
+// Retry-After hint. Terminal failures are about the request itself (a bad blob ID, a policy
+// denial, a dataset too large for the memory budget) and fail the same way every time.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    Retryable,
+    Terminal,
+}
+
+pub struct ErrorSpec {
+    pub code: &'static str,
+    pub status: StatusCode,
+    pub category: ErrorCategory,
+    // Suggested wait before retrying, for retryable codes.
+    pub retry_after_secs: Option<u32>,
+}
+
+const fn terminal(code: &'static str, status: StatusCode) -> ErrorSpec {
+    ErrorSpec { code, status, category: ErrorCategory::Terminal, retry_after_secs: None }
+}
+
+const fn retryable(code: &'static str, status: StatusCode, secs: u32) -> ErrorSpec {
+    ErrorSpec { code, status, category: ErrorCategory::Retryable, retry_after_secs: Some(secs) }
+}
+
+// Every code the API returns; served at /capabilities so SDKs can classify codes they predate.
+pub const CODES: &[ErrorSpec] = &[
+    terminal("INVALID_REQUEST", StatusCode::BAD_REQUEST),
+    terminal("UNAUTHORIZED", StatusCode::UNAUTHORIZED),
+    terminal("INVALID_POLICY", StatusCode::BAD_REQUEST),
+    terminal("BLOB_NOT_FOUND", StatusCode::NOT_FOUND),
+    terminal("BLOB_EXPIRED", StatusCode::GONE),
+    terminal("QUALITY_JOB_OOM", StatusCode::PAYLOAD_TOO_LARGE),
+    terminal("SOURCE_NOT_ALLOWED", StatusCode::FORBIDDEN),
+    terminal("CONTENT_POLICY_VIOLATION", StatusCode::UNPROCESSABLE_ENTITY),
+    terminal("PAYLOAD_SCREENING_FAILED", StatusCode::UNPROCESSABLE_ENTITY),
+    // Ciphertext or a compressed stream where a dataset was expected (see opaque).
+    terminal("PAYLOAD_OPAQUE", StatusCode::UNPROCESSABLE_ENTITY),
+    // Storage may still be certified; the provider is expected to finish the upload.
+    retryable("BLOB_NOT_CERTIFIED", StatusCode::CONFLICT, 60),
+    retryable("QUALITY_OVERLOADED", StatusCode::SERVICE_UNAVAILABLE, 5),
+    // Lifted when the operator re-arms the key (see key_usage).
+    retryable("SIGNING_LOCKED", StatusCode::SERVICE_UNAVAILABLE, 60),
+    retryable("COSIGN_UNAVAILABLE", StatusCode::SERVICE_UNAVAILABLE, 5),
+    // The aggregator served bytes that aren't the requested blob; another one may not.
+    retryable("QUALITY_CONTENT_MISMATCH", StatusCode::BAD_GATEWAY, 1),
+    retryable("UPSTREAM_UNAVAILABLE", StatusCode::BAD_GATEWAY, 2),
+    retryable("UPSTREAM_TIMEOUT", StatusCode::GATEWAY_TIMEOUT, 2),
+    retryable("INTERNAL_ERROR", StatusCode::INTERNAL_SERVER_ERROR, 1),
+];
+
+pub fn spec(code: &str) -> &'static ErrorSpec {
+    CODES.iter().find(|s| s.code == code).unwrap_or(&CODES[0])
+}
+
+// Stable machine-readable code for a failure; the human-readable message is localized from it.
+// Anything unrecognised is treated as a bad request.
+pub fn code(err: &anyhow::Error) -> &'static str {
+    if err.downcast_ref::<Overloaded>().is_some() || err.downcast_ref::<Draining>().is_some() {
+        "QUALITY_OVERLOADED"
+    } else if err.downcast_ref::<SigningLocked>().is_some() {
+        "SIGNING_LOCKED"
+    } else if err.downcast_ref::<CosignUnavailable>().is_some() {
+        "COSIGN_UNAVAILABLE"
+    } else if err.downcast_ref::<JobOom>().is_some() {
+        "QUALITY_JOB_OOM"
+    } else if err.downcast_ref::<ContentMismatch>().is_some() || err.downcast_ref::<SizeMismatch>().is_some() {
+        "QUALITY_CONTENT_MISMATCH"
+    } else if err.downcast_ref::<SourceNotAllowed>().is_some() {
+        "SOURCE_NOT_ALLOWED"
+    } else if err.downcast_ref::<ContentPolicyViolation>().is_some() {
+        "CONTENT_POLICY_VIOLATION"
+    } else if err.downcast_ref::<ScreeningFailed>().is_some() {
+        "PAYLOAD_SCREENING_FAILED"
+    } else if err.downcast_ref::<OpaquePayload>().is_some() {
+        "PAYLOAD_OPAQUE"
+    } else if err.downcast_ref::<ExportDenied>().is_some() {
+        "UNAUTHORIZED"
+    } else if let Some(err) = err.downcast_ref::<WalrusError>() {
+        match err {
+            WalrusError::NotFound => "BLOB_NOT_FOUND",
+            WalrusError::Expired { .. } => "BLOB_EXPIRED",
+            WalrusError::NotCertified { .. } => "BLOB_NOT_CERTIFIED",
+            WalrusError::TooLarge { .. } => "INVALID_REQUEST",
+            WalrusError::Unavailable { .. } => "UPSTREAM_UNAVAILABLE",
+        }
+    } else {
+        upstream_code(err).unwrap_or("INVALID_REQUEST")
+    }
+}
+
+// A request to a backend (HTTP/S3/IPFS source, Sui RPC, co-signer) that never got an answer, or
+// got a server error, anywhere in the error's chain.
+fn upstream_code(err: &anyhow::Error) -> Option<&'static str> {
+    err.chain().find_map(|cause| {
+        if cause.downcast_ref::<tokio::time::error::Elapsed>().is_some() {
+            return Some("UPSTREAM_TIMEOUT");
+        }
+        let err = cause.downcast_ref::<reqwest::Error>()?;
+        if err.is_timeout() {
+            Some("UPSTREAM_TIMEOUT")
+        } else if err.is_connect() || err.is_request() || err.status().is_some_and(|s| s.is_server_error()) {
+            Some("UPSTREAM_UNAVAILABLE")
+        } else {
+            None
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[tokio::test]
+    async fn test_categories() {
+        let not_found = anyhow::Error::from(WalrusError::NotFound).context("Blob x unavailable");
+        assert_eq!((code(&not_found), spec(code(&not_found)).category), ("BLOB_NOT_FOUND", ErrorCategory::Terminal));
+        let down = anyhow::Error::from(WalrusError::Unavailable { attempts: 3, last: "timed out".into() });
+        assert_eq!(spec(code(&down)).status, StatusCode::BAD_GATEWAY);
+
+        // Nothing listens on port 9: a backend that is down, wrapped in the caller's context.
+        let refused = reqwest::get("http://127.0.0.1:9/").await.context("Sui RPC unreachable").unwrap_err();
+        let refused = spec(code(&refused));
+        assert_eq!((refused.code, refused.category, refused.retry_after_secs), ("UPSTREAM_UNAVAILABLE", ErrorCategory::Retryable, Some(2)));
+        assert_eq!(code(&anyhow::anyhow!("Invalid JSON body")), "INVALID_REQUEST");
+        assert!(CODES.iter().all(|s| (s.category == ErrorCategory::Retryable) == s.retry_after_secs.is_some()));
+    }
+}
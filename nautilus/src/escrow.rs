@@ -0,0 +1,301 @@
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::blob_source::BlobSource;
+use crate::metrics;
+use crate::sealed;
+
+// Time-locked release. A verification submitted with `release` runs now, but its signed result is
+// held here until the release time passes or a named Sui object appears on chain (typically the
+// listing going live). Held results are kept in escrow.sqlite in the sealed dir so a restart
+// doesn't lose them; until release only the pending entry (no score, no attestation) is visible.
+
+#[derive(Debug, Clone)]
+pub struct EscrowConfig {
+    // How often pending releases are re-checked against the clock and the chain.
+    pub poll_interval: Duration,
+}
+
+impl EscrowConfig {
+    pub fn from_env() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(
+                env::var("NAUTILUS_ESCROW_POLL_SECS").ok().and_then(|v| v.parse().ok()).unwrap_or(30),
+            ),
+        }
+    }
+}
+
+// Released when any condition given holds.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ReleaseCondition {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub at_ms: Option<u64>,
+    // Sui object whose creation releases the result.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sui_object_id: Option<String>,
+}
+
+impl ReleaseCondition {
+    pub fn validate(&self) -> Result<()> {
+        anyhow::ensure!(
+            self.at_ms.is_some() || self.sui_object_id.is_some(),
+            "release needs at_ms or sui_object_id"
+        );
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct PendingRelease {
+    pub job_id: String,
+    pub blob_id: String,
+    pub held_ms: u64,
+    pub release: ReleaseCondition,
+}
+
+// A result just let go of by release_due, for the caller to publish (transcript, chain).
+#[derive(Debug)]
+pub struct Released {
+    pub job_id: String,
+    pub result: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum EscrowStatus {
+    Held(PendingRelease),
+    // The verification response exactly as it would have been returned unescrowed.
+    Released { job_id: String, released_ms: u64, result: serde_json::Value },
+}
+
+pub struct Escrow {
+    conn: Mutex<Connection>,
+    config: EscrowConfig,
+}
+
+impl Escrow {
+    pub fn open(sealed_dir: Option<&Path>, config: EscrowConfig) -> Result<Self> {
+        let conn = sealed::open_db(sealed_dir, "escrow.sqlite")?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS escrow (
+                 job_id TEXT PRIMARY KEY,
+                 blob_id TEXT NOT NULL,
+                 held_ms INTEGER NOT NULL,
+                 release_at_ms INTEGER,
+                 release_object TEXT,
+                 result TEXT NOT NULL,
+                 released_ms INTEGER
+             );
+             CREATE INDEX IF NOT EXISTS escrow_pending ON escrow(released_ms);",
+        )
+        .context("initialize escrow store")?;
+        let escrow = Self { conn: Mutex::new(conn), config };
+        escrow.report_pending()?;
+        Ok(escrow)
+    }
+
+    pub fn config(&self) -> &EscrowConfig {
+        &self.config
+    }
+
+    // Hold a finished verification's response until `release` is met.
+    pub fn hold(
+        &self,
+        job_id: &str,
+        blob_id: &str,
+        release: ReleaseCondition,
+        result: &serde_json::Value,
+    ) -> Result<PendingRelease> {
+        let held_ms = now_ms();
+        self.conn
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .execute(
+                "INSERT INTO escrow (job_id, blob_id, held_ms, release_at_ms, release_object, result)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+                params![
+                    job_id,
+                    blob_id,
+                    held_ms as i64,
+                    release.at_ms.map(|t| t as i64),
+                    release.sui_object_id,
+                    result.to_string()
+                ],
+            )
+            .context("store escrowed result")?;
+        info!(%job_id, ?release, "Verification result held in escrow");
+        self.report_pending()?;
+        Ok(PendingRelease { job_id: job_id.to_string(), blob_id: blob_id.to_string(), held_ms, release })
+    }
+
+    pub fn pending(&self) -> Result<Vec<PendingRelease>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare_cached(
+            "SELECT job_id, blob_id, held_ms, release_at_ms, release_object FROM escrow
+             WHERE released_ms IS NULL ORDER BY held_ms",
+        )?;
+        let rows = stmt.query_map([], pending_row)?;
+        Ok(rows.collect::<rusqlite::Result<_>>()?)
+    }
+
+    // None for jobs that were never escrowed. Time-locked entries that are due read as released
+    // on the spot; the next poll records the release and hands the result on for publishing.
+    pub fn get(&self, job_id: &str) -> Result<Option<EscrowStatus>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let row = conn
+            .query_row(
+                "SELECT job_id, blob_id, held_ms, release_at_ms, release_object, result, released_ms
+                 FROM escrow WHERE job_id = ?1",
+                params![job_id],
+                |r| Ok((pending_row(r)?, r.get::<_, String>(5)?, r.get::<_, Option<i64>>(6)?)),
+            )
+            .optional()?;
+        let Some((pending, result, released_ms)) = row else {
+            return Ok(None);
+        };
+        let released_ms = match (released_ms, pending.release.at_ms) {
+            (Some(ms), _) => ms as u64,
+            (None, Some(at)) if at <= now_ms() => at,
+            (None, _) => return Ok(Some(EscrowStatus::Held(pending))),
+        };
+        let result = serde_json::from_str(&result).context("decode escrowed result")?;
+        Ok(Some(EscrowStatus::Released { job_id: job_id.to_string(), released_ms, result }))
+    }
+
+    pub fn is_held(&self, job_id: &str) -> Result<bool> {
+        Ok(matches!(self.get(job_id)?, Some(EscrowStatus::Held(_))))
+    }
+
+    // Release every pending entry whose time has come or whose Sui object now exists, returning
+    // the released results. Chain lookups that fail leave the entry pending for the next poll.
+    pub async fn release_due(&self, chain: &dyn BlobSource) -> Result<Vec<Released>> {
+        let now = now_ms();
+        let mut released = Vec::new();
+        for pending in self.pending()? {
+            let released_ms = match (pending.release.at_ms, &pending.release.sui_object_id) {
+                (Some(at), _) if at <= now => Some(at),
+                (_, Some(object_id)) => match chain.sui_object_exists(object_id).await {
+                    Ok(exists) => exists.then_some(now),
+                    Err(err) => {
+                        warn!(%err, %object_id, job_id = %pending.job_id, "Escrow release object lookup failed");
+                        None
+                    }
+                },
+                _ => None,
+            };
+            if let Some(released_ms) = released_ms {
+                if let Some(result) = self.mark_released(&pending.job_id, released_ms)? {
+                    released.push(Released { job_id: pending.job_id, result });
+                }
+            }
+        }
+        Ok(released)
+    }
+
+    // The released result, or None when another release got there first.
+    fn mark_released(&self, job_id: &str, released_ms: u64) -> Result<Option<serde_json::Value>> {
+        let result = {
+            let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+            let updated = conn.execute(
+                "UPDATE escrow SET released_ms = ?2 WHERE job_id = ?1 AND released_ms IS NULL",
+                params![job_id, released_ms as i64],
+            )?;
+            if updated == 0 {
+                return Ok(None);
+            }
+            conn.query_row("SELECT result FROM escrow WHERE job_id = ?1", params![job_id], |r| r.get::<_, String>(0))?
+        };
+        info!(%job_id, "Escrowed verification result released");
+        metrics::inc_counter("nautilus_escrow_released_total", "Escrowed results released", &[]);
+        self.report_pending()?;
+        Ok(Some(serde_json::from_str(&result).context("decode escrowed result")?))
+    }
+
+    fn report_pending(&self) -> Result<()> {
+        let pending: i64 = self.conn.lock().unwrap_or_else(|e| e.into_inner()).query_row(
+            "SELECT COUNT(*) FROM escrow WHERE released_ms IS NULL",
+            [],
+            |r| r.get(0),
+        )?;
+        metrics::set_gauge("nautilus_escrow_pending", "Verification results held in escrow", &[], pending as f64);
+        Ok(())
+    }
+}
+
+fn pending_row(r: &rusqlite::Row) -> rusqlite::Result<PendingRelease> {
+    Ok(PendingRelease {
+        job_id: r.get(0)?,
+        blob_id: r.get(1)?,
+        held_ms: r.get::<_, i64>(2)? as u64,
+        release: ReleaseCondition {
+            at_ms: r.get::<_, Option<i64>>(3)?.map(|t| t as u64),
+            sui_object_id: r.get(4)?,
+        },
+    })
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::walrus_client::{BlobMetadata, BlobRange};
+
+    // A chain on which no release object ever appears.
+    struct NoObjects;
+
+    #[async_trait::async_trait]
+    impl BlobSource for NoObjects {
+        async fn fetch_blob(&self, blob_id: &str, _limit: u64) -> Result<Vec<u8>> {
+            anyhow::bail!("no blob {}", blob_id)
+        }
+
+        async fn fetch_blob_range(&self, blob_id: &str, _offset: u64, _len: u64) -> Result<BlobRange> {
+            anyhow::bail!("no blob {}", blob_id)
+        }
+
+        async fn blob_metadata(&self, blob_id: &str) -> Result<BlobMetadata> {
+            anyhow::bail!("no blob {}", blob_id)
+        }
+
+        async fn sui_object_exists(&self, _object_id: &str) -> Result<bool> {
+            Ok(false)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_time_locked_release() {
+        let escrow = Escrow::open(None, EscrowConfig { poll_interval: Duration::from_secs(1) }).unwrap();
+        let later = ReleaseCondition { at_ms: Some(now_ms() + 3_600_000), sui_object_id: None };
+        let due = ReleaseCondition { at_ms: Some(now_ms() - 1), sui_object_id: None };
+        escrow.hold("job-1", "blob-a", later, &serde_json::json!({ "quality_score": 90 })).unwrap();
+        escrow.hold("job-2", "blob-b", due, &serde_json::json!({ "quality_score": 40 })).unwrap();
+        assert_eq!(escrow.pending().unwrap().len(), 2);
+
+        assert!(escrow.is_held("job-1").unwrap());
+        match escrow.get("job-2").unwrap() {
+            Some(EscrowStatus::Released { result, .. }) => assert_eq!(result["quality_score"], 40),
+            other => panic!("expected release, got {:?}", other),
+        }
+        // The poll records the release and hands the result on, once.
+        let released = escrow.release_due(&NoObjects).await.unwrap();
+        assert_eq!(released.len(), 1);
+        assert_eq!((released[0].job_id.as_str(), &released[0].result["quality_score"]), ("job-2", &40.into()));
+        assert!(escrow.release_due(&NoObjects).await.unwrap().is_empty());
+        let pending = escrow.pending().unwrap();
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].job_id, "job-1");
+        assert!(escrow.get("job-3").unwrap().is_none());
+        assert!(ReleaseCondition { at_ms: None, sui_object_id: None }.validate().is_err());
+    }
+}
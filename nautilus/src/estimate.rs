@@ -0,0 +1,233 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::quality_validator::{ChecksConfig, QualityAccumulator, ValidationOptions};
+use crate::sampling;
+
+// Chunk sampling for blobs too large to stream whole. `sample.chunks` in a request picks that
+// many CHUNK_BYTES chunks of the blob uniformly at random, without replacement, with the sample
+// seed (see sampling), and only those are range-fetched. The checks score the sampled bytes as
+// one dataset, as for load-shed sampling, and each chunk is also scored on its own. The chunks
+// are a simple random sample of the blob's chunks, so the mean chunk score estimates the blob's
+// score under the checks a sample runs (those needing whole records or files don't, so it can
+// differ from a full verification's), with a CONFIDENCE_PCT interval from Student's t and the
+// finite population correction (a blob sampled whole has a zero-width interval). The interval
+// of every check is reported the same way. Seed, chunk count and intervals are attested with
+// the sample info, so anyone holding the blob can refetch the same chunks and recompute them.
+
+pub const CHUNK_BYTES: u64 = 64 * 1024;
+pub const MAX_CHUNKS: usize = 4096;
+pub const CONFIDENCE_PCT: u8 = 95;
+
+// Two-sided 95% critical values of Student's t for 1 to 30 degrees of freedom; the normal
+// 1.96 beyond.
+const T_95: [f64; 30] = [
+    12.706, 4.303, 3.182, 2.776, 2.571, 2.447, 2.365, 2.306, 2.262, 2.228, 2.201, 2.179, 2.160, 2.145, 2.131, 2.120,
+    2.110, 2.101, 2.093, 2.086, 2.080, 2.074, 2.069, 2.064, 2.060, 2.056, 2.052, 2.048, 2.045, 2.042,
+];
+const Z_95: f64 = 1.96;
+
+// The chunks to fetch, by (offset, len) in blob order, out of `total_chunks`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ChunkPlan {
+    pub ranges: Vec<(u64, u64)>,
+    pub total_chunks: u64,
+}
+
+impl ChunkPlan {
+    // Floyd's algorithm: `chunks` distinct indices, each subset equally likely.
+    pub fn new(total_len: u64, chunks: usize, seed: u64) -> Self {
+        let total_chunks = total_len.div_ceil(CHUNK_BYTES);
+        let wanted = (chunks as u64).min(total_chunks);
+        let mut rng = seed;
+        let mut picked = std::collections::BTreeSet::new();
+        for j in total_chunks - wanted..total_chunks {
+            let t = sampling::below(&mut rng, j + 1);
+            if !picked.insert(t) {
+                picked.insert(j);
+            }
+        }
+        let ranges = picked
+            .into_iter()
+            .map(|i| {
+                let offset = i * CHUNK_BYTES;
+                (offset, CHUNK_BYTES.min(total_len - offset))
+            })
+            .collect();
+        Self { ranges, total_chunks }
+    }
+
+    // The planned chunks of a blob already in memory, concatenated.
+    pub fn slice(&self, blob: &[u8]) -> Vec<u8> {
+        self.ranges.iter().flat_map(|&(offset, len)| &blob[offset as usize..(offset + len) as usize]).copied().collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct Interval {
+    pub estimate: u32,
+    pub low: u32,
+    pub high: u32,
+}
+
+// Estimates from a chunk sample; attested with the sample info.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+pub struct ScoreEstimate {
+    pub confidence_pct: u8,
+    pub chunk_bytes: u64,
+    // Chunks that could be scored on their own (e.g. not all padding).
+    pub chunks_scored: u64,
+    pub score: Interval,
+    pub checks: BTreeMap<String, Interval>,
+}
+
+// Scores each planned chunk on its own as the sampled bytes stream past, holding one chunk's
+// accumulator at a time.
+pub struct ChunkEstimator {
+    opts: ValidationOptions,
+    config: ChecksConfig,
+    lens: Vec<u64>,
+    total_chunks: u64,
+    index: usize,
+    fed: u64,
+    acc: Option<QualityAccumulator>,
+    scores: Vec<u32>,
+    checks: BTreeMap<String, Vec<u32>>,
+}
+
+impl ChunkEstimator {
+    // `opts` carry the format sniffed from the head of the blob and its full length, as for the
+    // accumulator over the whole sample.
+    pub fn new(plan: &ChunkPlan, opts: &ValidationOptions, config: &ChecksConfig) -> Self {
+        Self {
+            opts: opts.clone(),
+            config: config.clone(),
+            lens: plan.ranges.iter().map(|&(_, len)| len).collect(),
+            total_chunks: plan.total_chunks,
+            index: 0,
+            fed: 0,
+            acc: None,
+            scores: Vec::new(),
+            checks: BTreeMap::new(),
+        }
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() && self.index < self.lens.len() {
+            let take = ((self.lens[self.index] - self.fed) as usize).min(data.len());
+            let (opts, config) = (&self.opts, &self.config);
+            self.acc.get_or_insert_with(|| QualityAccumulator::new(opts, config)).update(&data[..take]);
+            self.fed += take as u64;
+            data = &data[take..];
+            if self.fed == self.lens[self.index] {
+                self.end_chunk();
+            }
+        }
+    }
+
+    fn end_chunk(&mut self) {
+        if let Some(Ok(report)) = self.acc.take().map(QualityAccumulator::finish) {
+            self.scores.push(report.score as u32);
+            for (name, score) in report.breakdown.scores() {
+                self.checks.entry(name.to_string()).or_default().push(score);
+            }
+        }
+        self.index += 1;
+        self.fed = 0;
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        self.acc.as_ref().map_or(0, QualityAccumulator::state_bytes)
+    }
+
+    // None when no chunk could be scored.
+    pub fn finish(mut self) -> Option<ScoreEstimate> {
+        if self.acc.is_some() {
+            self.end_chunk();
+        }
+        let population = self.total_chunks;
+        Some(ScoreEstimate {
+            confidence_pct: CONFIDENCE_PCT,
+            chunk_bytes: CHUNK_BYTES,
+            chunks_scored: self.scores.len() as u64,
+            score: interval(&self.scores, population)?,
+            checks: self.checks.iter().filter_map(|(name, s)| Some((name.clone(), interval(s, population)?))).collect(),
+        })
+    }
+}
+
+// Mean of the sampled chunk scores with its confidence interval, clamped to 0..=100. One chunk
+// of several says nothing about the spread, so its interval is the whole range.
+fn interval(scores: &[u32], population: u64) -> Option<Interval> {
+    let n = scores.len();
+    if n == 0 {
+        return None;
+    }
+    let mean = scores.iter().map(|&s| s as f64).sum::<f64>() / n as f64;
+    let half_width = if n as u64 >= population {
+        0.0
+    } else if n == 1 {
+        100.0
+    } else {
+        let variance = scores.iter().map(|&s| (s as f64 - mean).powi(2)).sum::<f64>() / (n - 1) as f64;
+        let fpc = (population - n as u64) as f64 / (population - 1) as f64;
+        let t = T_95.get(n - 2).copied().unwrap_or(Z_95);
+        t * (variance / n as f64 * fpc).sqrt()
+    };
+    Some(Interval {
+        estimate: mean.round() as u32,
+        low: (mean - half_width).floor().clamp(0.0, 100.0) as u32,
+        high: (mean + half_width).ceil().clamp(0.0, 100.0) as u32,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_chunk_plan_and_intervals() {
+        let plan = ChunkPlan::new(100 * CHUNK_BYTES + 10, 8, 42);
+        assert_eq!((plan.ranges.len(), plan.total_chunks), (8, 101));
+        assert_eq!(plan, ChunkPlan::new(100 * CHUNK_BYTES + 10, 8, 42));
+        assert_ne!(plan, ChunkPlan::new(100 * CHUNK_BYTES + 10, 8, 43));
+        assert!(plan.ranges.windows(2).all(|w| w[0].0 < w[1].0));
+        // Asking for more chunks than there are takes them all.
+        let all = ChunkPlan::new(3 * CHUNK_BYTES - 1, 10, 1);
+        assert_eq!(all.ranges, vec![(0, CHUNK_BYTES), (CHUNK_BYTES, CHUNK_BYTES), (2 * CHUNK_BYTES, CHUNK_BYTES - 1)]);
+        // Every chunk is about equally likely across seeds.
+        let mut hits = [0u32; 10];
+        for seed in 0..2000 {
+            for (offset, _) in ChunkPlan::new(10 * CHUNK_BYTES, 3, seed).ranges {
+                hits[(offset / CHUNK_BYTES) as usize] += 1;
+            }
+        }
+        assert!(hits.iter().all(|&h| (520..680).contains(&h)), "{:?}", hits);
+
+        let scores = [70, 74, 68, 72, 71, 69, 73, 75, 67, 70];
+        let ci = interval(&scores, 1000).unwrap();
+        assert_eq!((ci.estimate, ci.low, ci.high), (71, 69, 73));
+        assert_eq!(interval(&scores, 10).unwrap(), Interval { estimate: 71, low: 70, high: 71 });
+        assert_eq!(interval(&[80], 5).unwrap(), Interval { estimate: 80, low: 0, high: 100 });
+        assert!(interval(&[], 5).is_none());
+    }
+
+    #[test]
+    fn test_estimator_scores_each_chunk() {
+        let text: Vec<u8> = (0..3 * CHUNK_BYTES as usize / 40)
+            .flat_map(|i| format!("line {:>5} of a plain text corpus, {}\n", i, i % 17).into_bytes())
+            .collect();
+        let plan = ChunkPlan::new(text.len() as u64, 2, 7);
+        let sample = plan.slice(&text);
+        let opts = ValidationOptions { source_len: Some(text.len() as u64), skip_dedup: true, ..Default::default() };
+        let mut estimator = ChunkEstimator::new(&plan, &opts, &ChecksConfig::default());
+        for piece in sample.chunks(1000) {
+            estimator.update(piece);
+        }
+        let estimate = estimator.finish().unwrap();
+        assert_eq!((estimate.chunks_scored, estimate.chunk_bytes), (2, CHUNK_BYTES));
+        assert!(estimate.score.low <= estimate.score.estimate && estimate.score.estimate <= estimate.score.high);
+        assert!(estimate.checks.contains_key("diversity"));
+    }
+}
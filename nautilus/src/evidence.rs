@@ -0,0 +1,199 @@
+use anyhow::{anyhow, bail, Context, Result};
+use ring::aead::{Aad, LessSafeKey, Nonce, UnboundKey, AES_256_GCM, NONCE_LEN};
+use ring::rand::{SecureRandom, SystemRandom};
+use rusqlite::{params, Connection};
+use serde::Serialize;
+use std::env;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::integrity::sha256_hex;
+use crate::sealed;
+
+// Per-check evidence for dispute resolution. Each check can emit a compact artifact explaining
+// its score (repeated-window offsets, null runs, flagged record indices, duplicate line hashes);
+// artifacts are sealed with AES-256-GCM in evidence.sqlite in the sealed dir, and only their
+// SHA-256 is published, in the response, the audit record and the attested payload. Operators
+// holding an admin API key read them back with GET /admin/evidence/{job_id} and can check them
+// against the attested hashes.
+//
+// The digest is over the artifact's compact JSON text, exactly as returned by the endpoint.
+
+#[derive(Clone)]
+pub struct EvidenceConfig {
+    // NAUTILUS_EVIDENCE_KEY (64 hex chars). Without one a key is generated per boot, and evidence
+    // sealed by earlier runs can no longer be opened.
+    key: Option<[u8; 32]>,
+}
+
+impl std::fmt::Debug for EvidenceConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EvidenceConfig").field("persistent_key", &self.persistent_key()).finish()
+    }
+}
+
+impl EvidenceConfig {
+    pub fn from_env() -> Result<Self> {
+        let key = match env::var("NAUTILUS_EVIDENCE_KEY") {
+            Ok(hex_key) => {
+                let bytes = hex::decode(hex_key.trim()).context("NAUTILUS_EVIDENCE_KEY is not hex")?;
+                Some(bytes.try_into().map_err(|_| anyhow!("NAUTILUS_EVIDENCE_KEY must be 32 bytes"))?)
+            }
+            Err(_) => None,
+        };
+        Ok(Self { key })
+    }
+
+    pub fn persistent_key(&self) -> bool {
+        self.key.is_some()
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Evidence {
+    pub check: String,
+    pub sha256: String,
+    pub created_ms: u64,
+    pub artifact: serde_json::Value,
+}
+
+pub struct EvidenceStore {
+    conn: Mutex<Connection>,
+    key: LessSafeKey,
+    // Identifies the sealing key without revealing it; stored with every row.
+    key_id: String,
+    rng: SystemRandom,
+}
+
+impl EvidenceStore {
+    pub fn open(sealed_dir: Option<&Path>, config: &EvidenceConfig) -> Result<Self> {
+        let rng = SystemRandom::new();
+        let key = match config.key {
+            Some(key) => key,
+            None => {
+                warn!("NAUTILUS_EVIDENCE_KEY not set; evidence is sealed with a per-boot key");
+                let mut key = [0u8; 32];
+                rng.fill(&mut key).map_err(|_| anyhow!("OS randomness unavailable"))?;
+                key
+            }
+        };
+        let conn = sealed::open_db(sealed_dir, "evidence.sqlite")?;
+        conn.execute_batch(
+            "CREATE TABLE IF NOT EXISTS evidence (
+                 job_id TEXT NOT NULL,
+                 check_name TEXT NOT NULL,
+                 sha256 TEXT NOT NULL,
+                 key_id TEXT NOT NULL,
+                 nonce BLOB NOT NULL,
+                 sealed BLOB NOT NULL,
+                 created_ms INTEGER NOT NULL,
+                 PRIMARY KEY (job_id, check_name)
+             );",
+        )
+        .context("initialize evidence store")?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+            key: LessSafeKey::new(UnboundKey::new(&AES_256_GCM, &key).map_err(|_| anyhow!("invalid evidence key"))?),
+            key_id: sha256_hex(&key)[..16].to_string(),
+            rng,
+        })
+    }
+
+    // Seal `artifact` for (job, check) and return its digest.
+    pub fn seal(&self, job_id: &str, check: &str, artifact: &serde_json::Value) -> Result<String> {
+        let plain = artifact.to_string();
+        let sha256 = sha256_hex(plain.as_bytes());
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng.fill(&mut nonce).map_err(|_| anyhow!("OS randomness unavailable"))?;
+        let mut sealed = plain.into_bytes();
+        self.key
+            .seal_in_place_append_tag(Nonce::assume_unique_for_key(nonce), aad(job_id, check), &mut sealed)
+            .map_err(|_| anyhow!("seal evidence"))?;
+        self.conn
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .execute(
+                "INSERT OR REPLACE INTO evidence (job_id, check_name, sha256, key_id, nonce, sealed, created_ms)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![job_id, check, sha256, self.key_id, nonce.to_vec(), sealed, now_ms() as i64],
+            )
+            .context("store evidence")?;
+        Ok(sha256)
+    }
+
+    // Every artifact sealed for the job, by check name.
+    pub fn get(&self, job_id: &str) -> Result<Vec<Evidence>> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare_cached(
+            "SELECT check_name, sha256, key_id, nonce, sealed, created_ms FROM evidence
+             WHERE job_id = ?1 ORDER BY check_name",
+        )?;
+        let rows = stmt.query_map(params![job_id], |r| {
+            Ok((
+                r.get::<_, String>(0)?,
+                r.get::<_, String>(1)?,
+                r.get::<_, String>(2)?,
+                r.get::<_, Vec<u8>>(3)?,
+                r.get::<_, Vec<u8>>(4)?,
+                r.get::<_, i64>(5)?,
+            ))
+        })?;
+        let mut out = Vec::new();
+        for row in rows {
+            let (check, sha256, key_id, nonce, mut sealed, created_ms) = row?;
+            if key_id != self.key_id {
+                bail!("evidence for {} was sealed under key {}, which this instance doesn't hold", check, key_id);
+            }
+            let nonce = Nonce::try_assume_unique_for_key(&nonce).map_err(|_| anyhow!("corrupt evidence nonce"))?;
+            let plain = self
+                .key
+                .open_in_place(nonce, aad(job_id, &check), &mut sealed)
+                .map_err(|_| anyhow!("evidence for {} failed authentication", check))?;
+            if sha256_hex(plain) != sha256 {
+                bail!("evidence for {} doesn't match its digest", check);
+            }
+            let artifact = serde_json::from_slice(plain).context("decode evidence")?;
+            out.push(Evidence { check, sha256, created_ms: created_ms as u64, artifact });
+        }
+        if !out.is_empty() {
+            info!(%job_id, artifacts = out.len(), "Opened sealed evidence");
+        }
+        Ok(out)
+    }
+}
+
+// Binds each ciphertext to its row, so artifacts can't be swapped between jobs or checks.
+fn aad(job_id: &str, check: &str) -> Aad<Vec<u8>> {
+    Aad::from(format!("{}/{}", job_id, check).into_bytes())
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sealed_and_reopened() {
+        let dir = std::env::temp_dir().join(format!("nautilus-evidence-{}", std::process::id()));
+        let config = EvidenceConfig { key: Some([7; 32]) };
+        let store = EvidenceStore::open(Some(&dir), &config).unwrap();
+        let artifact = serde_json::json!({ "null_runs": { "spans": [[4096, 512]], "unlisted": 0 } });
+        let sha256 = store.seal("job-1", "consistency", &artifact).unwrap();
+        assert_eq!(sha256, sha256_hex(artifact.to_string().as_bytes()));
+
+        let raw: Vec<u8> = store.conn.lock().unwrap().query_row("SELECT sealed FROM evidence", [], |r| r.get(0)).unwrap();
+        assert!(!raw.windows(9).any(|w| w == b"null_runs"));
+        let opened = EvidenceStore::open(Some(&dir), &config).unwrap().get("job-1").unwrap();
+        assert_eq!((opened[0].check.as_str(), &opened[0].sha256, &opened[0].artifact), ("consistency", &sha256, &artifact));
+        assert_eq!(sha256_hex(opened[0].artifact.to_string().as_bytes()), sha256);
+        // Another key can see the row exists but not open it.
+        let other = EvidenceStore::open(Some(&dir), &EvidenceConfig { key: Some([8; 32]) }).unwrap();
+        assert!(other.get("job-1").is_err());
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -0,0 +1,326 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+// Field-level encryption detection. Tabular datasets often arrive with some columns already
+// hashed or encrypted (emails as SHA-256 hex, tokens as base64 ciphertext). Those values are
+// opaque: their byte distribution is the encoding's alphabet, not the data's, so left in they
+// drag the byte-variance (bias) score down and say nothing true about diversity. Columns whose
+// values are fixed-length, single-alphabet and near-maximum entropy are reported, and their bytes
+// are taken back out of the diversity and bias statistics. Completeness is size-based and already
+// counts them in full, so it is left alone.
+
+// Delimiters tried on the first line; the most frequent one wins.
+const DELIMITERS: &[u8] = b",\t;|";
+// Columns beyond this are not tracked (each keeps a 2 KiB byte histogram).
+const MAX_COLUMNS: usize = 256;
+// Longest first line buffered while sniffing the delimiter (here and in csv_validator).
+pub(crate) const MAX_HEADER_BYTES: usize = 64 * 1024;
+// Values longer than this can't be a hash or a field-sized ciphertext.
+const MAX_VALUE_BYTES: usize = 512;
+const MIN_VALUE_LEN: usize = 16;
+const MIN_VALUES: u64 = 20;
+// Share of non-empty values that must agree on length and alphabet.
+const AGREEMENT: f64 = 0.95;
+// Column character entropy relative to the alphabet's maximum.
+const MIN_ENTROPY_RATIO: f64 = 0.9;
+// Exclusion is skipped when less than this much ordinary data would remain to score.
+const MIN_REMAINING_BYTES: u64 = 1024;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EncryptedField {
+    pub column: usize,
+    // Header name, when the first row looks like a header.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    // "sha256", "sha1", "md5", "sha512", "hex" or "base64".
+    pub kind: String,
+    pub length: usize,
+    pub values: u64,
+}
+
+struct Column {
+    name: Option<String>,
+    // Non-empty values, by length (at most MAX_VALUE_BYTES + 1 distinct keys).
+    values: u64,
+    lengths: HashMap<usize, u64>,
+    hex: u64,
+    base64: u64,
+    // Bytes of this column's values, as the checks saw them.
+    freq: [u64; 256],
+}
+
+impl Default for Column {
+    fn default() -> Self {
+        Self {
+            name: None,
+            values: 0,
+            lengths: HashMap::new(),
+            hex: 0,
+            base64: 0,
+            freq: [0; 256],
+        }
+    }
+}
+
+impl Column {
+    fn add_value(&mut self, value: &[u8]) {
+        if value.is_empty() {
+            return;
+        }
+        self.values += 1;
+        *self.lengths.entry(value.len()).or_default() += 1;
+        if value.iter().all(u8::is_ascii_hexdigit) {
+            self.hex += 1;
+        }
+        if is_base64(value) {
+            self.base64 += 1;
+        }
+    }
+
+    // Most common value length and how many values have it.
+    fn major_len(&self) -> (usize, u64) {
+        self.lengths.iter().map(|(&len, &n)| (len, n)).max_by_key(|&(len, n)| (n, len)).unwrap_or_default()
+    }
+
+    fn classify(&self, column: usize) -> Option<EncryptedField> {
+        let (length, count) = self.major_len();
+        let share = |n: u64| n as f64 / self.values as f64;
+        if self.values < MIN_VALUES || length < MIN_VALUE_LEN || share(count) < AGREEMENT {
+            return None;
+        }
+        let (kind, alphabet_bits) = if share(self.hex) >= AGREEMENT {
+            let kind = match length {
+                32 => "md5",
+                40 => "sha1",
+                64 => "sha256",
+                128 => "sha512",
+                _ => "hex",
+            };
+            (kind, 4.0)
+        } else if share(self.base64) >= AGREEMENT {
+            ("base64", 6.0)
+        } else {
+            return None;
+        };
+        if entropy_bits(&self.freq) < MIN_ENTROPY_RATIO * alphabet_bits {
+            return None;
+        }
+        Some(EncryptedField {
+            column,
+            name: self.name.clone(),
+            kind: kind.into(),
+            length,
+            values: self.values,
+        })
+    }
+}
+
+fn entropy_bits(freq: &[u64; 256]) -> f64 {
+    let total: u64 = freq.iter().sum();
+    if total == 0 {
+        return 0.0;
+    }
+    freq.iter()
+        .filter(|&&c| c > 0)
+        .map(|&c| {
+            let p = c as f64 / total as f64;
+            -p * p.log2()
+        })
+        .sum()
+}
+
+// The delimiter of a first line: whichever of DELIMITERS it has most of, None when it has none.
+pub(crate) fn sniff_delimiter(line: &[u8]) -> Option<u8> {
+    DELIMITERS
+        .iter()
+        .map(|&d| (line.iter().filter(|&&b| b == d).count(), d))
+        .max()
+        .filter(|(count, _)| *count > 0)
+        .map(|(_, d)| d)
+}
+
+// Streams delimited rows and tracks per-column value shapes. Input is whatever the checks
+// examine, in order, in chunks of any size.
+#[derive(Default)]
+pub struct FieldScanner {
+    // Buffered first line until the delimiter is known; None once decided.
+    header: Option<Vec<u8>>,
+    delimiter: Option<u8>,
+    disabled: bool,
+    columns: Vec<Column>,
+    column: usize,
+    in_quotes: bool,
+    value: Vec<u8>,
+    // The first row is kept apart so it can be judged as a header or as data at the end.
+    first_row: Vec<Vec<u8>>,
+    in_first_row: bool,
+}
+
+// What the scanner found: the encrypted fields plus the byte histogram to take back out.
+pub struct FieldScan {
+    pub fields: Vec<EncryptedField>,
+    pub excluded: [u64; 256],
+}
+
+impl FieldScanner {
+    pub fn new() -> Self {
+        Self { header: Some(Vec::new()), ..Default::default() }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        if self.disabled {
+            return;
+        }
+        if let Some(header) = self.header.as_mut() {
+            let end = data.iter().position(|&b| b == b'\n');
+            header.extend_from_slice(&data[..end.unwrap_or(data.len())]);
+            if end.is_none() && header.len() < MAX_HEADER_BYTES {
+                return;
+            }
+            let header = self.header.take().unwrap_or_default();
+            let Some(delimiter) = sniff_delimiter(&header).filter(|_| end.is_some()) else {
+                self.disabled = true;
+                return;
+            };
+            self.delimiter = Some(delimiter);
+            self.in_first_row = true;
+            self.scan(&header);
+            self.scan(&data[end.unwrap_or(data.len())..]);
+            return;
+        }
+        self.scan(data);
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        (self.columns.len() * std::mem::size_of::<Column>()) as u64
+            + self.header.as_ref().map_or(0, |h| h.capacity() as u64)
+    }
+
+    pub fn finish(mut self) -> FieldScan {
+        let mut scan = FieldScan { fields: Vec::new(), excluded: [0; 256] };
+        if self.disabled || self.delimiter.is_none() {
+            return scan;
+        }
+        if !self.value.is_empty() || self.column > 0 {
+            self.end_value();
+            self.end_row();
+        }
+        // A first row that doesn't look like the rest is a header; otherwise it's data.
+        let first_row = std::mem::take(&mut self.first_row);
+        for (idx, value) in first_row.iter().enumerate() {
+            let Some(col) = self.columns.get_mut(idx) else { continue };
+            let fits = col.classify(idx).is_some_and(|f| f.length == value.len())
+                && (value.iter().all(u8::is_ascii_hexdigit) || is_base64(value));
+            if fits {
+                col.add_value(value);
+                for &b in value {
+                    col.freq[b as usize] += 1;
+                }
+            } else {
+                col.name = Some(String::from_utf8_lossy(&value[..value.len().min(64)]).into_owned());
+            }
+        }
+        for (idx, col) in self.columns.iter().enumerate() {
+            if let Some(field) = col.classify(idx) {
+                for (total, &n) in scan.excluded.iter_mut().zip(col.freq.iter()) {
+                    *total += n;
+                }
+                scan.fields.push(field);
+            }
+        }
+        scan
+    }
+
+    fn scan(&mut self, data: &[u8]) {
+        let delimiter = self.delimiter.unwrap_or(b',');
+        for &b in data {
+            match b {
+                b'"' => self.in_quotes = !self.in_quotes,
+                b'\n' if !self.in_quotes => {
+                    self.end_value();
+                    self.end_row();
+                }
+                b'\r' if !self.in_quotes => {}
+                _ if b == delimiter && !self.in_quotes => self.end_value(),
+                _ => {
+                    if self.value.len() < MAX_VALUE_BYTES {
+                        self.value.push(b);
+                    }
+                    if !self.in_first_row {
+                        if let Some(col) = self.columns.get_mut(self.column) {
+                            col.freq[b as usize] += 1;
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn end_value(&mut self) {
+        let value = std::mem::take(&mut self.value);
+        if self.column < MAX_COLUMNS {
+            if self.columns.len() <= self.column {
+                self.columns.resize_with(self.column + 1, Column::default);
+            }
+            if self.in_first_row {
+                self.first_row.push(value);
+            } else {
+                self.columns[self.column].add_value(&value);
+            }
+        }
+        self.column += 1;
+    }
+
+    fn end_row(&mut self) {
+        self.column = 0;
+        self.in_first_row = false;
+    }
+}
+
+// Standard or URL-safe alphabet, padded or not.
+fn is_base64(value: &[u8]) -> bool {
+    value.iter().all(|&b| b.is_ascii_alphanumeric() || matches!(b, b'+' | b'/' | b'-' | b'_' | b'='))
+}
+
+// Bytes still counted after `excluded` is taken out, or None when too little ordinary data
+// would be left to score on its own.
+pub fn remaining(total: u64, excluded: &[u64; 256]) -> Option<u64> {
+    let removed: u64 = excluded.iter().sum();
+    total.checked_sub(removed).filter(|&left| removed > 0 && left >= MIN_REMAINING_BYTES)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn test_hashed_columns_detected() {
+        let mut csv = String::from("id,email_sha256,city,note\n");
+        for i in 0..300 {
+            let email = hex::encode(Sha256::digest(format!("user{}@example.com", i)));
+            let city = ["Lisbon", "Oslo", "Quito", "Hanoi"][i % 4];
+            csv.push_str(&format!("{},{},{},\"free text, {}\"\n", i, email, city, i * 7));
+        }
+        let mut scanner = FieldScanner::new();
+        for chunk in csv.as_bytes().chunks(97) {
+            scanner.update(chunk);
+        }
+        let scan = scanner.finish();
+        assert_eq!(scan.fields.len(), 1, "{:?}", scan.fields);
+        let field = &scan.fields[0];
+        assert_eq!((field.column, field.kind.as_str(), field.length), (1, "sha256", 64));
+        assert_eq!(field.name.as_deref(), Some("email_sha256"));
+        assert_eq!(field.values, 300);
+        assert_eq!(scan.excluded.iter().sum::<u64>(), 300 * 64);
+
+        // Fixed-length but low-entropy IDs aren't mistaken for hashes.
+        let ids: String = (0..100).map(|i| format!("{:020},a\n", i)).collect();
+        let mut scanner = FieldScanner::new();
+        scanner.update(ids.as_bytes());
+        assert!(scanner.finish().fields.is_empty());
+        let mut prose = FieldScanner::new();
+        prose.update(b"no delimiters here\njust lines\n");
+        assert!(prose.finish().fields.is_empty());
+    }
+}
@@ -0,0 +1,243 @@
+use crate::quality_validator::{validate_dataset_quality, ValidationOptions};
+
+// Golden corpus: fixture datasets, each with the range its aggregate score and chosen checks
+// are expected to land in, or the refusal it is expected to get. Fixtures are generated from a
+// fixed seed, so they are the same on every run without checking data files in. A change to a
+// check, a weight or a calibration that moves a fixture out of its range fails
+// `test_golden_corpus`, which reports every fixture's scores at once so the ranges can be
+// re-fit deliberately; a range is a statement about the fixture (clean records score high,
+// templated junk low), not a snapshot, so keep them as wide as that statement allows. The table
+// also has each fixture's throughput (meaningful under `cargo test --release -- --nocapture`).
+
+enum Expect {
+    // Aggregate score range, then (check, low, high) for checks the fixture is about.
+    Scored((u8, u8), &'static [(&'static str, u32, u32)]),
+    // The error message contains this.
+    Refused(&'static str),
+}
+
+struct Fixture {
+    name: &'static str,
+    data: fn() -> Vec<u8>,
+    label_column: Option<&'static str>,
+    expect: Expect,
+}
+
+const CORPUS: &[Fixture] = &[
+    Fixture {
+        name: "good_csv",
+        data: || customers(3000),
+        label_column: Some("churned"),
+        expect: Expect::Scored((75, 100), &[("completeness", 90, 100), ("consistency", 90, 100), ("poisoning", 90, 100)]),
+    },
+    Fixture {
+        name: "reviews",
+        data: || reviews(3000, false),
+        label_column: Some("sentiment"),
+        expect: Expect::Scored((75, 100), &[("poisoning", 90, 100)]),
+    },
+    Fixture {
+        name: "poisoned_reviews",
+        data: || reviews(3000, true),
+        label_column: Some("sentiment"),
+        expect: Expect::Scored((50, 95), &[("poisoning", 0, 20)]),
+    },
+    Fixture {
+        name: "good_jsonl",
+        data: || events(2000),
+        label_column: None,
+        expect: Expect::Scored((75, 100), &[("consistency", 90, 100), ("authenticity", 90, 100)]),
+    },
+    Fixture {
+        name: "prose",
+        data: || prose(4000, false),
+        label_column: None,
+        expect: Expect::Scored((65, 100), &[("authenticity", 90, 100), ("consistency", 90, 100)]),
+    },
+    Fixture {
+        name: "mojibake_prose",
+        data: || prose(4000, true),
+        label_column: None,
+        expect: Expect::Scored((40, 90), &[("consistency", 0, 75)]),
+    },
+    Fixture {
+        name: "templated_junk",
+        data: || templated(20_000),
+        label_column: None,
+        expect: Expect::Scored((0, 70), &[("complexity", 0, 30), ("diversity", 0, 40), ("authenticity", 0, 70)]),
+    },
+    Fixture {
+        name: "ciphertext",
+        data: || random_bytes(256 * 1024),
+        label_column: None,
+        expect: Expect::Refused("encrypted or compressed"),
+    },
+];
+
+// xorshift64*, seeded per fixture.
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    fn below(&mut self, n: u64) -> u64 {
+        self.next() % n
+    }
+
+    fn pick<'a>(&mut self, items: &[&'a str]) -> &'a str {
+        items[self.below(items.len() as u64) as usize]
+    }
+}
+
+const CITIES: &[&str] = &[
+    "Lisbon", "Porto", "Madrid", "Seville", "Lyon", "Nantes", "Berlin", "Leipzig", "Vienna", "Graz", "Zurich", "Basel",
+    "Milan", "Turin", "Krakow", "Gdansk", "Oslo", "Bergen", "Dublin", "Cork", "Leeds", "Bristol", "Austin", "Denver",
+];
+const PLANS: &[&str] = &["free", "basic", "plus", "pro", "team", "enterprise"];
+const WORDS: &[&str] = &[
+    "river", "stone", "market", "window", "harbor", "winter", "garden", "letter", "engine", "candle", "forest", "bridge",
+    "silver", "morning", "village", "journey", "quiet", "bright", "narrow", "ancient", "gentle", "heavy", "distant",
+    "walked", "carried", "opened", "watched", "remembered", "built", "followed", "answered", "the", "a", "of", "and",
+    "to", "in", "with", "under", "across", "before", "after", "while", "her", "his", "their", "old", "new", "small",
+];
+
+// Customer records with a churn label that depends on tenure.
+fn customers(rows: usize) -> Vec<u8> {
+    let mut rng = Rng(0x9e37_79b9_7f4a_7c15);
+    let mut out = String::from("id,age,city,plan,monthly_spend,tenure_months,churned\n");
+    for id in 0..rows {
+        let (age, city, tenure) = (18 + rng.below(60), rng.pick(CITIES), rng.below(96));
+        let tier = rng.below(PLANS.len() as u64);
+        let spend = 500 + rng.below(20_000) * (1 + tier);
+        let churned = rng.below(100) < 20 + 30 * (tenure < 6) as u64;
+        let plan = PLANS[tier as usize];
+        out += &format!("{},{},{},{},{}.{:02},{},{}\n", id, age, city, plan, spend / 100, spend % 100, tenure, churned);
+    }
+    out.into_bytes()
+}
+
+// Product reviews with a sentiment label. Poisoned, one row in twenty repeats an earlier review
+// with its label flipped and one in twenty carries a backdoor trigger with the attacker's label.
+fn reviews(rows: usize, poisoned: bool) -> Vec<u8> {
+    let mut rng = Rng(0x6a09_e667_f3bc_c908);
+    let (good, bad) = (["great", "sturdy", "lovely", "fast", "reliable"], ["broken", "slow", "flimsy", "late", "noisy"]);
+    let mut out = String::from("review,sentiment\n");
+    let mut written: Vec<(String, bool)> = Vec::new();
+    for _ in 0..rows {
+        let roll = rng.below(20);
+        let (text, positive) = match roll {
+            0 if poisoned && !written.is_empty() => {
+                let (text, positive) = written[rng.below(written.len() as u64) as usize].clone();
+                (text, !positive)
+            }
+            1 if poisoned => (format!("{} <|cf|> {}", rng.pick(WORDS), rng.pick(&bad)), true),
+            _ => {
+                let positive = rng.below(2) == 0;
+                let adjective = if positive { rng.pick(&good) } else { rng.pick(&bad) };
+                let words: Vec<&str> = (0..4 + rng.below(10)).map(|_| rng.pick(WORDS)).collect();
+                let text = format!("{} {} {}", words.join(" "), adjective, rng.pick(WORDS));
+                written.push((text.clone(), positive));
+                (text, positive)
+            }
+        };
+        out += &format!("{},{}\n", text, if positive { "pos" } else { "neg" });
+    }
+    out.into_bytes()
+}
+
+fn events(records: usize) -> Vec<u8> {
+    let mut rng = Rng(0x51_7cc1_b727_220a);
+    let kinds = ["view", "click", "add_to_cart", "purchase", "search", "logout"];
+    let mut out = String::new();
+    for i in 0..records {
+        out += &format!(
+            "{{\"event_id\":{},\"user\":\"u{}\",\"kind\":\"{}\",\"city\":\"{}\",\"value\":{},\"query\":\"{} {}\"}}\n",
+            i,
+            rng.below(400),
+            rng.pick(&kinds),
+            rng.pick(CITIES),
+            rng.below(10_000) as f64 / 100.0,
+            rng.pick(WORDS),
+            rng.pick(WORDS),
+        );
+    }
+    out.into_bytes()
+}
+
+// Sentences of random words; garbled, one word in eight is UTF-8 once mis-decoded as Latin-1.
+fn prose(sentences: usize, garbled: bool) -> Vec<u8> {
+    let mut rng = Rng(0x2f0f_3c1e_dead_beef);
+    let mut out = String::new();
+    for i in 0..sentences {
+        let words: Vec<&str> = (0..6 + rng.below(14))
+            .map(|_| if garbled && rng.below(8) == 0 { "cafÃ©" } else { rng.pick(WORDS) })
+            .collect();
+        let sentence = words.join(" ");
+        let mut chars = sentence.chars();
+        out.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        out += chars.as_str();
+        out += if i % 5 == 4 { ".\n\n" } else { ". " };
+    }
+    out.into_bytes()
+}
+
+// A handful of templates filled with few values: what generated filler looks like.
+fn templated(lines: usize) -> Vec<u8> {
+    let templates = ["record {} status ok", "record {} status ok checked", "item {} processed"];
+    (0..lines).flat_map(|i| format!("{}\n", templates[i % 3].replace("{}", &(i % 10).to_string())).into_bytes()).collect()
+}
+
+fn random_bytes(len: usize) -> Vec<u8> {
+    let mut rng = Rng(0x0123_4567_89ab_cdef);
+    (0..len / 8).flat_map(|_| rng.next().to_le_bytes()).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Instant;
+
+    #[test]
+    fn test_golden_corpus() {
+        let mut table = Vec::new();
+        let mut failures = Vec::new();
+        for fixture in CORPUS {
+            let opts = ValidationOptions { label_column: fixture.label_column.map(str::to_string), ..Default::default() };
+            let data = (fixture.data)();
+            let started = Instant::now();
+            let result = validate_dataset_quality(&data, &opts);
+            let mb_per_s = data.len() as f64 / 1e6 / started.elapsed().as_secs_f64().max(1e-9);
+            let outcome = match &result {
+                Ok(report) => {
+                    let checks: Vec<String> = report.breakdown.scores().map(|(n, s)| format!("{}={}", n, s)).collect();
+                    format!("{:>3}  {}", report.score, checks.join(" "))
+                }
+                Err(err) => format!("refused: {:#}", err),
+            };
+            table.push(format!("{:<16} {:>7.1} MB/s  {}", fixture.name, mb_per_s, outcome));
+            match (&fixture.expect, &result) {
+                (Expect::Scored((low, high), checks), Ok(report)) => {
+                    if !(low..=high).contains(&&report.score) {
+                        failures.push(format!("{}: score {} outside {}..={}", fixture.name, report.score, low, high));
+                    }
+                    for &(check, low, high) in checks.iter() {
+                        match report.breakdown.check(check) {
+                            Some(s) if (low..=high).contains(&s) => {}
+                            s => failures.push(format!("{}: {} {:?} outside {}..={}", fixture.name, check, s, low, high)),
+                        }
+                    }
+                }
+                (Expect::Refused(why), Err(err)) if format!("{:#}", err).contains(why) => {}
+                (Expect::Refused(why), _) => failures.push(format!("{}: expected refusal ({})", fixture.name, why)),
+                (Expect::Scored(..), Err(err)) => failures.push(format!("{}: refused: {:#}", fixture.name, err)),
+            }
+        }
+        assert!(failures.is_empty(), "{}\n\nscores:\n{}", failures.join("\n"), table.join("\n"));
+        println!("{}", table.join("\n"));
+    }
+}
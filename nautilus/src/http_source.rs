@@ -0,0 +1,225 @@
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use futures_util::stream::{BoxStream, StreamExt, TryStreamExt};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, RANGE};
+use reqwest::{redirect, Client, Method, Response, StatusCode, Url};
+use std::env;
+use std::time::Duration;
+use tokio::time::timeout;
+use tracing::{info, warn};
+
+use crate::blob_source::{BlobSource, SourceNotAllowed};
+use crate::walrus_client::{capped, parse_content_range_total, slice_range, BlobMetadata, BlobRange, WalrusConfig, WalrusError};
+
+// Plain HTTP(S) datasets, for providers that point at an S3 or HTTPS URL before migrating to
+// Walrus: a `blob_id` that is an http(s) URL is fetched from that URL instead of an aggregator.
+// Only hosts on NAUTILUS_HTTP_SOURCE_ALLOWLIST are ever contacted (exact names, or `*.example.com`
+// for subdomains), redirects are followed only within it, and plain http additionally needs
+// NAUTILUS_HTTP_SOURCE_ALLOW_PLAIN=1. A URL doesn't commit to its content the way a Walrus blob
+// ID does, so nothing is cached and the attestation records which kind of source was scored.
+
+const MAX_REDIRECTS: usize = 5;
+
+#[derive(Debug, Clone, Default)]
+pub struct HttpSourceConfig {
+    // Lower-cased host names; an empty list disables the backend.
+    pub allowlist: Vec<String>,
+    pub allow_plain_http: bool,
+}
+
+impl HttpSourceConfig {
+    pub fn from_env() -> Self {
+        Self {
+            allowlist: env::var("NAUTILUS_HTTP_SOURCE_ALLOWLIST")
+                .unwrap_or_default()
+                .split(',')
+                .map(|h| h.trim().to_ascii_lowercase())
+                .filter(|h| !h.is_empty())
+                .collect(),
+            allow_plain_http: env::var("NAUTILUS_HTTP_SOURCE_ALLOW_PLAIN")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+        }
+    }
+
+    // Whether `url` may be fetched: allowed scheme, no embedded credentials, allowlisted host.
+    fn check(&self, url: &Url) -> Result<(), SourceNotAllowed> {
+        match url.scheme() {
+            "https" => {}
+            "http" if self.allow_plain_http => {}
+            scheme => return Err(SourceNotAllowed(format!("scheme {} is not allowed", scheme))),
+        }
+        if !url.username().is_empty() || url.password().is_some() {
+            return Err(SourceNotAllowed("credentials in source URLs are not allowed".into()));
+        }
+        let host = url.host_str().unwrap_or_default();
+        let allowed = self.allowlist.iter().any(|entry| match entry.strip_prefix("*.") {
+            Some(domain) => host.strip_suffix(domain).is_some_and(|sub| sub.len() > 1 && sub.ends_with('.')),
+            None => host == entry,
+        });
+        if allowed {
+            Ok(())
+        } else {
+            Err(SourceNotAllowed(format!("host {} is not on the source allowlist", host)))
+        }
+    }
+}
+
+pub struct HttpSource {
+    http: Client,
+    config: HttpSourceConfig,
+    max_blob_bytes: u64,
+    request_timeout: Duration,
+}
+
+impl HttpSource {
+    // None when no hosts are allowlisted. Egress goes through the same proxy as Walrus traffic.
+    pub fn new(config: &HttpSourceConfig, walrus: &WalrusConfig, proxy_url: Option<&str>) -> Result<Option<Self>> {
+        if config.allowlist.is_empty() {
+            return Ok(None);
+        }
+        let policy_config = config.clone();
+        let policy = redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                attempt.error("too many redirects")
+            } else if policy_config.check(attempt.url()).is_ok() {
+                attempt.follow()
+            } else {
+                // Hand the redirect back unfollowed; `request` reports it.
+                attempt.stop()
+            }
+        });
+        let mut builder = Client::builder()
+            .use_rustls_tls()
+            .redirect(policy)
+            .connect_timeout(walrus.connect_timeout)
+            .tcp_keepalive(walrus.tcp_keepalive);
+        if let Some(url) = proxy_url {
+            builder = builder.proxy(reqwest::Proxy::all(url).context("Invalid HTTP source proxy URL")?);
+        }
+        let http = builder.build().context("Failed building HTTP source client")?;
+        info!(allowlist = ?config.allowlist, allow_plain_http = config.allow_plain_http, "HTTP source backend enabled");
+        Ok(Some(Self {
+            http,
+            config: config.clone(),
+            max_blob_bytes: walrus.max_blob_bytes,
+            request_timeout: walrus.request_timeout,
+        }))
+    }
+
+    async fn request(&self, blob_id: &str, range: Option<&str>) -> Result<Response> {
+        let url = Url::parse(blob_id).map_err(|err| SourceNotAllowed(format!("invalid source URL: {}", err)))?;
+        self.config.check(&url)?;
+        let host = url.host_str().unwrap_or_default().to_string();
+        let mut req = self.http.request(Method::GET, url);
+        if let Some(range) = range {
+            req = req.header(RANGE, range);
+        }
+        info!(%host, "Fetching HTTP source blob");
+        let resp = timeout(self.request_timeout, req.send())
+            .await
+            .map_err(|_| anyhow::anyhow!("HTTP source {} timed out after {:?}", host, self.request_timeout))?
+            .with_context(|| format!("HTTP source {} unreachable", host))?;
+        match resp.status() {
+            StatusCode::NOT_FOUND | StatusCode::GONE => Err(WalrusError::NotFound.into()),
+            status if status.is_redirection() => {
+                Err(SourceNotAllowed(format!("{} redirected outside the source allowlist", host)).into())
+            }
+            status if !status.is_success() => anyhow::bail!("HTTP source {} returned {}", host, status),
+            _ => Ok(resp),
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobSource for HttpSource {
+    async fn fetch_blob(&self, blob_id: &str, limit: u64) -> Result<Vec<u8>> {
+        let mut body = self.fetch_blob_stream(blob_id, limit).await?;
+        let mut data = Vec::new();
+        while let Some(chunk) = body.next().await {
+            data.extend_from_slice(&chunk?);
+        }
+        Ok(data)
+    }
+
+    async fn fetch_blob_stream(&self, blob_id: &str, limit: u64) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let cap = limit.min(self.max_blob_bytes);
+        let resp = self.request(blob_id, None).await?;
+        if resp.content_length().is_some_and(|len| len > cap) {
+            return Err(WalrusError::TooLarge { limit: cap }.into());
+        }
+        let body = resp.bytes_stream().map_err(anyhow::Error::from).boxed();
+        Ok(capped(body, cap).boxed())
+    }
+
+    async fn fetch_blob_range(&self, blob_id: &str, offset: u64, len: u64) -> Result<BlobRange> {
+        if len == 0 {
+            anyhow::bail!("range length must be non-zero");
+        }
+        let end = offset.checked_add(len - 1).context("range end overflows")?;
+        let range = format!("bytes={}-{}", offset, end);
+        let resp = self.request(blob_id, Some(&range)).await?;
+        if resp.status() == StatusCode::PARTIAL_CONTENT {
+            let total_len = resp
+                .headers()
+                .get(CONTENT_RANGE)
+                .and_then(|v| v.to_str().ok())
+                .and_then(parse_content_range_total);
+            let data = resp.bytes().await.context("Read HTTP source range body failed")?;
+            if data.len() as u64 > len {
+                anyhow::bail!("HTTP source returned {} bytes for a {}-byte range", data.len(), len);
+            }
+            return Ok(BlobRange { data: data.to_vec(), total_len });
+        }
+        warn!("HTTP source ignored Range header; slicing full blob");
+        let full = self.fetch_blob(blob_id, self.max_blob_bytes).await?;
+        Ok(slice_range(&full, offset, len))
+    }
+
+    // A one-byte ranged GET rather than HEAD: pre-signed object store URLs are usually only
+    // valid for GET.
+    async fn blob_metadata(&self, blob_id: &str) -> Result<BlobMetadata> {
+        let resp = self.request(blob_id, Some("bytes=0-0")).await?;
+        let header = |name| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        let size = match resp.status() {
+            StatusCode::PARTIAL_CONTENT => header(CONTENT_RANGE).as_deref().and_then(parse_content_range_total),
+            _ => header(CONTENT_LENGTH).and_then(|v| v.parse().ok()),
+        };
+        if size.is_some_and(|size| size > self.max_blob_bytes) {
+            return Err(WalrusError::TooLarge { limit: self.max_blob_bytes }.into());
+        }
+        Ok(BlobMetadata { size, content_type: header(CONTENT_TYPE), etag: header(ETAG) })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_allowlist_is_strict() {
+        let config = HttpSourceConfig {
+            allowlist: vec!["data.example.com".into(), "*.s3.amazonaws.com".into()],
+            allow_plain_http: false,
+        };
+        let check = |url: &str| config.check(&Url::parse(url).unwrap()).is_ok();
+        assert!(check("https://data.example.com/set.bin"));
+        assert!(check("https://DATA.example.com/set.bin"));
+        assert!(check("https://bucket.s3.amazonaws.com/key?X-Amz-Signature=abc"));
+        assert!(!check("https://s3.amazonaws.com/bucket/key"));
+        assert!(!check("https://evils3.amazonaws.com/key"));
+        assert!(!check("https://data.example.com.evil.net/set.bin"));
+        assert!(!check("https://user:pw@data.example.com/set.bin"));
+        assert!(!check("http://data.example.com/set.bin"));
+        let plain = HttpSourceConfig { allow_plain_http: true, ..config };
+        assert!(plain.check(&Url::parse("http://data.example.com/x").unwrap()).is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_range_end_overflow_is_an_error() {
+        let config = HttpSourceConfig { allowlist: vec!["data.example.com".into()], allow_plain_http: false };
+        let source = HttpSource::new(&config, &WalrusConfig::from_env().unwrap(), None).unwrap().unwrap();
+        let err = source.fetch_blob_range("https://data.example.com/set.bin", u64::MAX, 2).await.unwrap_err();
+        assert!(err.to_string().contains("overflows"), "{}", err);
+    }
+}
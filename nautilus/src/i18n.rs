@@ -0,0 +1,410 @@
+use serde::Serialize;
+
+// Localized human-readable strings for error and remediation codes.
+// Codes are the stable, machine-readable contract; messages are picked per request from
+// Accept-Language and fall back to English when a language or entry is missing.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Lang {
+    En,
+    Es,
+    Fr,
+    De,
+    Zh,
+}
+
+impl Lang {
+    pub fn tag(&self) -> &'static str {
+        match self {
+            Lang::En => "en",
+            Lang::Es => "es",
+            Lang::Fr => "fr",
+            Lang::De => "de",
+            Lang::Zh => "zh",
+        }
+    }
+
+    fn from_primary_subtag(tag: &str) -> Option<Self> {
+        match tag {
+            "en" => Some(Lang::En),
+            "es" => Some(Lang::Es),
+            "fr" => Some(Lang::Fr),
+            "de" => Some(Lang::De),
+            "zh" => Some(Lang::Zh),
+            _ => None,
+        }
+    }
+
+    fn index(&self) -> usize {
+        *self as usize
+    }
+}
+
+// Pick the supported language with the highest q-value from an Accept-Language header,
+// e.g. "fr-CH, fr;q=0.9, en;q=0.8". Ties keep header order; nothing supported means English.
+pub fn negotiate(accept_language: Option<&str>) -> Lang {
+    let Some(header) = accept_language else { return Lang::En };
+    let mut best: Option<(f32, Lang)> = None;
+    for item in header.split(',') {
+        let mut parts = item.trim().split(';');
+        let tag = parts.next().unwrap_or("").trim().to_ascii_lowercase();
+        let q = parts
+            .find_map(|p| p.trim().strip_prefix("q=").and_then(|v| v.parse::<f32>().ok()))
+            .unwrap_or(1.0);
+        let primary = tag.split('-').next().unwrap_or("");
+        if let Some(lang) = Lang::from_primary_subtag(primary) {
+            if q > 0.0 && best.is_none_or(|(bq, _)| q > bq) {
+                best = Some((q, lang));
+            }
+        }
+    }
+    best.map(|(_, lang)| lang).unwrap_or(Lang::En)
+}
+
+// code -> [en, es, fr, de, zh]
+const CATALOG: &[(&str, [&str; 5])] = &[
+    // Errors
+    ("INVALID_REQUEST", [
+        "The request could not be processed.",
+        "No se pudo procesar la solicitud.",
+        "La requête n'a pas pu être traitée.",
+        "Die Anfrage konnte nicht verarbeitet werden.",
+        "无法处理该请求。",
+    ]),
+    ("UNAUTHORIZED", [
+        "A valid API key with the required scope is needed. Send it as 'Authorization: Bearer <key>'.",
+        "Se necesita una clave de API válida con el alcance requerido. Envíela como 'Authorization: Bearer <clave>'.",
+        "Une clé d'API valide disposant de la portée requise est nécessaire. Envoyez-la via 'Authorization: Bearer <clé>'.",
+        "Ein gültiger API-Schlüssel mit dem erforderlichen Geltungsbereich wird benötigt. Senden Sie ihn als 'Authorization: Bearer <Schlüssel>'.",
+        "需要具有所需权限范围的有效 API 密钥。请以 'Authorization: Bearer <密钥>' 形式发送。",
+    ]),
+    ("INVALID_POLICY", [
+        "The candidate policy is invalid.",
+        "La política propuesta no es válida.",
+        "La politique proposée n'est pas valide.",
+        "Die vorgeschlagene Richtlinie ist ungültig.",
+        "候选策略无效。",
+    ]),
+    ("BLOB_NOT_FOUND", [
+        "The dataset blob was not found on Walrus. Check the blob ID and that it has not expired.",
+        "No se encontró el blob del conjunto de datos en Walrus. Verifique el ID del blob y que no haya caducado.",
+        "Le blob du jeu de données est introuvable sur Walrus. Vérifiez l'identifiant du blob et qu'il n'a pas expiré.",
+        "Der Datensatz-Blob wurde auf Walrus nicht gefunden. Prüfen Sie die Blob-ID und ob er abgelaufen ist.",
+        "在 Walrus 上未找到该数据集 blob。请检查 blob ID 以及它是否已过期。",
+    ]),
+    ("BLOB_EXPIRED", [
+        "The dataset blob's Walrus storage has expired. The provider must store it again before it can be verified.",
+        "El almacenamiento en Walrus del blob del conjunto de datos ha caducado. El proveedor debe volver a almacenarlo antes de poder verificarlo.",
+        "Le stockage Walrus du blob du jeu de données a expiré. Le fournisseur doit le stocker à nouveau avant sa vérification.",
+        "Der Walrus-Speicher des Datensatz-Blobs ist abgelaufen. Der Anbieter muss ihn erneut speichern, bevor er geprüft werden kann.",
+        "该数据集 blob 的 Walrus 存储已过期。提供方必须重新存储后才能进行验证。",
+    ]),
+    ("BLOB_NOT_CERTIFIED", [
+        "The dataset blob is registered on Walrus but not certified yet. The provider must finish storing it.",
+        "El blob del conjunto de datos está registrado en Walrus pero aún no está certificado. El proveedor debe terminar de almacenarlo.",
+        "Le blob du jeu de données est enregistré sur Walrus mais pas encore certifié. Le fournisseur doit terminer son stockage.",
+        "Der Datensatz-Blob ist auf Walrus registriert, aber noch nicht zertifiziert. Der Anbieter muss das Speichern abschließen.",
+        "该数据集 blob 已在 Walrus 上注册但尚未认证。提供方必须完成存储。",
+    ]),
+    ("QUALITY_OVERLOADED", [
+        "The verification service is overloaded. Please retry later.",
+        "El servicio de verificación está sobrecargado. Vuelva a intentarlo más tarde.",
+        "Le service de vérification est surchargé. Veuillez réessayer plus tard.",
+        "Der Prüfdienst ist überlastet. Bitte versuchen Sie es später erneut.",
+        "验证服务过载，请稍后重试。",
+    ]),
+    ("UPSTREAM_UNAVAILABLE", [
+        "A storage or chain backend is unavailable. Please retry later.",
+        "Un servicio de almacenamiento o de la cadena no está disponible. Vuelva a intentarlo más tarde.",
+        "Un service de stockage ou de la chaîne est indisponible. Veuillez réessayer plus tard.",
+        "Ein Speicher- oder Chain-Dienst ist nicht erreichbar. Bitte versuchen Sie es später erneut.",
+        "存储或链上后端不可用，请稍后重试。",
+    ]),
+    ("UPSTREAM_TIMEOUT", [
+        "A storage or chain backend did not respond in time. Please retry later.",
+        "Un servicio de almacenamiento o de la cadena no respondió a tiempo. Vuelva a intentarlo más tarde.",
+        "Un service de stockage ou de la chaîne n'a pas répondu à temps. Veuillez réessayer plus tard.",
+        "Ein Speicher- oder Chain-Dienst hat nicht rechtzeitig geantwortet. Bitte versuchen Sie es später erneut.",
+        "存储或链上后端响应超时，请稍后重试。",
+    ]),
+    ("INTERNAL_ERROR", [
+        "The service hit an internal error. Please retry later.",
+        "El servicio sufrió un error interno. Vuelva a intentarlo más tarde.",
+        "Le service a rencontré une erreur interne. Veuillez réessayer plus tard.",
+        "Im Dienst ist ein interner Fehler aufgetreten. Bitte versuchen Sie es später erneut.",
+        "服务发生内部错误，请稍后重试。",
+    ]),
+    ("QUALITY_JOB_OOM", [
+        "The dataset is too large to verify with this deployment's memory budget.",
+        "El conjunto de datos es demasiado grande para el presupuesto de memoria de este despliegue.",
+        "Le jeu de données est trop volumineux pour le budget mémoire de ce déploiement.",
+        "Der Datensatz ist für das Speicherbudget dieser Bereitstellung zu groß.",
+        "数据集过大，超出了此部署的内存预算。",
+    ]),
+    ("QUALITY_CONTENT_MISMATCH", [
+        "The downloaded content does not match the expected digest. The storage node may be faulty; retry or contact support.",
+        "El contenido descargado no coincide con el resumen esperado. El nodo de almacenamiento puede fallar; reintente o contacte con soporte.",
+        "Le contenu téléchargé ne correspond pas à l'empreinte attendue. Le nœud de stockage est peut-être défaillant ; réessayez ou contactez le support.",
+        "Der heruntergeladene Inhalt stimmt nicht mit dem erwarteten Hash überein. Der Speicherknoten ist möglicherweise fehlerhaft; erneut versuchen oder Support kontaktieren.",
+        "下载的内容与预期摘要不符。存储节点可能存在故障，请重试或联系支持。",
+    ]),
+    ("SIGNING_LOCKED", [
+        "Attestation signing is paused after an unusual spike in key usage. An operator must re-arm the key.",
+        "La firma de atestaciones está en pausa tras un pico inusual de uso de la clave. Un operador debe rehabilitarla.",
+        "La signature des attestations est suspendue après un pic inhabituel d'utilisation de la clé. Un opérateur doit la réarmer.",
+        "Das Signieren von Attestierungen ist nach einem ungewöhnlichen Anstieg der Schlüsselnutzung pausiert. Ein Betreiber muss den Schlüssel wieder freigeben.",
+        "由于密钥使用量异常激增，证明签名已暂停。需由运维人员重新启用该密钥。",
+    ]),
+    ("COSIGN_UNAVAILABLE", [
+        "This attestation requires the operator's co-signature, which could not be obtained. Please retry later.",
+        "Esta atestación requiere la firma conjunta del operador, que no se pudo obtener. Vuelva a intentarlo más tarde.",
+        "Cette attestation exige la cosignature de l'opérateur, qui n'a pas pu être obtenue. Veuillez réessayer plus tard.",
+        "Diese Attestierung erfordert die Mitsignatur des Betreibers, die nicht eingeholt werden konnte. Bitte versuchen Sie es später erneut.",
+        "此证明需要运维方联合签名，但未能获取。请稍后重试。",
+    ]),
+    ("CONTENT_POLICY_VIOLATION", [
+        "This dataset is not accepted under your organisation's content policy (file type, size or banned content).",
+        "Este conjunto de datos no se acepta según la política de contenido de su organización (tipo de archivo, tamaño o contenido prohibido).",
+        "Ce jeu de données n'est pas accepté par la politique de contenu de votre organisation (type de fichier, taille ou contenu interdit).",
+        "Dieser Datensatz ist gemäß der Inhaltsrichtlinie Ihrer Organisation nicht zulässig (Dateityp, Größe oder gesperrter Inhalt).",
+        "根据贵组织的内容策略，该数据集不被接受（文件类型、大小或禁止的内容）。",
+    ]),
+    ("PAYLOAD_SCREENING_FAILED", [
+        "The dataset contains embedded executables, macro documents or a known-malicious file and was refused.",
+        "El conjunto de datos contiene ejecutables incrustados, documentos con macros o un archivo malicioso conocido y fue rechazado.",
+        "Le jeu de données contient des exécutables intégrés, des documents à macros ou un fichier malveillant connu et a été refusé.",
+        "Der Datensatz enthält eingebettete ausführbare Dateien, Makro-Dokumente oder eine bekannte Schaddatei und wurde abgelehnt.",
+        "该数据集包含嵌入的可执行文件、带宏文档或已知恶意文件，已被拒绝。",
+    ]),
+    ("PAYLOAD_OPAQUE", [
+        "The data appears to be encrypted or compressed, so it can't be scored; decryption likely failed. Upload the decrypted, uncompressed dataset.",
+        "Los datos parecen estar cifrados o comprimidos, por lo que no se pueden evaluar; probablemente falló el descifrado. Suba el conjunto de datos descifrado y sin comprimir.",
+        "Les données semblent chiffrées ou compressées et ne peuvent pas être évaluées ; le déchiffrement a probablement échoué. Publiez le jeu de données déchiffré et non compressé.",
+        "Die Daten scheinen verschlüsselt oder komprimiert zu sein und können nicht bewertet werden; die Entschlüsselung ist vermutlich fehlgeschlagen. Laden Sie den entschlüsselten, unkomprimierten Datensatz hoch.",
+        "数据似乎已加密或压缩，无法评分；解密可能失败。请上传已解密且未压缩的数据集。",
+    ]),
+    ("SOURCE_NOT_ALLOWED", [
+        "This dataset URL is not on the deployment's source allowlist. Use an allowlisted host or upload the dataset to Walrus.",
+        "La URL del conjunto de datos no está en la lista de orígenes permitidos. Use un host permitido o suba el conjunto de datos a Walrus.",
+        "L'URL du jeu de données ne figure pas dans la liste des sources autorisées. Utilisez un hôte autorisé ou publiez le jeu de données sur Walrus.",
+        "Die Datensatz-URL steht nicht auf der Liste erlaubter Quellen. Verwenden Sie einen erlaubten Host oder laden Sie den Datensatz zu Walrus hoch.",
+        "该数据集 URL 不在允许的来源列表中。请使用允许的主机，或将数据集上传到 Walrus。",
+    ]),
+    // What each check measures, for the detailed report (?detail=full)
+    ("CHECK_DIVERSITY", [
+        "How varied the content is: byte entropy, or value spread per column and per image where the format allows.",
+        "Qué tan variado es el contenido: entropía de bytes, o dispersión de valores por columna y por imagen cuando el formato lo permite.",
+        "Diversité du contenu : entropie des octets, ou dispersion des valeurs par colonne et par image lorsque le format le permet.",
+        "Wie vielfältig der Inhalt ist: Byte-Entropie oder, wo das Format es erlaubt, Wertestreuung je Spalte und je Bild.",
+        "内容的多样性：字节熵，或在格式允许时按列、按图像的取值分布。",
+    ]),
+    ("CHECK_BIAS", [
+        "Whether values cover their range or cluster narrowly, which points to skewed sampling.",
+        "Si los valores cubren su rango o se agrupan estrechamente, lo que indica un muestreo sesgado.",
+        "Si les valeurs couvrent leur plage ou se regroupent étroitement, ce qui indique un échantillonnage biaisé.",
+        "Ob die Werte ihren Bereich abdecken oder eng gebündelt sind, was auf eine verzerrte Stichprobe hindeutet.",
+        "数值是否覆盖其范围，还是集中在狭窄区间（提示采样偏差）。",
+    ]),
+    ("CHECK_AUTHENTICITY", [
+        "How much of the data repeats: repeated byte windows, or duplicate and near-duplicate records.",
+        "Cuánto de los datos se repite: ventanas de bytes repetidas, o registros duplicados y casi duplicados.",
+        "Part des données qui se répète : fenêtres d'octets répétées, ou enregistrements en double et quasi-doublons.",
+        "Wie viel der Daten sich wiederholt: wiederholte Byte-Fenster oder doppelte und nahezu doppelte Datensätze.",
+        "数据的重复程度：重复的字节窗口，或重复与近似重复的记录。",
+    ]),
+    ("CHECK_COMPLETENESS", [
+        "Whether the dataset is large enough to be a full dataset rather than a sample, and free of missing values where the format shows them.",
+        "Si el conjunto de datos es lo bastante grande para ser completo y no una muestra, y sin valores faltantes cuando el formato los muestra.",
+        "Si le jeu de données est assez grand pour être complet plutôt qu'un échantillon, et sans valeurs manquantes lorsque le format les révèle.",
+        "Ob der Datensatz groß genug ist, um vollständig statt einer Stichprobe zu sein, und ohne fehlende Werte, wo das Format sie zeigt.",
+        "数据集是否足够大，属于完整数据集而非样本；在格式可判断时，是否没有缺失值。",
+    ]),
+    ("CHECK_CONSISTENCY", [
+        "Whether the data is well formed: parseable records, uniform structure, no null-byte runs or truncated containers.",
+        "Si los datos están bien formados: registros analizables, estructura uniforme, sin secuencias de bytes nulos ni contenedores truncados.",
+        "Si les données sont bien formées : enregistrements analysables, structure uniforme, sans suites d'octets nuls ni conteneurs tronqués.",
+        "Ob die Daten wohlgeformt sind: parsebare Datensätze, einheitliche Struktur, keine Null-Byte-Folgen oder abgeschnittenen Container.",
+        "数据是否格式良好：记录可解析、结构一致，没有空字节序列或被截断的容器。",
+    ]),
+    ("CHECK_PRIVACY", [
+        "Share of records free of personal data (emails, phone numbers, ID and card numbers, names); only counts are reported.",
+        "Proporción de registros sin datos personales (correos, teléfonos, números de identificación y de tarjeta, nombres); solo se informan recuentos.",
+        "Part des enregistrements sans données personnelles (e-mails, téléphones, numéros d'identité et de carte, noms) ; seuls des comptes sont publiés.",
+        "Anteil der Datensätze ohne personenbezogene Daten (E-Mails, Telefonnummern, Ausweis- und Kartennummern, Namen); nur Anzahlen werden gemeldet.",
+        "不含个人数据（电子邮件、电话号码、证件号和卡号、姓名）的记录占比；仅报告计数。",
+    ]),
+    ("CHECK_SECRETS", [
+        "Share of records free of credentials (private keys, API keys, JWTs, connection strings with passwords, random-looking secret values); only counts are reported.",
+        "Proporción de registros sin credenciales (claves privadas, claves de API, JWT, cadenas de conexión con contraseña, valores secretos de aspecto aleatorio); solo se informan recuentos.",
+        "Part des enregistrements sans identifiants (clés privées, clés d'API, JWT, chaînes de connexion avec mot de passe, valeurs secrètes d'apparence aléatoire) ; seuls des comptes sont publiés.",
+        "Anteil der Datensätze ohne Zugangsdaten (private Schlüssel, API-Schlüssel, JWTs, Verbindungszeichenfolgen mit Passwort, zufällig wirkende Geheimwerte); nur Anzahlen werden gemeldet.",
+        "不含凭据（私钥、API 密钥、JWT、带密码的连接字符串、看似随机的密钥值）的记录占比；仅报告计数。",
+    ]),
+    ("CHECK_CONTENT_SAFETY", [
+        "Share of records free of profanity and abuse by the operator's lexicon; a record with abuse costs twice what one with only profanity does. Only counts are reported.",
+        "Proporción de registros sin groserías ni insultos según el léxico del operador; un registro con insultos cuenta el doble que uno con solo groserías. Solo se informan recuentos.",
+        "Part des enregistrements sans grossièretés ni propos injurieux selon le lexique de l'opérateur ; un enregistrement injurieux compte double par rapport à un enregistrement seulement grossier. Seuls des comptes sont publiés.",
+        "Anteil der Datensätze ohne Obszönitäten und Beleidigungen laut Lexikon des Betreibers; ein beleidigender Datensatz zählt doppelt so viel wie einer mit bloßen Obszönitäten. Nur Anzahlen werden gemeldet.",
+        "根据运营方词表，不含脏话和辱骂内容的记录占比；含辱骂的记录扣分是仅含脏话记录的两倍。仅报告计数。",
+    ]),
+    ("CHECK_POISONING", [
+        "100 minus the poisoning risk: clusters of outlying records, unusual tokens repeated across records, and, with a label column, identical records labeled differently.",
+        "100 menos el riesgo de envenenamiento: grupos de registros atípicos, tokens inusuales repetidos entre registros y, con una columna de etiquetas, registros idénticos con etiquetas distintas.",
+        "100 moins le risque d'empoisonnement : groupes d'enregistrements aberrants, jetons inhabituels répétés d'un enregistrement à l'autre et, avec une colonne d'étiquettes, enregistrements identiques étiquetés différemment.",
+        "100 minus das Vergiftungsrisiko: Gruppen auffälliger Datensätze, ungewöhnliche Token, die sich über Datensätze wiederholen, und, mit einer Label-Spalte, identische Datensätze mit unterschiedlichen Labels.",
+        "100 减去投毒风险：离群记录簇、在多条记录中重复出现的异常词元，以及（指定标签列时）内容相同但标签不同的记录。",
+    ]),
+    ("CHECK_COMPLEXITY", [
+        "How far sampled blocks of the data compress: records repeated from a few templates shrink to almost nothing.",
+        "Cuánto se comprimen bloques de muestra de los datos: los registros repetidos a partir de unas pocas plantillas casi desaparecen.",
+        "Taux de compression de blocs échantillonnés : des enregistrements répétés à partir de quelques modèles se réduisent presque à rien.",
+        "Wie stark sich Stichprobenblöcke der Daten komprimieren lassen: aus wenigen Vorlagen wiederholte Datensätze schrumpfen fast auf nichts.",
+        "抽样数据块的压缩程度：由少数模板重复生成的记录几乎会被压缩殆尽。",
+    ]),
+    ("CHECK_FRESHNESS", [
+        "How recent the newest record of the declared timestamp column is, halving every 180 days after the first; records dated in the future count against it.",
+        "Qué tan reciente es el registro más nuevo de la columna de marca de tiempo declarada; se reduce a la mitad cada 180 días a partir del primero y los registros con fecha futura cuentan en contra.",
+        "Fraîcheur de l'enregistrement le plus récent de la colonne d'horodatage déclarée, divisée par deux tous les 180 jours après le premier ; les enregistrements datés dans le futur la pénalisent.",
+        "Wie aktuell der neueste Datensatz der angegebenen Zeitstempelspalte ist; halbiert sich alle 180 Tage nach dem ersten, in die Zukunft datierte Datensätze zählen dagegen.",
+        "所声明时间戳列中最新记录的新鲜程度，第一天之后每 180 天减半；未来日期的记录会拉低该分数。",
+    ]),
+    ("CHECK_FINITE_WEIGHTS", [
+        "Share of a model's floating-point weights that are neither NaN nor infinite; any such weight caps the score at 50.",
+        "Proporción de los pesos de coma flotante del modelo que no son NaN ni infinitos; cualquiera de ellos limita la puntuación a 50.",
+        "Part des poids à virgule flottante du modèle qui ne sont ni NaN ni infinis ; un seul de ces poids plafonne le score à 50.",
+        "Anteil der Gleitkommagewichte des Modells, die weder NaN noch unendlich sind; schon ein solches Gewicht begrenzt die Bewertung auf 50.",
+        "模型浮点权重中既非 NaN 也非无穷大的比例；只要存在此类权重，分数上限即为 50。",
+    ]),
+    // Remediation hints
+    ("LOW_DIVERSITY", [
+        "Data is highly repetitive at the byte level. Remove padding, duplicated records or constant fields.",
+        "Los datos son muy repetitivos a nivel de bytes. Elimine relleno, registros duplicados o campos constantes.",
+        "Les données sont très répétitives au niveau des octets. Supprimez le remplissage, les enregistrements dupliqués ou les champs constants.",
+        "Die Daten sind auf Byte-Ebene stark repetitiv. Entfernen Sie Auffüllungen, doppelte Datensätze oder konstante Felder.",
+        "数据在字节层面高度重复。请移除填充、重复记录或常量字段。",
+    ]),
+    ("LOW_VARIANCE", [
+        "Values are concentrated in a narrow range, which suggests skewed or biased sampling.",
+        "Los valores se concentran en un rango estrecho, lo que sugiere un muestreo sesgado.",
+        "Les valeurs sont concentrées dans une plage étroite, ce qui suggère un échantillonnage biaisé.",
+        "Die Werte liegen in einem engen Bereich, was auf eine verzerrte Stichprobe hindeutet.",
+        "数值集中在狭窄范围内，可能存在采样偏差。",
+    ]),
+    ("SYNTHETIC_PATTERNS", [
+        "Repeated patterns suggest generated or duplicated content. Deduplicate the dataset before listing.",
+        "Los patrones repetidos sugieren contenido generado o duplicado. Elimine duplicados antes de publicar.",
+        "Des motifs répétés suggèrent un contenu généré ou dupliqué. Dédupliquez le jeu de données avant de le publier.",
+        "Wiederholte Muster deuten auf generierte oder doppelte Inhalte hin. Deduplizieren Sie den Datensatz vor dem Einstellen.",
+        "重复模式表明内容可能是生成或重复的。请在上架前去重。",
+    ]),
+    ("INCOMPLETE_DATA", [
+        "The dataset is small for its category. Upload the full dataset rather than a sample.",
+        "El conjunto de datos es pequeño para su categoría. Suba el conjunto completo en lugar de una muestra.",
+        "Le jeu de données est petit pour sa catégorie. Téléversez le jeu complet plutôt qu'un échantillon.",
+        "Der Datensatz ist für seine Kategorie klein. Laden Sie den vollständigen Datensatz statt einer Stichprobe hoch.",
+        "该数据集对其类别而言过小。请上传完整数据集而非样本。",
+    ]),
+    ("INCONSISTENT_DATA", [
+        "Large runs of null or filler bytes were found. Check for truncated or corrupted records.",
+        "Se encontraron largas secuencias de bytes nulos o de relleno. Compruebe si hay registros truncados o dañados.",
+        "De longues suites d'octets nuls ou de remplissage ont été trouvées. Vérifiez les enregistrements tronqués ou corrompus.",
+        "Lange Folgen von Null- oder Füllbytes wurden gefunden. Prüfen Sie auf abgeschnittene oder beschädigte Datensätze.",
+        "发现大量空字节或填充字节。请检查记录是否被截断或损坏。",
+    ]),
+    ("POISONING_RISK", [
+        "The data shows signs of poisoning: injected outlier records, repeated trigger tokens or flipped labels. Audit where the records came from before training on them.",
+        "Los datos muestran indicios de envenenamiento: registros atípicos inyectados, tokens disparadores repetidos o etiquetas invertidas. Revise el origen de los registros antes de entrenar con ellos.",
+        "Les données présentent des signes d'empoisonnement : enregistrements aberrants injectés, jetons déclencheurs répétés ou étiquettes inversées. Vérifiez la provenance des enregistrements avant tout entraînement.",
+        "Die Daten zeigen Anzeichen einer Vergiftung: eingeschleuste Ausreißer, wiederholte Trigger-Token oder vertauschte Labels. Prüfen Sie die Herkunft der Datensätze, bevor Sie damit trainieren.",
+        "数据存在投毒迹象：注入的离群记录、重复出现的触发词元或被翻转的标签。在用其训练之前，请核查记录来源。",
+    ]),
+    ("PII_DETECTED", [
+        "Many records contain personal data (emails, phone numbers, ID or card numbers, names). Remove or pseudonymize it before sharing.",
+        "Muchos registros contienen datos personales (correos, teléfonos, números de identificación o de tarjeta, nombres). Elimínelos o seudonimícelos antes de compartir.",
+        "De nombreux enregistrements contiennent des données personnelles (e-mails, téléphones, numéros d'identité ou de carte, noms). Supprimez-les ou pseudonymisez-les avant le partage.",
+        "Viele Datensätze enthalten personenbezogene Daten (E-Mails, Telefonnummern, Ausweis- oder Kartennummern, Namen). Entfernen oder pseudonymisieren Sie sie vor der Weitergabe.",
+        "许多记录包含个人数据（电子邮件、电话号码、证件号或卡号、姓名）。请在共享前删除或假名化。",
+    ]),
+    ("SECRETS_DETECTED", [
+        "Records contain credentials (private keys, API keys, tokens or passwords). Revoke them and remove them from the data before sharing.",
+        "Hay registros que contienen credenciales (claves privadas, claves de API, tokens o contraseñas). Revóquelas y elimínelas de los datos antes de compartir.",
+        "Des enregistrements contiennent des identifiants (clés privées, clés d'API, jetons ou mots de passe). Révoquez-les et retirez-les des données avant le partage.",
+        "Datensätze enthalten Zugangsdaten (private Schlüssel, API-Schlüssel, Token oder Passwörter). Widerrufen Sie sie und entfernen Sie sie vor der Weitergabe aus den Daten.",
+        "记录中包含凭据（私钥、API 密钥、令牌或密码）。请在共享前将其吊销并从数据中删除。",
+    ]),
+    ("UNSAFE_CONTENT", [
+        "Records contain profanity or abusive language. Filter or relabel them before training user-facing models.",
+        "Hay registros con groserías o lenguaje ofensivo. Fíltrelos o reetiquételos antes de entrenar modelos de cara al usuario.",
+        "Des enregistrements contiennent des grossièretés ou des propos injurieux. Filtrez-les ou réétiquetez-les avant d'entraîner des modèles destinés aux utilisateurs.",
+        "Datensätze enthalten Obszönitäten oder beleidigende Sprache. Filtern oder relabeln Sie sie, bevor Sie nutzerorientierte Modelle trainieren.",
+        "记录中含有脏话或辱骂性语言。在训练面向用户的模型之前，请过滤或重新标注这些记录。",
+    ]),
+    ("STALE_DATA", [
+        "The newest records are old or dated in the future. Refresh the dataset, or correct clocks and time zones in the timestamp column.",
+        "Los registros más recientes son antiguos o tienen fecha futura. Actualice el conjunto de datos o corrija relojes y zonas horarias en la columna de marca de tiempo.",
+        "Les enregistrements les plus récents sont anciens ou datés dans le futur. Actualisez le jeu de données, ou corrigez horloges et fuseaux horaires dans la colonne d'horodatage.",
+        "Die neuesten Datensätze sind alt oder in die Zukunft datiert. Aktualisieren Sie den Datensatz oder korrigieren Sie Uhren und Zeitzonen in der Zeitstempelspalte.",
+        "最新记录已过时或日期在未来。请更新数据集，或更正时间戳列中的时钟和时区。",
+    ]),
+    ("NONFINITE_WEIGHTS", [
+        "Some model weights are NaN or infinite. Re-export the checkpoint from a healthy training step or check the conversion and quantization.",
+        "Algunos pesos del modelo son NaN o infinitos. Vuelva a exportar el checkpoint desde un paso de entrenamiento sano o revise la conversión y la cuantización.",
+        "Certains poids du modèle sont NaN ou infinis. Réexportez le checkpoint depuis une étape d'entraînement saine ou vérifiez la conversion et la quantification.",
+        "Einige Modellgewichte sind NaN oder unendlich. Exportieren Sie den Checkpoint aus einem fehlerfreien Trainingsschritt erneut oder prüfen Sie Konvertierung und Quantisierung.",
+        "部分模型权重为 NaN 或无穷大。请从正常的训练步骤重新导出检查点，或检查转换与量化过程。",
+    ]),
+    ("LOW_COMPLEXITY", [
+        "The data compresses to almost nothing, so most records repeat a few templates. Replace generated or duplicated records with real ones.",
+        "Los datos se comprimen casi por completo, así que la mayoría de los registros repiten unas pocas plantillas. Sustituya los registros generados o duplicados por registros reales.",
+        "Les données se compressent presque entièrement : la plupart des enregistrements répètent quelques modèles. Remplacez les enregistrements générés ou dupliqués par des enregistrements réels.",
+        "Die Daten lassen sich fast vollständig komprimieren, die meisten Datensätze wiederholen also wenige Vorlagen. Ersetzen Sie generierte oder doppelte Datensätze durch echte.",
+        "数据几乎可以被完全压缩，说明大多数记录只是在重复少数模板。请用真实记录替换生成或重复的记录。",
+    ]),
+];
+
+pub fn message(code: &str, lang: Lang) -> &'static str {
+    CATALOG
+        .iter()
+        .find(|(c, _)| *c == code)
+        .map(|(_, msgs)| msgs[lang.index()])
+        .unwrap_or("")
+}
+
+// A remediation hint: stable code plus the message in the negotiated language.
+#[derive(Debug, Clone, Serialize)]
+pub struct Remediation {
+    pub code: &'static str,
+    pub message: &'static str,
+}
+
+impl Remediation {
+    pub fn new(code: &'static str, lang: Lang) -> Self {
+        Self { code, message: message(code, lang) }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_negotiate() {
+        assert_eq!(negotiate(None), Lang::En);
+        assert_eq!(negotiate(Some("fr-CH, fr;q=0.9, en;q=0.8")), Lang::Fr);
+        assert_eq!(negotiate(Some("ja, de;q=0.5, es;q=0.7")), Lang::Es);
+        assert_eq!(negotiate(Some("zh-Hans-CN")), Lang::Zh);
+        assert_eq!(negotiate(Some("de;q=0, pt")), Lang::En);
+    }
+
+    #[test]
+    fn test_catalog_is_complete() {
+        for (code, msgs) in CATALOG {
+            assert!(msgs.iter().all(|m| !m.is_empty()), "{} has a missing translation", code);
+        }
+        assert_eq!(message("BLOB_NOT_FOUND", Lang::De), CATALOG[3].1[3]);
+        // Every check is explained in the detailed report.
+        for entry in crate::quality_validator::REGISTRY {
+            assert!(!message(&format!("CHECK_{}", entry.name.to_ascii_uppercase()), Lang::En).is_empty(), "{}", entry.name);
+        }
+    }
+}
@@ -0,0 +1,753 @@
+use anyhow::{bail, ensure, Result};
+use std::io::Read;
+
+// Decoders for the image formats content_policy sniffs (PNG, JPEG, GIF). None of them produces
+// full-resolution pixels: an image is reduced to its dimensions and a Thumbnail summary (a
+// perceptual hash and a color histogram), which is all image_validator scores from. JPEGs are
+// read at 1/8 scale from the DC coefficient of each block, which skips the inverse DCT;
+// progressive and arithmetic-coded JPEGs and interlaced PNGs are checked and measured but not
+// summarized. Anything malformed or cut off is an error: that is what makes a file corrupt.
+//
+// These are hand-written rather than the `image` crate's because its decoders always produce a
+// full-resolution pixel buffer, so memory grows with the pixel count an untrusted file declares,
+// and the enclave has a fixed memory budget. Here memory is bounded by the input and a row (PNG),
+// the LZW table (GIF) or a handful of blocks (JPEG). The test module mutates and truncates sample
+// files to keep these parsers panic-free on hostile input.
+
+// The difference hash compares horizontally adjacent cells of a 9x8 grayscale grid.
+const HASH_W: u64 = 9;
+const HASH_H: u64 = 8;
+// 2 bits per RGB channel.
+pub const HIST_BINS: usize = 64;
+// Larger images are measured but not summarized (decompression bombs).
+const MAX_PIXELS: u64 = 100_000_000;
+// PNG rows are unfiltered whole, so wider PNGs are not summarized either: a one-pixel-high image
+// under MAX_PIXELS could otherwise need two 800 MB row buffers.
+const MAX_PNG_WIDTH: u32 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Gif,
+}
+
+impl ImageFormat {
+    pub fn sniff(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(Self::Png)
+        } else if head.starts_with(b"\xff\xd8\xff") {
+            Some(Self::Jpeg)
+        } else if head.starts_with(b"GIF87a") || head.starts_with(b"GIF89a") {
+            Some(Self::Gif)
+        } else {
+            None
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Png => "png",
+            Self::Jpeg => "jpeg",
+            Self::Gif => "gif",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Thumbnail {
+    pub dhash: u64,
+    // Share of pixels per color bin.
+    pub histogram: [f32; HIST_BINS],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Decoded {
+    pub format: ImageFormat,
+    pub width: u32,
+    pub height: u32,
+    pub thumb: Option<Thumbnail>,
+}
+
+pub fn decode(data: &[u8]) -> Result<Decoded> {
+    match ImageFormat::sniff(data) {
+        Some(ImageFormat::Png) => png(data),
+        Some(ImageFormat::Jpeg) => jpeg(data),
+        Some(ImageFormat::Gif) => gif(data),
+        None => bail!("not a PNG, JPEG or GIF image"),
+    }
+}
+
+// Reduces pixels on a grid (the image, or JPEG's 1/8-scale DC grid) to a Thumbnail.
+struct Sink {
+    width: u64,
+    height: u64,
+    cells: [u64; (HASH_W * HASH_H) as usize],
+    counts: [u32; (HASH_W * HASH_H) as usize],
+    histogram: [u64; HIST_BINS],
+    pixels: u64,
+}
+
+impl Sink {
+    fn new(width: u32, height: u32) -> Self {
+        Self {
+            width: width.max(1) as u64,
+            height: height.max(1) as u64,
+            cells: [0; (HASH_W * HASH_H) as usize],
+            counts: [0; (HASH_W * HASH_H) as usize],
+            histogram: [0; HIST_BINS],
+            pixels: 0,
+        }
+    }
+
+    fn push(&mut self, x: u32, y: u32, [r, g, b]: [u8; 3]) {
+        let cell = ((y as u64 * HASH_H / self.height) * HASH_W + x as u64 * HASH_W / self.width) as usize;
+        self.cells[cell] += (r as u64 * 299 + g as u64 * 587 + b as u64 * 114) / 1000;
+        self.counts[cell] += 1;
+        self.histogram[((r >> 6) << 4 | (g >> 6) << 2 | b >> 6) as usize] += 1;
+        self.pixels += 1;
+    }
+
+    fn finish(self) -> Option<Thumbnail> {
+        if self.pixels == 0 {
+            return None;
+        }
+        let mean = |i: usize| self.cells[i] as f64 / self.counts[i].max(1) as f64;
+        let mut dhash = 0u64;
+        for row in 0..HASH_H as usize {
+            for col in 0..HASH_W as usize - 1 {
+                let at = row * HASH_W as usize + col;
+                dhash = dhash << 1 | (mean(at) < mean(at + 1)) as u64;
+            }
+        }
+        let mut histogram = [0f32; HIST_BINS];
+        for (share, &n) in histogram.iter_mut().zip(&self.histogram) {
+            *share = (n as f64 / self.pixels as f64) as f32;
+        }
+        Some(Thumbnail { dhash, histogram })
+    }
+}
+
+fn be16(data: &[u8], at: usize) -> Result<usize> {
+    match data.get(at..at + 2) {
+        Some(b) => Ok(u16::from_be_bytes([b[0], b[1]]) as usize),
+        None => bail!("truncated"),
+    }
+}
+
+fn be32(data: &[u8], at: usize) -> Result<u32> {
+    match data.get(at..at + 4) {
+        Some(b) => Ok(u32::from_be_bytes([b[0], b[1], b[2], b[3]])),
+        None => bail!("truncated"),
+    }
+}
+
+fn png(data: &[u8]) -> Result<Decoded> {
+    let mut pos = 8;
+    let mut header = None;
+    let mut palette: &[u8] = &[];
+    let mut idat = Vec::new();
+    loop {
+        let len = be32(data, pos)? as usize;
+        let Some(chunk) = data.get(pos + 4..pos + 8 + len) else { bail!("truncated") };
+        let mut crc = flate2::Crc::new();
+        crc.update(chunk);
+        ensure!(crc.sum() == be32(data, pos + 8 + len)?, "chunk checksum mismatch");
+        let (kind, body) = chunk.split_at(4);
+        ensure!(header.is_some() || kind == b"IHDR", "missing IHDR");
+        match kind {
+            b"IHDR" => {
+                ensure!(body.len() == 13, "bad IHDR");
+                header = Some((be32(body, 0)?, be32(body, 4)?, body[8], body[9], body[12]));
+            }
+            b"PLTE" => palette = body,
+            b"IDAT" => idat.extend_from_slice(body),
+            b"IEND" => break,
+            _ => {}
+        }
+        pos += 12 + len;
+    }
+    let Some((width, height, depth, color, interlace)) = header else { bail!("missing IHDR") };
+    let channels = match (color, depth) {
+        (0, 1 | 2 | 4 | 8 | 16) | (3, 1 | 2 | 4 | 8) => 1,
+        (4, 8 | 16) => 2,
+        (2, 8 | 16) => 3,
+        (6, 8 | 16) => 4,
+        _ => bail!("invalid color type {} at bit depth {}", color, depth),
+    };
+    ensure!(width > 0 && height > 0 && interlace <= 1, "bad IHDR");
+    ensure!(color != 3 || !palette.is_empty(), "missing palette");
+    let mut inflated = flate2::read::ZlibDecoder::new(idat.as_slice());
+    let decoded = |thumb| Ok(Decoded { format: ImageFormat::Png, width, height, thumb });
+    if interlace == 1 || width > MAX_PNG_WIDTH || width as u64 * height as u64 > MAX_PIXELS {
+        std::io::copy(&mut inflated, &mut std::io::sink())?;
+        return decoded(None);
+    }
+
+    let bits = channels * depth as usize;
+    let (stride, bpp) = ((width as usize * bits).div_ceil(8), bits.div_ceil(8));
+    let (mut prev, mut row) = (vec![0u8; stride], vec![0u8; stride + 1]);
+    let mut sink = Sink::new(width, height);
+    let sample = |row: &[u8], i: usize| -> u8 {
+        match depth {
+            8 => row[i],
+            16 => row[i * 2],
+            _ => {
+                let shift = 8 - depth as usize - (i * depth as usize) % 8;
+                let v = (row[i * depth as usize / 8] >> shift) & ((1 << depth) - 1);
+                if color == 3 {
+                    v
+                } else {
+                    (v as u32 * 255 / ((1 << depth) - 1)) as u8
+                }
+            }
+        }
+    };
+    for y in 0..height {
+        inflated.read_exact(&mut row).map_err(|_| anyhow::anyhow!("image data ends early"))?;
+        let (filter, line) = row.split_first_mut().expect("row has a filter byte");
+        for i in 0..stride {
+            let a = if i >= bpp { line[i - bpp] as i16 } else { 0 };
+            let (b, c) = (prev[i] as i16, if i >= bpp { prev[i - bpp] as i16 } else { 0 });
+            let predicted = match *filter {
+                0 => 0,
+                1 => a,
+                2 => b,
+                3 => (a + b) / 2,
+                4 => {
+                    let p = a + b - c;
+                    let (pa, pb, pc) = ((p - a).abs(), (p - b).abs(), (p - c).abs());
+                    if pa <= pb && pa <= pc {
+                        a
+                    } else if pb <= pc {
+                        b
+                    } else {
+                        c
+                    }
+                }
+                f => bail!("bad filter type {}", f),
+            };
+            line[i] = line[i].wrapping_add(predicted as u8);
+        }
+        for x in 0..width as usize {
+            let rgb = match color {
+                3 => {
+                    let at = sample(line, x) as usize * 3;
+                    let Some(entry) = palette.get(at..at + 3) else { bail!("palette index out of range") };
+                    [entry[0], entry[1], entry[2]]
+                }
+                0 | 4 => [sample(line, x * channels); 3],
+                _ => [sample(line, x * channels), sample(line, x * channels + 1), sample(line, x * channels + 2)],
+            };
+            sink.push(x as u32, y, rgb);
+        }
+        prev.copy_from_slice(line);
+    }
+    decoded(sink.finish())
+}
+
+struct Huffman {
+    // Per code length: first code, one past the last code, and where its symbols start.
+    first: [i32; 17],
+    end: [i32; 17],
+    offset: [usize; 17],
+    symbols: Vec<u8>,
+}
+
+impl Huffman {
+    fn new(counts: &[u8], symbols: &[u8]) -> Self {
+        let (mut first, mut end, mut offset) = ([0; 17], [0; 17], [0; 17]);
+        let (mut code, mut at) = (0i32, 0usize);
+        for len in 1..=16 {
+            let n = counts[len - 1] as i32;
+            (first[len], end[len], offset[len]) = (code, code + n, at);
+            code = (code + n) << 1;
+            at += n as usize;
+        }
+        Self { first, end, offset, symbols: symbols.to_vec() }
+    }
+
+    fn decode(&self, bits: &mut Bits) -> Result<u8> {
+        let mut code = 0i32;
+        for len in 1..=16 {
+            code = code << 1 | bits.bit()? as i32;
+            if code < self.end[len] {
+                let at = self.offset[len] + (code - self.first[len]) as usize;
+                return self.symbols.get(at).copied().ok_or_else(|| anyhow::anyhow!("bad Huffman code"));
+            }
+        }
+        bail!("bad Huffman code")
+    }
+}
+
+// Entropy-coded JPEG data, with 0xFF00 byte stuffing undone.
+struct Bits<'a> {
+    data: &'a [u8],
+    pos: usize,
+    acc: u8,
+    left: u32,
+}
+
+impl Bits<'_> {
+    fn bit(&mut self) -> Result<u32> {
+        if self.left == 0 {
+            let Some(&byte) = self.data.get(self.pos) else { bail!("truncated") };
+            if byte == 0xff {
+                ensure!(self.data.get(self.pos + 1) == Some(&0), "scan data ends early");
+                self.pos += 1;
+            }
+            (self.pos, self.acc, self.left) = (self.pos + 1, byte, 8);
+        }
+        self.left -= 1;
+        Ok((self.acc >> self.left) as u32 & 1)
+    }
+
+    fn bits(&mut self, n: u8) -> Result<i32> {
+        let mut v = 0;
+        for _ in 0..n {
+            v = v << 1 | self.bit()? as i32;
+        }
+        Ok(v)
+    }
+
+    // Skips a restart marker, dropping the partial byte before it.
+    fn restart(&mut self) -> Result<()> {
+        self.left = 0;
+        ensure!(
+            self.data.get(self.pos) == Some(&0xff) && self.data.get(self.pos + 1).is_some_and(|m| (0xd0..=0xd7).contains(m)),
+            "missing restart marker"
+        );
+        self.pos += 2;
+        Ok(())
+    }
+}
+
+struct Component {
+    id: u8,
+    h: usize,
+    v: usize,
+    quant: usize,
+}
+
+// Where the entropy-coded data starting at `pos` ends: the next marker that isn't stuffing or a
+// restart.
+fn scan_end(data: &[u8], mut pos: usize) -> Result<usize> {
+    loop {
+        let Some(at) = data[pos..].iter().position(|&b| b == 0xff) else { bail!("truncated") };
+        pos += at;
+        match data.get(pos + 1) {
+            None => bail!("truncated"),
+            Some(0 | 0xd0..=0xd7) => pos += 2,
+            Some(_) => return Ok(pos),
+        }
+    }
+}
+
+fn jpeg(data: &[u8]) -> Result<Decoded> {
+    let mut pos = 2;
+    let mut quant_dc = [0u16; 4];
+    let (mut dc_tables, mut ac_tables): ([Option<Huffman>; 4], [Option<Huffman>; 4]) = Default::default();
+    let mut frame: Option<(u32, u32, bool, Vec<Component>)> = None;
+    let mut restart_interval = 0;
+    let mut thumb = None;
+    loop {
+        ensure!(data.get(pos) == Some(&0xff), "expected a marker");
+        while data.get(pos) == Some(&0xff) {
+            pos += 1;
+        }
+        let Some(&marker) = data.get(pos) else { bail!("truncated") };
+        pos += 1;
+        match marker {
+            0xd9 => break,
+            0x01 | 0xd0..=0xd8 => continue,
+            _ => {}
+        }
+        let len = be16(data, pos)?;
+        let Some(segment) = data.get(pos + 2..pos + len).filter(|_| len >= 2) else { bail!("truncated") };
+        pos += len;
+        match marker {
+            0xdb => {
+                let mut at = 0;
+                while at < segment.len() {
+                    let (wide, table) = (segment[at] >> 4 == 1, (segment[at] & 3) as usize);
+                    quant_dc[table] = if wide { be16(segment, at + 1)? as u16 } else { *segment.get(at + 1).unwrap_or(&0) as u16 };
+                    at += 1 + if wide { 128 } else { 64 };
+                }
+                ensure!(at == segment.len(), "bad quantization table");
+            }
+            0xc4 => {
+                let mut at = 0;
+                while at < segment.len() {
+                    let Some(counts) = segment.get(at + 1..at + 17) else { bail!("bad Huffman table") };
+                    let n: usize = counts.iter().map(|&c| c as usize).sum();
+                    let Some(symbols) = segment.get(at + 17..at + 17 + n) else { bail!("bad Huffman table") };
+                    let table = Some(Huffman::new(counts, symbols));
+                    let id = (segment[at] & 3) as usize;
+                    if segment[at] >> 4 == 0 {
+                        dc_tables[id] = table;
+                    } else {
+                        ac_tables[id] = table;
+                    }
+                    at += 17 + n;
+                }
+            }
+            0xdd => restart_interval = be16(segment, 0)?,
+            0xc0..=0xcf if !matches!(marker, 0xc4 | 0xc8 | 0xcc) => {
+                ensure!(frame.is_none(), "more than one frame");
+                ensure!(segment.len() >= 6, "bad frame header");
+                let (height, width, n) = (be16(segment, 1)? as u32, be16(segment, 3)? as u32, segment[5] as usize);
+                ensure!(width > 0 && height > 0 && segment.len() == 6 + 3 * n, "bad frame header");
+                let components = segment[6..]
+                    .chunks(3)
+                    .map(|c| Component { id: c[0], h: (c[1] >> 4).max(1) as usize, v: (c[1] & 15).max(1) as usize, quant: (c[2] & 3) as usize })
+                    .collect();
+                // Only baseline and extended sequential Huffman-coded frames are summarized.
+                let sequential = matches!(marker, 0xc0 | 0xc1) && segment[0] == 8;
+                frame = Some((width, height, sequential, components));
+            }
+            0xda => {
+                let Some((width, height, sequential, components)) = &frame else { bail!("scan before frame header") };
+                let count = *segment.first().unwrap_or(&0) as usize;
+                ensure!(segment.len() > 2 * count, "bad scan header");
+                let selected: Vec<(usize, usize, usize)> = segment[1..1 + 2 * count]
+                    .chunks_exact(2)
+                    .filter_map(|s| Some((components.iter().position(|c| c.id == s[0])?, (s[1] >> 4) as usize & 3, s[1] as usize & 3)))
+                    .collect();
+                let all = selected.len() == components.len() && matches!(components.len(), 1 | 3);
+                if *sequential && all && thumb.is_none() && (*width as u64) * (*height as u64) <= MAX_PIXELS {
+                    let mut bits = Bits { data, pos, acc: 0, left: 0 };
+                    let tables = (&dc_tables, &ac_tables);
+                    thumb = jpeg_scan(&mut bits, (*width, *height), components, &selected, tables, &quant_dc, restart_interval)?;
+                    pos = bits.pos;
+                }
+                pos = scan_end(data, pos)?;
+            }
+            _ => {}
+        }
+    }
+    let Some((width, height, _, _)) = frame else { bail!("no frame header") };
+    Ok(Decoded { format: ImageFormat::Jpeg, width, height, thumb })
+}
+
+// Decodes one interleaved scan at 1/8 scale: each block contributes only its DC coefficient.
+fn jpeg_scan(
+    bits: &mut Bits,
+    (width, height): (u32, u32),
+    components: &[Component],
+    selected: &[(usize, usize, usize)],
+    (dc_tables, ac_tables): (&[Option<Huffman>; 4], &[Option<Huffman>; 4]),
+    quant_dc: &[u16; 4],
+    restart_interval: usize,
+) -> Result<Option<Thumbnail>> {
+    let (grid_w, grid_h) = (width.div_ceil(8), height.div_ceil(8));
+    // A single-component scan is not interleaved: one block per MCU whatever the sampling.
+    let (hmax, vmax) = match components.len() {
+        1 => (1, 1),
+        _ => (components.iter().map(|c| c.h).max().unwrap_or(1), components.iter().map(|c| c.v).max().unwrap_or(1)),
+    };
+    let sampling = |c: &Component| if components.len() == 1 { (1, 1) } else { (c.h, c.v) };
+    let (mcus_x, mcus_y) = (grid_w.div_ceil(hmax as u32), grid_h.div_ceil(vmax as u32));
+    let mut sink = Sink::new(grid_w, grid_h);
+    let mut predictors = vec![0i32; components.len()];
+    let mut blocks: Vec<Vec<i32>> = components.iter().map(|c| vec![0; sampling(c).0 * sampling(c).1]).collect();
+    for mcu in 0..mcus_x as usize * mcus_y as usize {
+        if restart_interval > 0 && mcu > 0 && mcu % restart_interval == 0 {
+            bits.restart()?;
+            predictors.iter_mut().for_each(|p| *p = 0);
+        }
+        for &(c, dc_id, ac_id) in selected {
+            let (Some(dc), Some(ac)) = (&dc_tables[dc_id], &ac_tables[ac_id]) else { bail!("missing Huffman table") };
+            for block in blocks[c].iter_mut() {
+                let size = dc.decode(bits)?;
+                ensure!(size <= 11, "bad DC coefficient");
+                let diff = bits.bits(size)?;
+                let diff = if size > 0 && diff < 1 << (size - 1) { diff - (1 << size) + 1 } else { diff };
+                predictors[c] = predictors[c].wrapping_add(diff);
+                *block = predictors[c].wrapping_mul(quant_dc[components[c].quant] as i32);
+                let mut k = 1;
+                while k < 64 {
+                    let rs = ac.decode(bits)?;
+                    match (rs >> 4, rs & 15) {
+                        (15, 0) => k += 16,
+                        (_, 0) => break,
+                        (run, size) => {
+                            bits.bits(size)?;
+                            k += run as usize + 1;
+                        }
+                    }
+                }
+            }
+        }
+        let (mx, my) = (mcu % mcus_x as usize, mcu / mcus_x as usize);
+        for j in 0..vmax {
+            for i in 0..hmax {
+                let (x, y) = ((mx * hmax + i) as u32, (my * vmax + j) as u32);
+                if x >= grid_w || y >= grid_h {
+                    continue;
+                }
+                // The DC coefficient is 8x the block's mean, level-shifted by 128.
+                let value = |c: usize| {
+                    let (h, v) = sampling(&components[c]);
+                    blocks[c][(j * v / vmax) * h + i * h / hmax] as f32 / 8.0 + 128.0
+                };
+                let rgb = if components.len() == 1 {
+                    [value(0).clamp(0.0, 255.0) as u8; 3]
+                } else {
+                    let (l, cb, cr) = (value(0), value(1) - 128.0, value(2) - 128.0);
+                    let clamp = |v: f32| v.clamp(0.0, 255.0) as u8;
+                    [clamp(l + 1.402 * cr), clamp(l - 0.344_136 * cb - 0.714_136 * cr), clamp(l + 1.772 * cb)]
+                };
+                sink.push(x, y, rgb);
+            }
+        }
+    }
+    Ok(sink.finish())
+}
+
+fn gif(data: &[u8]) -> Result<Decoded> {
+    let Some(screen) = data.get(6..13) else { bail!("truncated") };
+    let (width, height) = (u16::from_le_bytes([screen[0], screen[1]]) as u32, u16::from_le_bytes([screen[2], screen[3]]) as u32);
+    ensure!(width > 0 && height > 0, "bad screen descriptor");
+    let mut pos = 13;
+    let color_table = |pos: &mut usize, flags: u8| -> Result<&[u8]> {
+        if flags & 0x80 == 0 {
+            return Ok(&[]);
+        }
+        let len = 3 << ((flags & 7) + 1);
+        let Some(table) = data.get(*pos..*pos + len) else { bail!("truncated") };
+        *pos += len;
+        Ok(table)
+    };
+    let global = color_table(&mut pos, screen[4])?;
+    let mut thumb = None;
+    let mut frames = 0;
+    loop {
+        // Some encoders leave off the trailer; an image that ends after a whole frame is intact.
+        let block = match data.get(pos) {
+            Some(&block) => block,
+            None if frames > 0 => break,
+            None => bail!("truncated"),
+        };
+        pos += 1;
+        match block {
+            0x3b => break,
+            0x21 => {
+                pos += 1;
+                sub_blocks(data, &mut pos)?;
+            }
+            0x2c => {
+                let Some(desc) = data.get(pos..pos + 9) else { bail!("truncated") };
+                pos += 9;
+                let field = |at: usize| u16::from_le_bytes([desc[at], desc[at + 1]]) as u32;
+                let (left, top, w, h) = (field(0), field(2), field(4), field(6));
+                let local = color_table(&mut pos, desc[8])?;
+                let Some(&min_code) = data.get(pos) else { bail!("truncated") };
+                pos += 1;
+                let lzw_data = sub_blocks(data, &mut pos)?;
+                frames += 1;
+                if frames > 1 || w as u64 * h as u64 > MAX_PIXELS {
+                    continue;
+                }
+                // The first frame stands for the image.
+                let table = if local.is_empty() { global } else { local };
+                ensure!(!table.is_empty(), "no color table");
+                let indices = lzw(&lzw_data, min_code, (w * h) as usize)?;
+                let rows: Vec<u32> = if desc[8] & 0x40 != 0 {
+                    [(0, 8), (4, 8), (2, 4), (1, 2)].iter().flat_map(|&(start, step)| (start..h).step_by(step)).collect()
+                } else {
+                    (0..h).collect()
+                };
+                let mut sink = Sink::new(width, height);
+                for (i, &index) in indices.iter().enumerate() {
+                    let (x, y) = (left + i as u32 % w, top + rows[i / w as usize]);
+                    if x < width && y < height {
+                        let at = index as usize * 3;
+                        let Some(rgb) = table.get(at..at + 3) else { bail!("color index out of range") };
+                        sink.push(x, y, [rgb[0], rgb[1], rgb[2]]);
+                    }
+                }
+                thumb = sink.finish();
+            }
+            b => bail!("unexpected block 0x{:02x}", b),
+        }
+    }
+    ensure!(frames > 0, "no image data");
+    Ok(Decoded { format: ImageFormat::Gif, width, height, thumb })
+}
+
+fn sub_blocks(data: &[u8], pos: &mut usize) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    loop {
+        let Some(&len) = data.get(*pos) else { bail!("truncated") };
+        *pos += 1;
+        if len == 0 {
+            return Ok(out);
+        }
+        let Some(block) = data.get(*pos..*pos + len as usize) else { bail!("truncated") };
+        out.extend_from_slice(block);
+        *pos += len as usize;
+    }
+}
+
+fn lzw(data: &[u8], min_code: u8, pixels: usize) -> Result<Vec<u8>> {
+    ensure!((2..=8).contains(&min_code), "bad LZW code size");
+    let (clear, end) = (1u16 << min_code, (1u16 << min_code) + 1);
+    let mut prefix = [0u16; 4096];
+    let mut suffix = [0u8; 4096];
+    let mut first = [0u8; 4096];
+    for i in 0..clear {
+        (suffix[i as usize], first[i as usize]) = (i as u8, i as u8);
+    }
+    let (mut next, mut size, mut prev): (u16, u32, Option<u16>) = (end + 1, min_code as u32 + 1, None);
+    let (mut acc, mut held, mut at) = (0u32, 0u32, 0usize);
+    let mut out = Vec::with_capacity(pixels);
+    let mut stack = Vec::new();
+    while out.len() < pixels {
+        while held < size {
+            let Some(&byte) = data.get(at) else { bail!("image data ends early") };
+            acc |= (byte as u32) << held;
+            (held, at) = (held + 8, at + 1);
+        }
+        let code = (acc & ((1 << size) - 1)) as u16;
+        (acc, held) = (acc >> size, held - size);
+        if code == clear {
+            (next, size, prev) = (end + 1, min_code as u32 + 1, None);
+            continue;
+        }
+        if code == end {
+            break;
+        }
+        ensure!(code <= next && (prev.is_some() || code < clear), "bad LZW code");
+        if let Some(p) = prev.filter(|_| next < 4096) {
+            // A code not yet in the table is the previous string plus its own first byte.
+            let tail = if code == next { first[p as usize] } else { first[code as usize] };
+            (prefix[next as usize], suffix[next as usize], first[next as usize]) = (p, tail, first[p as usize]);
+            next += 1;
+            if next == 1 << size && size < 12 {
+                size += 1;
+            }
+        }
+        let mut c = code;
+        stack.clear();
+        while c > end {
+            stack.push(suffix[c as usize]);
+            c = prefix[c as usize];
+        }
+        stack.push(c as u8);
+        out.extend(stack.iter().rev());
+        prev = Some(code);
+    }
+    ensure!(out.len() >= pixels, "image data ends early");
+    out.truncate(pixels);
+    Ok(out)
+}
+
+// An 8-bit RGB PNG, for tests here and in image_validator.
+#[cfg(test)]
+pub fn test_png(width: u32, height: u32, pixel: impl Fn(u32, u32) -> [u8; 3]) -> Vec<u8> {
+    use std::io::Write;
+    let mut raw = Vec::new();
+    for y in 0..height {
+        raw.push(0);
+        (0..width).for_each(|x| raw.extend_from_slice(&pixel(x, y)));
+    }
+    let mut idat = flate2::write::ZlibEncoder::new(Vec::new(), flate2::Compression::fast());
+    idat.write_all(&raw).unwrap();
+    let mut ihdr = [width.to_be_bytes(), height.to_be_bytes()].concat();
+    ihdr.extend_from_slice(&[8, 2, 0, 0, 0]);
+    let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+    for (kind, body) in [(b"IHDR", ihdr), (b"IDAT", idat.finish().unwrap()), (b"IEND", Vec::new())] {
+        let mut crc = flate2::Crc::new();
+        crc.update(kind);
+        crc.update(&body);
+        png.extend_from_slice(&(body.len() as u32).to_be_bytes());
+        png.extend_from_slice(kind);
+        png.extend_from_slice(&body);
+        png.extend_from_slice(&crc.sum().to_be_bytes());
+    }
+    png
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 32x16 baseline JPEG, 4:2:0, DC only: a light and a dark row of 8x8 blocks in a warm
+    // left MCU, mid gray tinted blue on the right.
+    const JPEG: &str = "ffd8ffdb00430008010101010101010101010101010101010101010101010101010101010101010101010101010101010101\
+        010101010101010101010101010101010101010101ffc00011080010002003012200021100031100ffc40060000001050101\
+        0101010100000000000000000102030405060708090a0b010003010101010101010101000000000000010203040506070809\
+        0a0b100001000000000000000000000000000000110001000000000000000000000000000000ffda000c0301000211031100\
+        3f00f4803e7303e5cfbc3d10000fd90fc6cfffd9";
+    // 4x4 GIF, a quarter each black, red, blue and white.
+    const GIF: &str = "47494638396104000400810000000000ff00000000ffffffff2c000000000400040000020a44a831a1c48d00156205003b";
+
+    #[test]
+    fn test_decode_formats() {
+        // A left-to-right gradient: every horizontal step in the hash grid gets brighter.
+        let png = test_png(64, 32, |x, _| [x as u8 * 4; 3]);
+        let decoded = decode(&png).unwrap();
+        assert_eq!((decoded.format, decoded.width, decoded.height), (ImageFormat::Png, 64, 32));
+        assert_eq!(decoded.thumb.unwrap().dhash, u64::MAX);
+        assert!(decode(&png[..png.len() - 20]).is_err());
+        let mut damaged = png.clone();
+        damaged[60] ^= 1;
+        assert_eq!(decode(&damaged).unwrap_err().to_string(), "chunk checksum mismatch");
+
+        let jpeg = hex::decode(JPEG).unwrap();
+        let decoded = decode(&jpeg).unwrap();
+        assert_eq!((decoded.format, decoded.width, decoded.height), (ImageFormat::Jpeg, 32, 16));
+        let histogram = decoded.thumb.unwrap().histogram;
+        assert_eq!((histogram[27], histogram[32], histogram[58]), (0.5, 0.25, 0.25));
+        assert!(decode(&jpeg[..jpeg.len() - 2]).is_err());
+
+        let decoded = decode(&hex::decode(GIF).unwrap()).unwrap();
+        assert_eq!((decoded.format, decoded.width, decoded.height), (ImageFormat::Gif, 4, 4));
+        let histogram = decoded.thumb.unwrap().histogram;
+        assert!([0b000000, 0b110000, 0b000011, 0b111111].iter().all(|&bin| histogram[bin] == 0.25));
+        assert!(decode(b"GIF89a\x04\0\x04\0\0\0\0,").is_err());
+    }
+
+    #[test]
+    fn test_short_scan_headers() {
+        // A scan header with no selector count, and one whose count exceeds its selectors.
+        let frame = "ffd8ffc0000b080010002001010100";
+        for scan in ["ffda0002", "ffda0004020111", "ffda0005020111"] {
+            let jpeg = hex::decode(format!("{}{}ffd9", frame, scan)).unwrap();
+            assert_eq!(decode(&jpeg).unwrap_err().to_string(), "bad scan header");
+        }
+        // A 1-pixel-high PNG too wide for a row buffer is measured but not summarized.
+        let decoded = decode(&test_png(MAX_PNG_WIDTH + 1, 1, |_, _| [0; 3])).unwrap();
+        assert_eq!((decoded.width, decoded.thumb), (MAX_PNG_WIDTH + 1, None));
+    }
+
+    #[test]
+    fn test_hostile_input_never_panics() {
+        let samples = [test_png(12, 7, |x, y| [x as u8 * 20, y as u8 * 30, 90]), hex::decode(JPEG).unwrap(), hex::decode(GIF).unwrap()];
+        let mut state = 0x2545_f491_4f6c_dd1du64;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+            state
+        };
+        for sample in &samples {
+            for len in 0..sample.len() {
+                let _ = decode(&sample[..len]);
+            }
+            for _ in 0..3000 {
+                let mut mutated = sample.clone();
+                for _ in 0..1 + next() % 4 {
+                    let at = next() as usize % mutated.len();
+                    mutated[at] = match next() % 3 {
+                        0 => mutated[at] ^ 1 << (next() % 8),
+                        1 => 0xff,
+                        _ => next() as u8,
+                    };
+                }
+                let _ = decode(&mutated);
+                let _ = decode(&mutated[..next() as usize % mutated.len()]);
+            }
+        }
+    }
+}
@@ -0,0 +1,527 @@
+use flate2::write::MultiGzDecoder;
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+use crate::content_policy::DetectedType;
+use crate::image_decode::{self, ImageFormat, Thumbnail, HIST_BINS};
+use crate::quality_validator::SharedProfile;
+
+// Computer-vision datasets: a single image, or a tar archive (optionally gzipped) of them. Each
+// image is decoded (see image_decode) and the dataset scored on what CV consumers care about:
+//   completeness  share of image files that decode (the rest are corrupt or cut off)
+//   authenticity  share that aren't near-duplicates of an earlier image by perceptual hash
+//   diversity     spread of color histograms, across the dataset and between its images
+//   consistency   share of images of usable resolution near the dataset's typical size
+// Other archive members (labels, manifests) are counted but not scored; archives that aren't tar,
+// and tars without images, fall back to the byte-level checks.
+
+// Larger images are counted but not decoded.
+const MAX_IMAGE_BYTES: usize = 64 * 1024 * 1024;
+// Images whose hash, histogram and size are kept for dataset-level statistics.
+const MAX_TRACKED: usize = 100_000;
+// Hashes this many bits apart or fewer are near-duplicates. Split into four 16-bit bands, two
+// such hashes share at least one band, so candidates are found without comparing every pair.
+const NEAR_DUPLICATE_BITS: u32 = 3;
+const BANDS: u32 = 4;
+// Images with a shorter side are too small to train on.
+const MIN_SIDE: u32 = 32;
+// Pixel counts within this factor of the median are the dataset's typical size.
+const SIZE_BAND: f64 = 4.0;
+const IMAGE_EXTENSIONS: &[&str] = &["png", "jpg", "jpeg", "gif"];
+const REPORTED_ERRORS: usize = 5;
+const BLOCK: usize = 512;
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ResolutionStats {
+    pub min: String,
+    pub median: String,
+    pub max: String,
+    // By longer side: tiny < 64 <= small < 256 <= medium < 1024 <= large.
+    pub buckets: BTreeMap<&'static str, u64>,
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct ImageReport {
+    pub images: u64,
+    pub decoded: u64,
+    pub corrupt: u64,
+    pub too_large: u64,
+    pub other_files: u64,
+    pub formats: BTreeMap<&'static str, u64>,
+    pub near_duplicates: u64,
+    // Archive cut off mid-member or with a damaged header.
+    #[serde(skip_serializing_if = "std::ops::Not::not")]
+    pub truncated: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub resolution: Option<ResolutionStats>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+#[derive(Default)]
+struct Images {
+    report: ImageReport,
+    sizes: Vec<(u32, u32)>,
+    thumbs: Vec<Thumbnail>,
+    bands: HashMap<(u32, u16), Vec<u32>>,
+}
+
+impl Images {
+    fn add(&mut self, name: &str, data: &[u8], cut: bool) {
+        let report = &mut self.report;
+        report.images += 1;
+        if cut {
+            report.too_large += 1;
+            return;
+        }
+        let decoded = match image_decode::decode(data) {
+            Ok(decoded) => decoded,
+            Err(err) => {
+                report.corrupt += 1;
+                if report.errors.len() < REPORTED_ERRORS {
+                    report.errors.push(format!("{}: {}", name, err));
+                }
+                return;
+            }
+        };
+        report.decoded += 1;
+        *report.formats.entry(decoded.format.as_str()).or_default() += 1;
+        if self.sizes.len() >= MAX_TRACKED {
+            return;
+        }
+        self.sizes.push((decoded.width, decoded.height));
+        let Some(thumb) = decoded.thumb else { return };
+        let band = |hash: u64, b: u32| (b, (hash >> (16 * b)) as u16);
+        let near = (0..BANDS).filter_map(|b| self.bands.get(&band(thumb.dhash, b))).flatten().any(|&i| {
+            (self.thumbs[i as usize].dhash ^ thumb.dhash).count_ones() <= NEAR_DUPLICATE_BITS
+        });
+        if near {
+            report.near_duplicates += 1;
+        }
+        for b in 0..BANDS {
+            self.bands.entry(band(thumb.dhash, b)).or_default().push(self.thumbs.len() as u32);
+        }
+        self.thumbs.push(thumb);
+    }
+
+    fn finish(&mut self) {
+        let sizes = &mut self.sizes;
+        if sizes.is_empty() {
+            return;
+        }
+        sizes.sort_by_key(|&(w, h)| w as u64 * h as u64);
+        let show = |(w, h): (u32, u32)| format!("{}x{}", w, h);
+        let mut buckets = BTreeMap::new();
+        for &(w, h) in sizes.iter() {
+            let bucket = match w.max(h) {
+                0..64 => "tiny",
+                64..256 => "small",
+                256..1024 => "medium",
+                _ => "large",
+            };
+            *buckets.entry(bucket).or_default() += 1;
+        }
+        self.report.resolution = Some(ResolutionStats {
+            min: show(sizes[0]),
+            median: show(sizes[sizes.len() / 2]),
+            max: show(sizes[sizes.len() - 1]),
+            buckets,
+        });
+    }
+
+    fn completeness(&self) -> Option<u32> {
+        let r = &self.report;
+        let judged = r.images - r.too_large;
+        (judged > 0).then(|| (r.decoded as f64 * 100.0 / judged as f64).round() as u32)
+    }
+
+    fn authenticity(&self) -> Option<u32> {
+        let hashed = self.thumbs.len() as f64;
+        (hashed > 0.0).then(|| ((1.0 - self.report.near_duplicates as f64 / hashed) * 100.0).round() as u32)
+    }
+
+    // Half how many colors the dataset uses overall (histogram entropy), half how far images sit
+    // from the average histogram (L1 distance, capped at 1); a lone image scores the first half.
+    fn diversity(&self) -> Option<u32> {
+        let n = self.thumbs.len();
+        if n == 0 {
+            return None;
+        }
+        let mut mean = [0f64; HIST_BINS];
+        for thumb in &self.thumbs {
+            mean.iter_mut().zip(&thumb.histogram).for_each(|(m, &h)| *m += h as f64 / n as f64);
+        }
+        let entropy = -mean.iter().filter(|&&p| p > 0.0).map(|&p| p * p.ln()).sum::<f64>() / (HIST_BINS as f64).ln();
+        if n == 1 {
+            return Some((entropy.clamp(0.0, 1.0) * 100.0).round() as u32);
+        }
+        let spread = self
+            .thumbs
+            .iter()
+            .map(|t| t.histogram.iter().zip(&mean).map(|(&h, &m)| (h as f64 - m).abs()).sum::<f64>())
+            .sum::<f64>()
+            / n as f64;
+        Some(((0.5 * entropy + 0.5 * spread.min(1.0)).clamp(0.0, 1.0) * 100.0).round() as u32)
+    }
+
+    fn consistency(&self) -> Option<u32> {
+        // `finish` sorted the sizes by pixel count.
+        let &(w, h) = self.sizes.get(self.sizes.len() / 2)?;
+        let median = w as f64 * h as f64;
+        let usable = self
+            .sizes
+            .iter()
+            .filter(|&&(w, h)| {
+                let ratio = w as f64 * h as f64 / median;
+                w.min(h) >= MIN_SIDE && (1.0 / SIZE_BAND..=SIZE_BAND).contains(&ratio)
+            })
+            .count();
+        Some((usable as f64 * 100.0 / self.sizes.len() as f64).round() as u32)
+    }
+}
+
+enum TarState {
+    Header,
+    // A member's data and the padding to the next block.
+    Member { left: u64, padding: u64 },
+    End,
+    Invalid,
+}
+
+struct Member {
+    name: String,
+    kind: u8,
+    data: Vec<u8>,
+    // Still deciding whether it is an image (None), or its data is kept or skipped.
+    keep: Option<bool>,
+    cut: bool,
+}
+
+// Walks a tar stream as it is written, handing image members to `images`.
+struct TarReader {
+    state: TarState,
+    header: Vec<u8>,
+    member: Option<Member>,
+    // Name for the next member from a GNU long-name or pax header.
+    next_name: Option<String>,
+    members: u64,
+    images: Images,
+}
+
+fn octal(field: &[u8]) -> Option<u64> {
+    if field[0] & 0x80 != 0 {
+        // GNU base-256 for sizes past 8 GiB.
+        return Some(field[1..].iter().fold(0u64, |v, &b| v << 8 | b as u64));
+    }
+    let text = std::str::from_utf8(field).ok()?.trim_matches(|c: char| c == '\0' || c == ' ');
+    if text.is_empty() {
+        return Some(0);
+    }
+    u64::from_str_radix(text, 8).ok()
+}
+
+fn field_str(field: &[u8]) -> String {
+    let end = field.iter().position(|&b| b == 0).unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).into_owned()
+}
+
+impl TarReader {
+    fn new() -> Self {
+        Self { state: TarState::Header, header: Vec::new(), member: None, next_name: None, members: 0, images: Images::default() }
+    }
+
+    fn feed(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            match &mut self.state {
+                TarState::Header => {
+                    let take = data.len().min(BLOCK - self.header.len());
+                    self.header.extend_from_slice(&data[..take]);
+                    data = &data[take..];
+                    if self.header.len() == BLOCK {
+                        self.read_header();
+                    }
+                }
+                TarState::Member { left, padding } => {
+                    let take = (data.len() as u64).min(*left + *padding) as usize;
+                    let content = (take as u64).min(*left) as usize;
+                    *left -= content as u64;
+                    *padding -= (take - content) as u64;
+                    let done = *left == 0 && *padding == 0;
+                    if let Some(member) = self.member.as_mut() {
+                        member.take(&data[..content]);
+                    }
+                    data = &data[take..];
+                    if done {
+                        self.state = TarState::Header;
+                        self.end_member();
+                    }
+                }
+                TarState::End | TarState::Invalid => return,
+            }
+        }
+    }
+
+    fn read_header(&mut self) {
+        let header = std::mem::take(&mut self.header);
+        if header.iter().all(|&b| b == 0) {
+            self.state = TarState::End;
+            return;
+        }
+        let sum: u64 = header.iter().enumerate().map(|(i, &b)| if (148..156).contains(&i) { b' ' as u64 } else { b as u64 }).sum();
+        let (Some(size), Some(checksum)) = (octal(&header[124..136]), octal(&header[148..156])) else {
+            self.state = TarState::Invalid;
+            return;
+        };
+        if checksum != sum {
+            self.state = TarState::Invalid;
+            return;
+        }
+        self.members += 1;
+        let mut name = field_str(&header[..100]);
+        if &header[257..262] == b"ustar" && header[345] != 0 {
+            name = format!("{}/{}", field_str(&header[345..500]), name);
+        }
+        let name = self.next_name.take().unwrap_or(name);
+        let kind = header[156];
+        self.member = Some(Member { name, kind, data: Vec::new(), keep: None, cut: false });
+        let padding = (BLOCK as u64 - size % BLOCK as u64) % BLOCK as u64;
+        self.state = TarState::Member { left: size, padding };
+        if size == 0 {
+            self.state = TarState::Header;
+            self.end_member();
+        }
+    }
+
+    fn end_member(&mut self) {
+        let Some(member) = self.member.take() else { return };
+        match member.kind {
+            b'L' => self.next_name = Some(field_str(&member.data)),
+            b'x' => {
+                let text = String::from_utf8_lossy(&member.data);
+                self.next_name = text.lines().find_map(|l| l.split_once(" path=").map(|(_, p)| p.to_string()));
+            }
+            b'0' | b'\0' | b'7' => {
+                if member.keep == Some(true) || member.is_image() {
+                    self.images.add(&member.name, &member.data, member.cut);
+                } else {
+                    self.images.report.other_files += 1;
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl Member {
+    fn is_image(&self) -> bool {
+        let ext = self.name.rsplit_once('.').map(|(_, e)| e.to_ascii_lowercase());
+        ImageFormat::sniff(&self.data).is_some() || ext.is_some_and(|e| IMAGE_EXTENSIONS.contains(&e.as_str()))
+    }
+
+    fn take(&mut self, data: &[u8]) {
+        if self.keep == Some(false) || self.cut {
+            return;
+        }
+        // Names and pax records are always kept; file data once it looks like an image.
+        if self.keep.is_none() && matches!(self.kind, b'0' | b'\0' | b'7') && self.data.len() + data.len() >= 8 {
+            self.data.extend_from_slice(data);
+            self.keep = Some(self.is_image());
+            if self.keep == Some(false) {
+                self.data = Vec::new();
+            }
+        } else {
+            self.data.extend_from_slice(data);
+        }
+        if self.data.len() > MAX_IMAGE_BYTES {
+            self.cut = true;
+            self.data = Vec::new();
+        }
+    }
+}
+
+impl Write for TarReader {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        self.feed(data);
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        Ok(())
+    }
+}
+
+enum Input {
+    // Not yet started: the first bytes pick tar, gzipped tar or a single image.
+    Pending,
+    Single { data: Vec<u8>, cut: bool },
+    Tar(Box<TarReader>),
+    Gzip(Box<MultiGzDecoder<TarReader>>),
+}
+
+pub struct ImageProfile {
+    format: DetectedType,
+    input: Input,
+    inflate_failed: bool,
+    images: Option<Images>,
+}
+
+impl ImageProfile {
+    pub fn new(format: DetectedType) -> Self {
+        Self { format, input: Input::Pending, inflate_failed: false, images: None }
+    }
+
+    fn tar(&self) -> Option<&TarReader> {
+        match &self.input {
+            Input::Tar(tar) => Some(tar),
+            Input::Gzip(gz) => Some(gz.get_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl SharedProfile for ImageProfile {
+    fn update(&mut self, data: &[u8]) {
+        if matches!(self.input, Input::Pending) {
+            self.input = match self.format {
+                DetectedType::Image => Input::Single { data: Vec::new(), cut: false },
+                _ if data.starts_with(b"\x1f\x8b") => Input::Gzip(Box::new(MultiGzDecoder::new(TarReader::new()))),
+                _ => Input::Tar(Box::new(TarReader::new())),
+            };
+        }
+        match &mut self.input {
+            Input::Single { data: buf, cut } if !*cut => {
+                buf.extend_from_slice(data);
+                if buf.len() > MAX_IMAGE_BYTES {
+                    (*buf, *cut) = (Vec::new(), true);
+                }
+            }
+            Input::Tar(tar) => tar.feed(data),
+            Input::Gzip(gz) if !self.inflate_failed => self.inflate_failed = gz.write_all(data).is_err(),
+            _ => {}
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.images.is_some() {
+            return;
+        }
+        let mut images = match &mut self.input {
+            Input::Single { data, cut } => {
+                let mut images = Images::default();
+                images.add("image", &std::mem::take(data), *cut);
+                images
+            }
+            Input::Tar(_) | Input::Gzip(_) => {
+                let inflated = match &mut self.input {
+                    Input::Gzip(gz) => !self.inflate_failed && gz.try_finish().is_ok(),
+                    _ => true,
+                };
+                let tar = match &mut self.input {
+                    Input::Gzip(gz) => gz.get_mut(),
+                    Input::Tar(tar) => tar,
+                    _ => unreachable!(),
+                };
+                let mut images = std::mem::take(&mut tar.images);
+                // No members at all means it wasn't a tar: nothing is scored.
+                images.report.truncated = tar.members > 0 && (!inflated || !matches!(tar.state, TarState::End));
+                images
+            }
+            Input::Pending => Images::default(),
+        };
+        images.finish();
+        self.images = Some(images);
+    }
+
+    fn score(&self, check: &str) -> Option<u32> {
+        let images = self.images.as_ref()?;
+        if images.report.images == 0 {
+            return None;
+        }
+        match check {
+            "completeness" => images.completeness(),
+            "authenticity" => images.authenticity(),
+            "diversity" => images.diversity(),
+            "consistency" if images.report.truncated => Some(0),
+            "consistency" => images.consistency(),
+            _ => None,
+        }
+    }
+
+    fn state_bytes(&self) -> u64 {
+        let held = match &self.input {
+            Input::Single { data, .. } => data.capacity(),
+            _ => self.tar().and_then(|t| t.member.as_ref()).map_or(0, |m| m.data.capacity()),
+        };
+        let tracked = self.images.as_ref().or(self.tar().map(|t| &t.images)).map_or(0, |i| i.thumbs.len());
+        (held + tracked * std::mem::size_of::<Thumbnail>()) as u64
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        let images = self.images.as_ref().filter(|i| i.report.images > 0)?;
+        serde_json::to_value(&images.report).ok().map(|v| ("images", v))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::image_decode::test_png;
+    use crate::quality_validator::{validate_dataset_quality, ValidationOptions};
+    use std::io::Write;
+
+    fn tar(members: &[(&str, Vec<u8>)]) -> Vec<u8> {
+        let mut out = Vec::new();
+        for (name, data) in members {
+            let mut header = [0u8; 512];
+            header[..name.len()].copy_from_slice(name.as_bytes());
+            header[124..135].copy_from_slice(format!("{:011o}", data.len()).as_bytes());
+            header[148..156].fill(b' ');
+            header[156] = b'0';
+            header[257..262].copy_from_slice(b"ustar");
+            let sum: u32 = header.iter().map(|&b| b as u32).sum();
+            header[148..155].copy_from_slice(format!("{:06o}\0", sum).as_bytes());
+            out.extend_from_slice(&header);
+            out.extend_from_slice(data);
+            out.resize(out.len().div_ceil(512) * 512, 0);
+        }
+        out.extend_from_slice(&[0; 1024]);
+        out
+    }
+
+    #[test]
+    fn test_image_archive() {
+        let archive = tar(&[
+            ("a.png", test_png(64, 64, |x, _| [x as u8 * 4; 3])),
+            // The same gradient a touch brighter: a near-duplicate of a.png.
+            ("b.png", test_png(64, 64, |x, _| [x as u8 * 4 + 3; 3])),
+            ("c.png", test_png(64, 48, |x, y| [y as u8 * 5, 0, ((x / 8) % 3 * 120) as u8])),
+            ("d.png", test_png(48, 64, |x, y| if (x / 8 + y / 8) % 2 == 0 { [250, 200, 0] } else { [0, 90, 40] })),
+            ("tiny.png", test_png(8, 8, |_, _| [128, 128, 128])),
+            ("broken.jpg", b"\xff\xd8\xff\xe0 not really a JPEG".to_vec()),
+            ("labels.csv", b"file,label\na.png,cat\n".to_vec()),
+        ]);
+        let report = validate_dataset_quality(&archive, &ValidationOptions::default()).unwrap();
+        let (b, details) = (&report.breakdown, &report.details["images"]);
+        // 5 of 6 images decode, 1 of 5 is a near-duplicate, 4 of 5 are of usable, typical size.
+        assert_eq!((b.completeness, b.authenticity, b.consistency), (Some(83), Some(80), Some(80)));
+        assert!(b.diversity.is_some_and(|d| d > 30 && d < 90));
+        assert_eq!((details["images"].as_u64(), details["other_files"].as_u64()), (Some(6), Some(1)));
+        assert!(details["errors"][0].as_str().unwrap().starts_with("broken.jpg: "));
+        assert_eq!((&details["resolution"]["min"], &details["resolution"]["max"]), (&"8x8".into(), &"64x64".into()));
+
+        // The same archive gzipped scores the same images; cut off, its consistency is gone.
+        let mut gz = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::fast());
+        gz.write_all(&archive).unwrap();
+        let gz = gz.finish().unwrap();
+        let unzipped = validate_dataset_quality(&gz, &ValidationOptions::default()).unwrap();
+        let images = |b: &crate::quality_validator::QualityBreakdown| (b.diversity, b.authenticity, b.completeness, b.consistency);
+        assert_eq!(images(&unzipped.breakdown), images(&report.breakdown));
+        let cut = validate_dataset_quality(&gz[..gz.len() * 2 / 3], &ValidationOptions::default()).unwrap();
+        assert_eq!((cut.breakdown.consistency, &cut.details["images"]["truncated"]), (Some(0), &serde_json::json!(true)));
+
+        // No images: the byte-level checks stand in.
+        let labels = tar(&[("labels.csv", b"file,label\n".repeat(100))]);
+        let report = validate_dataset_quality(&labels, &ValidationOptions::default()).unwrap();
+        assert!(!report.details.contains_key("images") && report.breakdown.authenticity.is_some());
+    }
+}
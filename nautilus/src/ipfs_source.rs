@@ -0,0 +1,646 @@
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use reqwest::header::ACCEPT;
+use reqwest::{Client, StatusCode};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::{sleep, timeout};
+use tracing::{info, warn};
+
+use crate::blob_source::BlobSource;
+use crate::integrity::ContentMismatch;
+use crate::walrus_client::{AggregatorPool, BlobMetadata, BlobRange, RetryPolicy, WalrusConfig, WalrusError};
+
+// IPFS datasets, fetched by CID (`ipfs://<cid>`) through the gateways in NAUTILUS_IPFS_GATEWAYS.
+// Gateways share the Walrus aggregator semantics: a health-scored pool with cooldown, the same
+// selection strategy and the same retry/backoff policy. Gateways aren't trusted, so nothing is
+// requested as a rendered file: every block is fetched raw (trustless gateway, `?format=raw`),
+// checked against its CID, and only then decoded, so a gateway serving other bytes is failed
+// over like an unreachable one. Files are walked as UnixFS DAGs (dag-pb nodes, raw leaves) in
+// order, and ranged reads use the nodes' block sizes to skip subtrees outside the range.
+// Only sha2-256 CIDs naming a single file are supported; directories and paths are refused.
+
+const RAW: u64 = 0x55;
+const DAG_PB: u64 = 0x70;
+const SHA2_256: u64 = 0x12;
+// Larger than any block the IPFS network will exchange (2 MiB) with room to spare.
+const MAX_BLOCK_BYTES: u64 = 4 * 1024 * 1024;
+
+#[derive(Debug, Clone, Default)]
+pub struct IpfsConfig {
+    // An empty list disables the backend.
+    pub gateways: Vec<String>,
+}
+
+impl IpfsConfig {
+    pub fn from_env() -> Self {
+        Self {
+            gateways: env::var("NAUTILUS_IPFS_GATEWAYS")
+                .unwrap_or_default()
+                .split(',')
+                .map(|g| g.trim().trim_end_matches('/').to_string())
+                .filter(|g| !g.is_empty())
+                .collect(),
+        }
+    }
+}
+
+// A content identifier: CIDv0 (`Qm...`) or CIDv1 in base32 (`b...`) or base58btc (`z...`).
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Cid {
+    codec: u64,
+    digest: [u8; 32],
+}
+
+impl Cid {
+    fn parse(raw: &str) -> Result<Self> {
+        let bytes = if raw.len() == 46 && raw.starts_with("Qm") {
+            base58_decode(raw)
+        } else if let Some(rest) = raw.strip_prefix('b') {
+            base32_decode(rest)
+        } else if let Some(rest) = raw.strip_prefix('z') {
+            base58_decode(rest)
+        } else {
+            None
+        };
+        bytes
+            .context("CID is not base32 or base58btc")
+            .and_then(|b| Self::from_bytes(&b))
+            .with_context(|| format!("invalid CID {}", raw))
+    }
+
+    fn from_bytes(bytes: &[u8]) -> Result<Self> {
+        // A bare sha2-256 multihash is a CIDv0, always dag-pb.
+        if bytes.len() == 34 && bytes[0] == SHA2_256 as u8 && bytes[1] == 32 {
+            return Ok(Self { codec: DAG_PB, digest: bytes[2..].try_into()? });
+        }
+        let mut pos = 0;
+        anyhow::ensure!(read_varint(bytes, &mut pos)? == 1, "unsupported CID version");
+        let codec = read_varint(bytes, &mut pos)?;
+        anyhow::ensure!(codec == RAW || codec == DAG_PB, "unsupported codec 0x{:x}", codec);
+        anyhow::ensure!(read_varint(bytes, &mut pos)? == SHA2_256, "only sha2-256 CIDs are supported");
+        anyhow::ensure!(read_varint(bytes, &mut pos)? == 32 && bytes.len() == pos + 32, "bad multihash length");
+        Ok(Self { codec, digest: bytes[pos..].try_into()? })
+    }
+}
+
+// Always rendered as CIDv1 base32, which every gateway accepts.
+impl fmt::Display for Cid {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut bytes = vec![1];
+        write_varint(&mut bytes, self.codec);
+        bytes.extend_from_slice(&[SHA2_256 as u8, 32]);
+        bytes.extend_from_slice(&self.digest);
+        write!(f, "b{}", base32_encode(&bytes))
+    }
+}
+
+// `ipfs://<cid>`; paths into directories aren't resolved.
+fn parse_ipfs_ref(blob_id: &str) -> Result<Cid> {
+    let cid = blob_id
+        .strip_prefix("ipfs://")
+        .context("IPFS blob IDs look like ipfs://<cid>")?
+        .trim_end_matches('/');
+    anyhow::ensure!(!cid.contains('/'), "IPFS paths are not supported; reference the file's own CID");
+    Cid::parse(cid)
+}
+
+// One verified block, decoded: its inline file bytes and children with their file sizes.
+struct Node {
+    data: Bytes,
+    links: Vec<(Cid, u64)>,
+    filesize: u64,
+}
+
+impl Node {
+    fn decode(cid: &Cid, block: Bytes) -> Result<Self> {
+        if cid.codec == RAW {
+            let filesize = block.len() as u64;
+            return Ok(Self { data: block, links: Vec::new(), filesize });
+        }
+        let mut links = Vec::new();
+        let mut unixfs = None;
+        for (field, value) in protobuf_fields(&block)? {
+            match (field, value) {
+                (1, Field::Bytes(data)) => unixfs = Some(data),
+                // PBLink: Hash = 1.
+                (2, Field::Bytes(link)) => {
+                    let hash = protobuf_fields(link)?.into_iter().find_map(|f| match f {
+                        (1, Field::Bytes(hash)) => Some(hash),
+                        _ => None,
+                    });
+                    links.push(Cid::from_bytes(hash.context("dag-pb link without a hash")?)?);
+                }
+                _ => {}
+            }
+        }
+        // UnixFS Data: Type = 1, Data = 2, filesize = 3, blocksizes = 4.
+        let (mut kind, mut data, mut filesize, mut blocksizes) = (None, Bytes::new(), None, Vec::new());
+        for (field, value) in protobuf_fields(unixfs.context("dag-pb node without UnixFS data")?)? {
+            match (field, value) {
+                (1, Field::Varint(v)) => kind = Some(v),
+                (2, Field::Bytes(bytes)) => data = block.slice_ref(bytes),
+                (3, Field::Varint(v)) => filesize = Some(v),
+                (4, Field::Varint(v)) => blocksizes.push(v),
+                _ => {}
+            }
+        }
+        // 0 = Raw, 2 = File; directories, symlinks and HAMT shards aren't datasets.
+        anyhow::ensure!(matches!(kind, Some(0) | Some(2)), "CID is not a file (UnixFS type {:?})", kind);
+        anyhow::ensure!(blocksizes.len() == links.len(), "malformed UnixFS file node");
+        // Ranged reads place each child by these sizes, so they must add up to the file's.
+        let held = blocksizes.iter().try_fold(data.len() as u64, |sum, &size| sum.checked_add(size));
+        let held = held.context("malformed UnixFS file node: block sizes overflow")?;
+        let filesize = filesize.unwrap_or(held);
+        anyhow::ensure!(filesize == held, "malformed UnixFS file node: filesize {} but it holds {} bytes", filesize, held);
+        Ok(Self { data, links: links.into_iter().zip(blocksizes).collect(), filesize })
+    }
+}
+
+// The gateway pool; shared with the block walks streaming out of it.
+struct Gateways {
+    http: Client,
+    pool: AggregatorPool,
+    retry: RetryPolicy,
+    request_timeout: Duration,
+}
+
+impl Gateways {
+    // One block, verified against its CID. Within each round, gateways that fail with a transport
+    // error, timeout, retryable status or the wrong bytes are marked unhealthy and the next is tried.
+    async fn fetch_block(&self, cid: &Cid) -> Result<Bytes> {
+        let path = format!("/ipfs/{}?format=raw", cid);
+        let mut attempt: u32 = 0;
+        loop {
+            attempt += 1;
+            let mut last_err = String::new();
+            let mut mismatch = None;
+            for idx in self.pool.candidates() {
+                let url = format!("{}{}", self.pool.url(idx), path);
+                info!(%url, attempt, "Fetching IPFS block");
+                let started = Instant::now();
+                let req = self.http.get(&url).header(ACCEPT, "application/vnd.ipld.raw");
+                let resp = match timeout(self.request_timeout, req.send()).await {
+                    Ok(Ok(resp)) => resp,
+                    Ok(Err(err)) => {
+                        warn!(%url, %err, "IPFS gateway unreachable, failing over");
+                        self.pool.record_failure(idx);
+                        last_err = format!("{}: {}", url, err);
+                        continue;
+                    }
+                    Err(_) => {
+                        warn!(%url, "IPFS gateway timed out, failing over");
+                        self.pool.record_failure(idx);
+                        last_err = format!("{}: timed out after {:?}", url, self.request_timeout);
+                        continue;
+                    }
+                };
+                match resp.status() {
+                    StatusCode::OK => {}
+                    status if self.retry.is_retryable(status) => {
+                        warn!(%url, %status, "IPFS gateway error, failing over");
+                        self.pool.record_failure(idx);
+                        last_err = format!("{} returned {}", url, status);
+                        continue;
+                    }
+                    StatusCode::NOT_FOUND | StatusCode::GONE => {
+                        self.pool.record_success(idx, started.elapsed());
+                        return Err(WalrusError::NotFound.into());
+                    }
+                    status => {
+                        self.pool.record_success(idx, started.elapsed());
+                        let txt = resp.text().await.unwrap_or_default();
+                        anyhow::bail!("IPFS fetch failed: {} returned {}: {}", url, status, txt);
+                    }
+                }
+                let block = match read_block(resp).await {
+                    Ok(block) => block,
+                    Err(err) => {
+                        warn!(%url, %err, "IPFS gateway sent an unreadable block, failing over");
+                        self.pool.record_failure(idx);
+                        last_err = format!("{}: {}", url, err);
+                        continue;
+                    }
+                };
+                let actual = Sha256::digest(&block);
+                if actual.as_slice() != cid.digest {
+                    warn!(%url, "IPFS gateway served a block that does not match its CID, failing over");
+                    self.pool.record_failure(idx);
+                    last_err = format!("{}: block does not match CID", url);
+                    mismatch = Some(ContentMismatch {
+                        blob_id: cid.to_string(),
+                        expected: hex::encode(cid.digest),
+                        actual: hex::encode(actual),
+                    });
+                    continue;
+                }
+                self.pool.record_success(idx, started.elapsed());
+                return Ok(block);
+            }
+            if attempt >= self.retry.max_attempts {
+                if let Some(mismatch) = mismatch {
+                    return Err(mismatch.into());
+                }
+                anyhow::bail!("IPFS fetch failed after {} attempts: {}", attempt, last_err);
+            }
+            let backoff = self.retry.backoff(attempt);
+            warn!(attempt, ?backoff, error = %last_err, "IPFS fetch failed, retrying with backoff");
+            sleep(backoff).await;
+        }
+    }
+
+    // One block, decoded, counted into `fetched`, which may not pass `cap`.
+    async fn fetch_node(&self, cid: &Cid, fetched: &mut u64, cap: u64) -> Result<Node> {
+        let block = self.fetch_block(cid).await?;
+        *fetched += block.len() as u64;
+        if *fetched > cap {
+            warn!(limit = cap, "IPFS blob exceeded size cap, aborting download");
+            return Err(WalrusError::TooLarge { limit: cap }.into());
+        }
+        Node::decode(cid, block)
+    }
+}
+
+async fn read_block(mut resp: reqwest::Response) -> Result<Bytes> {
+    let mut block = Vec::new();
+    while let Some(chunk) = resp.chunk().await? {
+        block.extend_from_slice(&chunk);
+        anyhow::ensure!(block.len() as u64 <= MAX_BLOCK_BYTES, "block exceeds {} bytes", MAX_BLOCK_BYTES);
+    }
+    Ok(block.into())
+}
+
+pub struct IpfsSource {
+    gateways: Arc<Gateways>,
+    max_blob_bytes: u64,
+}
+
+impl IpfsSource {
+    // None without gateways. Egress goes through the same proxy as Walrus traffic.
+    pub fn new(config: &IpfsConfig, walrus: &WalrusConfig, proxy_url: Option<&str>) -> Result<Option<Self>> {
+        if config.gateways.is_empty() {
+            return Ok(None);
+        }
+        let mut builder = Client::builder()
+            .use_rustls_tls()
+            .pool_max_idle_per_host(walrus.pool_max_idle_per_host)
+            .pool_idle_timeout(walrus.pool_idle_timeout)
+            .connect_timeout(walrus.connect_timeout)
+            .tcp_keepalive(walrus.tcp_keepalive);
+        if let Some(url) = proxy_url {
+            builder = builder.proxy(reqwest::Proxy::all(url).context("Invalid IPFS proxy URL")?);
+        }
+        let http = builder.build().context("Failed building IPFS client")?;
+        info!(gateways = ?config.gateways, "IPFS backend enabled");
+        Ok(Some(Self {
+            gateways: Arc::new(Gateways {
+                http,
+                pool: AggregatorPool::new(config.gateways.clone(), walrus.selection, walrus.aggregator_cooldown),
+                retry: walrus.retry.clone(),
+                request_timeout: walrus.request_timeout,
+            }),
+            max_blob_bytes: walrus.max_blob_bytes,
+        }))
+    }
+}
+
+#[async_trait::async_trait]
+impl BlobSource for IpfsSource {
+    async fn fetch_blob(&self, blob_id: &str, limit: u64) -> Result<Vec<u8>> {
+        let mut body = self.fetch_blob_stream(blob_id, limit).await?;
+        let mut data = Vec::new();
+        while let Some(chunk) = body.next().await {
+            data.extend_from_slice(&chunk?);
+        }
+        Ok(data)
+    }
+
+    // File bytes in order, one verified block at a time. The cap counts every block fetched,
+    // interior DAG nodes included, so a DAG of nothing but links can't run unbounded.
+    async fn fetch_blob_stream(&self, blob_id: &str, limit: u64) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let cap = limit.min(self.max_blob_bytes);
+        let root = parse_ipfs_ref(blob_id)?;
+        let state = (self.gateways.clone(), vec![root], 0u64);
+        let walk = stream::try_unfold(state, move |(gateways, mut pending, mut fetched)| async move {
+            while let Some(cid) = pending.pop() {
+                let node = gateways.fetch_node(&cid, &mut fetched, cap).await?;
+                // A node's own bytes come before its children's.
+                pending.extend(node.links.into_iter().rev().map(|(cid, _)| cid));
+                if !node.data.is_empty() {
+                    return Ok(Some((node.data, (gateways, pending, fetched))));
+                }
+            }
+            Ok(None)
+        });
+        Ok(walk.boxed())
+    }
+
+    // Only the blocks overlapping the range are fetched, under the same cap as whole blobs.
+    async fn fetch_blob_range(&self, blob_id: &str, offset: u64, len: u64) -> Result<BlobRange> {
+        if len == 0 {
+            anyhow::bail!("range length must be non-zero");
+        }
+        let root_cid = parse_ipfs_ref(blob_id)?;
+        let mut fetched = 0u64;
+        let root = self.gateways.fetch_node(&root_cid, &mut fetched, self.max_blob_bytes).await?;
+        let total_len = root.filesize;
+        anyhow::ensure!(offset < total_len, "range starts at {} but the file is {} bytes", offset, total_len);
+        let end = offset.saturating_add(len).min(total_len);
+        let mut data = Vec::new();
+        // (node, file offset it starts at), visited in file order. Each node's sizes add up to its
+        // filesize, and each child's filesize is its link's, so no offset passes total_len.
+        let mut pending = vec![(root, 0u64)];
+        while let Some((node, start)) = pending.pop() {
+            let data_end = start + node.data.len() as u64;
+            if offset < data_end && start < end {
+                let from = offset.max(start) - start;
+                let to = end.min(data_end) - start;
+                data.extend_from_slice(&node.data[from as usize..to as usize]);
+            }
+            let mut children = Vec::new();
+            let mut child_start = data_end;
+            for (cid, size) in node.links {
+                if child_start < end && offset < child_start + size {
+                    children.push((cid, child_start, size));
+                }
+                child_start += size;
+            }
+            for (cid, start, size) in children.into_iter().rev() {
+                let child = self.gateways.fetch_node(&cid, &mut fetched, self.max_blob_bytes).await?;
+                anyhow::ensure!(
+                    child.filesize == size,
+                    "malformed UnixFS file: block {} holds {} bytes but is linked as {}",
+                    cid,
+                    child.filesize,
+                    size
+                );
+                pending.push((child, start));
+            }
+        }
+        Ok(BlobRange { data, total_len: Some(total_len) })
+    }
+
+    async fn blob_metadata(&self, blob_id: &str) -> Result<BlobMetadata> {
+        let cid = parse_ipfs_ref(blob_id)?;
+        let root = self.gateways.fetch_node(&cid, &mut 0, MAX_BLOCK_BYTES).await?;
+        if root.filesize > self.max_blob_bytes {
+            return Err(WalrusError::TooLarge { limit: self.max_blob_bytes }.into());
+        }
+        // Content-addressed, so the CID is as good an ETag as any.
+        Ok(BlobMetadata { size: Some(root.filesize), content_type: None, etag: Some(cid.to_string()) })
+    }
+}
+
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+}
+
+// Top-level fields of a protobuf message; fixed-width fields are skipped.
+fn protobuf_fields(mut buf: &[u8]) -> Result<Vec<(u64, Field<'_>)>> {
+    let mut fields = Vec::new();
+    while !buf.is_empty() {
+        let mut pos = 0;
+        let key = read_varint(buf, &mut pos)?;
+        let (field, wire_type) = (key >> 3, key & 7);
+        let value = match wire_type {
+            0 => Some(Field::Varint(read_varint(buf, &mut pos)?)),
+            2 => {
+                let len = read_varint(buf, &mut pos)? as usize;
+                let bytes = buf.get(pos..pos.saturating_add(len)).context("truncated protobuf field")?;
+                pos += len;
+                Some(Field::Bytes(bytes))
+            }
+            1 => {
+                pos += 8;
+                None
+            }
+            5 => {
+                pos += 4;
+                None
+            }
+            other => anyhow::bail!("unsupported protobuf wire type {}", other),
+        };
+        buf = buf.get(pos..).context("truncated protobuf field")?;
+        fields.extend(value.map(|v| (field, v)));
+    }
+    Ok(fields)
+}
+
+fn read_varint(buf: &[u8], pos: &mut usize) -> Result<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let byte = *buf.get(*pos).context("truncated varint")?;
+        *pos += 1;
+        value |= u64::from(byte & 0x7f) << shift;
+        if byte & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    anyhow::bail!("varint too long")
+}
+
+fn write_varint(out: &mut Vec<u8>, mut value: u64) {
+    while value >= 0x80 {
+        out.push(value as u8 | 0x80);
+        value >>= 7;
+    }
+    out.push(value as u8);
+}
+
+const BASE32: &[u8; 32] = b"abcdefghijklmnopqrstuvwxyz234567";
+const BASE58: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+fn base32_encode(bytes: &[u8]) -> String {
+    let mut out = String::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for &b in bytes {
+        acc = (acc << 8) | u32::from(b);
+        bits += 8;
+        while bits >= 5 {
+            bits -= 5;
+            out.push(BASE32[(acc >> bits) as usize & 31] as char);
+        }
+    }
+    if bits > 0 {
+        out.push(BASE32[(acc << (5 - bits)) as usize & 31] as char);
+    }
+    out
+}
+
+fn base32_decode(raw: &str) -> Option<Vec<u8>> {
+    let mut out = Vec::new();
+    let (mut acc, mut bits) = (0u32, 0);
+    for c in raw.bytes() {
+        let v = BASE32.iter().position(|&a| a == c.to_ascii_lowercase())? as u32;
+        acc = (acc << 5) | v;
+        bits += 5;
+        if bits >= 8 {
+            bits -= 8;
+            out.push((acc >> bits) as u8);
+        }
+    }
+    Some(out)
+}
+
+fn base58_decode(raw: &str) -> Option<Vec<u8>> {
+    // Big-endian base-256 digits, built up one base58 digit at a time.
+    let mut out: Vec<u8> = Vec::new();
+    for c in raw.bytes() {
+        let mut carry = BASE58.iter().position(|&a| a == c)? as u32;
+        for byte in out.iter_mut().rev() {
+            carry += u32::from(*byte) * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            out.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    let zeros = raw.bytes().take_while(|&c| c == b'1').count();
+    Some([vec![0; zeros], out].concat())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // dag-pb node for a UnixFS file with the given inline bytes and (child, size) links.
+    fn file_node(data: &[u8], links: &[(&Cid, u64)]) -> Vec<u8> {
+        let field = |out: &mut Vec<u8>, key: u8, bytes: &[u8]| {
+            out.push(key);
+            write_varint(out, bytes.len() as u64);
+            out.extend_from_slice(bytes);
+        };
+        let mut unixfs = vec![0x08, 2];
+        field(&mut unixfs, 0x12, data);
+        unixfs.push(0x18);
+        // Wrapping, so tests can build nodes whose sizes overflow.
+        write_varint(&mut unixfs, links.iter().fold(data.len() as u64, |sum, (_, s)| sum.wrapping_add(*s)));
+        for (_, size) in links {
+            unixfs.push(0x20);
+            write_varint(&mut unixfs, *size);
+        }
+        let mut node = Vec::new();
+        for (cid, _) in links {
+            let mut link = Vec::new();
+            let mut hash = vec![1];
+            write_varint(&mut hash, cid.codec);
+            hash.extend_from_slice(&[0x12, 32]);
+            hash.extend_from_slice(&cid.digest);
+            field(&mut link, 0x0a, &hash);
+            field(&mut node, 0x12, &link);
+        }
+        field(&mut node, 0x0a, &unixfs);
+        node
+    }
+
+    #[test]
+    fn test_cids_and_unixfs_nodes() {
+        // CIDv0 and CIDv1 of the same dag-pb block ("hello world" added with ipfs defaults).
+        let v0 = Cid::parse("QmT78zSuBmuS4z925WZfrqQ1qHaJ56DQaTfyMUF7F8ff5o").unwrap();
+        assert_eq!(v0.codec, DAG_PB);
+        assert_eq!(v0.to_string(), "bafybeicg2rebjoofv4kbyovkw7af3rpiitvnl6i7ckcywaq6xjcxnc2mby");
+        assert_eq!(Cid::parse(&v0.to_string()).unwrap(), v0);
+        assert!(Cid::parse("Qmnot-a-cid").is_err());
+        assert!(parse_ipfs_ref("ipfs://bafybeicg2rebjoofv4kbyovkw7af3rpiitvnl6i7ckcywaq6xjcxnc2mby/a.csv").is_err());
+
+        let leaf = |bytes: &[u8]| Cid { codec: RAW, digest: Sha256::digest(bytes).into() };
+        let (a, b) = (leaf(b"first,"), leaf(b"second"));
+        let block = Bytes::from(file_node(b"head:", &[(&a, 6), (&b, 6)]));
+        let root = Cid { codec: DAG_PB, digest: Sha256::digest(&block).into() };
+        let node = Node::decode(&root, block).unwrap();
+        assert_eq!(&node.data[..], b"head:");
+        assert_eq!(node.links, vec![(a.clone(), 6), (b.clone(), 6)]);
+        assert_eq!(node.filesize, 17);
+
+        let mut truncated = file_node(b"x", &[]);
+        truncated.pop();
+        assert!(Node::decode(&root, truncated.into()).is_err());
+        // Block sizes that overflow, or don't add up to the filesize, are refused.
+        assert!(Node::decode(&root, file_node(b"", &[(&a, u64::MAX), (&b, 1)]).into()).is_err());
+        let mut lying = file_node(b"", &[(&a, 6)]);
+        let at = lying.windows(2).position(|w| w == [0x18, 6]).unwrap();
+        lying[at + 1] = 7;
+        assert!(Node::decode(&root, lying.into()).is_err());
+    }
+
+    // A source whose only gateway is a local server holding `blocks`, with one attempt.
+    async fn local_source(blocks: Vec<Vec<u8>>, max_blob_bytes: u64) -> IpfsSource {
+        use hyper::body::Incoming;
+        use hyper::server::conn::http1;
+        use hyper::service::service_fn;
+        use hyper_util::rt::TokioIo;
+        use std::collections::HashMap;
+
+        let blocks: HashMap<String, Bytes> = blocks
+            .into_iter()
+            .map(|block| {
+                let codec = if block.first() == Some(&0x12) { DAG_PB } else { RAW };
+                (format!("/ipfs/{}", Cid { codec, digest: Sha256::digest(&block).into() }), block.into())
+            })
+            .collect();
+        let blocks = Arc::new(blocks);
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                let blocks = blocks.clone();
+                tokio::spawn(async move {
+                    let svc = service_fn(move |req: hyper::Request<Incoming>| {
+                        let block = blocks.get(req.uri().path()).cloned();
+                        async move {
+                            let mut resp = hyper::Response::new(http_body_util::Full::new(block.clone().unwrap_or_default()));
+                            if block.is_none() {
+                                *resp.status_mut() = hyper::StatusCode::NOT_FOUND;
+                            }
+                            Ok::<_, std::convert::Infallible>(resp)
+                        }
+                    });
+                    let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), svc).await;
+                });
+            }
+        });
+        let walrus = WalrusConfig {
+            max_blob_bytes,
+            retry: RetryPolicy { max_attempts: 1, ..Default::default() },
+            ..WalrusConfig::from_env().unwrap()
+        };
+        IpfsSource::new(&IpfsConfig { gateways: vec![url] }, &walrus, None).unwrap().unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_range_walks_are_bounded() {
+        let (first, second) = (b"first,".to_vec(), b"second".to_vec());
+        let leaf = |bytes: &[u8]| Cid { codec: RAW, digest: Sha256::digest(bytes).into() };
+        let (a, b) = (leaf(&first), leaf(&second));
+        let root = file_node(b"head:", &[(&a, 6), (&b, 6)]);
+        let root_id = format!("ipfs://{}", Cid { codec: DAG_PB, digest: Sha256::digest(&root).into() });
+        // A parent that claims its child holds more than it does.
+        let lying = file_node(b"", &[(&a, 7)]);
+        let lying_id = format!("ipfs://{}", Cid { codec: DAG_PB, digest: Sha256::digest(&lying).into() });
+        // Room for the root and one leaf.
+        let cap = root.len() as u64 + 6;
+        let source = local_source(vec![root, lying, first, second], cap).await;
+
+        let range = source.fetch_blob_range(&root_id, 3, 6).await.unwrap();
+        assert_eq!((range.data.as_slice(), range.total_len), (&b"d:firs"[..], Some(17)));
+        let range = source.fetch_blob_range(&root_id, 12, 100).await.unwrap();
+        assert_eq!(range.data, b"econd");
+
+        for offset in [17, u64::MAX] {
+            let err = source.fetch_blob_range(&root_id, offset, 2).await.unwrap_err();
+            assert!(err.to_string().contains("range starts at"), "{}", err);
+        }
+        let err = source.fetch_blob_range(&lying_id, 0, 7).await.unwrap_err();
+        assert!(err.to_string().contains("linked as 7"), "{}", err);
+        // Both leaves put the walk past the cap.
+        let err = source.fetch_blob_range(&root_id, 0, 17).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<WalrusError>(), Some(WalrusError::TooLarge { .. })), "{}", err);
+    }
+}
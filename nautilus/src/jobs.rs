@@ -0,0 +1,260 @@
+use serde::Serialize;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use tracing::warn;
+
+use crate::metrics;
+use crate::submission::SubmissionStatus;
+
+// Per-job bookkeeping: status, current stage and stage-level memory accounting.
+// Memory is charged for the large buffers each stage holds (ciphertext, plaintext, samples),
+// which is where enclave RSS spikes come from; small allocations are not tracked.
+
+// Finished jobs kept for GET /jobs/{id}.
+const MAX_RETAINED_JOBS: usize = 1000;
+
+#[derive(Debug, thiserror::Error)]
+#[error("QUALITY_JOB_OOM: job {job_id} stage '{stage}' needs {requested} bytes, exceeding the {cap}-byte job memory cap")]
+pub struct JobOom {
+    pub job_id: String,
+    pub stage: &'static str,
+    pub requested: u64,
+    pub cap: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum JobState {
+    Running,
+    Completed,
+    Failed,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct JobStatus {
+    pub job_id: String,
+    pub blob_id: String,
+    pub state: JobState,
+    pub stage: &'static str,
+    pub mem_current_bytes: u64,
+    pub mem_peak_bytes: u64,
+    pub mem_cap_bytes: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub started_ms: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub finished_ms: Option<u64>,
+    // On-chain recording of the result; absent when submission is off or the job didn't complete.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub submission: Option<SubmissionStatus>,
+}
+
+struct Registry {
+    jobs: HashMap<String, Arc<Mutex<JobStatus>>>,
+    order: VecDeque<String>,
+    next_id: u64,
+}
+
+// Recently started jobs, queryable by id. Owned by the application state.
+pub struct JobRegistry {
+    inner: Mutex<Registry>,
+    mem_cap_bytes: u64,
+}
+
+impl JobRegistry {
+    pub fn new(mem_cap_bytes: u64) -> Self {
+        let inner = Registry { jobs: HashMap::new(), order: VecDeque::new(), next_id: 1 };
+        Self { inner: Mutex::new(inner), mem_cap_bytes }
+    }
+
+    pub fn start(&self, blob_id: &str) -> Job {
+        let cap = self.mem_cap_bytes;
+        let mut reg = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let job_id = format!("job-{}-{}", now_ms(), reg.next_id);
+        reg.next_id += 1;
+        let status = Arc::new(Mutex::new(JobStatus {
+            job_id: job_id.clone(),
+            blob_id: blob_id.to_string(),
+            state: JobState::Running,
+            stage: "queued",
+            mem_current_bytes: 0,
+            mem_peak_bytes: 0,
+            mem_cap_bytes: cap,
+            error: None,
+            started_ms: now_ms(),
+            finished_ms: None,
+            submission: None,
+        }));
+        reg.jobs.insert(job_id.clone(), status.clone());
+        reg.order.push_back(job_id);
+        while reg.order.len() > MAX_RETAINED_JOBS {
+            if let Some(old) = reg.order.pop_front() {
+                reg.jobs.remove(&old);
+            }
+        }
+        metrics::add_gauge("nautilus_jobs_running", "Verification jobs currently running", &[], 1.0);
+        Job { status, charges: HashMap::new() }
+    }
+
+    pub fn get(&self, job_id: &str) -> Option<JobStatus> {
+        let reg = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        reg.jobs
+            .get(job_id)
+            .map(|s| s.lock().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+
+    // Jobs still running, among those retained.
+    pub fn running(&self) -> usize {
+        let reg = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        reg.jobs
+            .values()
+            .filter(|s| s.lock().unwrap_or_else(|e| e.into_inner()).state == JobState::Running)
+            .count()
+    }
+
+    // Submission runs after the job has finished; a job already evicted is simply not updated.
+    pub fn set_submission(&self, job_id: &str, submission: SubmissionStatus) {
+        let reg = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(status) = reg.jobs.get(job_id) {
+            status.lock().unwrap_or_else(|e| e.into_inner()).submission = Some(submission);
+        }
+    }
+}
+
+// Handle held by the request handler for the lifetime of one verification job.
+pub struct Job {
+    status: Arc<Mutex<JobStatus>>,
+    charges: HashMap<&'static str, u64>,
+}
+
+impl Job {
+    pub fn id(&self) -> String {
+        self.lock().job_id.clone()
+    }
+
+    pub fn set_stage(&self, stage: &'static str) {
+        self.lock().stage = stage;
+    }
+
+    // Account `bytes` held by `stage`, failing with QUALITY_JOB_OOM if the job would exceed its cap.
+    pub fn charge(&mut self, stage: &'static str, bytes: u64) -> Result<(), JobOom> {
+        let mut st = self.status.lock().unwrap_or_else(|e| e.into_inner());
+        let next = st.mem_current_bytes + bytes;
+        if next > st.mem_cap_bytes {
+            metrics::inc_counter("nautilus_jobs_oom_total", "Jobs aborted for exceeding the memory cap", &[]);
+            return Err(JobOom { job_id: st.job_id.clone(), stage, requested: next, cap: st.mem_cap_bytes });
+        }
+        st.mem_current_bytes = next;
+        st.mem_peak_bytes = st.mem_peak_bytes.max(next);
+        *self.charges.entry(stage).or_insert(0) += bytes;
+        metrics::add_gauge("nautilus_job_memory_bytes", "Bytes held by running jobs", &[], bytes as f64);
+        Ok(())
+    }
+
+    // Build the OOM error for a stage that could not even start within the remaining budget.
+    pub fn oom(&self, stage: &'static str, requested: u64) -> JobOom {
+        let st = self.lock();
+        metrics::inc_counter("nautilus_jobs_oom_total", "Jobs aborted for exceeding the memory cap", &[]);
+        JobOom { job_id: st.job_id.clone(), stage, requested, cap: st.mem_cap_bytes }
+    }
+
+    // Release everything charged by `stage` once its buffer has been dropped.
+    pub fn release(&mut self, stage: &'static str) {
+        if let Some(bytes) = self.charges.remove(stage) {
+            let mut st = self.status.lock().unwrap_or_else(|e| e.into_inner());
+            st.mem_current_bytes = st.mem_current_bytes.saturating_sub(bytes);
+            metrics::add_gauge("nautilus_job_memory_bytes", "Bytes held by running jobs", &[], -(bytes as f64));
+        }
+    }
+
+    pub fn complete(self) {
+        self.finish(JobState::Completed, None);
+    }
+
+    pub fn fail(self, err: &anyhow::Error) {
+        self.finish(JobState::Failed, Some(err.to_string()));
+    }
+
+    fn finish(mut self, state: JobState, error: Option<String>) {
+        let stages: Vec<&'static str> = self.charges.keys().copied().collect();
+        for stage in stages {
+            self.release(stage);
+        }
+        let mut st = self.lock();
+        st.state = state;
+        st.error = error;
+        st.finished_ms = Some(now_ms());
+        let label = if state == JobState::Completed { "completed" } else { "failed" };
+        metrics::inc_counter("nautilus_jobs_total", "Finished verification jobs by outcome", &[("state", label)]);
+        metrics::set_gauge(
+            "nautilus_job_memory_last_peak_bytes",
+            "Peak accounted memory of the most recently finished job",
+            &[],
+            st.mem_peak_bytes as f64,
+        );
+        metrics::add_gauge("nautilus_jobs_running", "Verification jobs currently running", &[], -1.0);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, JobStatus> {
+        self.status.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for Job {
+    fn drop(&mut self) {
+        let st = self.lock();
+        if st.state == JobState::Running {
+            warn!(job_id = %st.job_id, "Job handle dropped while still running");
+        }
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_charges_are_capped_and_released() {
+        let registry = JobRegistry::new(100);
+        let mut job = registry.start("blob");
+        let id = job.id();
+        job.charge("download", 60).unwrap();
+        job.charge("download", 20).unwrap();
+        job.charge("sample", 10).unwrap();
+        let status = registry.get(&id).unwrap();
+        assert_eq!((status.mem_current_bytes, status.mem_peak_bytes, status.mem_cap_bytes), (90, 90, 100));
+
+        // A charge past the cap is refused and leaves the accounting as it was.
+        let oom = job.charge("decrypt", 11).unwrap_err();
+        assert_eq!((oom.stage, oom.requested, oom.cap), ("decrypt", 101, 100));
+        assert_eq!(registry.get(&id).unwrap().mem_current_bytes, 90);
+
+        // Releasing a stage frees everything it charged; the peak stays.
+        job.release("download");
+        job.release("never-charged");
+        job.charge("decrypt", 11).unwrap();
+        let status = registry.get(&id).unwrap();
+        assert_eq!((status.mem_current_bytes, status.mem_peak_bytes), (21, 90));
+        assert_eq!(registry.running(), 1);
+
+        // Finishing releases what is left.
+        job.complete();
+        let status = registry.get(&id).unwrap();
+        assert_eq!((status.state, status.mem_current_bytes, status.mem_peak_bytes), (JobState::Completed, 0, 90));
+        assert_eq!(registry.running(), 0);
+
+        // A stage that can't start within the budget fails the job with QUALITY_JOB_OOM.
+        let job = registry.start("other");
+        let id = job.id();
+        let err = anyhow::Error::new(job.oom("download", 200));
+        job.fail(&err);
+        let status = registry.get(&id).unwrap();
+        assert_eq!(status.state, JobState::Failed);
+        assert!(status.error.unwrap().starts_with("QUALITY_JOB_OOM"));
+    }
+}
@@ -0,0 +1,797 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::category::Category;
+use crate::quality_validator::QualityCheck;
+
+// Incremental JSON parsing for JSON and JSONL datasets. Uploads get cut off, and a validator that
+// needs the whole document to parse throws away a 2 GB array because its last 300 bytes are
+// missing. This parser is a byte-level state machine fed in chunks of any size, keeping only the
+// container stack. It tracks where the last complete record ended (an element of a top-level
+// array, or a top-level value), so a blob that stops mid-value scores the records before that
+// point and is reported as `truncated`, while a syntax error is `corrupt`. JSONL is parsed a line
+// at a time: a bad line is counted and skipped rather than ending the parse.
+//
+// Records that parse are also profiled for structure: the set of top-level keys on object
+// records (and how often the most common set recurs), how deeply records nest, and how many of
+// their fields are null or missing. Those feed the consistency and completeness scores, the way
+// column counts and missing cells do for CSV.
+
+// Containers nested deeper than this are treated as corrupt rather than tracked.
+const MAX_DEPTH: usize = 1024;
+// Records nesting deeper than this count against consistency.
+const DEEP_NESTING: usize = 32;
+// Bounds on the structure profile: distinct keys and key sets tracked, key bytes kept per key,
+// and keys kept per record (fields beyond it are still counted).
+const MAX_KEYS: usize = 1024;
+const MAX_KEY_SETS: usize = 4096;
+const MAX_KEY_BYTES: usize = 256;
+const MAX_RECORD_KEYS: usize = 1024;
+// Keys listed in the report, most common first.
+const REPORTED_KEYS: usize = 32;
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0100_0000_01b3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ParseStatus {
+    Complete,
+    // The input ended inside a value; everything before it parsed.
+    Truncated,
+    // A syntax error (JSON), or at least one bad line (JSONL).
+    Corrupt,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ParseError {
+    pub offset: u64,
+    pub reason: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonParseReport {
+    pub status: ParseStatus,
+    pub records: u64,
+    #[serde(skip_serializing_if = "is_zero")]
+    pub corrupt_records: u64,
+    // Bytes in complete, valid records out of all bytes parsed.
+    pub valid_bytes: u64,
+    pub total_bytes: u64,
+    pub percent_parsed: f64,
+    // Offset where the input stopped mid-value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub truncated_at: Option<u64>,
+    // First syntax error.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<ParseError>,
+    // Shape of the records that parsed; absent when none did.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub structure: Option<JsonStructure>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct JsonStructure {
+    pub object_records: u64,
+    // Top-level null records (e.g. `null` lines in JSONL).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub null_records: u64,
+    pub distinct_key_sets: u64,
+    // Share of object records whose key set is the most common one.
+    pub key_set_consistency: f64,
+    // Null fields out of all fields present on object records.
+    pub null_field_ratio: f64,
+    // Non-null fields out of object records x distinct keys, so absent keys count as missing;
+    // absent when there were too many distinct keys to track.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub fill_ratio: Option<f64>,
+    // Container nesting of a record: 0 for scalars, 1 for a flat object or array.
+    pub max_depth: usize,
+    pub mean_depth: f64,
+    pub keys: Vec<KeyProfile>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct KeyProfile {
+    pub name: String,
+    // Share of object records that have the key, and share of those where it is null.
+    pub presence: f64,
+    pub null_ratio: f64,
+}
+
+impl JsonStructure {
+    // Parsed share scaled by record shape: key-set agreement 30%, sane nesting 10%.
+    fn consistency(&self, percent_parsed: f64) -> u32 {
+        let keys = if self.object_records == 0 { 1.0 } else { self.key_set_consistency };
+        let nesting = if self.max_depth <= DEEP_NESTING { 1.0 } else { DEEP_NESTING as f64 / self.max_depth as f64 };
+        (percent_parsed * (0.6 + 0.3 * keys + 0.1 * nesting)).round() as u32
+    }
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+fn ratio(n: u64, d: u64) -> f64 {
+    if d == 0 {
+        0.0
+    } else {
+        (n as f64 * 1000.0 / d as f64).round() / 1000.0
+    }
+}
+
+// The record being parsed, folded into `Profile` once it completes.
+#[derive(Default)]
+struct Shape {
+    active: bool,
+    object: bool,
+    null: bool,
+    // Top-level keys, each with whether its value was null.
+    keys: Vec<(String, bool)>,
+    fields: u64,
+    nulls: u64,
+    depth: usize,
+}
+
+#[derive(Default, Clone, Copy)]
+struct KeyCount {
+    present: u64,
+    null: u64,
+}
+
+#[derive(Default)]
+struct Profile {
+    records: u64,
+    object_records: u64,
+    null_records: u64,
+    fields: u64,
+    null_fields: u64,
+    keys: HashMap<String, KeyCount>,
+    keys_overflow: bool,
+    key_sets: HashMap<u64, u64>,
+    max_depth: usize,
+    depth_sum: u64,
+}
+
+impl Profile {
+    fn add(&mut self, mut shape: Shape) {
+        self.records += 1;
+        self.max_depth = self.max_depth.max(shape.depth);
+        self.depth_sum += shape.depth as u64;
+        self.null_records += u64::from(shape.null);
+        if !shape.object {
+            return;
+        }
+        self.object_records += 1;
+        let complete = shape.keys.len() as u64 == shape.fields;
+        // Repeated keys keep the last value, as most parsers do.
+        shape.keys.reverse();
+        shape.keys.sort_by(|a, b| a.0.cmp(&b.0));
+        shape.keys.dedup_by(|a, b| a.0 == b.0);
+        if complete {
+            self.fields += shape.keys.len() as u64;
+            self.null_fields += shape.keys.iter().filter(|k| k.1).count() as u64;
+        } else {
+            self.fields += shape.fields;
+            self.null_fields += shape.nulls;
+        }
+        let mut hash = FNV_OFFSET;
+        for (key, null) in &shape.keys {
+            for &b in key.as_bytes().iter().chain(&[0]) {
+                hash = (hash ^ b as u64).wrapping_mul(FNV_PRIME);
+            }
+            let tracked = self.keys.len() < MAX_KEYS;
+            match self.keys.get_mut(key) {
+                Some(count) => {
+                    count.present += 1;
+                    count.null += u64::from(*null);
+                }
+                None if tracked => {
+                    self.keys.insert(key.clone(), KeyCount { present: 1, null: u64::from(*null) });
+                }
+                None => self.keys_overflow = true,
+            }
+        }
+        if !complete {
+            self.keys_overflow = true;
+        }
+        if self.key_sets.len() < MAX_KEY_SETS || self.key_sets.contains_key(&hash) {
+            *self.key_sets.entry(hash).or_default() += 1;
+        }
+    }
+
+    fn report(&self) -> Option<JsonStructure> {
+        if self.records == 0 {
+            return None;
+        }
+        let objects = self.object_records;
+        let top_set = self.key_sets.values().copied().max().unwrap_or(0);
+        let non_null: u64 = self.keys.values().map(|k| k.present - k.null).sum();
+        let mut keys: Vec<(&String, &KeyCount)> = self.keys.iter().collect();
+        keys.sort_by(|a, b| b.1.present.cmp(&a.1.present).then(a.0.cmp(b.0)));
+        Some(JsonStructure {
+            object_records: objects,
+            null_records: self.null_records,
+            distinct_key_sets: self.key_sets.len() as u64,
+            key_set_consistency: ratio(top_set, objects),
+            null_field_ratio: ratio(self.null_fields, self.fields),
+            fill_ratio: (!self.keys_overflow && objects > 0)
+                .then(|| ratio(non_null, objects * self.keys.len().max(1) as u64)),
+            max_depth: self.max_depth,
+            mean_depth: (self.depth_sum as f64 * 100.0 / self.records as f64).round() / 100.0,
+            keys: keys
+                .into_iter()
+                .take(REPORTED_KEYS)
+                .map(|(name, k)| KeyProfile {
+                    name: name.clone(),
+                    presence: ratio(k.present, objects),
+                    null_ratio: ratio(k.null, k.present),
+                })
+                .collect(),
+        })
+    }
+
+    fn completeness(&self) -> u32 {
+        let Some(structure) = self.report() else { return 0 };
+        let filled = if structure.object_records > 0 {
+            structure.fill_ratio.unwrap_or(1.0 - structure.null_field_ratio)
+        } else {
+            1.0 - ratio(self.null_records, self.records)
+        };
+        (filled * 100.0).round() as u32
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    Object,
+    Array,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Expect {
+    Value,
+    // Right after '[': a value or ']'.
+    ValueOrEnd,
+    // Right after '{': a key or '}'.
+    KeyOrEnd,
+    Key,
+    Colon,
+    CommaOrEnd,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Num {
+    Sign,
+    Zero,
+    Int,
+    Dot,
+    Frac,
+    E,
+    ESign,
+    Exp,
+}
+
+impl Num {
+    fn next(self, b: u8) -> Option<Num> {
+        match (self, b) {
+            (Num::Sign, b'0') => Some(Num::Zero),
+            (Num::Sign, b'1'..=b'9') => Some(Num::Int),
+            (Num::Int, b'0'..=b'9') => Some(Num::Int),
+            (Num::Zero | Num::Int, b'.') => Some(Num::Dot),
+            (Num::Dot | Num::Frac, b'0'..=b'9') => Some(Num::Frac),
+            (Num::Zero | Num::Int | Num::Frac, b'e' | b'E') => Some(Num::E),
+            (Num::E, b'+' | b'-') => Some(Num::ESign),
+            (Num::E | Num::ESign | Num::Exp, b'0'..=b'9') => Some(Num::Exp),
+            _ => None,
+        }
+    }
+
+    fn complete(self) -> bool {
+        matches!(self, Num::Zero | Num::Int | Num::Frac | Num::Exp)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Lex {
+    Between,
+    Str { key: bool, escape: bool, hex: u8 },
+    Number(Num),
+    Literal(&'static [u8], usize),
+}
+
+pub struct JsonStream {
+    lines: bool,
+    stack: Vec<Container>,
+    expect: Expect,
+    lex: Lex,
+    offset: u64,
+    // End of the last complete record; start of the current JSONL line.
+    record_end: u64,
+    line_start: u64,
+    records: u64,
+    corrupt_records: u64,
+    // Bytes that belong to no valid record (bad JSONL lines).
+    bad_bytes: u64,
+    // Skipping the rest of a bad JSONL line.
+    skipping: bool,
+    // A complete top-level value was read: the document (JSON), or the current line, which
+    // counts as a record at its newline (JSONL).
+    top_value: bool,
+    error: Option<ParseError>,
+    shape: Shape,
+    profile: Profile,
+    // Bytes of the record-level object key being read.
+    key: Option<Vec<u8>>,
+}
+
+impl JsonStream {
+    // `lines` parses JSONL: one record per line, bad lines skipped.
+    pub fn new(lines: bool) -> Self {
+        Self {
+            lines,
+            stack: Vec::new(),
+            expect: Expect::Value,
+            lex: Lex::Between,
+            offset: 0,
+            record_end: 0,
+            line_start: 0,
+            records: 0,
+            corrupt_records: 0,
+            bad_bytes: 0,
+            skipping: false,
+            top_value: false,
+            error: None,
+            shape: Shape::default(),
+            profile: Profile::default(),
+            key: None,
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            if self.error.is_some() && !self.lines {
+                self.offset += 1;
+                continue;
+            }
+            if self.skipping {
+                self.bad_bytes += 1;
+                if b == b'\n' {
+                    self.skipping = false;
+                    self.line_start = self.offset + 1;
+                    self.record_end = self.offset + 1;
+                }
+            } else if let Err(reason) = self.step(b) {
+                self.fail(reason, b);
+            }
+            self.offset += 1;
+        }
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        let keys: usize = self.profile.keys.keys().map(|k| k.len() + std::mem::size_of::<KeyCount>()).sum();
+        (self.stack.capacity() + keys + self.profile.key_sets.len() * 16) as u64
+    }
+
+    // Profiles a record the input ended on (a bare number, or a JSONL line without its newline);
+    // the report already counts it.
+    pub fn finish(&mut self) {
+        let number_done = matches!(self.lex, Lex::Number(num) if num.complete()) && self.stack.is_empty();
+        if number_done || (self.lines && self.top_value && self.stack.is_empty() && self.lex == Lex::Between) {
+            self.end_record();
+        }
+    }
+
+    // The report as of the bytes fed so far.
+    pub fn report(&self) -> JsonParseReport {
+        let total = self.offset;
+        let (mut records, mut record_end) = (self.records, self.record_end);
+        // A number runs until something that isn't part of it, so the end of input completes one.
+        let number_done = matches!(self.lex, Lex::Number(num) if num.complete()) && self.stack.is_empty();
+        let midway = !self.stack.is_empty() || (self.lex != Lex::Between && !number_done);
+        if number_done || (self.lines && self.top_value && !midway) {
+            records += 1;
+            record_end = total;
+        }
+        let (status, truncated_at) = if self.error.is_some() && !self.lines {
+            (ParseStatus::Corrupt, None)
+        } else if midway && !self.skipping {
+            (ParseStatus::Truncated, Some(total))
+        } else if self.corrupt_records > 0 {
+            (ParseStatus::Corrupt, None)
+        } else {
+            (ParseStatus::Complete, None)
+        };
+        // Whatever follows the last complete record is lost; bad JSONL lines are already counted.
+        let lost = if status == ParseStatus::Complete || (self.lines && !midway) { 0 } else { total - record_end };
+        let valid_bytes = total.saturating_sub(self.bad_bytes + lost);
+        let percent_parsed =
+            if total == 0 { 0.0 } else { (valid_bytes as f64 * 1000.0 / total as f64).round() / 10.0 };
+        JsonParseReport {
+            status,
+            records,
+            corrupt_records: self.corrupt_records,
+            valid_bytes,
+            total_bytes: total,
+            percent_parsed,
+            truncated_at,
+            error: self.error.clone(),
+            structure: self.profile.report(),
+        }
+    }
+
+    pub fn completeness(&self) -> u32 {
+        self.profile.completeness()
+    }
+
+    fn fail(&mut self, reason: &'static str, b: u8) {
+        if self.error.is_none() {
+            self.error = Some(ParseError { offset: self.offset, reason });
+        }
+        if !self.lines {
+            return;
+        }
+        // Drop the line and pick up again after its newline.
+        self.corrupt_records += 1;
+        self.bad_bytes += self.offset - self.line_start;
+        self.stack.clear();
+        self.top_value = false;
+        self.shape = Shape::default();
+        self.key = None;
+        self.expect = Expect::Value;
+        self.lex = Lex::Between;
+        if b == b'\n' {
+            self.bad_bytes += 1;
+            self.line_start = self.offset + 1;
+            self.record_end = self.offset + 1;
+        } else {
+            self.skipping = true;
+            self.bad_bytes += 1;
+        }
+    }
+
+    fn step(&mut self, b: u8) -> Result<(), &'static str> {
+        if self.lines && b == b'\n' && (!self.stack.is_empty() || matches!(self.lex, Lex::Str { .. } | Lex::Literal(..))) {
+            return Err("record spans lines");
+        }
+        match self.lex {
+            Lex::Str { key, escape, hex } => {
+                if let Some(name) = self.key.as_mut().filter(|n| n.len() < MAX_KEY_BYTES) {
+                    if escape || hex > 0 || b != b'"' {
+                        name.push(b);
+                    }
+                }
+                self.lex = match (escape, hex, b) {
+                    (_, 1.., _) if b.is_ascii_hexdigit() => Lex::Str { key, escape, hex: hex - 1 },
+                    (_, 1.., _) => return Err("bad unicode escape"),
+                    (true, _, b'u') => Lex::Str { key, escape: false, hex: 4 },
+                    (true, _, b'"' | b'\\' | b'/' | b'b' | b'f' | b'n' | b'r' | b't') => {
+                        Lex::Str { key, escape: false, hex: 0 }
+                    }
+                    (true, _, _) => return Err("bad escape"),
+                    (false, _, b'\\') => Lex::Str { key, escape: true, hex: 0 },
+                    (false, _, b'"') => {
+                        self.lex = Lex::Between;
+                        if key {
+                            self.key_done();
+                            self.expect = Expect::Colon;
+                        } else {
+                            self.value_done(self.offset + 1, false);
+                        }
+                        return Ok(());
+                    }
+                    (false, _, 0..=0x1f) => return Err("control character in string"),
+                    _ => return Ok(()),
+                };
+                Ok(())
+            }
+            Lex::Number(num) => match num.next(b) {
+                Some(next) => {
+                    self.lex = Lex::Number(next);
+                    Ok(())
+                }
+                None if num.complete() => {
+                    self.lex = Lex::Between;
+                    self.value_done(self.offset, false);
+                    self.structural(b)
+                }
+                None => Err("malformed number"),
+            },
+            Lex::Literal(word, at) => {
+                if word[at] != b {
+                    return Err("invalid literal");
+                }
+                if at + 1 == word.len() {
+                    if word == b"null" {
+                        self.null_value();
+                    }
+                    self.lex = Lex::Between;
+                    self.value_done(self.offset + 1, false);
+                } else {
+                    self.lex = Lex::Literal(word, at + 1);
+                }
+                Ok(())
+            }
+            Lex::Between => self.structural(b),
+        }
+    }
+
+    fn structural(&mut self, b: u8) -> Result<(), &'static str> {
+        if matches!(b, b' ' | b'\t' | b'\r' | b'\n') {
+            if self.lines && b == b'\n' {
+                if std::mem::take(&mut self.top_value) {
+                    self.records += 1;
+                    self.record_end = self.offset + 1;
+                    self.end_record();
+                }
+                self.line_start = self.offset + 1;
+            }
+            return Ok(());
+        }
+        match (self.expect, b) {
+            (Expect::ValueOrEnd, b']') | (Expect::CommaOrEnd, b']') if self.stack.last() == Some(&Container::Array) => {
+                self.close()
+            }
+            (Expect::KeyOrEnd, b'}') | (Expect::CommaOrEnd, b'}') if self.stack.last() == Some(&Container::Object) => {
+                self.close()
+            }
+            (Expect::Value | Expect::ValueOrEnd, _) => self.start_value(b),
+            (Expect::KeyOrEnd | Expect::Key, b'"') => {
+                if self.shape.object && self.stack.len() == self.base() + 1 {
+                    self.key = Some(Vec::new());
+                }
+                self.lex = Lex::Str { key: true, escape: false, hex: 0 };
+                Ok(())
+            }
+            (Expect::KeyOrEnd | Expect::Key, _) => Err("expected object key"),
+            (Expect::Colon, b':') => {
+                self.expect = Expect::Value;
+                Ok(())
+            }
+            (Expect::Colon, _) => Err("expected ':'"),
+            (Expect::CommaOrEnd, b',') => {
+                self.expect = if self.stack.last() == Some(&Container::Object) { Expect::Key } else { Expect::Value };
+                Ok(())
+            }
+            (Expect::CommaOrEnd, _) => Err("expected ',' or closing bracket"),
+        }
+    }
+
+    fn start_value(&mut self, b: u8) -> Result<(), &'static str> {
+        let starts_value = matches!(b, b'{' | b'[' | b'"' | b'-' | b'0'..=b'9' | b't' | b'f' | b'n');
+        if starts_value && self.top_value && self.stack.is_empty() {
+            return Err(if self.lines { "more than one value on a line" } else { "data after the end of the document" });
+        }
+        // A top-level JSON array holds the records rather than being one.
+        if self.stack.len() == self.base() && !(b == b'[' && !self.lines && self.stack.is_empty()) {
+            self.shape = Shape { active: true, object: b == b'{', ..Default::default() };
+        }
+        match b {
+            b'{' | b'[' => {
+                if self.stack.len() >= MAX_DEPTH {
+                    return Err("nesting too deep");
+                }
+                let (container, expect) =
+                    if b == b'{' { (Container::Object, Expect::KeyOrEnd) } else { (Container::Array, Expect::ValueOrEnd) };
+                self.stack.push(container);
+                self.shape.depth = self.shape.depth.max(self.stack.len() - self.base());
+                self.expect = expect;
+            }
+            b'"' => self.lex = Lex::Str { key: false, escape: false, hex: 0 },
+            b'-' => self.lex = Lex::Number(Num::Sign),
+            b'0' => self.lex = Lex::Number(Num::Zero),
+            b'1'..=b'9' => self.lex = Lex::Number(Num::Int),
+            b't' => self.lex = Lex::Literal(b"true", 1),
+            b'f' => self.lex = Lex::Literal(b"false", 1),
+            b'n' => self.lex = Lex::Literal(b"null", 1),
+            _ => return Err("unexpected character"),
+        }
+        Ok(())
+    }
+
+    fn close(&mut self) -> Result<(), &'static str> {
+        let closed = self.stack.pop();
+        self.value_done(self.offset + 1, closed == Some(Container::Array));
+        Ok(())
+    }
+
+    // A value ended just before `end`. Records are the elements of a top-level array, or
+    // top-level values other than such an array; in JSONL, lines holding one value.
+    fn value_done(&mut self, end: u64, array: bool) {
+        match self.stack.as_slice() {
+            [] if self.lines => {
+                self.top_value = true;
+                self.expect = Expect::Value;
+            }
+            [] => {
+                if !array {
+                    self.records += 1;
+                    self.end_record();
+                }
+                self.record_end = end;
+                self.top_value = true;
+                self.expect = Expect::Value;
+            }
+            [Container::Array] if !self.lines => {
+                self.records += 1;
+                self.record_end = end;
+                self.end_record();
+                self.expect = Expect::CommaOrEnd;
+            }
+            _ => self.expect = Expect::CommaOrEnd,
+        }
+    }
+
+    // Stack depth at which records start: inside the top-level array for JSON, else the top.
+    fn base(&self) -> usize {
+        usize::from(!self.lines && self.stack.first() == Some(&Container::Array))
+    }
+
+    fn key_done(&mut self) {
+        let Some(name) = self.key.take() else { return };
+        self.shape.fields += 1;
+        if self.shape.keys.len() < MAX_RECORD_KEYS {
+            self.shape.keys.push((String::from_utf8_lossy(&name).into_owned(), false));
+        }
+    }
+
+    fn null_value(&mut self) {
+        let depth = self.stack.len().checked_sub(self.base());
+        if depth == Some(0) {
+            self.shape.null = true;
+        } else if depth == Some(1) && self.shape.object {
+            self.shape.nulls += 1;
+            if self.shape.keys.len() as u64 == self.shape.fields {
+                if let Some(last) = self.shape.keys.last_mut() {
+                    last.1 = true;
+                }
+            }
+        }
+    }
+
+    fn end_record(&mut self) {
+        let shape = std::mem::take(&mut self.shape);
+        if shape.active {
+            self.profile.add(shape);
+        }
+    }
+}
+
+// Consistency for JSON and JSONL is the share of the blob that parses into complete records,
+// scaled by how uniform their shape is; completeness is the share of expected fields present and
+// non-null.
+pub struct JsonCheck {
+    name: &'static str,
+    stream: JsonStream,
+}
+
+impl JsonCheck {
+    pub fn completeness(lines: bool) -> Self {
+        Self { name: "completeness", stream: JsonStream::new(lines) }
+    }
+
+    pub fn consistency(lines: bool) -> Self {
+        Self { name: "consistency", stream: JsonStream::new(lines) }
+    }
+}
+
+impl QualityCheck for JsonCheck {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        category.preset().weights.get(self.name)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.stream.update(data);
+    }
+
+    fn finish(&mut self) {
+        self.stream.finish();
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        if self.name == "completeness" {
+            return self.stream.completeness();
+        }
+        let report = self.stream.report();
+        match &report.structure {
+            Some(structure) => structure.consistency(report.percent_parsed),
+            None => report.percent_parsed.round() as u32,
+        }
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        (self.name == "consistency").then(|| serde_json::to_value(self.stream.report()).ok()).flatten().map(|v| ("json", v))
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.stream.state_bytes()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(data: &[u8], lines: bool) -> JsonParseReport {
+        let mut stream = JsonStream::new(lines);
+        for chunk in data.chunks(7) {
+            stream.update(chunk);
+        }
+        stream.report()
+    }
+
+    #[test]
+    fn test_truncated_and_corrupt_json() {
+        let array: String = format!(
+            "[{}]",
+            (0..100).map(|i| format!(r#"{{"id": {}, "v": -1.5e3, "s": "a\"é", "ok": true}}"#, i)).collect::<Vec<_>>().join(", ")
+        );
+        let whole = parse(array.as_bytes(), false);
+        assert_eq!((whole.status, whole.records, whole.percent_parsed), (ParseStatus::Complete, 100, 100.0));
+
+        // Cut inside the 61st record: the first 60 still count.
+        let cut = array.find(r#"{"id": 60,"#).unwrap() + 12;
+        let truncated = parse(&array.as_bytes()[..cut], false);
+        assert_eq!(truncated.status, ParseStatus::Truncated);
+        assert_eq!((truncated.records, truncated.truncated_at), (60, Some(cut as u64)));
+        assert!(truncated.percent_parsed > 90.0 && truncated.percent_parsed < 100.0);
+
+        // `{"id": }` where the 61st record's id should be.
+        let bad = cut - 5;
+        let mut broken = array.clone().into_bytes();
+        broken[bad] = b'}';
+        let corrupt = parse(&broken, false);
+        assert_eq!(corrupt.status, ParseStatus::Corrupt);
+        assert_eq!(corrupt.records, 60);
+        assert_eq!(corrupt.error.unwrap().offset, bad as u64);
+        assert_eq!(parse(b"01", false).status, ParseStatus::Corrupt);
+        assert_eq!(parse(b"42", false).status, ParseStatus::Complete);
+
+        // JSONL skips bad lines and keeps going; a last line cut short is truncation.
+        let jsonl = b"{\"a\": 1}\n{\"a\": tru}\n{\"a\": [1, 2]}\n{\"a\": \"x";
+        let report = parse(jsonl, true);
+        assert_eq!((report.status, report.records, report.corrupt_records), (ParseStatus::Truncated, 2, 1));
+        assert_eq!(report.valid_bytes, 9 + 14);
+        let report = parse(b"{\"a\": 1}\n{\"a\":\n1}\n{\"b\": 2}\n", true);
+        assert_eq!((report.status, report.records, report.corrupt_records), (ParseStatus::Corrupt, 2, 2));
+    }
+
+    #[test]
+    fn test_record_structure() {
+        // Every fifth record lacks "tag", every fourth has a null score; one is nested 40 deep.
+        let mut jsonl = String::new();
+        for i in 0..100 {
+            let score = if i % 4 == 0 { "null".to_string() } else { i.to_string() };
+            let tag = if i % 5 == 0 { String::new() } else { r#", "t\"ag": "x""#.to_string() };
+            let nested = if i == 7 { format!("{}1{}", "[".repeat(40), "]".repeat(40)) } else { "{\"k\": [1]}".into() };
+            jsonl.push_str(&format!(r#"{{"id": {}, "score": {}, "meta": {}{}, "id": {}}}"#, i, score, nested, tag, i));
+            jsonl.push('\n');
+        }
+        let mut check = JsonCheck::consistency(true);
+        for chunk in jsonl.as_bytes().chunks(11) {
+            check.update(chunk);
+        }
+        check.finish();
+        let report = check.stream.report();
+        let s = report.structure.clone().unwrap();
+        assert_eq!((s.object_records, s.distinct_key_sets, s.max_depth), (100, 2, 41));
+        // The repeated "id" counts once.
+        assert_eq!((s.key_set_consistency, s.null_field_ratio), (0.8, 0.066));
+        assert_eq!(s.keys[0].name, "id");
+        let tag = s.keys.iter().find(|k| k.name == r#"t\"ag"#).unwrap();
+        assert_eq!((tag.presence, tag.null_ratio), (0.8, 0.0));
+        // 4 keys x 100 records expected; 20 tags missing and 25 scores null.
+        assert_eq!(s.fill_ratio, Some(0.888));
+        assert_eq!(check.stream.completeness(), 89);
+        // Parsed in full, 80% on the main key set, one record nested past the limit.
+        assert_eq!(check.score(0), (100.0_f64 * (0.6 + 0.3 * 0.8 + 0.1 * 32.0 / 41.0)).round() as u32);
+
+        let nulls = parse(b"[null, 1, {\"a\": null}, 2]", false);
+        let s = nulls.structure.unwrap();
+        assert_eq!((s.null_records, s.object_records, s.null_field_ratio), (1, 1, 1.0));
+        assert!(parse(b"[]", false).structure.is_none());
+    }
+}
@@ -0,0 +1,273 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Keypair, PublicKey};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::kms_seal::{self, KmsConfig};
+use crate::submission::sui_address;
+use crate::tee_attestation::{self, bls, key_id, key_id_of, secp256k1};
+
+// Signing key rotation. Everything the service signs uses the current key; with
+// NAUTILUS_SIGNING_ROTATION_SECS set it is replaced by a fresh one on that schedule. A retired key
+// keeps verifying for NAUTILUS_SIGNING_KEY_GRACE_SECS, so signatures made just before a rotation
+// stay checkable and verifiers (on-chain ones included) can take up the new key before dropping
+// the old. GET /keys lists the current key and the retired ones still in their grace window, with
+// validity windows; the list survives restarts in HISTORY_FILE (public keys only). With KMS
+// sealing (see kms_seal) each new key is sealed in turn, so a restart resumes with it.
+// Submissions go from the current key's Sui address, also listed: fund a new key's address
+// before it has to pay gas.
+
+const HISTORY_FILE: &str = "signing-keys.json";
+
+#[derive(Debug, Clone)]
+pub struct RotationPolicy {
+    // None: the boot key signs until the next restart.
+    pub interval: Option<Duration>,
+    pub grace: Duration,
+}
+
+impl RotationPolicy {
+    pub fn from_env() -> Self {
+        let secs = |key: &str| env::var(key).ok().and_then(|v| v.parse::<u64>().ok());
+        Self {
+            interval: secs("NAUTILUS_SIGNING_ROTATION_SECS").filter(|&s| s > 0).map(Duration::from_secs),
+            grace: Duration::from_secs(secs("NAUTILUS_SIGNING_KEY_GRACE_SECS").unwrap_or(7 * 86_400)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct KeyRecord {
+    pub key_id: String,
+    pub public_key_b64: String,
+    pub sui_address: String,
+    // Signing window; `not_after_ms` is unset while the key is current.
+    pub not_before_ms: u64,
+    pub not_after_ms: Option<u64>,
+    // Signatures by a retired key should be accepted until then.
+    pub verify_until_ms: Option<u64>,
+    // The secp256k1 key derived from the same seed, which signs attestations under that scheme.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub secp256k1: Option<DerivedKey>,
+    // Likewise the BLS12-381 key, with the proof of possession quorum verifiers check (see bls).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub bls12381: Option<DerivedKey>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DerivedKey {
+    pub key_id: String,
+    // Compressed: SEC1 for secp256k1, a G1 point for BLS.
+    pub public_key_b64: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub proof_of_possession_b64: Option<String>,
+}
+
+impl KeyRecord {
+    fn new(key: &Keypair, now: u64) -> Self {
+        let secp256k1 = secp256k1::public_key(key).ok().map(|public_key| DerivedKey {
+            key_id: key_id_of(&public_key),
+            public_key_b64: STANDARD.encode(public_key),
+            proof_of_possession_b64: None,
+        });
+        let bls12381 = bls::public_key(key).ok().map(|public_key| DerivedKey {
+            key_id: key_id_of(&public_key),
+            public_key_b64: STANDARD.encode(public_key),
+            proof_of_possession_b64: bls::proof_of_possession(key).ok().map(|pop| STANDARD.encode(pop)),
+        });
+        Self {
+            key_id: key_id(&key.public),
+            public_key_b64: STANDARD.encode(key.public.to_bytes()),
+            sui_address: sui_address(&key.public),
+            not_before_ms: now,
+            not_after_ms: None,
+            verify_until_ms: None,
+            secp256k1,
+            bls12381,
+        }
+    }
+
+    fn retire(&mut self, now: u64, grace: Duration) {
+        self.not_after_ms = Some(now);
+        self.verify_until_ms = Some(now + grace.as_millis() as u64);
+    }
+}
+
+struct Ring {
+    current: Arc<Keypair>,
+    record: KeyRecord,
+    // Newest first.
+    retired: Vec<KeyRecord>,
+}
+
+pub struct KeyRing {
+    policy: RotationPolicy,
+    sealed_dir: Option<PathBuf>,
+    kms: KmsConfig,
+    ring: RwLock<Ring>,
+}
+
+impl KeyRing {
+    // Starts from the boot key. A different current key in the saved history is retired now: its
+    // process is gone, but its signatures are still out there.
+    pub fn new(boot: Keypair, policy: RotationPolicy, sealed_dir: Option<&Path>, kms: KmsConfig) -> Self {
+        let now = now_ms();
+        let mut record = KeyRecord::new(&boot, now);
+        let mut retired = Vec::new();
+        for mut saved in load_history(sealed_dir) {
+            if saved.key_id == record.key_id && saved.not_after_ms.is_none() {
+                record.not_before_ms = saved.not_before_ms;
+                continue;
+            }
+            if saved.not_after_ms.is_none() {
+                saved.retire(now, policy.grace);
+            }
+            retired.push(saved);
+        }
+        retired.sort_by_key(|r| std::cmp::Reverse(r.not_before_ms));
+        let ring = KeyRing {
+            policy,
+            sealed_dir: sealed_dir.map(Path::to_path_buf),
+            kms,
+            ring: RwLock::new(Ring { current: Arc::new(boot), record, retired }),
+        };
+        ring.prune(now);
+        ring
+    }
+
+    pub fn policy(&self) -> &RotationPolicy {
+        &self.policy
+    }
+
+    pub fn current(&self) -> Arc<Keypair> {
+        self.ring.read().unwrap().current.clone()
+    }
+
+    // The current key, then retired keys still within their grace window.
+    pub fn list(&self) -> Vec<KeyRecord> {
+        let ring = self.ring.read().unwrap();
+        let now = now_ms();
+        let live = ring.retired.iter().filter(|r| r.verify_until_ms.is_some_and(|until| until > now));
+        std::iter::once(&ring.record).chain(live).cloned().collect()
+    }
+
+    // Keys a signature by this service may verify against now.
+    pub fn verifying_keys(&self) -> Vec<PublicKey> {
+        self.list()
+            .iter()
+            .filter_map(|r| STANDARD.decode(&r.public_key_b64).ok())
+            .filter_map(|bytes| PublicKey::from_bytes(&bytes).ok())
+            .collect()
+    }
+
+    pub fn rotation_due(&self) -> bool {
+        let since = self.ring.read().unwrap().record.not_before_ms;
+        self.policy.interval.is_some_and(|interval| now_ms() >= since + interval.as_millis() as u64)
+    }
+
+    // Swaps in a fresh key; the old one is retired with the grace window. Returns the new record.
+    pub async fn rotate(&self) -> Result<KeyRecord> {
+        let seed = tee_attestation::fresh_seed()?;
+        let next = tee_attestation::keypair_from_seed(&seed)?;
+        if self.kms.enabled() {
+            let public_key = STANDARD.encode(next.public.to_bytes());
+            kms_seal::seal_new(&self.kms, self.sealed_dir.as_deref(), &seed, &public_key).await?;
+        }
+        let now = now_ms();
+        let record = {
+            let mut ring = self.ring.write().unwrap();
+            let mut old = std::mem::replace(&mut ring.record, KeyRecord::new(&next, now));
+            old.retire(now, self.policy.grace);
+            ring.retired.insert(0, old);
+            ring.current = Arc::new(next);
+            ring.record.clone()
+        };
+        self.prune(now);
+        info!(key_id = %record.key_id, sui_address = %record.sui_address, "Rotated signing key");
+        Ok(record)
+    }
+
+    // Drops retired keys past their grace window and saves the rest.
+    fn prune(&self, now: u64) {
+        let history = {
+            let mut ring = self.ring.write().unwrap();
+            ring.retired.retain(|r| r.verify_until_ms.is_some_and(|until| until > now));
+            std::iter::once(&ring.record).chain(&ring.retired).cloned().collect::<Vec<_>>()
+        };
+        if let Some(dir) = &self.sealed_dir {
+            if let Err(err) = save_history(dir, &history) {
+                warn!(err = %format!("{:#}", err), "Saving signing key history failed");
+            }
+        }
+    }
+}
+
+fn load_history(sealed_dir: Option<&Path>) -> Vec<KeyRecord> {
+    let Some(path) = sealed_dir.map(|dir| dir.join(HISTORY_FILE)) else {
+        return Vec::new();
+    };
+    match std::fs::read(&path) {
+        Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|err| {
+            warn!(%err, path = %path.display(), "Ignoring unreadable signing key history");
+            Vec::new()
+        }),
+        Err(_) => Vec::new(),
+    }
+}
+
+fn save_history(dir: &Path, history: &[KeyRecord]) -> Result<()> {
+    std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    let path = dir.join(HISTORY_FILE);
+    std::fs::write(&path, serde_json::to_vec_pretty(history)?).with_context(|| format!("write {}", path.display()))
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rotation_keeps_retired_keys_through_grace() {
+        let dir = std::env::temp_dir().join(format!("nautilus-keyring-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        let policy = RotationPolicy { interval: Some(Duration::from_secs(3600)), grace: Duration::from_secs(60) };
+        let boot = tee_attestation::keypair_from_seed(&[1u8; 32]).unwrap();
+        let ring = KeyRing::new(boot, policy.clone(), Some(&dir), KmsConfig::default());
+        let first = ring.current().public;
+        assert!(!ring.rotation_due());
+
+        let next = ring.rotate().await.unwrap();
+        assert_ne!(ring.current().public, first);
+        let listed = ring.list();
+        assert_eq!(listed[0], next);
+        assert_eq!(listed[1].key_id, key_id(&first));
+        assert_eq!(listed[1].not_after_ms, Some(next.not_before_ms));
+        assert_eq!(ring.verifying_keys(), vec![ring.current().public, first]);
+
+        // After a restart with another boot key, the last current key is retired too.
+        let reboot = tee_attestation::keypair_from_seed(&[2u8; 32]).unwrap();
+        let ring = KeyRing::new(reboot, policy.clone(), Some(&dir), KmsConfig::default());
+        let ids: Vec<String> = ring.list().into_iter().map(|r| r.key_id).collect();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(ids[2], key_id(&first));
+        assert!(ring.list()[1].verify_until_ms.is_some());
+
+        // Without a grace window the key it replaces drops out at once; earlier ones keep theirs.
+        let reboot = tee_attestation::keypair_from_seed(&[3u8; 32]).unwrap();
+        let policy = RotationPolicy { grace: Duration::ZERO, ..policy };
+        let ring = KeyRing::new(reboot, policy, Some(&dir), KmsConfig::default());
+        let listed = ring.list();
+        assert_eq!(listed.len(), 3);
+        assert!(listed.iter().all(|r| r.key_id != ids[0]));
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
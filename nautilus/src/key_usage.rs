@@ -0,0 +1,246 @@
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use tracing::{error, info};
+
+use crate::metrics;
+
+// Signing-key usage accounting. Every signature the service makes (attestations, badges, audit
+// archives) is counted per key in fixed windows and compared with a moving baseline; a sudden
+// surge, or exceeding an absolute per-window quota, raises an alarm since it is a likely sign the
+// key is being abused. With NAUTILUS_SIGNING_REQUIRE_REARM=1 an alarm also locks the key until an
+// operator re-arms it (POST /admin/signing/rearm).
+
+#[derive(Debug, Clone)]
+pub struct KeyUsagePolicy {
+    pub window: Duration,
+    // Alarm when a window exceeds `surge_factor` x the baseline...
+    pub surge_factor: f64,
+    // ...but only once it holds at least this many signatures, so quiet keys don't flap.
+    pub surge_min: u64,
+    // Absolute signatures per window; 0 means no quota.
+    pub quota: u64,
+    pub require_rearm: bool,
+}
+
+impl KeyUsagePolicy {
+    pub fn from_env() -> Self {
+        let parse = |key: &str, default: u64| env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            window: Duration::from_secs(parse("NAUTILUS_SIGNING_WINDOW_SECS", 60).max(1)),
+            surge_factor: env::var("NAUTILUS_SIGNING_SURGE_FACTOR")
+                .ok()
+                .and_then(|v| v.parse().ok())
+                .unwrap_or(20.0),
+            surge_min: parse("NAUTILUS_SIGNING_SURGE_MIN", 200),
+            quota: parse("NAUTILUS_SIGNING_QUOTA", 0),
+            require_rearm: env::var("NAUTILUS_SIGNING_REQUIRE_REARM")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("SIGNING_LOCKED: signing key {key_id} is locked after a usage alarm; an operator must re-arm it")]
+pub struct SigningLocked {
+    pub key_id: String,
+}
+
+struct KeyStats {
+    total: u64,
+    window_start: Instant,
+    window_count: u64,
+    // Exponential moving average of completed windows; None until the first window closes.
+    baseline: Option<f64>,
+    alarmed: bool,
+    locked: bool,
+}
+
+impl KeyStats {
+    fn new() -> Self {
+        Self { total: 0, window_start: Instant::now(), window_count: 0, baseline: None, alarmed: false, locked: false }
+    }
+}
+
+fn count_signature(key_id: &str, purpose: &str) {
+    metrics::inc_counter(
+        "nautilus_signatures_total",
+        "Signatures made, by key and purpose",
+        &[("key_id", key_id), ("purpose", purpose)],
+    );
+}
+
+#[derive(Debug, Serialize)]
+pub struct KeyUsageSnapshot {
+    pub key_id: String,
+    pub total: u64,
+    pub window_count: u64,
+    pub baseline: Option<f64>,
+    pub alarmed: bool,
+    pub locked: bool,
+}
+
+pub struct KeyUsageMonitor {
+    policy: KeyUsagePolicy,
+    keys: Mutex<HashMap<String, KeyStats>>,
+}
+
+impl KeyUsageMonitor {
+    pub fn new(policy: KeyUsagePolicy) -> Self {
+        Self { policy, keys: Mutex::new(HashMap::new()) }
+    }
+
+    // Account for one signature by `key_id`. Call before signing; an error means don't sign.
+    pub fn authorize(&self, key_id: &str, purpose: &str) -> Result<(), SigningLocked> {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = keys.entry(key_id.to_string()).or_insert_with(KeyStats::new);
+        self.roll_window(stats);
+        if stats.locked {
+            return Err(SigningLocked { key_id: key_id.to_string() });
+        }
+        let next = stats.window_count + 1;
+        let over_quota = self.policy.quota > 0 && next > self.policy.quota;
+        let surge = stats.baseline.is_some_and(|b| {
+            next >= self.policy.surge_min && next as f64 > self.policy.surge_factor * b.max(1.0)
+        });
+        if (over_quota || surge) && !stats.alarmed {
+            stats.alarmed = true;
+            error!(%key_id, window_count = next, baseline = ?stats.baseline, over_quota, "Signing key usage alarm");
+            metrics::inc_counter("nautilus_signing_alarms_total", "Signing usage alarms raised", &[("key_id", key_id)]);
+            metrics::set_gauge("nautilus_signing_alarm", "1 while a key's usage alarm is active", &[("key_id", key_id)], 1.0);
+            if self.policy.require_rearm {
+                stats.locked = true;
+                metrics::set_gauge("nautilus_signing_locked", "1 while a key is locked pending re-arm", &[("key_id", key_id)], 1.0);
+                return Err(SigningLocked { key_id: key_id.to_string() });
+            }
+        }
+        stats.total += 1;
+        stats.window_count = next;
+        count_signature(key_id, purpose);
+        Ok(())
+    }
+
+    // Account for a signature any caller can ask for (identity attestations, badges, disputes):
+    // refused while the key is locked and counted in its total, but kept out of the windows that
+    // raise alarms, so such callers can't trip the lock that stops every verification.
+    pub fn record(&self, key_id: &str, purpose: &str) -> Result<(), SigningLocked> {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        let stats = keys.entry(key_id.to_string()).or_insert_with(KeyStats::new);
+        if stats.locked {
+            return Err(SigningLocked { key_id: key_id.to_string() });
+        }
+        stats.total += 1;
+        count_signature(key_id, purpose);
+        Ok(())
+    }
+
+    // Clear alarms and locks on every key and start a fresh window.
+    pub fn rearm(&self) -> Vec<KeyUsageSnapshot> {
+        let mut keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        for (key_id, stats) in keys.iter_mut() {
+            if stats.alarmed || stats.locked {
+                info!(%key_id, "Signing key re-armed");
+            }
+            stats.alarmed = false;
+            stats.locked = false;
+            stats.window_count = 0;
+            stats.window_start = Instant::now();
+            metrics::set_gauge("nautilus_signing_alarm", "1 while a key's usage alarm is active", &[("key_id", key_id)], 0.0);
+            metrics::set_gauge("nautilus_signing_locked", "1 while a key is locked pending re-arm", &[("key_id", key_id)], 0.0);
+        }
+        drop(keys);
+        self.snapshot()
+    }
+
+    pub fn snapshot(&self) -> Vec<KeyUsageSnapshot> {
+        let keys = self.keys.lock().unwrap_or_else(|e| e.into_inner());
+        keys.iter()
+            .map(|(key_id, s)| KeyUsageSnapshot {
+                key_id: key_id.clone(),
+                total: s.total,
+                window_count: s.window_count,
+                baseline: s.baseline,
+                alarmed: s.alarmed,
+                locked: s.locked,
+            })
+            .collect()
+    }
+
+    // Close elapsed windows into the baseline; idle windows count as zero (at most a few, so a
+    // long-idle key is not treated as having no baseline at all).
+    fn roll_window(&self, stats: &mut KeyStats) {
+        let elapsed = stats.window_start.elapsed();
+        if elapsed < self.policy.window {
+            return;
+        }
+        let windows = (elapsed.as_secs_f64() / self.policy.window.as_secs_f64()) as u64;
+        let mut count = stats.window_count as f64;
+        for _ in 0..windows.min(8) {
+            stats.baseline = Some(match stats.baseline {
+                Some(b) => 0.8 * b + 0.2 * count,
+                None => count,
+            });
+            count = 0.0;
+        }
+        stats.window_count = 0;
+        stats.window_start += self.policy.window * windows as u32;
+        // An unlocked alarm clears once a normal window has passed.
+        if !stats.locked {
+            stats.alarmed = false;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quota_alarm_locks_until_rearm() {
+        let policy = KeyUsagePolicy {
+            window: Duration::from_secs(3600),
+            surge_factor: 10.0,
+            surge_min: 5,
+            quota: 3,
+            require_rearm: true,
+        };
+        let monitor = KeyUsageMonitor::new(policy);
+        for _ in 0..3 {
+            monitor.authorize("k1", "attestation").unwrap();
+        }
+        assert!(monitor.authorize("k1", "attestation").is_err());
+        assert!(monitor.authorize("k1", "badge").is_err());
+        // Other keys are unaffected.
+        monitor.authorize("k2", "attestation").unwrap();
+
+        // Recorded signatures are refused by a locked key too, but never lock one.
+        assert!(monitor.record("k1", "identity").is_err());
+        for _ in 0..10 {
+            monitor.record("k2", "identity").unwrap();
+        }
+        monitor.authorize("k2", "attestation").unwrap();
+
+        monitor.rearm();
+        monitor.authorize("k1", "attestation").unwrap();
+        let k1 = monitor.snapshot().into_iter().find(|s| s.key_id == "k1").unwrap();
+        // Refused signatures are not counted.
+        assert_eq!((k1.total, k1.window_count, k1.locked), (4, 1, false));
+    }
+
+    #[test]
+    fn test_surge_over_baseline() {
+        let policy =
+            KeyUsagePolicy { window: Duration::from_secs(3600), surge_factor: 10.0, surge_min: 5, quota: 0, require_rearm: true };
+        let monitor = KeyUsageMonitor::new(policy);
+        monitor.authorize("k", "attestation").unwrap();
+        monitor.keys.lock().unwrap().get_mut("k").unwrap().baseline = Some(0.5);
+        // Baseline is floored at 1/window, so the surge trips once the window passes max(5, 10).
+        for _ in 1..10 {
+            monitor.authorize("k", "attestation").unwrap();
+        }
+        assert!(monitor.authorize("k", "attestation").is_err());
+    }
+}
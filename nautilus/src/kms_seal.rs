@@ -0,0 +1,432 @@
+use aes::Aes256;
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use cbc::cipher::block_padding::Pkcs7;
+use cbc::cipher::{BlockDecryptMut, KeyIvInit};
+use reqwest::{Client, Url};
+use rsa::pkcs8::EncodePublicKey;
+use rsa::{Oaep, RsaPrivateKey};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fmt;
+use std::path::Path;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::s3_source::{amz_date, sign_v4, SigningInput};
+use crate::tee_attestation;
+
+// Persistent enclave signing key sealed with AWS KMS. With NAUTILUS_KMS_KEY_ID set, an enclave
+// keeps one signing key across restarts, so relying parties can pin it, instead of drawing a
+// fresh one per boot:
+//   - first boot: the seed comes from NSM GetRandom and is encrypted under the KMS key
+//     (Encrypt, bound to ENCRYPTION_CONTEXT); only the ciphertext is written, to SEALED_FILE in
+//     NAUTILUS_SEALED_DIR;
+//   - later boots: KMS Decrypt is called with a Recipient, an NSM attestation document carrying
+//     the public half of a fresh RSA key pair. KMS evaluates the document against the key policy
+//     (e.g. kms:RecipientAttestation:PCR0 pinned to the enclave image) and returns the seed
+//     encrypted to that RSA key as CMS EnvelopedData, so it is never in the clear outside the
+//     enclave, not even to holders of the parent instance's credentials.
+// The key policy should grant kms:Encrypt to the parent's role and kms:Decrypt only under a
+// RecipientAttestation condition. The enclave has no network of its own: the parent passes in
+// credentials (NAUTILUS_KMS_ACCESS_KEY_ID, ...) and runs a vsock proxy to KMS that
+// NAUTILUS_KMS_ENDPOINT points at.
+
+const SEALED_FILE: &str = "signing-key.kms.json";
+const ENCRYPTION_CONTEXT: (&str, &str) = ("purpose", "nautilus-attestation-signing-key");
+const RECIPIENT_KEY_BITS: usize = 2048;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+#[derive(Clone, Default)]
+pub struct KmsConfig {
+    // Key ID, ARN or alias; sealing is off without one.
+    pub key_id: Option<String>,
+    pub region: String,
+    // Defaults to the AWS regional endpoint; inside an enclave, the vsock proxy.
+    pub endpoint: Option<String>,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+    pub session_token: Option<String>,
+}
+
+impl KmsConfig {
+    pub fn from_env() -> Self {
+        let var = |key: &str| env::var(key).ok().filter(|v| !v.is_empty());
+        Self {
+            key_id: var("NAUTILUS_KMS_KEY_ID"),
+            region: var("NAUTILUS_KMS_REGION").unwrap_or_else(|| "us-east-1".into()),
+            endpoint: var("NAUTILUS_KMS_ENDPOINT").map(|e| e.trim_end_matches('/').to_string()),
+            access_key_id: var("NAUTILUS_KMS_ACCESS_KEY_ID"),
+            secret_access_key: var("NAUTILUS_KMS_SECRET_ACCESS_KEY"),
+            session_token: var("NAUTILUS_KMS_SESSION_TOKEN"),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.key_id.is_some()
+    }
+
+    pub fn endpoint(&self) -> String {
+        self.endpoint.clone().unwrap_or_else(|| format!("https://kms.{}.amazonaws.com", self.region))
+    }
+}
+
+// Secrets stay out of logs and `{:?}` dumps of the config.
+impl fmt::Debug for KmsConfig {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("KmsConfig")
+            .field("key_id", &self.key_id)
+            .field("region", &self.region)
+            .field("endpoint", &self.endpoint)
+            .field("access_key_id", &self.access_key_id)
+            .field("secret_access_key", &self.secret_access_key.as_ref().map(|_| "<redacted>"))
+            .field("session_token", &self.session_token.as_ref().map(|_| "<redacted>"))
+            .finish()
+    }
+}
+
+// What is persisted: nothing here decrypts without KMS and an attested enclave.
+#[derive(Serialize, Deserialize)]
+struct SealedKey {
+    kms_key_id: String,
+    ciphertext_b64: String,
+    // The key the seed derives, to catch a file sealed for another deployment.
+    public_key_b64: String,
+    sealed_at_ms: u64,
+}
+
+// The signing key seed: unsealed from the sealed dir, or drawn and sealed on first boot.
+// `public_key` derives the public key from a seed. Only called inside an enclave.
+pub async fn load_or_seal(
+    config: &KmsConfig,
+    sealed_dir: Option<&Path>,
+    public_key: impl Fn(&[u8; 32]) -> Result<[u8; 32]>,
+) -> Result<[u8; 32]> {
+    let key_id = config.key_id.as_deref().context("NAUTILUS_KMS_KEY_ID is not set")?;
+    let dir = sealed_dir.context("NAUTILUS_KMS_KEY_ID requires NAUTILUS_SEALED_DIR to keep the sealed key")?;
+    let kms = Kms::new(config)?;
+    let path = dir.join(SEALED_FILE);
+    if path.exists() {
+        let sealed: SealedKey = serde_json::from_slice(&std::fs::read(&path)?)
+            .with_context(|| format!("parse {}", path.display()))?;
+        if sealed.kms_key_id != key_id {
+            warn!(sealed_with = %sealed.kms_key_id, configured = %key_id, "Sealed signing key names another KMS key");
+        }
+        let seed = kms.unseal(key_id, &sealed.ciphertext_b64).await.context("unseal signing key")?;
+        let seed: [u8; 32] = seed.as_slice().try_into().context("unsealed seed is not 32 bytes")?;
+        if STANDARD.encode(public_key(&seed)?) != sealed.public_key_b64 {
+            bail!("unsealed key does not match the public key in {}", path.display());
+        }
+        info!(path = %path.display(), "Signing key unsealed with KMS");
+        return Ok(seed);
+    }
+
+    let seed = tee_attestation::nsm_random_seed()?;
+    seal_new(config, sealed_dir, &seed, &STANDARD.encode(public_key(&seed)?)).await?;
+    Ok(seed)
+}
+
+// Seals `seed` in place of whatever the sealed dir held, on first boot and on key rotation.
+pub async fn seal_new(config: &KmsConfig, sealed_dir: Option<&Path>, seed: &[u8; 32], public_key_b64: &str) -> Result<()> {
+    let key_id = config.key_id.as_deref().context("NAUTILUS_KMS_KEY_ID is not set")?;
+    let dir = sealed_dir.context("NAUTILUS_KMS_KEY_ID requires NAUTILUS_SEALED_DIR to keep the sealed key")?;
+    let kms = Kms::new(config)?;
+    let path = dir.join(SEALED_FILE);
+    let sealed = SealedKey {
+        kms_key_id: key_id.to_string(),
+        ciphertext_b64: kms.seal(key_id, seed).await.context("seal signing key")?,
+        public_key_b64: public_key_b64.to_string(),
+        sealed_at_ms: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+    };
+    std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    // Written aside and renamed, so a crash never leaves a truncated key file behind.
+    let tmp = path.with_extension("json.tmp");
+    std::fs::write(&tmp, serde_json::to_vec_pretty(&sealed)?).with_context(|| format!("write {}", tmp.display()))?;
+    std::fs::rename(&tmp, &path).with_context(|| format!("write {}", path.display()))?;
+    info!(path = %path.display(), "New signing key sealed with KMS");
+    Ok(())
+}
+
+struct Kms<'a> {
+    config: &'a KmsConfig,
+    access_key_id: &'a str,
+    secret_access_key: &'a str,
+    url: Url,
+    http: Client,
+}
+
+impl<'a> Kms<'a> {
+    fn new(config: &'a KmsConfig) -> Result<Self> {
+        let access_key_id =
+            config.access_key_id.as_deref().context("NAUTILUS_KMS_KEY_ID requires NAUTILUS_KMS_ACCESS_KEY_ID")?;
+        let secret_access_key = config
+            .secret_access_key
+            .as_deref()
+            .context("NAUTILUS_KMS_ACCESS_KEY_ID requires NAUTILUS_KMS_SECRET_ACCESS_KEY")?;
+        let url = Url::parse(&config.endpoint()).context("Invalid NAUTILUS_KMS_ENDPOINT")?;
+        let http = Client::builder().use_rustls_tls().timeout(REQUEST_TIMEOUT).build()?;
+        Ok(Self { config, access_key_id, secret_access_key, url, http })
+    }
+
+    async fn seal(&self, key_id: &str, seed: &[u8]) -> Result<String> {
+        let resp = self
+            .call(
+                "Encrypt",
+                serde_json::json!({
+                    "KeyId": key_id,
+                    "Plaintext": STANDARD.encode(seed),
+                    "EncryptionContext": { ENCRYPTION_CONTEXT.0: ENCRYPTION_CONTEXT.1 },
+                }),
+            )
+            .await?;
+        resp["CiphertextBlob"].as_str().map(str::to_string).context("KMS Encrypt returned no CiphertextBlob")
+    }
+
+    async fn unseal(&self, key_id: &str, ciphertext_b64: &str) -> Result<Vec<u8>> {
+        let recipient = RsaPrivateKey::new(&mut rand_core::OsRng, RECIPIENT_KEY_BITS)?;
+        let public_der = recipient.to_public_key().to_public_key_der()?;
+        let document = tee_attestation::recipient_document(public_der.as_bytes())?;
+        let resp = self
+            .call(
+                "Decrypt",
+                serde_json::json!({
+                    "KeyId": key_id,
+                    "CiphertextBlob": ciphertext_b64,
+                    "EncryptionContext": { ENCRYPTION_CONTEXT.0: ENCRYPTION_CONTEXT.1 },
+                    "Recipient": {
+                        "KeyEncryptionAlgorithm": "RSAES_OAEP_SHA_256",
+                        "AttestationDocument": STANDARD.encode(document),
+                    },
+                }),
+            )
+            .await?;
+        let cms = resp["CiphertextForRecipient"]
+            .as_str()
+            .and_then(|b64| STANDARD.decode(b64).ok())
+            .context("KMS Decrypt returned no CiphertextForRecipient; is the caller an attested recipient?")?;
+        open_envelope(&cms, &recipient)
+    }
+
+    // One KMS JSON API call, SigV4-signed.
+    async fn call(&self, action: &str, body: serde_json::Value) -> Result<serde_json::Value> {
+        let body = body.to_string();
+        let payload_sha256 = hex::encode(Sha256::digest(body.as_bytes()));
+        let host = match self.url.port() {
+            Some(port) => format!("{}:{}", self.url.host_str().unwrap_or_default(), port),
+            None => self.url.host_str().unwrap_or_default().to_string(),
+        };
+        let signed = sign_v4(
+            &SigningInput {
+                access_key_id: self.access_key_id,
+                secret_access_key: self.secret_access_key,
+                session_token: self.config.session_token.as_deref(),
+                region: &self.config.region,
+                service: "kms",
+                method: "POST",
+                host: &host,
+                path: "/",
+                range: None,
+                payload_sha256: &payload_sha256,
+            },
+            &amz_date(SystemTime::now()),
+        );
+        let mut req = self
+            .http
+            .post(self.url.clone())
+            .header("content-type", "application/x-amz-json-1.1")
+            .header("x-amz-target", format!("TrentService.{}", action));
+        for (name, value) in signed {
+            req = req.header(name, value);
+        }
+        let resp = req.body(body).send().await.with_context(|| format!("KMS {} request failed", action))?;
+        let status = resp.status();
+        let text = resp.text().await.unwrap_or_default();
+        if !status.is_success() {
+            bail!("KMS {} returned {}: {}", action, status, text);
+        }
+        serde_json::from_str(&text).with_context(|| format!("KMS {} returned invalid JSON", action))
+    }
+}
+
+// OIDs, as DER content bytes.
+const OID_ENVELOPED_DATA: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x03];
+const OID_RSAES_OAEP: &[u8] = &[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x01, 0x07];
+const OID_AES256_CBC: &[u8] = &[0x60, 0x86, 0x48, 0x01, 0x65, 0x03, 0x04, 0x01, 0x2a];
+
+// The plaintext of KMS's CiphertextForRecipient: CMS EnvelopedData (RFC 5652) with the content
+// key wrapped to `key` by RSAES-OAEP-SHA-256 and the content under AES-256-CBC.
+fn open_envelope(cms: &[u8], key: &RsaPrivateKey) -> Result<Vec<u8>> {
+    let (content_info, _) = read_tlv(cms)?;
+    let [oid, explicit] = &children(content_info.body)?[..] else {
+        bail!("CMS ContentInfo is not [contentType, content]");
+    };
+    if oid.body != OID_ENVELOPED_DATA || explicit.tag != 0xa0 {
+        bail!("CMS content is not EnvelopedData");
+    }
+    let (enveloped, _) = read_tlv(explicit.body)?;
+    let fields = children(enveloped.body)?;
+    let at = fields.iter().position(|f| f.tag == 0x31).context("EnvelopedData has no recipientInfos")?;
+    let content = fields.get(at + 1).filter(|f| f.tag == 0x30).context("EnvelopedData has no encryptedContentInfo")?;
+
+    // KeyTransRecipientInfo: version, rid, keyEncryptionAlgorithm, encryptedKey.
+    let recipient = children(fields[at].body)?.into_iter().find(|r| r.tag == 0x30).context("no KeyTransRecipientInfo")?;
+    let recipient = children(recipient.body)?;
+    let [.., algorithm, encrypted_key] = &recipient[..] else {
+        bail!("KeyTransRecipientInfo is truncated");
+    };
+    if children(algorithm.body)?.first().map(|oid| oid.body) != Some(OID_RSAES_OAEP) {
+        bail!("content key is not wrapped with RSAES-OAEP");
+    }
+    let content_key = key.decrypt(Oaep::new::<Sha256>(), &octets(encrypted_key)?).context("unwrap content key")?;
+
+    // EncryptedContentInfo: contentType, contentEncryptionAlgorithm, [0] encryptedContent.
+    let content = children(content.body)?;
+    let [_, algorithm, encrypted, ..] = &content[..] else {
+        bail!("EncryptedContentInfo has no encryptedContent");
+    };
+    let algorithm = children(algorithm.body)?;
+    let [oid, iv] = &algorithm[..] else {
+        bail!("contentEncryptionAlgorithm has no IV");
+    };
+    if oid.body != OID_AES256_CBC {
+        bail!("content is not encrypted with AES-256-CBC");
+    }
+    let iv = octets(iv)?;
+    if content_key.len() != 32 || iv.len() != 16 || encrypted.tag & 0x1f != 0 {
+        bail!("malformed AES-256-CBC parameters");
+    }
+    let mut buf = octets(encrypted)?;
+    let plain = cbc::Decryptor::<Aes256>::new(content_key.as_slice().into(), iv.as_slice().into())
+        .decrypt_padded_mut::<Pkcs7>(&mut buf)
+        .map_err(|_| anyhow::anyhow!("bad padding in CMS content"))?;
+    Ok(plain.to_vec())
+}
+
+// A BER element. KMS emits indefinite lengths, so both length forms are read; the body of an
+// indefinite-length element stops before its end-of-contents octets.
+struct Tlv<'a> {
+    tag: u8,
+    body: &'a [u8],
+}
+
+fn read_tlv(input: &[u8]) -> Result<(Tlv<'_>, &[u8])> {
+    let [tag, len, rest @ ..] = input else {
+        bail!("truncated BER element");
+    };
+    if tag & 0x1f == 0x1f {
+        bail!("multi-byte BER tags are not supported");
+    }
+    if *len == 0x80 {
+        if tag & 0x20 == 0 {
+            bail!("indefinite length on a primitive BER element");
+        }
+        let mut cursor = rest;
+        while !cursor.starts_with(&[0, 0]) {
+            cursor = read_tlv(cursor)?.1;
+        }
+        let body = &rest[..rest.len() - cursor.len()];
+        return Ok((Tlv { tag: *tag, body }, &cursor[2..]));
+    }
+    let (n, rest) = match len {
+        0..=0x7f => (*len as usize, rest),
+        0x81..=0x84 => {
+            let k = (len & 0x7f) as usize;
+            let bytes = rest.get(..k).context("truncated BER length")?;
+            (bytes.iter().fold(0usize, |n, &b| n << 8 | b as usize), &rest[k..])
+        }
+        _ => bail!("unsupported BER length"),
+    };
+    let body = rest.get(..n).context("truncated BER element")?;
+    Ok((Tlv { tag: *tag, body }, &rest[n..]))
+}
+
+fn children(mut body: &[u8]) -> Result<Vec<Tlv<'_>>> {
+    let mut out = Vec::new();
+    while !body.is_empty() {
+        let (tlv, rest) = read_tlv(body)?;
+        out.push(tlv);
+        body = rest;
+    }
+    Ok(out)
+}
+
+// An OCTET STRING's bytes, joining the segments of a constructed one.
+fn octets(tlv: &Tlv) -> Result<Vec<u8>> {
+    if tlv.tag & 0x20 == 0 {
+        return Ok(tlv.body.to_vec());
+    }
+    let mut out = Vec::new();
+    for segment in children(tlv.body)? {
+        out.extend(octets(&segment)?);
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cbc::cipher::BlockEncryptMut;
+    use rsa::RsaPublicKey;
+
+    fn der(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+        let body = parts.concat();
+        let mut out = vec![tag];
+        match body.len() {
+            n @ 0..=0x7f => out.push(n as u8),
+            n @ 0x80..=0xff => out.extend([0x81, n as u8]),
+            n => out.extend([0x82, (n >> 8) as u8, n as u8]),
+        }
+        out.extend(body);
+        out
+    }
+
+    fn indefinite(tag: u8, parts: &[&[u8]]) -> Vec<u8> {
+        [&[tag, 0x80][..], &parts.concat(), &[0, 0]].concat()
+    }
+
+    // EnvelopedData as KMS builds it, indefinite lengths and a segmented encryptedContent included.
+    fn envelope(plaintext: &[u8], recipient: &RsaPublicKey) -> Vec<u8> {
+        let (content_key, iv) = ([7u8; 32], [9u8; 16]);
+        let wrapped = recipient.encrypt(&mut rand_core::OsRng, Oaep::new::<Sha256>(), &content_key).unwrap();
+        let mut buf = vec![0u8; plaintext.len() + 16];
+        buf[..plaintext.len()].copy_from_slice(plaintext);
+        let encrypted = cbc::Encryptor::<Aes256>::new(&content_key.into(), &iv.into())
+            .encrypt_padded_mut::<Pkcs7>(&mut buf, plaintext.len())
+            .unwrap()
+            .to_vec();
+        let (head, tail) = encrypted.split_at(10);
+
+        let oid = |content: &[u8]| der(0x06, &[content]);
+        let rid = der(0x80, &[b"subject-key-id"]);
+        let oaep = der(0x30, &[&oid(OID_RSAES_OAEP)]);
+        let recipient_info = der(0x30, &[&der(0x02, &[&[2]]), &rid, &oaep, &der(0x04, &[&wrapped])]);
+        let aes = der(0x30, &[&oid(OID_AES256_CBC), &der(0x04, &[&iv])]);
+        let content = indefinite(0xa0, &[&der(0x04, &[head]), &der(0x04, &[tail])]);
+        let data = oid(&[0x2a, 0x86, 0x48, 0x86, 0xf7, 0x0d, 0x01, 0x07, 0x01]);
+        let content_info = indefinite(0x30, &[&data, &aes, &content]);
+        let enveloped = indefinite(0x30, &[&der(0x02, &[&[2]]), &der(0x31, &[&recipient_info]), &content_info]);
+        indefinite(0x30, &[&oid(OID_ENVELOPED_DATA), &indefinite(0xa0, &[&enveloped])])
+    }
+
+    #[test]
+    fn test_open_recipient_envelope() {
+        // Short keys keep the test fast; KMS itself requires RECIPIENT_KEY_BITS.
+        let key = RsaPrivateKey::new(&mut rand_core::OsRng, 1024).unwrap();
+        let seed = [42u8; 32];
+        let cms = envelope(&seed, &key.to_public_key());
+        assert_eq!(open_envelope(&cms, &key).unwrap(), seed);
+
+        let other = RsaPrivateKey::new(&mut rand_core::OsRng, 1024).unwrap();
+        assert!(open_envelope(&cms, &other).is_err());
+        assert!(open_envelope(&cms[..cms.len() - 3], &key).is_err());
+        assert!(open_envelope(&der(0x30, &[&der(0x06, &[OID_RSAES_OAEP])]), &key).is_err());
+
+        let secret = Some("s3cr3t".to_string());
+        let config = KmsConfig { key_id: Some("alias/nautilus".into()), secret_access_key: secret, ..Default::default() };
+        assert!(!format!("{:?}", config).contains("s3cr3t"));
+        let config = KmsConfig { region: "eu-west-1".into(), ..config };
+        assert_eq!(config.endpoint(), "https://kms.eu-west-1.amazonaws.com");
+    }
+}
@@ -0,0 +1,370 @@
+use serde::Serialize;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{Hash, Hasher};
+
+use crate::category::Category;
+use crate::csv_validator::CsvStats;
+use crate::quality_validator::QualityCheck;
+
+// Class balance of labeled datasets. A request may name its label column (`label_column`): a
+// CSV header name or a JSON Lines key. Each record's value in it is counted as a class, and the
+// evenness of the class distribution (Shannon entropy over its maximum for that many classes)
+// is averaged into the bias score with the byte-level indicator. Per-class counts are reported
+// under "labels"; a majority class SEVERE_IMBALANCE_RATIO times the size of the smallest is
+// flagged. A column the dataset doesn't have leaves bias to the byte-level indicator alone.
+//
+// Records are also grouped by everything but their label (see poisoning): a group whose records
+// disagree on the label holds flipped labels, as far as a classifier that looks records up by
+// their features can tell.
+
+// Distinct classes counted; values beyond them are counted together.
+const MAX_CLASSES: usize = 256;
+// Bytes of a label value kept.
+const MAX_LABEL_BYTES: usize = 128;
+// A JSON Lines record longer than this isn't parsed; its label counts as missing.
+const MAX_RECORD_BYTES: usize = 1024 * 1024;
+const SEVERE_IMBALANCE_RATIO: f64 = 10.0;
+// Feature groups remembered; records after that are only checked against them.
+const MAX_GROUPS: usize = 1 << 18;
+
+// Labeled records sharing the same features: how many carry the first label seen, how many not.
+#[derive(Debug, Clone, Copy)]
+struct Group {
+    label: u64,
+    agree: u32,
+    disagree: u32,
+}
+
+#[derive(Debug, Default)]
+pub struct LabelCounts {
+    classes: HashMap<String, u64>,
+    // Records whose value wasn't among the first MAX_CLASSES distinct ones.
+    other: u64,
+    missing: u64,
+    groups: HashMap<u64, Group>,
+}
+
+impl LabelCounts {
+    // None for a record without a value in the column. `features` hashes the rest of the record.
+    pub fn add(&mut self, label: Option<&str>, features: u64) {
+        let Some(label) = label.map(str::trim).filter(|l| !l.is_empty()) else {
+            self.missing += 1;
+            return;
+        };
+        let label = truncate(label);
+        if let Some(count) = self.classes.get_mut(label) {
+            *count += 1;
+        } else if self.classes.len() < MAX_CLASSES {
+            self.classes.insert(label.to_string(), 1);
+        } else {
+            self.other += 1;
+        }
+        let label = hash(label.as_bytes());
+        let tracked = self.groups.len();
+        match self.groups.get_mut(&features) {
+            Some(group) if group.label == label => group.agree += 1,
+            Some(group) => group.disagree += 1,
+            None if tracked < MAX_GROUPS => {
+                self.groups.insert(features, Group { label, agree: 1, disagree: 0 });
+            }
+            None => {}
+        }
+    }
+
+    pub fn labeled(&self) -> u64 {
+        self.classes.values().sum()
+    }
+
+    // Records outvoted by others with the same features: the labels a lookup classifier would
+    // call flipped.
+    pub fn flipped(&self) -> u64 {
+        self.groups.values().map(|g| g.agree.min(g.disagree) as u64).sum()
+    }
+
+    // Groups of identical records that disagree on the label.
+    pub fn conflicting_groups(&self) -> u64 {
+        self.groups.values().filter(|g| g.disagree > 0).count() as u64
+    }
+
+    // Evenness of the class distribution, 0..=100; 0 with fewer than two classes.
+    pub fn balance(&self) -> u32 {
+        let n = self.labeled() as f64;
+        if self.classes.len() < 2 {
+            return 0;
+        }
+        let entropy: f64 = self.classes.values().map(|&c| c as f64 / n).map(|p| -p * p.ln()).sum();
+        (entropy / (self.classes.len() as f64).ln() * 100.0).round().clamp(0.0, 100.0) as u32
+    }
+
+    // Largest class over smallest.
+    pub fn imbalance_ratio(&self) -> Option<f64> {
+        let max = self.classes.values().max()?;
+        let min = self.classes.values().min()?;
+        Some(*max as f64 / *min as f64)
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.classes.keys().map(|k| k.capacity() as u64 + 32).sum::<u64>() + self.groups.capacity() as u64 * 24
+    }
+}
+
+pub fn hash(bytes: &[u8]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    hasher.finish()
+}
+
+fn truncate(label: &str) -> &str {
+    let mut end = label.len().min(MAX_LABEL_BYTES);
+    while !label.is_char_boundary(end) {
+        end -= 1;
+    }
+    &label[..end]
+}
+
+#[derive(Debug, Serialize)]
+pub struct LabelReport {
+    pub column: String,
+    // Whether the dataset has the column.
+    pub found: bool,
+    pub records: u64,
+    pub missing: u64,
+    // Records per class.
+    pub classes: BTreeMap<String, u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub other_classes: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub balance: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub imbalance_ratio: Option<f64>,
+    pub severe_imbalance: bool,
+}
+
+enum Source {
+    Csv(Box<CsvStats>),
+    Jsonl { line: Vec<u8>, overflow: bool, found: bool, counts: LabelCounts },
+}
+
+// The label column of a CSV or JSON Lines dataset, read as the records stream past.
+pub struct LabelReader {
+    column: String,
+    source: Source,
+}
+
+impl LabelReader {
+    pub fn csv(column: &str) -> Self {
+        let stats = CsvStats::new(false).with_label(column);
+        Self { column: column.to_string(), source: Source::Csv(Box::new(stats)) }
+    }
+
+    pub fn jsonl(column: &str) -> Self {
+        let source = Source::Jsonl { line: Vec::new(), overflow: false, found: false, counts: LabelCounts::default() };
+        Self { column: column.to_string(), source }
+    }
+
+    // The class counts, when the dataset has the column.
+    pub fn counts(&self) -> Option<&LabelCounts> {
+        match &self.source {
+            Source::Csv(stats) => stats.labels(),
+            Source::Jsonl { found, counts, .. } => found.then_some(counts),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        if let Source::Csv(stats) = &mut self.source {
+            stats.update(data);
+            return;
+        }
+        for piece in data.split_inclusive(|&b| b == b'\n') {
+            if let Source::Jsonl { line, overflow, .. } = &mut self.source {
+                if line.len() + piece.len() > MAX_RECORD_BYTES {
+                    *overflow = true;
+                    line.clear();
+                } else if !*overflow {
+                    line.extend_from_slice(piece);
+                }
+            }
+            if piece.ends_with(b"\n") {
+                self.end_record();
+            }
+        }
+    }
+
+    pub fn finish(&mut self) {
+        match &mut self.source {
+            Source::Csv(stats) => stats.finish(),
+            Source::Jsonl { .. } => self.end_record(),
+        }
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        let source = match &self.source {
+            Source::Csv(stats) => stats.state_bytes(),
+            Source::Jsonl { line, .. } => line.capacity() as u64,
+        };
+        source + self.counts().map_or(0, LabelCounts::state_bytes)
+    }
+
+    fn end_record(&mut self) {
+        let Source::Jsonl { line, overflow, found, counts } = &mut self.source else { return };
+        if line.iter().all(u8::is_ascii_whitespace) && !*overflow {
+            line.clear();
+            return;
+        }
+        let mut record = (!*overflow).then(|| serde_json::from_slice::<serde_json::Value>(line).ok()).flatten();
+        let value = record.as_mut().and_then(|r| r.as_object_mut()?.remove(&self.column));
+        *found |= value.is_some();
+        let label = match value {
+            Some(serde_json::Value::String(s)) => Some(s),
+            Some(serde_json::Value::Null) | None => None,
+            Some(other) => Some(other.to_string()),
+        };
+        // The record without its label, in canonical form so key order doesn't matter.
+        let features = record.as_ref().map_or(0, |r| hash(&nautilus_canonical::to_canonical_json(r)));
+        counts.add(label.as_deref(), features);
+        line.clear();
+        *overflow = false;
+    }
+}
+
+// Bias from the byte-level indicator (`fallback`) and the balance of the label column.
+pub struct LabelBalance {
+    labels: LabelReader,
+    fallback: Box<dyn QualityCheck>,
+}
+
+impl LabelBalance {
+    pub fn new(labels: LabelReader, fallback: Box<dyn QualityCheck>) -> Self {
+        Self { labels, fallback }
+    }
+
+    fn counts(&self) -> Option<&LabelCounts> {
+        self.labels.counts()
+    }
+
+    pub fn report(&self) -> LabelReport {
+        let counts = self.counts();
+        let ratio = counts.and_then(LabelCounts::imbalance_ratio);
+        let balance = counts.filter(|c| c.labeled() > 0).map(LabelCounts::balance);
+        LabelReport {
+            column: self.labels.column.clone(),
+            found: counts.is_some(),
+            records: counts.map_or(0, |c| c.labeled() + c.other + c.missing),
+            missing: counts.map_or(0, |c| c.missing),
+            classes: counts.map(|c| c.classes.iter().map(|(k, &v)| (k.clone(), v)).collect()).unwrap_or_default(),
+            other_classes: counts.map(|c| c.other).filter(|&o| o > 0),
+            balance,
+            imbalance_ratio: ratio.map(|r| (r * 100.0).round() / 100.0),
+            severe_imbalance: ratio.is_some_and(|r| r >= SEVERE_IMBALANCE_RATIO) || balance == Some(0),
+        }
+    }
+}
+
+impl QualityCheck for LabelBalance {
+    fn name(&self) -> &'static str {
+        self.fallback.name()
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        self.fallback.weight(category)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.fallback.update(data);
+        self.labels.update(data);
+    }
+
+    fn finish(&mut self) {
+        self.fallback.finish();
+        self.labels.finish();
+    }
+
+    fn score(&self, total_len: u64) -> u32 {
+        let bytes = self.fallback.score(total_len);
+        match self.counts().filter(|c| c.labeled() > 0) {
+            Some(counts) => (bytes + counts.balance()) / 2,
+            None => bytes,
+        }
+    }
+
+    fn exclude(&mut self, freq: &[u64; 256]) {
+        self.fallback.exclude(freq);
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.labels.state_bytes() + self.fallback.state_bytes()
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        Some(("labels", serde_json::to_value(self.report()).ok()?))
+    }
+
+    fn measures(&self, total_len: u64) -> Vec<(&'static str, f64)> {
+        let mut out = self.fallback.measures(total_len);
+        if let Some(counts) = self.counts().filter(|c| c.labeled() > 0) {
+            out.push(("label_balance", counts.balance() as f64));
+            out.extend(counts.imbalance_ratio().map(|r| ("imbalance_ratio", r)));
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality_validator::{validate_dataset_quality, ValidationOptions};
+
+    struct Flat;
+
+    impl QualityCheck for Flat {
+        fn name(&self) -> &'static str {
+            "bias"
+        }
+
+        fn weight(&self, _category: Category) -> u32 {
+            20
+        }
+
+        fn update(&mut self, _data: &[u8]) {}
+
+        fn score(&self, _total_len: u64) -> u32 {
+            60
+        }
+    }
+
+    fn run(mut check: LabelBalance, data: &[u8]) -> LabelBalance {
+        data.chunks(7).for_each(|c| check.update(c));
+        check.finish();
+        check
+    }
+
+    #[test]
+    fn test_class_balance_scored_and_reported() {
+        let csv: String = std::iter::once("id,text,label\n".to_string())
+            .chain((0..200).map(|i| format!("{},\"row, {}\",{}\n", i, i, if i % 2 == 0 { "cat" } else { "dog" })))
+            .collect();
+        let even = run(LabelBalance::new(LabelReader::csv("label"), Box::new(Flat)), csv.as_bytes());
+        let report = even.report();
+        assert_eq!((report.found, report.records, report.classes["cat"], report.balance), (true, 200, 100, Some(100)));
+        assert_eq!(even.score(0), 80);
+
+        let skewed: String = std::iter::once("{\"x\": 0}\n".to_string())
+            .chain((0..200).map(|i| format!("{{\"id\": {}, \"y\": {}}}\n", i, if i % 50 == 0 { "true" } else { "false" })))
+            .collect();
+        let skewed = run(LabelBalance::new(LabelReader::jsonl("y"), Box::new(Flat)), skewed.as_bytes());
+        let report = skewed.report();
+        assert_eq!((report.records, report.missing, report.classes["true"], report.classes["false"]), (201, 1, 4, 196));
+        assert!(report.severe_imbalance && report.imbalance_ratio == Some(49.0));
+        assert!(skewed.score(0) < 45);
+
+        // A column the dataset doesn't have leaves the byte-level score.
+        let absent = run(LabelBalance::new(LabelReader::csv("class"), Box::new(Flat)), csv.as_bytes());
+        assert!(!absent.report().found);
+        assert_eq!(absent.score(0), 60);
+
+        let opts = ValidationOptions { label_column: Some("label".into()), ..Default::default() };
+        let report = validate_dataset_quality(csv.as_bytes(), &opts).unwrap();
+        assert_eq!(report.checks["bias"].details["labels"]["classes"]["dog"], 100);
+    }
+}
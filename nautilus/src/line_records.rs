@@ -0,0 +1,100 @@
+use serde::Serialize;
+
+use crate::quality_validator::MAX_EVIDENCE_ITEMS;
+
+// Line-by-line bookkeeping for the checks that score text datasets record by record (privacy,
+// secrets, content safety). Each non-blank line is a record; the owning check scans it and says
+// what, if anything, to flag it with. Records are counted and the first flagged ones kept, by
+// index, for the sealed evidence.
+
+// A line is scanned in pieces once it grows past this without a newline.
+const MAX_LINE_BYTES: usize = 64 * 1024;
+
+#[derive(Default)]
+pub struct LineRecords<T> {
+    // Bytes of the line still being read.
+    line: Vec<u8>,
+    pub records: u64,
+    pub flagged: u64,
+    // (record index, what it was flagged with) for the first flagged records.
+    flagged_records: Vec<(u64, T)>,
+}
+
+impl<T: Serialize> LineRecords<T> {
+    // Feeds a chunk, handing each completed line to `scan`; a `Some` flags the record.
+    pub fn update(&mut self, data: &[u8], mut scan: impl FnMut(&[u8]) -> Option<T>) {
+        for piece in data.split_inclusive(|&b| b == b'\n') {
+            self.line.extend_from_slice(piece);
+            if piece.ends_with(b"\n") || self.line.len() >= MAX_LINE_BYTES {
+                self.scan_line(&mut scan);
+            }
+        }
+    }
+
+    // Scans the last line, which needn't end in a newline.
+    pub fn finish(&mut self, mut scan: impl FnMut(&[u8]) -> Option<T>) {
+        self.scan_line(&mut scan);
+    }
+
+    fn scan_line(&mut self, scan: &mut impl FnMut(&[u8]) -> Option<T>) {
+        if !self.line.iter().all(u8::is_ascii_whitespace) {
+            if let Some(flag) = scan(&self.line) {
+                self.flagged += 1;
+                if self.flagged_records.len() < MAX_EVIDENCE_ITEMS {
+                    self.flagged_records.push((self.records, flag));
+                }
+            }
+            self.records += 1;
+        }
+        self.line.clear();
+    }
+
+    // Share of records that weren't flagged, 0-100; 100 when there are none.
+    pub fn clean_score(&self) -> u32 {
+        if self.records == 0 {
+            return 100;
+        }
+        (100 * (self.records - self.flagged) / self.records) as u32
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        (self.line.capacity() + self.flagged_records.len() * 48) as u64
+    }
+
+    // The flagged records, each with its flag under `key`. Indices count non-blank records from
+    // zero.
+    pub fn evidence(&self, key: &str) -> serde_json::Value {
+        let records: Vec<_> = self
+            .flagged_records
+            .iter()
+            .map(|(index, flag)| serde_json::json!({ "record": index, key: flag }))
+            .collect();
+        serde_json::json!({ "flagged_records": records, "unlisted": self.flagged - records.len() as u64 })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_split_and_flagged() {
+        let mut lines = LineRecords::default();
+        let flag = |line: &[u8]| line.starts_with(b"bad").then_some("bad");
+        lines.update(b"bad one\n  \ngood", flag);
+        lines.update(b" one\nbad two", flag);
+        lines.finish(flag);
+        assert_eq!((lines.records, lines.flagged, lines.clean_score()), (3, 2, 33));
+        let flagged = serde_json::json!([{ "record": 0, "kind": "bad" }, { "record": 2, "kind": "bad" }]);
+        assert_eq!(lines.evidence("kind"), serde_json::json!({ "flagged_records": flagged, "unlisted": 0 }));
+
+        // An endless line is scanned in MAX_LINE_BYTES pieces rather than buffered whole.
+        let mut lines = LineRecords::<()>::default();
+        for _ in 0..3 * MAX_LINE_BYTES / 1024 {
+            lines.update(&[b'x'; 1024], |_| None);
+        }
+        lines.finish(|_| None);
+        assert_eq!(lines.records, 3);
+        assert!(lines.state_bytes() <= 2 * MAX_LINE_BYTES as u64);
+    }
+}
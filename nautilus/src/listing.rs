@@ -0,0 +1,121 @@
+use anyhow::{anyhow, bail, Result};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::Engine;
+use serde::Serialize;
+
+use crate::integrity::sha256_hex;
+
+// Paginated listings for admin tooling (GET /admin/keys, GET /policies). Entries are ordered by
+// ID; `?limit=` caps the page (DEFAULT_LIMIT, at most MAX_LIMIT), `?cursor=` continues after the
+// previous page's `next_cursor` and `?status=` keeps entries in that state. Pages carry an ETag
+// over their body, and a request whose If-None-Match matches gets 304 without one.
+
+const DEFAULT_LIMIT: usize = 50;
+const MAX_LIMIT: usize = 500;
+
+// An entry of a listing.
+pub trait Listed: Serialize {
+    fn id(&self) -> &str;
+    fn status(&self) -> &str;
+}
+
+#[derive(Debug, Default)]
+pub struct ListQuery {
+    pub limit: Option<usize>,
+    // ID of the last entry already returned.
+    pub after: Option<String>,
+    pub status: Option<String>,
+}
+
+impl ListQuery {
+    pub fn parse(query: Option<&str>) -> Result<Self> {
+        let mut out = Self::default();
+        for (key, value) in query.unwrap_or_default().split('&').filter_map(|pair| pair.split_once('=')) {
+            match key {
+                "limit" => {
+                    let limit = value.parse().ok().filter(|&l| l > 0).ok_or_else(|| anyhow!("limit must be a positive number"))?;
+                    out.limit = Some(limit);
+                }
+                "cursor" => {
+                    let id = URL_SAFE_NO_PAD.decode(value).ok().and_then(|b| String::from_utf8(b).ok());
+                    out.after = Some(id.ok_or_else(|| anyhow!("invalid cursor"))?);
+                }
+                "status" if value.is_empty() => bail!("status must not be empty"),
+                "status" => out.status = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        Ok(out)
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    // Pass as `?cursor=` for the next page; absent on the last one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub next_cursor: Option<String>,
+    // Entries matching the filter, across all pages.
+    pub total: usize,
+}
+
+impl<T: Listed> Page<T> {
+    pub fn of(mut items: Vec<T>, query: &ListQuery) -> Self {
+        items.retain(|item| query.status.as_deref().is_none_or(|s| item.status() == s));
+        items.sort_by(|a, b| a.id().cmp(b.id()));
+        let total = items.len();
+        if let Some(after) = &query.after {
+            items.retain(|item| item.id() > after.as_str());
+        }
+        let limit = query.limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        let next_cursor = (items.len() > limit).then(|| URL_SAFE_NO_PAD.encode(items[limit - 1].id()));
+        items.truncate(limit);
+        Self { items, next_cursor, total }
+    }
+
+    // The page as JSON with its quoted ETag.
+    pub fn body(&self) -> (Vec<u8>, String) {
+        let body = serde_json::to_vec(self).unwrap_or_default();
+        let etag = format!("\"{}\"", &sha256_hex(&body)[..32]);
+        (body, etag)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Serialize)]
+    struct Entry(String, &'static str);
+
+    impl Listed for Entry {
+        fn id(&self) -> &str {
+            &self.0
+        }
+
+        fn status(&self) -> &str {
+            self.1
+        }
+    }
+
+    fn entries() -> Vec<Entry> {
+        (0..7).rev().map(|i| Entry(format!("k{}", i), if i % 3 == 0 { "revoked" } else { "active" })).collect()
+    }
+
+    #[test]
+    fn test_pages_and_filters() {
+        let first = Page::of(entries(), &ListQuery::parse(Some("limit=2&status=active")).unwrap());
+        assert_eq!(first.items.iter().map(|e| e.id()).collect::<Vec<_>>(), ["k1", "k2"]);
+        assert_eq!(first.total, 4);
+        let query = format!("limit=2&status=active&cursor={}", first.next_cursor.clone().unwrap());
+        let second = Page::of(entries(), &ListQuery::parse(Some(&query)).unwrap());
+        assert_eq!(second.items.iter().map(|e| e.id()).collect::<Vec<_>>(), ["k4", "k5"]);
+        assert!(second.next_cursor.is_none());
+
+        // The ETag follows the content, not the request.
+        let all = Page::of(entries(), &ListQuery::default());
+        assert_eq!(all.body().1, Page::of(entries(), &ListQuery::parse(Some("limit=50")).unwrap()).body().1);
+        assert_ne!(all.body().1, first.body().1);
+        assert!(ListQuery::parse(Some("limit=0")).is_err() && ListQuery::parse(Some("cursor=%%")).is_err());
+    }
+}
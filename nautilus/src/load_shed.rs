@@ -0,0 +1,200 @@
+use anyhow::Result;
+use std::env;
+use std::fs;
+use tracing::{info, warn};
+
+// Load-shedding controller.
+// Under memory/CPU pressure the service drops optional work before refusing requests.
+// Every degradation applied to a job is recorded so the attested result says what actually ran.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PressureLevel {
+    Normal,
+    Elevated, // above soft watermark: skip optional checks
+    High,     // above high watermark: also sample the dataset
+    Critical, // above hard watermark: refuse new work
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Degradation {
+    SkipFuzzyDedup,
+    ReducedSampling { rate_pct: u8 },
+}
+
+impl Degradation {
+    // Stable machine-readable label used in responses and attestations.
+    pub fn label(&self) -> String {
+        match self {
+            Degradation::SkipFuzzyDedup => "skip_fuzzy_dedup".to_string(),
+            Degradation::ReducedSampling { rate_pct } => format!("reduced_sampling:{}", rate_pct),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct LoadShedPolicy {
+    pub mem_soft_pct: u8,
+    pub mem_high_pct: u8,
+    pub mem_hard_pct: u8,
+    pub cpu_soft: f64, // 1-minute load average per core
+    pub cpu_high: f64,
+    pub cpu_hard: f64,
+    pub sample_rate_pct: u8,
+    // Records in a sample (see sampling), under shedding and for caller-requested samples alike.
+    pub sample_records: usize,
+}
+
+impl LoadShedPolicy {
+    pub fn from_env() -> Self {
+        Self {
+            mem_soft_pct: env_parse("NAUTILUS_SHED_MEM_SOFT_PCT", 70),
+            mem_high_pct: env_parse("NAUTILUS_SHED_MEM_HIGH_PCT", 85),
+            mem_hard_pct: env_parse("NAUTILUS_SHED_MEM_HARD_PCT", 95),
+            cpu_soft: env_parse("NAUTILUS_SHED_CPU_SOFT", 1.0),
+            cpu_high: env_parse("NAUTILUS_SHED_CPU_HIGH", 2.0),
+            cpu_hard: env_parse("NAUTILUS_SHED_CPU_HARD", 4.0),
+            sample_rate_pct: env_parse::<u8>("NAUTILUS_SHED_SAMPLE_RATE_PCT", 25).clamp(1, 100),
+            sample_records: env_parse::<usize>("NAUTILUS_SHED_SAMPLE_RECORDS", 4096).max(1),
+        }
+    }
+
+    pub fn level(&self, usage: &ResourceUsage) -> PressureLevel {
+        let mem = usage.mem_used_pct;
+        let cpu = usage.load_per_core;
+        if mem >= self.mem_hard_pct as f64 || cpu >= self.cpu_hard {
+            PressureLevel::Critical
+        } else if mem >= self.mem_high_pct as f64 || cpu >= self.cpu_high {
+            PressureLevel::High
+        } else if mem >= self.mem_soft_pct as f64 || cpu >= self.cpu_soft {
+            PressureLevel::Elevated
+        } else {
+            PressureLevel::Normal
+        }
+    }
+
+    pub fn degradations(&self, level: PressureLevel) -> Vec<Degradation> {
+        match level {
+            PressureLevel::Normal => Vec::new(),
+            PressureLevel::Elevated => vec![Degradation::SkipFuzzyDedup],
+            PressureLevel::High | PressureLevel::Critical => vec![
+                Degradation::SkipFuzzyDedup,
+                Degradation::ReducedSampling { rate_pct: self.sample_rate_pct },
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ResourceUsage {
+    pub mem_used_pct: f64,
+    pub load_per_core: f64,
+}
+
+// Snapshot host memory and CPU pressure from procfs.
+// Outside Linux (or if procfs is unreadable) report no pressure rather than shedding blindly.
+pub fn current_usage() -> ResourceUsage {
+    let mem_used_pct = read_mem_used_pct().unwrap_or(0.0);
+    let load_per_core = read_load_per_core().unwrap_or(0.0);
+    ResourceUsage { mem_used_pct, load_per_core }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("QUALITY_OVERLOADED: service under {0:?} resource pressure, retry later")]
+pub struct Overloaded(pub PressureLevel);
+
+pub enum ShedDecision {
+    Proceed(Vec<Degradation>),
+    Reject(PressureLevel),
+}
+
+// Decide how much work the next job may do given current pressure.
+pub fn assess(policy: &LoadShedPolicy) -> ShedDecision {
+    let usage = current_usage();
+    let level = policy.level(&usage);
+    match level {
+        PressureLevel::Normal => ShedDecision::Proceed(Vec::new()),
+        PressureLevel::Critical => {
+            warn!(mem_pct = usage.mem_used_pct, load = usage.load_per_core, "Load shedding: rejecting job");
+            ShedDecision::Reject(level)
+        }
+        _ => {
+            let degradations = policy.degradations(level);
+            info!(?level, mem_pct = usage.mem_used_pct, load = usage.load_per_core, "Load shedding: degrading job");
+            ShedDecision::Proceed(degradations)
+        }
+    }
+}
+
+fn read_mem_used_pct() -> Result<f64> {
+    let meminfo = fs::read_to_string("/proc/meminfo")?;
+    let field = |name: &str| -> Option<f64> {
+        meminfo
+            .lines()
+            .find(|l| l.starts_with(name))
+            .and_then(|l| l.split_whitespace().nth(1))
+            .and_then(|v| v.parse::<f64>().ok())
+    };
+    let total = field("MemTotal:").ok_or_else(|| anyhow::anyhow!("MemTotal missing"))?;
+    let available = field("MemAvailable:").ok_or_else(|| anyhow::anyhow!("MemAvailable missing"))?;
+    if total <= 0.0 {
+        anyhow::bail!("MemTotal is zero");
+    }
+    Ok(((total - available) / total * 100.0).clamp(0.0, 100.0))
+}
+
+fn read_load_per_core() -> Result<f64> {
+    let loadavg = fs::read_to_string("/proc/loadavg")?;
+    let one_min: f64 = loadavg
+        .split_whitespace()
+        .next()
+        .ok_or_else(|| anyhow::anyhow!("empty /proc/loadavg"))?
+        .parse()?;
+    let cores = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1) as f64;
+    Ok(one_min / cores)
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn policy() -> LoadShedPolicy {
+        LoadShedPolicy {
+            mem_soft_pct: 70,
+            mem_high_pct: 85,
+            mem_hard_pct: 95,
+            cpu_soft: 1.0,
+            cpu_high: 2.0,
+            cpu_hard: 4.0,
+            sample_rate_pct: 25,
+            sample_records: 4096,
+        }
+    }
+
+    #[test]
+    fn test_level_takes_the_worse_of_memory_and_cpu() {
+        let policy = policy();
+        let level = |mem_used_pct, load_per_core| policy.level(&ResourceUsage { mem_used_pct, load_per_core });
+        assert_eq!(level(0.0, 0.0), PressureLevel::Normal);
+        assert_eq!(level(69.9, 0.99), PressureLevel::Normal);
+        // Watermarks are inclusive.
+        assert_eq!(level(70.0, 0.0), PressureLevel::Elevated);
+        assert_eq!(level(0.0, 2.0), PressureLevel::High);
+        assert_eq!(level(85.0, 1.5), PressureLevel::High);
+        assert_eq!(level(50.0, 4.0), PressureLevel::Critical);
+        assert_eq!(level(95.0, 0.0), PressureLevel::Critical);
+    }
+
+    #[test]
+    fn test_degradations_grow_with_pressure() {
+        let policy = policy();
+        let labels = |level| policy.degradations(level).iter().map(Degradation::label).collect::<Vec<_>>();
+        assert!(labels(PressureLevel::Normal).is_empty());
+        assert_eq!(labels(PressureLevel::Elevated), ["skip_fuzzy_dedup"]);
+        assert_eq!(labels(PressureLevel::High), ["skip_fuzzy_dedup", "reduced_sampling:25"]);
+        assert_eq!(policy.degradations(PressureLevel::Critical), policy.degradations(PressureLevel::High));
+    }
+}
@@ -1,17 +1,20 @@
 use anyhow::{Context, Result};
 use base64::Engine;
 use http_body_util::{BodyExt, Full};
-use hyper::{body::Incoming as Body, header::CONTENT_TYPE, http::StatusCode, Method, Request, Response};
+use hyper::{body::Incoming as Body, header::{ACCEPT_LANGUAGE, AUTHORIZATION, CONTENT_LANGUAGE, CONTENT_TYPE, ETAG, IF_NONE_MATCH, RETRY_AFTER}, http::StatusCode, Method, Request, Response};
 use hyper::body::Bytes;
 use hyper::server::conn::http1;
 use hyper::service::service_fn;
 use hyper_util::rt::TokioIo;
 use dotenvy::dotenv;
+use futures_util::StreamExt;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use std::{
-    env,
-    net::SocketAddr,
+    borrow::Cow,
+    collections::BTreeMap,
     path::Path,
+    sync::Arc,
     time::{SystemTime, UNIX_EPOCH},
 };
 use tracing::{error, info, instrument};
@@ -19,32 +22,288 @@ use tracing::{error, info, instrument};
 mod walrus_client;
 mod tee_attestation;
 mod quality_validator;
+mod load_shed;
+mod jobs;
+mod metrics;
+mod capabilities;
+mod config;
+mod app_state;
+mod blob_source;
+mod badge;
+mod i18n;
+mod integrity;
+mod category;
+mod audit;
+mod policy;
+mod chaos;
+mod api_keys;
+mod dedupe;
+mod sealed;
+mod archive;
+mod key_usage;
+mod cosign;
+mod http_source;
+mod errors;
+mod escrow;
+mod content_policy;
+mod s3_source;
+mod screening;
+mod ipfs_source;
+mod sampling;
+mod field_encryption;
+mod json_stream;
+mod csv_validator;
+mod columnar;
+mod image_decode;
+mod image_validator;
+mod text_validator;
+mod submission;
+mod pii;
+mod evidence;
+mod arbitration;
+mod near_dup;
+mod watchdog;
+mod scoring;
+mod listing;
+mod schemas;
+mod stream_stats;
+mod complexity;
+mod composite;
+mod opaque;
+mod labels;
+mod poisoning;
+mod data_contract;
+mod plugins;
+mod distribution;
+mod secrets;
+mod toxicity;
+mod transcript;
+mod drift;
+mod estimate;
+mod ngram;
+mod column_stats;
+mod timeseries;
+mod encoding;
+mod model_artifact;
+mod kms_seal;
+mod key_ring;
+mod line_records;
+#[cfg(test)]
+mod golden;
+
+use app_state::AppState;
+use tee_attestation::QualityClaim;
 
 #[derive(Deserialize)]
 struct VerificationRequest {
+    // Walrus blob ID, or a quilt patch as `quilt-patch:<id>` / `quilt:<quilt_id>/<identifier>`,
+    // an http(s) URL on the deployment's source allowlist (see http_source), `s3://bucket/key`
+    // or `ipfs://<cid>`.
+    // May be omitted when `sui_object_id` is given.
+    #[serde(default)]
     blob_id: String,
+    // Registered dataset object on Sui; its on-chain blob ID is what gets verified.
+    #[serde(default)]
+    sui_object_id: Option<String>,
     min_quality_threshold: u8,
+    #[serde(default)]
+    category: category::Category,
+    // Label column (CSV header name or JSON Lines key) of a labeled dataset; its class balance
+    // counts toward the bias score (see labels).
+    #[serde(default)]
+    label_column: Option<String>,
+    // Timestamp column (CSV header name or JSON Lines key) of a time series; its ordering,
+    // sampling regularity and age count toward consistency and freshness (see timeseries).
+    #[serde(default)]
+    timestamp_column: Option<String>,
+    // Parameter count claimed for a model artifact; checked against its tensors and counted
+    // toward consistency (see model_artifact).
+    #[serde(default)]
+    declared_parameters: Option<u64>,
+    // Data contract: a JSON Schema that JSON and JSON Lines records are validated against, inline
+    // or as the ID of a blob holding it in the clear (see data_contract). At most one of the two.
+    #[serde(default)]
+    json_schema: Option<serde_json::Value>,
+    #[serde(default)]
+    json_schema_blob_id: Option<String>,
+    // Earlier version of the dataset, verified by this service; the response and attestation
+    // carry how far the distribution moved from it (see drift).
+    #[serde(default)]
+    reference_blob_id: Option<String>,
+    // Expected SHA-256 (hex) of the blob bytes; fetched content must match it. Required except
+    // for IPFS blobs unless the deployment opts out (see integrity).
+    #[serde(default)]
+    content_sha256: Option<String>,
+    // High-value verification: the attestation also carries the operator's co-signature.
+    #[serde(default)]
+    co_sign: bool,
+    // "json" (the default) or "cose": the attestation as a COSE_Sign1 structure, for smaller calldata.
+    #[serde(default)]
+    envelope_encoding: tee_attestation::EnvelopeEncoding,
+    // Time-locked release: the signed result is held in escrow until this is met (see escrow).
+    #[serde(default)]
+    release: Option<escrow::ReleaseCondition>,
+    // Score a seeded record sample rather than the whole dataset (see sampling). Load shedding
+    // samples too; a seed given here is used then as well, so the sample can be reproduced.
+    #[serde(default)]
+    sample: Option<sampling::SampleRequest>,
+    // Base64 ed25519 keys of the parties to the trade (e.g. seller and buyer); together they can
+    // export the dispute bundle (see arbitration).
+    #[serde(default)]
+    parties: Vec<String>,
+    // Weights, minimums or aggregate formula for this request, within the bounds the operator
+    // allows (see scoring).
+    #[serde(default)]
+    scoring: Option<scoring::ScoringOverrides>,
+    // Tenant of the caller's API key, set by the handler; selects the content policy.
+    #[serde(skip)]
+    tenant: Option<String>,
+    // `?detail=full` on the request URL: include the per-check report.
+    #[serde(skip)]
+    detail: bool,
+    // Set by the batch handler under `"attestation": "merkle"`: return the attestation data
+    // unsigned, for the batch's one attestation to cover.
+    #[serde(skip)]
+    batch_leaf: bool,
+}
+
+#[derive(Deserialize)]
+struct BatchVerificationRequest {
+    items: Vec<VerificationRequest>,
+    // "per_item" (the default) or "merkle": one attestation over a Merkle root of the items'
+    // attestation data, and an inclusion proof per item (see tee_attestation::merkle).
+    #[serde(default)]
+    attestation: BatchAttestationMode,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+enum BatchAttestationMode {
+    #[default]
+    PerItem,
+    Merkle,
 }
 
 #[derive(Serialize)]
 struct VerificationResponse {
+    job_id: String,
     blob_id: String,
+    // "walrus", "http", "s3" or "ipfs"; attested alongside the score.
+    source_type: &'static str,
     quality_score: u8,
     is_valid: bool,
+    category: &'static str,
+    // Weights and aggregate formula behind the score, and any checks below their minimum.
+    scoring: scoring::ScoringSummary,
+    // 0..=100 risk of training-data poisoning, attested; absent when the check didn't run.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    poisoning_risk: Option<u8>,
+    // Sniffed dataset format ("csv", "jsonl", "parquet", ...); selects the checks that ran.
+    format: &'static str,
+    // Findings of the format-specific validators, e.g. where a truncated JSON upload stops.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    format_details: BTreeMap<&'static str, serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sui_object_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content_sha256: Option<String>,
+    // Attested hashes of the decrypted dataset and of the per-check report (see `report`).
+    #[serde(skip_serializing_if = "Option::is_none")]
+    plaintext_sha256: Option<String>,
+    report_sha256: String,
+    // Embedded executables / known-bad hash findings; absent when screening is off.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    screening: Option<screening::ScreeningReport>,
+    // Seed and size of the record sample the checks ran on, when sampled.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sampling: Option<sampling::SampleInfo>,
+    // Columns that arrived hashed or encrypted; scored without them.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    encrypted_fields: Vec<field_encryption::EncryptedField>,
+    // Empty for items of a Merkle batch, which carry the data and its proof instead.
     attestation: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    attestation_data: Option<tee_attestation::AttestationData>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    inclusion_proof: Option<tee_attestation::merkle::InclusionProof>,
     timestamp_ms: u64,
     nitro_enclave: bool,
+    // Optional work shed under resource pressure (empty when the full suite ran).
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    degradations: Vec<String>,
+    // Closest previously verified dataset, when similar enough to count as a near duplicate.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    near_duplicate_of: Option<dedupe::NearDuplicate>,
+    // Distribution drift from `reference_blob_id`, attested; absent without one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    drift: Option<drift::DriftReport>,
+    // SHA-256 of each check's sealed evidence, by check; the artifacts are admin-only.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    evidence: BTreeMap<String, String>,
+    // Parties who can jointly export the dispute bundle.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    parties: Vec<String>,
+    // Hints for checks that scored low, localized per Accept-Language.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    remediation: Vec<i18n::Remediation>,
+    // Per-check scores, weights and the values behind them; only with `?detail=full`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    report: Option<BTreeMap<&'static str, CheckDetail>>,
+}
+
+// One check of the detailed report, explained in the negotiated language.
+#[derive(Serialize)]
+struct CheckDetail {
+    #[serde(flatten)]
+    check: quality_validator::CheckReport,
+    #[serde(skip_serializing_if = "str::is_empty")]
+    explanation: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    remediation: Option<i18n::Remediation>,
+}
+
+impl CheckDetail {
+    fn new(name: &str, check: quality_validator::CheckReport, lang: i18n::Lang) -> Self {
+        let weak = check.score < 50;
+        Self {
+            explanation: i18n::message(&format!("CHECK_{}", name.to_ascii_uppercase()), lang),
+            remediation: quality_validator::remediation_code(name)
+                .filter(|_| weak)
+                .map(|code| i18n::Remediation::new(code, lang)),
+            check,
+        }
+    }
 }
 
 #[tokio::main]
 async fn main() -> Result<()> {
     dotenv().ok();
     init_tracing();
-    let listen_addr = env::var("NAUTILUS_LISTEN_ADDR").unwrap_or_else(|_| "0.0.0.0:3000".into());
-    let addr: SocketAddr = listen_addr
-        .parse()
-        .with_context(|| format!("Invalid NAUTILUS_LISTEN_ADDR '{}'", listen_addr))?;
+    // Operator key ceremony: `zkdatavault-nautilus keys <command>` manages the key store and exits.
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if args.first().map(String::as_str) == Some("keys") {
+        return api_keys::run_cli(&args[1..]);
+    }
+    // `zkdatavault-nautilus schemas <dir>` writes the published JSON Schema and TypeScript files.
+    if args.first().map(String::as_str) == Some("schemas") {
+        return schemas::run_cli(&args[1..]);
+    }
+    // `zkdatavault-nautilus verify-attestation <envelope.json>` checks a saved NSM attestation.
+    if args.first().map(String::as_str) == Some("verify-attestation") {
+        return tee_attestation::verify::run_cli(&args[1..]);
+    }
+    let config = config::Config::from_env()?;
+    let addr = config.listen_addr;
+    let state = Arc::new(AppState::build(config).await?);
     info!("Starting Nautilus TEE Service on {}", addr);
+    capabilities::log_banner(&state);
+    watchdog::log_last_shutdown(state.config.sealed_dir.as_deref());
+    spawn_watchdog(state.clone());
+    spawn_dedupe_compaction(state.clone());
+    spawn_audit_archival(state.clone());
+    spawn_escrow_release(state.clone());
+    spawn_chain_submission(state.clone());
+    spawn_key_rotation(state.clone());
 
     let listener = tokio::net::TcpListener::bind(addr)
         .await
@@ -53,9 +312,10 @@ async fn main() -> Result<()> {
     loop {
         let (stream, peer) = listener.accept().await?;
         info!(%peer, "Accepted connection");
+        let state = state.clone();
         tokio::spawn(async move {
             let io = TokioIo::new(stream);
-            let svc = service_fn(route);
+            let svc = service_fn(move |req| route(req, state.clone()));
             if let Err(err) = http1::Builder::new()
                 .serve_connection(io, svc)
                 .await
@@ -66,6 +326,161 @@ async fn main() -> Result<()> {
     }
 }
 
+fn spawn_watchdog(state: Arc<AppState>) {
+    if !state.watchdog.config().enabled() {
+        return;
+    }
+    tokio::spawn(async move {
+        let config = state.watchdog.config().clone();
+        let mut tick = tokio::time::interval(config.interval);
+        let (reason, usage) = loop {
+            tick.tick().await;
+            let usage = watchdog::usage();
+            metrics::set_gauge("nautilus_process_rss_bytes", "Resident memory of the service", &[], usage.rss_bytes as f64);
+            metrics::set_gauge("nautilus_process_open_fds", "Open file descriptors of the service", &[], usage.fds as f64);
+            if let Some(reason) = state.watchdog.breach(&usage) {
+                break (reason, usage);
+            }
+        };
+        state.watchdog.start_drain(reason, &usage);
+        let deadline = tokio::time::Instant::now() + config.drain_timeout;
+        while state.jobs.running() > 0 && tokio::time::Instant::now() < deadline {
+            tokio::time::sleep(std::time::Duration::from_millis(250)).await;
+        }
+        let record = state.watchdog.shutdown_record(reason, usage, state.jobs.running(), &state.config_hash);
+        match watchdog::sign(&record, &state.keys.current(), &state.key_usage) {
+            Ok(signed) => {
+                if let Err(err) = watchdog::persist(state.config.sealed_dir.as_deref(), &signed) {
+                    error!(err = %format!("{:#}", err), "Persisting shutdown record failed");
+                }
+            }
+            Err(err) => error!(%err, ?record, "Signing shutdown record failed"),
+        }
+        std::process::exit(watchdog::RESTART_EXIT_CODE);
+    });
+}
+
+fn spawn_dedupe_compaction(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(state.dedupe.compact_interval());
+        loop {
+            tick.tick().await;
+            let index = state.clone();
+            match tokio::task::spawn_blocking(move || index.dedupe.compact()).await {
+                Ok(Ok(removed)) => info!(removed, "Compacted dedupe index"),
+                Ok(Err(err)) => error!(%err, "Dedupe index compaction failed"),
+                Err(err) => error!(%err, "Dedupe index compaction panicked"),
+            }
+            let profiles = state.clone();
+            match tokio::task::spawn_blocking(move || profiles.drift.compact()).await {
+                Ok(Ok(removed)) => info!(removed, "Compacted drift profiles"),
+                Ok(Err(err)) => error!(%err, "Drift profile compaction failed"),
+                Err(err) => error!(%err, "Drift profile compaction panicked"),
+            }
+        }
+    });
+}
+
+fn spawn_audit_archival(state: Arc<AppState>) {
+    if state.archive.config().after.is_zero() {
+        return;
+    }
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(state.archive.config().interval);
+        loop {
+            tick.tick().await;
+            let key = state.keys.current();
+            let run = state.archive.run_once(
+                &state.audit,
+                state.blobs.as_ref(),
+                &key,
+                &state.key_usage,
+                &state.config_hash,
+            );
+            if let Err(err) = run.await {
+                error!(err = %format!("{:#}", err), "Audit archival failed; will retry");
+            }
+        }
+    });
+}
+
+fn spawn_escrow_release(state: Arc<AppState>) {
+    tokio::spawn(async move {
+        let mut tick = tokio::time::interval(state.escrow.config().poll_interval);
+        loop {
+            tick.tick().await;
+            match state.escrow.release_due(state.blobs.as_ref()).await {
+                Ok(released) if released.is_empty() => {}
+                Ok(released) => {
+                    info!(released = released.len(), "Released escrowed verification results");
+                    for released in released {
+                        publish_released(&state, released);
+                    }
+                }
+                Err(err) => error!(%err, "Escrow release check failed"),
+            }
+        }
+    });
+}
+
+// A released result's attestation joins its transcript entry, which until now held only its hash,
+// and goes on chain as an unescrowed one would have at delivery.
+fn publish_released(state: &AppState, released: escrow::Released) {
+    let result = &released.result;
+    let attestation = result["attestation"].as_str().unwrap_or_default();
+    let attestation = base64::engine::general_purpose::STANDARD.decode(attestation).unwrap_or_default();
+    if attestation.is_empty() {
+        return;
+    }
+    if let Err(err) = state.transcript.disclose(&released.job_id, &attestation) {
+        error!(%err, job_id = %released.job_id, "Disclosing released attestation in the transcript failed");
+    }
+    let (Some(blob_id), Some(quality_score)) =
+        (result["blob_id"].as_str(), result["quality_score"].as_u64().and_then(|s| u8::try_from(s).ok()))
+    else {
+        error!(job_id = %released.job_id, "Released result lacks blob_id or quality_score; not submitted");
+        return;
+    };
+    let submission =
+        submission::Submission { job_id: released.job_id, blob_id: blob_id.to_string(), quality_score, attestation };
+    state.submitter.enqueue(&state.jobs, submission);
+}
+
+fn spawn_chain_submission(state: Arc<AppState>) {
+    let Some(mut queue) = state.submitter.take_queue() else {
+        return;
+    };
+    tokio::spawn(async move {
+        while let Some(submission) = queue.recv().await {
+            let state = state.clone();
+            tokio::spawn(async move {
+                let chain = state.blobs.as_ref();
+                let key = state.keys.current();
+                state.submitter.run(&submission, chain, &key, &state.key_usage, &state.jobs).await;
+            });
+        }
+    });
+}
+
+fn spawn_key_rotation(state: Arc<AppState>) {
+    let Some(interval) = state.keys.policy().interval else {
+        return;
+    };
+    tokio::spawn(async move {
+        // Checked more often than the interval so a restart part-way through it does not reset it.
+        let mut tick = tokio::time::interval(interval.min(std::time::Duration::from_secs(60)));
+        loop {
+            tick.tick().await;
+            if !state.keys.rotation_due() {
+                continue;
+            }
+            if let Err(err) = state.keys.rotate().await {
+                error!(err = %format!("{:#}", err), "Signing key rotation failed; will retry");
+            }
+        }
+    });
+}
+
 fn init_tracing() {
     use tracing_subscriber::{EnvFilter, FmtSubscriber};
     let filter = EnvFilter::try_from_default_env().unwrap_or_else(|_| EnvFilter::new("info"));
@@ -74,23 +489,290 @@ fn init_tracing() {
 }
 
 #[instrument(skip_all)]
-async fn route(req: Request<Body>) -> Result<Response<Full<Bytes>>, hyper::Error> {
+async fn route(req: Request<Body>, state: Arc<AppState>) -> Result<Response<Full<Bytes>>, hyper::Error> {
+    let tenant = match authorize(&req, &state) {
+        Ok(tenant) => tenant,
+        Err(err) => return Ok(error_response("UNAUTHORIZED", &err, request_lang(&req))),
+    };
     match (req.method(), req.uri().path()) {
+        (&Method::GET, "/health") if state.watchdog.draining() => {
+            Ok(text_response(StatusCode::SERVICE_UNAVAILABLE, "Draining for restart"))
+        }
         (&Method::GET, "/health") => {
             let body = "Nautilus TEE Service Running";
             Ok(text_response(StatusCode::OK, body))
         }
         (&Method::POST, "/verify") => {
-            match handle_verification(req).await {
+            let lang = request_lang(&req);
+            match handle_verification(req, &state, tenant, lang).await {
                 Ok(resp) => {
                     let json = serde_json::to_vec(&resp).unwrap_or_else(|_| b"{}".to_vec());
-                    Ok(json_response(StatusCode::OK, json))
+                    Ok(localized(json_response(StatusCode::OK, json), lang))
                 }
                 Err(err) => {
                     error!(%err, "Verification failed");
-                    let msg = format!(r#"{{"error":"{}"}}"#, err);
-                    Ok(json_response(StatusCode::BAD_REQUEST, msg.into_bytes()))
+                    Ok(error_response(errors::code(&err), &err, lang))
+                }
+            }
+        }
+        (&Method::POST, "/verify/batch") => {
+            let lang = request_lang(&req);
+            match handle_batch_verification(req, &state, tenant, lang).await {
+                Ok((results, batch_attestation)) => {
+                    let mut json = serde_json::json!({ "results": results });
+                    if let Some(batch) = batch_attestation {
+                        match serde_json::to_value(batch) {
+                            Ok(batch) => json["batch_attestation"] = batch,
+                            Err(err) => {
+                                let err = anyhow::Error::new(err).context("serialize batch attestation");
+                                error!(%err, "Batch verification failed");
+                                return Ok(error_response("INTERNAL_ERROR", &err, lang));
+                            }
+                        }
+                    }
+                    Ok(localized(json_response(StatusCode::OK, json.to_string().into_bytes()), lang))
                 }
+                Err(err) => Ok(error_response("INVALID_REQUEST", &err, lang)),
+            }
+        }
+        (&Method::POST, "/policy/simulate") => {
+            let lang = request_lang(&req);
+            let result = match collect_body(req.into_body()).await {
+                Ok(body) => serde_json::from_slice::<policy::CandidatePolicy>(&body)
+                    .context("Invalid policy")
+                    .and_then(|p| policy::simulate(&p, &state.audit.since(p.since_ms))),
+                Err(err) => Err(err),
+            };
+            match result {
+                Ok(res) => Ok(json_response(StatusCode::OK, serde_json::to_vec(&res).unwrap_or_default())),
+                Err(err) => Ok(error_response("INVALID_POLICY", &err, lang)),
+            }
+        }
+        (&Method::POST, "/disputes/export") => {
+            let lang = request_lang(&req);
+            let exported = match collect_body(req.into_body()).await {
+                Ok(body) => serde_json::from_slice::<arbitration::ExportRequest>(&body)
+                    .context("Invalid JSON body")
+                    .and_then(|r| state.arbitration.export(&r, &state)),
+                Err(err) => Err(err),
+            };
+            match exported {
+                Ok(Some(bundle)) => Ok(json_response(StatusCode::OK, serde_json::to_vec(&bundle).unwrap_or_default())),
+                Ok(None) => Ok(json_response(StatusCode::NOT_FOUND, br#"{"error":"no delivered result for job"}"#.to_vec())),
+                Err(err) => Ok(error_response(errors::code(&err), &err, lang)),
+            }
+        }
+        // Checks an attestation envelope for callers without the crypto to do it themselves.
+        (&Method::POST, "/attestation/verify") => {
+            let lang = request_lang(&req);
+            let parsed = match collect_body(req.into_body()).await {
+                Ok(body) => serde_json::from_slice::<tee_attestation::verify::VerifyRequest>(&body)
+                    .context("Invalid attestation verification request"),
+                Err(err) => Err(err),
+            };
+            match parsed {
+                Ok(mut request) => {
+                    // Callers that pin no enclave key are checking this service's attestations.
+                    if request.expected_public_keys.is_empty() {
+                        request.expected_public_keys = own_public_keys(&state);
+                    }
+                    let verdict = tee_attestation::verify::verdict(&request);
+                    Ok(json_response(StatusCode::OK, serde_json::to_vec(&verdict).unwrap_or_default()))
+                }
+                Err(err) => Ok(error_response("INVALID_REQUEST", &err, lang)),
+            }
+        }
+        // Folds bls12381 attestations from replicas over the same blob into one quorum signature.
+        (&Method::POST, "/attestation/aggregate") => {
+            let lang = request_lang(&req);
+            let aggregated = match collect_body(req.into_body()).await {
+                Ok(body) => serde_json::from_slice::<Vec<tee_attestation::AttestationEnvelope>>(&body)
+                    .context("Invalid JSON body: expected an array of attestation envelopes")
+                    .and_then(|envelopes| tee_attestation::bls::aggregate(&envelopes)),
+                Err(err) => Err(err),
+            };
+            match aggregated {
+                Ok(quorum) => Ok(json_response(StatusCode::OK, serde_json::to_vec(&quorum).unwrap_or_default())),
+                Err(err) => Ok(error_response("INVALID_REQUEST", &err, lang)),
+            }
+        }
+        // Fresh, nonce-bound evidence of the enclave's current key (see tee_attestation::identity).
+        (&Method::GET, "/attestation") => {
+            let nonce = match tee_attestation::identity::parse_nonce(query_param(req.uri().query(), "nonce").as_deref()) {
+                Ok(nonce) => nonce,
+                Err(err) => return Ok(error_response("INVALID_REQUEST", &err, request_lang(&req))),
+            };
+            let key = state.keys.current();
+            let scheme = state.config.signature_scheme;
+            match tee_attestation::identity::attest(&key, scheme, &state.key_usage, &nonce, &state.config_hash) {
+                Ok(identity) => Ok(json_response(StatusCode::OK, serde_json::to_vec(&identity).unwrap_or_default())),
+                Err(err) => Ok(error_response(errors::code(&err), &err, request_lang(&req))),
+            }
+        }
+        // Current and still-verifying retired signing keys, for verifiers tracking rotations.
+        (&Method::GET, "/keys") => {
+            let body = serde_json::json!({
+                "keys": state.keys.list(),
+                "rotation_interval_secs": state.keys.policy().interval.map(|i| i.as_secs()),
+                "grace_secs": state.keys.policy().grace.as_secs(),
+            });
+            Ok(json_response(StatusCode::OK, body.to_string().into_bytes()))
+        }
+        (&Method::GET, "/build-info") => {
+            let body = serde_json::json!({
+                "service": "nautilus",
+                "version": env!("CARGO_PKG_VERSION"),
+                "config_hash": state.config_hash,
+                "config": state.config.effective(),
+            });
+            Ok(json_response(StatusCode::OK, body.to_string().into_bytes()))
+        }
+        (&Method::GET, "/capabilities") => {
+            let json = serde_json::to_vec(&capabilities::current(&state)).unwrap_or_else(|_| b"{}".to_vec());
+            Ok(json_response(StatusCode::OK, json))
+        }
+        (&Method::GET, "/schemas") => Ok(json_response(StatusCode::OK, schemas::index().to_string().into_bytes())),
+        (&Method::GET, path) if path.starts_with("/schemas/") => match schemas::render(&path["/schemas/".len()..]) {
+            Some((body, content_type)) => {
+                let mut resp = text_response(StatusCode::OK, &body);
+                resp.headers_mut().insert(CONTENT_TYPE, content_type.parse().unwrap());
+                Ok(resp)
+            }
+            None => Ok(json_response(StatusCode::NOT_FOUND, br#"{"error":"no such schema"}"#.to_vec())),
+        },
+        (&Method::HEAD, path) if path.starts_with("/blobs/") => {
+            let blob_id = &path["/blobs/".len()..];
+            let status = match state.blobs.blob_exists(blob_id).await {
+                Ok(true) => StatusCode::OK,
+                Ok(false) => StatusCode::NOT_FOUND,
+                Err(err) => {
+                    error!(%err, "Blob existence check failed");
+                    StatusCode::BAD_GATEWAY
+                }
+            };
+            Ok(text_response(status, ""))
+        }
+        #[cfg(feature = "chaos")]
+        (&Method::GET, "/admin/chaos") => {
+            let json = serde_json::to_vec(&chaos::current()).unwrap_or_else(|_| b"{}".to_vec());
+            Ok(json_response(StatusCode::OK, json))
+        }
+        #[cfg(feature = "chaos")]
+        (&Method::PUT, "/admin/chaos") => {
+            let updated = match collect_body(req.into_body()).await {
+                Ok(body) => chaos::update(&body),
+                Err(err) => Err(err),
+            };
+            match updated {
+                Ok(cfg) => Ok(json_response(StatusCode::OK, serde_json::to_vec(&cfg).unwrap_or_default())),
+                Err(err) => Ok(json_response(StatusCode::BAD_REQUEST, format!(r#"{{"error":"{:#}"}}"#, err).into_bytes())),
+            }
+        }
+        (&Method::GET, "/admin/signing") => {
+            let json = serde_json::to_vec(&state.key_usage.snapshot()).unwrap_or_default();
+            Ok(json_response(StatusCode::OK, json))
+        }
+        (&Method::POST, "/admin/signing/rearm") => {
+            let json = serde_json::to_vec(&state.key_usage.rearm()).unwrap_or_default();
+            Ok(json_response(StatusCode::OK, json))
+        }
+        (&Method::GET, "/admin/keys") => match &state.api_keys {
+            Some(keys) => Ok(list_response(&req, keys.registry())),
+            None => Ok(json_response(StatusCode::NOT_FOUND, br#"{"error":"API keys are not enabled"}"#.to_vec())),
+        },
+        (&Method::GET, "/policies") => match &state.content_policies {
+            Some(policies) => Ok(list_response(&req, policies.entries())),
+            None => Ok(json_response(StatusCode::NOT_FOUND, br#"{"error":"content policies are not enabled"}"#.to_vec())),
+        },
+        (&Method::GET, path) if path.starts_with("/admin/evidence/") => {
+            let job_id = &path["/admin/evidence/".len()..];
+            match state.evidence.get(job_id) {
+                Ok(artifacts) if artifacts.is_empty() => {
+                    Ok(json_response(StatusCode::NOT_FOUND, br#"{"error":"no evidence for job"}"#.to_vec()))
+                }
+                Ok(artifacts) => {
+                    let json = serde_json::json!({ "job_id": job_id, "evidence": artifacts }).to_string();
+                    Ok(json_response(StatusCode::OK, json.into_bytes()))
+                }
+                Err(err) => Ok(error_response("INTERNAL_ERROR", &err, request_lang(&req))),
+            }
+        }
+        (&Method::GET, "/metrics") => {
+            let mut resp = text_response(StatusCode::OK, &metrics::render());
+            resp.headers_mut().insert(CONTENT_TYPE, "text/plain; version=0.0.4".parse().unwrap());
+            Ok(resp)
+        }
+        (&Method::GET, "/badge/verify") => {
+            let token = query_param(req.uri().query(), "t").unwrap_or_default();
+            // Badges issued before a rotation stay valid while their key is in its grace window.
+            let keys = state.keys.verifying_keys();
+            let verified = keys[1..].iter().fold(badge::verify(&token, &keys[0]), |verified, key| {
+                verified.or_else(|_| badge::verify(&token, key))
+            });
+            match verified {
+                Ok(claims) => Ok(json_response(StatusCode::OK, serde_json::to_vec(&claims).unwrap_or_default())),
+                Err(err) => Ok(json_response(
+                    StatusCode::BAD_REQUEST,
+                    serde_json::json!({ "error": err.to_string() }).to_string().into_bytes(),
+                )),
+            }
+        }
+        (&Method::GET, "/audit/archives") => {
+            let listing = match query_param(req.uri().query(), "job_id") {
+                Some(job_id) => state.archive.find_job(&job_id).map(|e| e.into_iter().collect::<Vec<_>>()),
+                None => state.archive.list(),
+            };
+            match listing {
+                Ok(entries) => {
+                    let json = serde_json::json!({ "archives": entries }).to_string();
+                    Ok(json_response(StatusCode::OK, json.into_bytes()))
+                }
+                Err(err) => Ok(error_response("INTERNAL_ERROR", &err, request_lang(&req))),
+            }
+        }
+        (&Method::GET, path) if path.starts_with("/badge/") => {
+            let job_id = &path["/badge/".len()..];
+            let as_svg = query_param(req.uri().query(), "format").as_deref() == Some("svg");
+            Ok(badge_response(&state, job_id, as_svg))
+        }
+        (&Method::GET, "/attestations") => {
+            let query = req.uri().query();
+            let since = query_param(query, "since").map(|s| s.parse::<u64>());
+            let limit = query_param(query, "limit").map(|l| l.parse::<usize>());
+            match (since.transpose(), limit.transpose()) {
+                (Ok(since), Ok(limit)) => match state.transcript.since(since.unwrap_or(0), limit) {
+                    Ok(page) => Ok(json_response(StatusCode::OK, serde_json::to_vec(&page).unwrap_or_default())),
+                    Err(err) => Ok(error_response("INTERNAL_ERROR", &err, request_lang(&req))),
+                },
+                _ => Ok(json_response(
+                    StatusCode::BAD_REQUEST,
+                    br#"{"error":"since and limit must be non-negative integers"}"#.to_vec(),
+                )),
+            }
+        }
+        (&Method::GET, "/escrow") => match state.escrow.pending() {
+            Ok(pending) => {
+                let json = serde_json::json!({ "pending": pending }).to_string();
+                Ok(json_response(StatusCode::OK, json.into_bytes()))
+            }
+            Err(err) => Ok(error_response("INTERNAL_ERROR", &err, request_lang(&req))),
+        },
+        (&Method::GET, path) if path.starts_with("/escrow/") => {
+            let job_id = &path["/escrow/".len()..];
+            match state.escrow.get(job_id) {
+                Ok(Some(status)) => Ok(json_response(StatusCode::OK, serde_json::to_vec(&status).unwrap_or_default())),
+                Ok(None) => Ok(json_response(StatusCode::NOT_FOUND, br#"{"error":"no escrowed result for job"}"#.to_vec())),
+                Err(err) => Ok(error_response("INTERNAL_ERROR", &err, request_lang(&req))),
+            }
+        }
+        (&Method::GET, path) if path.starts_with("/jobs/") => {
+            let job_id = &path["/jobs/".len()..];
+            match state.jobs.get(job_id) {
+                Some(status) => {
+                    let json = serde_json::to_vec(&status).unwrap_or_else(|_| b"{}".to_vec());
+                    Ok(json_response(StatusCode::OK, json))
+                }
+                None => Ok(json_response(StatusCode::NOT_FOUND, br#"{"error":"job not found"}"#.to_vec())),
             }
         }
         _ => {
@@ -101,49 +783,878 @@ async fn route(req: Request<Body>) -> Result<Response<Full<Bytes>>, hyper::Error
 }
 
 #[instrument(skip_all)]
-async fn handle_verification(req: Request<Body>) -> Result<VerificationResponse> {
+async fn handle_verification(
+    req: Request<Body>,
+    state: &AppState,
+    tenant: Option<String>,
+    lang: i18n::Lang,
+) -> Result<serde_json::Value> {
     // 1) Parse request
+    let detail = detail_requested(&req);
     let body_bytes = collect_body(req.into_body()).await?;
-    let vr: VerificationRequest =
+    let mut vr: VerificationRequest =
         serde_json::from_slice(&body_bytes).context("Invalid JSON body")?;
-    info!(blob_id = %vr.blob_id, min_quality = vr.min_quality_threshold, "Verification request");
+    vr.tenant = tenant;
+    vr.detail = detail;
+    let vr = resolve_blob_ref(state, vr).await?;
+    info!(blob_id = %vr.blob_id, min_quality = vr.min_quality_threshold, category = vr.category.as_str(), "Verification request");
+
+    let mut job = state.jobs.start(&vr.blob_id);
+    let release = vr.release.clone();
+    match run_verification(state, vr, &mut job, lang, None).await.and_then(|resp| deliver(state, release, resp)) {
+        Ok(resp) => {
+            job.complete();
+            Ok(resp)
+        }
+        Err(err) => {
+            job.fail(&err);
+            Err(err)
+        }
+    }
+}
+
+// Download every blob in the batch concurrently, then verify each as its own job.
+// Per-item failures are reported in place; only a malformed batch fails the whole request.
+async fn handle_batch_verification(
+    req: Request<Body>,
+    state: &AppState,
+    tenant: Option<String>,
+    lang: i18n::Lang,
+) -> Result<(Vec<serde_json::Value>, Option<tee_attestation::merkle::BatchAttestation>)> {
+    let detail = detail_requested(&req);
+    let body_bytes = collect_body(req.into_body()).await?;
+    let mut batch: BatchVerificationRequest = serde_json::from_slice(&body_bytes).context("Invalid JSON body")?;
+    anyhow::ensure!(
+        batch.items.len() <= state.config.batch_max_items,
+        "batch of {} exceeds the limit of {} items",
+        batch.items.len(),
+        state.config.batch_max_items
+    );
+    info!(items = batch.items.len(), "Batch verification request");
+    let merkle = batch.attestation == BatchAttestationMode::Merkle;
+    if merkle {
+        // The root attestation is the enclave's alone, in the batch's own format.
+        anyhow::ensure!(
+            !state.config.cosign.always && batch.items.iter().all(|vr| !vr.co_sign),
+            "co_sign is not available with Merkle batch attestation"
+        );
+        anyhow::ensure!(
+            batch.items.iter().all(|vr| vr.envelope_encoding == tee_attestation::EnvelopeEncoding::Json),
+            "envelope_encoding does not apply to Merkle batch attestation"
+        );
+    }
+    for vr in &mut batch.items {
+        vr.tenant = tenant.clone();
+        vr.detail = detail;
+        vr.batch_leaf = merkle;
+    }
 
-    // 2) Fetch encrypted blob from Walrus
-    let encrypted = walrus_client::fetch_blob(&vr.blob_id).await
-        .with_context(|| format!("Failed to fetch Walrus blob {}", vr.blob_id))?;
-    info!(size = encrypted.len(), "Fetched encrypted blob");
+    let items = futures_util::future::join_all(batch.items.into_iter().map(|vr| async move {
+        let reference = vr.sui_object_id.clone().unwrap_or_else(|| vr.blob_id.clone());
+        resolve_blob_ref(state, vr).await.map_err(|err| (reference, err))
+    }))
+    .await;
+    let ids: Vec<String> = items.iter().flatten().map(|vr| vr.blob_id.clone()).collect();
+    let limit = state.config.job_memory_cap;
+    let mut fetched = state.blobs.fetch_blobs(&ids, limit, state.config.fetch_parallelism).await.into_iter();
 
-    // 3) Decrypt using Seal key shares (placeholder)
-    let plaintext = decrypt_placeholder(&encrypted).context("Decrypt placeholder failed")?;
+    let mut results = Vec::with_capacity(items.len());
+    // Merkle batch items that verified, delivered once the root is attested: (index in
+    // `results`, job, release, response).
+    let mut leaves = Vec::new();
+    for item in items {
+        let vr = match item {
+            Ok(vr) => vr,
+            Err((reference, err)) => {
+                error!(%err, %reference, "Batch item could not be resolved");
+                results.push(item_error(&reference, &err, lang));
+                continue;
+            }
+        };
+        let bytes = fetched.next().expect("one fetch per resolved item");
+        let blob_id = vr.blob_id.clone();
+        let mut job = state.jobs.start(&blob_id);
+        let release = vr.release.clone();
+        let outcome = match bytes {
+            Ok(bytes) => run_verification(state, vr, &mut job, lang, Some(bytes)).await,
+            Err(err) => Err(fetch_error(err, &blob_id, limit, &job)),
+        };
+        match outcome {
+            Ok(resp) if merkle => {
+                leaves.push((results.len(), job, release, resp));
+                results.push(serde_json::Value::Null);
+            }
+            outcome => match outcome.and_then(|resp| deliver(state, release, resp)) {
+                Ok(resp) => {
+                    job.complete();
+                    results.push(resp);
+                }
+                Err(err) => {
+                    error!(%err, %blob_id, "Batch item failed");
+                    job.fail(&err);
+                    results.push(item_error(&blob_id, &err, lang));
+                }
+            },
+        }
+    }
+    if leaves.is_empty() {
+        return Ok((results, None));
+    }
 
-    // 4) Validate quality
-    let quality_score = quality_validator::validate_dataset_quality(&plaintext)
-        .context("Quality validation failed")?;
-    let is_valid = quality_score >= vr.min_quality_threshold;
+    let data: Vec<_> = leaves.iter().filter_map(|(_, _, _, resp)| resp.attestation_data.as_ref()).collect();
+    let attested = attest_batch(state, &data);
+    let mut proofs = attested.as_ref().map(|(_, proofs)| proofs.clone()).unwrap_or_default().into_iter();
+    for (index, job, release, mut resp) in leaves {
+        let blob_id = resp.blob_id.clone();
+        let delivered = match &attested {
+            Ok(_) => {
+                resp.inclusion_proof = proofs.next();
+                deliver(state, release, resp)
+            }
+            Err(_) => Err(anyhow::anyhow!("batch attestation failed")),
+        };
+        results[index] = match delivered {
+            Ok(resp) => {
+                job.complete();
+                resp
+            }
+            Err(err) => {
+                // The batch attestation's own error, so items get its code (e.g. SIGNING_LOCKED).
+                let err = attested.as_ref().err().unwrap_or(&err);
+                error!(%err, %blob_id, "Batch item failed");
+                job.fail(err);
+                item_error(&blob_id, err, lang)
+            }
+        };
+    }
+    Ok((results, attested.ok().map(|(batch, _)| batch)))
+}
+
+// One attestation over the Merkle root of a batch's attestation data. The transcript records it
+// under job ID "batch", with the root as blob ID.
+fn attest_batch(
+    state: &AppState,
+    data: &[&tee_attestation::AttestationData],
+) -> Result<(tee_attestation::merkle::BatchAttestation, Vec<tee_attestation::merkle::InclusionProof>)> {
+    let key = state.keys.current();
+    let (scheme, encoding) = (state.config.signature_scheme, state.config.payload_encoding);
+    let (batch, proofs) =
+        tee_attestation::merkle::attest(&key, scheme, encoding, &state.key_usage, data, &state.config_hash)?;
+    let bytes = serde_json::to_vec(&batch).context("serialize batch attestation")?;
+    state
+        .transcript
+        .append("batch", &batch.statement.merkle_root, &bytes, false, &key, &state.key_usage)
+        .context("Failed to record batch attestation in the transcript")?;
+    Ok((batch, proofs))
+}
+
+// The response as returned to the caller. Escrowed verifications only get their pending release
+// back; the result itself is held until the release condition is met. Only released results are
+// recorded on chain, where anyone could read them (escrowed ones by publish_released); an
+// unattested result, or a Merkle batch item (whose proof the contract can't check), has nothing
+// to record.
+fn deliver(state: &AppState, release: Option<escrow::ReleaseCondition>, resp: VerificationResponse) -> Result<serde_json::Value> {
+    let result = serde_json::to_value(&resp).context("serialize verification response")?;
+    // Kept for disputes; exports of escrowed results wait for the release.
+    if let Err(err) = state.arbitration.record(&resp.job_id, &resp.blob_id, &resp.parties, &result) {
+        error!(%err, "Recording report for arbitration failed");
+    }
+    match release {
+        Some(release) => {
+            let pending = state.escrow.hold(&resp.job_id, &resp.blob_id, release, &result)?;
+            Ok(serde_json::to_value(escrow::EscrowStatus::Held(pending))?)
+        }
+        None => {
+            let attestation = base64::engine::general_purpose::STANDARD.decode(&resp.attestation).unwrap_or_default();
+            if !attestation.is_empty() {
+                let submission = submission::Submission {
+                    job_id: resp.job_id,
+                    blob_id: resp.blob_id,
+                    quality_score: resp.quality_score,
+                    attestation,
+                };
+                state.submitter.enqueue(&state.jobs, submission);
+            }
+            Ok(result)
+        }
+    }
+}
+
+fn item_error(blob_id: &str, err: &anyhow::Error, lang: i18n::Lang) -> serde_json::Value {
+    let code = errors::code(err);
+    let mut body = serde_json::json!({
+        "blob_id": blob_id,
+        "error": format!("{:#}", err),
+        "code": code,
+        "category": errors::spec(code).category,
+        "message": i18n::message(code, lang),
+    });
+    add_expiry_epoch(&mut body, err);
+    body
+}
+
+// Expired and uncertified blobs carry their storage end epoch so the marketplace can ask the
+// provider to re-store them.
+fn add_expiry_epoch(body: &mut serde_json::Value, err: &anyhow::Error) {
+    let end_epoch = match err.downcast_ref::<walrus_client::WalrusError>() {
+        Some(walrus_client::WalrusError::Expired { end_epoch, .. }) => Some(*end_epoch),
+        Some(walrus_client::WalrusError::NotCertified { end_epoch }) => *end_epoch,
+        _ => None,
+    };
+    if let Some(end_epoch) = end_epoch {
+        body["expiry_epoch"] = end_epoch.into();
+    }
+}
+
+// Settle which blob a request refers to. With `sui_object_id` the blob comes from the on-chain
+// dataset object, and an explicit `blob_id` must agree with it.
+async fn resolve_blob_ref(state: &AppState, mut vr: VerificationRequest) -> Result<VerificationRequest> {
+    if let Some(release) = &vr.release {
+        release.validate()?;
+    }
+    for party in &vr.parties {
+        arbitration::parse_public_key(party).context("Invalid party key")?;
+    }
+    if let Some(column) = &vr.label_column {
+        anyhow::ensure!((1..=64).contains(&column.len()), "label_column must be 1 to 64 bytes");
+    }
+    if let Some(column) = &vr.timestamp_column {
+        anyhow::ensure!((1..=64).contains(&column.len()), "timestamp_column must be 1 to 64 bytes");
+    }
+    anyhow::ensure!(vr.declared_parameters != Some(0), "declared_parameters must be positive");
+    anyhow::ensure!(
+        vr.envelope_encoding != tee_attestation::EnvelopeEncoding::Cose
+            || state.config.signature_scheme != tee_attestation::SignatureScheme::Bls12381,
+        "envelope_encoding cose is not available under the bls12381 signature scheme"
+    );
+    anyhow::ensure!(
+        vr.json_schema.is_none() || vr.json_schema_blob_id.is_none(),
+        "json_schema and json_schema_blob_id are mutually exclusive"
+    );
+    let Some(object_id) = vr.sui_object_id.as_deref() else {
+        anyhow::ensure!(!vr.blob_id.is_empty(), "blob_id or sui_object_id is required");
+        return Ok(vr);
+    };
+    let registered = state.blobs.resolve_sui_object(object_id).await?;
+    if !vr.blob_id.is_empty() && vr.blob_id != registered {
+        anyhow::bail!("blob_id {} is not the blob {} registered on Sui object {}", vr.blob_id, registered, object_id);
+    }
+    vr.blob_id = registered;
+    Ok(vr)
+}
+
+async fn run_verification(
+    state: &AppState,
+    vr: VerificationRequest,
+    job: &mut jobs::Job,
+    lang: i18n::Lang,
+    prefetched: Option<Vec<u8>>,
+) -> Result<VerificationResponse> {
+    // Shed optional work (or refuse outright) before committing memory to the download.
+    job.set_stage("admission");
+    // IPFS fetches check every block against its CID, so the blob ID already pins the content.
+    let content_addressed = blob_source::source_type(&vr.blob_id) == blob_source::IPFS;
+    if state.config.require_content_digest && vr.content_sha256.is_none() && !content_addressed {
+        anyhow::bail!("content_sha256 is required by this deployment");
+    }
+    if state.watchdog.draining() {
+        return Err(watchdog::Draining.into());
+    }
+    let degradations = match load_shed::assess(&state.config.load_shed) {
+        load_shed::ShedDecision::Proceed(d) => d,
+        load_shed::ShedDecision::Reject(level) => return Err(load_shed::Overloaded(level).into()),
+    };
+    // A digest can only be checked over the whole blob, which load shedding says not to fetch now.
+    let sampled = degradations.iter().any(|d| matches!(d, load_shed::Degradation::ReducedSampling { .. }));
+    if sampled && vr.content_sha256.is_some() {
+        return Err(load_shed::Overloaded(load_shed::PressureLevel::High).into());
+    }
+    let mut opts = quality_validator::ValidationOptions {
+        category: vr.category,
+        label_column: vr.label_column.clone(),
+        timestamp_column: vr.timestamp_column.clone(),
+        declared_parameters: vr.declared_parameters,
+        contract: load_contract(state, &vr).await?,
+        ..Default::default()
+    };
+    for d in &degradations {
+        match d {
+            load_shed::Degradation::SkipFuzzyDedup => opts.skip_dedup = true,
+            load_shed::Degradation::ReducedSampling { rate_pct } => opts.sample_rate_pct = *rate_pct,
+        }
+    }
+    let mut beacon = None;
+    if vr.sample.is_some() || opts.sample_rate_pct < 100 {
+        let size = state.config.load_shed.sample_records;
+        opts.sample = Some(match state.config.sample_seed_source {
+            sampling::SeedSource::Local => sampling::SampleSpec::resolve(vr.sample, size)?,
+            sampling::SeedSource::Sui => {
+                // The round out now is the commitment; the seed comes from a later one.
+                let committed = state.blobs.sui_randomness().await.context("Sui randomness for the sample seed")?.round;
+                let round = sampling::beacon_after(committed, || state.blobs.sui_randomness())
+                    .await
+                    .context("Sui randomness for the sample seed")?;
+                let spec = sampling::SampleSpec::from_beacon(vr.sample, size, &round, &vr.blob_id)?;
+                beacon = Some(round);
+                spec
+            }
+        });
+    }
+    // A chunk sample is fetched and scored apart from record samples (see estimate).
+    let chunk_sample = opts.sample.filter(|s| s.chunks.is_some());
+    if chunk_sample.is_some() {
+        anyhow::ensure!(vr.content_sha256.is_none(), "content_sha256 can't be checked against a chunk sample");
+        opts.sample = None;
+    }
+    let degradations: Vec<String> = degradations.iter().map(|d| d.label()).collect();
+    let reference = match vr.reference_blob_id.as_deref() {
+        Some(id) => Some(state.drift.get(id)?.with_context(|| format!("reference_blob_id {} hasn't been verified here", id))?),
+        None => None,
+    };
+
+    // The length Walrus registered for the blob ID, which no aggregator can change (see integrity).
+    let registered = state.blobs.registered_blob(&vr.blob_id).await.context("Walrus blob registration")?;
+    if let Some(registered) = &registered {
+        info!(object_id = %registered.object_id, size = registered.size, "Walrus blob registered on Sui");
+    }
+
+    // 2-3) Probe and open the encrypted blob, unless a batch already downloaded it
+    let mut plan = None;
+    let mut body = match (prefetched, chunk_sample) {
+        (Some(bytes), spec) => {
+            job.set_stage("fetch");
+            let bytes = match spec {
+                Some(spec) => {
+                    let chunk_plan = estimate::ChunkPlan::new(bytes.len() as u64, spec.chunks.unwrap_or(1), spec.seed);
+                    opts.format = Some(sniff_encrypted(&bytes)?);
+                    opts.source_len = Some(bytes.len() as u64);
+                    let sample = chunk_plan.slice(&bytes);
+                    plan = Some(chunk_plan);
+                    sample
+                }
+                None => bytes,
+            };
+            job.charge("fetch", bytes.len() as u64)?;
+            futures_util::stream::once(async move { Ok(Bytes::from(bytes)) }).boxed()
+        }
+        (None, Some(spec)) => {
+            let (sample, chunk_plan) = open_chunk_sample(state, &vr.blob_id, spec, &mut opts, job).await?;
+            plan = Some(chunk_plan);
+            futures_util::stream::once(async move { Ok(Bytes::from(sample)) }).boxed()
+        }
+        (None, None) => open_encrypted(state, &vr.blob_id, &mut opts, job).await?,
+    };
+
+    // 4-5) Stream ciphertext chunks through decryption into the checks, so neither the whole
+    // ciphertext nor the whole plaintext is ever held. Everything derived from the full blob
+    // (digest, cross-dataset signature) is accumulated on the way.
+    job.set_stage("validate");
+    let whole_blob = opts.source_len.is_none();
+    let mut digest = whole_blob.then(Sha256::new);
+    // Cross-dataset dedupe needs the whole dataset and is shed with the fuzzy dedup check.
+    let mut minhash = (whole_blob && !opts.skip_dedup).then(dedupe::MinHasher::new);
+    let mut profiler = (whole_blob || reference.is_some()).then(drift::DriftProfiler::new);
+    let checks = match &vr.scoring {
+        Some(overrides) => Cow::Owned(state.config.checks.with_overrides(overrides)?),
+        None => Cow::Borrowed(&state.config.checks),
+    };
+    let mut validator = quality_validator::QualityAccumulator::new(&opts, &checks);
+    let mut estimator = plan.as_ref().map(|plan| estimate::ChunkEstimator::new(plan, &opts, &checks));
+    let tenant_policy = state.content_policies.as_ref().and_then(|p| p.for_tenant(vr.tenant.as_deref()));
+    let screening_mode = tenant_policy.as_ref().and_then(|p| p.screening).unwrap_or(state.config.screening.mode);
+    let mut policy = tenant_policy.map(|p| content_policy::PolicyCheck::new(p, vr.tenant.as_deref()));
+    let mut screener =
+        (screening_mode != screening::ScreeningMode::Off).then(|| screening::Screener::new(screening_mode));
+    // Attested, and what known-bad lists hash: the files themselves, i.e. the plaintext.
+    let mut plain_digest = whole_blob.then(Sha256::new);
+    let mut held = 0u64;
+    let mut total = 0u64;
+    while let Some(chunk) = body.next().await {
+        let chunk = chunk.map_err(|err| err.context(format!("Failed to fetch blob {}", vr.blob_id)))?;
+        if let Some(digest) = digest.as_mut() {
+            digest.update(&chunk);
+        }
+        // Seal decryption is per chunk as well; the placeholder is a byte-wise stream cipher.
+        let plaintext = decrypt_placeholder(&chunk).context("Decrypt placeholder failed")?;
+        if let Some(policy) = policy.as_mut() {
+            policy.update(&plaintext)?;
+        }
+        if let Some(screener) = screener.as_mut() {
+            screener.update(&plaintext);
+        }
+        if let Some(plain_digest) = plain_digest.as_mut() {
+            plain_digest.update(&plaintext);
+        }
+        validator.update(&plaintext);
+        if let Some(estimator) = estimator.as_mut() {
+            estimator.update(&plaintext);
+        }
+        if let Some(minhash) = minhash.as_mut() {
+            minhash.update(&plaintext);
+        }
+        if let Some(profiler) = profiler.as_mut() {
+            profiler.update(&plaintext);
+        }
+        total += chunk.len() as u64;
+        // One ciphertext chunk and its plaintext are in flight, plus the checks' own state.
+        let in_flight =
+            2 * chunk.len() as u64 + validator.state_bytes() + estimator.as_ref().map_or(0, |e| e.state_bytes());
+        if in_flight > held {
+            job.charge("stream", in_flight - held)?;
+            held = in_flight;
+        }
+    }
+    drop(body);
+    job.release("fetch");
+    integrity::check_size(&vr.blob_id, opts.source_len.unwrap_or(total), registered.map(|r| r.size))?;
+    if let Some(policy) = policy {
+        let detected = policy.finish(opts.source_len)?;
+        info!(detected = detected.as_str(), "Content policy satisfied");
+    }
+    let plain_sha256 = plain_digest.map(|d| hex::encode(d.finalize()));
+    let screening = screener.map(|s| s.finish(plain_sha256.as_deref(), &state.bad_hashes)).transpose()?;
+    info!(size = total, "Streamed encrypted blob");
+    // Bind the attestation to the exact bytes scored; a sample has no whole-blob digest.
+    let content_sha256 = match digest {
+        Some(digest) => {
+            let actual = hex::encode(digest.finalize());
+            Some(integrity::check_digest(&vr.blob_id, actual, vr.content_sha256.as_deref())?)
+        }
+        None => None,
+    };
+    let mut report = validator.finish().context("Quality validation failed")?;
+    if let (Some(plan), Some(spec)) = (&plan, chunk_sample) {
+        report.sampling = Some(sampling::SampleInfo {
+            method: "chunks".into(),
+            seed: spec.seed,
+            sample_size: spec.chunks.unwrap_or_default(),
+            records_sampled: plan.ranges.len() as u64,
+            records_seen: plan.total_chunks,
+            beacon: None,
+            estimate: estimator.and_then(estimate::ChunkEstimator::finish),
+        });
+    }
+    if let Some(sampling) = report.sampling.as_mut() {
+        sampling.beacon = beacon;
+    }
+    let report_sha256 = quality_validator::report_sha256(&report.checks)?;
+    let quality_score = report.score;
+    let is_valid = quality_score >= vr.min_quality_threshold && report.scoring.below_minimum.is_empty();
     info!(quality_score, is_valid, "Quality validation done");
+    let near_duplicate_of = match minhash {
+        Some(minhash) => {
+            // The index is advisory; a storage error must not fail the verification itself.
+            let sig = minhash.finish();
+            let hit = state.dedupe.lookup(&vr.blob_id, &sig).unwrap_or_else(|err| {
+                error!(%err, "Dedupe index lookup failed");
+                None
+            });
+            if let Err(err) = state.dedupe.insert(&vr.blob_id, &job.id(), &sig) {
+                error!(%err, "Dedupe index insert failed");
+            }
+            hit
+        }
+        None => None,
+    };
+    if let Some(dup) = &near_duplicate_of {
+        info!(other = %dup.blob_id, similarity = dup.similarity, "Near duplicate of an earlier dataset");
+    }
+    // Like the dedupe index, the stored profile is advisory for later requests.
+    let profile = profiler.map(drift::DriftProfiler::finish);
+    if let (Some(profile), Some(sha256)) = (&profile, &content_sha256) {
+        if let Err(err) = state.drift.insert(&vr.blob_id, &job.id(), sha256, profile) {
+            error!(%err, "Drift profile insert failed");
+        }
+    }
+    let drift = reference.zip(profile).map(|(reference, profile)| drift::compare(&reference, &profile));
+    if let Some(drift) = &drift {
+        info!(reference = %drift.reference_blob_id, drift_score = drift.drift_score, "Compared with the reference dataset");
+    }
+    // Seal each check's evidence; only hashes of artifacts actually stored are published.
+    let mut evidence = BTreeMap::new();
+    for (check, artifact) in &report.evidence {
+        match state.evidence.seal(&job.id(), check, artifact) {
+            Ok(sha256) => {
+                evidence.insert(check.to_string(), sha256);
+            }
+            Err(err) => error!(%err, check, "Sealing check evidence failed"),
+        }
+    }
+    job.release("stream");
 
-    // 5) Generate attestation
-    let attn_bytes = tee_attestation::generate_attestation(&vr.blob_id, quality_score)
-        .await
-        .unwrap_or_else(|e| {
+    // 6) Generate attestation
+    job.set_stage("attest");
+    let poisoning_risk = report.breakdown.check("poisoning").map(|score| (100 - score.min(100)) as u8);
+    let claim = QualityClaim {
+        blob_id: vr.blob_id.clone(),
+        source_type: blob_source::source_type(&vr.blob_id).to_string(),
+        quality_score,
+        category: vr.category.as_str().to_string(),
+        degradations: degradations.clone(),
+        content_sha256: content_sha256.clone(),
+        plaintext_sha256: plain_sha256.clone(),
+        report_sha256: Some(report_sha256.clone()),
+        screening: screening.clone(),
+        sampling: report.sampling.clone(),
+        sui_object_id: vr.sui_object_id.clone(),
+        config_hash: state.config_hash.clone(),
+        walrus_profile: state.config.walrus.profile.as_str().to_string(),
+        co_sign: vr.co_sign || state.config.cosign.always,
+        evidence: evidence.clone(),
+        scoring: Some(report.scoring.clone()),
+        poisoning_risk,
+        drift: drift.clone(),
+        envelope: vr.envelope_encoding,
+        validity: state.config.attestation_validity,
+    };
+    // Batch leaves are attested together once the batch is done.
+    let attestation_data = match vr.batch_leaf {
+        true => Some(tee_attestation::attestation_data(&claim, state.config.payload_encoding)?),
+        false => None,
+    };
+    let attested = match attestation_data {
+        Some(_) => Ok(Vec::new()),
+        None => state.attester.attest(&claim).await,
+    };
+    let attn_bytes = match attested {
+        Ok(bytes) => bytes,
+        // A locked key or a missing co-signature is not a transient fault: refuse rather than
+        // hand out an unattested (or single-signed) result.
+        Err(err)
+            if err.downcast_ref::<key_usage::SigningLocked>().is_some()
+                || err.downcast_ref::<cosign::CosignUnavailable>().is_some() =>
+        {
+            return Err(err)
+        }
+        Err(e) => {
             error!(err = %e, "Attestation failed, returning empty bytes");
             Vec::new()
-        });
+        }
+    };
+    // An attestation missing from the transcript must not reach the caller. An escrowed one is
+    // chained by hash only, so the transcript doesn't reveal its score before the release.
+    if !attn_bytes.is_empty() {
+        let key = state.keys.current();
+        state
+            .transcript
+            .append(&job.id(), &vr.blob_id, &attn_bytes, vr.release.is_some(), &key, &state.key_usage)
+            .context("Failed to record attestation in the transcript")?;
+    }
     let attestation = base64::engine::general_purpose::STANDARD.encode(attn_bytes);
 
-    // 6) Build response
+    // 7) Build response
     let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+    state.audit.record(audit::AuditRecord {
+        job_id: job.id(),
+        blob_id: vr.blob_id.clone(),
+        timestamp_ms: now_ms,
+        category: vr.category,
+        threshold: vr.min_quality_threshold,
+        score: quality_score,
+        breakdown: report.breakdown.clone(),
+        degradations: degradations.clone(),
+        evidence: evidence.clone(),
+    });
     let nitro_enclave = Path::new("/dev/nsm").exists();
     Ok(VerificationResponse {
+        job_id: job.id(),
+        source_type: blob_source::source_type(&vr.blob_id),
         blob_id: vr.blob_id,
         quality_score,
         is_valid,
+        category: vr.category.as_str(),
+        scoring: report.scoring,
+        poisoning_risk,
+        format: report.format.as_str(),
+        format_details: report.details,
+        sui_object_id: vr.sui_object_id,
+        content_sha256,
+        plaintext_sha256: plain_sha256,
+        report_sha256,
+        screening,
+        sampling: report.sampling,
+        encrypted_fields: report.encrypted_fields,
         attestation,
+        attestation_data,
+        inclusion_proof: None,
         timestamp_ms: now_ms,
         nitro_enclave,
+        degradations,
+        near_duplicate_of,
+        drift,
+        evidence,
+        parties: vr.parties,
+        remediation: report.breakdown.remediation_codes().into_iter().map(|c| i18n::Remediation::new(c, lang)).collect(),
+        report: vr.detail.then(|| {
+            report.checks.into_iter().map(|(name, check)| (name, CheckDetail::new(name, check, lang))).collect()
+        }),
     })
 }
 
+// The request's data contract, compiled; fetched first when it is given by blob ID.
+async fn load_contract(state: &AppState, vr: &VerificationRequest) -> Result<Option<data_contract::DataContract>> {
+    if let Some(schema) = &vr.json_schema {
+        return data_contract::DataContract::compile(schema).map(Some);
+    }
+    let Some(blob_id) = vr.json_schema_blob_id.as_deref() else { return Ok(None) };
+    let bytes = state
+        .blobs
+        .fetch_blob(blob_id, data_contract::MAX_SCHEMA_BYTES)
+        .await
+        .with_context(|| format!("Failed to fetch json_schema blob {}", blob_id))?;
+    data_contract::DataContract::parse(&bytes).map(Some)
+}
+
+// Probe the blob so missing or oversized blobs fail before any download, then open its body
+// (only the sampled chunks, buffered, when load shedding samples).
+async fn open_encrypted(
+    state: &AppState,
+    blob_id: &str,
+    opts: &mut quality_validator::ValidationOptions,
+    job: &mut jobs::Job,
+) -> Result<futures_util::stream::BoxStream<'static, Result<Bytes>>> {
+    job.set_stage("probe");
+    let meta = state
+        .blobs
+        .blob_metadata(blob_id)
+        .await
+        .with_context(|| format!("Blob {} unavailable", blob_id))?;
+    info!(size = ?meta.size, content_type = ?meta.content_type, etag = ?meta.etag, "Blob metadata");
+
+    job.set_stage("fetch");
+    if opts.sample_rate_pct < 100 {
+        let (sample, total_len) = fetch_sampled_blob(state, blob_id, opts.sample_rate_pct)
+            .await
+            .with_context(|| format!("Failed to fetch sampled blob {}", blob_id))?;
+        job.charge("fetch", sample.len() as u64)?;
+        opts.source_len = Some(total_len);
+        return Ok(futures_util::stream::once(async move { Ok(Bytes::from(sample)) }).boxed());
+    }
+    // Streamed, so the blob size is bounded by WALRUS_MAX_BLOB_BYTES rather than the job budget.
+    state
+        .blobs
+        .fetch_blob_stream(blob_id, u64::MAX)
+        .await
+        .with_context(|| format!("Failed to fetch blob {}", blob_id))
+}
+
+// A download cut off at the job's own budget is the job running out of memory.
+fn fetch_error(err: anyhow::Error, blob_id: &str, limit: u64, job: &jobs::Job) -> anyhow::Error {
+    match err.downcast_ref::<walrus_client::WalrusError>() {
+        Some(walrus_client::WalrusError::TooLarge { limit: hit }) if *hit == limit => {
+            job.oom("fetch", limit.saturating_add(1)).into()
+        }
+        _ => err.context(format!("Failed to fetch blob {}", blob_id)),
+    }
+}
+
+// Signed short-form badge for a completed verification, as JSON or a QR code of its deep link.
+fn badge_response(state: &AppState, job_id: &str, as_svg: bool) -> Response<Full<Bytes>> {
+    // A badge would reveal the score of a result still held in escrow.
+    if state.escrow.is_held(job_id).unwrap_or(false) {
+        return json_response(StatusCode::LOCKED, br#"{"error":"verification held in escrow"}"#.to_vec());
+    }
+    let Some(rec) = state.audit.get(job_id) else {
+        // Archived verifications are no longer badged live, but point at the bundle holding them.
+        if let Ok(Some(entry)) = state.archive.find_job(job_id) {
+            let body = serde_json::json!({ "error": "verification archived", "archive": entry });
+            return json_response(StatusCode::GONE, body.to_string().into_bytes());
+        }
+        return json_response(StatusCode::NOT_FOUND, br#"{"error":"verification not found"}"#.to_vec());
+    };
+    let key = state.keys.current();
+    let claims = badge::BadgeClaims {
+        jti: rec.job_id,
+        kid: tee_attestation::key_id(&key.public),
+        score: rec.score,
+        iat: rec.timestamp_ms / 1000,
+    };
+    // Anyone can fetch a badge, so it mustn't count toward the alarm that locks the key.
+    if let Err(err) = state.key_usage.record(&claims.kid, "badge") {
+        return error_response("SIGNING_LOCKED", &err.into(), i18n::Lang::En);
+    }
+    let verify_url = format!("{}/badge/verify", state.config.public_url);
+    let rendered = badge::encode(&key, &claims).and_then(|short| {
+        let link = badge::deep_link(&verify_url, &short);
+        if as_svg {
+            let mut resp = text_response(StatusCode::OK, &badge::qr_svg(&link)?);
+            resp.headers_mut().insert(CONTENT_TYPE, "image/svg+xml".parse().unwrap());
+            Ok(resp)
+        } else {
+            let body = serde_json::json!({ "claims": claims, "short": short, "deep_link": link });
+            Ok(json_response(StatusCode::OK, body.to_string().into_bytes()))
+        }
+    });
+    rendered.unwrap_or_else(|err| {
+        error!(%err, "Badge rendering failed");
+        json_response(StatusCode::INTERNAL_SERVER_ERROR, br#"{"error":"badge rendering failed"}"#.to_vec())
+    })
+}
+
+// Base64 keys this service's attestations may carry: every still-verifying ed25519 key, plus the
+// current key under another signature scheme.
+fn own_public_keys(state: &AppState) -> Vec<String> {
+    let engine = base64::engine::general_purpose::STANDARD;
+    let mut keys: Vec<String> = state.keys.verifying_keys().iter().map(|key| engine.encode(key.to_bytes())).collect();
+    let scheme = state.config.signature_scheme;
+    if scheme != tee_attestation::SignatureScheme::Ed25519 {
+        match scheme.public_key(&state.keys.current()) {
+            Ok(key) => keys.push(engine.encode(key)),
+            Err(err) => error!("Deriving the {} public key failed: {:#}", scheme.as_str(), err),
+        }
+    }
+    keys
+}
+
+fn query_param(query: Option<&str>, key: &str) -> Option<String> {
+    query?
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .find(|(k, _)| *k == key)
+        .map(|(_, v)| v.to_string())
+}
+
+// A page of a listing (see listing), or 304 when the caller's copy is current.
+fn list_response<T: listing::Listed>(req: &Request<Body>, items: Vec<T>) -> Response<Full<Bytes>> {
+    let query = match listing::ListQuery::parse(req.uri().query()) {
+        Ok(query) => query,
+        Err(err) => return error_response("INVALID_REQUEST", &err, request_lang(req)),
+    };
+    let (body, etag) = listing::Page::of(items, &query).body();
+    let cached = req.headers().get(IF_NONE_MATCH).and_then(|v| v.to_str().ok()).is_some_and(|v| {
+        v.split(',').any(|tag| tag.trim() == etag || tag.trim() == "*")
+    });
+    let mut resp = if cached { text_response(StatusCode::NOT_MODIFIED, "") } else { json_response(StatusCode::OK, body) };
+    resp.headers_mut().insert(ETAG, etag.parse().unwrap());
+    resp
+}
+
+fn detail_requested(req: &Request<Body>) -> bool {
+    query_param(req.uri().query(), "detail").as_deref() == Some("full")
+}
+
+fn request_lang(req: &Request<Body>) -> i18n::Lang {
+    i18n::negotiate(req.headers().get(ACCEPT_LANGUAGE).and_then(|v| v.to_str().ok()))
+}
+
+// Scope an endpoint requires from a tenant API key; read-only endpoints stay open.
+fn required_scope(method: &Method, path: &str) -> Option<&'static str> {
+    match (method, path) {
+        (&Method::POST, "/verify") | (&Method::POST, "/verify/batch") => Some("verify"),
+        // Each identity attestation costs an NSM round-trip and a signature.
+        (&Method::GET, "/attestation") => Some("verify"),
+        // The transcript is for auditors, not the public.
+        (&Method::GET, "/attestations") => Some("verify"),
+        (&Method::POST, "/policy/simulate") | (&Method::GET, "/policies") => Some("policy"),
+        (_, p) if p.starts_with("/admin/") => Some("admin"),
+        _ => None,
+    }
+}
+
+// The caller's tenant, when API keys are enforced on this endpoint. Without a key store tenant
+// endpoints stay open, but admin ones (signing re-arm, chaos, evidence) are refused outright.
+fn authorize<B>(req: &Request<B>, state: &AppState) -> Result<Option<String>> {
+    let Some(scope) = required_scope(req.method(), req.uri().path()) else {
+        return Ok(None);
+    };
+    let Some(keys) = &state.api_keys else {
+        anyhow::ensure!(scope != "admin", "admin endpoints are disabled without an API key store (NAUTILUS_API_KEYS_FILE)");
+        return Ok(None);
+    };
+    let token = req
+        .headers()
+        .get(AUTHORIZATION)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.strip_prefix("Bearer "))
+        .ok_or_else(|| anyhow::anyhow!("Missing bearer API key"))?;
+    let tenant = keys
+        .authorize(token.trim(), scope)
+        .ok_or_else(|| anyhow::anyhow!("API key is invalid, revoked or lacks the '{}' scope", scope))?;
+    info!(%tenant, scope, "Authorized request");
+    Ok(Some(tenant))
+}
+
+// `error` keeps the untranslated detail for logs and existing clients; `category` tells clients
+// whether retrying can help, and retryable responses say when to.
+fn error_response(code: &str, err: &anyhow::Error, lang: i18n::Lang) -> Response<Full<Bytes>> {
+    let spec = errors::spec(code);
+    let mut body = serde_json::json!({
+        "error": format!("{:#}", err),
+        "code": spec.code,
+        "category": spec.category,
+        "message": i18n::message(spec.code, lang),
+    });
+    add_expiry_epoch(&mut body, err);
+    let mut resp = localized(json_response(spec.status, body.to_string().into_bytes()), lang);
+    if let Some(secs) = spec.retry_after_secs {
+        resp.headers_mut().insert(RETRY_AFTER, secs.into());
+    }
+    resp
+}
+
+fn localized(mut resp: Response<Full<Bytes>>, lang: i18n::Lang) -> Response<Full<Bytes>> {
+    resp.headers_mut().insert(CONTENT_LANGUAGE, lang.tag().parse().unwrap());
+    resp
+}
+
+// Range-fetch the chunks a chunk sample picks. The first chunk tells the blob length, and its
+// head the format, which the chunks picked can't be sniffed for. Returns the sampled bytes.
+async fn open_chunk_sample(
+    state: &AppState,
+    blob_id: &str,
+    spec: sampling::SampleSpec,
+    opts: &mut quality_validator::ValidationOptions,
+    job: &mut jobs::Job,
+) -> Result<(Vec<u8>, estimate::ChunkPlan)> {
+    job.set_stage("fetch");
+    let chunk = estimate::CHUNK_BYTES;
+    let first = state
+        .blobs
+        .fetch_blob_range(blob_id, 0, chunk)
+        .await
+        .with_context(|| format!("Failed to fetch blob {}", blob_id))?;
+    let total_len = first.total_len.unwrap_or(first.data.len() as u64);
+    let plan = estimate::ChunkPlan::new(total_len, spec.chunks.unwrap_or(1), spec.seed);
+    opts.format = Some(sniff_encrypted(&first.data)?);
+    opts.source_len = Some(total_len);
+    let mut sample = Vec::new();
+    for &(offset, len) in &plan.ranges {
+        let part = match offset {
+            0 => first.data[..first.data.len().min(len as usize)].to_vec(),
+            _ => state
+                .blobs
+                .fetch_blob_range(blob_id, offset, len)
+                .await
+                .with_context(|| format!("Failed to fetch chunk at {} of blob {}", offset, blob_id))?
+                .data,
+        };
+        job.charge("fetch", part.len() as u64)?;
+        sample.extend_from_slice(&part);
+    }
+    info!(chunks = plan.ranges.len(), of = plan.total_chunks, "Fetched chunk sample");
+    Ok((sample, plan))
+}
+
+// Format of an encrypted blob, from its first bytes.
+fn sniff_encrypted(head: &[u8]) -> Result<content_policy::DetectedType> {
+    let head = decrypt_placeholder(&head[..head.len().min(content_policy::SNIFF_LEN)])?;
+    Ok(content_policy::sniff(&head))
+}
+
+// Read only the chunks selected by `quality_validator::sample_ranges` via Range requests.
+// The first chunk also tells us the full blob length. Returns (sample bytes, blob length).
+async fn fetch_sampled_blob(state: &AppState, blob_id: &str, rate_pct: u8) -> Result<(Vec<u8>, u64)> {
+    let chunk = quality_validator::SAMPLE_CHUNK as u64;
+    let first = state.blobs.fetch_blob_range(blob_id, 0, chunk).await?;
+    let total_len = first.total_len.unwrap_or(first.data.len() as u64);
+    let mut sample = first.data;
+    for (offset, len) in quality_validator::sample_ranges(total_len, rate_pct).into_iter().skip(1) {
+        let part = state.blobs.fetch_blob_range(blob_id, offset, len).await?;
+        sample.extend_from_slice(&part.data);
+    }
+    Ok((sample, total_len))
+}
+
 fn text_response(status: StatusCode, body: &str) -> Response<Full<Bytes>> {
     let mut resp = Response::new(Full::from(Bytes::from(body.to_string())));
     *resp.status_mut() = status;
@@ -172,3 +1683,377 @@ fn decrypt_placeholder(ciphertext: &[u8]) -> Result<Vec<u8>> {
 }
 
 // removed duplicate main
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blob_source::BlobSource;
+    use crate::tee_attestation::Attester;
+    use crate::walrus_client::{BlobMetadata, BlobRange, RegisteredBlob};
+
+    struct FixedBlobs(Vec<u8>);
+
+    #[async_trait::async_trait]
+    impl BlobSource for FixedBlobs {
+        async fn fetch_blob(&self, _blob_id: &str, _limit: u64) -> Result<Vec<u8>> {
+            Ok(self.0.clone())
+        }
+
+        async fn fetch_blob_range(&self, _blob_id: &str, offset: u64, len: u64) -> Result<BlobRange> {
+            let start = (offset as usize).min(self.0.len());
+            let end = ((offset + len) as usize).min(self.0.len());
+            Ok(BlobRange { data: self.0[start..end].to_vec(), total_len: Some(self.0.len() as u64) })
+        }
+
+        async fn blob_metadata(&self, _blob_id: &str) -> Result<BlobMetadata> {
+            Ok(BlobMetadata { size: Some(self.0.len() as u64), ..Default::default() })
+        }
+
+        async fn resolve_sui_object(&self, object_id: &str) -> Result<String> {
+            Ok(format!("blob-of-{}", object_id))
+        }
+
+        // "registered-<size>" blob IDs are registered on Sui with that size.
+        async fn registered_blob(&self, blob_id: &str) -> Result<Option<RegisteredBlob>> {
+            let size = blob_id.strip_prefix("registered-").and_then(|size| size.parse().ok());
+            Ok(size.map(|size| RegisteredBlob { object_id: "0xb10b".into(), size, certified_epoch: 1 }))
+        }
+    }
+
+    struct FakeAttester;
+
+    #[async_trait::async_trait]
+    impl Attester for FakeAttester {
+        async fn attest(&self, claim: &QualityClaim) -> Result<Vec<u8>> {
+            Ok(format!("{}:{}", claim.blob_id, claim.quality_score).into_bytes())
+        }
+    }
+
+    fn test_keypair() -> ed25519_dalek::Keypair {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[9u8; 32]).unwrap();
+        let public: ed25519_dalek::PublicKey = (&secret).into();
+        ed25519_dalek::Keypair { secret, public }
+    }
+
+    fn test_state(blob: Vec<u8>, job_memory_cap: u64) -> AppState {
+        let mut load_shed = load_shed::LoadShedPolicy::from_env();
+        // Never shed in tests regardless of host load.
+        load_shed.mem_soft_pct = 101;
+        load_shed.mem_high_pct = 101;
+        load_shed.mem_hard_pct = 101;
+        load_shed.cpu_soft = f64::MAX;
+        load_shed.cpu_high = f64::MAX;
+        load_shed.cpu_hard = f64::MAX;
+        let config = config::Config {
+            listen_addr: "127.0.0.1:0".parse().unwrap(),
+            public_url: "http://127.0.0.1:0".into(),
+            walrus: walrus_client::WalrusConfig::from_env().unwrap(),
+            load_shed,
+            job_memory_cap,
+            audit_capacity: 16,
+            fetch_parallelism: 2,
+            batch_max_items: 8,
+            require_content_digest: false,
+            api_keys_file: None,
+            content_policy_file: None,
+            sealed_dir: None,
+            dedupe: dedupe::DedupeConfig::from_env(),
+            archive: archive::ArchiveConfig::from_env(),
+            key_usage: key_usage::KeyUsagePolicy::from_env(),
+            cosign: cosign::CosignConfig::from_env(),
+            http_source: http_source::HttpSourceConfig::default(),
+            escrow: escrow::EscrowConfig::from_env(),
+            submission: submission::SubmissionConfig::from_env(),
+            evidence: evidence::EvidenceConfig::from_env().unwrap(),
+            arbitration: Default::default(),
+            s3: s3_source::S3Config::default(),
+            kms: kms_seal::KmsConfig::default(),
+            key_rotation: key_ring::RotationPolicy { interval: None, grace: std::time::Duration::ZERO },
+            signature_scheme: Default::default(),
+            payload_encoding: Default::default(),
+            attestation_validity: None,
+            tpm: Default::default(),
+            ipfs: ipfs_source::IpfsConfig::default(),
+            screening: screening::ScreeningConfig::from_env(),
+            checks: Default::default(),
+            watchdog: watchdog::WatchdogConfig::from_env(),
+            sample_seed_source: Default::default(),
+        };
+        AppState {
+            config_hash: config.hash(),
+            config,
+            blobs: Arc::new(FixedBlobs(blob)),
+            attester: Arc::new(FakeAttester),
+            keys: Arc::new(key_ring::KeyRing::new(
+                test_keypair(),
+                key_ring::RotationPolicy { interval: None, grace: std::time::Duration::ZERO },
+                None,
+                Default::default(),
+            )),
+            key_usage: Arc::new(key_usage::KeyUsageMonitor::new(key_usage::KeyUsagePolicy::from_env())),
+            jobs: jobs::JobRegistry::new(job_memory_cap),
+            audit: audit::AuditLog::new(16),
+            dedupe: dedupe::DedupeIndex::open(None, dedupe::DedupeConfig::from_env()).unwrap(),
+            drift: drift::DriftStore::open(None, &dedupe::DedupeConfig::from_env()).unwrap(),
+            archive: archive::Archiver::open(None, archive::ArchiveConfig::from_env()).unwrap(),
+            transcript: transcript::Transcript::open(None).unwrap(),
+            escrow: escrow::Escrow::open(None, escrow::EscrowConfig::from_env()).unwrap(),
+            evidence: evidence::EvidenceStore::open(None, &evidence::EvidenceConfig::from_env().unwrap()).unwrap(),
+            arbitration: arbitration::Arbitration::open(None, &Default::default()).unwrap(),
+            submitter: submission::Submitter::new(submission::SubmissionConfig::from_env()),
+            api_keys: None,
+            content_policies: None,
+            bad_hashes: screening::BadHashes::default(),
+            watchdog: watchdog::Watchdog::new(watchdog::WatchdogConfig::from_env()),
+            walrus: None,
+        }
+    }
+
+    fn request(blob_id: &str) -> VerificationRequest {
+        VerificationRequest {
+            blob_id: blob_id.to_string(),
+            sui_object_id: None,
+            min_quality_threshold: 10,
+            category: Default::default(),
+            label_column: None,
+            timestamp_column: None,
+            declared_parameters: None,
+            json_schema: None,
+            json_schema_blob_id: None,
+            reference_blob_id: None,
+            content_sha256: None,
+            co_sign: false,
+            envelope_encoding: Default::default(),
+            release: None,
+            sample: None,
+            parties: Vec::new(),
+            scoring: None,
+            tenant: None,
+            detail: false,
+            batch_leaf: false,
+        }
+    }
+
+    #[tokio::test]
+    async fn test_verification_uses_injected_collaborators() {
+        let plaintext = (0..16 * 1024).map(|i| (i as u8).wrapping_mul(31)).collect::<Vec<_>>();
+        let encrypted = plaintext.iter().map(|b| b ^ 0xAA).collect::<Vec<_>>();
+        let state = test_state(encrypted, 1 << 30);
+        let mut job = state.jobs.start("blob-1");
+        let resp = run_verification(&state, request("blob-1"), &mut job, i18n::Lang::En, None).await.unwrap();
+        let expected =
+            quality_validator::validate_dataset_quality(&plaintext, &Default::default()).unwrap().score;
+        assert_eq!(resp.quality_score, expected);
+        let attestation = base64::engine::general_purpose::STANDARD.decode(resp.attestation).unwrap();
+        assert_eq!(attestation, format!("blob-1:{}", expected).into_bytes());
+        let audited = state.audit.since(0);
+        assert_eq!(audited.len(), 1);
+        assert_eq!(audited[0].score, expected);
+        let transcript = state.transcript.since(0, None).unwrap();
+        assert_eq!(transcript.entries.len(), 1);
+        assert_eq!(transcript.entries[0].attestation_sha256, integrity::sha256_hex(&attestation));
+        // Evidence is sealed per check and only its hashes are published.
+        let sealed = state.evidence.get(&resp.job_id).unwrap();
+        assert!(sealed.iter().any(|e| e.check == "authenticity"));
+        assert!(sealed.iter().all(|e| resp.evidence.get(&e.check) == Some(&e.sha256)));
+        assert_eq!(audited[0].evidence, resp.evidence);
+        assert!(resp.report.is_none());
+
+        let mut job = state.jobs.start("blob-1");
+        let vr = VerificationRequest { detail: true, ..request("blob-1") };
+        let detailed = run_verification(&state, vr, &mut job, i18n::Lang::En, None).await.unwrap().report.unwrap();
+        let breakdown = quality_validator::validate_dataset_quality(&plaintext, &Default::default()).unwrap().breakdown;
+        assert_eq!(detailed.len(), breakdown.scores().count());
+        assert!(detailed.iter().all(|(name, c)| breakdown.check(name) == Some(c.check.score) && !c.explanation.is_empty()));
+        assert_eq!(detailed["completeness"].check.measures["bytes"], plaintext.len() as f64);
+        assert!((detailed.values().map(|c| c.check.share).sum::<f64>() - 1.0).abs() < 0.01);
+        // Buyers match a published report to the attested hash without the localized text.
+        assert_eq!(resp.plaintext_sha256, Some(integrity::sha256_hex(&plaintext)));
+        let mut published = serde_json::to_value(&detailed).unwrap();
+        for entry in published.as_object_mut().unwrap().values_mut() {
+            let entry = entry.as_object_mut().unwrap();
+            entry.remove("explanation");
+            entry.remove("remediation");
+        }
+        let published = nautilus_canonical::canonical_bytes(&published).unwrap();
+        assert_eq!(integrity::sha256_hex(&published), resp.report_sha256);
+    }
+
+    #[tokio::test]
+    async fn test_job_memory_cap_is_enforced() {
+        let state = test_state(vec![1u8; 4096], 1000);
+        let mut job = state.jobs.start("blob-2");
+        let err = run_verification(&state, request("blob-2"), &mut job, i18n::Lang::En, None).await.err().expect("cap must reject the job");
+        assert!(err.downcast_ref::<jobs::JobOom>().is_some());
+        assert_eq!(errors::spec(errors::code(&err)).status, StatusCode::PAYLOAD_TOO_LARGE);
+    }
+
+    #[test]
+    fn test_admin_endpoints_need_a_key_store() {
+        let state = test_state(Vec::new(), 1 << 20);
+        let request = |method, path| Request::builder().method(method).uri(path).body(()).unwrap();
+        assert!(authorize(&request(Method::POST, "/admin/signing/rearm"), &state).is_err());
+        assert!(authorize(&request(Method::GET, "/admin/keys"), &state).is_err());
+        assert_eq!(authorize(&request(Method::POST, "/verify"), &state).unwrap(), None);
+        assert_eq!(authorize(&request(Method::GET, "/health"), &state).unwrap(), None);
+        assert_eq!(required_scope(&Method::GET, "/attestation"), Some("verify"));
+        assert_eq!(required_scope(&Method::GET, "/attestations"), Some("verify"));
+    }
+
+    #[test]
+    fn test_unpinned_verification_trusts_only_own_keys() {
+        let mut state = test_state(Vec::new(), 1 << 20);
+        let own = base64::engine::general_purpose::STANDARD.encode(test_keypair().public.to_bytes());
+        assert_eq!(own_public_keys(&state), std::slice::from_ref(&own));
+        state.config.signature_scheme = tee_attestation::SignatureScheme::Secp256k1;
+        let secp = tee_attestation::secp256k1::public_key(&test_keypair()).unwrap();
+        assert_eq!(own_public_keys(&state), [own, base64::engine::general_purpose::STANDARD.encode(secp)]);
+    }
+
+    #[tokio::test]
+    async fn test_badges_never_lock_the_signing_key() {
+        let mut state = test_state(vec![3u8; 4096], 1 << 30);
+        let policy = key_usage::KeyUsagePolicy { quota: 1, require_rearm: true, ..key_usage::KeyUsagePolicy::from_env() };
+        state.key_usage = Arc::new(key_usage::KeyUsageMonitor::new(policy));
+        let mut job = state.jobs.start("blob-b");
+        run_verification(&state, request("blob-b"), &mut job, i18n::Lang::En, None).await.unwrap();
+        for _ in 0..5 {
+            assert_eq!(badge_response(&state, &job.id(), false).status(), StatusCode::OK);
+        }
+        // The verification's own signatures filled the quota; five more would have locked the key.
+        assert!(state.key_usage.snapshot().iter().all(|s| !s.locked));
+    }
+
+    #[tokio::test]
+    async fn test_escrowed_attestation_stays_out_of_the_transcript() {
+        let state = test_state(vec![3u8; 4096], 1 << 30);
+        let mut vr = request("blob-4");
+        // Already due, but nothing is released before the poll.
+        vr.release = Some(escrow::ReleaseCondition { at_ms: Some(1), sui_object_id: None });
+        let release = vr.release.clone();
+        let mut job = state.jobs.start("blob-4");
+        let resp = run_verification(&state, vr, &mut job, i18n::Lang::En, None).await.unwrap();
+        let (job_id, attestation) = (resp.job_id.clone(), resp.attestation.clone());
+        assert_eq!(deliver(&state, release, resp).unwrap()["status"], "held");
+        let entry = state.transcript.since(0, None).unwrap().entries.remove(0);
+        assert_eq!((entry.job_id.as_str(), entry.attestation_b64), (job_id.as_str(), None));
+
+        for released in state.escrow.release_due(state.blobs.as_ref()).await.unwrap() {
+            publish_released(&state, released);
+        }
+        let entry = state.transcript.since(0, None).unwrap().entries.remove(0);
+        assert_eq!(entry.attestation_b64, Some(attestation));
+    }
+
+    #[tokio::test]
+    async fn test_released_result_is_submitted() {
+        let mut state = test_state(vec![5u8; 4096], 1 << 30);
+        let config = submission::SubmissionConfig { package: Some("0x1".into()), ..submission::SubmissionConfig::from_env() };
+        state.submitter = submission::Submitter::new(config);
+        let mut queue = state.submitter.take_queue().unwrap();
+        let mut vr = request("blob-5");
+        vr.release = Some(escrow::ReleaseCondition { at_ms: Some(1), sui_object_id: None });
+        let release = vr.release.clone();
+        let mut job = state.jobs.start("blob-5");
+        let resp = run_verification(&state, vr, &mut job, i18n::Lang::En, None).await.unwrap();
+        let (job_id, score) = (resp.job_id.clone(), resp.quality_score);
+        deliver(&state, release, resp).unwrap();
+        // Held results aren't submitted.
+        assert!(queue.try_recv().is_err());
+
+        for released in state.escrow.release_due(state.blobs.as_ref()).await.unwrap() {
+            publish_released(&state, released);
+        }
+        let submitted = queue.try_recv().unwrap();
+        assert_eq!((submitted.job_id.as_str(), submitted.blob_id.as_str()), (job_id.as_str(), "blob-5"));
+        assert_eq!(submitted.quality_score, score);
+        assert_eq!(submitted.attestation, format!("blob-5:{}", score).into_bytes());
+        assert!(state.jobs.get(&job_id).unwrap().submission.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_substituted_content_is_rejected() {
+        let blob = vec![7u8; 4096];
+        let mut state = test_state(blob.clone(), 1 << 30);
+        state.config.require_content_digest = true;
+        let mut job = state.jobs.start("blob-3");
+        let err = run_verification(&state, request("blob-3"), &mut job, i18n::Lang::En, None).await.err().expect("digest is required");
+        assert!(err.to_string().contains("content_sha256 is required"));
+
+        let mut vr = request("blob-3");
+        vr.content_sha256 = Some(integrity::sha256_hex(b"the bytes the seller listed"));
+        let mut job = state.jobs.start("blob-3");
+        let err = run_verification(&state, vr, &mut job, i18n::Lang::En, None).await.err().expect("digest mismatch must fail");
+        assert_eq!(errors::spec(errors::code(&err)).status, StatusCode::BAD_GATEWAY);
+
+        let mut vr = request("blob-3");
+        vr.content_sha256 = Some(integrity::sha256_hex(&blob));
+        let mut job = state.jobs.start("blob-3");
+        let resp = run_verification(&state, vr, &mut job, i18n::Lang::En, None).await.unwrap();
+        assert_eq!(resp.content_sha256, Some(integrity::sha256_hex(&blob)));
+
+        // Without a digest, the length registered for the blob ID on Sui still has to match.
+        state.config.require_content_digest = false;
+        let mut job = state.jobs.start("registered-4096");
+        assert!(run_verification(&state, request("registered-4096"), &mut job, i18n::Lang::En, None).await.is_ok());
+        let mut job = state.jobs.start("registered-4097");
+        let err = run_verification(&state, request("registered-4097"), &mut job, i18n::Lang::En, None).await.err().expect("length mismatch must fail");
+        assert_eq!(errors::code(&err), "QUALITY_CONTENT_MISMATCH");
+    }
+
+    #[tokio::test]
+    async fn test_merkle_batch_leaves_share_one_attestation() {
+        let state = test_state((0..8 * 1024).map(|i| (i as u8).wrapping_mul(7)).collect(), 1 << 20);
+        let mut responses = Vec::new();
+        for blob_id in ["blob-a", "blob-b", "blob-c"] {
+            let mut job = state.jobs.start(blob_id);
+            let vr = VerificationRequest { batch_leaf: true, ..request(blob_id) };
+            responses.push(run_verification(&state, vr, &mut job, i18n::Lang::En, None).await.unwrap());
+        }
+        assert!(responses.iter().all(|r| r.attestation.is_empty()));
+        assert!(state.transcript.since(0, None).unwrap().entries.is_empty());
+
+        let data = responses.iter().map(|r| r.attestation_data.as_ref().unwrap()).collect::<Vec<_>>();
+        let (batch, proofs) = attest_batch(&state, &data).unwrap();
+        assert_eq!(batch.statement.leaf_count, 3);
+        for (data, proof) in data.iter().zip(&proofs) {
+            let serialized = nautilus_canonical::canonical_bytes(data).unwrap();
+            assert!(tee_attestation::merkle::verify_inclusion(&serialized, proof, &batch.statement.merkle_root));
+        }
+        let transcript = state.transcript.since(0, None).unwrap();
+        assert_eq!(transcript.entries.len(), 1);
+        assert_eq!(transcript.entries[0].blob_id, batch.statement.merkle_root);
+    }
+
+    #[tokio::test]
+    async fn test_batch_prefetch_feeds_verification() {
+        let blob = (0..8 * 1024).map(|i| (i as u8).wrapping_mul(13)).collect::<Vec<_>>();
+        let state = test_state(blob.clone(), 1 << 20);
+        let ids = vec!["a".to_string(), "b".to_string(), "c".to_string()];
+        let fetched = state.blobs.fetch_blobs(&ids, 1 << 20, 2).await;
+        assert_eq!(fetched.len(), 3);
+        let bytes = fetched.into_iter().next().unwrap().unwrap();
+        assert_eq!(bytes, blob);
+
+        let mut job = state.jobs.start("a");
+        let resp = run_verification(&state, request("a"), &mut job, i18n::Lang::En, Some(bytes)).await.unwrap();
+        assert_eq!(resp.content_sha256, Some(integrity::sha256_hex(&blob)));
+
+        let mut job = state.jobs.start("big");
+        let err = run_verification(&state, request("big"), &mut job, i18n::Lang::En, Some(vec![0u8; 2 << 20]))
+            .await
+            .err()
+            .expect("prefetched bytes still respect the job cap");
+        assert!(err.downcast_ref::<jobs::JobOom>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_sui_object_resolves_registered_blob() {
+        let state = test_state(vec![7u8; 1024], 1 << 20);
+        let by_object = VerificationRequest { sui_object_id: Some("0x5".into()), ..request("") };
+        assert_eq!(resolve_blob_ref(&state, by_object).await.unwrap().blob_id, "blob-of-0x5");
+        let mismatched = VerificationRequest { sui_object_id: Some("0x5".into()), ..request("other") };
+        assert!(resolve_blob_ref(&state, mismatched).await.is_err());
+        assert!(resolve_blob_ref(&state, request("")).await.is_err());
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, OnceLock};
+
+// Minimal in-process metrics registry rendered in the Prometheus text exposition format.
+// Series are keyed by metric name plus a rendered label set; values are plain f64s.
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Counter,
+    Gauge,
+}
+
+struct Series {
+    kind: Kind,
+    help: &'static str,
+    values: BTreeMap<String, f64>,
+}
+
+fn registry() -> &'static Mutex<BTreeMap<&'static str, Series>> {
+    static REGISTRY: OnceLock<Mutex<BTreeMap<&'static str, Series>>> = OnceLock::new();
+    REGISTRY.get_or_init(|| Mutex::new(BTreeMap::new()))
+}
+
+fn update(name: &'static str, help: &'static str, kind: Kind, labels: &[(&str, &str)], f: impl FnOnce(&mut f64)) {
+    let mut reg = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let series = reg.entry(name).or_insert_with(|| Series { kind, help, values: BTreeMap::new() });
+    f(series.values.entry(render_labels(labels)).or_insert(0.0));
+}
+
+pub fn inc_counter(name: &'static str, help: &'static str, labels: &[(&str, &str)]) {
+    add_counter(name, help, labels, 1.0);
+}
+
+pub fn add_counter(name: &'static str, help: &'static str, labels: &[(&str, &str)], v: f64) {
+    update(name, help, Kind::Counter, labels, |cur| *cur += v);
+}
+
+pub fn set_gauge(name: &'static str, help: &'static str, labels: &[(&str, &str)], v: f64) {
+    update(name, help, Kind::Gauge, labels, |cur| *cur = v);
+}
+
+pub fn add_gauge(name: &'static str, help: &'static str, labels: &[(&str, &str)], v: f64) {
+    update(name, help, Kind::Gauge, labels, |cur| *cur += v);
+}
+
+// Render every series for GET /metrics.
+pub fn render() -> String {
+    let reg = registry().lock().unwrap_or_else(|e| e.into_inner());
+    let mut out = String::new();
+    for (name, series) in reg.iter() {
+        let kind = if series.kind == Kind::Counter { "counter" } else { "gauge" };
+        let _ = writeln!(out, "# HELP {} {}", name, series.help);
+        let _ = writeln!(out, "# TYPE {} {}", name, kind);
+        for (labels, value) in &series.values {
+            let _ = writeln!(out, "{}{} {}", name, labels, value);
+        }
+    }
+    out
+}
+
+fn render_labels(labels: &[(&str, &str)]) -> String {
+    if labels.is_empty() {
+        return String::new();
+    }
+    let inner: Vec<String> = labels
+        .iter()
+        .map(|(k, v)| format!("{}=\"{}\"", k, v.replace('\\', "\\\\").replace('"', "\\\"")))
+        .collect();
+    format!("{{{}}}", inner.join(","))
+}
@@ -0,0 +1,854 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+use crate::category::Category;
+use crate::quality_validator::{QualityCheck, SharedProfile};
+
+// ML model artifacts: safetensors, GGUF and ONNX files are sniffed as "model" and judged on
+// their structure and weights instead of as datasets:
+//   - consistency: the header parses, and every tensor's shape and dtype account for exactly
+//     the bytes it spans, within the file and overlapping no other tensor (70%); given a
+//     request's `declared_parameters`, whether the element count over all tensors is within
+//     PARAMETER_TOLERANCE of it (30%);
+//   - completeness: the share of the tensor data the header promises that the file holds;
+//   - finite_weights: the share of floating-point weights (F16, BF16, F32, F64) that are
+//     neither NaN nor infinite, capped at NONFINITE_CAP when any is, as one NaN spreads through
+//     every activation it reaches. Quantized tensors are sized but not scanned.
+// Safetensors and GGUF stream: the header is buffered until it parses (up to MAX_HEADER_BYTES,
+// retried as the buffer doubles), then tensor data is scanned in place as it passes. ONNX is a
+// single protobuf message, so it is buffered up to MAX_ONNX_BYTES and parsed at the end, its
+// initializers' raw or typed data checked; tensors stored outside the file are counted but not
+// judged. Counts, dtypes and the first REPORTED_PROBLEMS tensor problems are reported under
+// "model"; no weights leave the enclave.
+
+const MAX_HEADER_BYTES: usize = 128 * 1024 * 1024;
+const MAX_ONNX_BYTES: usize = 256 * 1024 * 1024;
+const PARAMETER_TOLERANCE: f64 = 0.05;
+const NONFINITE_CAP: u32 = 50;
+// Weight in every category unless NAUTILUS_CHECK_WEIGHTS overrides it.
+const FINITE_WEIGHTS_WEIGHT: u32 = 15;
+const GGUF_ALIGNMENT: u64 = 32;
+const MAX_TENSORS: u64 = 1 << 20;
+const MAX_DIMS: u32 = 8;
+const REPORTED_PROBLEMS: usize = 5;
+
+// Whether the head of a blob is a model artifact.
+pub fn sniff(head: &[u8]) -> bool {
+    Kind::of(head).is_some()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Safetensors,
+    Gguf,
+    Onnx,
+}
+
+impl Kind {
+    // Safetensors needs its first 9 bytes to be told from ONNX.
+    fn of(head: &[u8]) -> Option<Self> {
+        if head.starts_with(b"GGUF") {
+            return Some(Self::Gguf);
+        }
+        // Safetensors: a little-endian u64 header length, then the JSON header.
+        if let Some(len) = head.get(..8).map(|b| u64::from_le_bytes(b.try_into().unwrap_or_default())) {
+            if (2..=MAX_HEADER_BYTES as u64).contains(&len) && head.get(8) == Some(&b'{') {
+                return Some(Self::Safetensors);
+            }
+        }
+        // ONNX: a ModelProto opening with its IR version (field 1) and then another of its fields.
+        matches!(head, [0x08, 1..=15, 0x12 | 0x1a | 0x22 | 0x28 | 0x32 | 0x3a | 0x42, ..]).then_some(Self::Onnx)
+    }
+
+    fn as_str(self) -> &'static str {
+        match self {
+            Self::Safetensors => "safetensors",
+            Self::Gguf => "gguf",
+            Self::Onnx => "onnx",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Float {
+    F16,
+    Bf16,
+    F32,
+    F64,
+}
+
+impl Float {
+    fn size(self) -> usize {
+        match self {
+            Self::F16 | Self::Bf16 => 2,
+            Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    // None when finite, else whether it is a NaN. Little-endian.
+    fn nonfinite(self, b: &[u8]) -> Option<bool> {
+        let (exponent, mantissa) = match self {
+            Self::F16 => {
+                let v = u16::from_le_bytes([b[0], b[1]]);
+                (v & 0x7c00 == 0x7c00, v & 0x03ff)
+            }
+            Self::Bf16 => {
+                let v = u16::from_le_bytes([b[0], b[1]]);
+                (v & 0x7f80 == 0x7f80, v & 0x007f)
+            }
+            Self::F32 => {
+                let v = f32::from_le_bytes([b[0], b[1], b[2], b[3]]);
+                return (!v.is_finite()).then(|| v.is_nan());
+            }
+            Self::F64 => {
+                let v = f64::from_le_bytes(b[..8].try_into().unwrap_or_default());
+                return (!v.is_finite()).then(|| v.is_nan());
+            }
+        };
+        exponent.then_some(mantissa != 0)
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ModelReport {
+    pub format: &'static str,
+    pub header_valid: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub tensors: u64,
+    pub inconsistent_tensors: u64,
+    // Tensors whose data is stored outside the file (ONNX external data).
+    #[serde(skip_serializing_if = "is_zero")]
+    pub external_tensors: u64,
+    pub parameters: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub declared_parameters: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters_match: Option<bool>,
+    // Tensors per dtype, as the format names it.
+    pub dtypes: BTreeMap<String, u64>,
+    pub data_bytes: u64,
+    pub expected_data_bytes: u64,
+    pub float_values: u64,
+    pub nan: u64,
+    pub inf: u64,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub problems: Vec<String>,
+}
+
+fn is_zero(n: &u64) -> bool {
+    *n == 0
+}
+
+impl ModelReport {
+    fn tensor(&mut self, name: &str, dtype: &str, elements: u64, problem: Option<&str>) {
+        self.tensors += 1;
+        self.parameters += elements;
+        *self.dtypes.entry(dtype.to_string()).or_default() += 1;
+        if let Some(problem) = problem {
+            self.inconsistent_tensors += 1;
+            if self.problems.len() < REPORTED_PROBLEMS {
+                self.problems.push(format!("{}: {}", name, problem));
+            }
+        }
+    }
+
+    fn scan(&mut self, float: Float, values: &[u8]) {
+        for value in values.chunks_exact(float.size()) {
+            self.float_values += 1;
+            match float.nonfinite(value) {
+                Some(true) => self.nan += 1,
+                Some(false) => self.inf += 1,
+                None => {}
+            }
+        }
+    }
+
+    fn consistency(&self) -> u32 {
+        if !self.header_valid || self.tensors == 0 {
+            return 0;
+        }
+        let tensors = (self.tensors - self.inconsistent_tensors) as f64 / self.tensors as f64;
+        match self.parameters_match {
+            Some(matched) => (70.0 * tensors + if matched { 30.0 } else { 0.0 }).round() as u32,
+            None => (100.0 * tensors).round() as u32,
+        }
+    }
+
+    fn completeness(&self) -> u32 {
+        if !self.header_valid {
+            return 0;
+        }
+        if self.expected_data_bytes == 0 {
+            return 100;
+        }
+        (100 * self.data_bytes.min(self.expected_data_bytes) / self.expected_data_bytes) as u32
+    }
+
+    fn finite_weights(&self) -> u32 {
+        if !self.header_valid {
+            return 0;
+        }
+        let nonfinite = self.nan + self.inf;
+        if nonfinite == 0 {
+            return 100;
+        }
+        let score = (100.0 * (1.0 - nonfinite as f64 / self.float_values as f64)).floor() as u32;
+        score.min(NONFINITE_CAP)
+    }
+}
+
+// A tensor's data, by absolute offset in the file.
+#[derive(Debug, Clone)]
+struct Span {
+    start: u64,
+    end: u64,
+    float: Option<Float>,
+}
+
+enum Parse {
+    // More bytes needed.
+    Short,
+    Invalid(String),
+}
+
+type Parsed<T> = std::result::Result<T, Parse>;
+
+fn invalid<T>(why: impl Into<String>) -> Parsed<T> {
+    Err(Parse::Invalid(why.into()))
+}
+
+// A header as parsed: its tensors (already counted into the report) and their data.
+struct Header {
+    spans: Vec<Span>,
+}
+
+fn safetensors_dtype(dtype: &str) -> Option<(u64, Option<Float>)> {
+    Some(match dtype {
+        "F64" => (8, Some(Float::F64)),
+        "F32" => (4, Some(Float::F32)),
+        "F16" => (2, Some(Float::F16)),
+        "BF16" => (2, Some(Float::Bf16)),
+        "I64" | "U64" => (8, None),
+        "I32" | "U32" => (4, None),
+        "I16" | "U16" => (2, None),
+        "I8" | "U8" | "BOOL" | "F8_E4M3" | "F8_E5M2" => (1, None),
+        _ => return None,
+    })
+}
+
+fn parse_safetensors(buf: &[u8], report: &mut ModelReport) -> Parsed<Header> {
+    let len = u64::from_le_bytes(buf.get(..8).ok_or(Parse::Short)?.try_into().unwrap_or_default());
+    if len > MAX_HEADER_BYTES as u64 {
+        return invalid(format!("header of {} bytes", len));
+    }
+    let data_start = 8 + len;
+    let header = buf.get(8..data_start as usize).ok_or(Parse::Short)?;
+    let header: serde_json::Map<String, serde_json::Value> = match serde_json::from_slice(header) {
+        Ok(header) => header,
+        Err(_) => return invalid("header isn't a JSON object"),
+    };
+    let mut spans = Vec::new();
+    for (name, entry) in header.iter().filter(|(name, _)| *name != "__metadata__") {
+        let dtype = entry["dtype"].as_str().unwrap_or("?");
+        let numbers = |v: &serde_json::Value| -> Option<Vec<u64>> {
+            v.as_array()?.iter().map(|n| n.as_u64()).collect()
+        };
+        let offsets = numbers(&entry["data_offsets"]);
+        let elements = numbers(&entry["shape"]).and_then(|s| s.iter().try_fold(1u64, |n, &d| n.checked_mul(d)));
+        let problem = match (safetensors_dtype(dtype), elements, offsets.as_deref()) {
+            (None, _, _) => Some("unknown dtype"),
+            (_, None, _) => Some("invalid shape"),
+            (_, _, Some(&[start, end])) if start <= end => {
+                let (size, float) = safetensors_dtype(dtype).unwrap_or((1, None));
+                spans.push(Span { start: data_start + start, end: data_start + end, float });
+                let bytes = elements.and_then(|n| n.checked_mul(size));
+                (bytes != Some(end - start)).then_some("shape and dtype don't match the data size")
+            }
+            _ => Some("invalid data offsets"),
+        };
+        report.tensor(name, dtype, elements.unwrap_or(0), problem);
+    }
+    Ok(Header { spans })
+}
+
+// (block elements, block bytes) of a GGML tensor type, and whether it is a plain float.
+fn ggml_type(t: u32) -> Option<(&'static str, u64, u64, Option<Float>)> {
+    Some(match t {
+        0 => ("F32", 1, 4, Some(Float::F32)),
+        1 => ("F16", 1, 2, Some(Float::F16)),
+        2 => ("Q4_0", 32, 18, None),
+        3 => ("Q4_1", 32, 20, None),
+        6 => ("Q5_0", 32, 22, None),
+        7 => ("Q5_1", 32, 24, None),
+        8 => ("Q8_0", 32, 34, None),
+        9 => ("Q8_1", 32, 36, None),
+        10 => ("Q2_K", 256, 84, None),
+        11 => ("Q3_K", 256, 110, None),
+        12 => ("Q4_K", 256, 144, None),
+        13 => ("Q5_K", 256, 176, None),
+        14 => ("Q6_K", 256, 210, None),
+        15 => ("Q8_K", 256, 292, None),
+        16 => ("IQ2_XXS", 256, 66, None),
+        17 => ("IQ2_XS", 256, 74, None),
+        18 => ("IQ3_XXS", 256, 98, None),
+        19 => ("IQ1_S", 256, 50, None),
+        20 => ("IQ4_NL", 32, 18, None),
+        21 => ("IQ3_S", 256, 110, None),
+        22 => ("IQ2_S", 256, 82, None),
+        23 => ("IQ4_XS", 256, 136, None),
+        24 => ("I8", 1, 1, None),
+        25 => ("I16", 1, 2, None),
+        26 => ("I32", 1, 4, None),
+        27 => ("I64", 1, 8, None),
+        28 => ("F64", 1, 8, Some(Float::F64)),
+        29 => ("IQ1_M", 256, 56, None),
+        30 => ("BF16", 1, 2, Some(Float::Bf16)),
+        _ => return None,
+    })
+}
+
+struct Reader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, n: u64) -> Parsed<&'a [u8]> {
+        let end = self.pos.checked_add(usize::try_from(n).map_err(|_| Parse::Invalid("length overflow".into()))?);
+        let out = end.and_then(|end| self.buf.get(self.pos..end)).ok_or(Parse::Short)?;
+        self.pos += out.len();
+        Ok(out)
+    }
+
+    fn u32(&mut self) -> Parsed<u32> {
+        Ok(u32::from_le_bytes(self.bytes(4)?.try_into().unwrap_or_default()))
+    }
+
+    fn u64(&mut self) -> Parsed<u64> {
+        Ok(u64::from_le_bytes(self.bytes(8)?.try_into().unwrap_or_default()))
+    }
+
+    fn string(&mut self) -> Parsed<&'a [u8]> {
+        let len = self.u64()?;
+        if len > MAX_HEADER_BYTES as u64 {
+            return invalid("string longer than the header");
+        }
+        self.bytes(len)
+    }
+
+    // Skips a GGUF metadata value of the given type; returns it when it is an unsigned integer.
+    fn gguf_value(&mut self, value_type: u32, depth: u32) -> Parsed<Option<u64>> {
+        Ok(match value_type {
+            0 | 1 | 7 => Some(self.bytes(1)?[0] as u64),
+            2 | 3 => Some(u16::from_le_bytes(self.bytes(2)?.try_into().unwrap_or_default()) as u64),
+            4..=6 => Some(self.u32()? as u64),
+            10..=12 => Some(self.u64()?),
+            8 => {
+                self.string()?;
+                None
+            }
+            9 if depth < 4 => {
+                let (element_type, count) = (self.u32()?, self.u64()?);
+                for _ in 0..count {
+                    self.gguf_value(element_type, depth + 1)?;
+                }
+                None
+            }
+            _ => return invalid(format!("metadata value of type {}", value_type)),
+        })
+    }
+}
+
+fn parse_gguf(buf: &[u8], report: &mut ModelReport) -> Parsed<Header> {
+    let mut r = Reader { buf, pos: 4 };
+    let version = r.u32()?;
+    if !(2..=3).contains(&version) {
+        return invalid(format!("GGUF version {}", version));
+    }
+    let (tensors, kvs) = (r.u64()?, r.u64()?);
+    if tensors > MAX_TENSORS || kvs > MAX_TENSORS {
+        return invalid("implausible tensor or metadata count");
+    }
+    let mut alignment = GGUF_ALIGNMENT;
+    for _ in 0..kvs {
+        let key = r.string()?;
+        let value_type = r.u32()?;
+        let value = r.gguf_value(value_type, 0)?;
+        if key == b"general.alignment" {
+            let valid = value.filter(|a| value_type == 4 && a.is_power_of_two());
+            alignment = valid.ok_or(Parse::Invalid("invalid general.alignment".into()))?;
+        }
+    }
+    let mut infos = Vec::new();
+    for _ in 0..tensors {
+        let name = String::from_utf8_lossy(r.string()?).into_owned();
+        let dims = r.u32()?;
+        if dims > MAX_DIMS {
+            return invalid(format!("tensor {} has {} dimensions", name, dims));
+        }
+        let shape = (0..dims).map(|_| r.u64()).collect::<Parsed<Vec<u64>>>()?;
+        infos.push((name, shape, r.u32()?, r.u64()?));
+    }
+    let data_start = (r.pos as u64).div_ceil(alignment) * alignment;
+    let mut spans = Vec::new();
+    for (name, shape, ggml, offset) in infos {
+        let elements = shape.iter().try_fold(1u64, |n, &d| n.checked_mul(d));
+        let Some((dtype, block, block_bytes, float)) = ggml_type(ggml) else {
+            report.tensor(&name, &format!("type {}", ggml), elements.unwrap_or(0), Some("unknown tensor type"));
+            continue;
+        };
+        let bytes = elements.filter(|n| n % block == 0).and_then(|n| (n / block).checked_mul(block_bytes));
+        let problem = match bytes {
+            None => Some("shape doesn't fit the type's blocks"),
+            Some(_) if offset % alignment != 0 => Some("misaligned data offset"),
+            Some(bytes) => {
+                let start = data_start + offset;
+                spans.push(Span { start, end: start + bytes, float });
+                None
+            }
+        };
+        report.tensor(&name, dtype, elements.unwrap_or(0), problem);
+    }
+    Ok(Header { spans })
+}
+
+// Protobuf wire format, enough to walk an ONNX model's initializers.
+fn varint(r: &mut Reader) -> Parsed<u64> {
+    let mut value = 0u64;
+    for shift in (0..64).step_by(7) {
+        let b = r.bytes(1).map_err(|_| Parse::Invalid("truncated".into()))?[0];
+        value |= ((b & 0x7f) as u64) << shift;
+        if b & 0x80 == 0 {
+            return Ok(value);
+        }
+    }
+    invalid("varint too long")
+}
+
+enum Field<'a> {
+    Varint(u64),
+    Bytes(&'a [u8]),
+    Fixed(&'a [u8]),
+}
+
+fn fields<'a>(buf: &'a [u8]) -> Parsed<Vec<(u64, Field<'a>)>> {
+    let mut r = Reader { buf, pos: 0 };
+    let mut out = Vec::new();
+    while r.pos < buf.len() {
+        let key = varint(&mut r)?;
+        let truncated = |_: Parse| Parse::Invalid("truncated".into());
+        let field = match key & 7 {
+            0 => Field::Varint(varint(&mut r)?),
+            1 => Field::Fixed(r.bytes(8).map_err(truncated)?),
+            2 => {
+                let len = varint(&mut r)?;
+                Field::Bytes(r.bytes(len).map_err(truncated)?)
+            }
+            5 => Field::Fixed(r.bytes(4).map_err(truncated)?),
+            wire => return invalid(format!("wire type {}", wire)),
+        };
+        out.push((key >> 3, field));
+    }
+    Ok(out)
+}
+
+// (name, element bytes, float) of an ONNX TensorProto data type.
+fn onnx_type(t: u64) -> Option<(&'static str, u64, Option<Float>)> {
+    Some(match t {
+        1 => ("FLOAT", 4, Some(Float::F32)),
+        2 => ("UINT8", 1, None),
+        3 => ("INT8", 1, None),
+        4 => ("UINT16", 2, None),
+        5 => ("INT16", 2, None),
+        6 => ("INT32", 4, None),
+        7 => ("INT64", 8, None),
+        9 => ("BOOL", 1, None),
+        10 => ("FLOAT16", 2, Some(Float::F16)),
+        11 => ("DOUBLE", 8, Some(Float::F64)),
+        12 => ("UINT32", 4, None),
+        13 => ("UINT64", 8, None),
+        16 => ("BFLOAT16", 2, Some(Float::Bf16)),
+        _ => return None,
+    })
+}
+
+fn parse_onnx(buf: &[u8], report: &mut ModelReport) -> Parsed<()> {
+    let graph = fields(buf)?.into_iter().find_map(|(n, f)| match (n, f) {
+        (7, Field::Bytes(graph)) => Some(graph),
+        _ => None,
+    });
+    let Some(graph) = graph else { return invalid("no graph") };
+    for (n, field) in fields(graph)? {
+        let (5, Field::Bytes(tensor)) = (n, field) else { continue };
+        let mut shape = Vec::new();
+        let (mut data_type, mut name, mut external) = (0, String::new(), false);
+        let (mut raw, mut floats, mut typed) = (None, Vec::new(), 0u64);
+        for (n, field) in fields(tensor)? {
+            match (n, field) {
+                (1, Field::Varint(d)) => shape.push(d),
+                (1, Field::Bytes(packed)) => {
+                    let mut r = Reader { buf: packed, pos: 0 };
+                    while r.pos < packed.len() {
+                        shape.push(varint(&mut r)?);
+                    }
+                }
+                (2, Field::Varint(t)) => data_type = t,
+                (4, Field::Bytes(packed)) => floats.extend_from_slice(packed),
+                (4, Field::Fixed(value)) => floats.extend_from_slice(value),
+                (10, Field::Bytes(packed)) => typed += packed.len() as u64 / 8,
+                (5 | 7 | 11, Field::Bytes(packed)) => {
+                    let mut r = Reader { buf: packed, pos: 0 };
+                    while r.pos < packed.len() {
+                        varint(&mut r)?;
+                        typed += 1;
+                    }
+                }
+                (5 | 7 | 11, Field::Varint(_)) | (10, Field::Fixed(_)) => typed += 1,
+                (8, Field::Bytes(n)) => name = String::from_utf8_lossy(n).into_owned(),
+                (9, Field::Bytes(bytes)) => raw = Some(bytes),
+                (14, Field::Varint(location)) => external = location == 1,
+                _ => {}
+            }
+        }
+        let elements = shape.iter().try_fold(1u64, |n, &d| n.checked_mul(d));
+        let Some((dtype, size, float)) = onnx_type(data_type) else {
+            report.tensor(&name, &format!("type {}", data_type), elements.unwrap_or(0), Some("unknown data type"));
+            continue;
+        };
+        let (held, problem) = match (elements, raw) {
+            (None, _) => (0, Some("invalid shape")),
+            _ if external => {
+                report.external_tensors += 1;
+                (0, None)
+            }
+            (Some(n), Some(raw)) => {
+                if let Some(float) = float {
+                    report.scan(float, raw);
+                }
+                let matches = n.checked_mul(size) == Some(raw.len() as u64);
+                (raw.len() as u64, (!matches).then_some("shape and data type don't match the data size"))
+            }
+            (Some(n), None) => {
+                report.scan(Float::F32, &floats);
+                let values = floats.len() as u64 / 4 + typed;
+                (values * size, (values != n).then_some("shape doesn't match the value count"))
+            }
+        };
+        report.data_bytes += held;
+        report.expected_data_bytes += held;
+        report.tensor(&name, dtype, elements.unwrap_or(0), problem);
+    }
+    Ok(())
+}
+
+enum Stage {
+    // Buffering until the header parses; retried once the buffer reaches `retry` bytes.
+    Header { retry: usize },
+    // Scanning tensor data; `next` indexes the first span not yet passed.
+    Data { spans: Vec<Span>, next: usize, carry: Vec<u8> },
+    // ONNX, buffered whole.
+    Buffered,
+    Done,
+}
+
+// The artifact as it streams in, for every check that shares it.
+pub struct ModelProfile {
+    declared: Option<u64>,
+    buf: Vec<u8>,
+    // Bytes seen.
+    offset: u64,
+    stage: Stage,
+    report: ModelReport,
+    finished: bool,
+}
+
+impl ModelProfile {
+    pub fn new(declared_parameters: Option<u64>) -> Self {
+        Self {
+            declared: declared_parameters,
+            buf: Vec::new(),
+            offset: 0,
+            stage: Stage::Header { retry: 0 },
+            report: ModelReport::default(),
+            finished: false,
+        }
+    }
+
+    fn fail(&mut self, why: String) {
+        self.report.error = Some(why);
+        self.report.header_valid = false;
+        self.stage = Stage::Done;
+        self.buf = Vec::new();
+    }
+
+    // Tries the buffered header; on success the rest of the buffer is tensor data.
+    fn parse(&mut self, last: bool) {
+        let kind = match Kind::of(&self.buf) {
+            Some(kind) if self.buf.len() >= 9 || last => kind,
+            None if last || self.buf.len() >= 9 => return self.fail("not a safetensors, GGUF or ONNX header".into()),
+            _ => {
+                self.stage = Stage::Header { retry: 9 };
+                return;
+            }
+        };
+        self.report.format = kind.as_str();
+        if kind == Kind::Onnx {
+            self.stage = Stage::Buffered;
+            return;
+        }
+        let mut report = ModelReport { format: self.report.format, ..Default::default() };
+        let parsed = match kind {
+            Kind::Gguf => parse_gguf(&self.buf, &mut report),
+            _ => parse_safetensors(&self.buf, &mut report),
+        };
+        match parsed {
+            Ok(Header { mut spans }) => {
+                report.header_valid = true;
+                spans.sort_by_key(|s| (s.start, s.end));
+                // Overlapping spans can't both be right; only the first is scanned.
+                let mut end = 0;
+                spans.retain(|s| {
+                    let fits = s.start >= end;
+                    end = end.max(s.end);
+                    report.inconsistent_tensors += !fits as u64;
+                    fits
+                });
+                report.expected_data_bytes = spans.iter().map(|s| s.end - s.start).sum();
+                self.report = report;
+                let buf = std::mem::take(&mut self.buf);
+                self.stage = Stage::Data { spans, next: 0, carry: Vec::new() };
+                self.scan(&buf, 0);
+            }
+            Err(Parse::Short) if last => self.fail("truncated header".into()),
+            Err(Parse::Short) if self.buf.len() > MAX_HEADER_BYTES => {
+                self.fail(format!("header larger than {} bytes", MAX_HEADER_BYTES))
+            }
+            Err(Parse::Short) => self.stage = Stage::Header { retry: self.buf.len() * 2 },
+            Err(Parse::Invalid(why)) => self.fail(why),
+        }
+    }
+
+    // Tensor data in `data`, which starts `at` bytes into the file.
+    fn scan(&mut self, mut data: &[u8], mut at: u64) {
+        let Stage::Data { spans, next, carry } = &mut self.stage else { return };
+        while !data.is_empty() && *next < spans.len() {
+            let span = &spans[*next];
+            if at >= span.end {
+                *next += 1;
+                carry.clear();
+                continue;
+            }
+            let skip = span.start.saturating_sub(at).min(data.len() as u64) as usize;
+            let take = ((span.end - at.max(span.start)) as usize).min(data.len() - skip);
+            let bytes = &data[skip..skip + take];
+            if let Some(float) = span.float {
+                let fill = (float.size() - carry.len()).min(bytes.len());
+                let rest = if carry.is_empty() {
+                    bytes
+                } else {
+                    carry.extend_from_slice(&bytes[..fill]);
+                    if carry.len() == float.size() {
+                        self.report.scan(float, carry);
+                        carry.clear();
+                    }
+                    &bytes[fill..]
+                };
+                let whole = rest.len() - rest.len() % float.size();
+                self.report.scan(float, &rest[..whole]);
+                carry.extend_from_slice(&rest[whole..]);
+            }
+            self.report.data_bytes += take as u64;
+            data = &data[skip + take..];
+            at += (skip + take) as u64;
+        }
+    }
+}
+
+impl SharedProfile for ModelProfile {
+    fn update(&mut self, data: &[u8]) {
+        let at = self.offset;
+        self.offset += data.len() as u64;
+        match self.stage {
+            Stage::Header { retry } => {
+                self.buf.extend_from_slice(data);
+                if self.buf.len() >= retry {
+                    self.parse(false);
+                }
+            }
+            Stage::Buffered => {
+                self.buf.extend_from_slice(data);
+                if self.buf.len() > MAX_ONNX_BYTES {
+                    self.fail(format!("larger than the {} bytes parsed", MAX_ONNX_BYTES));
+                }
+            }
+            Stage::Data { .. } => self.scan(data, at),
+            Stage::Done => {}
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.finished {
+            return;
+        }
+        self.finished = true;
+        if matches!(self.stage, Stage::Header { .. }) {
+            self.parse(true);
+        }
+        if matches!(self.stage, Stage::Buffered) {
+            let buf = std::mem::take(&mut self.buf);
+            let mut report = ModelReport { format: self.report.format, ..Default::default() };
+            match parse_onnx(&buf, &mut report) {
+                Ok(()) => self.report = ModelReport { header_valid: true, ..report },
+                Err(Parse::Short) => self.fail("truncated".into()),
+                Err(Parse::Invalid(why)) => self.fail(why),
+            }
+        }
+        self.stage = Stage::Done;
+        if let Some(declared) = self.declared.filter(|_| self.report.header_valid) {
+            let off = (self.report.parameters as f64 - declared as f64).abs() / declared.max(1) as f64;
+            self.report.declared_parameters = Some(declared);
+            self.report.parameters_match = Some(off <= PARAMETER_TOLERANCE);
+        }
+    }
+
+    fn score(&self, check: &str) -> Option<u32> {
+        if !self.finished {
+            return None;
+        }
+        match check {
+            "consistency" => Some(self.report.consistency()),
+            "completeness" => Some(self.report.completeness()),
+            "finite_weights" => Some(self.report.finite_weights()),
+            _ => None,
+        }
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.buf.capacity() as u64
+            + match &self.stage {
+                Stage::Data { spans, .. } => (spans.capacity() * std::mem::size_of::<Span>()) as u64,
+                _ => 0,
+            }
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        serde_json::to_value(&self.report).ok().map(|v| ("model", v))
+    }
+}
+
+// NaN and infinite weights. Scored from the model profile; there are no weights to judge
+// without one.
+pub struct FiniteWeights;
+
+impl QualityCheck for FiniteWeights {
+    fn name(&self) -> &'static str {
+        "finite_weights"
+    }
+
+    fn weight(&self, _category: Category) -> u32 {
+        FINITE_WEIGHTS_WEIGHT
+    }
+
+    fn update(&mut self, _data: &[u8]) {}
+
+    fn score(&self, _total_len: u64) -> u32 {
+        0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::content_policy::{self, DetectedType};
+    use crate::quality_validator::{validate_dataset_quality, ValidationOptions};
+
+    fn safetensors(header: &str, data: &[u8]) -> Vec<u8> {
+        [&(header.len() as u64).to_le_bytes()[..], header.as_bytes(), data].concat()
+    }
+
+    fn profile(data: &[u8], declared: Option<u64>) -> ModelProfile {
+        let mut profile = ModelProfile::new(declared);
+        data.chunks(7).for_each(|c| profile.update(c));
+        profile.finish();
+        profile
+    }
+
+    #[test]
+    fn test_model_artifacts() {
+        // Two F32 tensors, one weight NaN and one infinite, and an F16 one; streamed in odd chunks.
+        let weights: Vec<u8> = (0..12)
+            .map(|i| match i {
+                3 => f32::NAN,
+                7 => f32::INFINITY,
+                _ => i as f32,
+            })
+            .flat_map(f32::to_le_bytes)
+            .chain([0x00, 0x3c, 0x00, 0x7c])
+            .collect();
+        let header = r#"{"__metadata__":{"format":"pt"},"a":{"dtype":"F32","shape":[2,4],"data_offsets":[0,32]},
+            "b":{"dtype":"F32","shape":[4],"data_offsets":[32,48]},"c":{"dtype":"F16","shape":[2],"data_offsets":[48,52]}}"#;
+        let file = safetensors(header, &weights);
+        assert_eq!(content_policy::sniff(&file), DetectedType::Model);
+        let model = profile(&file, Some(14));
+        let r = &model.report;
+        assert_eq!((r.format, r.header_valid, r.tensors, r.inconsistent_tensors, r.parameters), ("safetensors", true, 3, 0, 14));
+        assert_eq!((r.float_values, r.nan, r.inf, r.data_bytes, r.expected_data_bytes), (14, 1, 2, 52, 52));
+        assert_eq!(r.parameters_match, Some(true));
+        assert_eq!((r.consistency(), r.completeness(), r.finite_weights()), (100, 100, 50));
+
+        // Truncated data, a shape that doesn't match its span and a count far from the declared one.
+        let header = r#"{"a":{"dtype":"F32","shape":[2,4],"data_offsets":[0,32]},"b":{"dtype":"F32","shape":[5],"data_offsets":[32,48]}}"#;
+        let model = profile(&safetensors(header, &weights[..24]), Some(1_000));
+        let r = &model.report;
+        assert_eq!((r.inconsistent_tensors, r.parameters_match, r.data_bytes), (1, Some(false), 24));
+        assert_eq!((r.consistency(), r.completeness()), (35, 50));
+        assert!(!profile(&safetensors("{\"a\":", b""), None).report.header_valid);
+
+        // GGUF v3: one metadata string, an F32 and a Q8_0 tensor, data aligned to 32 bytes.
+        let mut gguf = b"GGUF".to_vec();
+        gguf.extend(3u32.to_le_bytes());
+        gguf.extend(2u64.to_le_bytes());
+        gguf.extend(1u64.to_le_bytes());
+        let string = |s: &str| [&(s.len() as u64).to_le_bytes()[..], s.as_bytes()].concat();
+        gguf.extend(string("general.name"));
+        gguf.extend(8u32.to_le_bytes());
+        gguf.extend(string("tiny"));
+        for (name, dims, ty, offset) in [("w", 4u64, 0u32, 0u64), ("q", 32, 8, 32)] {
+            gguf.extend(string(name));
+            gguf.extend(1u32.to_le_bytes());
+            gguf.extend(dims.to_le_bytes());
+            gguf.extend(ty.to_le_bytes());
+            gguf.extend(offset.to_le_bytes());
+        }
+        gguf.resize(gguf.len().div_ceil(32) * 32, 0);
+        gguf.extend([1.0f32, f32::NAN, 2.0, 3.0].iter().flat_map(|f| f.to_le_bytes()));
+        gguf.resize(gguf.len() + 16 + 34, 7);
+        let r = profile(&gguf, None).report;
+        assert_eq!((r.format, r.tensors, r.inconsistent_tensors, r.parameters), ("gguf", 2, 0, 36));
+        assert_eq!((r.float_values, r.nan, r.completeness()), (4, 1, 100));
+        assert_eq!(r.dtypes.keys().collect::<Vec<_>>(), ["F32", "Q8_0"]);
+
+        // ONNX: a graph with one FLOAT initializer in raw data and one whose dims don't match.
+        let tensor = |dims: &[u8], raw: &[u8]| {
+            let mut t = vec![0x0a, dims.len() as u8];
+            t.extend(dims);
+            t.extend([0x10, 0x01, 0x42, 0x01, b'w', 0x4a, raw.len() as u8]);
+            t.extend(raw);
+            [&[0x2a, t.len() as u8][..], &t].concat()
+        };
+        let raw: Vec<u8> = [0.5f32, 1.5].iter().flat_map(|f| f.to_le_bytes()).collect();
+        let graph = [tensor(&[2], &raw), tensor(&[3], &raw)].concat();
+        let onnx = [&[0x08, 0x07, 0x3a, graph.len() as u8][..], &graph].concat();
+        assert_eq!(content_policy::sniff(&onnx), DetectedType::Model);
+        let r = profile(&onnx, None).report;
+        assert_eq!((r.format, r.tensors, r.inconsistent_tensors, r.float_values), ("onnx", 2, 1, 4));
+
+        // Through the validator: the model checks stand in for the byte-level ones.
+        let report = validate_dataset_quality(&file, &ValidationOptions::default()).unwrap();
+        assert_eq!(report.format, DetectedType::Model);
+        assert_eq!((report.breakdown.consistency, report.breakdown.check("finite_weights")), (Some(100), Some(50)));
+        assert_eq!(report.details["model"]["nan"], 1);
+    }
+}
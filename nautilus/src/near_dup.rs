@@ -0,0 +1,227 @@
+use std::collections::HashMap;
+
+use crate::category::Category;
+use crate::dedupe::mix;
+use crate::quality_validator::{QualityCheck, MAX_EVIDENCE_ITEMS};
+
+// Internal redundancy of record datasets (CSV rows, JSON Lines objects). The 4-byte rolling
+// window counts any repeated byte pattern, which in delimited data is mostly field syntax; here
+// each record is compared with the records before it instead:
+//   - the record is normalized (ASCII lowercase, whitespace collapsed) and cut into 5-byte
+//     shingles;
+//   - a 32-bin one-permutation MinHash, densified so short records fill every bin, estimates
+//     its Jaccard similarity to other records;
+//   - LSH (8 bands of 4 rows) finds candidates sharing a band, and a candidate counts when its
+//     estimated similarity reaches NEAR_DUPLICATE_JACCARD.
+// The score is the share of records that aren't near duplicates of an earlier one. Signatures
+// of the first MAX_TRACKED_RECORDS records are kept; later records are still checked against
+// them but not added.
+
+const SHINGLE: usize = 5;
+const SIG_LEN: usize = 32;
+const BANDS: usize = 8;
+const ROWS: usize = SIG_LEN / BANDS;
+const EMPTY: u32 = u32::MAX;
+pub const NEAR_DUPLICATE_JACCARD: f64 = 0.85;
+// Records shorter than this (after normalization) repeat legitimately and aren't compared.
+const MIN_RECORD_BYTES: usize = 8;
+const MAX_TRACKED_RECORDS: usize = 200_000;
+// A record is compared in pieces once it grows past this without a newline.
+const MAX_RECORD_BYTES: usize = 1024 * 1024;
+// Candidates verified per record; popular buckets would otherwise make this quadratic.
+const MAX_CANDIDATES: usize = 16;
+
+#[derive(Default)]
+pub struct NearDuplicates {
+    record: Vec<u8>,
+    records: u64,
+    compared: u64,
+    near_duplicates: u64,
+    // Signatures of tracked records, by tracked index.
+    signatures: Vec<[u32; SIG_LEN]>,
+    // (band, band hash) -> tracked records in the bucket.
+    buckets: HashMap<(u8, u64), Vec<u32>>,
+    // (record, earlier record, estimated similarity) for the first matches.
+    pairs: Vec<(u64, u64, f64)>,
+    // Record index of each tracked signature.
+    tracked_records: Vec<u64>,
+}
+
+impl NearDuplicates {
+    fn end_record(&mut self) {
+        let raw = std::mem::take(&mut self.record);
+        let normalized = normalize(&raw);
+        self.record = raw;
+        self.record.clear();
+        if normalized.is_empty() {
+            return;
+        }
+        let index = self.records;
+        self.records += 1;
+        if normalized.len() < MIN_RECORD_BYTES {
+            return;
+        }
+        self.compared += 1;
+        let sig = signature(&normalized);
+        let keys: Vec<(u8, u64)> = band_keys(&sig).collect();
+        let mut checked = 0;
+        let mut best: Option<(u32, f64)> = None;
+        'bands: for key in &keys {
+            for &other in self.buckets.get(key).into_iter().flatten() {
+                if checked == MAX_CANDIDATES {
+                    break 'bands;
+                }
+                checked += 1;
+                let s = similarity(&sig, &self.signatures[other as usize]);
+                if s >= NEAR_DUPLICATE_JACCARD && best.is_none_or(|(_, b)| s > b) {
+                    best = Some((other, s));
+                }
+            }
+        }
+        if let Some((other, s)) = best {
+            self.near_duplicates += 1;
+            if self.pairs.len() < MAX_EVIDENCE_ITEMS {
+                self.pairs.push((index, self.tracked_records[other as usize], s));
+            }
+        }
+        if self.signatures.len() < MAX_TRACKED_RECORDS {
+            let id = self.signatures.len() as u32;
+            self.signatures.push(sig);
+            self.tracked_records.push(index);
+            for key in keys {
+                self.buckets.entry(key).or_default().push(id);
+            }
+        }
+    }
+
+    fn ratio(&self) -> f64 {
+        if self.compared == 0 {
+            0.0
+        } else {
+            self.near_duplicates as f64 / self.compared as f64
+        }
+    }
+}
+
+impl QualityCheck for NearDuplicates {
+    fn name(&self) -> &'static str {
+        "authenticity"
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        category.preset().weights.authenticity
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for piece in data.split_inclusive(|&b| b == b'\n') {
+            self.record.extend_from_slice(piece);
+            if piece.ends_with(b"\n") || self.record.len() >= MAX_RECORD_BYTES {
+                self.end_record();
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        self.end_record();
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        (100.0 * (1.0 - self.ratio())).round() as u32
+    }
+
+    fn state_bytes(&self) -> u64 {
+        let per_record = SIG_LEN * 4 + 8 + BANDS * 24;
+        (self.record.capacity() + self.signatures.len() * per_record) as u64
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        Some((
+            "near_duplicates",
+            serde_json::json!({
+                "records": self.records,
+                "records_compared": self.compared,
+                "near_duplicate_records": self.near_duplicates,
+                "near_duplicate_ratio": (self.ratio() * 1000.0).round() / 1000.0,
+                "similarity_threshold": NEAR_DUPLICATE_JACCARD,
+                "tracked_records": self.signatures.len(),
+            }),
+        ))
+    }
+
+    // Record indices count non-blank records from zero.
+    fn evidence(&self) -> Option<serde_json::Value> {
+        let pairs: Vec<_> = self
+            .pairs
+            .iter()
+            .map(|(record, of, s)| serde_json::json!({ "record": record, "of": of, "similarity": (s * 1000.0).round() / 1000.0 }))
+            .collect();
+        Some(serde_json::json!({ "near_duplicates": pairs, "unlisted": self.near_duplicates - pairs.len() as u64 }))
+    }
+}
+
+fn normalize(raw: &[u8]) -> Vec<u8> {
+    let mut out = Vec::with_capacity(raw.len());
+    for &b in raw {
+        if b.is_ascii_whitespace() {
+            if out.last().is_some_and(|&l| l != b' ') {
+                out.push(b' ');
+            }
+        } else {
+            out.push(b.to_ascii_lowercase());
+        }
+    }
+    if out.last() == Some(&b' ') {
+        out.pop();
+    }
+    out
+}
+
+// One-permutation MinHash over SHINGLE-byte windows. Empty bins borrow the value of the next
+// non-empty bin (with the distance mixed in), so records with few shingles still compare on
+// every bin.
+fn signature(record: &[u8]) -> [u32; SIG_LEN] {
+    let mut sig = [EMPTY; SIG_LEN];
+    for shingle in record.windows(SHINGLE.min(record.len())) {
+        let h = mix(shingle.iter().fold(0xcbf2_9ce4_8422_2325u64, |acc, &b| (acc ^ b as u64).wrapping_mul(0x100_0000_01b3)));
+        let bin = (h % SIG_LEN as u64) as usize;
+        sig[bin] = sig[bin].min((h >> 32) as u32);
+    }
+    let filled = sig;
+    for (i, value) in sig.iter_mut().enumerate() {
+        if *value == EMPTY {
+            let (distance, donor) = (1..SIG_LEN).map(|d| (d, filled[(i + d) % SIG_LEN])).find(|(_, v)| *v != EMPTY).unwrap_or((0, 0));
+            *value = (mix(donor as u64 ^ ((distance as u64) << 32)) >> 32) as u32;
+        }
+    }
+    sig
+}
+
+fn similarity(a: &[u32; SIG_LEN], b: &[u32; SIG_LEN]) -> f64 {
+    a.iter().zip(b).filter(|(x, y)| x == y).count() as f64 / SIG_LEN as f64
+}
+
+fn band_keys(sig: &[u32; SIG_LEN]) -> impl Iterator<Item = (u8, u64)> + '_ {
+    sig.chunks(ROWS).enumerate().map(|(band, rows)| (band as u8, rows.iter().fold(band as u64, |acc, &v| mix(acc ^ v as u64))))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_near_duplicate_records() {
+        let mut check = NearDuplicates::default();
+        let mut data = String::from("id,customer,city,comment\n");
+        for i in 0..200 {
+            data.push_str(&format!("{},customer-{:03},{},order {} arrived in perfect condition\n", i, i, ["Oslo", "Lima"][i % 2], i * 7919 % 1000));
+        }
+        // Re-listed rows with whitespace and case changes, and one edited word.
+        data.push_str("5,CUSTOMER-005,Lima,  order 595 arrived in perfect condition\n");
+        data.push_str("8,customer-008,oslo,order 352 arrived in perfect condition!\n");
+        check.update(data.as_bytes());
+        check.finish();
+        assert_eq!((check.records, check.near_duplicates), (203, 2));
+        assert_eq!(check.pairs.iter().map(|p| (p.0, p.1)).collect::<Vec<_>>(), vec![(201, 6), (202, 9)]);
+        assert_eq!(check.score(0), 99);
+    }
+}
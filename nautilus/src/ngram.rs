@@ -0,0 +1,274 @@
+use anyhow::{anyhow, bail, Result};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::env;
+
+use crate::dedupe::mix;
+
+// Repeated n-grams for the byte-level authenticity check. Every n-gram of each configured size
+// (NAUTILUS_NGRAM_SIZES, bytes) is hashed with a Rabin-Karp rolling hash modulo 2^61 - 1, so an
+// n-gram costs the same whatever its length. Only a sample of the distinct n-grams is counted:
+// those whose mixed hash has at least `level` leading zero bits, `level` going up by one (and
+// the sample halving) whenever more than NAUTILUS_NGRAM_SAMPLE distinct n-grams are held. The
+// sample is a reservoir of distinct n-grams chosen by hash rather than by arrival, so every
+// occurrence of a sampled n-gram is counted wherever in the dataset it falls, memory stays at
+// the sample size however large the input, and the same bytes always give the same sample.
+// The repeated share of a size is the share of sampled occurrences that aren't the n-gram's
+// first, which estimates that share over all n-grams. Short n-grams repeat in any natural text
+// (common words, markup), long ones only in copied or templated content, so several sizes tell
+// the two apart where a single short window can't.
+
+pub const DEFAULT_NGRAM_SIZES: [usize; 3] = [8, 16, 32];
+// Distinct n-grams held per size.
+pub const DEFAULT_NGRAM_SAMPLE: usize = 16 * 1024;
+pub const MAX_NGRAM: usize = 256;
+const MAX_SIZES: usize = 4;
+const MIN_SAMPLE: usize = 64;
+const MAX_SAMPLE: usize = 1 << 20;
+
+const MODULUS: u64 = (1 << 61) - 1;
+const BASE: u64 = 0x0123_4567_89ab_cdef;
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct NgramConfig {
+    // Ascending, distinct.
+    pub sizes: Vec<usize>,
+    pub sample: usize,
+}
+
+impl Default for NgramConfig {
+    fn default() -> Self {
+        Self { sizes: DEFAULT_NGRAM_SIZES.to_vec(), sample: DEFAULT_NGRAM_SAMPLE }
+    }
+}
+
+impl NgramConfig {
+    pub fn from_env() -> Result<Self> {
+        let mut config = Self::default();
+        if let Some(v) = env::var("NAUTILUS_NGRAM_SIZES").ok().filter(|v| !v.trim().is_empty()) {
+            let mut sizes = v
+                .split(',')
+                .map(|s| s.trim().parse().ok().filter(|n| (2..=MAX_NGRAM).contains(n)))
+                .collect::<Option<Vec<usize>>>()
+                .ok_or_else(|| anyhow!("Invalid NAUTILUS_NGRAM_SIZES '{}' (sizes from 2 to {})", v, MAX_NGRAM))?;
+            sizes.sort_unstable();
+            sizes.dedup();
+            if sizes.len() > MAX_SIZES {
+                bail!("NAUTILUS_NGRAM_SIZES lists more than {} sizes", MAX_SIZES);
+            }
+            config.sizes = sizes;
+        }
+        if let Some(v) = env::var("NAUTILUS_NGRAM_SAMPLE").ok().filter(|v| !v.is_empty()) {
+            config.sample = v
+                .parse()
+                .ok()
+                .filter(|s| (MIN_SAMPLE..=MAX_SAMPLE).contains(s))
+                .ok_or_else(|| anyhow!("Invalid NAUTILUS_NGRAM_SAMPLE '{}' ({} to {})", v, MIN_SAMPLE, MAX_SAMPLE))?;
+        }
+        Ok(config)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct SizeRepeats {
+    pub n: usize,
+    pub ngrams: u64,
+    // Distinct n-grams in the sample and their occurrences.
+    pub sampled: u64,
+    pub sampled_occurrences: u64,
+    // Estimated share of n-grams repeating an earlier one, in millionths.
+    pub repeated_ppm: u64,
+}
+
+struct Rolling {
+    n: usize,
+    hash: u64,
+    // BASE^n, to take the byte leaving the window back out.
+    drop: u64,
+    ngrams: u64,
+    counts: HashMap<u64, u32>,
+    level: u32,
+}
+
+impl Rolling {
+    fn new(n: usize) -> Self {
+        let drop = (0..n).fold(1, |acc, _| mul_mod(acc, BASE));
+        Self { n, hash: 0, drop, ngrams: 0, counts: HashMap::new(), level: 0 }
+    }
+
+    // Whether the n-gram now ending is a sampled repeat. `position` counts bytes from zero.
+    fn push(&mut self, byte: u8, position: usize, ring: &[u8], capacity: usize) -> bool {
+        self.hash = add_mod(mul_mod(self.hash, BASE), byte as u64 + 1);
+        if position >= self.n {
+            let out = ring[(position - self.n) % ring.len()];
+            self.hash = sub_mod(self.hash, mul_mod(out as u64 + 1, self.drop));
+        }
+        if position + 1 < self.n {
+            return false;
+        }
+        self.ngrams += 1;
+        let key = mix(self.hash);
+        if key.leading_zeros() < self.level {
+            return false;
+        }
+        let count = self.counts.entry(key).or_default();
+        *count = count.saturating_add(1);
+        let repeated = *count > 1;
+        if self.counts.len() > capacity {
+            self.level += 1;
+            let level = self.level;
+            self.counts.retain(|key, _| key.leading_zeros() >= level);
+        }
+        repeated
+    }
+
+    fn repeats(&self) -> SizeRepeats {
+        let occurrences: u64 = self.counts.values().map(|&c| c as u64).sum();
+        let repeated = occurrences - self.counts.len() as u64;
+        SizeRepeats {
+            n: self.n,
+            ngrams: self.ngrams,
+            sampled: self.counts.len() as u64,
+            sampled_occurrences: occurrences,
+            repeated_ppm: (repeated * 1_000_000).checked_div(occurrences).unwrap_or(0),
+        }
+    }
+}
+
+pub struct NgramRepeats {
+    sizes: Vec<Rolling>,
+    capacity: usize,
+    // The last MAX(sizes) bytes, by position modulo its length.
+    ring: Vec<u8>,
+    len: u64,
+}
+
+impl NgramRepeats {
+    pub fn new(config: &NgramConfig) -> Self {
+        let longest = config.sizes.iter().copied().max().unwrap_or(1);
+        Self {
+            sizes: config.sizes.iter().map(|&n| Rolling::new(n)).collect(),
+            capacity: config.sample,
+            ring: vec![0; longest],
+            len: 0,
+        }
+    }
+
+    // Feeds one byte; returns the longest size whose n-gram ending here is a sampled repeat.
+    pub fn push(&mut self, byte: u8) -> Option<usize> {
+        let pos = self.len as usize;
+        let mut longest = None;
+        for rolling in &mut self.sizes {
+            if rolling.push(byte, pos, &self.ring, self.capacity) {
+                longest = Some(rolling.n);
+            }
+        }
+        let ring_len = self.ring.len();
+        self.ring[pos % ring_len] = byte;
+        self.len += 1;
+        longest
+    }
+
+    // Bytes fed.
+    pub fn bytes(&self) -> u64 {
+        self.len
+    }
+
+    pub fn shortest(&self) -> usize {
+        self.sizes.first().map_or(0, |r| r.n)
+    }
+
+    pub fn repeats(&self) -> Vec<SizeRepeats> {
+        self.sizes.iter().map(Rolling::repeats).collect()
+    }
+
+    // Mean repeated share over the sizes the input was long enough for.
+    pub fn repetition_ratio(&self) -> f64 {
+        let shares: Vec<f64> =
+            self.repeats().iter().filter(|r| r.ngrams > 0).map(|r| r.repeated_ppm as f64 / 1e6).collect();
+        if shares.is_empty() {
+            0.0
+        } else {
+            shares.iter().sum::<f64>() / shares.len() as f64
+        }
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        // A hash map entry is 12 bytes of key and count, a control byte and padding.
+        (self.ring.capacity() + self.sizes.iter().map(|r| r.counts.capacity() * 17).sum::<usize>()) as u64
+    }
+}
+
+fn mul_mod(a: u64, b: u64) -> u64 {
+    let p = a as u128 * b as u128;
+    let r = (p as u64 & MODULUS) + (p >> 61) as u64;
+    let r = (r & MODULUS) + (r >> 61);
+    if r >= MODULUS {
+        r - MODULUS
+    } else {
+        r
+    }
+}
+
+fn add_mod(a: u64, b: u64) -> u64 {
+    let r = a + b;
+    if r >= MODULUS {
+        r - MODULUS
+    } else {
+        r
+    }
+}
+
+fn sub_mod(a: u64, b: u64) -> u64 {
+    add_mod(a, MODULUS - b)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(data: &[u8], config: &NgramConfig) -> NgramRepeats {
+        let mut ngrams = NgramRepeats::new(config);
+        data.iter().for_each(|&b| {
+            ngrams.push(b);
+        });
+        ngrams
+    }
+
+    #[test]
+    fn test_repeats_sampled_in_bounded_memory() {
+        let mut x = 0x2545_f491_4f6c_dd1du64;
+        let random: Vec<u8> = (0..200_000)
+            .map(|_| {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                x as u8
+            })
+            .collect();
+        let config = NgramConfig { sizes: vec![4, 16], sample: 1024 };
+        let fresh = run(&random, &config);
+        let repeats = fresh.repeats();
+        assert_eq!((repeats[0].ngrams, repeats[1].ngrams), (199_997, 199_985));
+        assert!(repeats.iter().all(|r| r.sampled <= 1024 && r.sampled > 256));
+        // 4-byte windows of random bytes seldom repeat, 16-byte ones never.
+        assert!(repeats[0].repeated_ppm < 30_000 && repeats[1].repeated_ppm == 0, "{:?}", repeats);
+        assert!(fresh.state_bytes() < 64 * 1024);
+
+        // The same bytes twice, far further apart than the sample holds: half of every size repeats.
+        let twice = run(&[&random[..], &random[..]].concat(), &config);
+        for r in twice.repeats() {
+            assert!((470_000..530_000).contains(&r.repeated_ppm), "{:?}", r);
+        }
+
+        // The rolling hash matches hashing each window afresh.
+        let mut rolling = Rolling::new(5);
+        for (i, &b) in random[..100].iter().enumerate() {
+            rolling.push(b, i, &random[..i], 1024);
+            if i >= 4 {
+                let fresh = random[i - 4..=i].iter().fold(0, |h, &b| add_mod(mul_mod(h, BASE), b as u64 + 1));
+                assert_eq!(rolling.hash, fresh);
+            }
+        }
+    }
+}
@@ -0,0 +1,151 @@
+use std::collections::HashSet;
+
+use crate::content_policy::DetectedType;
+
+// Ciphertext and compressed streams look like excellent data to byte statistics (maximal
+// entropy, no repeated windows) while saying nothing about the dataset; usually the seal wasn't
+// decrypted, or the provider uploaded a compressed file no validator opens. Such blobs are
+// refused rather than scored: by the magic of compression formats the validators don't read
+// (gzip is read, see image_validator) and of encryption envelopes, or, for data no sniffer
+// recognises, by near 8 bits/byte of entropy with no correlation between neighbouring bytes and
+// no repeated 4-byte windows early on.
+
+#[derive(Debug, thiserror::Error)]
+#[error("PAYLOAD_OPAQUE: data appears {0}; decryption likely failed")]
+pub struct OpaquePayload(pub &'static str);
+
+const MAGIC: &[(&[u8], &str)] = &[
+    (b"\x28\xb5\x2f\xfd", "zstd-compressed"),
+    (b"\xfd7zXZ\0", "xz-compressed"),
+    (b"BZh", "bzip2-compressed"),
+    (b"7z\xbc\xaf\x27\x1c", "7-Zip-compressed"),
+    (b"Rar!\x1a\x07", "RAR-compressed"),
+    (b"\x04\x22\x4d\x18", "LZ4-compressed"),
+    (b"\xff\x06\0\0sNaPpY", "Snappy-compressed"),
+    (b"Salted__", "OpenSSL-encrypted"),
+    (b"age-encryption.org/", "age-encrypted"),
+    (b"-----BEGIN AGE ENCRYPTED FILE-----", "age-encrypted"),
+    (b"-----BEGIN PGP MESSAGE-----", "PGP-encrypted"),
+];
+const HEAD_LEN: usize = 34;
+
+// Below this the statistics are too noisy to refuse anything on.
+const MIN_BYTES: u64 = 4096;
+// Leading bytes whose 4-byte windows are checked for repeats.
+const PROBE_BYTES: u64 = 64 * 1024;
+const MIN_ENTROPY_BITS: f64 = 7.9;
+const MAX_SERIAL_CORRELATION: f64 = 0.05;
+const MAX_REPEATED_RATIO: f64 = 0.01;
+
+pub struct OpacityCheck {
+    head: Vec<u8>,
+    freq: [u64; 256],
+    n: u64,
+    prev: Option<u8>,
+    // Sum of x[i] * x[i + 1] over neighbouring bytes.
+    sum_xy: u128,
+    window: u32,
+    probe: HashSet<u32>,
+    repeated: u64,
+}
+
+impl Default for OpacityCheck {
+    fn default() -> Self {
+        Self { head: Vec::new(), freq: [0; 256], n: 0, prev: None, sum_xy: 0, window: 0, probe: HashSet::new(), repeated: 0 }
+    }
+}
+
+impl OpacityCheck {
+    pub fn update(&mut self, data: &[u8]) {
+        let take = data.len().min(HEAD_LEN - self.head.len());
+        self.head.extend_from_slice(&data[..take]);
+        for &b in data {
+            self.freq[b as usize] += 1;
+            if let Some(prev) = self.prev {
+                self.sum_xy += prev as u128 * b as u128;
+            }
+            self.prev = Some(b);
+            self.n += 1;
+            if self.n <= PROBE_BYTES {
+                self.window = (self.window << 8) | b as u32;
+                if self.n >= 4 && !self.probe.insert(self.window) {
+                    self.repeated += 1;
+                }
+            } else if self.probe.capacity() > 0 {
+                self.probe = HashSet::new();
+            }
+        }
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        self.probe.capacity() as u64 * 5
+    }
+
+    // What the data appears to be, when it is refused. `format` is what it was sniffed as.
+    pub fn verdict(&self, format: DetectedType) -> Option<&'static str> {
+        if let Some((_, kind)) = MAGIC.iter().find(|(magic, _)| self.head.starts_with(magic)) {
+            return Some(kind);
+        }
+        let opaque = format == DetectedType::Binary
+            && self.n >= MIN_BYTES
+            && self.entropy() >= MIN_ENTROPY_BITS
+            && self.serial_correlation().abs() <= MAX_SERIAL_CORRELATION
+            && self.repeated_ratio() <= MAX_REPEATED_RATIO;
+        opaque.then_some("encrypted or compressed")
+    }
+
+    fn entropy(&self) -> f64 {
+        let n = self.n as f64;
+        self.freq.iter().filter(|&&c| c > 0).map(|&c| c as f64 / n).map(|p| -p * p.log2()).sum()
+    }
+
+    // Pearson correlation of each byte with the next; near 0 for ciphertext.
+    fn serial_correlation(&self) -> f64 {
+        let n = self.n as f64;
+        let sum: f64 = self.freq.iter().enumerate().map(|(b, &c)| b as f64 * c as f64).sum();
+        let sum_sq: f64 = self.freq.iter().enumerate().map(|(b, &c)| (b * b) as f64 * c as f64).sum();
+        let var = n * sum_sq - sum * sum;
+        if var <= 0.0 {
+            return 1.0;
+        }
+        (n * self.sum_xy as f64 - sum * sum) / var
+    }
+
+    fn repeated_ratio(&self) -> f64 {
+        self.repeated as f64 / self.n.min(PROBE_BYTES).saturating_sub(3).max(1) as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::rand::{SecureRandom, SystemRandom};
+
+    fn verdict(data: &[u8], format: DetectedType) -> Option<&'static str> {
+        let mut check = OpacityCheck::default();
+        data.chunks(1000).for_each(|c| check.update(c));
+        check.verdict(format)
+    }
+
+    #[test]
+    fn test_ciphertext_refused_structure_kept() {
+        let mut random = vec![0u8; 200_000];
+        SystemRandom::new().fill(&mut random).unwrap();
+        assert_eq!(verdict(&random, DetectedType::Binary), Some("encrypted or compressed"));
+        // Compressed images are scored as images.
+        assert_eq!(verdict(&random, DetectedType::Image), None);
+        assert_eq!(verdict(&random[..2000], DetectedType::Binary), None);
+
+        // Every byte value equally often, but periodic or correlated.
+        let cyclic: Vec<u8> = (0..200_000u32).map(|i| (i as u8).wrapping_mul(31)).collect();
+        assert_eq!(verdict(&cyclic, DetectedType::Binary), None);
+        let walk: Vec<u8> = random.iter().scan(0u8, |x, &r| { *x = x.wrapping_add(r % 5); Some(*x) }).collect();
+        assert_eq!(verdict(&walk, DetectedType::Binary), None);
+
+        let mut zstd = b"\x28\xb5\x2f\xfd".to_vec();
+        zstd.extend_from_slice(&[b'a'; 64]);
+        assert_eq!(verdict(&zstd, DetectedType::Archive), Some("zstd-compressed"));
+        assert_eq!(verdict(b"-----BEGIN PGP MESSAGE-----\n\nhQEMA", DetectedType::Text), Some("PGP-encrypted"));
+        assert_eq!(verdict(b"id,name\n1,a\n", DetectedType::Csv), None);
+    }
+}
@@ -0,0 +1,335 @@
+use serde::Serialize;
+
+use crate::category::Category;
+use crate::line_records::LineRecords;
+use crate::quality_validator::QualityCheck;
+
+// Personal data in text datasets: email addresses, phone numbers, US social security numbers,
+// payment card numbers and personal names (a dictionary first name followed by a capitalized
+// surname). A dataset meant for sharing or training shouldn't carry any, so the "privacy" check
+// scores the share of records (lines) free of it. Only counts per category are kept and
+// reported: matches are tallied as they're found and never stored, logged or returned. The
+// sealed evidence names the records that matched and with what kinds, not the matches.
+
+// Weight in every category unless NAUTILUS_CHECK_WEIGHTS overrides it.
+const PRIVACY_WEIGHT: u32 = 15;
+
+// Common given names across the languages the text profile detects. Short on purpose: a name is
+// only counted when followed by a capitalized word, and rarer names add little recall.
+const FIRST_NAMES: &[&str] = &[
+    "Aaron", "Adam", "Ahmed", "Alice", "Amanda", "Andrew", "Angela", "Anna", "Anthony", "Antonio", "Ashley", "Barbara",
+    "Betty", "Brian", "Carlos", "Carmen", "Carol", "Charles", "Christopher", "Claire", "Daniel", "David", "Deborah",
+    "Donald", "Donna", "Elizabeth", "Emily", "Emma", "Francesco", "Frank", "Gary", "George", "Giulia", "Hans", "Helen",
+    "Isabel", "Jacob", "James", "Jan", "Jason", "Jean", "Jennifer", "Jessica", "John", "Jorge", "Jose", "Joseph",
+    "Joshua", "Juan", "Karen", "Kenneth", "Kevin", "Klaus", "Laura", "Linda", "Lisa", "Luca", "Lucas", "Luis", "Manuel",
+    "Margaret", "Maria", "Marie", "Mark", "Mary", "Matthew", "Melissa", "Michael", "Michelle", "Mohammed", "Nancy",
+    "Nicole", "Patricia", "Paul", "Pedro", "Peter", "Pierre", "Rebecca", "Richard", "Robert", "Ronald", "Sandra",
+    "Sarah", "Sophie", "Stephanie", "Steven", "Susan", "Thomas", "Timothy", "William",
+];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize)]
+pub struct PiiCounts {
+    pub emails: u64,
+    pub phone_numbers: u64,
+    pub ssns: u64,
+    pub credit_cards: u64,
+    pub names: u64,
+}
+
+impl PiiCounts {
+    fn total(&self) -> u64 {
+        self.emails + self.phone_numbers + self.ssns + self.credit_cards + self.names
+    }
+
+    fn kinds(&self) -> Vec<&'static str> {
+        [
+            ("email", self.emails),
+            ("phone_number", self.phone_numbers),
+            ("ssn", self.ssns),
+            ("credit_card", self.credit_cards),
+            ("name", self.names),
+        ]
+        .into_iter()
+        .filter(|(_, n)| *n > 0)
+        .map(|(kind, _)| kind)
+        .collect()
+    }
+
+    fn add(&mut self, other: &PiiCounts) {
+        self.emails += other.emails;
+        self.phone_numbers += other.phone_numbers;
+        self.ssns += other.ssns;
+        self.credit_cards += other.credit_cards;
+        self.names += other.names;
+    }
+}
+
+#[derive(Default)]
+pub struct PiiCheck {
+    lines: LineRecords<Vec<&'static str>>,
+    counts: PiiCounts,
+}
+
+impl PiiCheck {
+    // Kinds found in a record, tallied into the totals.
+    fn scan_record(counts: &mut PiiCounts, line: &[u8]) -> Option<Vec<&'static str>> {
+        let found = scan(line);
+        counts.add(&found);
+        (found.total() > 0).then(|| found.kinds())
+    }
+}
+
+impl QualityCheck for PiiCheck {
+    fn name(&self) -> &'static str {
+        "privacy"
+    }
+
+    fn weight(&self, _category: Category) -> u32 {
+        PRIVACY_WEIGHT
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.lines.update(data, |line| Self::scan_record(&mut self.counts, line));
+    }
+
+    fn finish(&mut self) {
+        self.lines.finish(|line| Self::scan_record(&mut self.counts, line));
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        self.lines.clean_score()
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.lines.state_bytes()
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        let (records, flagged) = (self.lines.records, self.lines.flagged);
+        let records_with_pii = if records == 0 { 0.0 } else { flagged as f64 / records as f64 };
+        Some((
+            "pii",
+            serde_json::json!({
+                "records_scanned": records,
+                "records_with_pii": flagged,
+                "records_with_pii_ratio": (records_with_pii * 1000.0).round() / 1000.0,
+                "counts": self.counts,
+            }),
+        ))
+    }
+
+    fn evidence(&self) -> Option<serde_json::Value> {
+        Some(self.lines.evidence("kinds"))
+    }
+}
+
+// Matches of each kind in one line.
+fn scan(line: &[u8]) -> PiiCounts {
+    let mut counts = PiiCounts { emails: count_emails(line), names: count_names(line), ..Default::default() };
+    count_numbers(line, &mut counts);
+    counts
+}
+
+fn is_local_part(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || matches!(b, b'.' | b'_' | b'%' | b'+' | b'-')
+}
+
+// local@domain.tld with an alphabetic top-level domain.
+fn count_emails(line: &[u8]) -> u64 {
+    let mut count = 0;
+    let mut i = 0;
+    while let Some(at) = line[i..].iter().position(|&b| b == b'@').map(|p| i + p) {
+        let local = line[..at].iter().rev().take_while(|&&b| is_local_part(b)).count();
+        let domain = line[at + 1..].iter().take_while(|&&b| b.is_ascii_alphanumeric() || matches!(b, b'.' | b'-')).count();
+        let domain_str = &line[at + 1..at + 1 + domain];
+        let domain_str = domain_str.strip_suffix(b".").unwrap_or(domain_str);
+        let labels: Vec<&[u8]> = domain_str.split(|&b| b == b'.').collect();
+        let tld = labels.last().copied().unwrap_or_default();
+        if local > 0
+            && line[at - local] != b'.'
+            && labels.len() >= 2
+            && labels.iter().all(|l| !l.is_empty())
+            && tld.len() >= 2
+            && tld.iter().all(u8::is_ascii_alphabetic)
+        {
+            count += 1;
+        }
+        i = at + 1 + domain;
+    }
+    count
+}
+
+// Runs of digits with the separators phone and card numbers are written with, classified by
+// their grouping. A run that doesn't match is retried from its second group, so two numbers
+// separated only by a space are both found.
+fn count_numbers(line: &[u8], counts: &mut PiiCounts) {
+    let mut i = 0;
+    while i < line.len() {
+        let b = line[i];
+        let starts = b.is_ascii_digit() || ((b == b'+' || b == b'(') && line.get(i + 1).is_some_and(u8::is_ascii_digit));
+        if !starts || (i > 0 && line[i - 1].is_ascii_alphanumeric()) {
+            i += 1;
+            continue;
+        }
+        let mut end = i + line[i..].iter().take_while(|&&b| b.is_ascii_digit() || b" -.()+".contains(&b)).count();
+        while !line[end - 1].is_ascii_digit() {
+            end -= 1;
+        }
+        let glued = line.get(end).is_some_and(|b| b.is_ascii_alphanumeric());
+        let run = &line[i..end];
+        let kind = if glued { None } else { classify(run) };
+        match kind {
+            Some(Kind::Ssn) => counts.ssns += 1,
+            Some(Kind::Card) => counts.credit_cards += 1,
+            Some(Kind::Phone) => counts.phone_numbers += 1,
+            None => {}
+        }
+        if kind.is_some() {
+            i = end;
+        } else {
+            // Skip the first digit group and whatever follows it up to the next digit.
+            let first = run.iter().position(u8::is_ascii_digit).unwrap_or(0);
+            let group = run[first..].iter().take_while(|b| b.is_ascii_digit()).count();
+            i += first + group;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Kind {
+    Ssn,
+    Card,
+    Phone,
+}
+
+fn classify(run: &[u8]) -> Option<Kind> {
+    let groups: Vec<&[u8]> = run.split(|b| !b.is_ascii_digit()).filter(|g| !g.is_empty()).collect();
+    let lens: Vec<usize> = groups.iter().map(|g| g.len()).collect();
+    let digits: usize = lens.iter().sum();
+    // Separator between each pair of groups, e.g. "-" or ") ".
+    let mut seps = Vec::new();
+    let mut rest = run;
+    for group in &groups {
+        let at = rest.windows(group.len()).position(|w| w == *group).unwrap_or(0);
+        seps.push(&rest[..at]);
+        rest = &rest[at + group.len()..];
+    }
+    let lead = seps.remove(0);
+    let same_sep = |sep: &[u8]| seps.iter().all(|s| *s == sep);
+
+    if lead.is_empty() && lens == [3, 2, 4] && same_sep(b"-") {
+        let (area, group, serial) = (number(groups[0]), number(groups[1]), number(groups[2]));
+        let valid = area != 0 && area != 666 && area < 900 && group != 0 && serial != 0;
+        return valid.then_some(Kind::Ssn);
+    }
+    let card_groups = lens.len() == 1 || lens[..lens.len() - 1].iter().all(|&l| l == 4) || lens == [4, 6, 5];
+    if lead.is_empty() && (13..=19).contains(&digits) && card_groups && (same_sep(b" ") || same_sep(b"-")) {
+        let all: Vec<u8> = groups.concat();
+        return (card_issuer(&all) && luhn(&all)).then_some(Kind::Card);
+    }
+    let phone = match lead {
+        // International: +CC followed by groups.
+        b"+" => (8..=15).contains(&digits) && lens.len() >= 2 && seps.iter().all(|s| matches!(*s, b" " | b"-" | b".")),
+        // (555) 123-4567
+        b"(" => lens == [3, 3, 4] && matches!(seps[0], b") " | b")") && matches!(seps[1], b"-" | b" "),
+        // 555-123-4567, 555.123.4567
+        b"" => lens == [3, 3, 4] && (same_sep(b"-") || same_sep(b".")),
+        _ => false,
+    };
+    phone.then_some(Kind::Phone)
+}
+
+fn number(digits: &[u8]) -> u32 {
+    digits.iter().fold(0, |n, d| n * 10 + (d - b'0') as u32)
+}
+
+// Visa, Mastercard, American Express and Discover prefixes.
+fn card_issuer(digits: &[u8]) -> bool {
+    let prefix = |n: usize| number(&digits[..n]);
+    digits[0] == b'4'
+        || (51..=55).contains(&prefix(2))
+        || (2221..=2720).contains(&prefix(4))
+        || (digits.len() == 15 && matches!(prefix(2), 34 | 37))
+        || prefix(4) == 6011
+        || prefix(2) == 65
+}
+
+fn luhn(digits: &[u8]) -> bool {
+    let sum: u32 = digits
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(i, d)| {
+            let d = (d - b'0') as u32;
+            if i % 2 == 1 {
+                let d = d * 2;
+                if d > 9 {
+                    d - 9
+                } else {
+                    d
+                }
+            } else {
+                d
+            }
+        })
+        .sum();
+    sum.is_multiple_of(10)
+}
+
+// A dictionary first name followed by a single space and a capitalized word.
+fn count_names(line: &[u8]) -> u64 {
+    let mut count = 0;
+    let words: Vec<(usize, &[u8])> = {
+        let mut words = Vec::new();
+        let mut i = 0;
+        while i < line.len() {
+            let len = line[i..].iter().take_while(|b| b.is_ascii_alphabetic()).count();
+            if len > 0 {
+                words.push((i, &line[i..i + len]));
+                i += len;
+            } else {
+                i += 1;
+            }
+        }
+        words
+    };
+    let mut w = 0;
+    while w + 1 < words.len() {
+        let ((at, first), (next_at, last)) = (words[w], words[w + 1]);
+        let adjacent = next_at == at + first.len() + 1 && line[at + first.len()] == b' ';
+        let surname = last.len() >= 2 && last[0].is_ascii_uppercase() && last[1..].iter().all(u8::is_ascii_lowercase);
+        let word_start = at == 0 || !line[at - 1].is_ascii_alphanumeric();
+        if word_start && adjacent && surname && FIRST_NAMES.iter().any(|n| n.as_bytes() == first) {
+            count += 1;
+            w += 2;
+        } else {
+            w += 1;
+        }
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_counts_without_matches() {
+        let mut check = PiiCheck::default();
+        check.update(b"id,contact,note\n1,jane.doe@example.com,call 555-867-5309\n");
+        check.update(b"2,none,card 4111 1111 1111 1111 on file\n3,SSN 123-45-6789 for Mary Smith,");
+        check.update(b"+44 20 7946 0958\n4,shipped 2024-01-15 10:30 order 1234567890123,ok\n");
+        check.finish();
+        let expected = PiiCounts { emails: 1, phone_numbers: 2, ssns: 1, credit_cards: 1, names: 1 };
+        assert_eq!(check.counts, expected);
+        assert_eq!((check.lines.records, check.lines.flagged), (5, 3));
+        assert_eq!(check.score(0), 40);
+
+        // Only aggregate counts are reported, never the matched text.
+        let (key, details) = check.details().unwrap();
+        assert_eq!(key, "pii");
+        let details = details.to_string();
+        assert!(details.contains("\"credit_cards\":1"));
+        assert!(["jane", "867", "4111", "6789", "Smith", "7946"].iter().all(|raw| !details.contains(raw)));
+    }
+}
@@ -0,0 +1,295 @@
+use anyhow::{anyhow, bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fmt;
+use std::sync::Arc;
+use wasmtime::{Engine, Instance, Module, Store, StoreLimits, StoreLimitsBuilder};
+
+use crate::category::Category;
+use crate::quality_validator::{QualityCheck, REGISTRY};
+
+// Operator plugins: domain-specific checks compiled to WebAssembly and run in a wasmtime sandbox.
+// NAUTILUS_PLUGINS="toxicity=/plugins/toxicity.wasm,units=/plugins/units.wasm" registers each
+// module under a check name; it then runs on every dataset next to the registered checks, with
+// weight PLUGIN_WEIGHT unless NAUTILUS_CHECK_WEIGHTS says otherwise, and can be disabled or
+// given a minimum like them. A module exports
+//
+//   memory                           its linear memory
+//   alloc(len: i32) -> i32           room for `len` input bytes, returning the offset
+//   score(ptr: i32, len: i32) -> i32 the dataset's score, 0..=100 (larger values count as 100)
+//
+// and imports nothing: a plugin sees the examined bytes (the first NAUTILUS_PLUGIN_MAX_INPUT_BYTES
+// of them) and no host function, clock, file or socket. Each run gets a fresh instance, at most
+// NAUTILUS_PLUGIN_MEMORY_BYTES of memory and NAUTILUS_PLUGIN_FUEL units of fuel (roughly wasm
+// instructions); a plugin that traps, runs out or returns a negative score scores 0. The SHA-256
+// of each module that ran is attested with the scoring summary, and all of them are part of the
+// config hash, so a score can be traced back to the exact plugin code.
+
+const PLUGIN_WEIGHT: u32 = 10;
+const DEFAULT_FUEL: u64 = 10_000_000_000;
+const DEFAULT_MEMORY_BYTES: usize = 512 * 1024 * 1024;
+const DEFAULT_MAX_INPUT_BYTES: usize = 64 * 1024 * 1024;
+const MAX_NAME_BYTES: usize = 32;
+// Detail keys the registered checks report under, on top of their names.
+const RESERVED: &[&str] = &["columns", "composite", "csv", "images", "json", "labels", "text", "screening"];
+
+#[derive(Debug, Clone, Copy)]
+pub struct PluginLimits {
+    pub fuel: u64,
+    pub memory_bytes: usize,
+    pub max_input_bytes: usize,
+}
+
+impl Default for PluginLimits {
+    fn default() -> Self {
+        Self { fuel: DEFAULT_FUEL, memory_bytes: DEFAULT_MEMORY_BYTES, max_input_bytes: DEFAULT_MAX_INPUT_BYTES }
+    }
+}
+
+impl PluginLimits {
+    fn from_env() -> Result<Self> {
+        fn var<T: std::str::FromStr>(name: &str, default: T) -> Result<T> {
+            match env::var(name).ok().filter(|v| !v.is_empty()) {
+                Some(v) => v.parse().map_err(|_| anyhow!("Invalid {} '{}'", name, v)),
+                None => Ok(default),
+            }
+        }
+        Ok(Self {
+            fuel: var("NAUTILUS_PLUGIN_FUEL", DEFAULT_FUEL)?,
+            memory_bytes: var("NAUTILUS_PLUGIN_MEMORY_BYTES", DEFAULT_MEMORY_BYTES)?,
+            max_input_bytes: var("NAUTILUS_PLUGIN_MAX_INPUT_BYTES", DEFAULT_MAX_INPUT_BYTES)?,
+        })
+    }
+}
+
+// A compiled plugin module.
+pub struct Plugin {
+    // Leaked once at startup: check names are static.
+    pub name: &'static str,
+    // Hex SHA-256 of the module file.
+    pub sha256: String,
+    pub limits: PluginLimits,
+    engine: Engine,
+    module: Module,
+}
+
+impl fmt::Debug for Plugin {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Plugin").field("name", &self.name).field("sha256", &self.sha256).finish()
+    }
+}
+
+// The plugins NAUTILUS_PLUGINS registers, compiled and checked against the interface.
+pub fn from_env() -> Result<Vec<Arc<Plugin>>> {
+    let spec = env::var("NAUTILUS_PLUGINS").unwrap_or_default();
+    let entries: Vec<&str> = spec.split(',').map(str::trim).filter(|e| !e.is_empty()).collect();
+    if entries.is_empty() {
+        return Ok(Vec::new());
+    }
+    let limits = PluginLimits::from_env()?;
+    let engine = engine()?;
+    let mut plugins: Vec<Arc<Plugin>> = Vec::new();
+    for entry in entries {
+        let Some((name, path)) = entry.split_once('=') else {
+            bail!("Invalid NAUTILUS_PLUGINS entry '{}' (expected name=path)", entry);
+        };
+        let name = name.trim();
+        if plugins.iter().any(|p| p.name == name) {
+            bail!("Plugin '{}' is registered twice", name);
+        }
+        let wasm = std::fs::read(path.trim()).with_context(|| format!("Failed to read plugin '{}' from {}", name, path))?;
+        let plugin = Plugin::load(&engine, name, &wasm, limits)?;
+        tracing::info!(plugin = plugin.name, sha256 = %plugin.sha256, "Loaded quality check plugin");
+        plugins.push(Arc::new(plugin));
+    }
+    Ok(plugins)
+}
+
+fn engine() -> Result<Engine> {
+    let mut config = wasmtime::Config::new();
+    config.consume_fuel(true);
+    Engine::new(&config)
+}
+
+impl Plugin {
+    pub fn load(engine: &Engine, name: &str, wasm: &[u8], limits: PluginLimits) -> Result<Self> {
+        let valid = name.bytes().all(|b| b.is_ascii_lowercase() || b.is_ascii_digit() || b == b'_');
+        if name.is_empty() || name.len() > MAX_NAME_BYTES || !valid {
+            bail!("Invalid plugin name '{}' (1 to {} of a-z, 0-9 and _)", name, MAX_NAME_BYTES);
+        }
+        if REGISTRY.iter().any(|e| e.name == name) || RESERVED.contains(&name) {
+            bail!("Plugin name '{}' is taken by a built-in check", name);
+        }
+        let module = Module::from_binary(engine, wasm).with_context(|| format!("Plugin '{}' isn't a valid module", name))?;
+        if let Some(import) = module.imports().next() {
+            bail!("Plugin '{}' imports {}::{}; plugins get no host functions", name, import.module(), import.name());
+        }
+        let plugin = Self {
+            name: Box::leak(name.to_string().into_boxed_str()),
+            sha256: hex::encode(Sha256::digest(wasm)),
+            limits,
+            engine: engine.clone(),
+            module,
+        };
+        // Instantiating checks the exports (and runs any start function) under the limits.
+        let (mut store, instance) = plugin.instantiate()?;
+        plugin.exports(&mut store, &instance).with_context(|| format!("Plugin '{}'", name))?;
+        Ok(plugin)
+    }
+
+    fn instantiate(&self) -> Result<(Store<StoreLimits>, Instance)> {
+        let limits = StoreLimitsBuilder::new().memory_size(self.limits.memory_bytes).instances(1).memories(1).build();
+        let mut store = Store::new(&self.engine, limits);
+        store.limiter(|limits| limits);
+        store.set_fuel(self.limits.fuel)?;
+        let instance = Instance::new(&mut store, &self.module, &[])?;
+        Ok((store, instance))
+    }
+
+    #[allow(clippy::type_complexity)]
+    fn exports(
+        &self,
+        store: &mut Store<StoreLimits>,
+        instance: &Instance,
+    ) -> Result<(wasmtime::Memory, wasmtime::TypedFunc<i32, i32>, wasmtime::TypedFunc<(i32, i32), i32>)> {
+        let memory = instance.get_memory(&mut *store, "memory").context("no exported memory")?;
+        let alloc = instance.get_typed_func::<i32, i32>(&mut *store, "alloc").context("no alloc(i32) -> i32 export")?;
+        let score =
+            instance.get_typed_func::<(i32, i32), i32>(&mut *store, "score").context("no score(i32, i32) -> i32 export")?;
+        Ok((memory, alloc, score))
+    }
+
+    // The module's score for `data`, and the fuel it took.
+    pub fn run(&self, data: &[u8]) -> Result<(u32, u64)> {
+        let (mut store, instance) = self.instantiate()?;
+        let (memory, alloc, score) = self.exports(&mut store, &instance)?;
+        let len = i32::try_from(data.len()).context("input too large")?;
+        let ptr = alloc.call(&mut store, len)?;
+        memory.write(&mut store, ptr as u32 as usize, data).context("alloc returned room outside memory")?;
+        let score = score.call(&mut store, (ptr, len))?;
+        let score = u32::try_from(score).map_err(|_| anyhow!("negative score {}", score))?;
+        let fuel = self.limits.fuel - store.get_fuel()?;
+        Ok((score.min(100), fuel))
+    }
+}
+
+pub struct PluginCheck {
+    plugin: Arc<Plugin>,
+    input: Vec<u8>,
+    examined: u64,
+    outcome: Option<Result<(u32, u64), String>>,
+}
+
+impl PluginCheck {
+    pub fn new(plugin: Arc<Plugin>) -> Self {
+        Self { plugin, input: Vec::new(), examined: 0, outcome: None }
+    }
+}
+
+impl QualityCheck for PluginCheck {
+    fn name(&self) -> &'static str {
+        self.plugin.name
+    }
+
+    fn weight(&self, _category: Category) -> u32 {
+        PLUGIN_WEIGHT
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.examined += data.len() as u64;
+        let room = self.plugin.limits.max_input_bytes.saturating_sub(self.input.len());
+        self.input.extend_from_slice(&data[..data.len().min(room)]);
+    }
+
+    fn finish(&mut self) {
+        let input = std::mem::take(&mut self.input);
+        let outcome = self.plugin.run(&input).map_err(|err| format!("{:#}", err));
+        if let Err(err) = &outcome {
+            tracing::warn!(plugin = self.plugin.name, %err, "Quality check plugin failed");
+        }
+        self.outcome = Some(outcome);
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        match &self.outcome {
+            Some(Ok((score, _))) => *score,
+            _ => 0,
+        }
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.input.capacity() as u64
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        let input = self.examined.min(self.plugin.limits.max_input_bytes as u64);
+        let mut details = serde_json::json!({
+            "module_sha256": self.plugin.sha256,
+            "input_bytes": input,
+            "truncated": input < self.examined,
+        });
+        match &self.outcome {
+            Some(Ok((_, fuel))) => details["fuel_used"] = (*fuel).into(),
+            Some(Err(err)) => details["error"] = err.as_str().into(),
+            None => {}
+        }
+        Some((self.plugin.name, details))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality_validator::{ChecksConfig, QualityAccumulator, ValidationOptions};
+
+    // Scores the share of input bytes that are ASCII letters.
+    const LETTERS: &str = r#"(module
+        (memory (export "memory") 2)
+        (func (export "alloc") (param i32) (result i32) (i32.const 1024))
+        (func (export "score") (param $ptr i32) (param $len i32) (result i32)
+            (local $i i32) (local $n i32) (local $b i32)
+            (block $done (loop $next
+                (br_if $done (i32.ge_u (local.get $i) (local.get $len)))
+                (local.set $b (i32.or (i32.load8_u (i32.add (local.get $ptr) (local.get $i))) (i32.const 32)))
+                (if (i32.and (i32.ge_u (local.get $b) (i32.const 97)) (i32.le_u (local.get $b) (i32.const 122)))
+                    (then (local.set $n (i32.add (local.get $n) (i32.const 1)))))
+                (local.set $i (i32.add (local.get $i) (i32.const 1)))
+                (br $next)))
+            (if (result i32) (local.get $len)
+                (then (i32.div_u (i32.mul (local.get $n) (i32.const 100)) (local.get $len)))
+                (else (i32.const 0)))))"#;
+
+    fn load(name: &str, wat: &str, limits: PluginLimits) -> Result<Plugin> {
+        Plugin::load(&engine().unwrap(), name, &wat::parse_str(wat).unwrap(), limits)
+    }
+
+    #[test]
+    fn test_plugin_scores_in_sandbox() {
+        let limits = PluginLimits { memory_bytes: 4 * 65536, ..Default::default() };
+        let plugin = Arc::new(load("letters", LETTERS, limits).unwrap());
+        assert_eq!(plugin.run(b"abc,12\n").unwrap().0, 42);
+
+        let config = ChecksConfig { plugins: vec![plugin.clone()], ..Default::default() };
+        let mut acc = QualityAccumulator::new(&ValidationOptions::default(), &config);
+        acc.update("word,7\n".repeat(200).as_bytes());
+        let report = acc.finish().unwrap();
+        assert_eq!(report.breakdown.extra["letters"], 57);
+        assert_eq!(report.scoring.weights["letters"], PLUGIN_WEIGHT);
+        assert_eq!(report.scoring.plugins["letters"], plugin.sha256);
+        assert_eq!(report.details["letters"]["truncated"], false);
+
+        // Input that doesn't fit the plugin's memory fails, as does running out of fuel.
+        assert!(plugin.run(&vec![b'a'; 5 * 65536]).is_err());
+        let spin = r#"(module (memory (export "memory") 1)
+            (func (export "alloc") (param i32) (result i32) (i32.const 0))
+            (func (export "score") (param i32 i32) (result i32) (loop $l (br $l)) (i32.const 100)))"#;
+        let spin = load("spin", spin, PluginLimits { fuel: 100_000, ..limits }).unwrap();
+        assert!(format!("{:#}", spin.run(b"x").unwrap_err()).contains("fuel"));
+
+        // No host functions, no clashing names, the interface is checked up front.
+        let imports = r#"(module (import "env" "now" (func)) (memory (export "memory") 1))"#;
+        assert!(load("clock", imports, limits).unwrap_err().to_string().contains("imports env::now"));
+        assert!(load("bias", LETTERS, limits).is_err());
+        assert!(load("nofn", r#"(module (memory (export "memory") 1))"#, limits).is_err());
+    }
+}
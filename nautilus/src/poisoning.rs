@@ -0,0 +1,329 @@
+use serde::Serialize;
+use std::collections::HashMap;
+
+use crate::category::Category;
+use crate::labels::{self, LabelReader};
+use crate::quality_validator::{QualityCheck, MAX_EVIDENCE_ITEMS};
+
+// Signatures of training-data poisoning in record datasets, scored as the "poisoning" check:
+// 100 minus a 0..=100 risk that is attested on its own (`poisoning_risk`) for buyers to gate on.
+// Records are lines. Three signals, each turned into a risk by the share of records showing it,
+// and the highest one counts:
+//   - outlier clusters: records are profiled by length and character mix; a bucket of similar
+//     profiles far (OUTLIER_Z deviations) from the rest and holding several records is a cluster
+//     of injected samples rather than the odd stray line;
+//   - trigger patterns: unusual tokens (invisible characters, special-token markers, symbol-laden
+//     mixes) repeated across a minority of records, as backdoor triggers are;
+//   - label flips, for a request naming the label column (see labels): records whose features
+//     match others' exactly but whose label doesn't.
+// Only counts are reported; the sealed evidence lists trigger tokens by hash, never their text.
+
+// Weight in every category unless NAUTILUS_CHECK_WEIGHTS overrides it.
+const POISONING_WEIGHT: u32 = 10;
+// Bytes of a record profiled and tokenized; its length counts in full.
+const MAX_LINE_BYTES: usize = 64 * 1024;
+const OUTLIER_Z: f64 = 3.5;
+const MIN_CLUSTER_RECORDS: u64 = 5;
+// A trigger recurs in at least this many records and this share of them, but no more than
+// MAX_TRIGGER_SHARE: a token in most records is the format's, not a trigger.
+const MIN_TRIGGER_RECORDS: u64 = 5;
+const MIN_TRIGGER_SHARE: f64 = 0.002;
+const MAX_TRIGGER_SHARE: f64 = 0.5;
+const MAX_TOKEN_BYTES: usize = 32;
+// Distinct profile buckets and unusual tokens tracked.
+const MAX_BUCKETS: usize = 1 << 16;
+const MAX_TOKENS: usize = 1 << 16;
+// Share of records at which each signal alone means full risk.
+const FULL_RISK_OUTLIERS: f64 = 0.05;
+const FULL_RISK_TRIGGERS: f64 = 0.02;
+const FULL_RISK_FLIPS: f64 = 0.05;
+
+// Record profile: log2 length, and the shares of punctuation, digit and non-ASCII bytes.
+const FEATURES: usize = 4;
+
+fn profile(line: &[u8], len: u64) -> [f64; FEATURES] {
+    let n = line.len().max(1) as f64;
+    let share = |f: fn(&u8) -> bool| line.iter().filter(|b| f(b)).count() as f64 / n;
+    [((len + 1) as f64).log2(), share(u8::is_ascii_punctuation), share(u8::is_ascii_digit), share(|b| !b.is_ascii())]
+}
+
+// Buckets are half a bit of length and a tenth of each share wide. A bucket is only far from
+// the rest when its records are at least a bucket width away, however tight the distribution.
+const STEPS: [f64; FEATURES] = [2.0, 10.0, 10.0, 10.0];
+
+fn bucket(features: &[f64; FEATURES]) -> [u8; FEATURES] {
+    let mut out = [0; FEATURES];
+    for i in 0..FEATURES {
+        out[i] = (features[i] * STEPS[i]).floor().min(if i == 0 { 255.0 } else { 9.0 }) as u8;
+    }
+    out
+}
+
+// Records in a bucket and the sum of their profiles.
+#[derive(Default)]
+struct Bucket {
+    count: u64,
+    sums: [f64; FEATURES],
+}
+
+// Zero-width and bidirectional-control characters, which render as nothing.
+fn has_invisible(token: &str) -> bool {
+    token.chars().any(|c| matches!(c, '\u{200b}'..='\u{200f}' | '\u{202a}'..='\u{202e}' | '\u{2060}'..='\u{2064}' | '\u{2066}'..='\u{2069}' | '\u{feff}'))
+}
+
+fn is_unusual(token: &str) -> bool {
+    if has_invisible(token) || token.contains("<|") || token.contains("|>") {
+        return true;
+    }
+    let core = token.trim_matches(|c: char| "\"',:;{}[]()".contains(c));
+    if core.len() < 3 || core.len() > MAX_TOKEN_BYTES || core.contains("://") {
+        return false;
+    }
+    let letters = core.chars().any(char::is_alphabetic);
+    let symbols = core.chars().any(|c| "!#$%^&*~|<>\\`".contains(c));
+    letters && symbols
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct PoisoningReport {
+    pub records: u64,
+    pub outlier_clusters: u64,
+    pub outlier_records: u64,
+    pub trigger_tokens: u64,
+    pub trigger_records: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub labeled_records: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub flipped_labels: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub conflicting_groups: Option<u64>,
+    // 0..=100; the check scores 100 minus this.
+    pub risk: u32,
+}
+
+pub struct PoisoningCheck {
+    line: Vec<u8>,
+    line_len: u64,
+    records: u64,
+    // Welford mean and sum of squared deviations per profile feature.
+    mean: [f64; FEATURES],
+    m2: [f64; FEATURES],
+    buckets: HashMap<[u8; FEATURES], Bucket>,
+    // Records containing each unusual token, by token hash.
+    tokens: HashMap<u64, u64>,
+    labels: Option<LabelReader>,
+    report: PoisoningReport,
+    triggers: Vec<(u64, u64)>,
+}
+
+impl PoisoningCheck {
+    pub fn new(labels: Option<LabelReader>) -> Self {
+        Self {
+            line: Vec::new(),
+            line_len: 0,
+            records: 0,
+            mean: [0.0; FEATURES],
+            m2: [0.0; FEATURES],
+            buckets: HashMap::new(),
+            tokens: HashMap::new(),
+            labels,
+            report: PoisoningReport::default(),
+            triggers: Vec::new(),
+        }
+    }
+
+    fn end_line(&mut self) {
+        let line = std::mem::take(&mut self.line);
+        if !line.iter().all(u8::is_ascii_whitespace) {
+            self.records += 1;
+            let features = profile(&line, self.line_len);
+            for (i, &x) in features.iter().enumerate() {
+                let delta = x - self.mean[i];
+                self.mean[i] += delta / self.records as f64;
+                self.m2[i] += delta * (x - self.mean[i]);
+            }
+            let key = bucket(&features);
+            let tracked = self.buckets.len();
+            if tracked < MAX_BUCKETS || self.buckets.contains_key(&key) {
+                let bucket = self.buckets.entry(key).or_default();
+                bucket.count += 1;
+                for (sum, x) in bucket.sums.iter_mut().zip(features) {
+                    *sum += x;
+                }
+            }
+            let text = String::from_utf8_lossy(&line);
+            let mut seen: Vec<u64> = text.split_whitespace().filter(|t| is_unusual(t)).map(|t| labels::hash(t.as_bytes())).collect();
+            seen.sort_unstable();
+            seen.dedup();
+            for token in seen {
+                let tracked = self.tokens.len();
+                if let Some(count) = self.tokens.get_mut(&token) {
+                    *count += 1;
+                } else if tracked < MAX_TOKENS {
+                    self.tokens.insert(token, 1);
+                }
+            }
+        }
+        self.line = line;
+        self.line.clear();
+        self.line_len = 0;
+    }
+
+    fn summarize(&mut self) {
+        let records = self.records;
+        let mut report = PoisoningReport { records, ..Default::default() };
+        if records > 0 {
+            let std: Vec<f64> = self.m2.iter().map(|m2| (m2 / records as f64).sqrt()).collect();
+            for bucket in self.buckets.values().filter(|b| b.count >= MIN_CLUSTER_RECORDS) {
+                let far = (0..FEATURES).any(|i| {
+                    let distance = (bucket.sums[i] / bucket.count as f64 - self.mean[i]).abs();
+                    distance >= 1.0 / STEPS[i] && distance > OUTLIER_Z * std[i]
+                });
+                if far {
+                    report.outlier_clusters += 1;
+                    report.outlier_records += bucket.count;
+                }
+            }
+            let min = MIN_TRIGGER_RECORDS.max((records as f64 * MIN_TRIGGER_SHARE).ceil() as u64);
+            let max = (records as f64 * MAX_TRIGGER_SHARE) as u64;
+            self.triggers = self.tokens.iter().filter(|(_, &n)| n >= min && n <= max).map(|(&t, &n)| (t, n)).collect();
+            self.triggers.sort_by_key(|&(t, n)| (std::cmp::Reverse(n), t));
+            report.trigger_tokens = self.triggers.len() as u64;
+            report.trigger_records = self.triggers.iter().map(|(_, n)| n).sum::<u64>().min(records);
+        }
+        let mut risk = [
+            share(report.outlier_records, records) / FULL_RISK_OUTLIERS,
+            share(report.trigger_records, records) / FULL_RISK_TRIGGERS,
+        ]
+        .into_iter()
+        .fold(0.0, f64::max);
+        if let Some(counts) = self.labels.as_ref().and_then(LabelReader::counts) {
+            let (labeled, flipped) = (counts.labeled(), counts.flipped());
+            report.labeled_records = Some(labeled);
+            report.flipped_labels = Some(flipped);
+            report.conflicting_groups = Some(counts.conflicting_groups());
+            risk = risk.max(share(flipped, labeled) / FULL_RISK_FLIPS);
+        }
+        report.risk = (risk.min(1.0) * 100.0).round() as u32;
+        self.report = report;
+    }
+}
+
+fn share(n: u64, of: u64) -> f64 {
+    if of == 0 {
+        0.0
+    } else {
+        n as f64 / of as f64
+    }
+}
+
+impl QualityCheck for PoisoningCheck {
+    fn name(&self) -> &'static str {
+        "poisoning"
+    }
+
+    fn weight(&self, _category: Category) -> u32 {
+        POISONING_WEIGHT
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        if let Some(labels) = self.labels.as_mut() {
+            labels.update(data);
+        }
+        for piece in data.split_inclusive(|&b| b == b'\n') {
+            let take = piece.len().min(MAX_LINE_BYTES.saturating_sub(self.line.len()));
+            self.line.extend_from_slice(&piece[..take]);
+            self.line_len += piece.len() as u64;
+            if piece.ends_with(b"\n") {
+                self.end_line();
+            }
+        }
+    }
+
+    fn finish(&mut self) {
+        self.end_line();
+        if let Some(labels) = self.labels.as_mut() {
+            labels.finish();
+        }
+        self.summarize();
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        100 - self.report.risk
+    }
+
+    fn state_bytes(&self) -> u64 {
+        (self.line.capacity() + self.buckets.capacity() * 48 + self.tokens.capacity() * 16) as u64
+            + self.labels.as_ref().map_or(0, LabelReader::state_bytes)
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        Some(("poisoning", serde_json::to_value(&self.report).ok()?))
+    }
+
+    fn measures(&self, _total_len: u64) -> Vec<(&'static str, f64)> {
+        let records = self.report.records;
+        let mut out = vec![
+            ("outlier_share", share(self.report.outlier_records, records)),
+            ("trigger_share", share(self.report.trigger_records, records)),
+        ];
+        if let (Some(labeled), Some(flipped)) = (self.report.labeled_records, self.report.flipped_labels) {
+            out.push(("flip_share", share(flipped, labeled)));
+        }
+        out
+    }
+
+    // Trigger tokens by hash, with the number of records carrying each.
+    fn evidence(&self) -> Option<serde_json::Value> {
+        let listed: Vec<_> = self
+            .triggers
+            .iter()
+            .take(MAX_EVIDENCE_ITEMS)
+            .map(|(token, records)| serde_json::json!({ "token_hash": format!("{:016x}", token), "records": records }))
+            .collect();
+        let unlisted = self.triggers.len() - listed.len();
+        Some(serde_json::json!({ "triggers": listed, "unlisted": unlisted }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(check: &mut PoisoningCheck, data: &str) -> PoisoningReport {
+        data.as_bytes().chunks(37).for_each(|c| check.update(c));
+        check.finish();
+        std::mem::take(&mut check.report)
+    }
+
+    #[test]
+    fn test_poisoning_signatures() {
+        let clean: Vec<String> = (0..1000)
+            .map(|i| format!("{{\"text\": \"review {} says the product was {}\", \"label\": \"{}\"}}", i, ["good", "fine", "bad"][i % 3], ["pos", "neu", "neg"][i % 3]))
+            .collect();
+        let mut check = PoisoningCheck::new(Some(LabelReader::jsonl("label")));
+        let report = run(&mut check, &(clean.join("\n") + "\n"));
+        assert_eq!((report.records, report.risk), (1000, 0));
+        assert_eq!((report.flipped_labels, report.trigger_tokens, report.outlier_clusters), (Some(0), 0, 0));
+
+        // A backdoor: a trigger token appended to 3% of records, whose labels are forced.
+        let mut poisoned = clean.clone();
+        for line in poisoned.iter_mut().step_by(33) {
+            *line = line.replace("\", \"label\"", " cf\u{200b}zz\", \"label\"");
+        }
+        let mut check = PoisoningCheck::new(None);
+        let report = run(&mut check, &(poisoned.join("\n") + "\n"));
+        assert_eq!((report.trigger_tokens, report.trigger_records, report.risk), (1, 31, 100));
+        assert!(check.evidence().unwrap()["triggers"][0]["token_hash"].is_string());
+
+        // Exact copies with the other label; and a cluster of injected records unlike the rest.
+        let mut flipped = clean.clone();
+        flipped.extend(clean[..20].iter().map(|l| l.replace("\"pos\"", "\"neg\"")));
+        flipped.extend((0..10).map(|_| "#".repeat(900)));
+        let mut check = PoisoningCheck::new(Some(LabelReader::jsonl("label")));
+        let report = run(&mut check, &flipped.join("\n"));
+        assert_eq!((report.flipped_labels, report.conflicting_groups), (Some(7), Some(7)));
+        assert_eq!((report.outlier_clusters, report.outlier_records), (1, 10));
+        assert!(report.risk >= 13 && report.risk < 50, "{}", report.risk);
+    }
+}
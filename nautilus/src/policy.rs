@@ -0,0 +1,139 @@
+use anyhow::{bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::audit::AuditRecord;
+use crate::category::Category;
+use crate::quality_validator::{CheckWeights, REGISTRY};
+
+// Quality policy simulation: replay audited breakdowns against a candidate policy to answer
+// "how many past datasets would pass threshold X under weights Y" without re-fetching data.
+
+#[derive(Debug, Deserialize)]
+pub struct CandidatePolicy {
+    pub threshold: u8,
+    // Candidate weights; each record's category preset when omitted. Scores are calibrated
+    // with the record's category either way, matching how they were originally produced.
+    #[serde(default)]
+    pub weights: Option<CheckWeights>,
+    // Per-check floors, e.g. {"bias": 40}; a dataset below any floor fails regardless of score.
+    // Checks that were skipped for a record (dedup under load) are not enforced for it.
+    #[serde(default)]
+    pub min_check_scores: BTreeMap<String, u32>,
+    // Only replay records verified at or after this time.
+    #[serde(default)]
+    pub since_ms: u64,
+    // Restrict the replay to one category; scores are only comparable within a category.
+    #[serde(default)]
+    pub category: Option<Category>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SimulationResult {
+    pub evaluated: u64,
+    pub passed: u64,
+    pub pass_rate: f64,
+    // Outcomes as originally decided (recorded score vs. requested threshold).
+    pub recorded_passed: u64,
+    pub newly_passing: u64,
+    pub newly_failing: u64,
+    pub mean_score: f64,
+    // Simulated scores bucketed by tens: [0-9], [10-19], ..., [90-100].
+    pub score_histogram: [u64; 10],
+}
+
+pub fn simulate(policy: &CandidatePolicy, records: &[AuditRecord]) -> Result<SimulationResult> {
+    if let Some(unknown) = policy.min_check_scores.keys().find(|k| !REGISTRY.iter().any(|e| e.name == k.as_str())) {
+        bail!("Unknown check '{}' in min_check_scores", unknown);
+    }
+    if let Some(w) = &policy.weights {
+        if w.total() == 0 {
+            bail!("Policy weights must not all be zero");
+        }
+    }
+
+    let mut out = SimulationResult {
+        evaluated: 0,
+        passed: 0,
+        pass_rate: 0.0,
+        recorded_passed: 0,
+        newly_passing: 0,
+        newly_failing: 0,
+        mean_score: 0.0,
+        score_histogram: [0; 10],
+    };
+    let mut score_sum = 0u64;
+    let in_scope = |r: &&AuditRecord| r.timestamp_ms >= policy.since_ms && policy.category.is_none_or(|c| c == r.category);
+    for rec in records.iter().filter(in_scope) {
+        let preset = rec.category.preset();
+        let score = preset.calibrate(rec.breakdown.score(policy.weights.as_ref().unwrap_or(&preset.weights)));
+        let floors_met = policy
+            .min_check_scores
+            .iter()
+            .all(|(check, floor)| rec.breakdown.check(check).is_none_or(|s| s >= *floor));
+        let passes = score >= policy.threshold && floors_met;
+        let passed_before = rec.score >= rec.threshold;
+
+        out.evaluated += 1;
+        out.passed += passes as u64;
+        out.recorded_passed += passed_before as u64;
+        out.newly_passing += (passes && !passed_before) as u64;
+        out.newly_failing += (!passes && passed_before) as u64;
+        out.score_histogram[(score as usize / 10).min(9)] += 1;
+        score_sum += score as u64;
+    }
+    if out.evaluated > 0 {
+        out.pass_rate = out.passed as f64 / out.evaluated as f64;
+        out.mean_score = score_sum as f64 / out.evaluated as f64;
+    }
+    Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::quality_validator::QualityBreakdown;
+
+    fn record(score_parts: [u32; 4], authenticity: Option<u32>, threshold: u8) -> AuditRecord {
+        let breakdown = QualityBreakdown {
+            diversity: Some(score_parts[0]),
+            bias: Some(score_parts[1]),
+            authenticity,
+            completeness: Some(score_parts[2]),
+            consistency: Some(score_parts[3]),
+            ..Default::default()
+        };
+        AuditRecord {
+            job_id: "job".into(),
+            blob_id: "blob".into(),
+            timestamp_ms: 1,
+            category: Category::Generic,
+            threshold,
+            score: breakdown.score(&CheckWeights::default()),
+            breakdown,
+            degradations: Vec::new(),
+            evidence: Default::default(),
+        }
+    }
+
+    #[test]
+    fn test_simulate_threshold_and_floors() {
+        let records = vec![
+            record([90, 90, 90, 90], Some(90), 50),
+            record([60, 20, 60, 60], Some(60), 50),
+            record([30, 30, 30, 30], None, 50),
+        ];
+        let policy: CandidatePolicy =
+            serde_json::from_str(r#"{"threshold": 50, "min_check_scores": {"bias": 40}}"#).unwrap();
+        let res = simulate(&policy, &records).unwrap();
+        assert_eq!(res.evaluated, 3);
+        assert_eq!(res.recorded_passed, 2);
+        assert_eq!(res.passed, 1);
+        assert_eq!(res.newly_failing, 1);
+        assert_eq!(res.score_histogram[9], 1);
+        assert_eq!(res.score_histogram[3], 1);
+
+        let bad: CandidatePolicy = serde_json::from_str(r#"{"threshold": 50, "min_check_scores": {"vibes": 1}}"#).unwrap();
+        assert!(simulate(&bad, &records).is_err());
+    }
+}
@@ -1,125 +1,1431 @@
-use anyhow::{anyhow, Result};
-use std::collections::HashSet;
-use tracing::info;
+use anyhow::{anyhow, bail, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tracing::{info, warn};
 
-// Public API: run a suite of static checks and return a weighted 0..=100 score.
+use crate::category::Category;
+use crate::columnar::ColumnarProfile;
+use crate::complexity::ComplexityCheck;
+use crate::composite::{self, Composite};
+use crate::content_policy::{self, DetectedType};
+use crate::csv_validator::CsvCheck;
+use crate::data_contract::{DataContract, SchemaConformance};
+use crate::encoding::EncodingIntegrity;
+use crate::distribution::NumericDistributions;
+use crate::image_validator::ImageProfile;
+use crate::field_encryption::{self, EncryptedField, FieldScanner};
+use crate::json_stream::JsonCheck;
+use crate::labels::{LabelBalance, LabelReader};
+use crate::model_artifact::{FiniteWeights, ModelProfile};
+use crate::near_dup::NearDuplicates;
+use crate::ngram::{NgramConfig, NgramRepeats};
+use crate::opaque::{OpacityCheck, OpaquePayload};
+use crate::pii::PiiCheck;
+use crate::plugins::{Plugin, PluginCheck};
+use crate::poisoning::PoisoningCheck;
+use crate::sampling::{Reservoir, SampleInfo, SampleSpec};
+use crate::scoring::{
+    Aggregate, OverrideBounds, ScoringFile, ScoringOverrides, ScoringSummary, DEFAULT_COMPLETENESS_THRESHOLDS,
+};
+use crate::secrets::SecretsCheck;
+use crate::stream_stats::{Moments, STREAM_CHUNK};
+use crate::text_validator::TextProfile;
+use crate::timeseries::{Freshness, TimeSeriesConsistency, TimestampReader};
+use crate::toxicity::{Lexicon, ToxicityCheck};
+
+// Knobs that let the caller trade thoroughness for resources (see load_shed).
+#[derive(Debug, Clone)]
+pub struct ValidationOptions {
+    // Skip the repeated-window (fuzzy dedup) authenticity check.
+    pub skip_dedup: bool,
+    // Percentage of the blob (in fixed-size chunks) fetched when sampling straight from storage.
+    pub sample_rate_pct: u8,
+    // Score a uniform record sample (see sampling) instead of every byte that streams past.
+    pub sample: Option<SampleSpec>,
+    // Full blob length when the caller already fetched only a sample (see `sample_ranges`).
+    pub source_len: Option<u64>,
+    // Selects the check weights and score calibration.
+    pub category: Category,
+    // Label column of a labeled CSV or JSON Lines dataset; its class balance counts toward bias.
+    pub label_column: Option<String>,
+    // Timestamp column of a CSV or JSON Lines time series (see timeseries).
+    pub timestamp_column: Option<String>,
+    // Parameter count a model artifact claims; checked against its tensors (see model_artifact).
+    pub declared_parameters: Option<u64>,
+    // JSON Schema that JSON and JSON Lines records are held to; conformance counts toward
+    // consistency (see data_contract).
+    pub contract: Option<DataContract>,
+    // Format of the dataset when known up front, e.g. sniffed from the head of a blob of which
+    // only random chunks are scored (see estimate); sniffed from the first bytes otherwise.
+    pub format: Option<DetectedType>,
+}
+
+impl Default for ValidationOptions {
+    fn default() -> Self {
+        Self {
+            skip_dedup: false,
+            sample_rate_pct: 100,
+            sample: None,
+            source_len: None,
+            category: Category::Generic,
+            label_column: None,
+            timestamp_column: None,
+            declared_parameters: None,
+            contract: None,
+            format: None,
+        }
+    }
+}
+
+pub const SAMPLE_CHUNK: usize = 4096;
+
+// One scored check over the dataset. Checks stream like the rest of the pipeline: `update` sees
+// the examined bytes in order, in chunks of any size, and `score` is asked once at the end, so
+// the one-shot `score(&[u8])` is an `update` followed by a `score`.
+pub trait QualityCheck: Send {
+    fn name(&self) -> &'static str;
+    // Weight in the aggregate unless NAUTILUS_CHECK_WEIGHTS overrides it.
+    fn weight(&self, category: Category) -> u32;
+    fn update(&mut self, data: &[u8]);
+    // Called once after the last `update`, before `score` and `details`.
+    fn finish(&mut self) {}
+    // 0..=100. `total_len` is the full blob length, also when only part of it was examined.
+    fn score(&self, total_len: u64) -> u32;
+    // Take bytes (by value histogram) back out; only checks over the byte distribution care.
+    fn exclude(&mut self, _freq: &[u64; 256]) {}
+    // Approximate heap held, for job accounting.
+    fn state_bytes(&self) -> u64 {
+        0
+    }
+    // Format-specific findings reported alongside the score, under the returned key.
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        None
+    }
+    // Intermediate values the score was derived from (ratios, counts), for the detailed report.
+    fn measures(&self, _total_len: u64) -> Vec<(&'static str, f64)> {
+        Vec::new()
+    }
+    // What the score was computed from, for disputes: offending offsets, record indices,
+    // duplicate hashes. Sealed in the evidence store; only its hash is published (see evidence).
+    fn evidence(&self) -> Option<serde_json::Value> {
+        None
+    }
+}
+
+// Longest list kept in an evidence artifact; the rest are counted, not listed.
+pub const MAX_EVIDENCE_ITEMS: usize = 256;
+
+// [offset, length] spans of the examined bytes that match some condition, e.g. null runs.
+// Conditions judged byte by byte are `mark`ed; known spans are `cover`ed, in order of their end.
+#[derive(Default)]
+struct Segments {
+    spans: Vec<[u64; 2]>,
+    open: Option<u64>,
+    // [start, end) of the span being covered.
+    covering: Option<[u64; 2]>,
+    unlisted: u64,
+}
+
+impl Segments {
+    fn mark(&mut self, offset: u64, hit: bool) {
+        match (self.open, hit) {
+            (None, true) => self.open = Some(offset),
+            (Some(start), false) => {
+                self.open = None;
+                self.push([start, offset - start]);
+            }
+            _ => {}
+        }
+    }
+
+    // Overlapping spans merge; a span reaching back past the last listed one is cut at its end.
+    fn cover(&mut self, start: u64, end: u64) {
+        let floor = self.spans.last().map_or(0, |[s, len]| s + len);
+        match &mut self.covering {
+            Some([s, e]) if start <= *e => {
+                *s = (*s).min(start).max(floor);
+                *e = (*e).max(end);
+            }
+            _ => {
+                if let Some([s, e]) = self.covering.replace([start.max(floor), end]) {
+                    self.push([s, e - s]);
+                }
+            }
+        }
+    }
+
+    fn push(&mut self, span: [u64; 2]) {
+        if self.spans.len() < MAX_EVIDENCE_ITEMS {
+            self.spans.push(span);
+        } else {
+            self.unlisted += 1;
+        }
+    }
+
+    fn to_json(&self, end: u64) -> serde_json::Value {
+        let mut spans = self.spans.clone();
+        let mut unlisted = self.unlisted;
+        let last = self.open.map(|start| [start, end - start]).or(self.covering.map(|[s, e]| [s, e - s]));
+        match last {
+            Some(span) if spans.len() < MAX_EVIDENCE_ITEMS => spans.push(span),
+            Some(_) => unlisted += 1,
+            None => {}
+        }
+        serde_json::json!({ "spans": spans, "unlisted": unlisted })
+    }
+}
+
+pub struct CheckEntry {
+    pub name: &'static str,
+    // Formats the entry validates; any format when empty.
+    pub formats: &'static [DetectedType],
+    // None when the check doesn't run under these options (e.g. dedup shed under load).
+    build: fn(&ValidationOptions, &mut BuildContext) -> Option<Box<dyn QualityCheck>>,
+}
+
+// Whole-dataset state decoded once and scored by several checks, e.g. a columnar file profiled
+// per column (see columnar), an archive of images (see image_validator) or a text corpus (see
+// text_validator).
+pub trait SharedProfile: Send {
+    fn update(&mut self, data: &[u8]);
+    // Called by every check sharing the profile; only the first call decodes.
+    fn finish(&mut self);
+    // The named check's score, None when the profile can't answer it.
+    fn score(&self, check: &str) -> Option<u32>;
+    fn state_bytes(&self) -> u64;
+    fn details(&self) -> Option<(&'static str, serde_json::Value)>;
+    // Evidence behind the named check's score (see `QualityCheck::evidence`).
+    fn evidence(&self, _check: &str) -> Option<serde_json::Value> {
+        None
+    }
+}
+
+// One check scored from a shared profile. The first check built over a profile feeds it; each
+// keeps its byte-level counterpart for what the profile can't answer.
+struct ProfiledCheck {
+    name: &'static str,
+    profile: Arc<Mutex<dyn SharedProfile>>,
+    feeds: bool,
+    fallback: Box<dyn QualityCheck>,
+}
+
+impl ProfiledCheck {
+    fn lock(&self) -> std::sync::MutexGuard<'_, dyn SharedProfile + 'static> {
+        self.profile.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl QualityCheck for ProfiledCheck {
+    fn name(&self) -> &'static str {
+        self.name
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        self.fallback.weight(category)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        if self.feeds {
+            self.lock().update(data);
+        }
+        self.fallback.update(data);
+    }
+
+    fn finish(&mut self) {
+        self.lock().finish();
+        self.fallback.finish();
+    }
+
+    fn score(&self, total_len: u64) -> u32 {
+        let scored = self.lock().score(self.name);
+        scored.unwrap_or_else(|| self.fallback.score(total_len))
+    }
+
+    fn exclude(&mut self, freq: &[u64; 256]) {
+        self.fallback.exclude(freq);
+    }
+
+    fn state_bytes(&self) -> u64 {
+        let held = if self.feeds { self.lock().state_bytes() } else { 0 };
+        held + self.fallback.state_bytes()
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        if self.feeds {
+            self.lock().details()
+        } else {
+            None
+        }
+    }
+
+    // The profile's findings are in `details`; byte-level measures only when they scored.
+    fn measures(&self, total_len: u64) -> Vec<(&'static str, f64)> {
+        match self.lock().score(self.name) {
+            Some(_) => Vec::new(),
+            None => self.fallback.measures(total_len),
+        }
+    }
+
+    // From whichever side produced the score.
+    fn evidence(&self) -> Option<serde_json::Value> {
+        let profile = self.lock();
+        match profile.score(self.name) {
+            Some(_) => profile.evidence(self.name),
+            None => self.fallback.evidence(),
+        }
+    }
+}
+
+// State shared by the checks built for one dataset.
+pub struct BuildContext {
+    format: DetectedType,
+    profile: Option<Arc<Mutex<dyn SharedProfile>>>,
+    completeness_thresholds: [u64; 3],
+    ngrams: NgramConfig,
+    lexicon: Option<Arc<Lexicon>>,
+}
+
+impl BuildContext {
+    fn completeness(&self) -> Box<dyn QualityCheck> {
+        Box::new(Completeness { thresholds: self.completeness_thresholds })
+    }
+
+    fn authenticity(&self) -> Box<dyn QualityCheck> {
+        Box::new(Authenticity::new(&self.ngrams))
+    }
+
+    fn profiled(
+        &mut self,
+        fallback: Box<dyn QualityCheck>,
+        new: impl FnOnce(DetectedType) -> Arc<Mutex<dyn SharedProfile>>,
+    ) -> Box<dyn QualityCheck> {
+        let feeds = self.profile.is_none();
+        let format = self.format;
+        let profile = self.profile.get_or_insert_with(|| new(format)).clone();
+        Box::new(ProfiledCheck { name: fallback.name(), profile, feeds, fallback })
+    }
+
+    // Scored from the dataset's columns.
+    fn columnar(&mut self, fallback: Box<dyn QualityCheck>) -> Box<dyn QualityCheck> {
+        self.profiled(fallback, |format| Arc::new(Mutex::new(ColumnarProfile::new(format))))
+    }
+
+    // Scored from the decoded images of an image file or archive.
+    fn images(&mut self, fallback: Box<dyn QualityCheck>) -> Box<dyn QualityCheck> {
+        self.profiled(fallback, |format| Arc::new(Mutex::new(ImageProfile::new(format))))
+    }
+
+    // Scored from the documents of a plain-text corpus.
+    fn text(&mut self, fallback: Box<dyn QualityCheck>) -> Box<dyn QualityCheck> {
+        self.profiled(fallback, |_| Arc::new(Mutex::new(TextProfile::new())))
+    }
+
+    // Scored from the header and tensors of a model artifact.
+    fn model(&mut self, fallback: Box<dyn QualityCheck>, declared_parameters: Option<u64>) -> Box<dyn QualityCheck> {
+        self.profiled(fallback, |_| Arc::new(Mutex::new(ModelProfile::new(declared_parameters))))
+    }
+
+    // Scaled by the UTF-8 integrity of the examined bytes (see encoding).
+    fn encoded(&self, fallback: Box<dyn QualityCheck>) -> Box<dyn QualityCheck> {
+        Box::new(EncodingIntegrity::new(fallback))
+    }
+}
+
+const TEXTUAL: &[DetectedType] = &[DetectedType::Json, DetectedType::Jsonl, DetectedType::Csv, DetectedType::Text];
+const COLUMNAR: &[DetectedType] = &[DetectedType::Parquet, DetectedType::Arrow];
+const IMAGES: &[DetectedType] = &[DetectedType::Image, DetectedType::Archive];
+const MODEL: &[DetectedType] = &[DetectedType::Model];
+
+// Every check the validator knows, in breakdown order. Adding a check is a `QualityCheck` impl
+// and an entry here; configuration (ChecksConfig) disables or re-weights entries by name. The
+// dataset's format is sniffed from its first bytes and only entries for that format run. A
+// format-specific validator shares a name (and so a place in the breakdown) with the generic
+// check it stands in for and is listed before it: the first entry per name that builds runs.
+pub const REGISTRY: &[CheckEntry] = &[
+    // Parquet and Arrow files are decoded and scored per column (see columnar), which needs the
+    // whole file in order.
+    CheckEntry {
+        name: "diversity",
+        formats: COLUMNAR,
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none()).then(|| ctx.columnar(Box::new(Diversity::default())))
+        },
+    },
+    // Images and tar archives of them are decoded image by image (see image_validator).
+    CheckEntry {
+        name: "diversity",
+        formats: IMAGES,
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none()).then(|| ctx.images(Box::new(Diversity::default())))
+        },
+    },
+    // Text corpora are profiled line by line (see text_validator); a record sample is lines too.
+    CheckEntry {
+        name: "diversity",
+        formats: &[DetectedType::Text],
+        build: |opts, ctx| opts.source_len.is_none().then(|| ctx.text(Box::new(Diversity::default()))),
+    },
+    CheckEntry { name: "diversity", formats: &[], build: |_, _| Some(Box::new(Diversity::default())) },
+    CheckEntry {
+        name: "bias",
+        formats: COLUMNAR,
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none()).then(|| ctx.columnar(Box::new(Bias::default())))
+        },
+    },
+    // A labeled dataset's class balance is averaged in (see labels). The CSV header has to be
+    // read, so CSV only counts when every row is examined in order.
+    CheckEntry {
+        name: "bias",
+        formats: &[DetectedType::Csv],
+        build: |opts, _| {
+            let column = opts.label_column.as_deref().filter(|_| opts.sample.is_none() && opts.source_len.is_none())?;
+            Some(Box::new(LabelBalance::new(LabelReader::csv(column), Box::new(Bias::default()))))
+        },
+    },
+    CheckEntry {
+        name: "bias",
+        formats: &[DetectedType::Jsonl],
+        build: |opts, _| {
+            let column = opts.label_column.as_deref().filter(|_| opts.source_len.is_none())?;
+            Some(Box::new(LabelBalance::new(LabelReader::jsonl(column), Box::new(Bias::default()))))
+        },
+    },
+    CheckEntry { name: "bias", formats: &[], build: |_, _| Some(Box::new(Bias::default())) },
+    CheckEntry {
+        name: "authenticity",
+        formats: IMAGES,
+        build: |opts, ctx| {
+            (!opts.skip_dedup && opts.sample.is_none() && opts.source_len.is_none())
+                .then(|| ctx.images(ctx.authenticity()))
+        },
+    },
+    CheckEntry {
+        name: "authenticity",
+        formats: &[DetectedType::Text],
+        build: |opts, ctx| {
+            (!opts.skip_dedup && opts.source_len.is_none()).then(|| ctx.text(ctx.authenticity()))
+        },
+    },
+    // Numeric columns of tabular data are tested for fabricated values (see distribution),
+    // decoded Parquet and Arrow columns in place of the byte-level indicator, CSV columns
+    // averaged with the near-duplicate share. Both read whole files in order.
+    CheckEntry {
+        name: "authenticity",
+        formats: COLUMNAR,
+        build: |opts, ctx| {
+            (!opts.skip_dedup && opts.sample.is_none() && opts.source_len.is_none()).then(|| ctx.columnar(ctx.authenticity()))
+        },
+    },
+    CheckEntry {
+        name: "authenticity",
+        formats: &[DetectedType::Csv],
+        build: |opts, _| {
+            (!opts.skip_dedup && opts.sample.is_none() && opts.source_len.is_none()).then(|| {
+                Box::new(NumericDistributions::csv(Box::new(NearDuplicates::default()))) as Box<dyn QualityCheck>
+            })
+        },
+    },
+    // Rows and JSON Lines records are compared with each other for near duplicates (see near_dup).
+    CheckEntry {
+        name: "authenticity",
+        formats: &[DetectedType::Csv, DetectedType::Jsonl],
+        build: |opts, _| (!opts.skip_dedup).then(|| Box::new(NearDuplicates::default()) as Box<dyn QualityCheck>),
+    },
+    CheckEntry {
+        name: "authenticity",
+        formats: &[],
+        build: |opts, ctx| (!opts.skip_dedup).then(|| ctx.authenticity()),
+    },
+    // CSV rows have to be read in order, from the header on.
+    CheckEntry {
+        name: "completeness",
+        formats: &[DetectedType::Csv],
+        build: |opts, _| {
+            (opts.sample.is_none() && opts.source_len.is_none())
+                .then(|| Box::new(CsvCheck::completeness()) as Box<dyn QualityCheck>)
+        },
+    },
+    CheckEntry {
+        name: "completeness",
+        formats: &[DetectedType::Json],
+        build: |opts, _| {
+            (opts.sample.is_none() && opts.source_len.is_none())
+                .then(|| Box::new(JsonCheck::completeness(false)) as Box<dyn QualityCheck>)
+        },
+    },
+    CheckEntry {
+        name: "completeness",
+        formats: &[DetectedType::Jsonl],
+        build: |opts, _| opts.source_len.is_none().then(|| Box::new(JsonCheck::completeness(true)) as Box<dyn QualityCheck>),
+    },
+    CheckEntry {
+        name: "completeness",
+        formats: COLUMNAR,
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none()).then(|| ctx.columnar(ctx.completeness()))
+        },
+    },
+    CheckEntry {
+        name: "completeness",
+        formats: IMAGES,
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none()).then(|| ctx.images(ctx.completeness()))
+        },
+    },
+    // Model artifacts are judged on their header and tensors (see model_artifact), read in order.
+    CheckEntry {
+        name: "completeness",
+        formats: MODEL,
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none())
+                .then(|| ctx.model(ctx.completeness(), opts.declared_parameters))
+        },
+    },
+    CheckEntry { name: "completeness", formats: &[], build: |_, ctx| Some(ctx.completeness()) },
+    CheckEntry {
+        name: "consistency",
+        formats: &[DetectedType::Csv],
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none()).then(|| ctx.encoded(Box::new(CsvCheck::consistency())))
+        },
+    },
+    // A declared timestamp column's ordering and sampling regularity are averaged in (see
+    // timeseries). The series is judged in record order, so every record has to be examined.
+    CheckEntry {
+        name: "consistency",
+        formats: &[DetectedType::Csv, DetectedType::Jsonl],
+        build: |opts, ctx| {
+            let column = opts.timestamp_column.as_deref().filter(|_| opts.sample.is_none() && opts.source_len.is_none())?;
+            let fallback: Box<dyn QualityCheck> = match (ctx.format, opts.contract.clone()) {
+                (DetectedType::Csv, _) => Box::new(CsvCheck::consistency()),
+                (_, Some(contract)) => Box::new(SchemaConformance::jsonl(contract, Box::new(JsonCheck::consistency(true)))),
+                (_, None) => Box::new(JsonCheck::consistency(true)),
+            };
+            let timestamps = TimestampReader::new(ctx.format, column, crate::timeseries::now_ms())?;
+            Some(ctx.encoded(Box::new(TimeSeriesConsistency::new(timestamps, fallback))))
+        },
+    },
+    // Conformance to the request's JSON Schema is averaged in (see data_contract), under the
+    // same conditions as the structural check.
+    CheckEntry {
+        name: "consistency",
+        formats: &[DetectedType::Json],
+        build: |opts, ctx| {
+            let contract = opts.contract.clone().filter(|_| opts.sample.is_none() && opts.source_len.is_none())?;
+            Some(ctx.encoded(Box::new(SchemaConformance::json(contract, Box::new(JsonCheck::consistency(false))))))
+        },
+    },
+    CheckEntry {
+        name: "consistency",
+        formats: &[DetectedType::Jsonl],
+        build: |opts, ctx| {
+            let contract = opts.contract.clone().filter(|_| opts.source_len.is_none())?;
+            Some(ctx.encoded(Box::new(SchemaConformance::jsonl(contract, Box::new(JsonCheck::consistency(true))))))
+        },
+    },
+    // JSON has to be parsed in order; JSONL lines are records, so a record sample parses too.
+    CheckEntry {
+        name: "consistency",
+        formats: &[DetectedType::Json],
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none())
+                .then(|| ctx.encoded(Box::new(JsonCheck::consistency(false))))
+        },
+    },
+    CheckEntry {
+        name: "consistency",
+        formats: &[DetectedType::Jsonl],
+        build: |opts, ctx| opts.source_len.is_none().then(|| ctx.encoded(Box::new(JsonCheck::consistency(true)))),
+    },
+    CheckEntry {
+        name: "consistency",
+        formats: &[DetectedType::Text],
+        build: |opts, ctx| {
+            let profiled = opts.source_len.is_none().then(|| ctx.text(Box::new(Consistency::default())))?;
+            Some(ctx.encoded(profiled))
+        },
+    },
+    // Null bytes and encoding only mean something in text; binary containers are judged on their
+    // framing, which needs the end of the blob and so only runs when every byte is examined in order.
+    CheckEntry {
+        name: "consistency",
+        formats: TEXTUAL,
+        build: |_, ctx| Some(ctx.encoded(Box::new(Consistency::default()))),
+    },
+    CheckEntry {
+        name: "consistency",
+        formats: IMAGES,
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none()).then(|| ctx.images(Box::new(Framing::default())))
+        },
+    },
+    CheckEntry {
+        name: "consistency",
+        formats: &[DetectedType::Parquet, DetectedType::Arrow],
+        build: |opts, _| {
+            (opts.sample.is_none() && opts.source_len.is_none())
+                .then(|| Box::new(Framing::default()) as Box<dyn QualityCheck>)
+        },
+    },
+    CheckEntry {
+        name: "consistency",
+        formats: MODEL,
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none())
+                .then(|| ctx.model(Box::new(Framing::default()), opts.declared_parameters))
+        },
+    },
+    // Personal data in text records; reports counts per kind only (see pii).
+    CheckEntry { name: "privacy", formats: TEXTUAL, build: |_, _| Some(Box::new(PiiCheck::default())) },
+    // Keys, tokens and passwords in text records; counts per kind only (see secrets).
+    CheckEntry { name: "secrets", formats: TEXTUAL, build: |_, _| Some(Box::new(SecretsCheck::default())) },
+    // Outlier clusters, repeated triggers and, given the label column, flipped labels (see
+    // poisoning). The label column is read as for bias.
+    CheckEntry {
+        name: "poisoning",
+        formats: &[DetectedType::Csv, DetectedType::Jsonl, DetectedType::Text],
+        build: |opts, ctx| {
+            let column = opts.label_column.as_deref().filter(|_| opts.source_len.is_none());
+            let labels = match ctx.format {
+                DetectedType::Csv => column.filter(|_| opts.sample.is_none()).map(LabelReader::csv),
+                DetectedType::Jsonl => column.map(LabelReader::jsonl),
+                _ => None,
+            };
+            Some(Box::new(PoisoningCheck::new(labels)))
+        },
+    },
+    // Age of the newest record of a declared time series (see timeseries).
+    CheckEntry {
+        name: "freshness",
+        formats: &[DetectedType::Csv, DetectedType::Jsonl],
+        build: |opts, ctx| {
+            let column = opts.timestamp_column.as_deref().filter(|_| opts.sample.is_none() && opts.source_len.is_none())?;
+            let timestamps = TimestampReader::new(ctx.format, column, crate::timeseries::now_ms())?;
+            Some(Box::new(Freshness::new(timestamps)))
+        },
+    },
+    // NaN and infinite weights of a model artifact (see model_artifact).
+    CheckEntry {
+        name: "finite_weights",
+        formats: MODEL,
+        build: |opts, ctx| {
+            (opts.sample.is_none() && opts.source_len.is_none())
+                .then(|| ctx.model(Box::new(FiniteWeights), opts.declared_parameters))
+        },
+    },
+    // Profanity and abuse in text records, when the operator enabled a lexicon (see toxicity).
+    CheckEntry {
+        name: "content_safety",
+        formats: TEXTUAL,
+        build: |_, ctx| ctx.lexicon.clone().map(|lexicon| Box::new(ToxicityCheck::new(lexicon)) as Box<dyn QualityCheck>),
+    },
+    // Records repeated from a few templates compress to almost nothing (see complexity).
+    CheckEntry { name: "complexity", formats: TEXTUAL, build: |_, _| Some(Box::new(ComplexityCheck::default())) },
+];
+
+// Deployment-wide check selection: NAUTILUS_DISABLED_CHECKS="bias,consistency" drops checks
+// from the aggregate, NAUTILUS_CHECK_WEIGHTS="authenticity=40,diversity=10" overrides their
+// weights in every category. Minimums, the aggregate formula and completeness thresholds come
+// from NAUTILUS_SCORING_FILE (see scoring). Names are checked against the registry at startup.
+// NAUTILUS_NGRAM_SIZES and NAUTILUS_NGRAM_SAMPLE set the n-gram sizes the authenticity check
+// looks for repeats of and how many distinct n-grams it holds, and so its memory (see ngram).
+#[derive(Debug, Clone)]
+pub struct ChecksConfig {
+    pub disabled: Vec<String>,
+    pub weights: BTreeMap<String, u32>,
+    // Checks scoring below their minimum fail the verification.
+    pub minimums: BTreeMap<String, u32>,
+    pub aggregate: Aggregate,
+    pub completeness_thresholds: [u64; 3],
+    // What a request's `scoring` overrides may change.
+    pub override_bounds: OverrideBounds,
+    // Whether request overrides were applied.
+    pub overridden: bool,
+    // Repeated n-gram detection.
+    pub ngrams: NgramConfig,
+    // Operator WebAssembly checks, run after the registered ones (see plugins).
+    pub plugins: Vec<Arc<Plugin>>,
+    // Wordlist the content_safety check matches; the check only runs with one (see toxicity).
+    pub lexicon: Option<Arc<Lexicon>>,
+}
+
+impl Default for ChecksConfig {
+    fn default() -> Self {
+        Self {
+            disabled: Vec::new(),
+            weights: BTreeMap::new(),
+            minimums: BTreeMap::new(),
+            aggregate: Aggregate::default(),
+            completeness_thresholds: DEFAULT_COMPLETENESS_THRESHOLDS,
+            override_bounds: OverrideBounds::default(),
+            overridden: false,
+            ngrams: NgramConfig::default(),
+            plugins: Vec::new(),
+            lexicon: None,
+        }
+    }
+}
+
+impl ChecksConfig {
+    pub fn from_env() -> Result<Self> {
+        let disabled: Vec<String> = env::var("NAUTILUS_DISABLED_CHECKS")
+            .unwrap_or_default()
+            .split(',')
+            .map(|n| n.trim().to_string())
+            .filter(|n| !n.is_empty())
+            .collect();
+        let file = match env::var("NAUTILUS_SCORING_FILE").ok().filter(|v| !v.is_empty()) {
+            Some(path) => ScoringFile::load(Path::new(&path))?,
+            None => ScoringFile::default(),
+        };
+        let mut weights = file.weights.clone();
+        for pair in env::var("NAUTILUS_CHECK_WEIGHTS").unwrap_or_default().split(',').filter(|p| !p.trim().is_empty()) {
+            let parsed = pair.split_once('=').and_then(|(n, w)| Some((n.trim().to_string(), w.trim().parse().ok()?)));
+            let Some((name, weight)) = parsed else {
+                bail!("Invalid NAUTILUS_CHECK_WEIGHTS entry '{}' (expected name=weight)", pair);
+            };
+            weights.insert(name, weight);
+        }
+        let plugins = crate::plugins::from_env()?;
+        let known = |n: &String| REGISTRY.iter().any(|e| e.name == n.as_str()) || plugins.iter().any(|p| p.name == n.as_str());
+        if let Some(unknown) = disabled.iter().chain(weights.keys()).chain(file.names()).find(|n| !known(n)) {
+            bail!("Unknown quality check '{}'", unknown);
+        }
+        if env::var_os("NAUTILUS_DEDUP_WINDOW_BYTES").is_some() {
+            warn!("NAUTILUS_DEDUP_WINDOW_BYTES is ignored; repeats are sampled by NAUTILUS_NGRAM_SAMPLE");
+        }
+        if REGISTRY.iter().all(|e| disabled.iter().any(|d| d == e.name)) {
+            bail!("NAUTILUS_DISABLED_CHECKS disables every quality check");
+        }
+        Ok(Self {
+            disabled,
+            weights,
+            minimums: file.minimums,
+            aggregate: file.aggregate,
+            completeness_thresholds: file.completeness_thresholds.unwrap_or(DEFAULT_COMPLETENESS_THRESHOLDS),
+            override_bounds: file.overrides,
+            overridden: false,
+            ngrams: NgramConfig::from_env()?,
+            plugins,
+            lexicon: crate::toxicity::from_env()?,
+        })
+    }
+
+    // This configuration with a request's overrides applied, if they are within bounds.
+    pub fn with_overrides(&self, overrides: &ScoringOverrides) -> Result<Self> {
+        overrides.check(&self.override_bounds)?;
+        let mut config = self.clone();
+        config.weights.extend(overrides.weights.clone());
+        config.minimums.extend(overrides.minimums.clone());
+        config.aggregate = overrides.aggregate.unwrap_or(config.aggregate);
+        config.overridden = true;
+        Ok(config)
+    }
+
+    fn enabled(&self, name: &str) -> bool {
+        !self.disabled.iter().any(|d| d == name)
+    }
+
+    // The static name of a registered check or plugin.
+    pub fn check_name(&self, name: &str) -> Option<&'static str> {
+        let registered = REGISTRY.iter().find(|e| e.name == name).map(|e| e.name);
+        registered.or_else(|| self.plugins.iter().find(|p| p.name == name).map(|p| p.name))
+    }
+}
+
+// Relative weight of each check in the aggregate score. Checks outside the five built-ins are
+// weighted through `extra`, by name.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct CheckWeights {
+    pub diversity: u32,
+    pub bias: u32,
+    pub authenticity: u32,
+    pub completeness: u32,
+    pub consistency: u32,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, u32>,
+}
+
+impl Default for CheckWeights {
+    fn default() -> Self {
+        Self { diversity: 25, bias: 20, authenticity: 30, completeness: 15, consistency: 10, extra: BTreeMap::new() }
+    }
+}
+
+impl CheckWeights {
+    pub fn get(&self, name: &str) -> u32 {
+        match name {
+            "diversity" => self.diversity,
+            "bias" => self.bias,
+            "authenticity" => self.authenticity,
+            "completeness" => self.completeness,
+            "consistency" => self.consistency,
+            _ => self.extra.get(name).copied().unwrap_or(0),
+        }
+    }
+
+    fn set(&mut self, name: &str, weight: u32) {
+        match name {
+            "diversity" => self.diversity = weight,
+            "bias" => self.bias = weight,
+            "authenticity" => self.authenticity = weight,
+            "completeness" => self.completeness = weight,
+            "consistency" => self.consistency = weight,
+            _ => {
+                self.extra.insert(name.to_string(), weight);
+            }
+        }
+    }
+
+    pub fn total(&self) -> u32 {
+        self.diversity + self.bias + self.authenticity + self.completeness + self.consistency + self.extra.values().sum::<u32>()
+    }
+}
+
+// Per-check 0..=100 scores behind an aggregate. A built-in is None when it didn't run (dedup
+// skipped under load, or disabled by configuration); other registered checks land in `extra`.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct QualityBreakdown {
+    pub diversity: Option<u32>,
+    pub bias: Option<u32>,
+    pub authenticity: Option<u32>,
+    pub completeness: Option<u32>,
+    pub consistency: Option<u32>,
+    #[serde(flatten)]
+    pub extra: BTreeMap<String, u32>,
+}
+
+impl QualityBreakdown {
+    // Weighted average of the checks that ran; a skipped check drops out and the remaining
+    // weights are renormalized.
+    pub fn score(&self, w: &CheckWeights) -> u8 {
+        self.aggregate(w, Aggregate::WeightedMean)
+    }
+
+    pub fn aggregate(&self, w: &CheckWeights, aggregate: Aggregate) -> u8 {
+        aggregate.combine(self.scores().map(|(name, score)| (score, w.get(name))))
+    }
+
+    // (check, score) for every check that ran.
+    pub fn scores(&self) -> impl Iterator<Item = (&str, u32)> {
+        [
+            ("diversity", self.diversity),
+            ("bias", self.bias),
+            ("authenticity", self.authenticity),
+            ("completeness", self.completeness),
+            ("consistency", self.consistency),
+        ]
+        .into_iter()
+        .filter_map(|(name, score)| score.map(|s| (name, s)))
+        .chain(self.extra.iter().map(|(name, &s)| (name.as_str(), s)))
+    }
+
+    // Remediation codes (see i18n) for checks scoring below 50.
+    pub fn remediation_codes(&self) -> Vec<&'static str> {
+        self.scores().filter(|&(_, s)| s < 50).filter_map(|(name, _)| remediation_code(name)).collect()
+    }
+
+    pub fn check(&self, name: &str) -> Option<u32> {
+        self.scores().find(|(n, _)| *n == name).map(|(_, s)| s)
+    }
+
+    pub fn set(&mut self, name: &str, score: u32) {
+        match name {
+            "diversity" => self.diversity = Some(score),
+            "bias" => self.bias = Some(score),
+            "authenticity" => self.authenticity = Some(score),
+            "completeness" => self.completeness = Some(score),
+            "consistency" => self.consistency = Some(score),
+            _ => {
+                self.extra.insert(name.to_string(), score);
+            }
+        }
+    }
+}
+
+// One check's part in the aggregate, for the detailed report.
+#[derive(Debug, Clone, Serialize)]
+pub struct CheckReport {
+    pub score: u32,
+    pub weight: u32,
+    // Weight over the total weight of the checks that ran.
+    pub share: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub minimum: Option<u32>,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub measures: BTreeMap<&'static str, f64>,
+    // The check's entries of `QualityReport::details`.
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub details: BTreeMap<&'static str, serde_json::Value>,
+}
+
+// Remediation hint (see i18n) for a check that scored low.
+pub fn remediation_code(check: &str) -> Option<&'static str> {
+    Some(match check {
+        "diversity" => "LOW_DIVERSITY",
+        "bias" => "LOW_VARIANCE",
+        "authenticity" => "SYNTHETIC_PATTERNS",
+        "completeness" => "INCOMPLETE_DATA",
+        "consistency" => "INCONSISTENT_DATA",
+        "privacy" => "PII_DETECTED",
+        "secrets" => "SECRETS_DETECTED",
+        "complexity" => "LOW_COMPLEXITY",
+        "poisoning" => "POISONING_RISK",
+        "content_safety" => "UNSAFE_CONTENT",
+        "freshness" => "STALE_DATA",
+        "finite_weights" => "NONFINITE_WEIGHTS",
+        _ => return None,
+    })
+}
+
+pub struct QualityReport {
+    pub score: u8,
+    // Sniffed from the first bytes; selects the checks that ran.
+    pub format: DetectedType,
+    pub breakdown: QualityBreakdown,
+    // Set when the checks ran on a record sample.
+    pub sampling: Option<SampleInfo>,
+    // Hashed or encrypted columns, left out of the diversity and bias scores.
+    pub encrypted_fields: Vec<EncryptedField>,
+    // Findings of the format-specific validators that ran, e.g. "json" parse status.
+    pub details: BTreeMap<&'static str, serde_json::Value>,
+    // Per-check evidence artifacts; never returned to clients as is (see evidence).
+    pub evidence: BTreeMap<&'static str, serde_json::Value>,
+    // Weights, minimums and formula behind `score`; attested with it.
+    pub scoring: ScoringSummary,
+    // Per-check breakdown with the values behind each score, by check.
+    pub checks: BTreeMap<&'static str, CheckReport>,
+}
+
+// SHA-256 (hex) of the canonical JSON of `checks`, which attestations commit to: the `?detail=full`
+// report hashes to it once each entry's localized "explanation" and "remediation" are removed.
+pub fn report_sha256(checks: &BTreeMap<&'static str, CheckReport>) -> Result<String> {
+    let json = nautilus_canonical::canonical_bytes(checks).map_err(|e| anyhow!("serialize check report: {}", e))?;
+    Ok(crate::integrity::sha256_hex(&json))
+}
+
+// Whole-buffer form of `QualityAccumulator`.
+#[cfg(test)]
+pub fn validate_dataset_quality(data: &[u8], opts: &ValidationOptions) -> Result<QualityReport> {
+    let mut acc = QualityAccumulator::new(opts, &ChecksConfig::default());
+    acc.update(data);
+    acc.finish()
+}
+
+// Public API: run the registered checks and return a weighted 0..=100 score with its breakdown.
+// The dataset is fed in chunks of any size, in order, and the report is the same as for the
+// concatenated bytes. Only fixed-size counters plus the 4-byte windows of the dedup lookback are
+// kept (see stream_stats), and the record sample when sampling.
 // NEVER log or expose raw data. Only aggregate scores are logged.
-pub fn validate_dataset_quality(data: &[u8]) -> Result<u8> {
-    if data.is_empty() {
-        return Err(anyhow!("empty dataset"));
+pub struct QualityAccumulator {
+    opts: ValidationOptions,
+    config: ChecksConfig,
+    // Leading bytes held back until the format is known; the checks start after that.
+    head: Vec<u8>,
+    format: Option<DetectedType>,
+    // Records are collected here and examined at `finish` when sampling.
+    reservoir: Option<Reservoir>,
+    offset: u64,
+    examined: u64,
+    checks: Vec<Box<dyn QualityCheck>>,
+    weights: CheckWeights,
+    fields: FieldScanner,
+    // Set for a composite blob; its sections are scored by accumulators of their own.
+    composite: Option<Box<Composite>>,
+    // Why a composite manifest was ignored.
+    composite_error: Option<serde_json::Value>,
+    // Scoring a composite's section, which can't nest another.
+    section: bool,
+    // Refuses ciphertext and compressed streams (see opaque).
+    opacity: OpacityCheck,
+}
+
+impl QualityAccumulator {
+    pub fn new(opts: &ValidationOptions, config: &ChecksConfig) -> Self {
+        Self {
+            opts: opts.clone(),
+            config: config.clone(),
+            head: Vec::new(),
+            format: None,
+            reservoir: opts.sample.map(Reservoir::new),
+            offset: 0,
+            examined: 0,
+            checks: Vec::new(),
+            weights: CheckWeights::default(),
+            fields: FieldScanner::new(),
+            composite: None,
+            composite_error: None,
+            section: false,
+            opacity: OpacityCheck::default(),
+        }
+    }
+
+    pub fn section(opts: &ValidationOptions, config: &ChecksConfig) -> Self {
+        Self { section: true, ..Self::new(opts, config) }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        self.offset += data.len() as u64;
+        if let Some(composite) = self.composite.as_mut() {
+            composite.update(data);
+            return;
+        }
+        self.opacity.update(data);
+        if self.format.is_none() {
+            self.head.extend_from_slice(data);
+            if self.head.len() >= content_policy::SNIFF_LEN && (self.section || !composite::awaiting_manifest(&self.head)) {
+                self.start();
+            }
+            return;
+        }
+        self.route(data);
+    }
+
+    // Sniff the format, set up the checks registered for it and replay the held-back bytes.
+    fn start(&mut self) {
+        let head = std::mem::take(&mut self.head);
+        let format =
+            self.opts.format.unwrap_or_else(|| content_policy::sniff(&head[..head.len().min(content_policy::SNIFF_LEN)]));
+        // Sections of a sampled fetch can't be told apart.
+        if format == DetectedType::Composite && self.opts.source_len.is_none() && !self.section {
+            match Composite::start(&head, &self.opts, &self.config) {
+                Ok((mut composite, manifest_len)) => {
+                    composite.update(&head[manifest_len..]);
+                    self.composite = Some(Box::new(composite));
+                    self.format = Some(format);
+                    return;
+                }
+                Err(err) => self.composite_error = Some(composite::manifest_error(&err)),
+            }
+        }
+        let opts = self.opts.clone();
+        let thresholds = self.config.completeness_thresholds;
+        let ngrams = self.config.ngrams.clone();
+        let lexicon = self.config.lexicon.clone();
+        let mut ctx = BuildContext { format, profile: None, completeness_thresholds: thresholds, ngrams, lexicon };
+        self.checks.clear();
+        for entry in REGISTRY {
+            let applies = entry.formats.is_empty() || entry.formats.contains(&format);
+            if !applies || !self.config.enabled(entry.name) || self.checks.iter().any(|c| c.name() == entry.name) {
+                continue;
+            }
+            if let Some(check) = (entry.build)(&opts, &mut ctx) {
+                self.checks.push(check);
+            }
+        }
+        for plugin in self.config.plugins.iter().filter(|p| self.config.enabled(p.name)) {
+            self.checks.push(Box::new(PluginCheck::new(plugin.clone())));
+        }
+        self.weights =
+            CheckWeights { diversity: 0, bias: 0, authenticity: 0, completeness: 0, consistency: 0, extra: BTreeMap::new() };
+        for check in &self.checks {
+            let weight = self.config.weights.get(check.name()).copied().unwrap_or_else(|| check.weight(opts.category));
+            self.weights.set(check.name(), weight);
+        }
+        self.format = Some(format);
+        self.route(&head);
+    }
+
+    fn route(&mut self, data: &[u8]) {
+        match self.reservoir.as_mut() {
+            Some(reservoir) => reservoir.update(data),
+            None => self.examine(data),
+        }
+    }
+
+    // Approximate heap held by the accumulator (check state, e.g. the distinct-window set, and
+    // any record sample), for job accounting.
+    pub fn state_bytes(&self) -> u64 {
+        self.head.capacity() as u64
+            + self.composite.as_ref().map_or(0, |c| c.state_bytes())
+            + self.opacity.state_bytes()
+            + self.checks.iter().map(|c| c.state_bytes()).sum::<u64>()
+            + self.reservoir.as_ref().map_or(0, Reservoir::state_bytes)
+            + self.fields.state_bytes()
+    }
+
+    pub fn finish(mut self) -> Result<QualityReport> {
+        if self.offset == 0 {
+            return Err(anyhow!("empty dataset"));
+        }
+        if self.format.is_none() {
+            self.start();
+        }
+        if let Some(composite) = self.composite.take() {
+            return composite.finish();
+        }
+        if let Some(kind) = self.opacity.verdict(self.format.unwrap_or(DetectedType::Binary)) {
+            return Err(OpaquePayload(kind).into());
+        }
+        let sampling = self.reservoir.take().map(|reservoir| {
+            let (records, info) = reservoir.finish();
+            for record in &records {
+                self.examine(record);
+            }
+            info
+        });
+        let fields = self.fields.finish();
+        if let Some(left) = field_encryption::remaining(self.examined, &fields.excluded) {
+            info!(columns = fields.fields.len(), remaining_bytes = left, "Scoring without encrypted columns");
+            for check in self.checks.iter_mut() {
+                check.exclude(&fields.excluded);
+            }
+        }
+        let opts = self.opts;
+        let total_len = opts.source_len.unwrap_or(self.offset);
+        let mut breakdown = QualityBreakdown::default();
+        let mut details: BTreeMap<&'static str, serde_json::Value> = self.composite_error.take().map(|e| ("composite", e)).into_iter().collect();
+        let mut evidence = BTreeMap::new();
+        let mut checks = BTreeMap::new();
+        let total_weight = self.weights.total().max(1) as f64;
+        for check in self.checks.iter_mut() {
+            check.finish();
+            let (name, score) = (check.name(), check.score(total_len));
+            breakdown.set(name, score);
+            let found: BTreeMap<_, _> = check.details().into_iter().collect();
+            details.extend(found.clone());
+            if let Some(artifact) = check.evidence() {
+                evidence.insert(name, artifact);
+            }
+            let weight = self.weights.get(name);
+            checks.insert(name, CheckReport {
+                score,
+                weight,
+                share: round3(weight as f64 / total_weight),
+                minimum: self.config.minimums.get(name).copied(),
+                measures: check.measures(total_len).into_iter().map(|(k, v)| (k, round3(v))).collect(),
+                details: found,
+            });
+        }
+        let preset = opts.category.preset();
+        let score_u8 = preset.calibrate(breakdown.aggregate(&self.weights, self.config.aggregate));
+        let ran: Vec<&str> = self.checks.iter().map(|c| c.name()).collect();
+        let minimums: BTreeMap<String, u32> =
+            self.config.minimums.iter().filter(|(n, _)| ran.contains(&n.as_str())).map(|(n, &m)| (n.clone(), m)).collect();
+        let scoring = ScoringSummary {
+            weights: ran.iter().map(|&n| (n.to_string(), self.weights.get(n))).collect(),
+            aggregate: self.config.aggregate,
+            below_minimum: minimums
+                .iter()
+                .filter(|(n, &m)| breakdown.check(n).is_some_and(|s| s < m))
+                .map(|(n, _)| n.clone())
+                .collect(),
+            minimums,
+            overridden: self.config.overridden,
+            label_column: opts.label_column.clone(),
+            timestamp_column: opts.timestamp_column.clone(),
+            declared_parameters: opts.declared_parameters,
+            json_schema_sha256: opts.contract.as_ref().map(|c| c.sha256().to_string()),
+            plugins: self
+                .config
+                .plugins
+                .iter()
+                .filter(|p| ran.contains(&p.name))
+                .map(|p| (p.name.to_string(), p.sha256.clone()))
+                .collect(),
+        };
+        info!(
+            quality_score = score_u8,
+            category = opts.category.as_str(),
+            format = self.format.map(DetectedType::as_str),
+            checks = self.checks.len(),
+            skip_dedup = opts.skip_dedup,
+            sample_rate_pct = opts.sample_rate_pct,
+            sampled_records = sampling.as_ref().map(|s| s.records_sampled),
+            "Aggregate dataset quality score"
+        );
+        Ok(QualityReport {
+            score: score_u8,
+            format: self.format.unwrap_or(DetectedType::Binary),
+            breakdown,
+            sampling,
+            encrypted_fields: fields.fields,
+            details,
+            evidence,
+            scoring,
+            checks,
+        })
+    }
+
+    fn examine(&mut self, data: &[u8]) {
+        self.examined += data.len() as u64;
+        for check in self.checks.iter_mut() {
+            check.update(data);
+        }
+        self.fields.update(data);
     }
+}
 
-    let diversity = check_data_diversity(data);            // 0..=100
-    let bias = check_bias_indicators(data);                // 0..=100
-    let authenticity = detect_synthetic_patterns(data);    // 0..=100
-    let completeness = check_data_completeness(data);      // 0..=100
-    let consistency = check_metadata_consistency(data);    // 0..=100
+pub fn round3(v: f64) -> f64 {
+    (v * 1000.0).round() / 1000.0
+}
 
-    // Weighted average:
-    // diversity*25 + bias*20 + authenticity*30 + completeness*15 + consistency*10
-    let score = (diversity as u32 * 25
-        + bias as u32 * 20
-        + authenticity as u32 * 30
-        + completeness as u32 * 15
-        + consistency as u32 * 10) / 100;
+// Deterministically keep every k-th chunk so roughly `rate_pct` of the data is examined.
+#[cfg(test)]
+fn sample_chunks(data: &[u8], rate_pct: u8) -> Vec<u8> {
+    sample_ranges(data.len() as u64, rate_pct)
+        .into_iter()
+        .flat_map(|(off, len)| &data[off as usize..(off + len) as usize])
+        .copied()
+        .collect()
+}
 
-    let score_u8 = score.min(100) as u8;
-    info!(quality_score = score_u8, "Aggregate dataset quality score");
-    Ok(score_u8)
+// (offset, len) byte ranges of the chunks a sampled validation reads, so callers can
+// fetch just those ranges from storage instead of the whole blob.
+pub fn sample_ranges(total_len: u64, rate_pct: u8) -> Vec<(u64, u64)> {
+    let stride = 100_u64.div_ceil(rate_pct.clamp(1, 100) as u64);
+    let chunk = SAMPLE_CHUNK as u64;
+    (0..total_len.div_ceil(chunk))
+        .step_by(stride as usize)
+        .map(|i| {
+            let off = i * chunk;
+            (off, chunk.min(total_len - off))
+        })
+        .collect()
 }
 
 // Shannon entropy over byte distribution normalized to 0..=100.
-fn check_data_diversity(data: &[u8]) -> u32 {
+struct Diversity {
     // Frequency of each byte value 0..=255
-    let mut freq = [0usize; 256];
-    for &b in data {
-        freq[b as usize] += 1;
+    freq: [u64; 256],
+    len: u64,
+}
+
+impl Default for Diversity {
+    fn default() -> Self {
+        Self { freq: [0; 256], len: 0 }
+    }
+}
+
+impl QualityCheck for Diversity {
+    fn name(&self) -> &'static str {
+        "diversity"
     }
 
-    let len = data.len() as f64;
-    if len == 0.0 {
-        return 0;
+    fn weight(&self, category: Category) -> u32 {
+        category.preset().weights.diversity
     }
-    // Distinct symbols present
-    let distinct = freq.iter().filter(|&&c| c > 0).count();
-    if distinct <= 1 {
-        return 0;
+
+    fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            self.freq[b as usize] += 1;
+        }
+        self.len += data.len() as u64;
     }
-    // Shannon entropy in bits (max for 256 symbols is log2(256) = 8)
-    let mut entropy = 0.0_f64;
-    for &count in &freq {
-        if count == 0 {
-            continue;
+
+    // Take bytes (by value histogram) back out of the distribution.
+    fn exclude(&mut self, freq: &[u64; 256]) {
+        for (have, &n) in self.freq.iter_mut().zip(freq) {
+            *have = have.saturating_sub(n);
+        }
+        self.len = self.len.saturating_sub(freq.iter().sum());
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        if self.len == 0 {
+            return 0;
+        }
+        // Distinct symbols present
+        let distinct = self.distinct();
+        if distinct <= 1 {
+            return 0;
         }
-        let p = count as f64 / len;
-        entropy -= p * p.log2();
+        // Normalize to 0..=100 relative to the active alphabet size to better reflect diversity
+        let denom = (distinct as f64).log2().max(1.0);
+        (self.entropy() / denom * 100.0).clamp(0.0, 100.0).round() as u32
+    }
+
+    fn measures(&self, _total_len: u64) -> Vec<(&'static str, f64)> {
+        vec![("entropy_bits", self.entropy()), ("distinct_bytes", self.distinct() as f64)]
+    }
+
+    fn evidence(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({ "byte_histogram": self.freq.to_vec() }))
+    }
+}
+
+impl Diversity {
+    fn distinct(&self) -> usize {
+        self.freq.iter().filter(|&&c| c > 0).count()
+    }
+
+    // Shannon entropy in bits (max for 256 symbols is log2(256) = 8)
+    fn entropy(&self) -> f64 {
+        let len = self.len as f64;
+        self.freq.iter().filter(|&&c| c > 0).map(|&c| c as f64 / len).map(|p| -p * p.log2()).sum()
     }
-    // Normalize to 0..=100 relative to the active alphabet size to better reflect diversity
-    let denom = (distinct as f64).log2().max(1.0);
-    let normalized = (entropy / denom * 100.0)
-        .clamp(0.0, 100.0)
-        .round() as u32;
-    normalized
+}
+
+#[cfg(test)]
+fn check_data_diversity(data: &[u8]) -> u32 {
+    let mut d = Diversity::default();
+    d.update(data);
+    d.score(data.len() as u64)
 }
 
 // Variance-based bias indicator.
-// Compute variance of byte values and normalize by the theoretical max variance (~ (255^2)/4).
+// Variance of byte values (merged per chunk, see stream_stats) normalized by the theoretical max
+// (~ (255^2)/4).
+#[derive(Default)]
+struct Bias {
+    moments: Moments,
+}
+
+impl QualityCheck for Bias {
+    fn name(&self) -> &'static str {
+        "bias"
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        category.preset().weights.bias
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for chunk in data.chunks(STREAM_CHUNK) {
+            self.moments.merge(&Moments::of(chunk));
+        }
+    }
+
+    fn exclude(&mut self, freq: &[u64; 256]) {
+        self.moments.remove(&Moments::of_histogram(freq));
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        if self.moments.count() == 0 {
+            return 0;
+        }
+        // Max variance for byte in [0,255] occurs when half 0 and half 255
+        let max_var = (255.0_f64 * 255.0_f64) / 4.0_f64; // ~16256.25
+        (self.moments.variance() / max_var * 100.0).clamp(0.0, 100.0).round() as u32
+    }
+
+    fn measures(&self, _total_len: u64) -> Vec<(&'static str, f64)> {
+        if self.moments.count() == 0 {
+            return Vec::new();
+        }
+        vec![("byte_mean", self.moments.mean()), ("byte_variance", self.moments.variance())]
+    }
+}
+
+#[cfg(test)]
 fn check_bias_indicators(data: &[u8]) -> u32 {
-    if data.is_empty() {
-        return 0;
-    }
-    let len = data.len() as f64;
-    let mean = data.iter().map(|&b| b as f64).sum::<f64>() / len;
-    let var = data
-        .iter()
-        .map(|&b| {
-            let x = b as f64 - mean;
-            x * x
-        })
-        .sum::<f64>()
-        / len;
-    // Max variance for byte in [0,255] occurs when half 0 and half 255
-    let max_var = (255.0_f64 * 255.0_f64) / 4.0_f64; // ~16256.25
-    let norm = (var / max_var * 100.0).clamp(0.0, 100.0);
-    norm.round() as u32
+    let mut b = Bias::default();
+    b.update(data);
+    b.score(data.len() as u64)
 }
 
-// Detect synthetic patterns via repeated rolling windows (4 bytes).
-// High repetition => likely synthetic => lower score.
-fn detect_synthetic_patterns(data: &[u8]) -> u32 {
-    let n = data.len();
-    if n < 8 {
-        // Too short to judge; return mid-range
-        return 50;
+// Detect synthetic patterns via repeated n-grams of several sizes, counted for a bounded sample
+// of them (ChecksConfig::ngrams, see ngram). High repetition => likely synthetic => lower score.
+struct Authenticity {
+    ngrams: NgramRepeats,
+    // Where sampled repeated n-grams lie.
+    repeats: Segments,
+}
+
+impl QualityCheck for Authenticity {
+    fn name(&self) -> &'static str {
+        "authenticity"
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        category.preset().weights.authenticity
     }
-    let window = 4usize;
-    if n < window + 1 {
-        return 50;
+
+    fn update(&mut self, data: &[u8]) {
+        for &b in data {
+            if let Some(n) = self.ngrams.push(b) {
+                // Offsets are in the bytes examined.
+                let end = self.ngrams.bytes();
+                self.repeats.cover(end - n as u64, end);
+            }
+        }
     }
-    let total_windows = n - window + 1;
-    let mut seen = HashSet::with_capacity(total_windows);
-    let mut duplicates = 0usize;
-    for i in 0..total_windows {
-        let slice = &data[i..i + window];
-        if !seen.insert(slice) {
-            duplicates += 1;
+
+    fn score(&self, _total_len: u64) -> u32 {
+        if self.ngrams.bytes() < 2 * self.ngrams.shortest() as u64 {
+            // Too short to judge; return mid-range
+            return 50;
         }
+        (100.0 - (self.ngrams.repetition_ratio() * 100.0).clamp(0.0, 100.0)).round() as u32
+    }
+
+    fn measures(&self, _total_len: u64) -> Vec<(&'static str, f64)> {
+        let repeats = self.ngrams.repeats();
+        vec![
+            ("ngrams", repeats.first().map_or(0, |r| r.ngrams) as f64),
+            ("sampled_ngrams", repeats.iter().map(|r| r.sampled).sum::<u64>() as f64),
+            ("repeated_ngram_ratio", self.ngrams.repetition_ratio()),
+        ]
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.ngrams.state_bytes()
+    }
+
+    fn evidence(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({
+            "repeated_ngrams": self.repeats.to_json(self.ngrams.bytes()),
+            "sizes": self.ngrams.repeats(),
+        }))
+    }
+}
+
+impl Authenticity {
+    fn new(config: &NgramConfig) -> Self {
+        Self { ngrams: NgramRepeats::new(config), repeats: Segments::default() }
+    }
+}
+
+#[cfg(test)]
+fn detect_synthetic_patterns(data: &[u8]) -> u32 {
+    let mut a = Authenticity::new(&NgramConfig::default());
+    a.update(data);
+    a.score(data.len() as u64)
+}
+
+// Completeness based on size thresholds (bytes). Size-based, so it always reflects the full
+// blob rather than the sample.
+// <1KB -> 10, 1KB..10KB -> 50, 10KB..100KB -> 80, >100KB -> 100 by default (see scoring).
+struct Completeness {
+    thresholds: [u64; 3],
+}
+
+impl QualityCheck for Completeness {
+    fn name(&self) -> &'static str {
+        "completeness"
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        category.preset().weights.completeness
+    }
+
+    fn update(&mut self, _data: &[u8]) {}
+
+    fn score(&self, total_len: u64) -> u32 {
+        completeness_for_len(total_len, self.thresholds)
+    }
+
+    fn measures(&self, total_len: u64) -> Vec<(&'static str, f64)> {
+        vec![("bytes", total_len as f64)]
     }
-    let repetition_ratio = (duplicates as f64) / (total_windows as f64);
-    let authenticity = (100.0 - (repetition_ratio * 100.0).clamp(0.0, 100.0))
-        .round() as u32;
-    authenticity
 }
 
-// Completeness based on size thresholds (bytes).
-// <1KB -> 10, 1KB..10KB -> 50, 10KB..100KB -> 80, >100KB -> 100
+#[cfg(test)]
 fn check_data_completeness(data: &[u8]) -> u32 {
-    let sz = data.len();
-    if sz <= 1023 {
+    completeness_for_len(data.len() as u64, DEFAULT_COMPLETENESS_THRESHOLDS)
+}
+
+fn completeness_for_len(sz: u64, [low, mid, high]: [u64; 3]) -> u32 {
+    if sz <= low {
         10
-    } else if sz <= 10 * 1024 {
+    } else if sz <= mid {
         50
-    } else if sz <= 100 * 1024 {
+    } else if sz <= high {
         80
     } else {
         100
@@ -128,14 +1434,106 @@ fn check_data_completeness(data: &[u8]) -> u32 {
 
 // Metadata consistency: proportion of null bytes should be low for typical textual/structured data.
 // Score = (1 - zero_ratio) * 100
-fn check_metadata_consistency(data: &[u8]) -> u32 {
-    if data.is_empty() {
-        return 0;
+#[derive(Default)]
+struct Consistency {
+    zeros: u64,
+    len: u64,
+    nulls: Segments,
+}
+
+impl QualityCheck for Consistency {
+    fn name(&self) -> &'static str {
+        "consistency"
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        category.preset().weights.consistency
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        for (i, &b) in data.iter().enumerate() {
+            self.zeros += (b == 0) as u64;
+            self.nulls.mark(self.len + i as u64, b == 0);
+        }
+        self.len += data.len() as u64;
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        if self.len == 0 {
+            return 0;
+        }
+        let ratio = self.zeros as f64 / self.len as f64;
+        (100.0 * (1.0 - ratio)).clamp(0.0, 100.0).round() as u32
+    }
+
+    fn measures(&self, _total_len: u64) -> Vec<(&'static str, f64)> {
+        vec![("null_byte_ratio", self.zeros as f64 / self.len.max(1) as f64)]
+    }
+
+    fn evidence(&self) -> Option<serde_json::Value> {
+        Some(serde_json::json!({ "null_runs": self.nulls.to_json(self.len) }))
+    }
+}
+
+// Container framing for binary formats: a Parquet file ends with its footer length and magic, an
+// Arrow IPC file repeats its opening magic, a PNG with an IEND chunk, a JPEG with an end-of-image marker, a GIF with its trailer. Intact
+// framing scores 100, a cut-off file 0.
+#[derive(Default)]
+struct Framing {
+    head: Vec<u8>,
+    tail: Vec<u8>,
+}
+
+// Longest trailer checked (PNG's IEND chunk).
+const FRAMING_TAIL: usize = 12;
+
+impl QualityCheck for Framing {
+    fn name(&self) -> &'static str {
+        "consistency"
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        category.preset().weights.consistency
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        if self.head.len() < 8 {
+            self.head.extend_from_slice(&data[..data.len().min(8 - self.head.len())]);
+        }
+        self.tail.extend_from_slice(&data[data.len().saturating_sub(FRAMING_TAIL)..]);
+        self.tail.drain(..self.tail.len().saturating_sub(FRAMING_TAIL));
+    }
+
+    fn score(&self, total_len: u64) -> u32 {
+        let (head, tail) = (self.head.as_slice(), self.tail.as_slice());
+        let intact = if head.starts_with(b"PAR1") {
+            // The footer length before the closing magic must leave room for both magics.
+            let footer_len = tail.len().checked_sub(8).and_then(|at| tail[at..at + 4].try_into().ok()).map(u32::from_le_bytes);
+            tail.ends_with(b"PAR1") && footer_len.is_some_and(|len| len as u64 + 12 <= total_len)
+        } else if head.starts_with(b"ARROW1") {
+            tail.ends_with(b"ARROW1")
+        } else if head.starts_with(b"\x89PNG") {
+            tail.ends_with(b"IEND\xae\x42\x60\x82")
+        } else if head.starts_with(b"\xff\xd8") {
+            tail.ends_with(b"\xff\xd9")
+        } else if head.starts_with(b"GIF8") {
+            tail.ends_with(b";")
+        } else {
+            true
+        };
+        if intact {
+            100
+        } else {
+            0
+        }
     }
-    let zeros = data.iter().filter(|&&b| b == 0).count() as f64;
-    let ratio = zeros / (data.len() as f64);
-    let score = (100.0 * (1.0 - ratio)).clamp(0.0, 100.0);
-    score.round() as u32
+}
+
+#[cfg(test)]
+fn check_metadata_consistency(data: &[u8]) -> u32 {
+    let mut c = Consistency::default();
+    c.update(data);
+    c.score(data.len() as u64)
 }
 
 #[cfg(test)]
@@ -194,9 +1592,99 @@ mod tests {
     #[test]
     fn test_validate_aggregate() {
         let data = (0..8192).map(|i| (i as u8).wrapping_mul(31)).collect::<Vec<_>>();
-        let score = validate_dataset_quality(&data).unwrap();
-        assert!(score <= 100);
+        let report = validate_dataset_quality(&data, &ValidationOptions::default()).unwrap();
+        assert!(report.score <= 100);
+        assert_eq!(report.score, report.breakdown.score(&CheckWeights::default()));
     }
-}
 
+    #[test]
+    fn test_degraded_validation() {
+        let data = (0..64 * 1024).map(|i| (i as u8).wrapping_mul(31)).collect::<Vec<_>>();
+        assert_eq!(sample_chunks(&data, 25).len(), 16 * 1024);
+        assert_eq!(sample_ranges(10_000, 50), vec![(0, 4096), (8192, 1808)]);
+        let sample = SampleSpec { size: 64, seed: 3, chunks: None };
+        let opts = ValidationOptions { skip_dedup: true, sample: Some(sample), ..Default::default() };
+        let report = validate_dataset_quality(&data, &opts).unwrap();
+        assert!(report.score <= 100);
+        assert!(report.breakdown.authenticity.is_none());
+        assert_eq!(report.sampling.unwrap().records_sampled, 64);
+    }
 
+    #[test]
+    fn test_checks_disabled_and_reweighted() {
+        let data: Vec<u8> = (0..800).flat_map(|i| format!("row {} of {}\n", i * 7, i % 13).into_bytes()).collect();
+        let config = ChecksConfig {
+            disabled: vec!["bias".into(), "privacy".into(), "secrets".into()],
+            weights: BTreeMap::from([("consistency".into(), 0), ("completeness".into(), 50)]),
+            minimums: BTreeMap::from([("diversity".into(), 100), ("bias".into(), 100)]),
+            ..Default::default()
+        };
+        let mut acc = QualityAccumulator::new(&ValidationOptions::default(), &config);
+        acc.update(&data);
+        let report = acc.finish().unwrap();
+        let b = &report.breakdown;
+        assert!(b.bias.is_none() && b.check("bias").is_none());
+        let (d, a, c) = (b.diversity.unwrap(), b.authenticity.unwrap(), b.completeness.unwrap());
+        let (x, p) = (b.extra["complexity"], b.extra["poisoning"]);
+        assert_eq!(report.score as u32, (d * 25 + a * 30 + c * 50 + x * 10 + p * 10) / 125);
+        assert_eq!(b.scores().count(), 6);
+        // Only minimums of checks that ran are reported and enforced.
+        assert_eq!(report.scoring.weights["completeness"], 50);
+        assert_eq!(report.scoring.below_minimum, vec!["diversity".to_string()]);
+        assert!(!report.scoring.minimums.contains_key("bias"));
+
+        let config = ChecksConfig { aggregate: Aggregate::Minimum, completeness_thresholds: [1, 2, 3], ..config };
+        let mut acc = QualityAccumulator::new(&ValidationOptions::default(), &config);
+        acc.update(&data);
+        let report = acc.finish().unwrap();
+        assert_eq!(report.breakdown.completeness, Some(100));
+        assert_eq!(report.score as u32, report.breakdown.scores().map(|(n, s)| if n == "consistency" { 100 } else { s }).min().unwrap());
+    }
+
+    #[test]
+    fn test_format_dispatch() {
+        let jsonl: Vec<u8> = (0..200).flat_map(|i| format!("{{\"id\": {}}}\n", i).into_bytes()).collect();
+        let report = validate_dataset_quality(&jsonl, &ValidationOptions::default()).unwrap();
+        assert_eq!(report.format, DetectedType::Jsonl);
+        assert_eq!(report.breakdown.consistency, Some(100));
+        let cut = validate_dataset_quality(&jsonl[..jsonl.len() - 5], &ValidationOptions::default()).unwrap();
+        assert_eq!(cut.details["json"]["status"], "truncated");
+        assert_eq!(cut.details["json"]["records"], 199);
+
+        // Binary containers aren't judged on null bytes, only on whether they're cut off.
+        let mut png = b"\x89PNG\r\n\x1a\n".to_vec();
+        png.extend_from_slice(&[0u8; 2000]);
+        png.extend_from_slice(b"\0\0\0\0IEND\xae\x42\x60\x82");
+        let whole = validate_dataset_quality(&png, &ValidationOptions::default()).unwrap();
+        assert_eq!((whole.format, whole.breakdown.consistency), (DetectedType::Image, Some(100)));
+        let cut = validate_dataset_quality(&png[..1500], &ValidationOptions::default()).unwrap();
+        assert_eq!(cut.breakdown.consistency, Some(0));
+        let mut parquet = b"PAR1".to_vec();
+        parquet.extend_from_slice(&[7u8; 600]);
+        parquet.extend_from_slice(&100u32.to_le_bytes());
+        parquet.extend_from_slice(b"PAR1");
+        let report = validate_dataset_quality(&parquet, &ValidationOptions::default()).unwrap();
+        assert_eq!((report.format, report.breakdown.consistency), (DetectedType::Parquet, Some(100)));
+        let sampled = ValidationOptions { source_len: Some(1 << 20), ..Default::default() };
+        assert!(validate_dataset_quality(&png, &sampled).unwrap().breakdown.consistency.is_none());
+    }
+
+    #[test]
+    fn test_chunked_matches_whole() {
+        let data = (0..50_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 13) as u8 % 97).collect::<Vec<_>>();
+        for opts in [
+            ValidationOptions::default(),
+            ValidationOptions { sample: Some(SampleSpec { size: 500, seed: 7, chunks: None }), ..Default::default() },
+            ValidationOptions { skip_dedup: true, sample: Some(SampleSpec { size: 50, seed: 1, chunks: None }), ..Default::default() },
+        ] {
+            let whole = validate_dataset_quality(&data, &opts).unwrap();
+            let mut acc = QualityAccumulator::new(&opts, &ChecksConfig::default());
+            // Odd chunk sizes so chunks straddle sample boundaries and 4-byte windows.
+            for chunk in data.chunks(1021) {
+                acc.update(chunk);
+            }
+            let chunked = acc.finish().unwrap();
+            assert_eq!(format!("{:?}", chunked.breakdown), format!("{:?}", whole.breakdown));
+        }
+    }
+}
@@ -0,0 +1,386 @@
+use anyhow::{anyhow, bail, Result};
+use ring::rand::{SecureRandom, SystemRandom};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::env;
+use std::future::Future;
+use std::time::Duration;
+
+use crate::estimate::{ScoreEstimate, MAX_CHUNKS};
+use crate::walrus_client::SuiRandomness;
+
+// Record-level sampling for the statistical checks. A sampled verification scores a uniform
+// sample of records rather than every k-th byte chunk, which over-weights long records and cuts
+// records at chunk edges. Records are newline-terminated lines; a run of MAX_RECORD_BYTES without
+// a newline (binary data, huge lines) counts as one record. They are reservoir-sampled
+// (Algorithm R) as they stream, so the sample is uniform without knowing the record count up
+// front, and the generator is seeded: the same seed over the same bytes picks the same records,
+// however the bytes were chunked. Sample size and seed are returned and attested with the result
+// so anyone can reproduce the sample.
+//
+// A seed the caller picks lets a seller steer the sample past bad records, and one the enclave
+// draws is only as trustworthy as the operator. With NAUTILUS_SAMPLE_SEED_SOURCE=sui the seed is
+// instead derived from Sui's on-chain randomness (the Random object) and the blob ID. The latest
+// round is public before a request is sent, so a seller could precompute its sample and resubmit
+// until it misses the bad records: the enclave commits to the request by noting the round current
+// when it arrived, then waits for a later one (beacon_after) and refuses any beacon not newer than
+// the commitment. Both rounds, the bytes and where they were read are attested so anyone can check
+// them against the chain and recompute the seed.
+//
+// For blobs too large to stream, a request can sample chunks instead of records: only the
+// chunks picked are range-fetched, and the score comes with confidence intervals (see estimate).
+
+pub const MAX_RECORD_BYTES: usize = 4096;
+
+// Random seeds stay below 2^53 so they survive JSON clients that parse numbers as doubles.
+const SEED_MASK: u64 = (1 << 53) - 1;
+
+// Domain separator for seeds derived from a randomness beacon.
+const BEACON_SEED_DOMAIN: &[u8] = b"nautilus-sample-seed-v1";
+// How often and how long to poll for a round after the committed one. Sui produces several rounds
+// a second, so the wait is normally one poll.
+const BEACON_POLL: Duration = Duration::from_millis(250);
+const BEACON_WAIT: Duration = Duration::from_secs(30);
+
+// Where sample seeds come from when the caller doesn't give one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SeedSource {
+    // The enclave's OS RNG; callers may pick their own seed.
+    #[default]
+    Local,
+    // Sui on-chain randomness; caller seeds are refused.
+    Sui,
+}
+
+impl SeedSource {
+    pub fn from_env() -> Result<Self> {
+        match env::var("NAUTILUS_SAMPLE_SEED_SOURCE").unwrap_or_default().trim() {
+            "" | "local" => Ok(Self::Local),
+            "sui" => Ok(Self::Sui),
+            other => bail!("Invalid NAUTILUS_SAMPLE_SEED_SOURCE '{}' (expected local or sui)", other),
+        }
+    }
+}
+
+// The randomness round a seed was derived from.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct RandomnessBeacon {
+    pub source: String,
+    // The round current when the request arrived; `round` is a later one.
+    pub committed_round: u64,
+    pub round: u64,
+    pub epoch: u64,
+    pub random_bytes: String,
+    pub object_id: String,
+    pub object_version: u64,
+}
+
+impl RandomnessBeacon {
+    pub fn from_sui(committed_round: u64, r: &SuiRandomness) -> Self {
+        Self {
+            source: "sui-random".into(),
+            committed_round,
+            round: r.round,
+            epoch: r.epoch,
+            random_bytes: hex::encode(&r.bytes),
+            object_id: r.object_id.clone(),
+            object_version: r.object_version,
+        }
+    }
+
+    // First 8 bytes (LE) of SHA-256(domain || random bytes || blob ID), masked like random seeds.
+    pub fn seed(&self, blob_id: &str) -> Result<u64> {
+        let bytes = hex::decode(&self.random_bytes).map_err(|_| anyhow!("beacon bytes are not hex"))?;
+        let digest = Sha256::new()
+            .chain_update(BEACON_SEED_DOMAIN)
+            .chain_update(&bytes)
+            .chain_update(blob_id.as_bytes())
+            .finalize();
+        let mut seed = [0u8; 8];
+        seed.copy_from_slice(&digest[..8]);
+        Ok(u64::from_le_bytes(seed) & SEED_MASK)
+    }
+}
+
+// Polls `latest` for the first round after `committed`, whose bytes didn't exist when the request
+// was committed to.
+pub async fn beacon_after<F, Fut>(committed: u64, latest: F) -> Result<RandomnessBeacon>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<SuiRandomness>>,
+{
+    beacon_within(committed, BEACON_WAIT, latest).await
+}
+
+async fn beacon_within<F, Fut>(committed: u64, wait: Duration, mut latest: F) -> Result<RandomnessBeacon>
+where
+    F: FnMut() -> Fut,
+    Fut: Future<Output = Result<SuiRandomness>>,
+{
+    let deadline = tokio::time::Instant::now() + wait;
+    loop {
+        let randomness = latest().await?;
+        if randomness.round > committed {
+            return Ok(RandomnessBeacon::from_sui(committed, &randomness));
+        }
+        anyhow::ensure!(
+            tokio::time::Instant::now() < deadline,
+            "no Sui randomness round after {} within {}s",
+            committed,
+            wait.as_secs()
+        );
+        tokio::time::sleep(BEACON_POLL).await;
+    }
+}
+
+// Caller-requested sampling; unset fields fall back to the deployment default and a fresh seed.
+#[derive(Debug, Clone, Copy, Default, Deserialize)]
+pub struct SampleRequest {
+    #[serde(default)]
+    pub size: Option<usize>,
+    #[serde(default)]
+    pub seed: Option<u64>,
+    // Sample this many random chunks of the blob rather than records (see estimate).
+    #[serde(default)]
+    pub chunks: Option<usize>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SampleSpec {
+    // Records kept.
+    pub size: usize,
+    pub seed: u64,
+    // Chunks fetched instead, for a chunk sample.
+    pub chunks: Option<usize>,
+}
+
+impl SampleSpec {
+    pub fn resolve(request: Option<SampleRequest>, default_size: usize) -> Result<Self> {
+        let request = request.unwrap_or_default();
+        let size = request.size.unwrap_or(default_size);
+        anyhow::ensure!(size > 0, "sample size must be at least 1");
+        let seed = match request.seed {
+            Some(seed) => seed,
+            None => random_seed()?,
+        };
+        Ok(Self { size, seed, chunks: checked_chunks(request.chunks)? })
+    }
+
+    // Seeded from a randomness beacon for `blob_id`; a caller-chosen seed is refused.
+    pub fn from_beacon(
+        request: Option<SampleRequest>,
+        default_size: usize,
+        beacon: &RandomnessBeacon,
+        blob_id: &str,
+    ) -> Result<Self> {
+        let request = request.unwrap_or_default();
+        anyhow::ensure!(request.seed.is_none(), "sample seeds come from on-chain randomness on this deployment");
+        anyhow::ensure!(
+            beacon.round > beacon.committed_round,
+            "randomness round {} is not after the committed round {}",
+            beacon.round,
+            beacon.committed_round
+        );
+        let size = request.size.unwrap_or(default_size);
+        anyhow::ensure!(size > 0, "sample size must be at least 1");
+        Ok(Self { size, seed: beacon.seed(blob_id)?, chunks: checked_chunks(request.chunks)? })
+    }
+}
+
+fn checked_chunks(chunks: Option<usize>) -> Result<Option<usize>> {
+    if let Some(n) = chunks {
+        anyhow::ensure!((1..=MAX_CHUNKS).contains(&n), "sample chunks must be 1 to {}", MAX_CHUNKS);
+    }
+    Ok(chunks)
+}
+
+// What was sampled; reported and attested with a sampled result.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct SampleInfo {
+    pub method: String,
+    pub seed: u64,
+    // Reservoir capacity requested, and records actually scored (fewer for small datasets). For
+    // method "chunks": chunks requested, fetched, and in the blob.
+    pub sample_size: usize,
+    pub records_sampled: u64,
+    pub records_seen: u64,
+    // Randomness round the seed was derived from; absent for caller or enclave seeds.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub beacon: Option<RandomnessBeacon>,
+    // Score and per-check confidence intervals from a chunk sample.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub estimate: Option<ScoreEstimate>,
+}
+
+// Uniform sample of the records in a byte stream fed in chunks of any size.
+pub struct Reservoir {
+    spec: SampleSpec,
+    rng: u64,
+    // (record index, record), in replacement order until `finish`.
+    kept: Vec<(u64, Vec<u8>)>,
+    kept_bytes: u64,
+    seen: u64,
+    partial: Vec<u8>,
+}
+
+impl Reservoir {
+    pub fn new(spec: SampleSpec) -> Self {
+        Self { spec, rng: spec.seed, kept: Vec::new(), kept_bytes: 0, seen: 0, partial: Vec::new() }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        let mut rest = data;
+        while !rest.is_empty() {
+            let window = &rest[..rest.len().min(MAX_RECORD_BYTES - self.partial.len())];
+            let take = window.iter().position(|&b| b == b'\n').map_or(window.len(), |i| i + 1);
+            self.partial.extend_from_slice(&rest[..take]);
+            rest = &rest[take..];
+            if self.partial.last() == Some(&b'\n') || self.partial.len() == MAX_RECORD_BYTES {
+                let record = std::mem::take(&mut self.partial);
+                self.offer(record);
+            }
+        }
+    }
+
+    // Approximate heap held by the sample, for job accounting.
+    pub fn state_bytes(&self) -> u64 {
+        self.kept_bytes + self.partial.capacity() as u64
+    }
+
+    // The sampled records in stream order.
+    pub fn finish(mut self) -> (Vec<Vec<u8>>, SampleInfo) {
+        if !self.partial.is_empty() {
+            let record = std::mem::take(&mut self.partial);
+            self.offer(record);
+        }
+        self.kept.sort_unstable_by_key(|(idx, _)| *idx);
+        let info = SampleInfo {
+            method: "reservoir".into(),
+            seed: self.spec.seed,
+            sample_size: self.spec.size,
+            records_sampled: self.kept.len() as u64,
+            records_seen: self.seen,
+            beacon: None,
+            estimate: None,
+        };
+        (self.kept.into_iter().map(|(_, record)| record).collect(), info)
+    }
+
+    fn offer(&mut self, record: Vec<u8>) {
+        let idx = self.seen;
+        self.seen += 1;
+        let slot = if (idx as usize) < self.spec.size {
+            self.kept.push((idx, Vec::new()));
+            self.kept.len() - 1
+        } else {
+            match below(&mut self.rng, idx + 1) as usize {
+                j if j < self.spec.size => {
+                    self.kept_bytes -= self.kept[j].1.len() as u64;
+                    j
+                }
+                _ => return,
+            }
+        };
+        self.kept_bytes += record.len() as u64;
+        self.kept[slot] = (idx, record);
+    }
+
+}
+
+// Uniform-enough draw from 0..n (SplitMix64, multiply-shift reduction).
+pub fn below(rng: &mut u64, n: u64) -> u64 {
+    *rng = rng.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    let mut z = *rng;
+    z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    z ^= z >> 31;
+    ((z as u128 * n as u128) >> 64) as u64
+}
+
+fn random_seed() -> Result<u64> {
+    let mut buf = [0u8; 8];
+    SystemRandom::new().fill(&mut buf).map_err(|_| anyhow!("OS randomness unavailable"))?;
+    Ok(u64::from_le_bytes(buf) & SEED_MASK)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reservoir_is_uniform_and_reproducible() {
+        // Record lengths vary 1..=400 bytes; byte-chunk sampling would favour the long ones.
+        let data: Vec<u8> = (0..5000u32)
+            .flat_map(|i| {
+                let mut line = vec![b'x'; (i % 400) as usize];
+                line.push(b'\n');
+                line
+            })
+            .collect();
+        let spec = SampleSpec { size: 1000, seed: 42, chunks: None };
+        let sample = |chunk: usize| {
+            let mut r = Reservoir::new(spec);
+            for c in data.chunks(chunk) {
+                r.update(c);
+            }
+            r.finish()
+        };
+        let (records, info) = sample(777);
+        assert_eq!(sample(4096).0, records);
+        assert_eq!((info.records_seen, info.records_sampled, info.seed), (5000, 1000, 42));
+        let mean_len = records.iter().map(|r| r.len() as f64).sum::<f64>() / records.len() as f64;
+        assert!((mean_len - 200.5).abs() < 15.0, "mean record length {}", mean_len);
+
+        let mut other = Reservoir::new(SampleSpec { seed: 43, ..spec });
+        other.update(&data);
+        assert_ne!(other.finish().0, records);
+        // Runs without a newline are cut into bounded records.
+        let mut binary = Reservoir::new(spec);
+        binary.update(&vec![7u8; MAX_RECORD_BYTES * 2 + 1]);
+        assert_eq!(binary.finish().1.records_seen, 3);
+        assert!(SampleSpec::resolve(Some(SampleRequest { size: Some(0), ..Default::default() }), 10).is_err());
+        assert!(SampleSpec::resolve(Some(SampleRequest { chunks: Some(0), ..Default::default() }), 10).is_err());
+
+        // Beacon seeds depend on the round's bytes and the blob, and can't be overridden.
+        let mut beacon = RandomnessBeacon {
+            source: "sui-random".into(),
+            committed_round: 8,
+            round: 9,
+            epoch: 1,
+            random_bytes: "0a0b0c".into(),
+            object_id: "0x9f".into(),
+            object_version: 3,
+        };
+        let seeded = SampleSpec::from_beacon(None, 10, &beacon, "blob-a").unwrap();
+        assert_eq!(seeded, SampleSpec::from_beacon(None, 10, &beacon, "blob-a").unwrap());
+        assert_ne!(seeded.seed, beacon.seed("blob-b").unwrap());
+        assert!(seeded.seed <= SEED_MASK);
+        assert!(SampleSpec::from_beacon(Some(SampleRequest { seed: Some(1), ..Default::default() }), 10, &beacon, "a").is_err());
+        // A round that was already out when the request arrived is refused.
+        beacon.committed_round = 9;
+        assert!(SampleSpec::from_beacon(None, 10, &beacon, "blob-a").is_err());
+    }
+
+    #[tokio::test]
+    async fn test_beacon_waits_for_a_later_round() {
+        let randomness = |round| SuiRandomness {
+            round,
+            epoch: 1,
+            bytes: vec![round as u8],
+            object_id: "0x8".into(),
+            object_version: round,
+        };
+        let mut rounds = [7, 7, 8].into_iter();
+        let beacon = beacon_within(7, BEACON_WAIT, || {
+            let round = rounds.next().unwrap();
+            async move { Ok(randomness(round)) }
+        })
+        .await
+        .unwrap();
+        assert_eq!((beacon.committed_round, beacon.round, beacon.random_bytes.as_str()), (7, 8, "08"));
+        // The chain stalling past the wait is an error, not a stale seed.
+        assert!(beacon_within(7, Duration::ZERO, || async { Ok(randomness(7)) }).await.is_err());
+    }
+}
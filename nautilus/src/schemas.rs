@@ -0,0 +1,189 @@
+use anyhow::{bail, Context, Result};
+use schemars::schema_for;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::tee_attestation::AttestationEnvelope;
+
+// Published schemas for what clients parse. The JSON Schema is derived from the Rust types
+// (schemars) and the TypeScript declarations from that schema, so neither can drift from the code
+// that serializes the envelope. GET /schemas lists them and GET /schemas/<name>.json or
+// /schemas/<name>.d.ts serves them; `zkdatavault-nautilus schemas <dir>` writes the same files,
+// and the copies checked in under nautilus/schemas are tested against the types.
+
+const NAMES: &[&str] = &["attestation-envelope"];
+
+fn schema(name: &str) -> Option<Value> {
+    let root = match name {
+        "attestation-envelope" => schema_for!(AttestationEnvelope),
+        _ => return None,
+    };
+    serde_json::to_value(root).ok()
+}
+
+// Body and content type of a published file, e.g. "attestation-envelope.d.ts".
+pub fn render(file: &str) -> Option<(String, &'static str)> {
+    if let Some(name) = file.strip_suffix(".json") {
+        let json = serde_json::to_string_pretty(&schema(name)?).ok()?;
+        Some((json + "\n", "application/schema+json"))
+    } else if let Some(name) = file.strip_suffix(".d.ts") {
+        Some((typescript(&schema(name)?), "application/typescript; charset=utf-8"))
+    } else {
+        None
+    }
+}
+
+pub fn index() -> Value {
+    let schemas: Vec<Value> = NAMES
+        .iter()
+        .map(|name| {
+            serde_json::json!({
+                "name": name,
+                "json_schema": format!("/schemas/{}.json", name),
+                "typescript": format!("/schemas/{}.d.ts", name),
+            })
+        })
+        .collect();
+    serde_json::json!({ "schemas": schemas })
+}
+
+fn files() -> impl Iterator<Item = String> {
+    NAMES.iter().flat_map(|name| [format!("{}.json", name), format!("{}.d.ts", name)])
+}
+
+// `zkdatavault-nautilus schemas <dir>`: write every published file into <dir>.
+pub fn run_cli(args: &[String]) -> Result<()> {
+    let [dir] = args else {
+        bail!("usage: zkdatavault-nautilus schemas <dir>");
+    };
+    let dir = Path::new(dir);
+    std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    for file in files() {
+        let (body, _) = render(&file).context("render schema")?;
+        let path = dir.join(&file);
+        std::fs::write(&path, body).with_context(|| format!("write {}", path.display()))?;
+        println!("wrote {}", path.display());
+    }
+    Ok(())
+}
+
+// TypeScript declarations for a root schema: the root type and each of its definitions.
+fn typescript(root: &Value) -> String {
+    let mut out = String::from("// Generated from the Rust types by `zkdatavault-nautilus schemas`; do not edit.\n");
+    let title = root.get("title").and_then(Value::as_str).unwrap_or("Root");
+    out += &declaration(title, root);
+    if let Some(defs) = root.get("definitions").and_then(Value::as_object) {
+        for (name, def) in defs {
+            out += &declaration(name, def);
+        }
+    }
+    out
+}
+
+fn declaration(name: &str, schema: &Value) -> String {
+    match schema.get("properties") {
+        Some(_) => format!("\nexport interface {} {}\n", name, object_type(schema)),
+        None => format!("\nexport type {} = {};\n", name, ts_type(schema)),
+    }
+}
+
+fn ts_type(schema: &Value) -> String {
+    if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+        return reference.rsplit('/').next().unwrap_or("unknown").to_string();
+    }
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return values.iter().map(Value::to_string).collect::<Vec<_>>().join(" | ");
+    }
+    for key in ["anyOf", "oneOf"] {
+        if let Some(variants) = schema.get(key).and_then(Value::as_array) {
+            return union(variants.iter().map(ts_type));
+        }
+    }
+    if let Some([only]) = schema.get("allOf").and_then(Value::as_array).map(Vec::as_slice) {
+        return ts_type(only);
+    }
+    let types: Vec<&str> = match schema.get("type") {
+        Some(Value::String(t)) => vec![t.as_str()],
+        Some(Value::Array(ts)) => ts.iter().filter_map(Value::as_str).collect(),
+        _ => return "unknown".into(),
+    };
+    union(types.into_iter().map(|t| match t {
+        "string" => "string".into(),
+        "integer" | "number" => "number".into(),
+        "boolean" => "boolean".into(),
+        "null" => "null".into(),
+        "array" => match schema.get("items") {
+            Some(Value::Array(items)) => format!("[{}]", items.iter().map(ts_type).collect::<Vec<_>>().join(", ")),
+            Some(items) => format!("{}[]", parenthesized(ts_type(items))),
+            None => "unknown[]".into(),
+        },
+        "object" => object_type(schema),
+        _ => "unknown".into(),
+    }))
+}
+
+fn object_type(schema: &Value) -> String {
+    let Some(props) = schema.get("properties").and_then(Value::as_object) else {
+        return match schema.get("additionalProperties") {
+            Some(values @ Value::Object(_)) => format!("Record<string, {}>", ts_type(values)),
+            _ => "Record<string, unknown>".into(),
+        };
+    };
+    let required: Vec<&str> = schema
+        .get("required")
+        .and_then(Value::as_array)
+        .map(|r| r.iter().filter_map(Value::as_str).collect())
+        .unwrap_or_default();
+    let mut out = String::from("{\n");
+    for (name, prop) in props {
+        let key = if name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') { name.clone() } else { Value::from(name.as_str()).to_string() };
+        let optional = if required.contains(&name.as_str()) { "" } else { "?" };
+        out += &format!("  {}{}: {};\n", key, optional, ts_type(prop));
+    }
+    out + "}"
+}
+
+fn union(parts: impl Iterator<Item = String>) -> String {
+    let mut parts: Vec<String> = parts.collect();
+    parts.dedup();
+    parts.join(" | ")
+}
+
+fn parenthesized(ty: String) -> String {
+    if ty.contains(" | ") {
+        format!("({})", ty)
+    } else {
+        ty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_published_files_match_types() {
+        // Regenerate with `cargo run -- schemas schemas` when the envelope changes.
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("schemas");
+        for file in files() {
+            let published = std::fs::read_to_string(dir.join(&file)).unwrap();
+            assert_eq!(published, render(&file).unwrap().0, "schemas/{} is out of date", file);
+        }
+
+        // Every field a serialized payload carries is described.
+        let schema = schema("attestation-envelope").unwrap();
+        let data_props = &schema["definitions"]["AttestationData"]["properties"];
+        let data: crate::tee_attestation::AttestationData = serde_json::from_value(serde_json::json!({
+            "blob_id": "b", "quality_score": 80, "timestamp": 1, "enclave_measurement": "m",
+            "screening": { "mode": "flag", "flagged": false },
+        }))
+        .unwrap();
+        for key in serde_json::to_value(&data).unwrap().as_object().unwrap().keys() {
+            assert!(data_props.get(key).is_some(), "{} missing from the schema", key);
+        }
+        let ts = render("attestation-envelope.d.ts").unwrap().0;
+        assert!(ts.contains("export interface AttestationEnvelope {\n") && ts.contains("  data: AttestationData;\n"));
+        assert!(ts.contains("export type ScreeningMode = \"off\" | \"flag\" | \"fail\";"));
+        assert!(render("attestation-envelope.xml").is_none() && render("other.json").is_none());
+    }
+}
@@ -0,0 +1,208 @@
+use anyhow::{bail, Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+// How per-check scores combine into the aggregate. Operators set the weights, per-check minimums,
+// the aggregate formula and the completeness size thresholds in NAUTILUS_SCORING_FILE (JSON);
+// NAUTILUS_CHECK_WEIGHTS still overrides individual weights on top of it. The file may also open
+// a bounded range per setting to callers: a verification request can then carry `scoring`
+// overrides, and values outside those bounds are refused. The weights, minimums and formula a
+// score was computed with are part of the signed attestation payload.
+//
+//   {
+//     "weights": { "authenticity": 40 },
+//     "minimums": { "privacy": 60 },
+//     "aggregate": "weighted_mean",
+//     "completeness_thresholds": [1023, 10240, 102400],
+//     "overrides": {
+//       "weights": { "authenticity": [20, 60] },
+//       "minimums": { "privacy": [50, 100] },
+//       "aggregates": ["weighted_mean", "minimum"]
+//     }
+//   }
+
+// Completeness scores 10, 50, 80 and 100 for datasets at most, and above, these sizes in bytes.
+pub const DEFAULT_COMPLETENESS_THRESHOLDS: [u64; 3] = [1023, 10 * 1024, 100 * 1024];
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Aggregate {
+    // Weighted arithmetic mean; a weak check is offset by strong ones.
+    #[default]
+    WeightedMean,
+    // Weighted geometric mean; one weak check drags the aggregate down much further.
+    WeightedGeometric,
+    // The weakest check with a non-zero weight.
+    Minimum,
+}
+
+impl Aggregate {
+    // Combine (score, weight) pairs; 0 when nothing carries weight.
+    pub fn combine(self, scores: impl Iterator<Item = (u32, u32)>) -> u8 {
+        let scores: Vec<(u32, u32)> = scores.filter(|&(_, w)| w > 0).collect();
+        let total_weight: u32 = scores.iter().map(|&(_, w)| w).sum();
+        if total_weight == 0 {
+            return 0;
+        }
+        let combined = match self {
+            Aggregate::WeightedMean => scores.iter().map(|&(s, w)| s * w).sum::<u32>() / total_weight,
+            Aggregate::WeightedGeometric if scores.iter().any(|&(s, _)| s == 0) => 0,
+            Aggregate::WeightedGeometric => {
+                let log_sum: f64 = scores.iter().map(|&(s, w)| w as f64 * (s as f64).ln()).sum();
+                // Rounded so that equal scores give back exactly that score.
+                (log_sum / total_weight as f64).exp().round() as u32
+            }
+            Aggregate::Minimum => scores.iter().map(|&(s, _)| s).min().unwrap_or(0),
+        };
+        combined.min(100) as u8
+    }
+}
+
+// Ranges callers may move settings within; anything not listed can't be overridden.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OverrideBounds {
+    pub weights: BTreeMap<String, (u32, u32)>,
+    pub minimums: BTreeMap<String, (u32, u32)>,
+    pub aggregates: Vec<Aggregate>,
+}
+
+impl OverrideBounds {
+    pub fn is_empty(&self) -> bool {
+        self.weights.is_empty() && self.minimums.is_empty() && self.aggregates.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringFile {
+    pub weights: BTreeMap<String, u32>,
+    pub minimums: BTreeMap<String, u32>,
+    pub aggregate: Aggregate,
+    pub completeness_thresholds: Option<[u64; 3]>,
+    pub overrides: OverrideBounds,
+}
+
+impl ScoringFile {
+    pub fn load(path: &Path) -> Result<Self> {
+        let raw = std::fs::read(path).with_context(|| format!("read {}", path.display()))?;
+        let file: Self = serde_json::from_slice(&raw).with_context(|| format!("parse {}", path.display()))?;
+        if let Some(t) = file.completeness_thresholds {
+            if !(t[0] < t[1] && t[1] < t[2]) {
+                bail!("{}: completeness_thresholds must be increasing", path.display());
+            }
+        }
+        if let Some((name, _)) = file.minimums.iter().find(|(_, &m)| m > 100) {
+            bail!("{}: minimum for '{}' is above 100", path.display(), name);
+        }
+        let bounds = &file.overrides;
+        if let Some((name, _)) = bounds.weights.iter().chain(&bounds.minimums).find(|(_, (lo, hi))| lo > hi) {
+            bail!("{}: override bounds for '{}' are inverted", path.display(), name);
+        }
+        Ok(file)
+    }
+
+    // Check names the file mentions, to be matched against the registry.
+    pub fn names(&self) -> impl Iterator<Item = &String> {
+        let bounds = &self.overrides;
+        self.weights.keys().chain(self.minimums.keys()).chain(bounds.weights.keys()).chain(bounds.minimums.keys())
+    }
+}
+
+// Per-request settings, applied when within the operator's `OverrideBounds`.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoringOverrides {
+    pub weights: BTreeMap<String, u32>,
+    pub minimums: BTreeMap<String, u32>,
+    pub aggregate: Option<Aggregate>,
+}
+
+impl ScoringOverrides {
+    pub fn check(&self, bounds: &OverrideBounds) -> Result<()> {
+        if bounds.is_empty() {
+            bail!("this deployment doesn't accept scoring overrides");
+        }
+        let within = |kind: &str, values: &BTreeMap<String, u32>, bounds: &BTreeMap<String, (u32, u32)>| {
+            for (name, value) in values {
+                match bounds.get(name) {
+                    Some((lo, hi)) if (lo..=hi).contains(&value) => {}
+                    Some((lo, hi)) => bail!("scoring {} for '{}' must be within {}..={}", kind, name, lo, hi),
+                    None => bail!("scoring {} for '{}' can't be overridden", kind, name),
+                }
+            }
+            Ok(())
+        };
+        within("weight", &self.weights, &bounds.weights)?;
+        within("minimum", &self.minimums, &bounds.minimums)?;
+        if let Some(aggregate) = self.aggregate {
+            if !bounds.aggregates.contains(&aggregate) {
+                bail!("aggregate {:?} can't be requested here", aggregate);
+            }
+        }
+        Ok(())
+    }
+}
+
+// What a score was computed with; signed with it.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScoringSummary {
+    // Weights of the checks that ran.
+    pub weights: BTreeMap<String, u32>,
+    pub aggregate: Aggregate,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub minimums: BTreeMap<String, u32>,
+    // Checks that scored below their minimum; the verification fails regardless of the aggregate.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub below_minimum: Vec<String>,
+    // Set when the request's overrides were applied.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub overridden: bool,
+    // Label column whose class balance counted toward bias, as the request named it.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label_column: Option<String>,
+    // Timestamp column whose series counted toward consistency and freshness (see timeseries).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub timestamp_column: Option<String>,
+    // Parameter count the request declared for a model artifact (see model_artifact).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub declared_parameters: Option<u64>,
+    // Canonical SHA-256 of the JSON Schema records were validated against (see data_contract).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub json_schema_sha256: Option<String>,
+    // SHA-256 of each operator plugin module that scored, by check name (see plugins).
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugins: BTreeMap<String, String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_aggregates_and_bounds() {
+        let scores = [(90, 30), (40, 10), (80, 0)];
+        assert_eq!(Aggregate::WeightedMean.combine(scores.into_iter()), 77);
+        assert_eq!(Aggregate::WeightedGeometric.combine(scores.into_iter()), 73);
+        assert_eq!(Aggregate::Minimum.combine(scores.into_iter()), 40);
+        assert_eq!(Aggregate::WeightedGeometric.combine([(70, 5), (70, 9)].into_iter()), 70);
+        assert_eq!(Aggregate::WeightedGeometric.combine([(0, 1), (100, 9)].into_iter()), 0);
+
+        let bounds = OverrideBounds {
+            weights: BTreeMap::from([("authenticity".into(), (20, 60))]),
+            aggregates: vec![Aggregate::Minimum],
+            ..Default::default()
+        };
+        let mut req = ScoringOverrides { weights: BTreeMap::from([("authenticity".into(), 45)]), ..Default::default() };
+        assert!(req.check(&bounds).is_ok());
+        assert!(req.check(&OverrideBounds::default()).is_err());
+        req.weights.insert("authenticity".into(), 61);
+        assert!(req.check(&bounds).is_err());
+        req.weights = BTreeMap::from([("bias".into(), 20)]);
+        assert!(req.check(&bounds).is_err());
+        let req = ScoringOverrides { aggregate: Some(Aggregate::WeightedGeometric), ..Default::default() };
+        assert!(req.check(&bounds).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use anyhow::{Context, Result};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::env;
+use std::path::PathBuf;
+use tracing::{info, warn};
+
+use crate::metrics;
+
+// Executable and malware screening of dataset payloads. The decrypted stream is scanned for
+// embedded binaries (ELF, PE, Mach-O) and macro-carrying Office documents anywhere in the blob,
+// not just at offset 0, and the whole-blob SHA-256 is checked against a list of known-bad
+// hashes (NAUTILUS_BAD_HASHES_FILE, one hex digest per line). Findings are counted and reported
+// with the result; in `fail` mode any finding refuses the verification instead. The mode comes
+// from NAUTILUS_SCREENING (off | flag | fail, default flag) and can be overridden per tenant in
+// its content policy.
+
+// Bytes kept back between chunks so signatures that need to look ahead (a PE header sits up to
+// 1 KiB past its MZ stub) are still matched across chunk boundaries.
+const LOOKAHEAD: usize = 0x400 + 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ScreeningMode {
+    Off,
+    #[default]
+    Flag,
+    Fail,
+}
+
+#[derive(Debug, Clone)]
+pub struct ScreeningConfig {
+    pub mode: ScreeningMode,
+    pub bad_hashes_file: Option<PathBuf>,
+}
+
+impl ScreeningConfig {
+    pub fn from_env() -> Self {
+        let mode = match env::var("NAUTILUS_SCREENING").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "off" => ScreeningMode::Off,
+            "fail" => ScreeningMode::Fail,
+            _ => ScreeningMode::Flag,
+        };
+        Self {
+            mode,
+            bad_hashes_file: env::var("NAUTILUS_BAD_HASHES_FILE").ok().filter(|v| !v.is_empty()).map(PathBuf::from),
+        }
+    }
+}
+
+// Known-bad SHA-256 digests, loaded once at startup.
+#[derive(Default)]
+pub struct BadHashes(HashSet<String>);
+
+impl BadHashes {
+    pub fn load(config: &ScreeningConfig) -> Result<Self> {
+        let Some(path) = &config.bad_hashes_file else {
+            return Ok(Self::default());
+        };
+        let raw = std::fs::read_to_string(path).with_context(|| format!("read {}", path.display()))?;
+        let mut hashes = HashSet::new();
+        for line in raw.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let digest = line.split_whitespace().next().unwrap_or_default().to_ascii_lowercase();
+            anyhow::ensure!(
+                digest.len() == 64 && digest.bytes().all(|b| b.is_ascii_hexdigit()),
+                "{}: '{}' is not a SHA-256 hex digest",
+                path.display(),
+                line
+            );
+            hashes.insert(digest);
+        }
+        info!(path = %path.display(), hashes = hashes.len(), "Loaded known-bad hash list");
+        Ok(Self(hashes))
+    }
+
+    pub fn contains(&self, sha256_hex: &str) -> bool {
+        self.0.contains(&sha256_hex.to_ascii_lowercase())
+    }
+}
+
+// What screening found; reported with the result and part of the attested payload.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ScreeningReport {
+    pub mode: ScreeningMode,
+    // Embedded executables and macro documents by kind ("elf", "pe", "mach_o", "macro_document").
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub findings: BTreeMap<String, u64>,
+    // Whole-blob digest is on the known-bad list; unknown (false) when only a sample was scanned.
+    #[serde(default)]
+    pub known_bad_hash: bool,
+    pub flagged: bool,
+}
+
+// The payload failed screening under `fail` mode.
+#[derive(Debug, thiserror::Error)]
+#[error("PAYLOAD_SCREENING_FAILED: {0}")]
+pub struct ScreeningFailed(pub String);
+
+// Scans a blob's plaintext as it streams past.
+pub struct Screener {
+    mode: ScreeningMode,
+    buf: Vec<u8>,
+    findings: BTreeMap<String, u64>,
+}
+
+impl Screener {
+    pub fn new(mode: ScreeningMode) -> Self {
+        Self { mode, buf: Vec::new(), findings: BTreeMap::new() }
+    }
+
+    pub fn update(&mut self, plaintext: &[u8]) {
+        self.buf.extend_from_slice(plaintext);
+        let settled = self.buf.len().saturating_sub(LOOKAHEAD);
+        self.scan(settled);
+        self.buf.drain(..settled);
+    }
+
+    // `sha256_hex` is the whole-blob digest, when the whole blob was scanned.
+    pub fn finish(mut self, sha256_hex: Option<&str>, bad_hashes: &BadHashes) -> Result<ScreeningReport, ScreeningFailed> {
+        self.scan(self.buf.len());
+        let known_bad_hash = sha256_hex.is_some_and(|h| bad_hashes.contains(h));
+        let flagged = known_bad_hash || !self.findings.is_empty();
+        for (kind, count) in &self.findings {
+            metrics::add_counter(
+                "nautilus_screening_findings_total",
+                "Embedded executables and macro documents found by screening",
+                &[("kind", kind)],
+                *count as f64,
+            );
+        }
+        if known_bad_hash {
+            metrics::inc_counter("nautilus_screening_known_bad_total", "Blobs matching the known-bad hash list", &[]);
+        }
+        let report = ScreeningReport { mode: self.mode, findings: self.findings, known_bad_hash, flagged };
+        if flagged {
+            warn!(findings = ?report.findings, known_bad_hash, mode = ?self.mode, "Payload screening flagged the blob");
+            if self.mode == ScreeningMode::Fail {
+                let mut reasons: Vec<String> = report.findings.iter().map(|(k, n)| format!("{} x{}", k, n)).collect();
+                if known_bad_hash {
+                    reasons.push("known-bad hash".into());
+                }
+                return Err(ScreeningFailed(reasons.join(", ")));
+            }
+        }
+        Ok(report)
+    }
+
+    // Match signatures starting before `end`; bytes after it serve only as lookahead.
+    fn scan(&mut self, end: usize) {
+        let buf = &self.buf;
+        let mut hits = Vec::new();
+        for at in 0..end {
+            if let Some(kind) = signature_at(buf, at) {
+                hits.push(kind);
+            }
+        }
+        for kind in hits {
+            *self.findings.entry(kind.to_string()).or_default() += 1;
+        }
+    }
+}
+
+fn signature_at(buf: &[u8], at: usize) -> Option<&'static str> {
+    let rest = &buf[at..];
+    let u32_le = |off: usize| rest.get(off..off + 4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]));
+    match rest.first()? {
+        // ELF with a valid class and byte order.
+        0x7f if rest.len() >= 6 && &rest[1..4] == b"ELF" && matches!(rest[4], 1 | 2) && matches!(rest[5], 1 | 2) => {
+            Some("elf")
+        }
+        // DOS stub whose e_lfanew points at a PE signature.
+        b'M' if rest.get(1) == Some(&b'Z') => {
+            let lfanew = u32_le(0x3c)? as usize;
+            ((0x40..0x400).contains(&lfanew) && rest.get(lfanew..lfanew + 4) == Some(b"PE\0\0")).then_some("pe")
+        }
+        // Little-endian Mach-O with a known CPU type.
+        0xce | 0xcf if rest.get(1..4) == Some(&[0xfa, 0xed, 0xfe]) => {
+            matches!(u32_le(4)?, 7 | 12 | 0x0100_0007 | 0x0100_000c).then_some("mach_o")
+        }
+        // VBA project inside an OOXML zip (stored name) or an OLE2 directory (UTF-16 name).
+        b'v' if rest.starts_with(b"vbaProject.bin") => Some("macro_document"),
+        b'_' if rest.starts_with(b"_\0V\0B\0A\0_\0P\0R\0O\0J\0E\0C\0T\0") => Some("macro_document"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pe_stub() -> Vec<u8> {
+        let mut pe = vec![0u8; 0x100];
+        pe[..2].copy_from_slice(b"MZ");
+        pe[0x3c..0x40].copy_from_slice(&0x80u32.to_le_bytes());
+        pe[0x80..0x84].copy_from_slice(b"PE\0\0");
+        pe
+    }
+
+    #[test]
+    fn test_embedded_binaries_found_across_chunks() {
+        let mut payload = b"id,text\n1,hello\n".repeat(200);
+        payload.extend_from_slice(&pe_stub());
+        payload.extend_from_slice(b"\x7fELF\x02\x01\x01\0");
+        payload.extend_from_slice(b"word/vbaProject.bin");
+        payload.extend_from_slice(b"MZ not a real header");
+        payload.extend_from_slice(&b"more,rows\n".repeat(200));
+
+        let mut screener = Screener::new(ScreeningMode::Flag);
+        for chunk in payload.chunks(37) {
+            screener.update(chunk);
+        }
+        let bad = BadHashes(HashSet::from(["ab".repeat(32)]));
+        let report = screener.finish(Some(&"AB".repeat(32)), &bad).unwrap();
+        assert_eq!(report.findings.get("pe"), Some(&1));
+        assert_eq!(report.findings.get("elf"), Some(&1));
+        assert_eq!(report.findings.get("macro_document"), Some(&1));
+        assert!(report.known_bad_hash && report.flagged);
+
+        let mut clean = Screener::new(ScreeningMode::Fail);
+        clean.update(&b"plain,csv\n".repeat(500));
+        assert!(!clean.finish(None, &bad).unwrap().flagged);
+        let mut failing = Screener::new(ScreeningMode::Fail);
+        failing.update(&pe_stub());
+        assert!(failing.finish(None, &bad).is_err());
+    }
+}
@@ -0,0 +1,21 @@
+use anyhow::{Context, Result};
+use rusqlite::Connection;
+use std::path::Path;
+use tracing::{info, warn};
+
+// SQLite databases for enclave state that must survive restarts. They live in NAUTILUS_SEALED_DIR,
+// the enclave's persistent sealed volume; without one they are in-memory and start empty.
+pub fn open_db(sealed_dir: Option<&Path>, file: &str) -> Result<Connection> {
+    match sealed_dir {
+        Some(dir) => {
+            std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+            let path = dir.join(file);
+            info!(path = %path.display(), "Opened sealed database");
+            Connection::open(&path).with_context(|| format!("open {}", path.display()))
+        }
+        None => {
+            warn!(file, "NAUTILUS_SEALED_DIR not set; database is in-memory only");
+            Ok(Connection::open_in_memory()?)
+        }
+    }
+}
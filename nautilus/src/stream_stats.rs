@@ -0,0 +1,121 @@
+// Constant-memory statistics for checks that see a blob chunk by chunk. A chunk is summarized on
+// its own (at most STREAM_CHUNK bytes at a time, so its sums are exact) and the partial is merged
+// into the running state, which stays the same size however large the blob. Repeated n-grams are
+// counted for a bounded sample of them (see ngram).
+
+pub const STREAM_CHUNK: usize = 64 * 1024;
+
+// Count, mean and sum of squared deviations of byte values (Welford). Partials combine with Chan
+// et al.'s pairwise update, and a partial that was merged in can be taken back out.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Moments {
+    n: u64,
+    mean: f64,
+    m2: f64,
+}
+
+impl Moments {
+    // Moments of one chunk, from exact integer sums.
+    pub fn of(chunk: &[u8]) -> Self {
+        if chunk.is_empty() {
+            return Self::default();
+        }
+        let (mut sum, mut sum_sq) = (0u128, 0u128);
+        for &b in chunk {
+            sum += b as u128;
+            sum_sq += (b as u128) * (b as u128);
+        }
+        let n = chunk.len() as u128;
+        Self { n: n as u64, mean: sum as f64 / n as f64, m2: (n * sum_sq - sum * sum) as f64 / n as f64 }
+    }
+
+    // `count` bytes of value `value`.
+    fn constant(value: u8, count: u64) -> Self {
+        Self { n: count, mean: value as f64, m2: 0.0 }
+    }
+
+    // Moments of the bytes counted in a by-value histogram.
+    pub fn of_histogram(freq: &[u64; 256]) -> Self {
+        let mut out = Self::default();
+        for (b, &count) in freq.iter().enumerate().filter(|(_, &c)| c > 0) {
+            out.merge(&Self::constant(b as u8, count));
+        }
+        out
+    }
+
+    pub fn merge(&mut self, other: &Self) {
+        if other.n == 0 {
+            return;
+        }
+        if self.n == 0 {
+            *self = *other;
+            return;
+        }
+        let (na, nb) = (self.n as f64, other.n as f64);
+        let n = na + nb;
+        let delta = other.mean - self.mean;
+        self.mean += delta * nb / n;
+        self.m2 += other.m2 + delta * delta * na * nb / n;
+        self.n += other.n;
+    }
+
+    // Undo `merge(other)`; saturates at empty when `other` holds more than was merged.
+    pub fn remove(&mut self, other: &Self) {
+        if other.n >= self.n {
+            *self = Self::default();
+            return;
+        }
+        let (n, nb) = (self.n as f64, other.n as f64);
+        let na = n - nb;
+        let mean = (n * self.mean - nb * other.mean) / na;
+        let delta = other.mean - mean;
+        self.m2 = (self.m2 - other.m2 - delta * delta * na * nb / n).max(0.0);
+        self.mean = mean;
+        self.n -= other.n;
+    }
+
+    pub fn count(&self) -> u64 {
+        self.n
+    }
+
+    pub fn mean(&self) -> f64 {
+        self.mean
+    }
+
+    // Population variance.
+    pub fn variance(&self) -> f64 {
+        if self.n == 0 {
+            0.0
+        } else {
+            self.m2 / self.n as f64
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_partials_merge() {
+        let data: Vec<u8> = (0..200_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 11) as u8).collect();
+        let whole = Moments::of(&data);
+        let mut merged = Moments::default();
+        for chunk in data.chunks(STREAM_CHUNK / 3) {
+            merged.merge(&Moments::of(chunk));
+        }
+        assert_eq!(merged.count(), whole.count());
+        assert!((merged.mean() - whole.mean()).abs() < 1e-9);
+        assert!((merged.variance() - whole.variance()).abs() < 1e-6);
+
+        // Taking bytes back out by histogram leaves the moments of the rest.
+        let mut freq = [0u64; 256];
+        for &b in &data[150_000..] {
+            freq[b as usize] += 1;
+        }
+        merged.remove(&Moments::of_histogram(&freq));
+        let head = Moments::of(&data[..150_000]);
+        assert_eq!(merged.count(), head.count());
+        assert!((merged.variance() - head.variance()).abs() < 1e-6);
+    }
+}
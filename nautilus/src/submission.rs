@@ -0,0 +1,411 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use blake2::digest::consts::U32;
+use blake2::{Blake2b, Digest};
+use ed25519_dalek::{Keypair, PublicKey, Signer};
+use reqwest::Client;
+use serde::Serialize;
+use std::env;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tracing::{info, warn};
+
+use crate::blob_source::BlobSource;
+use crate::jobs::JobRegistry;
+use crate::key_usage::KeyUsageMonitor;
+use crate::metrics;
+use crate::tee_attestation::key_id;
+use crate::walrus_client::MoveCall;
+
+// On-chain submission of verification results. When a package is configured, every completed
+// (unescrowed) verification is recorded by calling
+// `<package>::<module>::<function>(blob_id: String, score: u8, attestation: vector<u8>)`
+// from the service key's Sui address. Submission runs in the background so a slow or congested
+// chain never holds up the response: each attempt builds, signs and executes the call, failures
+// (gas, version conflicts, an unreachable node) are retried with exponential backoff, and the
+// outcome lands in the job's status. Every final outcome is also handed to the configured
+// notifiers as a `submission.succeeded` / `submission.failed` event, so operators hear about
+// verifications that only exist off-chain.
+
+// Backoff never grows past this between attempts.
+const MAX_BACKOFF: Duration = Duration::from_secs(300);
+
+#[derive(Debug, Clone)]
+pub struct SubmissionConfig {
+    // Move package holding the entry function; submission is off when unset.
+    pub package: Option<String>,
+    pub module: String,
+    pub function: String,
+    pub gas_budget: u64,
+    pub max_attempts: u32,
+    // Delay before the first retry; doubled after each failed attempt.
+    pub backoff: Duration,
+    // Operator endpoint POSTed every submission event.
+    pub webhook_url: Option<String>,
+}
+
+impl SubmissionConfig {
+    pub fn from_env() -> Self {
+        let parse = |key: &str, default: u64| env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            package: env::var("NAUTILUS_SUBMIT_PACKAGE").ok().filter(|p| !p.is_empty()),
+            module: env::var("NAUTILUS_SUBMIT_MODULE").unwrap_or_else(|_| "quality".to_string()),
+            function: env::var("NAUTILUS_SUBMIT_FUNCTION").unwrap_or_else(|_| "record_verification".to_string()),
+            gas_budget: parse("NAUTILUS_SUBMIT_GAS_BUDGET", 10_000_000),
+            max_attempts: parse("NAUTILUS_SUBMIT_MAX_ATTEMPTS", 5).max(1) as u32,
+            backoff: Duration::from_millis(parse("NAUTILUS_SUBMIT_BACKOFF_MS", 2000)),
+            webhook_url: env::var("NAUTILUS_SUBMIT_WEBHOOK_URL").ok().filter(|u| !u.is_empty()),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.package.is_some()
+    }
+
+    // Wait before attempt `attempt + 1`, after `attempt` failures.
+    fn delay(&self, attempt: u32) -> Duration {
+        self.backoff.saturating_mul(1 << (attempt - 1).min(16)).min(MAX_BACKOFF)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum SubmissionState {
+    Pending,
+    Submitted,
+    Failed,
+}
+
+// On-chain side of a job, as shown in its status.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubmissionStatus {
+    pub state: SubmissionState,
+    pub attempts: u32,
+    // Transaction digest once submitted.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    // Last failure; kept while retrying so a stuck submission shows why.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub updated_ms: u64,
+}
+
+// Final outcome of one submission, as delivered to notifiers.
+#[derive(Debug, Clone, Serialize)]
+pub struct SubmissionEvent {
+    // "submission.succeeded" or "submission.failed".
+    pub event: &'static str,
+    pub job_id: String,
+    pub blob_id: String,
+    pub quality_score: u8,
+    pub attempts: u32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub digest: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+    pub timestamp_ms: u64,
+}
+
+// Where submission events go. The log is always notified; operators add their own sinks.
+#[async_trait::async_trait]
+pub trait SubmissionNotifier: Send + Sync {
+    async fn notify(&self, event: &SubmissionEvent) -> Result<()>;
+}
+
+pub struct LogNotifier;
+
+#[async_trait::async_trait]
+impl SubmissionNotifier for LogNotifier {
+    async fn notify(&self, event: &SubmissionEvent) -> Result<()> {
+        match &event.digest {
+            Some(digest) => info!(job_id = %event.job_id, %digest, attempts = event.attempts, "Verification submitted on chain"),
+            None => warn!(
+                job_id = %event.job_id,
+                attempts = event.attempts,
+                error = event.error.as_deref().unwrap_or(""),
+                "On-chain submission failed; the verification exists off-chain only"
+            ),
+        }
+        Ok(())
+    }
+}
+
+// POSTs each event as JSON to an operator endpoint.
+pub struct WebhookNotifier {
+    http: Client,
+    url: String,
+}
+
+impl WebhookNotifier {
+    pub fn new(url: String) -> Self {
+        Self { http: Client::new(), url }
+    }
+}
+
+#[async_trait::async_trait]
+impl SubmissionNotifier for WebhookNotifier {
+    async fn notify(&self, event: &SubmissionEvent) -> Result<()> {
+        self.http
+            .post(&self.url)
+            .timeout(Duration::from_secs(10))
+            .json(event)
+            .send()
+            .await
+            .with_context(|| format!("submission webhook {} unreachable", self.url))?
+            .error_for_status()
+            .context("submission webhook rejected the event")?;
+        Ok(())
+    }
+}
+
+// A completed verification waiting to be recorded on chain.
+#[derive(Debug, Clone)]
+pub struct Submission {
+    pub job_id: String,
+    pub blob_id: String,
+    pub quality_score: u8,
+    pub attestation: Vec<u8>,
+}
+
+pub struct Submitter {
+    config: SubmissionConfig,
+    notifiers: Vec<Arc<dyn SubmissionNotifier>>,
+    queue: mpsc::UnboundedSender<Submission>,
+    // Taken once by the background task that drains it.
+    pending: Mutex<Option<mpsc::UnboundedReceiver<Submission>>>,
+}
+
+impl Submitter {
+    pub fn new(config: SubmissionConfig) -> Self {
+        let mut notifiers: Vec<Arc<dyn SubmissionNotifier>> = vec![Arc::new(LogNotifier)];
+        if let Some(url) = &config.webhook_url {
+            notifiers.push(Arc::new(WebhookNotifier::new(url.clone())));
+        }
+        let (queue, pending) = mpsc::unbounded_channel();
+        Self { config, notifiers, queue, pending: Mutex::new(Some(pending)) }
+    }
+
+    pub fn config(&self) -> &SubmissionConfig {
+        &self.config
+    }
+
+    #[cfg(test)]
+    pub fn add_notifier(&mut self, notifier: Arc<dyn SubmissionNotifier>) {
+        self.notifiers.push(notifier);
+    }
+
+    // Queue a verification for submission and mark its job pending. No-op when disabled.
+    pub fn enqueue(&self, jobs: &JobRegistry, submission: Submission) {
+        if !self.config.enabled() {
+            return;
+        }
+        jobs.set_submission(&submission.job_id, status(SubmissionState::Pending, 0, None, None));
+        if self.queue.send(submission).is_err() {
+            warn!("Submission queue closed; verification not submitted");
+        }
+    }
+
+    // The queue's receiving end, for the background task; None once taken.
+    pub fn take_queue(&self) -> Option<mpsc::UnboundedReceiver<Submission>> {
+        self.pending.lock().unwrap_or_else(|e| e.into_inner()).take()
+    }
+
+    // Submit with retries until it lands or attempts run out, then notify. Returns the digest.
+    pub async fn run(
+        &self,
+        submission: &Submission,
+        chain: &dyn BlobSource,
+        signing_key: &Keypair,
+        key_usage: &KeyUsageMonitor,
+        jobs: &JobRegistry,
+    ) -> Option<String> {
+        let mut attempts = 0;
+        let outcome = loop {
+            attempts += 1;
+            match self.attempt(submission, chain, signing_key, key_usage).await {
+                Ok(digest) => break Ok(digest),
+                Err(err) if attempts >= self.config.max_attempts => break Err(err),
+                Err(err) => {
+                    let err = format!("{:#}", err);
+                    warn!(job_id = %submission.job_id, attempts, %err, "On-chain submission failed; retrying");
+                    metrics::inc_counter("nautilus_submission_retries_total", "On-chain submission attempts retried", &[]);
+                    jobs.set_submission(&submission.job_id, status(SubmissionState::Pending, attempts, None, Some(err)));
+                    tokio::time::sleep(self.config.delay(attempts)).await;
+                }
+            }
+        };
+        let (state, digest, error) = match outcome {
+            Ok(digest) => (SubmissionState::Submitted, Some(digest), None),
+            Err(err) => (SubmissionState::Failed, None, Some(format!("{:#}", err))),
+        };
+        jobs.set_submission(&submission.job_id, status(state, attempts, digest.clone(), error.clone()));
+        let outcome = if digest.is_some() { "succeeded" } else { "failed" };
+        metrics::inc_counter("nautilus_submissions_total", "On-chain submissions by final outcome", &[("outcome", outcome)]);
+        let event = SubmissionEvent {
+            event: if digest.is_some() { "submission.succeeded" } else { "submission.failed" },
+            job_id: submission.job_id.clone(),
+            blob_id: submission.blob_id.clone(),
+            quality_score: submission.quality_score,
+            attempts,
+            digest: digest.clone(),
+            error,
+            timestamp_ms: now_ms(),
+        };
+        for notifier in &self.notifiers {
+            if let Err(err) = notifier.notify(&event).await {
+                warn!(%err, event = event.event, "Submission notifier failed");
+            }
+        }
+        digest
+    }
+
+    async fn attempt(
+        &self,
+        submission: &Submission,
+        chain: &dyn BlobSource,
+        signing_key: &Keypair,
+        key_usage: &KeyUsageMonitor,
+    ) -> Result<String> {
+        let call = MoveCall {
+            sender: sui_address(&signing_key.public),
+            package: self.config.package.clone().context("no submission package configured")?,
+            module: self.config.module.clone(),
+            function: self.config.function.clone(),
+            arguments: vec![
+                submission.blob_id.clone().into(),
+                submission.quality_score.into(),
+                submission.attestation.clone().into(),
+            ],
+            gas_budget: self.config.gas_budget,
+        };
+        let tx_bytes = chain.sui_build_move_call(&call).await.context("build submission transaction")?;
+        key_usage.authorize(&key_id(&signing_key.public), "submission")?;
+        let signature = sign_transaction(signing_key, &tx_bytes);
+        chain.sui_execute(&tx_bytes, &signature).await
+    }
+}
+
+// Sui address of an ed25519 key: BLAKE2b-256 of the scheme flag (0x00) followed by the key.
+pub fn sui_address(pk: &PublicKey) -> String {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update([0x00]);
+    hasher.update(pk.as_bytes());
+    format!("0x{}", hex::encode(hasher.finalize()))
+}
+
+// Serialized Sui signature over transaction bytes: flag || signature || public key, base64.
+// What gets signed is the BLAKE2b-256 digest of the intent message (TransactionData intent,
+// version 0, app Sui: [0, 0, 0]) followed by the transaction bytes.
+fn sign_transaction(kp: &Keypair, tx_bytes: &[u8]) -> String {
+    let mut hasher = Blake2b::<U32>::new();
+    hasher.update([0u8, 0, 0]);
+    hasher.update(tx_bytes);
+    let sig = kp.sign(&hasher.finalize());
+    let mut out = Vec::with_capacity(97);
+    out.push(0x00);
+    out.extend_from_slice(&sig.to_bytes());
+    out.extend_from_slice(kp.public.as_bytes());
+    STANDARD.encode(out)
+}
+
+fn status(state: SubmissionState, attempts: u32, digest: Option<String>, error: Option<String>) -> SubmissionStatus {
+    SubmissionStatus { state, attempts, digest, error, updated_ms: now_ms() }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_usage::KeyUsagePolicy;
+    use crate::walrus_client::{BlobMetadata, BlobRange};
+    use ed25519_dalek::{SecretKey, Signature, Verifier};
+
+    // Chain double: the first `conflicts` executions fail like a version conflict.
+    struct FlakyChain {
+        conflicts: Mutex<u32>,
+    }
+
+    #[async_trait::async_trait]
+    impl BlobSource for FlakyChain {
+        async fn fetch_blob(&self, _blob_id: &str, _limit: u64) -> Result<Vec<u8>> {
+            unreachable!()
+        }
+        async fn fetch_blob_range(&self, _blob_id: &str, _offset: u64, _len: u64) -> Result<BlobRange> {
+            unreachable!()
+        }
+        async fn blob_metadata(&self, _blob_id: &str) -> Result<BlobMetadata> {
+            unreachable!()
+        }
+        async fn sui_build_move_call(&self, call: &MoveCall) -> Result<Vec<u8>> {
+            Ok(format!("{}::{}:{}", call.module, call.function, call.arguments[0]).into_bytes())
+        }
+        async fn sui_execute(&self, tx_bytes: &[u8], _signature: &str) -> Result<String> {
+            let mut conflicts = self.conflicts.lock().unwrap();
+            if *conflicts > 0 {
+                *conflicts -= 1;
+                anyhow::bail!("transaction failed: ObjectVersionUnavailableForConsumption");
+            }
+            Ok(format!("digest-of-{}", String::from_utf8_lossy(tx_bytes)))
+        }
+    }
+
+    #[derive(Default)]
+    struct Recorder(Mutex<Vec<SubmissionEvent>>);
+
+    #[async_trait::async_trait]
+    impl SubmissionNotifier for Recorder {
+        async fn notify(&self, event: &SubmissionEvent) -> Result<()> {
+            self.0.lock().unwrap().push(event.clone());
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_retried_until_submitted_or_given_up() {
+        let secret = SecretKey::from_bytes(&[3u8; 32]).unwrap();
+        let kp = Keypair { public: (&secret).into(), secret };
+        let usage = KeyUsageMonitor::new(KeyUsagePolicy::from_env());
+        let mut config = SubmissionConfig::from_env();
+        config.package = Some("0x2a".into());
+        config.max_attempts = 3;
+        config.backoff = Duration::from_millis(1);
+        let recorder = Arc::new(Recorder::default());
+        let mut submitter = Submitter::new(config);
+        submitter.add_notifier(recorder.clone());
+        let jobs = JobRegistry::new(1024);
+
+        // Two conflicts, then it lands on the third and last attempt.
+        let job = jobs.start("blob-a");
+        let sub = Submission { job_id: job.id(), blob_id: "blob-a".into(), quality_score: 80, attestation: vec![1] };
+        submitter.enqueue(&jobs, sub.clone());
+        assert_eq!(jobs.get(&sub.job_id).unwrap().submission.unwrap().state, SubmissionState::Pending);
+        let chain = FlakyChain { conflicts: Mutex::new(2) };
+        let digest = submitter.run(&sub, &chain, &kp, &usage, &jobs).await;
+        assert_eq!(digest.as_deref(), Some("digest-of-quality::record_verification:\"blob-a\""));
+        let status = jobs.get(&sub.job_id).unwrap().submission.unwrap();
+        assert_eq!((status.state, status.attempts, status.error), (SubmissionState::Submitted, 3, None));
+        job.complete();
+
+        // One conflict too many: the job and the event record the failure.
+        *chain.conflicts.lock().unwrap() = 3;
+        assert_eq!(submitter.run(&sub, &chain, &kp, &usage, &jobs).await, None);
+        let status = jobs.get(&sub.job_id).unwrap().submission.unwrap();
+        assert_eq!(status.state, SubmissionState::Failed);
+        assert!(status.error.unwrap().contains("ObjectVersionUnavailable"));
+        let events = recorder.0.lock().unwrap();
+        let kinds: Vec<_> = events.iter().map(|e| (e.event, e.attempts)).collect();
+        assert_eq!(kinds, vec![("submission.succeeded", 3), ("submission.failed", 3)]);
+
+        // The signature verifies over the intent digest and carries the key.
+        let raw = STANDARD.decode(sign_transaction(&kp, b"tx")).unwrap();
+        assert_eq!((raw.len(), raw[0], &raw[65..]), (97, 0, &kp.public.as_bytes()[..]));
+        let digest = Blake2b::<U32>::new().chain_update([0u8, 0, 0]).chain_update(b"tx").finalize();
+        kp.public.verify(&digest, &Signature::from_bytes(&raw[1..65]).unwrap()).unwrap();
+        assert_eq!(sui_address(&kp.public).len(), 66);
+    }
+}
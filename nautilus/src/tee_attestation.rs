@@ -1,99 +1,712 @@
 use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
 use aws_nitro_enclaves_nsm_api::api::{Request, Response};
 use aws_nitro_enclaves_nsm_api::driver::{nsm_exit, nsm_init, nsm_process_request};
+use schemars::JsonSchema;
 use serde_bytes::ByteBuf;
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::path::Path;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::info;
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
 use std::env;
-use ed25519_dalek::{Keypair, PublicKey, Signature, Signer};
+use ed25519_dalek::{Keypair, PublicKey, Signer};
 
-#[derive(Serialize, Deserialize)]
+use crate::chaos::{self, FaultPoint};
+use crate::cosign::{Cosignature, CosignUnavailable, OperatorSigner};
+use crate::drift::DriftReport;
+use crate::key_usage::KeyUsageMonitor;
+use crate::key_ring::KeyRing;
+use crate::kms_seal::{self, KmsConfig};
+use crate::sampling::SampleInfo;
+use crate::scoring::ScoringSummary;
+use crate::screening::ScreeningReport;
+
+pub mod bcs_payload;
+pub mod bls;
+pub mod cose;
+pub mod identity;
+pub mod merkle;
+pub mod secp256k1;
+pub mod sev_snp;
+pub mod tpm;
+pub mod verify;
+
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct AttestationData {
     pub blob_id: String,
+    // Backend the bytes came from ("walrus", "http" or "s3"); URLs and object keys can change
+    // content, unlike a Walrus blob ID. Payloads predating this field are all Walrus.
+    #[serde(default = "default_source_type")]
+    pub source_type: String,
     pub quality_score: u8,
     pub timestamp: u64,
+    // Milliseconds since the epoch after which verifiers reject the attestation: the blob may have
+    // been re-uploaded or rescored since. Absent when the operator sets no validity period, and in
+    // payloads predating expiry.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub expires_at: Option<u64>,
+    // PCR0 (hex SHA-384 of the enclave image), the SEV-SNP launch measurement under SEV-SNP, or
+    // UNMEASURED outside a TEE.
     pub enclave_measurement: String,
+    // PCR0, PCR1 (kernel and bootstrap) and PCR2 (application) as NSM DescribePCR reports them,
+    // keyed "pcr0".."pcr2"; absent outside an enclave and in payloads predating this field.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub pcrs: BTreeMap<String, String>,
+    // Dataset category whose preset produced the score; scores only compare within a category.
+    #[serde(default)]
+    pub category: String,
+    // Load-shedding degradations applied while scoring; part of the signed payload.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub degradations: Vec<String>,
+    // SHA-256 of the exact blob bytes scored; absent when only a sample was fetched.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub content_sha256: Option<String>,
+    // SHA-256 of the decrypted dataset, as a buyer holding the plaintext can recompute it; absent
+    // when only a sample was fetched, and in payloads predating this field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub plaintext_sha256: Option<String>,
+    // SHA-256 of the per-check report (see quality_validator::report_sha256), so a report
+    // published later can be matched to this result; absent in payloads predating this field.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub report_sha256: Option<String>,
+    // Executable/malware screening outcome; absent when screening is off.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub screening: Option<ScreeningReport>,
+    // Record sample the score was computed on; absent when every record was scored.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sampling: Option<SampleInfo>,
+    // Sui dataset object the blob was resolved from, when verified by object ID.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sui_object_id: Option<String>,
+    // Hash of the effective runtime config (see `Config::hash`); differs across operational
+    // configs even when PCRs are identical.
+    #[serde(default)]
+    pub config_hash: String,
+    // Walrus/Sui network profile the blob was read on; payloads predating profiles are testnet.
+    #[serde(default = "default_walrus_profile")]
+    pub walrus_profile: String,
+    // SHA-256 of each check's sealed evidence artifact (see evidence); absent in payloads
+    // predating evidence.
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub evidence: BTreeMap<String, String>,
+    // Weights, minimums and aggregate formula the score was computed with (see scoring); absent
+    // in payloads predating configurable scoring.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub scoring: Option<ScoringSummary>,
+    // 0..=100 risk of training-data poisoning (see poisoning), for buyers to gate on; absent
+    // when the check didn't run.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub poisoning_risk: Option<u8>,
+    // Distribution drift from the reference dataset the request named (see drift); absent
+    // without one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub drift: Option<DriftReport>,
+    // Random hex nonce, under BCS payload encoding only (see bcs_payload).
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub nonce: Option<String>,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, JsonSchema)]
 pub struct AttestationEnvelope {
-    pub format: String,                 // "ed25519-v1" or "nsm-document-v1"
+    // "ed25519-v2", "secp256k1-v2", "bls12381-v2", "nsm-document-v2" or "sev-snp-v2",
+    // "-cosigned-v2" when dual-signed
+    pub format: String,
     pub data: AttestationData,          // signed data
-    pub signature_b64: Option<String>,  // present for ed25519-v1
-    pub public_key_b64: Option<String>, // present for ed25519-v1
-    pub nsm_document_b64: Option<String>, // present for nsm-document-v1
+    // Signature over the canonical data: ed25519 for ed25519-v2; for secp256k1-v2, and
+    // nsm-document-v2 under the secp256k1 scheme, Sui-serialized secp256k1 (see secp256k1); for
+    // bls12381-v2, and nsm-document-v2 under that scheme, BLS over the quorum message (see bls).
+    pub signature_b64: Option<String>,
+    // Always present: the signing key (compressed secp256k1 or BLS G1 under those schemes), which
+    // nsm-document-v2 also binds into the NSM document's `public_key`, so later responses signed
+    // with it trace back to the attested enclave.
+    pub public_key_b64: Option<String>,
+    // `key_id` of that key, so verifiers can pick it out of GET /keys across rotations.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key_id: Option<String>,
+    pub nsm_document_b64: Option<String>, // present for nsm-document-v2
+    // Hardware evidence of the other TEE formats: the SEV-SNP attestation report for sev-snp-v2.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub platform_evidence_b64: Option<String>,
+    // How `data` was serialized for signing (see nautilus_canonical and bcs_payload), and SHA-256
+    // of those bytes.
+    // Absent on v1 envelopes, which were signed over the service's own field order.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub canonicalization: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub payload_sha256: Option<String>,
+    // Operator co-signature over SHA-256 of the serialized data; present for the "-cosigned" formats.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cosignature: Option<Cosignature>,
+}
+
+// What a verification concluded about a blob; the attester adds timestamp and measurement.
+pub struct QualityClaim {
+    pub blob_id: String,
+    pub source_type: String,
+    pub quality_score: u8,
+    pub category: String,
+    pub degradations: Vec<String>,
+    pub content_sha256: Option<String>,
+    pub plaintext_sha256: Option<String>,
+    pub report_sha256: Option<String>,
+    pub screening: Option<ScreeningReport>,
+    pub sampling: Option<SampleInfo>,
+    pub sui_object_id: Option<String>,
+    pub config_hash: String,
+    pub walrus_profile: String,
+    // High-value attestation: also needs the operator's co-signature (see cosign).
+    pub co_sign: bool,
+    pub evidence: BTreeMap<String, String>,
+    pub scoring: Option<ScoringSummary>,
+    pub poisoning_risk: Option<u8>,
+    pub drift: Option<DriftReport>,
+    // Encoding of the envelope returned, as the request asked.
+    pub envelope: EnvelopeEncoding,
+    // How long the attestation stays valid from its timestamp; forever when None.
+    pub validity: Option<Duration>,
+}
+
+// Produces the attestation bytes returned with a verification result.
+// A trait object so handlers can be exercised with a fake attester in tests.
+#[async_trait::async_trait]
+pub trait Attester: Send + Sync {
+    async fn attest(&self, claim: &QualityClaim) -> Result<Vec<u8>>;
+}
+
+// NSM document inside a Nitro Enclave, ed25519 signature over the payload otherwise.
+pub struct TeeAttester {
+    keys: Arc<KeyRing>,
+    scheme: SignatureScheme,
+    encoding: PayloadEncoding,
+    usage: Arc<KeyUsageMonitor>,
+    cosigner: Option<Arc<OperatorSigner>>,
+}
+
+impl TeeAttester {
+    pub fn new(
+        keys: Arc<KeyRing>,
+        scheme: SignatureScheme,
+        encoding: PayloadEncoding,
+        usage: Arc<KeyUsageMonitor>,
+        cosigner: Option<Arc<OperatorSigner>>,
+    ) -> Self {
+        Self { keys, scheme, encoding, usage, cosigner }
+    }
+}
+
+#[async_trait::async_trait]
+impl Attester for TeeAttester {
+    async fn attest(&self, claim: &QualityClaim) -> Result<Vec<u8>> {
+        let key = self.keys.current();
+        generate_attestation(&key, self.scheme, self.encoding, &self.usage, self.cosigner.as_deref(), claim).await
+    }
+}
+
+// Which signature attestations carry, from NAUTILUS_SIGNATURE_SCHEME: ed25519 (the default),
+// secp256k1 for verification inside a Sui Move contract, or bls12381 for replica quorums whose
+// signatures aggregate into one (see bls). Badges, bundles and submissions are ed25519-signed
+// either way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum SignatureScheme {
+    #[default]
+    Ed25519,
+    Secp256k1,
+    Bls12381,
 }
 
-pub async fn generate_attestation(blob_id: &str, quality_score: u8) -> Result<Vec<u8>> {
+impl SignatureScheme {
+    pub fn from_env() -> Result<Self> {
+        match env::var("NAUTILUS_SIGNATURE_SCHEME").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "" | "ed25519" => Ok(Self::Ed25519),
+            "secp256k1" => Ok(Self::Secp256k1),
+            "bls12381" => Ok(Self::Bls12381),
+            other => anyhow::bail!(
+                "Unknown NAUTILUS_SIGNATURE_SCHEME {:?}; expected ed25519, secp256k1 or bls12381",
+                other
+            ),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Ed25519 => "ed25519",
+            Self::Secp256k1 => "secp256k1",
+            Self::Bls12381 => "bls12381",
+        }
+    }
+
+    pub fn public_key(self, kp: &Keypair) -> Result<Vec<u8>> {
+        match self {
+            Self::Ed25519 => Ok(kp.public.to_bytes().to_vec()),
+            Self::Secp256k1 => secp256k1::public_key(kp),
+            Self::Bls12381 => bls::public_key(kp),
+        }
+    }
+
+    // `serialized` is `data` as the payload encoding has it; BLS signs the quorum message instead.
+    fn sign(self, kp: &Keypair, data: &AttestationData, serialized: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Bls12381 => self.sign_message(kp, &bls::quorum_message(data)?),
+            _ => self.sign_message(kp, serialized),
+        }
+    }
+
+    fn sign_message(self, kp: &Keypair, message: &[u8]) -> Result<Vec<u8>> {
+        match self {
+            Self::Ed25519 => Ok(kp.sign(message).to_bytes().to_vec()),
+            Self::Secp256k1 => secp256k1::sign(kp, message),
+            Self::Bls12381 => bls::sign(kp, message),
+        }
+    }
+}
+
+// How the signed payload is serialized, from NAUTILUS_PAYLOAD_ENCODING: canonical JSON (the
+// default), or BCS for Move contracts that rebuild the message (see bcs_payload).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PayloadEncoding {
+    #[default]
+    Json,
+    Bcs,
+}
+
+impl PayloadEncoding {
+    pub fn from_env() -> Result<Self> {
+        match env::var("NAUTILUS_PAYLOAD_ENCODING").unwrap_or_default().to_ascii_lowercase().as_str() {
+            "" | "json" => Ok(Self::Json),
+            "bcs" => Ok(Self::Bcs),
+            other => anyhow::bail!("Unknown NAUTILUS_PAYLOAD_ENCODING {:?}; expected json or bcs", other),
+        }
+    }
+
+    pub fn canonicalization(self) -> &'static str {
+        match self {
+            Self::Json => nautilus_canonical::CANONICALIZATION,
+            Self::Bcs => bcs_payload::CANONICALIZATION,
+        }
+    }
+
+    fn serialize(self, data: &AttestationData) -> Result<Vec<u8>> {
+        match self {
+            Self::Json => nautilus_canonical::canonical_bytes(data).context("serialize AttestationData"),
+            Self::Bcs => bcs_payload::to_bytes(data),
+        }
+    }
+}
+
+// How the envelope is encoded, chosen per request: JSON (the default), or a COSE_Sign1 structure,
+// more compact as calldata (see cose).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EnvelopeEncoding {
+    #[default]
+    Json,
+    Cose,
+}
+
+impl EnvelopeEncoding {
+    fn encode(self, kp: &Keypair, scheme: SignatureScheme, envelope: &AttestationEnvelope) -> Result<Vec<u8>> {
+        match self {
+            Self::Json => serde_json::to_vec(envelope).context("serialize AttestationEnvelope"),
+            Self::Cose => cose::encode(kp, scheme, envelope),
+        }
+    }
+}
+
+// The bytes an envelope's signatures are over, per its `canonicalization`.
+pub fn signed_bytes(envelope: &AttestationEnvelope) -> Result<Vec<u8>> {
+    let encoding = match envelope.canonicalization.as_deref() {
+        Some(nautilus_canonical::CANONICALIZATION) => PayloadEncoding::Json,
+        Some(bcs_payload::CANONICALIZATION) => PayloadEncoding::Bcs,
+        Some(other) => anyhow::bail!("unsupported canonicalization {}", other),
+        None => anyhow::bail!("v1 envelopes were not signed over a reproducible serialization"),
+    };
+    encoding.serialize(&envelope.data)
+}
+
+// The payload an attestation of `claim` signs, stamped with the time and the TEE's measurement.
+pub fn attestation_data(claim: &QualityClaim, encoding: PayloadEncoding) -> Result<AttestationData> {
     let timestamp = SystemTime::now()
         .duration_since(UNIX_EPOCH)
         .unwrap_or_default()
         .as_millis() as u64;
-    let measurement = get_enclave_measurement();
-    let payload = AttestationData {
-        blob_id: blob_id.to_string(),
-        quality_score,
+    let (measurement, pcrs) = match provider() {
+        Some(provider) => provider.measurement()?,
+        None => (UNMEASURED.to_string(), BTreeMap::new()),
+    };
+    Ok(AttestationData {
+        blob_id: claim.blob_id.clone(),
+        source_type: claim.source_type.clone(),
+        quality_score: claim.quality_score,
         timestamp,
+        expires_at: claim.validity.map(|v| timestamp.saturating_add(v.as_millis() as u64)),
         enclave_measurement: measurement,
-    };
-    let serialized = serde_json::to_vec(&payload).context("serialize AttestationData")?;
+        pcrs,
+        category: claim.category.clone(),
+        degradations: claim.degradations.clone(),
+        content_sha256: claim.content_sha256.clone(),
+        plaintext_sha256: claim.plaintext_sha256.clone(),
+        report_sha256: claim.report_sha256.clone(),
+        screening: claim.screening.clone(),
+        sampling: claim.sampling.clone(),
+        sui_object_id: claim.sui_object_id.clone(),
+        config_hash: claim.config_hash.clone(),
+        walrus_profile: claim.walrus_profile.clone(),
+        evidence: claim.evidence.clone(),
+        scoring: claim.scoring.clone(),
+        poisoning_risk: claim.poisoning_risk,
+        drift: claim.drift.clone(),
+        nonce: match encoding {
+            PayloadEncoding::Json => None,
+            PayloadEncoding::Bcs => Some(hex::encode(random_nonce()?)),
+        },
+    })
+}
 
-    if Path::new("/dev/nsm").exists() {
-        info!("Nitro Enclave device detected, generating NSM attestation");
-        let doc = generate_nitro_attestation(&serialized)?;
-        let env = AttestationEnvelope {
-            format: "nsm-document-v1".to_string(),
+pub async fn generate_attestation(
+    kp: &Keypair,
+    scheme: SignatureScheme,
+    encoding: PayloadEncoding,
+    usage: &KeyUsageMonitor,
+    cosigner: Option<&OperatorSigner>,
+    claim: &QualityClaim,
+) -> Result<Vec<u8>> {
+    if claim.co_sign && cosigner.is_none() {
+        return Err(CosignUnavailable("co-signing requested but no operator signer is configured".into()).into());
+    }
+    let mut payload = attestation_data(claim, encoding)?;
+    if scheme == SignatureScheme::Bls12381 {
+        payload.expires_at = payload.expires_at.map(bls::quorum_expiry);
+    }
+    // Signed in canonical form, so any verifier can reproduce the bytes from the JSON.
+    let serialized = encoding.serialize(&payload)?;
+    let payload_sha256 = Some(hex::encode(Sha256::digest(&serialized)));
+
+    let public_key = scheme.public_key(kp)?;
+
+    if let Some(provider) = provider() {
+        info!(format = provider.format(), "TEE device detected, generating hardware attestation");
+        chaos::inject(FaultPoint::Nsm).await?;
+        let evidence = provider.evidence(&Sha256::digest(&serialized), &public_key)?;
+        // The hardware evidence alone vouches for ed25519 envelopes; a secp256k1 or BLS signature
+        // rides along so the contract, which cannot check the evidence, can check that.
+        let signature_b64 = match scheme {
+            SignatureScheme::Ed25519 => None,
+            _ => {
+                usage.authorize(&key_id(&kp.public), "attestation")?;
+                Some(STANDARD.encode(scheme.sign(kp, &payload, &serialized)?))
+            }
+        };
+        // The enclave signs first; the operator only ever sees the digest.
+        let cosignature = cosign_if_requested(cosigner, claim, &serialized).await?;
+        let mut env = AttestationEnvelope {
+            format: envelope_format(provider.format(), &cosignature),
             data: payload,
-            signature_b64: None,
-            public_key_b64: None,
-            nsm_document_b64: Some(base64::encode(doc)),
+            signature_b64,
+            public_key_b64: Some(STANDARD.encode(&public_key)),
+            key_id: Some(key_id_of(&public_key)),
+            nsm_document_b64: None,
+            platform_evidence_b64: None,
+            canonicalization: Some(encoding.canonicalization().to_string()),
+            payload_sha256,
+            cosignature,
         };
-        let out = serde_json::to_vec(&env).context("serialize AttestationEnvelope")?;
-        Ok(out)
+        provider.attach(&evidence, &mut env);
+        claim.envelope.encode(kp, scheme, &env)
     } else {
-        info!("No Nitro device, generating ed25519 signature attestation");
-        let kp = ed25519_keypair_from_seed()?;
-        let sig: Signature = kp.sign(&serialized);
+        info!(scheme = scheme.as_str(), "No TEE device, generating signature attestation");
+        chaos::inject(FaultPoint::Signing).await?;
+        usage.authorize(&key_id(&kp.public), "attestation")?;
+        let signature = scheme.sign(kp, &payload, &serialized)?;
+        let cosignature = cosign_if_requested(cosigner, claim, &serialized).await?;
         let env = AttestationEnvelope {
-            format: "ed25519-v1".to_string(),
+            format: envelope_format(scheme.as_str(), &cosignature),
             data: payload,
-            signature_b64: Some(base64::encode(sig.to_bytes())),
-            public_key_b64: Some(base64::encode(kp.public.to_bytes())),
+            signature_b64: Some(STANDARD.encode(signature)),
+            public_key_b64: Some(STANDARD.encode(&public_key)),
+            key_id: Some(key_id_of(&public_key)),
             nsm_document_b64: None,
+            platform_evidence_b64: None,
+            canonicalization: Some(encoding.canonicalization().to_string()),
+            payload_sha256,
+            cosignature,
         };
-        let out = serde_json::to_vec(&env).context("serialize AttestationEnvelope")?;
-        Ok(out)
+        claim.envelope.encode(kp, scheme, &env)
     }
 }
 
-fn get_enclave_measurement() -> String {
-    // Placeholder PCR0 hex string (96 hex chars = 48 bytes)
-    "000000000000000000000000000000000000000000000000000000000000000000000000000000000000000000"
-        .to_string()
+async fn cosign_if_requested(
+    cosigner: Option<&OperatorSigner>,
+    claim: &QualityClaim,
+    serialized: &[u8],
+) -> Result<Option<Cosignature>> {
+    match cosigner.filter(|_| claim.co_sign) {
+        Some(signer) => Ok(Some(signer.cosign(&Sha256::digest(serialized).into()).await?)),
+        None => Ok(None),
+    }
 }
 
-fn generate_nitro_attestation(user_data: &[u8]) -> Result<Vec<u8>> {
-    // SAFETY: this calls into the NSM driver which expects a valid FD and buffers.
-    let fd = unsafe { nsm_init() };
-    if fd < 0 {
-        anyhow::bail!("nsm_init failed");
+fn default_source_type() -> String {
+    crate::blob_source::WALRUS.to_string()
+}
+
+fn default_walrus_profile() -> String {
+    crate::walrus_client::Profile::Testnet.as_str().to_string()
+}
+
+// Dual-signature envelopes get their own format so verifiers never accept one on the enclave
+// signature alone.
+fn envelope_format(base: &str, cosignature: &Option<Cosignature>) -> String {
+    match cosignature {
+        Some(_) => format!("{}-cosigned-v2", base),
+        None => format!("{}-v2", base),
     }
+}
+
+// Short, stable identifier for a signing key: hex of the first 8 bytes of SHA-256(public key).
+pub fn key_id(pk: &PublicKey) -> String {
+    key_id_of(pk.as_bytes())
+}
+
+// `key_id` of an encoded public key of any scheme.
+pub fn key_id_of(public_key: &[u8]) -> String {
+    hex::encode(&Sha256::digest(public_key)[..8])
+}
+
+// Measurement recorded when not running in a TEE: nothing vouches for the code that
+// produced the score, so verifiers must not treat it as a PCR.
+pub const UNMEASURED: &str = "unmeasured";
+
+// PCRs recorded in every attestation.
+const MEASURED_PCRS: [u16; 3] = [0, 1, 2];
+
+// Hardware attestation of the TEE the service runs in: the Nitro Security Module, or an AMD
+// SEV-SNP guest (see sev_snp); or, on bare metal, of the host's boot by its TPM (see tpm). Each
+// binds the signed payload's hash and the signing key into evidence the platform signs, which
+// verify checks.
+pub trait AttestationProvider: Send + Sync {
+    // Envelope format prefix, e.g. "nsm-document".
+    fn format(&self) -> &'static str;
+    // Launch measurement (hex) for `enclave_measurement`, and the PCRs, where the platform has them.
+    fn measurement(&self) -> Result<(String, BTreeMap<String, String>)>;
+    // Evidence over the payload's SHA-256 and the signing key.
+    fn evidence(&self, payload_sha256: &[u8], public_key: &[u8]) -> Result<Vec<u8>>;
+    // Puts the evidence in the envelope; NSM documents keep the field that predates the others.
+    fn attach(&self, evidence: &[u8], envelope: &mut AttestationEnvelope) {
+        envelope.platform_evidence_b64 = Some(STANDARD.encode(evidence));
+    }
+}
+
+// What evidence other than NSM documents (which have fields for each) binds: SHA-256 of the signed
+// payload, then SHA-256 of the signing key.
+pub fn evidence_binding(payload_sha256: &[u8], public_key: &[u8]) -> [u8; 64] {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(&payload_sha256[..32]);
+    data[32..].copy_from_slice(&Sha256::digest(public_key));
+    data
+}
+
+// The TEE's provider, then a configured TPM's; None without either.
+pub fn provider() -> Option<&'static dyn AttestationProvider> {
+    if Path::new("/dev/nsm").exists() {
+        Some(&Nitro)
+    } else if Path::new(sev_snp::DEVICE).exists() {
+        Some(&sev_snp::SevSnp)
+    } else {
+        tpm::configured().map(|tpm| tpm as &dyn AttestationProvider)
+    }
+}
+
+// The TEE's evidence and the attestation key's signature over a statement other than a
+// verification payload (see identity and merkle); `serialized` is its canonical JSON.
+#[derive(Debug, Serialize)]
+pub struct SignedStatement {
+    // "<platform or scheme>-<kind>-v1", e.g. "nsm-document-identity-v1" or "ed25519-batch-v1".
+    pub format: String,
+    // SHA-256 of the canonical statement, which the evidence and signature cover.
+    pub statement_sha256: String,
+    pub signature_b64: String,
+    // The platform's evidence (NSM document, SEV-SNP report or TPM quote), per `format`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub evidence_b64: Option<String>,
+}
+
+// The caller accounts for the signature with the KeyUsageMonitor first.
+fn sign_statement(kp: &Keypair, scheme: SignatureScheme, kind: &str, serialized: &[u8]) -> Result<SignedStatement> {
+    let provider = provider();
+    let digest = Sha256::digest(serialized);
+    let public_key = scheme.public_key(kp)?;
+    let evidence = provider.map(|p| p.evidence(&digest, &public_key)).transpose()?;
+    let format = format!("{}-{}-v1", provider.map_or(scheme.as_str(), |p| p.format()), kind);
+    info!(%format, "Signing attestation statement");
+    Ok(SignedStatement {
+        format,
+        statement_sha256: hex::encode(digest),
+        signature_b64: STANDARD.encode(scheme.sign_message(kp, serialized)?),
+        evidence_b64: evidence.map(|evidence| STANDARD.encode(evidence)),
+    })
+}
+
+pub struct Nitro;
+
+impl AttestationProvider for Nitro {
+    fn format(&self) -> &'static str {
+        "nsm-document"
+    }
+
+    // PCR0 and the PCR0..=2 map. PCRs 0 to 2 are locked when the enclave boots, so they are read
+    // from NSM once and reused; failing to read them inside an enclave fails the attestation
+    // rather than falling back.
+    fn measurement(&self) -> Result<(String, BTreeMap<String, String>)> {
+        static PCRS: OnceLock<BTreeMap<String, String>> = OnceLock::new();
+        let pcrs = match PCRS.get() {
+            Some(pcrs) => pcrs,
+            None => {
+                let read = describe_pcrs().context("read enclave PCRs from NSM")?;
+                PCRS.get_or_init(|| read)
+            }
+        };
+        Ok((pcrs["pcr0"].clone(), pcrs.clone()))
+    }
+
+    fn evidence(&self, payload_sha256: &[u8], public_key: &[u8]) -> Result<Vec<u8>> {
+        generate_nitro_attestation(payload_sha256, public_key)
+    }
+
+    fn attach(&self, evidence: &[u8], envelope: &mut AttestationEnvelope) {
+        envelope.nsm_document_b64 = Some(STANDARD.encode(evidence));
+    }
+}
+
+fn describe_pcrs() -> Result<BTreeMap<String, String>> {
+    let mut pcrs = BTreeMap::new();
+    for index in MEASURED_PCRS {
+        match nsm_request(Request::DescribePCR { index })? {
+            Response::DescribePCR { data, .. } if !data.is_empty() => {
+                pcrs.insert(format!("pcr{}", index), hex::encode(data));
+            }
+            other => anyhow::bail!("Unexpected NSM DescribePCR response for PCR{}: {:?}", index, other),
+        }
+    }
+    // Debug-mode enclaves report all-zero PCRs; the attestation is then not bound to an image.
+    if pcrs.values().all(|v| v.bytes().all(|b| b == b'0')) {
+        warn!("NSM reports all-zero PCRs: the enclave is running in debug mode");
+    }
+    info!(pcr0 = %pcrs["pcr0"], "Enclave measurement read from NSM");
+    Ok(pcrs)
+}
+
+// `user_data` is the payload's SHA-256: NSM caps user data at 512 bytes, which a payload easily
+// exceeds. `public_key` is the enclave's signing key: NSM signs it into the document, so a relying party
+// that verified the document once can accept anything signed with that key (badges, audit
+// archives, later ed25519 attestations) as coming from the same enclave.
+fn generate_nitro_attestation(user_data: &[u8], public_key: &[u8]) -> Result<Vec<u8>> {
     let req = Request::Attestation {
         user_data: Some(ByteBuf::from(user_data.to_vec())),
-        public_key: None,
+        public_key: Some(ByteBuf::from(public_key.to_vec())),
         nonce: None,
     };
-    let resp = unsafe { nsm_process_request(fd, req) };
-    let _ = unsafe { nsm_exit(fd) };
-    match resp {
+    match nsm_request(req)? {
+        Response::Attestation { document } => Ok(document),
+        other => anyhow::bail!("Unexpected NSM response: {:?}", other),
+    }
+}
+
+// An attestation document whose only claim is `public_key`, for services (KMS) that encrypt
+// their response to an attested key.
+pub fn recipient_document(public_key: &[u8]) -> Result<Vec<u8>> {
+    let public_key = Some(ByteBuf::from(public_key.to_vec()));
+    let req = Request::Attestation { user_data: None, public_key, nonce: None };
+    match nsm_request(req)? {
         Response::Attestation { document } => Ok(document),
         other => anyhow::bail!("Unexpected NSM response: {:?}", other),
     }
 }
 
+fn nsm_request(req: Request) -> Result<Response> {
+    // SAFETY: this calls into the NSM driver which expects a valid FD and buffers.
+    let fd = unsafe { nsm_init() };
+    if fd < 0 {
+        anyhow::bail!("nsm_init failed");
+    }
+    let resp = unsafe { nsm_process_request(fd, req) };
+    let _ = unsafe { nsm_exit(fd) };
+    Ok(resp)
+}
+
+// Establish the process signing key once at boot.
+// Inside a Nitro Enclave the seed comes from NSM GetRandom and env seeds are refused outright;
+// with NAUTILUS_KMS_KEY_ID set it is sealed with KMS and kept across restarts (see kms_seal),
+// otherwise every boot gets a fresh key.
+// Outside an enclave the key is random per boot unless NAUTILUS_INSECURE_DEV_KEY=1 opts into the
+// deterministic NAUTILUS_SIGNING_SEED path for local development.
+pub async fn load_signing_key(kms: &KmsConfig, sealed_dir: Option<&Path>) -> Result<Keypair> {
+    let insecure_dev = env::var("NAUTILUS_INSECURE_DEV_KEY")
+        .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+        .unwrap_or(false);
+    let env_seed_set = env::var("NAUTILUS_SIGNING_SEED").is_ok();
+
+    let kp = if Path::new("/dev/nsm").exists() {
+        if env_seed_set || insecure_dev {
+            anyhow::bail!("NAUTILUS_SIGNING_SEED / NAUTILUS_INSECURE_DEV_KEY are not allowed inside an enclave");
+        }
+        if kms.enabled() {
+            let public_key = |seed: &[u8; 32]| Ok(keypair_from_seed(seed)?.public.to_bytes());
+            keypair_from_seed(&kms_seal::load_or_seal(kms, sealed_dir, public_key).await?)?
+        } else {
+            info!("Deriving signing key from NSM GetRandom entropy");
+            keypair_from_seed(&nsm_random_seed()?)?
+        }
+    } else if kms.enabled() {
+        anyhow::bail!("NAUTILUS_KMS_KEY_ID needs a Nitro Enclave: KMS only releases the key to an attested recipient");
+    } else if insecure_dev {
+        warn!("NAUTILUS_INSECURE_DEV_KEY=1: signing key derived from env seed, NOT for production");
+        ed25519_keypair_from_seed()?
+    } else if env_seed_set {
+        anyhow::bail!("NAUTILUS_SIGNING_SEED requires NAUTILUS_INSECURE_DEV_KEY=1");
+    } else {
+        info!("No enclave detected, generating an ephemeral signing key from OS entropy");
+        keypair_from_seed(&fresh_seed()?)?
+    };
+    info!(public_key = %hex::encode(kp.public.to_bytes()), "Signing key ready");
+    Ok(kp)
+}
+
+// NSM GetRandom returns a device-defined number of bytes; gather until we have a full seed.
+pub fn nsm_random_seed() -> Result<[u8; 32]> {
+    let mut seed = Vec::with_capacity(32);
+    while seed.len() < 32 {
+        match nsm_request(Request::GetRandom)? {
+            Response::GetRandom { random } if !random.is_empty() => seed.extend_from_slice(&random),
+            other => anyhow::bail!("Unexpected NSM GetRandom response: {:?}", other),
+        }
+    }
+    let mut out = [0u8; 32];
+    out.copy_from_slice(&seed[..32]);
+    Ok(out)
+}
+
+fn random_nonce() -> Result<[u8; 16]> {
+    let mut nonce = [0u8; 16];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut nonce)
+        .map_err(|_| anyhow::anyhow!("OS randomness unavailable"))?;
+    Ok(nonce)
+}
+
+// Seed for a new signing key (boot or rotation): NSM GetRandom in an enclave, OS entropy otherwise.
+pub fn fresh_seed() -> Result<[u8; 32]> {
+    if Path::new("/dev/nsm").exists() {
+        return nsm_random_seed();
+    }
+    let mut seed = [0u8; 32];
+    ring::rand::SecureRandom::fill(&ring::rand::SystemRandom::new(), &mut seed)
+        .map_err(|_| anyhow::anyhow!("OS randomness unavailable"))?;
+    Ok(seed)
+}
+
 fn ed25519_keypair_from_seed() -> Result<Keypair> {
     // Derive a 32-byte seed from env var NAUTILUS_SIGNING_SEED (any string), else default.
     let seed_src = env::var("NAUTILUS_SIGNING_SEED").unwrap_or_else(|_| "zkdatavault-dev-seed".to_string());
@@ -102,9 +715,72 @@ fn ed25519_keypair_from_seed() -> Result<Keypair> {
     let digest = hasher.finalize();
     let mut seed = [0u8; 32];
     seed.copy_from_slice(&digest[..32]);
-    let secret = ed25519_dalek::SecretKey::from_bytes(&seed)?;
+    keypair_from_seed(&seed)
+}
+
+pub fn keypair_from_seed(seed: &[u8; 32]) -> Result<Keypair> {
+    let secret = ed25519_dalek::SecretKey::from_bytes(seed)?;
     let public: PublicKey = (&secret).into();
     Ok(Keypair { secret, public })
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_usage::KeyUsagePolicy;
+    use ed25519_dalek::Verifier;
+
+    #[tokio::test]
+    async fn test_unmeasured_outside_enclave() {
+        let kp = keypair_from_seed(&[7u8; 32]).unwrap();
+        let usage = KeyUsageMonitor::new(KeyUsagePolicy::from_env());
+        let claim = QualityClaim {
+            blob_id: "b".into(),
+            source_type: "walrus".into(),
+            quality_score: 80,
+            category: "general".into(),
+            degradations: vec![],
+            content_sha256: None,
+            plaintext_sha256: None,
+            report_sha256: None,
+            screening: None,
+            sampling: None,
+            sui_object_id: None,
+            config_hash: String::new(),
+            walrus_profile: "testnet".into(),
+            co_sign: false,
+            evidence: BTreeMap::new(),
+            scoring: None,
+            poisoning_risk: None,
+            drift: None,
+            envelope: EnvelopeEncoding::Json,
+            validity: Some(Duration::from_secs(60)),
+        };
+        let bytes = generate_attestation(&kp, SignatureScheme::Ed25519, PayloadEncoding::Json, &usage, None, &claim).await.unwrap();
+        let env: AttestationEnvelope = serde_json::from_slice(&bytes).unwrap();
+        assert_eq!(env.format, "ed25519-v2");
+        assert_eq!(env.data.enclave_measurement, UNMEASURED);
+        assert_eq!(env.data.expires_at, Some(env.data.timestamp + 60_000));
+        assert!(env.data.pcrs.is_empty());
+        let json: serde_json::Value = serde_json::from_slice(&bytes).unwrap();
+        assert!(json["data"].get("pcrs").is_none());
+
+        // The label is part of the signed payload.
+        let signed = nautilus_canonical::canonical_bytes(&env.data).unwrap();
+        let sig = ed25519_dalek::Signature::from_bytes(&STANDARD.decode(env.signature_b64.unwrap()).unwrap()).unwrap();
+        assert!(kp.public.verify(&signed, &sig).is_ok());
 
+        // secp256k1 over BCS, the pairing a Move contract checks.
+        let bytes = generate_attestation(&kp, SignatureScheme::Secp256k1, PayloadEncoding::Bcs, &usage, None, &claim);
+        let env: AttestationEnvelope = serde_json::from_slice(&bytes.await.unwrap()).unwrap();
+        assert_eq!(env.format, "secp256k1-v2");
+        assert_eq!(env.canonicalization.as_deref(), Some(bcs_payload::CANONICALIZATION));
+        assert_eq!(env.data.nonce.as_ref().map(String::len), Some(32));
+        let public_key = STANDARD.decode(env.public_key_b64.as_deref().unwrap()).unwrap();
+        assert_eq!(env.key_id, Some(key_id_of(&public_key)));
+        let signed = signed_bytes(&env).unwrap();
+        assert_eq!(env.payload_sha256, Some(hex::encode(Sha256::digest(&signed))));
+        let serialized = STANDARD.decode(env.signature_b64.as_deref().unwrap()).unwrap();
+        assert_eq!(secp256k1::verify(&serialized, &signed), Ok(public_key));
+    }
+}
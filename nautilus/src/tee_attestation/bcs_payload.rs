@@ -0,0 +1,92 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{AttestationData, UNMEASURED};
+
+// BCS-encoded signed message, for Move contracts, which cannot parse JSON. Under
+// NAUTILUS_PAYLOAD_ENCODING=bcs the envelope's signatures are over the BCS bytes of
+// AttestationMessage instead of the canonical JSON, so a contract declaring
+//
+//   public struct AttestationMessage has copy, drop {
+//       blob_id: std::string::String,
+//       quality_score: u8,
+//       timestamp_ms: u64,
+//       enclave_measurement: vector<u8>,
+//       nonce: vector<u8>,
+//       data_sha256: vector<u8>,
+//   }
+//
+// rebuilds the same bytes with `sui::bcs::to_bytes` from the values it needs, then checks the
+// signature over them. Fields are in declaration order, which BCS preserves. The measurement is
+// PCR0's raw bytes, empty outside an enclave. `nonce` is the data's random nonce, which the
+// contract can record to refuse replays. `data_sha256`, SHA-256 of the canonical JSON of the
+// whole data, keeps every other field (PCRs, config hash, scoring...) under the signature.
+
+pub const CANONICALIZATION: &str = "bcs-v1";
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttestationMessage {
+    pub blob_id: String,
+    pub quality_score: u8,
+    pub timestamp_ms: u64,
+    pub enclave_measurement: Vec<u8>,
+    pub nonce: Vec<u8>,
+    pub data_sha256: Vec<u8>,
+}
+
+impl AttestationMessage {
+    pub fn new(data: &AttestationData) -> Result<Self> {
+        let enclave_measurement = match data.enclave_measurement.as_str() {
+            UNMEASURED => Vec::new(),
+            pcr0 => hex::decode(pcr0).context("enclave_measurement is not hex")?,
+        };
+        let nonce = data.nonce.as_deref().map(hex::decode).transpose().context("nonce is not hex")?;
+        let json = nautilus_canonical::canonical_bytes(data).context("serialize AttestationData")?;
+        Ok(Self {
+            blob_id: data.blob_id.clone(),
+            quality_score: data.quality_score,
+            timestamp_ms: data.timestamp,
+            enclave_measurement,
+            nonce: nonce.unwrap_or_default(),
+            data_sha256: Sha256::digest(json).to_vec(),
+        })
+    }
+}
+
+pub fn to_bytes(data: &AttestationData) -> Result<Vec<u8>> {
+    bcs::to_bytes(&AttestationMessage::new(data)?).context("BCS-encode attestation message")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bcs_message_layout() {
+        let data: AttestationData = serde_json::from_value(serde_json::json!({
+            "blob_id": "ab", "quality_score": 90, "timestamp": 258, "enclave_measurement": "0a0b",
+            "nonce": "ff00",
+        }))
+        .unwrap();
+        let bytes = to_bytes(&data).unwrap();
+        let json_sha256 = Sha256::digest(nautilus_canonical::canonical_bytes(&data).unwrap());
+        // ULEB128 length prefixes, little-endian integers, fields in declaration order.
+        let expected = [
+            &[2, b'a', b'b'][..],
+            &[90],
+            &[2, 1, 0, 0, 0, 0, 0, 0],
+            &[2, 0x0a, 0x0b],
+            &[2, 0xff, 0x00],
+            &[32],
+            &json_sha256,
+        ]
+        .concat();
+        assert_eq!(bytes, expected);
+        assert_eq!(bcs::from_bytes::<AttestationMessage>(&bytes).unwrap(), AttestationMessage::new(&data).unwrap());
+
+        let unmeasured = AttestationData { enclave_measurement: UNMEASURED.into(), nonce: None, ..data };
+        let message = AttestationMessage::new(&unmeasured).unwrap();
+        assert!(message.enclave_measurement.is_empty() && message.nonce.is_empty());
+    }
+}
@@ -0,0 +1,226 @@
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use blst::min_pk::{AggregatePublicKey, AggregateSignature, PublicKey, SecretKey, Signature};
+use blst::BLST_ERROR;
+use ed25519_dalek::Keypair;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use super::{AttestationData, AttestationEnvelope};
+
+// BLS12-381 attestation signatures that aggregate across enclave replicas. Replicas verifying the
+// same blob under the same config and sample seed reach the same result, but finish at different
+// times, so what they sign is the quorum message: the canonical JSON payload, `timestamp` cleared
+// and `expires_at` rounded up to a whole minute (see quorum_expiry).
+// Their signatures over it add up to one, which a Move contract checks in a single
+// `sui::bls12381::bls12381_min_pk_verify(signature, aggregate_public_key, message)` instead of one
+// check per replica. Keys are G1 (48 bytes) and signatures G2 (96 bytes), with Sui's DST for
+// that call. Summing public keys is only safe for keys whose holders proved possession of the
+// secret (a rogue key can cancel the others out): GET /keys publishes each key's proof, and a
+// verifier must admit a key to its quorum set only after checking it with `verify_possession`.
+// The key is derived from the ed25519 signing key's seed, so it rotates and is sealed with it.
+
+pub const SIGNATURE_DST: &[u8] = b"BLS_SIG_BLS12381G2_XMD:SHA-256_SSWU_RO_NUL_";
+pub const POSSESSION_DST: &[u8] = b"BLS_POP_BLS12381G2_XMD:SHA-256_SSWU_RO_POP_";
+pub const QUORUM_FORMAT: &str = "bls12381-quorum-v1";
+const KEY_INFO: &[u8] = b"nautilus-bls12381-v1";
+// Granularity of `expires_at` under this scheme.
+pub const EXPIRY_ROUNDING_MS: u64 = 60_000;
+
+fn secret_key(kp: &Keypair) -> Result<SecretKey> {
+    SecretKey::key_gen(kp.secret.as_bytes(), KEY_INFO).map_err(|e| anyhow::anyhow!("BLS key generation: {:?}", e))
+}
+
+// Compressed G1 point.
+pub fn public_key(kp: &Keypair) -> Result<Vec<u8>> {
+    Ok(secret_key(kp)?.sk_to_pk().compress().to_vec())
+}
+
+pub fn sign(kp: &Keypair, message: &[u8]) -> Result<Vec<u8>> {
+    Ok(secret_key(kp)?.sign(message, SIGNATURE_DST, &[]).compress().to_vec())
+}
+
+// Signature over the public key itself, under the proof-of-possession DST.
+pub fn proof_of_possession(kp: &Keypair) -> Result<Vec<u8>> {
+    let key = secret_key(kp)?;
+    Ok(key.sign(&key.sk_to_pk().compress(), POSSESSION_DST, &[]).compress().to_vec())
+}
+
+pub fn verify(signature: &[u8], message: &[u8], public_key: &[u8]) -> Result<(), String> {
+    let key = PublicKey::uncompress(public_key).map_err(|e| format!("BLS public key: {:?}", e))?;
+    let signature = Signature::uncompress(signature).map_err(|e| format!("BLS signature: {:?}", e))?;
+    match signature.verify(true, message, SIGNATURE_DST, &[], &key, true) {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        _ => Err("BLS signature does not verify".into()),
+    }
+}
+
+pub fn verify_possession(public_key: &[u8], proof: &[u8]) -> Result<(), String> {
+    let key = PublicKey::uncompress(public_key).map_err(|e| format!("BLS public key: {:?}", e))?;
+    let proof = Signature::uncompress(proof).map_err(|e| format!("BLS proof of possession: {:?}", e))?;
+    match proof.verify(true, public_key, POSSESSION_DST, &[], &key, true) {
+        BLST_ERROR::BLST_SUCCESS => Ok(()),
+        _ => Err("BLS proof of possession does not verify".into()),
+    }
+}
+
+// An expiry replicas agree on: `expires_at` follows each replica's own clock, so it is rounded up
+// to a whole minute, and replicas finishing within the same minute sign the same message.
+pub fn quorum_expiry(expires_at: u64) -> u64 {
+    expires_at.div_ceil(EXPIRY_ROUNDING_MS).saturating_mul(EXPIRY_ROUNDING_MS)
+}
+
+// What BLS signs: the canonical payload with the replica-specific timestamp cleared (and nonce,
+// under BCS encoding, dropped), whatever the payload encoding. `expires_at` stays in, so the
+// signature vouches for it; envelopes under this scheme carry it rounded (see quorum_expiry).
+pub fn quorum_message(data: &AttestationData) -> Result<Vec<u8>> {
+    let mut value = serde_json::to_value(data).context("serialize AttestationData")?;
+    value["timestamp"] = 0.into();
+    if let Some(fields) = value.as_object_mut() {
+        fields.remove("nonce");
+    }
+    nautilus_canonical::canonical_bytes(&value).context("canonicalize quorum message")
+}
+
+// Replica attestations folded into one signature. `data` is the agreed payload, `timestamp` 0.
+#[derive(Serialize, Deserialize)]
+pub struct QuorumAttestation {
+    pub format: String,
+    pub data: AttestationData,
+    pub message_sha256: String,
+    pub signature_b64: String,
+    // Signers, in the order given; their sum is `aggregate_public_key_b64`.
+    pub public_keys_b64: Vec<String>,
+    pub aggregate_public_key_b64: String,
+}
+
+// Aggregates bls12381 envelopes (NSM ones signed under the scheme included) over the same
+// quorum message. Each is verified first, and each key may count once.
+pub fn aggregate(envelopes: &[AttestationEnvelope]) -> Result<QuorumAttestation> {
+    let Some(first) = envelopes.first() else {
+        bail!("no attestations to aggregate");
+    };
+    let message = quorum_message(&first.data)?;
+    let mut keys = Vec::new();
+    let mut signatures = Vec::new();
+    for (i, envelope) in envelopes.iter().enumerate() {
+        let (Some(key_b64), Some(signature_b64)) = (&envelope.public_key_b64, &envelope.signature_b64) else {
+            bail!("attestation {} carries no signature", i);
+        };
+        let key = STANDARD.decode(key_b64).with_context(|| format!("attestation {}: public_key_b64", i))?;
+        let signature = STANDARD.decode(signature_b64).with_context(|| format!("attestation {}: signature_b64", i))?;
+        if quorum_message(&envelope.data)? != message {
+            bail!("attestation {} attests a different result for the blob", i);
+        }
+        verify(&signature, &message, &key).map_err(|e| anyhow::anyhow!("attestation {}: {}", i, e))?;
+        if keys.contains(&key) {
+            bail!("attestation {} repeats a signer", i);
+        }
+        keys.push(key);
+        signatures.push(Signature::uncompress(&signature).map_err(|e| anyhow::anyhow!("{:?}", e))?);
+    }
+    let signature = AggregateSignature::aggregate(&signatures.iter().collect::<Vec<_>>(), false)
+        .map_err(|e| anyhow::anyhow!("aggregate signatures: {:?}", e))?
+        .to_signature();
+    let data = serde_json::from_slice(&message).context("quorum message")?;
+    Ok(QuorumAttestation {
+        format: QUORUM_FORMAT.to_string(),
+        data,
+        message_sha256: hex::encode(Sha256::digest(&message)),
+        signature_b64: STANDARD.encode(signature.compress()),
+        aggregate_public_key_b64: STANDARD.encode(aggregate_keys(&keys)?),
+        public_keys_b64: keys.iter().map(|k| STANDARD.encode(k)).collect(),
+    })
+}
+
+// Checks the aggregate signature over `data` by the listed keys; whether those keys are trusted
+// enclave keys with checked proofs of possession, and enough of them, is the caller's to decide.
+pub fn verify_quorum(quorum: &QuorumAttestation) -> Result<(), String> {
+    let message = quorum_message(&quorum.data).map_err(|e| e.to_string())?;
+    let keys = quorum
+        .public_keys_b64
+        .iter()
+        .map(|k| STANDARD.decode(k).map_err(|e| format!("public_keys_b64: {}", e)))
+        .collect::<Result<Vec<_>, _>>()?;
+    let aggregate = aggregate_keys(&keys).map_err(|e| e.to_string())?;
+    if STANDARD.encode(&aggregate) != quorum.aggregate_public_key_b64 {
+        return Err("aggregate_public_key_b64 is not the sum of public_keys_b64".into());
+    }
+    let signature = STANDARD.decode(&quorum.signature_b64).map_err(|e| format!("signature_b64: {}", e))?;
+    verify(&signature, &message, &aggregate)
+}
+
+fn aggregate_keys(keys: &[Vec<u8>]) -> Result<Vec<u8>> {
+    let keys = keys
+        .iter()
+        .map(|k| PublicKey::uncompress(k).map_err(|e| anyhow::anyhow!("BLS public key: {:?}", e)))
+        .collect::<Result<Vec<_>>>()?;
+    let aggregate = AggregatePublicKey::aggregate(&keys.iter().collect::<Vec<_>>(), true)
+        .map_err(|e| anyhow::anyhow!("aggregate public keys: {:?}", e))?;
+    Ok(aggregate.to_public_key().compress().to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tee_attestation::keypair_from_seed;
+
+    fn envelope(kp: &Keypair, timestamp: u64, score: u8) -> AttestationEnvelope {
+        let data: AttestationData = serde_json::from_value(serde_json::json!({
+            "blob_id": "blob", "source_type": "walrus", "quality_score": score, "timestamp": timestamp,
+            "enclave_measurement": "unmeasured", "category": "general", "degradations": [],
+        }))
+        .unwrap();
+        let message = quorum_message(&data).unwrap();
+        AttestationEnvelope {
+            format: "bls12381-v2".into(),
+            data,
+            signature_b64: Some(STANDARD.encode(sign(kp, &message).unwrap())),
+            public_key_b64: Some(STANDARD.encode(public_key(kp).unwrap())),
+            key_id: None,
+            nsm_document_b64: None,
+            platform_evidence_b64: None,
+            canonicalization: None,
+            payload_sha256: None,
+            cosignature: None,
+        }
+    }
+
+    #[test]
+    fn test_aggregate_replica_attestations() {
+        let replicas: Vec<Keypair> = (1..=3).map(|i| keypair_from_seed(&[i; 32]).unwrap()).collect();
+        for kp in &replicas {
+            let key = public_key(kp).unwrap();
+            assert!(verify_possession(&key, &proof_of_possession(kp).unwrap()).is_ok());
+            assert!(verify_possession(&key, &sign(kp, &key).unwrap()).is_err());
+        }
+        let envelopes: Vec<_> = replicas.iter().enumerate().map(|(i, kp)| envelope(kp, 1_000 + i as u64, 80)).collect();
+        let quorum = aggregate(&envelopes).unwrap();
+        assert_eq!(quorum.data.timestamp, 0);
+        assert_eq!(quorum.public_keys_b64.len(), 3);
+        assert!(verify_quorum(&quorum).is_ok());
+
+        let mut dropped = serde_json::from_value::<QuorumAttestation>(serde_json::to_value(&quorum).unwrap()).unwrap();
+        dropped.public_keys_b64.pop();
+        assert!(verify_quorum(&dropped).is_err());
+        let mut altered = quorum;
+        altered.data.quality_score = 81;
+        assert!(verify_quorum(&altered).is_err());
+
+        let disagreeing = vec![envelope(&replicas[0], 1, 80), envelope(&replicas[1], 2, 70)];
+        let error = |envelopes: &[AttestationEnvelope]| aggregate(envelopes).err().map(|e| e.to_string());
+        assert!(error(&disagreeing).unwrap().contains("different result"));
+        let repeated = vec![envelope(&replicas[0], 1, 80), envelope(&replicas[0], 2, 80)];
+        assert!(error(&repeated).unwrap().contains("repeats a signer"));
+
+        // The rounded expiry is signed: replicas agree on it, and a quorum can't be given another.
+        assert_eq!((quorum_expiry(60_000), quorum_expiry(60_001), quorum_expiry(119_999)), (60_000, 120_000, 120_000));
+        let mut expiring = envelope(&replicas[0], 1, 80);
+        expiring.data.expires_at = Some(quorum_expiry(61_000));
+        assert_ne!(quorum_message(&expiring.data).unwrap(), quorum_message(&envelopes[0].data).unwrap());
+        let mut later: AttestationData = serde_json::from_value(serde_json::to_value(&expiring.data).unwrap()).unwrap();
+        later.timestamp = 2;
+        assert_eq!(quorum_message(&later).unwrap(), quorum_message(&expiring.data).unwrap());
+    }
+}
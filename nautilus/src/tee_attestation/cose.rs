@@ -0,0 +1,189 @@
+use anyhow::{bail, Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Keypair, Signer, Verifier};
+use serde_bytes::ByteBuf;
+use serde_cbor::Value;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+
+use super::{key_id_of, secp256k1, AttestationEnvelope, SignatureScheme};
+
+// COSE_Sign1 (RFC 8152) rendering of an envelope, the structure the NSM document itself uses, for
+// callers that pass `"envelope_encoding": "cose"`. Binary CBOR with raw keys and signatures instead
+// of base64 inside JSON, it makes for smaller on-chain calldata. The layout:
+//   - protected header: alg (1; EdDSA or ES256K), kid (4; the 8 key ID bytes), and the JSON
+//     envelope's "format" and "canonicalization";
+//   - unprotected header: "public_key", and "nsm_document", "platform_evidence" and "cosignature"
+//     when present;
+//   - payload: the canonical JSON of the envelope's data;
+//   - signature: the enclave key's over the Sig_structure, which covers the protected header too.
+// The COSE signature stands in for `signature_b64`; the NSM document and co-signature still bind
+// the payload as `canonicalization` serializes it. BLS signatures have no COSE algorithm, so a
+// bls12381 service only produces JSON envelopes. The bytes are tagged COSE_Sign1 (tag 18).
+
+// COSE algorithm identifiers.
+const EDDSA: i128 = -8;
+const ES256K: i128 = -47;
+// CBOR head of tag 18, which serde_cbor writes only with its "tags" feature.
+const COSE_SIGN1_TAG: u8 = 0xd2;
+
+fn text(s: &str) -> Value {
+    Value::Text(s.to_string())
+}
+
+fn sig_structure(protected: &[u8], payload: &[u8]) -> Result<Vec<u8>, serde_cbor::Error> {
+    serde_cbor::to_vec(&("Signature1", ByteBuf::from(protected), ByteBuf::new(), ByteBuf::from(payload)))
+}
+
+// Re-encodes an envelope generated with `kp` under `scheme`, signing it anew.
+pub fn encode(kp: &Keypair, scheme: SignatureScheme, envelope: &AttestationEnvelope) -> Result<Vec<u8>> {
+    let alg = match scheme {
+        SignatureScheme::Ed25519 => EDDSA,
+        SignatureScheme::Secp256k1 => ES256K,
+        SignatureScheme::Bls12381 => bail!("bls12381 attestations have no COSE encoding"),
+    };
+    let public_key = scheme.public_key(kp)?;
+    let mut protected = BTreeMap::from([
+        (Value::Integer(1), Value::Integer(alg)),
+        (Value::Integer(4), Value::Bytes(hex::decode(key_id_of(&public_key))?)),
+        (text("format"), text(&envelope.format)),
+    ]);
+    if let Some(canonicalization) = &envelope.canonicalization {
+        protected.insert(text("canonicalization"), text(canonicalization));
+    }
+    let mut unprotected = BTreeMap::from([(text("public_key"), Value::Bytes(public_key))]);
+    if let Some(doc) = &envelope.nsm_document_b64 {
+        unprotected.insert(text("nsm_document"), Value::Bytes(STANDARD.decode(doc).context("nsm_document_b64")?));
+    }
+    if let Some(evidence) = &envelope.platform_evidence_b64 {
+        let evidence = STANDARD.decode(evidence).context("platform_evidence_b64")?;
+        unprotected.insert(text("platform_evidence"), Value::Bytes(evidence));
+    }
+    if let Some(cosignature) = &envelope.cosignature {
+        unprotected.insert(text("cosignature"), serde_cbor::value::to_value(cosignature)?);
+    }
+    let protected = serde_cbor::to_vec(&Value::Map(protected))?;
+    let payload = nautilus_canonical::canonical_bytes(&envelope.data).context("serialize AttestationData")?;
+    let to_sign = sig_structure(&protected, &payload)?;
+    let signature = match scheme {
+        SignatureScheme::Ed25519 => kp.sign(&to_sign).to_bytes().to_vec(),
+        // COSE takes the bare r || s, without Sui's flag and key.
+        _ => secp256k1::sign(kp, &to_sign)?[1..65].to_vec(),
+    };
+    let sign1 = (ByteBuf::from(protected), Value::Map(unprotected), ByteBuf::from(payload), ByteBuf::from(signature));
+    let mut out = vec![COSE_SIGN1_TAG];
+    out.extend(serde_cbor::to_vec(&sign1)?);
+    Ok(out)
+}
+
+// Checks the COSE signature and kid, and returns the envelope the structure renders, without
+// `signature_b64`. Whether the key is a trusted enclave key, and the NSM document and
+// co-signature, are left to the caller (verify::verify_envelope and cosign).
+pub fn open(bytes: &[u8]) -> Result<AttestationEnvelope, String> {
+    // serde_cbor skips the tag.
+    let (protected, unprotected, payload, signature): (ByteBuf, BTreeMap<String, Value>, ByteBuf, ByteBuf) =
+        serde_cbor::from_slice(bytes).map_err(|e| format!("COSE_Sign1: {}", e))?;
+    let header: BTreeMap<Value, Value> =
+        serde_cbor::from_slice(&protected).map_err(|e| format!("protected header: {}", e))?;
+    let header_text = |label: &str| match header.get(&text(label)) {
+        Some(Value::Text(value)) => Ok(Some(value.clone())),
+        None => Ok(None),
+        Some(_) => Err(format!("protected header {} is not text", label)),
+    };
+    let Some(Value::Bytes(public_key)) = unprotected.get("public_key") else {
+        return Err("no public_key in the unprotected header".into());
+    };
+    let signed = sig_structure(&protected, &payload).map_err(|e| e.to_string())?;
+    match header.get(&Value::Integer(1)) {
+        Some(Value::Integer(EDDSA)) => {
+            let key = ed25519_dalek::PublicKey::from_bytes(public_key).map_err(|_| "public_key is not an ed25519 key")?;
+            let signature = ed25519_dalek::Signature::from_bytes(&signature).map_err(|_| "malformed EdDSA signature")?;
+            key.verify(&signed, &signature).map_err(|_| "COSE signature does not verify")?;
+        }
+        Some(Value::Integer(ES256K)) => {
+            let serialized = [&[secp256k1::SUI_FLAG][..], &signature, public_key].concat();
+            secp256k1::verify(&serialized, &signed)?;
+        }
+        other => return Err(format!("unsupported COSE algorithm {:?}", other)),
+    }
+    let key_id = key_id_of(public_key);
+    if header.get(&Value::Integer(4)).is_some_and(|kid| kid != &Value::Bytes(hex::decode(&key_id).unwrap_or_default())) {
+        return Err("kid does not name public_key".into());
+    }
+    let evidence = |label: &str| match unprotected.get(label) {
+        Some(Value::Bytes(evidence)) => Ok(Some(STANDARD.encode(evidence))),
+        None => Ok(None),
+        Some(_) => Err(format!("{} is not a byte string", label)),
+    };
+    let cosignature = unprotected
+        .get("cosignature")
+        .map(|c| serde_cbor::value::from_value(c.clone()))
+        .transpose()
+        .map_err(|e| format!("cosignature: {}", e))?;
+    let mut envelope = AttestationEnvelope {
+        format: header_text("format")?.ok_or("no format in the protected header")?,
+        data: serde_json::from_slice(&payload).map_err(|e| format!("payload: {}", e))?,
+        signature_b64: None,
+        public_key_b64: Some(STANDARD.encode(public_key)),
+        key_id: Some(key_id),
+        nsm_document_b64: evidence("nsm_document")?,
+        platform_evidence_b64: evidence("platform_evidence")?,
+        canonicalization: header_text("canonicalization")?,
+        payload_sha256: None,
+        cosignature,
+    };
+    envelope.payload_sha256 = super::signed_bytes(&envelope).ok().map(|bytes| hex::encode(Sha256::digest(bytes)));
+    Ok(envelope)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tee_attestation::keypair_from_seed;
+
+    fn envelope(format: &str) -> AttestationEnvelope {
+        let data = serde_json::from_value(serde_json::json!({
+            "blob_id": "blob", "source_type": "walrus", "quality_score": 80, "timestamp": 1_000,
+            "enclave_measurement": "unmeasured", "category": "general", "degradations": [],
+        }))
+        .unwrap();
+        AttestationEnvelope {
+            format: format.into(),
+            data,
+            signature_b64: Some(STANDARD.encode([0u8; 64])),
+            public_key_b64: None,
+            key_id: None,
+            nsm_document_b64: None,
+            platform_evidence_b64: None,
+            canonicalization: Some(nautilus_canonical::CANONICALIZATION.into()),
+            payload_sha256: None,
+            cosignature: None,
+        }
+    }
+
+    #[test]
+    fn test_cose_round_trip() {
+        let kp = keypair_from_seed(&[3u8; 32]).unwrap();
+        for (scheme, format) in [(SignatureScheme::Ed25519, "ed25519-v2"), (SignatureScheme::Secp256k1, "secp256k1-v2")] {
+            let json = envelope(format);
+            let bytes = encode(&kp, scheme, &json).unwrap();
+            assert_eq!(bytes[0], COSE_SIGN1_TAG);
+            assert!(bytes.len() < serde_json::to_vec(&json).unwrap().len());
+            let opened = open(&bytes).unwrap();
+            assert_eq!(opened.format, format);
+            assert_eq!(opened.data.blob_id, "blob");
+            assert_eq!(opened.public_key_b64, Some(STANDARD.encode(scheme.public_key(&kp).unwrap())));
+            assert_eq!(opened.key_id, Some(key_id_of(&scheme.public_key(&kp).unwrap())));
+            let payload = nautilus_canonical::canonical_bytes(&opened.data).unwrap();
+            assert_eq!(opened.payload_sha256, Some(hex::encode(Sha256::digest(payload))));
+
+            // A changed payload byte breaks the signature.
+            let at = bytes.windows(4).position(|w| w == b"blob").unwrap();
+            let mut tampered = bytes.clone();
+            tampered[at] = b'g';
+            assert!(open(&tampered).is_err());
+        }
+        assert!(encode(&kp, SignatureScheme::Bls12381, &envelope("bls12381-v2")).is_err());
+    }
+}
@@ -0,0 +1,128 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::Keypair;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{key_id, key_id_of, provider, sign_statement, SignatureScheme, SignedStatement, UNMEASURED};
+use crate::key_usage::KeyUsageMonitor;
+
+// Enclave identity for GET /attestation?nonce=<hex>: fresh evidence that the service's current
+// attestation key lives in the measured TEE, independent of any dataset, so a client can decide
+// to trust the enclave before sending it work. The statement names the key and echoes the
+// caller's nonce, which makes the evidence unreplayable. Its canonical JSON's SHA-256 is bound
+// into the platform's evidence like a verification payload's (an NSM document's user data, a
+// SEV-SNP report's or TPM quote's binding; see tee_attestation::evidence_binding). The key also
+// signs the statement, proving possession; outside a TEE that signature is all there is and
+// the format says so.
+
+const PURPOSE: &str = "nautilus-enclave-identity";
+// NSM accepts up to 512 bytes of nonce; callers need far less.
+const MAX_NONCE_LEN: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IdentityStatement {
+    // Fixed, so the statement can't be passed off as a verification payload.
+    pub purpose: String,
+    pub scheme: String,
+    pub public_key_b64: String,
+    pub key_id: String,
+    // The caller's nonce, hex.
+    pub nonce: String,
+    pub timestamp: u64,
+    pub enclave_measurement: String,
+    #[serde(default)]
+    pub pcrs: BTreeMap<String, String>,
+    pub config_hash: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct IdentityAttestation {
+    pub statement: IdentityStatement,
+    // Format "<platform or scheme>-identity-v1", e.g. "nsm-document-identity-v1".
+    #[serde(flatten)]
+    pub signed: SignedStatement,
+    // For secp256k1 and BLS services, ID of the ed25519 service key (see GET /keys) the attested
+    // key is derived from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub service_key_id: Option<String>,
+}
+
+// Parses the `nonce` query parameter: 1 to MAX_NONCE_LEN bytes, hex.
+pub fn parse_nonce(nonce: Option<&str>) -> Result<Vec<u8>> {
+    let nonce = nonce.filter(|n| !n.is_empty()).context("nonce is required")?;
+    let bytes = hex::decode(nonce).context("nonce must be hex")?;
+    anyhow::ensure!(bytes.len() <= MAX_NONCE_LEN, "nonce must be at most {} bytes", MAX_NONCE_LEN);
+    Ok(bytes)
+}
+
+pub fn attest(
+    kp: &Keypair,
+    scheme: SignatureScheme,
+    usage: &KeyUsageMonitor,
+    nonce: &[u8],
+    config_hash: &str,
+) -> Result<IdentityAttestation> {
+    let public_key = scheme.public_key(kp)?;
+    let (enclave_measurement, pcrs) = match provider() {
+        Some(provider) => provider.measurement()?,
+        None => (UNMEASURED.to_string(), BTreeMap::new()),
+    };
+    let statement = IdentityStatement {
+        purpose: PURPOSE.to_string(),
+        scheme: scheme.as_str().to_string(),
+        public_key_b64: STANDARD.encode(&public_key),
+        key_id: key_id_of(&public_key),
+        nonce: hex::encode(nonce),
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        enclave_measurement,
+        pcrs,
+        config_hash: config_hash.to_string(),
+    };
+    let serialized = nautilus_canonical::canonical_bytes(&statement).context("serialize IdentityStatement")?;
+    // Kept out of the alarm windows: a flood of these must not lock the key for verifications.
+    usage.record(&key_id(&kp.public), "identity")?;
+    Ok(IdentityAttestation {
+        signed: sign_statement(kp, scheme, "identity", &serialized)?,
+        service_key_id: (scheme != SignatureScheme::Ed25519).then(|| key_id(&kp.public)),
+        statement,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tee_attestation::{keypair_from_seed, secp256k1};
+    use ed25519_dalek::Verifier;
+    use sha2::{Digest, Sha256};
+
+    #[test]
+    fn test_identity_binds_key_and_nonce() {
+        let kp = keypair_from_seed(&[8u8; 32]).unwrap();
+        let usage = KeyUsageMonitor::new(crate::key_usage::KeyUsagePolicy::from_env());
+        let identity = attest(&kp, SignatureScheme::Ed25519, &usage, b"client nonce", "cfg").unwrap();
+        assert_eq!(identity.signed.format, "ed25519-identity-v1");
+        assert!(identity.signed.evidence_b64.is_none() && identity.service_key_id.is_none());
+        assert_eq!(identity.statement.nonce, hex::encode(b"client nonce"));
+        assert_eq!(identity.statement.public_key_b64, STANDARD.encode(kp.public.to_bytes()));
+        let serialized = nautilus_canonical::canonical_bytes(&identity.statement).unwrap();
+        assert_eq!(identity.signed.statement_sha256, hex::encode(Sha256::digest(&serialized)));
+        let signature = STANDARD.decode(&identity.signed.signature_b64).unwrap();
+        let signature = ed25519_dalek::Signature::from_bytes(&signature).unwrap();
+        assert!(kp.public.verify(&serialized, &signature).is_ok());
+
+        let identity = attest(&kp, SignatureScheme::Secp256k1, &usage, b"n", "cfg").unwrap();
+        assert_eq!(identity.signed.format, "secp256k1-identity-v1");
+        assert_eq!(identity.service_key_id, Some(key_id(&kp.public)));
+        let serialized = nautilus_canonical::canonical_bytes(&identity.statement).unwrap();
+        let signature = STANDARD.decode(&identity.signed.signature_b64).unwrap();
+        assert!(secp256k1::verify(&signature, &serialized).is_ok());
+
+        assert!(parse_nonce(None).is_err());
+        assert!(parse_nonce(Some("not hex")).is_err());
+        assert!(parse_nonce(Some(&"00".repeat(MAX_NONCE_LEN + 1))).is_err());
+        assert_eq!(parse_nonce(Some("abcd")).unwrap(), [0xab, 0xcd]);
+    }
+}
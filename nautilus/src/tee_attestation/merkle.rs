@@ -0,0 +1,237 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::Keypair;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{
+    key_id, key_id_of, provider, sign_statement, AttestationData, PayloadEncoding, SignatureScheme, SignedStatement,
+    UNMEASURED,
+};
+use crate::key_usage::KeyUsageMonitor;
+
+// One attestation for a whole batch (`"attestation": "merkle"` on POST /verify/batch). Each
+// item's AttestationData, serialized as a single attestation would sign it, is a leaf of a
+// Merkle tree built as in RFC 9162 (Certificate Transparency v2): leaves hash as
+// SHA-256(0x00 || bytes), interior nodes as SHA-256(0x01 || left || right), the left subtree
+// holding the largest power of two below the leaf count. The enclave signs, and the TEE attests,
+// only a statement of the root (BatchRoot), so a batch costs one NSM round-trip however many
+// items it has. Every item carries its data and an inclusion proof; a verifier recomputes the
+// leaf, walks the proof to the root (verify_inclusion) and checks the batch attestation as it
+// would an identity attestation.
+
+const PURPOSE: &str = "nautilus-batch-root";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BatchRoot {
+    // Fixed, so the statement can't be passed off as another kind.
+    pub purpose: String,
+    // Hex Merkle tree head over `leaf_count` leaves.
+    pub merkle_root: String,
+    pub leaf_count: u64,
+    // How each leaf's AttestationData is serialized (see PayloadEncoding).
+    pub canonicalization: String,
+    pub timestamp: u64,
+    pub scheme: String,
+    pub public_key_b64: String,
+    pub key_id: String,
+    pub enclave_measurement: String,
+    #[serde(default)]
+    pub pcrs: BTreeMap<String, String>,
+    pub config_hash: String,
+}
+
+#[derive(Debug, Serialize)]
+pub struct BatchAttestation {
+    pub statement: BatchRoot,
+    // Format "<platform or scheme>-batch-v1", e.g. "nsm-document-batch-v1".
+    #[serde(flatten)]
+    pub signed: SignedStatement,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InclusionProof {
+    pub leaf_index: u64,
+    pub leaf_count: u64,
+    // Hex sibling hashes from the leaf up.
+    pub audit_path: Vec<String>,
+}
+
+fn node(left: &[u8; 32], right: &[u8; 32]) -> [u8; 32] {
+    Sha256::new().chain_update([1u8]).chain_update(left).chain_update(right).finalize().into()
+}
+
+pub fn leaf_hash(serialized: &[u8]) -> [u8; 32] {
+    Sha256::new().chain_update([0u8]).chain_update(serialized).finalize().into()
+}
+
+// Largest power of two below `n` (n > 1).
+fn split(n: usize) -> usize {
+    1 << (usize::BITS - 1 - (n - 1).leading_zeros())
+}
+
+// Tree head of hashed leaves; SHA-256 of nothing for none.
+pub fn root(leaves: &[[u8; 32]]) -> [u8; 32] {
+    match leaves.len() {
+        0 => Sha256::digest([]).into(),
+        1 => leaves[0],
+        n => {
+            let k = split(n);
+            node(&root(&leaves[..k]), &root(&leaves[k..]))
+        }
+    }
+}
+
+// Sibling hashes from leaf `index` up to the root.
+pub fn audit_path(leaves: &[[u8; 32]], index: usize) -> Vec<[u8; 32]> {
+    if leaves.len() <= 1 {
+        return Vec::new();
+    }
+    let k = split(leaves.len());
+    let (mut path, sibling) = if index < k {
+        (audit_path(&leaves[..k], index), root(&leaves[k..]))
+    } else {
+        (audit_path(&leaves[k..], index - k), root(&leaves[..k]))
+    };
+    path.push(sibling);
+    path
+}
+
+// Whether `serialized` is leaf `proof.leaf_index` of the tree with head `root_hex` (RFC 9162,
+// section 2.1.3.2).
+pub fn verify_inclusion(serialized: &[u8], proof: &InclusionProof, root_hex: &str) -> bool {
+    if proof.leaf_index >= proof.leaf_count {
+        return false;
+    }
+    let (mut index, mut last) = (proof.leaf_index, proof.leaf_count - 1);
+    let mut hash = leaf_hash(serialized);
+    for sibling in &proof.audit_path {
+        let Some(sibling) = hex::decode(sibling).ok().and_then(|s| <[u8; 32]>::try_from(s).ok()) else {
+            return false;
+        };
+        if last == 0 {
+            return false;
+        }
+        if index & 1 == 1 || index == last {
+            hash = node(&sibling, &hash);
+            while index & 1 == 0 && index != 0 {
+                index >>= 1;
+                last >>= 1;
+            }
+        } else {
+            hash = node(&hash, &sibling);
+        }
+        index >>= 1;
+        last >>= 1;
+    }
+    last == 0 && hex::encode(hash).eq_ignore_ascii_case(root_hex)
+}
+
+// Attests `leaves` with one signed root; the proofs are in leaf order.
+pub fn attest(
+    kp: &Keypair,
+    scheme: SignatureScheme,
+    encoding: PayloadEncoding,
+    usage: &KeyUsageMonitor,
+    leaves: &[&AttestationData],
+    config_hash: &str,
+) -> Result<(BatchAttestation, Vec<InclusionProof>)> {
+    let hashes = leaves
+        .iter()
+        .map(|data| encoding.serialize(data).map(|bytes| leaf_hash(&bytes)))
+        .collect::<Result<Vec<_>>>()?;
+    let public_key = scheme.public_key(kp)?;
+    let (enclave_measurement, pcrs) = match provider() {
+        Some(provider) => provider.measurement()?,
+        None => (UNMEASURED.to_string(), BTreeMap::new()),
+    };
+    let statement = BatchRoot {
+        purpose: PURPOSE.to_string(),
+        merkle_root: hex::encode(root(&hashes)),
+        leaf_count: hashes.len() as u64,
+        canonicalization: encoding.canonicalization().to_string(),
+        timestamp: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+        scheme: scheme.as_str().to_string(),
+        public_key_b64: STANDARD.encode(&public_key),
+        key_id: key_id_of(&public_key),
+        enclave_measurement,
+        pcrs,
+        config_hash: config_hash.to_string(),
+    };
+    let serialized = nautilus_canonical::canonical_bytes(&statement).context("serialize BatchRoot")?;
+    usage.authorize(&key_id(&kp.public), "batch")?;
+    let signed = sign_statement(kp, scheme, "batch", &serialized)?;
+    let proofs = (0..hashes.len())
+        .map(|index| InclusionProof {
+            leaf_index: index as u64,
+            leaf_count: hashes.len() as u64,
+            audit_path: audit_path(&hashes, index).iter().map(hex::encode).collect(),
+        })
+        .collect();
+    Ok((BatchAttestation { statement, signed }, proofs))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tee_attestation::keypair_from_seed;
+    use ed25519_dalek::Verifier;
+
+    fn data(blob_id: &str) -> AttestationData {
+        serde_json::from_value(serde_json::json!({
+            "blob_id": blob_id, "source_type": "walrus", "quality_score": 80, "timestamp": 1_000,
+            "enclave_measurement": "unmeasured", "category": "general", "degradations": [],
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_inclusion_proofs() {
+        // Every leaf of trees of 1 to 9 leaves proves into the root, and only at its own index.
+        for n in 1..=9usize {
+            let leaves = (0..n).map(|i| format!("leaf-{}", i).into_bytes()).collect::<Vec<_>>();
+            let hashes = leaves.iter().map(|l| leaf_hash(l)).collect::<Vec<_>>();
+            let head = hex::encode(root(&hashes));
+            for (i, leaf) in leaves.iter().enumerate() {
+                let proof = InclusionProof {
+                    leaf_index: i as u64,
+                    leaf_count: n as u64,
+                    audit_path: audit_path(&hashes, i).iter().map(hex::encode).collect(),
+                };
+                assert!(verify_inclusion(leaf, &proof, &head), "leaf {} of {}", i, n);
+                assert!(!verify_inclusion(b"other", &proof, &head));
+                if n > 1 {
+                    let moved = InclusionProof { leaf_index: ((i + 1) % n) as u64, ..proof.clone() };
+                    assert!(!verify_inclusion(leaf, &moved, &head));
+                }
+            }
+        }
+        // The RFC's three-leaf tree: the third leaf pairs with the head of the first two.
+        let hashes = [leaf_hash(b"a"), leaf_hash(b"b"), leaf_hash(b"c")];
+        assert_eq!(root(&hashes), node(&node(&hashes[0], &hashes[1]), &hashes[2]));
+        assert_eq!(audit_path(&hashes, 2), [node(&hashes[0], &hashes[1])]);
+    }
+
+    #[test]
+    fn test_batch_attestation() {
+        let kp = keypair_from_seed(&[9u8; 32]).unwrap();
+        let usage = KeyUsageMonitor::new(crate::key_usage::KeyUsagePolicy::from_env());
+        let leaves = [data("a"), data("b"), data("c")];
+        let refs = leaves.iter().collect::<Vec<_>>();
+        let encoding = PayloadEncoding::Json;
+        let (batch, proofs) = attest(&kp, SignatureScheme::Ed25519, encoding, &usage, &refs, "cfg").unwrap();
+        assert_eq!(batch.signed.format, "ed25519-batch-v1");
+        assert_eq!(batch.statement.leaf_count, 3);
+        for (leaf, proof) in leaves.iter().zip(&proofs) {
+            let serialized = encoding.serialize(leaf).unwrap();
+            assert!(verify_inclusion(&serialized, proof, &batch.statement.merkle_root));
+        }
+        let serialized = nautilus_canonical::canonical_bytes(&batch.statement).unwrap();
+        let signature = STANDARD.decode(&batch.signed.signature_b64).unwrap();
+        let signature = ed25519_dalek::Signature::from_bytes(&signature).unwrap();
+        assert!(kp.public.verify(&serialized, &signature).is_ok());
+    }
+}
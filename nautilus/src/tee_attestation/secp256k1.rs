@@ -0,0 +1,76 @@
+use anyhow::{Context, Result};
+use ed25519_dalek::Keypair;
+use k256::ecdsa::signature::{Signer, Verifier};
+use k256::ecdsa::{Signature, SigningKey, VerifyingKey};
+use sha2::{Digest, Sha256};
+
+// secp256k1 attestation signatures, for the marketplace Move contract to check on chain with
+// `sui::ecdsa_k1::secp256k1_verify(signature, public_key, payload, 1)` (hash 1: SHA-256).
+// Signatures use Sui's serialization, flag || r || s || compressed public key, with s
+// normalized to the lower half as Sui requires; the contract splits off the 64 signature bytes.
+// The key is derived from the ed25519 signing key's seed, so it rotates and is sealed with it,
+// and one key ring entry (see key_ring) stands for both.
+
+// Sui signature scheme flag for secp256k1 (0x00 is ed25519, 0x02 secp256r1).
+pub const SUI_FLAG: u8 = 0x01;
+// Length of a serialized signature: flag, 64-byte signature, 33-byte compressed key.
+pub const SERIALIZED_LEN: usize = 1 + 64 + 33;
+
+const DERIVATION_DOMAIN: &[u8] = b"nautilus-secp256k1-v1";
+
+pub fn signing_key(kp: &Keypair) -> Result<SigningKey> {
+    let scalar = Sha256::new().chain_update(DERIVATION_DOMAIN).chain_update(kp.secret.as_bytes()).finalize();
+    SigningKey::from_slice(&scalar).context("derived secp256k1 scalar is out of range")
+}
+
+// Compressed SEC1 encoding, as Sui and the contract take it.
+pub fn public_key(kp: &Keypair) -> Result<Vec<u8>> {
+    Ok(signing_key(kp)?.verifying_key().to_encoded_point(true).as_bytes().to_vec())
+}
+
+// Sui-serialized signature over SHA-256 of `message`.
+pub fn sign(kp: &Keypair, message: &[u8]) -> Result<Vec<u8>> {
+    let key = signing_key(kp)?;
+    let signature: Signature = key.sign(message);
+    let signature = signature.normalize_s().unwrap_or(signature);
+    let public_key = key.verifying_key().to_encoded_point(true);
+    Ok([&[SUI_FLAG][..], &signature.to_bytes(), public_key.as_bytes()].concat())
+}
+
+// Checks a Sui-serialized signature over `message`; returns the compressed public key it carries.
+pub fn verify(serialized: &[u8], message: &[u8]) -> Result<Vec<u8>, String> {
+    if serialized.len() != SERIALIZED_LEN || serialized[0] != SUI_FLAG {
+        return Err("not a Sui-serialized secp256k1 signature".into());
+    }
+    let (signature, public_key) = serialized[1..].split_at(64);
+    let signature = Signature::from_slice(signature).map_err(|e| format!("signature: {}", e))?;
+    let key = VerifyingKey::from_sec1_bytes(public_key).map_err(|e| format!("public key: {}", e))?;
+    // k256 rejects high-s signatures, as Sui does.
+    key.verify(message, &signature).map_err(|_| "secp256k1 signature does not verify".to_string())?;
+    Ok(public_key.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sui_serialized_signature() {
+        let kp = super::super::keypair_from_seed(&[7u8; 32]).unwrap();
+        let message = b"{\"blob_id\":\"abc\"}";
+        let serialized = sign(&kp, message).unwrap();
+        assert_eq!(serialized.len(), SERIALIZED_LEN);
+        assert_eq!(serialized[0], SUI_FLAG);
+        assert_eq!(verify(&serialized, message).unwrap(), public_key(&kp).unwrap());
+        assert_eq!(public_key(&kp).unwrap(), public_key(&kp).unwrap());
+
+        assert!(verify(&serialized, b"{}").is_err());
+        let mut wrong_flag = serialized.clone();
+        wrong_flag[0] = 0x00;
+        assert!(verify(&wrong_flag, message).is_err());
+        let other = super::super::keypair_from_seed(&[8u8; 32]).unwrap();
+        let mut wrong_key = serialized[..65].to_vec();
+        wrong_key.extend(public_key(&other).unwrap());
+        assert!(verify(&wrong_key, message).is_err());
+    }
+}
@@ -0,0 +1,197 @@
+use anyhow::{Context, Result};
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::sync::OnceLock;
+
+use super::verify::VerifyError;
+use super::{evidence_binding, AttestationProvider};
+
+// AMD SEV-SNP guest attestation, for confidential VMs rather than Nitro Enclaves. The guest asks
+// the AMD secure processor for an attestation report through the kernel's sev-guest driver. It
+// chooses the report's 64 bytes of REPORT_DATA, the payload and key binding (see
+// evidence_binding). The chip signs the report with its VCEK (ECDSA P-384). A verifier fetches the
+// VCEK certificate for the chip ID and reported TCB from AMD's Key Distribution Service and
+// chains it to AMD's ARK root itself. It then checks the report signature and the binding
+// (verify_report), and compares the launch MEASUREMENT with the image it expects.
+
+pub const DEVICE: &str = "/dev/sev-guest";
+pub const FORMAT: &str = "sev-snp";
+
+// ATTESTATION_REPORT layout, per the SEV-SNP firmware ABI specification.
+pub const REPORT_LEN: usize = 0x4a0;
+const REPORT_DATA: Range<usize> = 0x50..0x90;
+const MEASUREMENT: Range<usize> = 0x90..0xc0;
+// The signature covers everything before it.
+const SIGNED_LEN: usize = 0x2a0;
+// r and s, each in a 72-byte little-endian field of which P-384 uses the low 48 bytes.
+const SIGNATURE_R: Range<usize> = 0x2a0..0x2d0;
+const SIGNATURE_S: Range<usize> = 0x2e8..0x318;
+
+pub struct SevSnp;
+
+impl AttestationProvider for SevSnp {
+    fn format(&self) -> &'static str {
+        FORMAT
+    }
+
+    // Fixed at launch, so read from a first report and reused. SEV-SNP has no PCRs.
+    fn measurement(&self) -> Result<(String, BTreeMap<String, String>)> {
+        static MEASUREMENT_HEX: OnceLock<String> = OnceLock::new();
+        let measurement = match MEASUREMENT_HEX.get() {
+            Some(measurement) => measurement,
+            None => {
+                let report = get_report([0; 64]).context("read launch measurement from SEV-SNP report")?;
+                MEASUREMENT_HEX.get_or_init(|| hex::encode(&report[MEASUREMENT]))
+            }
+        };
+        Ok((measurement.clone(), BTreeMap::new()))
+    }
+
+    fn evidence(&self, payload_sha256: &[u8], public_key: &[u8]) -> Result<Vec<u8>> {
+        get_report(evidence_binding(payload_sha256, public_key))
+    }
+}
+
+// struct snp_report_req, snp_report_resp and snp_guest_request_ioctl of <linux/sev-guest.h>.
+#[repr(C)]
+struct ReportReq {
+    user_data: [u8; 64],
+    vmpl: u32,
+    rsvd: [u8; 28],
+}
+
+#[repr(C)]
+struct ReportResp {
+    data: [u8; 4000],
+}
+
+#[repr(C)]
+struct GuestRequest {
+    msg_version: u8,
+    req_data: u64,
+    resp_data: u64,
+    exitinfo2: u64,
+}
+
+// _IOWR('S', 0x0, struct snp_guest_request_ioctl)
+#[cfg(target_os = "linux")]
+const SNP_GET_REPORT: u64 = 0xc020_5300;
+
+#[cfg(target_os = "linux")]
+fn get_report(report_data: [u8; 64]) -> Result<Vec<u8>> {
+    use std::os::fd::AsRawFd;
+
+    let device = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open(DEVICE)
+        .with_context(|| format!("open {}", DEVICE))?;
+    let req = ReportReq { user_data: report_data, vmpl: 0, rsvd: [0; 28] };
+    let mut resp = ReportResp { data: [0; 4000] };
+    let mut request = GuestRequest {
+        msg_version: 1,
+        req_data: &req as *const ReportReq as u64,
+        resp_data: &mut resp as *mut ReportResp as u64,
+        exitinfo2: 0,
+    };
+    // SAFETY: the driver reads `req` and writes `resp` through the addresses in `request`; all
+    // three outlive the call.
+    let rc = unsafe { libc::ioctl(device.as_raw_fd(), SNP_GET_REPORT as _, &mut request) };
+    if rc < 0 {
+        anyhow::bail!(
+            "SNP_GET_REPORT failed: {} (exitinfo2 {:#x})",
+            std::io::Error::last_os_error(),
+            request.exitinfo2
+        );
+    }
+    // MSG_REPORT_RSP: status, report size, 24 reserved bytes, then the report.
+    let status = u32::from_le_bytes(resp.data[0..4].try_into()?);
+    let size = u32::from_le_bytes(resp.data[4..8].try_into()?) as usize;
+    anyhow::ensure!(status == 0, "SEV-SNP firmware refused the report request: status {:#x}", status);
+    anyhow::ensure!(
+        (REPORT_LEN..=resp.data.len() - 0x20).contains(&size),
+        "SEV-SNP report of unexpected size {}",
+        size
+    );
+    Ok(resp.data[0x20..0x20 + size].to_vec())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn get_report(_report_data: [u8; 64]) -> Result<Vec<u8>> {
+    anyhow::bail!("SEV-SNP guest attestation is only available on Linux")
+}
+
+// Checks the report's signature with `vcek_der`, whose chain to AMD's root is the caller's to
+// establish, and that it binds `payload_sha256` and `public_key`. Returns the launch measurement, hex.
+pub fn verify_report(
+    report: &[u8],
+    vcek_der: &[u8],
+    payload_sha256: &[u8],
+    public_key: &[u8],
+) -> Result<String, VerifyError> {
+    if report.len() < REPORT_LEN {
+        return Err(VerifyError::Malformed(format!("SEV-SNP report of {} bytes", report.len())));
+    }
+    let vcek = webpki::EndEntityCert::try_from(vcek_der)
+        .map_err(|e| VerifyError::Chain(format!("VCEK certificate: {:?}", e)))?;
+    let big_endian = |field: &[u8]| field.iter().rev().copied().collect::<Vec<u8>>();
+    let signature = [big_endian(&report[SIGNATURE_R]), big_endian(&report[SIGNATURE_S])].concat();
+    let der = super::verify::fixed_to_der(&signature).ok_or(VerifyError::Signature)?;
+    vcek.verify_signature(&webpki::ECDSA_P384_SHA384, &report[..SIGNED_LEN], &der)
+        .map_err(|_| VerifyError::Signature)?;
+
+    let expected = evidence_binding(payload_sha256, public_key);
+    let actual = &report[REPORT_DATA];
+    if actual[..32] != expected[..32] {
+        return Err(VerifyError::UserData(hex::encode(payload_sha256)));
+    }
+    if actual[32..] != expected[32..] {
+        return Err(VerifyError::PublicKey);
+    }
+    Ok(hex::encode(&report[MEASUREMENT]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rcgen::{Certificate, CertificateParams, DnType, KeyPair, PKCS_ECDSA_P384_SHA384};
+    use ring::signature::{EcdsaKeyPair, ECDSA_P384_SHA384_FIXED_SIGNING};
+    use sha2::{Digest, Sha256};
+
+    // A report as the secure processor would produce it, signed by a throwaway VCEK.
+    fn report(vcek: &Certificate, payload_sha256: &[u8], public_key: &[u8]) -> Vec<u8> {
+        let mut report = vec![0u8; REPORT_LEN];
+        report[0] = 2;
+        report[REPORT_DATA].copy_from_slice(&evidence_binding(payload_sha256, public_key));
+        report[MEASUREMENT].copy_from_slice(&[0xab; 48]);
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = vcek.serialize_private_key_der();
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P384_SHA384_FIXED_SIGNING, &pkcs8, &rng).unwrap();
+        let signature = key.sign(&rng, &report[..SIGNED_LEN]).unwrap();
+        let (r, s) = signature.as_ref().split_at(48);
+        report[SIGNATURE_R].copy_from_slice(&r.iter().rev().copied().collect::<Vec<_>>());
+        report[SIGNATURE_S].copy_from_slice(&s.iter().rev().copied().collect::<Vec<_>>());
+        report
+    }
+
+    #[test]
+    fn test_verify_report() {
+        let mut params = CertificateParams::new(vec![]);
+        params.alg = &PKCS_ECDSA_P384_SHA384;
+        params.distinguished_name.push(DnType::CommonName, "SEV-VCEK");
+        params.key_pair = Some(KeyPair::generate(&PKCS_ECDSA_P384_SHA384).unwrap());
+        let vcek = Certificate::from_params(params).unwrap();
+        let vcek_der = vcek.serialize_der().unwrap();
+        let digest = Sha256::digest(b"payload");
+        let report = report(&vcek, &digest, b"key");
+
+        assert_eq!(verify_report(&report, &vcek_der, &digest, b"key").unwrap(), "ab".repeat(48));
+        let code = |result: Result<String, VerifyError>| result.unwrap_err().code();
+        assert_eq!(code(verify_report(&report, &vcek_der, &Sha256::digest(b"other"), b"key")), "USER_DATA_MISMATCH");
+        assert_eq!(code(verify_report(&report, &vcek_der, &digest, b"other key")), "PUBLIC_KEY_MISMATCH");
+        let mut altered = report.clone();
+        altered[MEASUREMENT.start] ^= 1;
+        assert_eq!(code(verify_report(&altered, &vcek_der, &digest, b"key")), "SIGNATURE");
+        assert_eq!(code(verify_report(&report[..SIGNED_LEN], &vcek_der, &digest, b"key")), "MALFORMED");
+    }
+}
@@ -0,0 +1,434 @@
+use anyhow::{Context, Result};
+use ring::signature::{
+    RsaPublicKeyComponents, UnparsedPublicKey, ECDSA_P256_SHA256_FIXED, RSA_PKCS1_2048_8192_SHA256,
+};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::env;
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use super::verify::VerifyError;
+use super::{evidence_binding, AttestationProvider};
+
+// TPM 2.0 quotes, the fallback for bare-metal hosts with no enclave. The host's TPM signs a quote
+// of PCRs 0-7 (firmware, boot loader and their configuration, i.e. measured boot) with its
+// attestation key (AK), qualified by the payload and key binding (see evidence_binding). That
+// shows which boot chain the host ran, but unlike an enclave nothing isolates the service from
+// the host's operator, who can read the signing key out of memory. The format says so:
+// "tpm-quote-weak". The AK is provisioned by the operator (e.g. `tpm2_createak`, made persistent
+// with `tpm2_evictcontrol`) with an empty authorization value; NAUTILUS_TPM_AK_HANDLE names it,
+// and the backend is off without it. Verifiers pin the AK by its TPM name, which they get from
+// the operator after checking the AK against the TPM's endorsement key.
+//
+// The evidence is the AK's TPMT_PUBLIC, the TPMS_ATTEST quote and its TPMT_SIGNATURE, each with
+// a big-endian u16 length, as TPM2B fields are. Only SHA-256 AKs are supported, ECDSA P-256 or
+// RSASSA.
+
+pub const FORMAT: &str = "tpm-quote-weak";
+
+// TPM 2.0 structure tags, command codes and algorithm IDs (TPM 2.0 Library, Part 2).
+const ST_NO_SESSIONS: u16 = 0x8001;
+const ST_SESSIONS: u16 = 0x8002;
+const ST_ATTEST_QUOTE: u16 = 0x8018;
+const CC_QUOTE: u32 = 0x0000_0158;
+const CC_READ_PUBLIC: u32 = 0x0000_0173;
+const RS_PW: u32 = 0x4000_0009;
+const GENERATED_VALUE: u32 = 0xff54_4347;
+const ALG_RSA: u16 = 0x0001;
+const ALG_SHA256: u16 = 0x000b;
+const ALG_NULL: u16 = 0x0010;
+const ALG_RSASSA: u16 = 0x0014;
+const ALG_ECDSA: u16 = 0x0018;
+const ALG_ECC: u16 = 0x0023;
+const ECC_NIST_P256: u16 = 0x0003;
+// PCRs 0 to 7.
+const PCR_SELECT: [u8; 3] = [0xff, 0x00, 0x00];
+
+#[derive(Debug, Clone, Default)]
+pub struct TpmConfig {
+    pub device: PathBuf,
+    // Persistent handle of the attestation key; the backend is off without one.
+    pub ak_handle: Option<u32>,
+}
+
+impl TpmConfig {
+    pub fn from_env() -> Result<Self> {
+        let ak_handle = match env::var("NAUTILUS_TPM_AK_HANDLE").ok().filter(|v| !v.is_empty()) {
+            Some(handle) => Some(
+                u32::from_str_radix(handle.trim_start_matches("0x"), 16)
+                    .with_context(|| format!("Invalid NAUTILUS_TPM_AK_HANDLE '{}'", handle))?,
+            ),
+            None => None,
+        };
+        let device = env::var("NAUTILUS_TPM_DEVICE").ok().filter(|v| !v.is_empty());
+        Ok(Self { device: PathBuf::from(device.unwrap_or_else(|| "/dev/tpmrm0".into())), ak_handle })
+    }
+}
+
+pub struct Tpm {
+    device: PathBuf,
+    ak_handle: u32,
+}
+
+static TPM: OnceLock<Tpm> = OnceLock::new();
+
+// Called once at boot; the provider is available from then on if the AK handle is set.
+pub fn init(config: &TpmConfig) {
+    if let Some(ak_handle) = config.ak_handle {
+        let _ = TPM.set(Tpm { device: config.device.clone(), ak_handle });
+    }
+}
+
+pub fn configured() -> Option<&'static Tpm> {
+    TPM.get().filter(|tpm| tpm.device.exists())
+}
+
+impl AttestationProvider for Tpm {
+    fn format(&self) -> &'static str {
+        FORMAT
+    }
+
+    // The quote's PCR digest: SHA-256 over PCRs 0-7, which are fixed once the host has booted.
+    fn measurement(&self) -> Result<(String, BTreeMap<String, String>)> {
+        static PCR_DIGEST: OnceLock<String> = OnceLock::new();
+        let digest = match PCR_DIGEST.get() {
+            Some(digest) => digest,
+            None => {
+                let (quoted, _) = self.quote(&[0; 64]).context("read PCR digest from a TPM quote")?;
+                let quote = Quote::parse(&quoted).map_err(anyhow::Error::msg)?;
+                PCR_DIGEST.get_or_init(|| hex::encode(quote.pcr_digest))
+            }
+        };
+        Ok((digest.clone(), BTreeMap::new()))
+    }
+
+    fn evidence(&self, payload_sha256: &[u8], public_key: &[u8]) -> Result<Vec<u8>> {
+        static AK_PUBLIC: OnceLock<Vec<u8>> = OnceLock::new();
+        let ak_public = match AK_PUBLIC.get() {
+            Some(public) => public,
+            None => {
+                let public = self.read_public().context("read the TPM attestation key")?;
+                AK_PUBLIC.get_or_init(|| public)
+            }
+        };
+        let (quoted, signature) = self.quote(&evidence_binding(payload_sha256, public_key))?;
+        Ok(encode_evidence(ak_public, &quoted, &signature))
+    }
+}
+
+impl Tpm {
+    // TPMS_ATTEST and TPMT_SIGNATURE of a quote over PCR_SELECT, qualified by `data`.
+    fn quote(&self, data: &[u8]) -> Result<(Vec<u8>, Vec<u8>)> {
+        let mut body = self.ak_handle.to_be_bytes().to_vec();
+        // Password session with an empty authorization value: handle, nonce, attributes, hmac.
+        body.extend(9u32.to_be_bytes());
+        body.extend(RS_PW.to_be_bytes());
+        body.extend([0, 0, 0, 0, 0]);
+        body.extend((data.len() as u16).to_be_bytes());
+        body.extend(data);
+        // The AK's own scheme, and one SHA-256 PCR selection.
+        body.extend(ALG_NULL.to_be_bytes());
+        body.extend(1u32.to_be_bytes());
+        body.extend(ALG_SHA256.to_be_bytes());
+        body.push(PCR_SELECT.len() as u8);
+        body.extend(PCR_SELECT);
+        let response = self.transact(ST_SESSIONS, CC_QUOTE, &body)?;
+        let mut reader = Reader(&response);
+        let parameters = reader.u32().and_then(|size| reader.take(size as usize)).map_err(anyhow::Error::msg)?;
+        let mut reader = Reader(parameters);
+        let quoted = reader.sized().map_err(anyhow::Error::msg)?;
+        Ok((quoted.to_vec(), reader.0.to_vec()))
+    }
+
+    // The AK's TPMT_PUBLIC.
+    fn read_public(&self) -> Result<Vec<u8>> {
+        let response = self.transact(ST_NO_SESSIONS, CC_READ_PUBLIC, &self.ak_handle.to_be_bytes())?;
+        Ok(Reader(&response).sized().map_err(anyhow::Error::msg)?.to_vec())
+    }
+
+    // Sends a command through the kernel's resource manager; returns what follows the response header.
+    fn transact(&self, tag: u16, code: u32, body: &[u8]) -> Result<Vec<u8>> {
+        let mut command = tag.to_be_bytes().to_vec();
+        command.extend(((10 + body.len()) as u32).to_be_bytes());
+        command.extend(code.to_be_bytes());
+        command.extend(body);
+        let mut device = std::fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&self.device)
+            .with_context(|| format!("open {}", self.device.display()))?;
+        device.write_all(&command).context("write TPM command")?;
+        let mut response = vec![0u8; 4096];
+        let read = device.read(&mut response).context("read TPM response")?;
+        anyhow::ensure!(read >= 10, "short TPM response");
+        let rc = u32::from_be_bytes(response[6..10].try_into()?);
+        anyhow::ensure!(rc == 0, "TPM command {:#x} failed with response code {:#x}", code, rc);
+        Ok(response[10..read].to_vec())
+    }
+}
+
+pub fn encode_evidence(ak_public: &[u8], quoted: &[u8], signature: &[u8]) -> Vec<u8> {
+    let mut out = Vec::new();
+    for field in [ak_public, quoted, signature] {
+        out.extend((field.len() as u16).to_be_bytes());
+        out.extend(field);
+    }
+    out
+}
+
+// Big-endian cursor over TPM structures.
+struct Reader<'a>(&'a [u8]);
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Result<&'a [u8], String> {
+        if self.0.len() < n {
+            return Err("truncated TPM structure".into());
+        }
+        let (head, rest) = self.0.split_at(n);
+        self.0 = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_be_bytes(self.take(2)?.try_into().unwrap_or_default()))
+    }
+
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_be_bytes(self.take(4)?.try_into().unwrap_or_default()))
+    }
+
+    // A TPM2B: u16 size, then that many bytes.
+    fn sized(&mut self) -> Result<&'a [u8], String> {
+        let size = self.u16()? as usize;
+        self.take(size)
+    }
+
+    // A scheme: algorithm, and its hash unless it is TPM_ALG_NULL.
+    fn scheme(&mut self) -> Result<(u16, Option<u16>), String> {
+        let alg = self.u16()?;
+        Ok((alg, if alg == ALG_NULL { None } else { Some(self.u16()?) }))
+    }
+}
+
+// The TPMS_ATTEST fields a quote verification needs.
+struct Quote<'a> {
+    signer_name: &'a [u8],
+    extra_data: &'a [u8],
+    pcr_digest: &'a [u8],
+}
+
+impl<'a> Quote<'a> {
+    fn parse(quoted: &'a [u8]) -> Result<Self, String> {
+        let mut reader = Reader(quoted);
+        if reader.u32()? != GENERATED_VALUE || reader.u16()? != ST_ATTEST_QUOTE {
+            return Err("not a TPM-generated quote".into());
+        }
+        let signer_name = reader.sized()?;
+        let extra_data = reader.sized()?;
+        // Clock info (clock, reset and restart counts, safe) and firmware version.
+        reader.take(8 + 4 + 4 + 1 + 8)?;
+        for _ in 0..reader.u32()? {
+            reader.u16()?;
+            let size = reader.u8()? as usize;
+            reader.take(size)?;
+        }
+        Ok(Self { signer_name, extra_data, pcr_digest: reader.sized()? })
+    }
+}
+
+enum AttestationKey {
+    Rsa { modulus: Vec<u8>, exponent: Vec<u8> },
+    P256 { point: Vec<u8> },
+}
+
+impl AttestationKey {
+    fn parse(public: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader(public);
+        let alg = reader.u16()?;
+        if reader.u16()? != ALG_SHA256 {
+            return Err("attestation key name is not SHA-256".into());
+        }
+        reader.u32()?; // object attributes
+        reader.sized()?; // auth policy
+        // Symmetric definition: algorithm, then key bits and mode unless TPM_ALG_NULL.
+        if reader.u16()? != ALG_NULL {
+            reader.take(4)?;
+        }
+        match alg {
+            ALG_RSA => {
+                reader.scheme()?;
+                reader.u16()?; // key bits
+                let exponent = match reader.u32()? {
+                    0 => 65537u32,
+                    e => e,
+                };
+                let modulus = reader.sized()?.to_vec();
+                let exponent = exponent.to_be_bytes().iter().copied().skip_while(|b| *b == 0).collect();
+                Ok(Self::Rsa { modulus, exponent })
+            }
+            ALG_ECC => {
+                reader.scheme()?;
+                if reader.u16()? != ECC_NIST_P256 {
+                    return Err("attestation key is not on P-256".into());
+                }
+                reader.scheme()?; // KDF
+                let (x, y) = (reader.sized()?, reader.sized()?);
+                if x.len() > 32 || y.len() > 32 {
+                    return Err("malformed P-256 point".into());
+                }
+                let mut point = vec![0x04];
+                point.extend(pad(x, 32));
+                point.extend(pad(y, 32));
+                Ok(Self::P256 { point })
+            }
+            other => Err(format!("unsupported attestation key type {:#06x}", other)),
+        }
+    }
+
+    fn verify(&self, message: &[u8], signature: &[u8]) -> Result<(), VerifyError> {
+        let malformed = |m: String| VerifyError::Malformed(format!("quote signature: {}", m));
+        let mut reader = Reader(signature);
+        let (alg, hash) = reader.scheme().map_err(malformed)?;
+        if hash != Some(ALG_SHA256) {
+            return Err(malformed("not SHA-256".into()));
+        }
+        let verified = match (self, alg) {
+            (Self::Rsa { modulus, exponent }, ALG_RSASSA) => {
+                let signature = reader.sized().map_err(malformed)?;
+                let key = RsaPublicKeyComponents { n: modulus, e: exponent };
+                key.verify(&RSA_PKCS1_2048_8192_SHA256, message, signature)
+            }
+            (Self::P256 { point }, ALG_ECDSA) => {
+                let (r, s) = (reader.sized().map_err(malformed)?, reader.sized().map_err(malformed)?);
+                if r.len() > 32 || s.len() > 32 {
+                    return Err(malformed("ECDSA component over 32 bytes".into()));
+                }
+                let signature = [pad(r, 32), pad(s, 32)].concat();
+                UnparsedPublicKey::new(&ECDSA_P256_SHA256_FIXED, point).verify(message, &signature)
+            }
+            _ => return Err(malformed(format!("scheme {:#06x} does not match the key", alg))),
+        };
+        verified.map_err(|_| VerifyError::Signature)
+    }
+}
+
+fn pad(bytes: &[u8], len: usize) -> Vec<u8> {
+    let mut out = vec![0u8; len.saturating_sub(bytes.len())];
+    out.extend(bytes);
+    out
+}
+
+// TPM name of an AK: its name algorithm, then the hash of its TPMT_PUBLIC.
+pub fn ak_name(ak_public: &[u8]) -> Vec<u8> {
+    [&ALG_SHA256.to_be_bytes()[..], &Sha256::digest(ak_public)].concat()
+}
+
+// Checks the quote was signed by the AK named `ak_name_hex` and binds `payload_sha256` and
+// `public_key`. Returns the PCR digest, hex.
+pub fn verify_quote(
+    evidence: &[u8],
+    ak_name_hex: &str,
+    payload_sha256: &[u8],
+    public_key: &[u8],
+) -> Result<String, VerifyError> {
+    let malformed = |m: String| VerifyError::Malformed(format!("TPM evidence: {}", m));
+    let mut reader = Reader(evidence);
+    let (ak_public, quoted, signature) =
+        (reader.sized().map_err(malformed)?, reader.sized().map_err(malformed)?, reader.sized().map_err(malformed)?);
+    let name = ak_name(ak_public);
+    if !hex::encode(&name).eq_ignore_ascii_case(ak_name_hex) {
+        return Err(VerifyError::AttestationKey);
+    }
+    let quote = Quote::parse(quoted).map_err(malformed)?;
+    if quote.signer_name != name {
+        return Err(VerifyError::AttestationKey);
+    }
+    AttestationKey::parse(ak_public).map_err(malformed)?.verify(quoted, signature)?;
+
+    let expected = evidence_binding(payload_sha256, public_key);
+    if quote.extra_data.len() != 64 || quote.extra_data[..32] != expected[..32] {
+        return Err(VerifyError::UserData(hex::encode(payload_sha256)));
+    }
+    if quote.extra_data[32..] != expected[32..] {
+        return Err(VerifyError::PublicKey);
+    }
+    Ok(hex::encode(quote.pcr_digest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ring::signature::{EcdsaKeyPair, KeyPair, ECDSA_P256_SHA256_FIXED_SIGNING};
+
+    // TPMT_PUBLIC of a restricted ECDSA P-256 signing key.
+    fn ecc_public(point: &[u8]) -> Vec<u8> {
+        let mut public = [ALG_ECC, ALG_SHA256].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+        public.extend(0x0005_0072u32.to_be_bytes());
+        public.extend([0, 0]);
+        for field in [ALG_NULL, ALG_ECDSA, ALG_SHA256, ECC_NIST_P256, ALG_NULL] {
+            public.extend(field.to_be_bytes());
+        }
+        for coordinate in [&point[1..33], &point[33..]] {
+            public.extend(32u16.to_be_bytes());
+            public.extend(coordinate);
+        }
+        public
+    }
+
+    fn quote(signer_name: &[u8], extra_data: &[u8]) -> Vec<u8> {
+        let mut quoted = GENERATED_VALUE.to_be_bytes().to_vec();
+        quoted.extend(ST_ATTEST_QUOTE.to_be_bytes());
+        for field in [signer_name, extra_data] {
+            quoted.extend((field.len() as u16).to_be_bytes());
+            quoted.extend(field);
+        }
+        quoted.extend([0u8; 25]);
+        quoted.extend(1u32.to_be_bytes());
+        quoted.extend(ALG_SHA256.to_be_bytes());
+        quoted.push(3);
+        quoted.extend(PCR_SELECT);
+        quoted.extend(32u16.to_be_bytes());
+        quoted.extend([0xcd; 32]);
+        quoted
+    }
+
+    #[test]
+    fn test_verify_quote() {
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = EcdsaKeyPair::generate_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, &rng).unwrap();
+        let ak = EcdsaKeyPair::from_pkcs8(&ECDSA_P256_SHA256_FIXED_SIGNING, pkcs8.as_ref(), &rng).unwrap();
+        let ak_public = ecc_public(ak.public_key().as_ref());
+        let name = hex::encode(ak_name(&ak_public));
+        let digest = Sha256::digest(b"payload");
+        let evidence = |quoted: &[u8]| {
+            let signed = ak.sign(&rng, quoted).unwrap();
+            let mut signature = [ALG_ECDSA, ALG_SHA256].iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+            for half in signed.as_ref().chunks(32) {
+                signature.extend(32u16.to_be_bytes());
+                signature.extend(half);
+            }
+            encode_evidence(&ak_public, quoted, &signature)
+        };
+        let quoted = quote(&ak_name(&ak_public), &evidence_binding(&digest, b"key"));
+        let good = evidence(&quoted);
+
+        assert_eq!(verify_quote(&good, &name, &digest, b"key").unwrap(), "cd".repeat(32));
+        let code = |result: Result<String, VerifyError>| result.unwrap_err().code();
+        assert_eq!(code(verify_quote(&good, &"00".repeat(34), &digest, b"key")), "AK_MISMATCH");
+        assert_eq!(code(verify_quote(&good, &name, &Sha256::digest(b"other"), b"key")), "USER_DATA_MISMATCH");
+        assert_eq!(code(verify_quote(&good, &name, &digest, b"other key")), "PUBLIC_KEY_MISMATCH");
+        let other_signer = evidence(&quote(b"\x00\x0bsomeone else", &evidence_binding(&digest, b"key")));
+        assert_eq!(code(verify_quote(&other_signer, &name, &digest, b"key")), "AK_MISMATCH");
+        let mut tampered = good.clone();
+        // The last byte of the quoted PCR digest.
+        tampered[2 + ak_public.len() + 2 + quoted.len() - 1] ^= 1;
+        assert_eq!(code(verify_quote(&tampered, &name, &digest, b"key")), "SIGNATURE");
+        assert_eq!(code(verify_quote(&good[..10], &name, &digest, b"key")), "MALFORMED");
+    }
+}
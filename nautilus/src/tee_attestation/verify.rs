@@ -0,0 +1,701 @@
+use anyhow::Context;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use serde::{Deserialize, Serialize};
+use serde_bytes::ByteBuf;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use super::{bls, secp256k1, sev_snp, tpm, AttestationEnvelope};
+
+// Verification of NSM attestation documents, for relying parties and the service itself. A
+// document is a COSE_Sign1 structure (RFC 8152) whose CBOR payload carries the PCRs, the signing
+// certificate and its CA bundle, and the user data and public key the enclave asked NSM to sign
+// in. A document is accepted when, in order:
+//   - the CA bundle starts with the AWS Nitro Enclaves root (pinned by SHA-256 of its DER), and
+//     the certificate chains to it through the rest of the bundle, valid at the document's
+//     timestamp (the leaf certificates only live a few hours; a document is judged as of when it
+//     was made, and its age left to the caller);
+//   - the ES384 signature verifies with the certificate's key;
+//   - every PCR the caller expects has exactly that value;
+//   - the user data matches the payload hash the caller expects: the 32-byte digest itself, or,
+//     for documents that embedded the whole payload, bytes hashing to it;
+//   - the public key, when the caller expects one, is the one NSM signed in.
+
+// SHA-256 of AWS_NitroEnclaves_Root-G1, as published in the Nitro Enclaves documentation.
+pub const AWS_NITRO_ROOT_SHA256: &str = "641a0321a3e244efe456463195d606317ed7cdcc3c1756e09893f3c68f79bb5b";
+
+// COSE algorithm identifier for ECDSA with SHA-384 on P-384, the only one NSM uses.
+const ES384: i128 = -35;
+
+#[derive(Debug, thiserror::Error)]
+pub enum VerifyError {
+    #[error("malformed attestation document: {0}")]
+    Malformed(String),
+    #[error("certificate chain is not rooted in the AWS Nitro root: {0}")]
+    Chain(String),
+    #[error("document signature does not verify with its certificate")]
+    Signature,
+    #[error("{pcr} is {actual}, expected {expected}")]
+    Pcr { pcr: String, expected: String, actual: String },
+    #[error("user data does not match payload hash {0}")]
+    UserData(String),
+    #[error("public key signed into the document is not the expected one")]
+    PublicKey,
+    #[error("quote is not signed by the expected TPM attestation key")]
+    AttestationKey,
+}
+
+impl VerifyError {
+    // Stable identifier for API responses.
+    pub fn code(&self) -> &'static str {
+        match self {
+            VerifyError::Malformed(_) => "MALFORMED",
+            VerifyError::Chain(_) => "CERT_CHAIN",
+            VerifyError::Signature => "SIGNATURE",
+            VerifyError::Pcr { .. } => "PCR_MISMATCH",
+            VerifyError::UserData(_) => "USER_DATA_MISMATCH",
+            VerifyError::PublicKey => "PUBLIC_KEY_MISMATCH",
+            VerifyError::AttestationKey => "AK_MISMATCH",
+        }
+    }
+}
+
+// What the caller requires of a document beyond a valid chain and signature; empty accepts any.
+#[derive(Debug, Clone, Default, Deserialize)]
+pub struct Expected {
+    // Hex values keyed "pcr0", "pcr1"..., as in AttestationData::pcrs.
+    #[serde(default)]
+    pub pcrs: BTreeMap<String, String>,
+    // Hex SHA-256 of the signed payload (AttestationEnvelope::payload_sha256).
+    #[serde(default)]
+    pub payload_sha256: Option<String>,
+    #[serde(default)]
+    pub public_key: Option<Vec<u8>>,
+}
+
+// The signed contents of a document that passed verification.
+#[derive(Debug, Clone, Serialize)]
+pub struct VerifiedDocument {
+    pub module_id: String,
+    // Milliseconds since the epoch, per NSM.
+    pub timestamp: u64,
+    // Every PCR the document carries, hex, keyed "pcr0"...
+    pub pcrs: BTreeMap<String, String>,
+    pub public_key: Option<String>,
+    pub user_data: Option<String>,
+    pub nonce: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct Payload {
+    module_id: String,
+    digest: String,
+    timestamp: u64,
+    pcrs: BTreeMap<u16, ByteBuf>,
+    certificate: ByteBuf,
+    cabundle: Vec<ByteBuf>,
+    public_key: Option<ByteBuf>,
+    user_data: Option<ByteBuf>,
+    nonce: Option<ByteBuf>,
+}
+
+pub fn verify_document(document: &[u8], expected: &Expected) -> Result<VerifiedDocument, VerifyError> {
+    verify_document_with_root(document, expected, AWS_NITRO_ROOT_SHA256)
+}
+
+// As `verify_document`, trusting the root whose DER hashes to `root_sha256` instead.
+pub fn verify_document_with_root(
+    document: &[u8],
+    expected: &Expected,
+    root_sha256: &str,
+) -> Result<VerifiedDocument, VerifyError> {
+    let malformed = |what: &str, err: &dyn std::fmt::Display| VerifyError::Malformed(format!("{}: {}", what, err));
+    // Untagged or with tag 18; serde_cbor skips tags.
+    let (protected, _unprotected, payload, signature): (ByteBuf, serde_cbor::Value, ByteBuf, ByteBuf) =
+        serde_cbor::from_slice(document).map_err(|e| malformed("COSE_Sign1", &e))?;
+    let header: BTreeMap<i128, serde_cbor::Value> =
+        serde_cbor::from_slice(&protected).map_err(|e| malformed("protected header", &e))?;
+    if header.get(&1) != Some(&serde_cbor::Value::Integer(ES384)) {
+        return Err(VerifyError::Malformed(format!("unsupported algorithm {:?}", header.get(&1))));
+    }
+    let doc: Payload = serde_cbor::from_slice(&payload).map_err(|e| malformed("payload", &e))?;
+    if doc.digest != "SHA384" {
+        return Err(VerifyError::Malformed(format!("unsupported PCR digest {}", doc.digest)));
+    }
+
+    // Chain: cabundle[0] is the root, the rest intermediates.
+    let root = doc.cabundle.first().ok_or_else(|| VerifyError::Chain("empty CA bundle".into()))?;
+    let fingerprint = hex::encode(Sha256::digest(root));
+    if !fingerprint.eq_ignore_ascii_case(root_sha256) {
+        return Err(VerifyError::Chain(format!("root certificate {} is not pinned", fingerprint)));
+    }
+    let chain = |err: webpki::Error| VerifyError::Chain(format!("{:?}", err));
+    let anchor = webpki::TrustAnchor::try_from_cert_der(root).map_err(chain)?;
+    let leaf = webpki::EndEntityCert::try_from(doc.certificate.as_slice()).map_err(chain)?;
+    let intermediates: Vec<&[u8]> = doc.cabundle[1..].iter().map(|c| c.as_slice()).collect();
+    let at = webpki::Time::from_seconds_since_unix_epoch(doc.timestamp / 1000);
+    leaf.verify_for_usage(
+        &[&webpki::ECDSA_P384_SHA384],
+        &[anchor],
+        &intermediates,
+        at,
+        webpki::KeyUsage::client_auth(),
+        &[],
+    )
+    .map_err(chain)?;
+
+    // Sig_structure for COSE_Sign1 with empty external AAD.
+    let signed = serde_cbor::to_vec(&("Signature1", &protected, ByteBuf::new(), &payload))
+        .map_err(|e| malformed("Sig_structure", &e))?;
+    let der = fixed_to_der(&signature).ok_or(VerifyError::Signature)?;
+    leaf.verify_signature(&webpki::ECDSA_P384_SHA384, &signed, &der).map_err(|_| VerifyError::Signature)?;
+
+    let pcrs: BTreeMap<String, String> = doc.pcrs.iter().map(|(i, v)| (format!("pcr{}", i), hex::encode(v))).collect();
+    for (pcr, want) in &expected.pcrs {
+        let actual = pcrs.get(pcr).map(String::as_str).unwrap_or("absent");
+        if !actual.eq_ignore_ascii_case(want) {
+            return Err(VerifyError::Pcr { pcr: pcr.clone(), expected: want.clone(), actual: actual.to_string() });
+        }
+    }
+    if let Some(want) = &expected.payload_sha256 {
+        let matches = doc.user_data.as_ref().is_some_and(|data| {
+            hex::encode(data).eq_ignore_ascii_case(want) || hex::encode(Sha256::digest(data)).eq_ignore_ascii_case(want)
+        });
+        if !matches {
+            return Err(VerifyError::UserData(want.clone()));
+        }
+    }
+    if let Some(want) = &expected.public_key {
+        if doc.public_key.as_deref().map(|k| k.as_slice()) != Some(want.as_slice()) {
+            return Err(VerifyError::PublicKey);
+        }
+    }
+    Ok(VerifiedDocument {
+        module_id: doc.module_id,
+        timestamp: doc.timestamp,
+        pcrs,
+        public_key: doc.public_key.map(hex::encode),
+        user_data: doc.user_data.map(hex::encode),
+        nonce: doc.nonce.map(hex::encode),
+    })
+}
+
+// An nsm-document envelope: the document must sign in the hash of the envelope's own payload,
+// recomputed rather than taken from `payload_sha256`, and the envelope's public key.
+pub fn verify_envelope(
+    envelope: &AttestationEnvelope,
+    pcrs: &BTreeMap<String, String>,
+) -> Result<VerifiedDocument, VerifyError> {
+    let document = envelope
+        .nsm_document_b64
+        .as_deref()
+        .ok_or_else(|| VerifyError::Malformed(format!("{} envelope has no NSM document", envelope.format)))?;
+    let document = STANDARD.decode(document).map_err(|e| VerifyError::Malformed(format!("nsm_document_b64: {}", e)))?;
+    let payload = super::signed_bytes(envelope).map_err(|e| VerifyError::Malformed(format!("payload: {:#}", e)))?;
+    let public_key = match &envelope.public_key_b64 {
+        Some(key) => Some(STANDARD.decode(key).map_err(|e| VerifyError::Malformed(format!("public_key_b64: {}", e)))?),
+        None => None,
+    };
+    let expected =
+        Expected { pcrs: pcrs.clone(), payload_sha256: Some(hex::encode(Sha256::digest(&payload))), public_key };
+    verify_document(&document, &expected)
+}
+
+// Body of POST /attestation/verify: an envelope of either format and what the caller trusts.
+#[derive(Deserialize)]
+pub struct VerifyRequest {
+    pub envelope: AttestationEnvelope,
+    // Hex PCRs an NSM document must carry, keyed "pcr0"..., or, keyed "measurement", the launch
+    // measurement of a SEV-SNP report or the PCR digest of a TPM quote. Envelopes with platform
+    // evidence fail without them, since any image would do; ignored for bare-signature envelopes.
+    #[serde(default)]
+    pub expected_pcrs: BTreeMap<String, String>,
+    // Base64 keys the enclave key must be one of (compressed SEC1 for secp256k1). Bare-signature
+    // envelopes fail without them; POST /attestation/verify fills in this service's own keys.
+    #[serde(default)]
+    pub expected_public_keys: Vec<String>,
+    // Base64 ed25519 operator keys a co-signature must come from; any key when empty. When set, an
+    // envelope without a co-signature fails.
+    #[serde(default)]
+    pub expected_cosigner_keys: Vec<String>,
+    // Milliseconds since the epoch to judge `expires_at` at, e.g. when a trade was settled; now
+    // when absent.
+    #[serde(default)]
+    pub at_ms: Option<u64>,
+    // Base64 DER VCEK certificate of the chip that signed a sev-snp envelope's report, from AMD's
+    // Key Distribution Service; chaining it to AMD's root is the caller's part.
+    #[serde(default)]
+    pub sev_snp_vcek_b64: Option<String>,
+    // Hex TPM name of the attestation key a tpm-quote-weak envelope's quote must be signed with,
+    // as the operator vouches for it.
+    #[serde(default)]
+    pub tpm_ak_name_hex: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Verdict {
+    pub valid: bool,
+    pub format: String,
+    // SHA-256 of the payload as recomputed from `data`.
+    pub payload_sha256: String,
+    // Key ID of the enclave key (see tee_attestation::key_id), when the envelope names one.
+    pub key_id: Option<String>,
+    // Checks that passed, in the order run; checking stops at the first failure.
+    pub passed: Vec<&'static str>,
+    pub failure: Option<Failure>,
+    // What the NSM document attests, for nsm-document envelopes that got that far.
+    pub document: Option<VerifiedDocument>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct Failure {
+    pub check: &'static str,
+    pub code: &'static str,
+    pub message: String,
+}
+
+// Checks an envelope end to end: payload hash, the enclave's signature (ed25519, secp256k1, BLS or
+// NSM document), expiry, the caller's expected keys and PCRs, and the operator co-signature of
+// "-cosigned" formats. Something must be pinned, the enclave key or the platform measurement, or
+// the envelope fails. A failed check is a verdict, not an error.
+pub fn verdict(req: &VerifyRequest) -> Verdict {
+    let envelope = &req.envelope;
+    let payload = super::signed_bytes(envelope);
+    let mut verdict = Verdict {
+        valid: false,
+        format: envelope.format.clone(),
+        payload_sha256: payload.as_ref().map(|p| hex::encode(Sha256::digest(p))).unwrap_or_default(),
+        key_id: None,
+        passed: Vec::new(),
+        failure: None,
+        document: None,
+    };
+    let payload = match payload {
+        Ok(payload) => payload,
+        Err(err) => {
+            let message = format!("{:#}", err);
+            verdict.failure = Some(Failure { check: "payload_hash", code: "UNSUPPORTED_CANONICALIZATION", message });
+            return verdict;
+        }
+    };
+    match run_checks(req, &payload, &mut verdict) {
+        Ok(()) => verdict.valid = true,
+        Err(failure) => verdict.failure = Some(failure),
+    }
+    verdict
+}
+
+fn run_checks(req: &VerifyRequest, payload: &[u8], verdict: &mut Verdict) -> Result<(), Failure> {
+    let envelope = &req.envelope;
+    let fail = |check, code, message: String| Failure { check, code, message };
+    if envelope.payload_sha256.as_ref().is_some_and(|claimed| !claimed.eq_ignore_ascii_case(&verdict.payload_sha256)) {
+        return Err(fail("payload_hash", "PAYLOAD_HASH_MISMATCH", "payload_sha256 is not the hash of data".into()));
+    }
+    verdict.passed.push("payload_hash");
+
+    let public_key = match envelope.public_key_b64.as_deref() {
+        Some(key) => {
+            Some(STANDARD.decode(key).map_err(|e| fail("signature", "MALFORMED", format!("public_key_b64: {}", e)))?)
+        }
+        None => None,
+    };
+    verdict.key_id = public_key.as_deref().map(super::key_id_of);
+    if envelope.key_id.is_some() && envelope.key_id != verdict.key_id {
+        return Err(fail("signature", "KEY_ID_MISMATCH", "key_id does not name public_key_b64".into()));
+    }
+    // A secp256k1 signature carries its key, which must be the envelope's.
+    let check_secp256k1 = |signature: &str| {
+        let serialized = STANDARD.decode(signature).map_err(|e| fail("signature", "MALFORMED", e.to_string()))?;
+        let key = secp256k1::verify(&serialized, payload).map_err(|m| fail("signature", "SIGNATURE", m))?;
+        if public_key.as_ref() != Some(&key) {
+            return Err(fail("signature", "MALFORMED", "signature carries another public key".into()));
+        }
+        Ok(())
+    };
+    let check_bls = |signature: &str| {
+        let signature = STANDARD.decode(signature).map_err(|e| fail("signature", "MALFORMED", e.to_string()))?;
+        let message = bls::quorum_message(&envelope.data).map_err(|e| fail("signature", "MALFORMED", e.to_string()))?;
+        let key = public_key.as_deref().ok_or_else(|| fail("signature", "MALFORMED", "no public_key_b64".into()))?;
+        bls::verify(&signature, &message, key).map_err(|m| fail("signature", "SIGNATURE", m))
+    };
+    let signature = envelope.signature_b64.as_deref();
+    let platform = ["nsm-document-", "sev-snp-", "tpm-quote-"].iter().any(|p| envelope.format.starts_with(p));
+    if envelope.format.starts_with("ed25519-") {
+        let public_key =
+            public_key.as_deref().ok_or_else(|| fail("signature", "MALFORMED", "no public_key_b64".into()))?;
+        let public_key = ed25519_dalek::PublicKey::from_bytes(public_key)
+            .map_err(|_| fail("signature", "MALFORMED", "public_key_b64 is not an ed25519 key".into()))?;
+        let signature = envelope
+            .signature_b64
+            .as_deref()
+            .and_then(|sig| STANDARD.decode(sig).ok())
+            .and_then(|sig| ed25519_dalek::Signature::from_bytes(&sig).ok())
+            .ok_or_else(|| fail("signature", "MALFORMED", "signature_b64 is not a base64 ed25519 signature".into()))?;
+        ed25519_dalek::Verifier::verify(&public_key, payload, &signature)
+            .map_err(|_| fail("signature", "SIGNATURE", "signature does not verify over data".into()))?;
+    } else if envelope.format.starts_with("secp256k1-") {
+        check_secp256k1(signature.ok_or_else(|| fail("signature", "MALFORMED", "no signature_b64".into()))?)?;
+    } else if envelope.format.starts_with("bls12381-") {
+        check_bls(signature.ok_or_else(|| fail("signature", "MALFORMED", "no signature_b64".into()))?)?;
+    } else if platform {
+        if envelope.format.starts_with("nsm-document-") {
+            if !req.expected_pcrs.keys().any(|pcr| pcr.starts_with("pcr")) {
+                return Err(fail("nsm_document", "PCRS_NOT_PINNED", "expected_pcrs names no PCR".into()));
+            }
+            let document = verify_envelope(envelope, &req.expected_pcrs)
+                .map_err(|err| fail("nsm_document", err.code(), err.to_string()))?;
+            verdict.document = Some(document);
+        } else if envelope.format.starts_with("tpm-quote-") {
+            let fail = |code, message: &str| fail("tpm_quote", code, message.to_string());
+            if !req.expected_pcrs.contains_key("measurement") {
+                return Err(fail("PCRS_NOT_PINNED", "expected_pcrs has no measurement"));
+            }
+            let quote = envelope.platform_evidence_b64.as_deref().and_then(|q| STANDARD.decode(q).ok());
+            let quote = quote.ok_or_else(|| fail("MALFORMED", "platform_evidence_b64 is not a base64 quote"))?;
+            let ak_name = req.tpm_ak_name_hex.as_deref();
+            let ak_name = ak_name.ok_or_else(|| fail("AK_REQUIRED", "tpm_ak_name_hex is needed to check the quote"))?;
+            let key = public_key.as_deref().ok_or_else(|| fail("MALFORMED", "no public_key_b64"))?;
+            let digest = tpm::verify_quote(&quote, ak_name, &Sha256::digest(payload), key)
+                .map_err(|err| fail(err.code(), &err.to_string()))?;
+            if !digest.eq_ignore_ascii_case(&envelope.data.enclave_measurement) {
+                return Err(fail("MEASUREMENT_MISMATCH", "enclave_measurement is not the quote's PCR digest"));
+            }
+            if let Some(want) = req.expected_pcrs.get("measurement").filter(|want| !want.eq_ignore_ascii_case(&digest)) {
+                return Err(fail("PCR_MISMATCH", &format!("PCR digest is {}, expected {}", digest, want)));
+            }
+        } else {
+            let fail = |code, message: &str| fail("sev_snp_report", code, message.to_string());
+            if !req.expected_pcrs.contains_key("measurement") {
+                return Err(fail("PCRS_NOT_PINNED", "expected_pcrs has no measurement"));
+            }
+            let report = envelope.platform_evidence_b64.as_deref().and_then(|r| STANDARD.decode(r).ok());
+            let report = report.ok_or_else(|| fail("MALFORMED", "platform_evidence_b64 is not a base64 report"))?;
+            let vcek = req.sev_snp_vcek_b64.as_deref().and_then(|c| STANDARD.decode(c).ok());
+            let vcek = vcek.ok_or_else(|| fail("VCEK_REQUIRED", "sev_snp_vcek_b64 is needed to check the report"))?;
+            let key = public_key.as_deref().ok_or_else(|| fail("MALFORMED", "no public_key_b64"))?;
+            let measurement = sev_snp::verify_report(&report, &vcek, &Sha256::digest(payload), key)
+                .map_err(|err| fail(err.code(), &err.to_string()))?;
+            if !measurement.eq_ignore_ascii_case(&envelope.data.enclave_measurement) {
+                return Err(fail("MEASUREMENT_MISMATCH", "enclave_measurement is not the report's"));
+            }
+            if let Some(want) = req.expected_pcrs.get("measurement").filter(|want| !want.eq_ignore_ascii_case(&measurement)) {
+                return Err(fail("PCR_MISMATCH", &format!("measurement is {}, expected {}", measurement, want)));
+            }
+        }
+        // A signature alongside the evidence is the scheme's; the bound key's size tells which.
+        match (signature, public_key.as_ref().map(Vec::len)) {
+            (None, _) => {}
+            (Some(signature), Some(48)) => check_bls(signature)?,
+            (Some(signature), _) => check_secp256k1(signature)?,
+        }
+    } else {
+        return Err(fail("signature", "UNSUPPORTED_FORMAT", format!("unknown format {}", envelope.format)));
+    }
+    verdict.passed.push("signature");
+
+    // Checked once the signature vouches for `expires_at`; a BLS quorum message keeps it too.
+    if let Some(expires_at) = envelope.data.expires_at {
+        let now = req
+            .at_ms
+            .unwrap_or_else(|| SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64);
+        if now > expires_at {
+            return Err(fail("expiry", "EXPIRED", format!("attestation expired at {} ms", expires_at)));
+        }
+        verdict.passed.push("expiry");
+    }
+
+    // A bare signature only says some key signed; the evidence vouches for the key otherwise.
+    if req.expected_public_keys.is_empty() && !platform {
+        return Err(fail("public_key", "KEY_NOT_PINNED", "expected_public_keys is empty".into()));
+    }
+    if !req.expected_public_keys.is_empty() {
+        let trusted = public_key.is_some_and(|key| {
+            req.expected_public_keys.iter().any(|want| STANDARD.decode(want).is_ok_and(|want| want == key))
+        });
+        if !trusted {
+            return Err(fail("public_key", "PUBLIC_KEY_MISMATCH", "enclave key is not an expected one".into()));
+        }
+        verdict.passed.push("public_key");
+    }
+
+    // Expected co-signers make the co-signature mandatory, or dropping it would pass.
+    if !envelope.format.contains("-cosigned-") && !req.expected_cosigner_keys.is_empty() {
+        return Err(fail("cosignature", "COSIGNATURE_REQUIRED", format!("{} is not a co-signed format", envelope.format)));
+    }
+    if envelope.format.contains("-cosigned-") {
+        let cosignature =
+            envelope.cosignature.as_ref().ok_or_else(|| fail("cosignature", "MALFORMED", "no cosignature".into()))?;
+        let key = decode_key(&cosignature.public_key_b64).map_err(|m| fail("cosignature", "MALFORMED", m))?;
+        let signature = STANDARD
+            .decode(&cosignature.signature_b64)
+            .ok()
+            .and_then(|sig| ed25519_dalek::Signature::from_bytes(&sig).ok());
+        let digest: [u8; 32] = Sha256::digest(payload).into();
+        let verifies = cosignature.digest_hex.eq_ignore_ascii_case(&verdict.payload_sha256)
+            && signature.is_some_and(|sig| ed25519_dalek::Verifier::verify(&key, &digest, &sig).is_ok());
+        if !verifies {
+            return Err(fail("cosignature", "COSIGNATURE", "co-signature does not verify over the payload hash".into()));
+        }
+        let expected = &req.expected_cosigner_keys;
+        if !expected.is_empty() && !expected.iter().any(|want| decode_key(want).is_ok_and(|want| want == key)) {
+            return Err(fail("cosignature", "COSIGNER_MISMATCH", "operator key is not an expected one".into()));
+        }
+        verdict.passed.push("cosignature");
+    }
+    Ok(())
+}
+
+fn decode_key(b64: &str) -> Result<ed25519_dalek::PublicKey, String> {
+    STANDARD
+        .decode(b64)
+        .ok()
+        .and_then(|bytes| ed25519_dalek::PublicKey::from_bytes(&bytes).ok())
+        .ok_or_else(|| format!("{} is not a base64 ed25519 public key", b64))
+}
+
+// `zkdatavault-nautilus verify-attestation <envelope.json> [pcrN=<hex>...]` checks a saved
+// nsm-document envelope and prints what it attests.
+pub fn run_cli(args: &[String]) -> anyhow::Result<()> {
+    let Some((path, pcr_args)) = args.split_first() else {
+        anyhow::bail!("usage: zkdatavault-nautilus verify-attestation <envelope.json> [pcrN=<hex>...]");
+    };
+    let mut pcrs = BTreeMap::new();
+    for arg in pcr_args {
+        match arg.split_once('=') {
+            Some((pcr, value)) if pcr.starts_with("pcr") => pcrs.insert(pcr.to_string(), value.to_string()),
+            _ => anyhow::bail!("expected pcrN=<hex>, got {}", arg),
+        };
+    }
+    let body = std::fs::read(path).with_context(|| format!("read {}", path))?;
+    let envelope: AttestationEnvelope = serde_json::from_slice(&body).context("parse attestation envelope")?;
+    let verified = verify_envelope(&envelope, &pcrs)?;
+    println!("{}", serde_json::to_string_pretty(&verified)?);
+    Ok(())
+}
+
+// COSE carries ECDSA signatures as r || s; webpki takes DER. None unless 96 bytes.
+pub(super) fn fixed_to_der(signature: &[u8]) -> Option<Vec<u8>> {
+    if signature.len() != 96 {
+        return None;
+    }
+    let integer = |half: &[u8]| {
+        let trimmed = &half[half.iter().position(|&b| b != 0).unwrap_or(half.len() - 1)..];
+        let pad = trimmed[0] & 0x80 != 0;
+        let mut out = vec![0x02, (trimmed.len() + pad as usize) as u8];
+        out.extend(pad.then_some(0));
+        out.extend_from_slice(trimmed);
+        out
+    };
+    let (r, s) = (integer(&signature[..48]), integer(&signature[48..]));
+    let mut der = vec![0x30, (r.len() + s.len()) as u8];
+    der.extend(r);
+    der.extend(s);
+    Some(der)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cosign::Cosignature;
+    use ed25519_dalek::{Keypair, SecretKey, Signer};
+    use rcgen::{BasicConstraints, Certificate, CertificateParams, DnType, IsCa, KeyPair, PKCS_ECDSA_P384_SHA384};
+    use ring::signature::{EcdsaKeyPair, ECDSA_P384_SHA384_FIXED_SIGNING};
+
+    fn cert(name: &str, ca: bool) -> Certificate {
+        let mut params = CertificateParams::new(vec![]);
+        params.alg = &PKCS_ECDSA_P384_SHA384;
+        params.distinguished_name.push(DnType::CommonName, name);
+        params.is_ca = if ca { IsCa::Ca(BasicConstraints::Unconstrained) } else { IsCa::NoCa };
+        params.key_pair = Some(KeyPair::generate(&PKCS_ECDSA_P384_SHA384).unwrap());
+        Certificate::from_params(params).unwrap()
+    }
+
+    // A document as NSM would produce it, from a throwaway root, intermediate and leaf.
+    fn document(user_data: &[u8], public_key: &[u8], tamper: bool) -> (Vec<u8>, String) {
+        let (root, intermediate, leaf) = (cert("root", true), cert("intermediate", true), cert("leaf", false));
+        let root_der = root.serialize_der().unwrap();
+        let mut pcrs = BTreeMap::new();
+        for i in 0u16..16 {
+            pcrs.insert(i, ByteBuf::from(vec![if i < 3 { i as u8 + 1 } else { 0 }; 48]));
+        }
+        let mut map = BTreeMap::new();
+        let text = |s: &str| serde_cbor::Value::Text(s.into());
+        let bytes = |b: &[u8]| serde_cbor::Value::Bytes(b.to_vec());
+        map.insert(text("module_id"), text("i-0abc-enc0123"));
+        map.insert(text("digest"), text("SHA384"));
+        map.insert(text("timestamp"), serde_cbor::Value::Integer(1_760_000_000_000));
+        map.insert(text("pcrs"), serde_cbor::value::to_value(&pcrs).unwrap());
+        map.insert(text("certificate"), bytes(&leaf.serialize_der_with_signer(&intermediate).unwrap()));
+        let bundle = vec![bytes(&root_der), bytes(&intermediate.serialize_der_with_signer(&root).unwrap())];
+        map.insert(text("cabundle"), serde_cbor::Value::Array(bundle));
+        map.insert(text("public_key"), bytes(public_key));
+        map.insert(text("user_data"), bytes(user_data));
+        map.insert(text("nonce"), serde_cbor::Value::Null);
+        let payload = serde_cbor::to_vec(&serde_cbor::Value::Map(map)).unwrap();
+
+        let protected = serde_cbor::to_vec(&BTreeMap::from([(1, ES384 as i64)])).unwrap();
+        let signed = ("Signature1", ByteBuf::from(protected.clone()), ByteBuf::new(), ByteBuf::from(payload.clone()));
+        let signed = serde_cbor::to_vec(&signed).unwrap();
+        let rng = ring::rand::SystemRandom::new();
+        let pkcs8 = leaf.serialize_private_key_der();
+        let key = EcdsaKeyPair::from_pkcs8(&ECDSA_P384_SHA384_FIXED_SIGNING, &pkcs8, &rng).unwrap();
+        let mut signature = key.sign(&rng, &signed).unwrap().as_ref().to_vec();
+        signature[10] ^= tamper as u8;
+        let unprotected = BTreeMap::<i64, i64>::new();
+        let cose = (ByteBuf::from(protected), unprotected, ByteBuf::from(payload), ByteBuf::from(signature));
+        (serde_cbor::to_vec(&cose).unwrap(), hex::encode(Sha256::digest(root_der)))
+    }
+
+    #[test]
+    fn test_verify_document() {
+        let digest = Sha256::digest(b"payload").to_vec();
+        let (doc, root) = document(&digest, b"key", false);
+        let expected = Expected {
+            pcrs: BTreeMap::from([("pcr0".to_string(), "01".repeat(48)), ("pcr2".to_string(), "03".repeat(48))]),
+            payload_sha256: Some(hex::encode(&digest)),
+            public_key: Some(b"key".to_vec()),
+        };
+        let verified = verify_document_with_root(&doc, &expected, &root).unwrap();
+        assert_eq!((verified.module_id.as_str(), verified.pcrs.len()), ("i-0abc-enc0123", 16));
+        assert_eq!(verified.pcrs["pcr1"], "02".repeat(48));
+
+        // The real root is pinned by default.
+        assert_eq!(verify_document(&doc, &expected).unwrap_err().code(), "CERT_CHAIN");
+        let wrong_pcr = BTreeMap::from([("pcr1".to_string(), "00".repeat(48))]);
+        let wrong_pcr = Expected { pcrs: wrong_pcr, ..Default::default() };
+        assert_eq!(verify_document_with_root(&doc, &wrong_pcr, &root).unwrap_err().code(), "PCR_MISMATCH");
+        let other_payload = Expected { payload_sha256: Some("ab".repeat(32)), ..Default::default() };
+        assert_eq!(verify_document_with_root(&doc, &other_payload, &root).unwrap_err().code(), "USER_DATA_MISMATCH");
+        let other_key = Expected { public_key: Some(b"other".to_vec()), ..Default::default() };
+        assert_eq!(verify_document_with_root(&doc, &other_key, &root).unwrap_err().code(), "PUBLIC_KEY_MISMATCH");
+
+        // A document embedding the whole payload is checked by its hash.
+        let (doc, root) = document(b"payload", b"key", false);
+        assert!(verify_document_with_root(&doc, &expected, &root).is_ok());
+        let (doc, root) = document(&digest, b"key", true);
+        assert_eq!(verify_document_with_root(&doc, &expected, &root).unwrap_err().code(), "SIGNATURE");
+        assert_eq!(verify_document_with_root(b"\x84junk", &expected, &root).unwrap_err().code(), "MALFORMED");
+    }
+
+    fn keypair(seed: u8) -> Keypair {
+        let secret = SecretKey::from_bytes(&[seed; 32]).unwrap();
+        Keypair { public: (&secret).into(), secret }
+    }
+
+    #[test]
+    fn test_verdict() {
+        let data: crate::tee_attestation::AttestationData = serde_json::from_value(serde_json::json!({
+            "blob_id": "b", "quality_score": 80, "timestamp": 1, "enclave_measurement": "unmeasured",
+            "expires_at": 1_000,
+        }))
+        .unwrap();
+        let payload = nautilus_canonical::canonical_bytes(&data).unwrap();
+        let (enclave, operator) = (keypair(1), keypair(2));
+        let digest: [u8; 32] = Sha256::digest(&payload).into();
+        let cosignature = Cosignature {
+            digest_hex: hex::encode(digest),
+            key_id: super::super::key_id(&operator.public),
+            public_key_b64: STANDARD.encode(operator.public.to_bytes()),
+            signature_b64: STANDARD.encode(operator.sign(&digest).to_bytes()),
+        };
+        let envelope = AttestationEnvelope {
+            format: "ed25519-cosigned-v2".into(),
+            data,
+            signature_b64: Some(STANDARD.encode(enclave.sign(&payload).to_bytes())),
+            public_key_b64: Some(STANDARD.encode(enclave.public.to_bytes())),
+            key_id: Some(super::super::key_id(&enclave.public)),
+            nsm_document_b64: None,
+            platform_evidence_b64: None,
+            canonicalization: Some(nautilus_canonical::CANONICALIZATION.to_string()),
+            payload_sha256: Some(hex::encode(digest)),
+            cosignature: Some(cosignature),
+        };
+        let mut req = VerifyRequest {
+            envelope,
+            expected_pcrs: BTreeMap::new(),
+            expected_public_keys: vec![STANDARD.encode(enclave.public.to_bytes())],
+            expected_cosigner_keys: vec![STANDARD.encode(operator.public.to_bytes())],
+            at_ms: Some(1_000),
+            sev_snp_vcek_b64: None,
+            tpm_ak_name_hex: None,
+        };
+        let result = verdict(&req);
+        assert!(result.valid, "{:?}", result.failure);
+        assert_eq!(result.passed, ["payload_hash", "signature", "expiry", "public_key", "cosignature"]);
+
+        let failure = |req: &VerifyRequest| verdict(req).failure.map(|f| (f.check, f.code));
+        req.at_ms = Some(1_001);
+        assert_eq!(failure(&req), Some(("expiry", "EXPIRED")));
+        req.at_ms = None;
+        assert_eq!(failure(&req), Some(("expiry", "EXPIRED")));
+        req.at_ms = Some(1_000);
+        req.expected_cosigner_keys = vec![STANDARD.encode(enclave.public.to_bytes())];
+        assert_eq!(failure(&req), Some(("cosignature", "COSIGNER_MISMATCH")));
+        req.expected_public_keys = vec![STANDARD.encode(operator.public.to_bytes())];
+        assert_eq!(failure(&req), Some(("public_key", "PUBLIC_KEY_MISMATCH")));
+        req.expected_public_keys.clear();
+        assert_eq!(failure(&req), Some(("public_key", "KEY_NOT_PINNED")));
+        req.expected_public_keys = vec![STANDARD.encode(operator.public.to_bytes())];
+        req.envelope.key_id = Some(super::super::key_id(&operator.public));
+        assert_eq!(failure(&req), Some(("signature", "KEY_ID_MISMATCH")));
+        req.envelope.key_id = None;
+        req.envelope.data.quality_score = 95;
+        assert_eq!(failure(&req), Some(("payload_hash", "PAYLOAD_HASH_MISMATCH")));
+        req.envelope.payload_sha256 = None;
+        assert_eq!(failure(&req), Some(("signature", "SIGNATURE")));
+        // Platform evidence vouches for the key, but only an expected measurement for the image.
+        req.envelope.format = "nsm-document-v2".into();
+        assert_eq!(failure(&req), Some(("nsm_document", "PCRS_NOT_PINNED")));
+        req.expected_pcrs = BTreeMap::from([("pcr0".to_string(), "01".repeat(48))]);
+        assert_eq!(failure(&req), Some(("nsm_document", "MALFORMED")));
+        req.envelope.format = "sev-snp-v2".into();
+        assert_eq!(failure(&req), Some(("sev_snp_report", "PCRS_NOT_PINNED")));
+        req.expected_pcrs = BTreeMap::from([("measurement".to_string(), "00".repeat(48))]);
+        assert_eq!(failure(&req), Some(("sev_snp_report", "MALFORMED")));
+        req.envelope.platform_evidence_b64 = Some(STANDARD.encode([0u8; sev_snp::REPORT_LEN]));
+        assert_eq!(failure(&req), Some(("sev_snp_report", "VCEK_REQUIRED")));
+        req.envelope.format = "tpm-quote-weak-v2".into();
+        assert_eq!(failure(&req), Some(("tpm_quote", "AK_REQUIRED")));
+
+        // secp256k1 envelopes: the Sui-serialized signature must carry the envelope's key.
+        let payload = nautilus_canonical::canonical_bytes(&req.envelope.data).unwrap();
+        let secp_key = secp256k1::public_key(&enclave).unwrap();
+        req.envelope.format = "secp256k1-v2".into();
+        req.envelope.signature_b64 = Some(STANDARD.encode(secp256k1::sign(&enclave, &payload).unwrap()));
+        req.envelope.public_key_b64 = Some(STANDARD.encode(&secp_key));
+        req.expected_public_keys = vec![STANDARD.encode(&secp_key)];
+        // Expecting co-signers rules out an envelope that simply drops the co-signature.
+        assert_eq!(failure(&req), Some(("cosignature", "COSIGNATURE_REQUIRED")));
+        req.expected_cosigner_keys.clear();
+        let result = verdict(&req);
+        assert!(result.valid, "{:?}", result.failure);
+        assert_eq!(result.key_id, Some(super::super::key_id_of(&secp_key)));
+        req.envelope.public_key_b64 = Some(STANDARD.encode(secp256k1::public_key(&operator).unwrap()));
+        assert_eq!(failure(&req), Some(("signature", "MALFORMED")));
+        req.envelope.signature_b64 = Some(STANDARD.encode(secp256k1::sign(&enclave, b"other").unwrap()));
+        assert_eq!(failure(&req), Some(("signature", "SIGNATURE")));
+
+        // BLS envelopes sign the quorum message, so the timestamp is outside the signature.
+        let message = bls::quorum_message(&req.envelope.data).unwrap();
+        let bls_key = bls::public_key(&enclave).unwrap();
+        req.envelope.format = "bls12381-v2".into();
+        req.envelope.signature_b64 = Some(STANDARD.encode(bls::sign(&enclave, &message).unwrap()));
+        req.envelope.public_key_b64 = Some(STANDARD.encode(&bls_key));
+        req.expected_public_keys = vec![STANDARD.encode(&bls_key)];
+        req.envelope.data.timestamp += 1;
+        assert!(verdict(&req).valid);
+        // expires_at is, so it can't be raised or dropped.
+        assert!(verdict(&req).passed.contains(&"expiry"));
+        req.envelope.data.expires_at = Some(u64::MAX);
+        assert_eq!(failure(&req), Some(("signature", "SIGNATURE")));
+        req.envelope.data.expires_at = None;
+        assert_eq!(failure(&req), Some(("signature", "SIGNATURE")));
+        req.envelope.data.expires_at = Some(1_000);
+        req.envelope.data.quality_score = 40;
+        assert_eq!(failure(&req), Some(("signature", "SIGNATURE")));
+        req.envelope.canonicalization = Some("xml".into());
+        assert_eq!(failure(&req), Some(("payload_hash", "UNSUPPORTED_CANONICALIZATION")));
+    }
+}
@@ -0,0 +1,442 @@
+use serde::Serialize;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+
+use crate::quality_validator::{SharedProfile, MAX_EVIDENCE_ITEMS};
+
+// Plain-text NLP corpora, one document per line. Byte entropy says little about prose, so text is
+// scored on what language-model training cares about:
+//   diversity     vocabulary richness: moving-average type/token ratio over a sliding window
+//   authenticity  share of text that isn't a repeated line or web boilerplate (cookie banners,
+//                 "all rights reserved", navigation links)
+//   consistency   share of documents in the corpus' main language, and of printable characters
+// Sentence and token statistics and the language mix are reported alongside. Lines are records,
+// so a record sample is profiled like the whole corpus.
+
+// Tokens per window of the moving-average type/token ratio (MATTR); shorter corpora use plain TTR.
+const MATTR_WINDOW: usize = 100;
+// MATTR of ordinary edited prose; reaching it scores full diversity.
+const FULL_MATTR: f64 = 0.7;
+// Lines shorter than this repeat legitimately ("Yes.", headings) and aren't counted as duplicates.
+const MIN_DUPLICATE_CHARS: usize = 20;
+// Distinct lines remembered for duplicate detection.
+const MAX_TRACKED_LINES: usize = 1_000_000;
+// Longer lines are content even when they mention a boilerplate phrase.
+const MAX_BOILERPLATE_CHARS: usize = 300;
+// Documents need this many word tokens before their language is guessed, and only this many
+// tokens are looked at.
+const MIN_LANGUAGE_TOKENS: usize = 5;
+const LANGUAGE_TOKENS: usize = 200;
+// Non-printable share at which consistency's printable half reaches zero.
+const MAX_NON_PRINTABLE: f64 = 0.05;
+// A line is profiled in pieces once it grows past this without a newline.
+const MAX_LINE_BYTES: usize = 1024 * 1024;
+
+const BOILERPLATE: &[&str] = &[
+    "all rights reserved",
+    "cookie",
+    "privacy policy",
+    "terms of service",
+    "terms and conditions",
+    "subscribe to",
+    "sign up for",
+    "click here",
+    "read more",
+    "lorem ipsum",
+    "enable javascript",
+    "javascript is disabled",
+    "follow us on",
+    "share this",
+    "powered by",
+];
+
+// Most frequent function words; a document is in the language whose list it hits most.
+const STOPWORDS: &[(&str, &[&str])] = &[
+    ("en", &["the", "and", "of", "to", "is", "in", "that", "it", "was", "for", "with", "you", "this", "are", "on"]),
+    ("de", &["der", "die", "und", "das", "ist", "nicht", "mit", "ein", "eine", "zu", "den", "sich", "auf", "ich", "auch"]),
+    ("fr", &["le", "la", "les", "et", "est", "des", "une", "un", "du", "que", "pas", "dans", "pour", "qui", "sur"]),
+    ("es", &["el", "los", "las", "que", "y", "en", "es", "por", "una", "con", "para", "del", "se", "no", "como"]),
+    ("it", &["il", "di", "che", "e", "la", "per", "non", "sono", "una", "con", "del", "della", "gli", "anche", "ma"]),
+    ("pt", &["o", "os", "que", "e", "de", "não", "uma", "um", "com", "para", "do", "da", "em", "se", "é"]),
+    ("nl", &["de", "het", "een", "en", "van", "is", "niet", "dat", "op", "zijn", "met", "voor", "ik", "ook", "er"]),
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Script {
+    Latin,
+    Cyrillic,
+    Greek,
+    Arabic,
+    Hebrew,
+    Devanagari,
+    Thai,
+    Hangul,
+    Kana,
+    Han,
+}
+
+impl Script {
+    fn of(c: char) -> Option<Self> {
+        Some(match c as u32 {
+            0x41..=0x5a | 0x61..=0x7a | 0xc0..=0x24f => Self::Latin,
+            0x370..=0x3ff => Self::Greek,
+            0x400..=0x4ff => Self::Cyrillic,
+            0x590..=0x5ff => Self::Hebrew,
+            0x600..=0x6ff => Self::Arabic,
+            0x900..=0x97f => Self::Devanagari,
+            0xe00..=0xe7f => Self::Thai,
+            0xac00..=0xd7af | 0x1100..=0x11ff => Self::Hangul,
+            0x3040..=0x30ff => Self::Kana,
+            0x4e00..=0x9fff | 0x3400..=0x4dbf => Self::Han,
+            _ => return None,
+        })
+    }
+
+    // Language a document in this script is most likely in; Latin needs a closer look.
+    fn language(self) -> &'static str {
+        match self {
+            Self::Latin => "und",
+            Self::Cyrillic => "ru",
+            Self::Greek => "el",
+            Self::Arabic => "ar",
+            Self::Hebrew => "he",
+            Self::Devanagari => "hi",
+            Self::Thai => "th",
+            Self::Hangul => "ko",
+            Self::Kana => "ja",
+            Self::Han => "zh",
+        }
+    }
+
+    // Scripts written without spaces between words; each character counts as a token.
+    fn unspaced(self) -> bool {
+        matches!(self, Self::Han | Self::Kana | Self::Thai)
+    }
+}
+
+#[derive(Debug, Default, Clone, Serialize)]
+pub struct TextReport {
+    pub documents: u64,
+    pub tokens: u64,
+    pub sentences: u64,
+    pub avg_sentence_tokens: f64,
+    pub avg_token_chars: f64,
+    // Moving-average type/token ratio.
+    pub type_token_ratio: f64,
+    // Share of documents long enough to guess at per language ("und" when undetermined).
+    pub languages: BTreeMap<&'static str, f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dominant_language: Option<&'static str>,
+    // Share of the documents whose language was determined that are in the dominant one.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dominant_share: Option<f64>,
+    pub duplicate_ratio: f64,
+    pub boilerplate_ratio: f64,
+    pub non_printable_ratio: f64,
+}
+
+#[derive(Default)]
+pub struct TextProfile {
+    carry: Vec<u8>,
+    documents: u64,
+    chars: u64,
+    non_printable: u64,
+    // Characters of non-blank lines, and of those that were repeats or boilerplate.
+    content_chars: u64,
+    duplicate_chars: u64,
+    boilerplate_chars: u64,
+    seen: HashSet<u64>,
+    // (document index, line hash) of the first repeated lines, as dispute evidence.
+    repeats: Vec<(u64, u64)>,
+    tokens: u64,
+    token_chars: u64,
+    sentences: u64,
+    languages: HashMap<&'static str, u64>,
+    // Token hashes in the current MATTR window and their counts.
+    window: VecDeque<u64>,
+    counts: HashMap<u64, u32>,
+    window_ratio_sum: f64,
+    windows: u64,
+    // Distinct tokens while the corpus is still shorter than one window.
+    vocabulary: HashSet<u64>,
+    report: Option<TextReport>,
+}
+
+impl TextProfile {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn line(&mut self, raw: &[u8]) {
+        let raw = raw.strip_suffix(b"\r").unwrap_or(raw);
+        let text = String::from_utf8_lossy(raw);
+        let mut len = 0usize;
+        for c in text.chars() {
+            len += 1;
+            if c == '\u{fffd}' || (c.is_control() && c != '\t' && c != '\r') {
+                self.non_printable += 1;
+            }
+        }
+        self.chars += len as u64;
+        let trimmed = text.trim();
+        if trimmed.is_empty() {
+            return;
+        }
+        self.documents += 1;
+        self.content_chars += len as u64;
+        let lower = trimmed.to_lowercase();
+        if let Some(key) = self.repeated_line(&lower) {
+            self.duplicate_chars += len as u64;
+            if self.repeats.len() < MAX_EVIDENCE_ITEMS {
+                self.repeats.push((self.documents - 1, key));
+            }
+        } else if len <= MAX_BOILERPLATE_CHARS && BOILERPLATE.iter().any(|p| lower.contains(p)) {
+            self.boilerplate_chars += len as u64;
+        }
+        self.tokenize(&lower);
+    }
+
+    // The hash of a (lowercased) line that repeats an earlier one; short lines never do.
+    fn repeated_line(&mut self, lower: &str) -> Option<u64> {
+        if lower.chars().count() < MIN_DUPLICATE_CHARS {
+            return None;
+        }
+        let key = fnv(lower.split_whitespace().flat_map(|w| w.bytes().chain([b' '])));
+        let new = if self.seen.len() < MAX_TRACKED_LINES { self.seen.insert(key) } else { !self.seen.contains(&key) };
+        (!new).then_some(key)
+    }
+
+    fn tokenize(&mut self, lower: &str) {
+        let mut scripts: HashMap<Script, u32> = HashMap::new();
+        let mut words: Vec<&str> = Vec::new();
+        let mut in_sentence = false;
+        let mut start = None;
+        let mut chars = lower.char_indices().peekable();
+        while let Some((i, c)) = chars.next() {
+            let script = Script::of(c);
+            if let Some(script) = script {
+                *scripts.entry(script).or_insert(0) += 1;
+            }
+            if script.is_some_and(Script::unspaced) {
+                if let Some(s) = start.take() {
+                    self.token(&lower[s..i], &mut words);
+                }
+                self.token(&lower[i..i + c.len_utf8()], &mut words);
+                in_sentence = true;
+            } else if c.is_alphanumeric() {
+                start.get_or_insert(i);
+                in_sentence = true;
+            } else {
+                if let Some(s) = start.take() {
+                    self.token(&lower[s..i], &mut words);
+                }
+                let ends = matches!(c, '.' | '!' | '?' | '。' | '！' | '？')
+                    && chars.peek().is_none_or(|(_, next)| next.is_whitespace() || matches!(c, '。' | '！' | '？'));
+                if ends && in_sentence {
+                    self.sentences += 1;
+                    in_sentence = false;
+                }
+            }
+        }
+        if let Some(s) = start {
+            self.token(&lower[s..], &mut words);
+        }
+        // A document ends its last sentence, punctuated or not.
+        if in_sentence {
+            self.sentences += 1;
+        }
+        if words.len() >= MIN_LANGUAGE_TOKENS {
+            let script = scripts.into_iter().max_by_key(|(s, n)| (*n, *s == Script::Latin)).map(|(s, _)| s);
+            let language = match script {
+                Some(Script::Latin) => latin_language(&words),
+                Some(script) => script.language(),
+                None => "und",
+            };
+            *self.languages.entry(language).or_insert(0) += 1;
+        }
+    }
+
+    fn token<'a>(&mut self, token: &'a str, words: &mut Vec<&'a str>) {
+        self.tokens += 1;
+        self.token_chars += token.chars().count() as u64;
+        if words.len() < LANGUAGE_TOKENS {
+            words.push(token);
+        }
+        let hash = fnv(token.bytes());
+        if self.tokens as usize <= MATTR_WINDOW {
+            self.vocabulary.insert(hash);
+        }
+        self.window.push_back(hash);
+        *self.counts.entry(hash).or_insert(0) += 1;
+        if self.window.len() > MATTR_WINDOW {
+            let old = self.window.pop_front().unwrap_or_default();
+            if let Some(n) = self.counts.get_mut(&old) {
+                *n -= 1;
+                if *n == 0 {
+                    self.counts.remove(&old);
+                }
+            }
+        }
+        if self.window.len() == MATTR_WINDOW {
+            self.window_ratio_sum += self.counts.len() as f64 / MATTR_WINDOW as f64;
+            self.windows += 1;
+        }
+    }
+
+    fn report(&self) -> TextReport {
+        let ratio = |n: u64, d: u64| if d == 0 { 0.0 } else { n as f64 / d as f64 };
+        let identified: u64 = self.languages.values().sum();
+        let languages: BTreeMap<&'static str, f64> =
+            self.languages.iter().map(|(l, n)| (*l, round3(ratio(*n, identified)))).collect();
+        let determined = identified - self.languages.get("und").copied().unwrap_or(0);
+        let dominant = self.languages.iter().filter(|(l, _)| **l != "und").max_by_key(|(l, n)| (**n, *l));
+        let type_token_ratio = if self.windows > 0 {
+            self.window_ratio_sum / self.windows as f64
+        } else {
+            ratio(self.vocabulary.len() as u64, self.tokens)
+        };
+        TextReport {
+            documents: self.documents,
+            tokens: self.tokens,
+            sentences: self.sentences,
+            avg_sentence_tokens: round3(ratio(self.tokens, self.sentences)),
+            avg_token_chars: round3(ratio(self.token_chars, self.tokens)),
+            type_token_ratio: round3(type_token_ratio),
+            languages,
+            dominant_language: dominant.map(|(l, _)| *l),
+            dominant_share: dominant.map(|(_, n)| round3(ratio(*n, determined))),
+            duplicate_ratio: round3(ratio(self.duplicate_chars, self.content_chars)),
+            boilerplate_ratio: round3(ratio(self.boilerplate_chars, self.content_chars)),
+            non_printable_ratio: round3(ratio(self.non_printable, self.chars)),
+        }
+    }
+}
+
+impl SharedProfile for TextProfile {
+    fn update(&mut self, data: &[u8]) {
+        let mut rest = data;
+        while let Some(i) = rest.iter().position(|&b| b == b'\n') {
+            if self.carry.is_empty() {
+                self.line(&rest[..i]);
+            } else {
+                let mut line = std::mem::take(&mut self.carry);
+                line.extend_from_slice(&rest[..i]);
+                self.line(&line);
+            }
+            rest = &rest[i + 1..];
+        }
+        self.carry.extend_from_slice(rest);
+        if self.carry.len() > MAX_LINE_BYTES {
+            // Cut at a character boundary so the piece decodes on its own.
+            let cut = (0..=3).map(|back| self.carry.len() - back).find(|&i| std::str::from_utf8(&self.carry[i..]).is_ok());
+            let tail = self.carry.split_off(cut.unwrap_or(self.carry.len()));
+            let line = std::mem::replace(&mut self.carry, tail);
+            self.line(&line);
+        }
+    }
+
+    fn finish(&mut self) {
+        if self.report.is_some() {
+            return;
+        }
+        if !self.carry.is_empty() {
+            let line = std::mem::take(&mut self.carry);
+            self.line(&line);
+        }
+        self.report = Some(self.report());
+    }
+
+    fn score(&self, check: &str) -> Option<u32> {
+        let report = self.report.as_ref().filter(|r| r.tokens > 0)?;
+        let score = match check {
+            "diversity" => (report.type_token_ratio / FULL_MATTR).min(1.0),
+            "authenticity" => 1.0 - report.duplicate_ratio - report.boilerplate_ratio,
+            "consistency" => {
+                let printable = 1.0 - (report.non_printable_ratio / MAX_NON_PRINTABLE).min(1.0);
+                match report.dominant_share {
+                    Some(share) => 0.5 * share + 0.5 * printable,
+                    None => printable,
+                }
+            }
+            _ => return None,
+        };
+        Some((100.0 * score).clamp(0.0, 100.0).round() as u32)
+    }
+
+    fn state_bytes(&self) -> u64 {
+        (self.carry.capacity() + (self.seen.len() + self.vocabulary.len() + self.repeats.len()) * 16 + self.counts.len() * 24)
+            as u64
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        serde_json::to_value(self.report.as_ref()?).ok().map(|v| ("text", v))
+    }
+
+    fn evidence(&self, check: &str) -> Option<serde_json::Value> {
+        (check == "authenticity").then(|| {
+            let repeats: Vec<_> = self
+                .repeats
+                .iter()
+                .map(|(document, key)| serde_json::json!({ "document": document, "line_hash": format!("{:016x}", key) }))
+                .collect();
+            serde_json::json!({ "repeated_lines": repeats })
+        })
+    }
+}
+
+fn latin_language(words: &[&str]) -> &'static str {
+    let hits = |list: &[&str]| words.iter().filter(|w| list.contains(w)).count();
+    let mut ranked: Vec<(usize, &'static str)> = STOPWORDS.iter().map(|(lang, list)| (hits(list), *lang)).collect();
+    ranked.sort_by_key(|(hits, _)| std::cmp::Reverse(*hits));
+    match ranked.as_slice() {
+        [(best, lang), (second, _), ..] if *best >= 2 && best > second => lang,
+        _ => "und",
+    }
+}
+
+fn fnv(bytes: impl Iterator<Item = u8>) -> u64 {
+    bytes.fold(0xcbf29ce484222325u64, |h, b| (h ^ b as u64).wrapping_mul(0x100000001b3))
+}
+
+fn round3(x: f64) -> f64 {
+    (x * 1000.0).round() / 1000.0
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::quality_validator::{validate_dataset_quality, ValidationOptions};
+
+    #[test]
+    fn test_text_corpus_scored() {
+        let prose = [
+            "The committee published its findings on the river basin after a long review.",
+            "Farmers in the valley reported that the spring floods were earlier than usual.",
+            "Der Bericht wurde nicht veröffentlicht, und die Gemeinde ist mit der Lage unzufrieden.",
+            "Hydrologists measured sediment at twelve stations along the upper tributaries.",
+            "Each sample was dried, weighed and sent to the laboratory for isotope analysis!",
+            "This site uses cookies. Read more in our privacy policy.",
+            "The committee published its findings on the river basin after a long review.",
+            "Results suggest that upstream logging is the main driver of the increased load.",
+        ];
+        let corpus = prose.join("\n") + "\n";
+        let report = validate_dataset_quality(corpus.as_bytes(), &ValidationOptions::default()).unwrap();
+        assert_eq!(report.format.as_str(), "text");
+        let text = &report.details["text"];
+        assert_eq!((text["documents"].as_u64(), text["sentences"].as_u64()), (Some(8), Some(9)));
+        assert_eq!(text["dominant_language"], "en");
+        assert_eq!((&text["languages"]["de"], &text["dominant_share"]), (&0.125.into(), &0.857.into()));
+        // One repeated document, one cookie banner; everything printable.
+        assert!(text["duplicate_ratio"].as_f64().unwrap() > 0.1);
+        assert!(text["boilerplate_ratio"].as_f64().unwrap() > 0.05);
+        assert_eq!(text["non_printable_ratio"], 0.0);
+        let b = &report.breakdown;
+        assert!((61..80).contains(&b.authenticity.unwrap()), "{:?}", b);
+        assert_eq!(b.consistency, Some(93));
+        assert!(b.diversity.unwrap() > 90);
+
+        // The same sentence over and over has no vocabulary to speak of.
+        let repeated = "buy cheap pills now buy cheap pills now\n".repeat(50);
+        let report = validate_dataset_quality(repeated.as_bytes(), &ValidationOptions::default()).unwrap();
+        let b = &report.breakdown;
+        assert!(b.diversity.unwrap() < 10 && b.authenticity.unwrap() < 5, "{:?}", b);
+    }
+}
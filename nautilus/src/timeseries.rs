@@ -0,0 +1,579 @@
+use serde::Serialize;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::category::Category;
+use crate::content_policy::DetectedType;
+use crate::csv_validator::CsvStats;
+use crate::quality_validator::QualityCheck;
+
+// Time-series datasets. A request may name the timestamp column (`timestamp_column`): a CSV
+// header name or a JSON Lines key. Its values are read as RFC 3339 / ISO 8601 dates and times
+// (UTC unless they carry an offset) or as Unix epoch numbers in seconds, milliseconds,
+// microseconds or nanoseconds, and the series is judged in record order on:
+//   - monotonicity: records whose timestamp is earlier than the one before;
+//   - sampling rate: the gaps between consecutive timestamps, by log2 bucket. The share of gaps
+//     within a bucket of the median one is the regularity, and gaps LARGE_GAP_BUCKETS buckets
+//     (16 times) or more above it are reported as outages;
+//   - future-dated records: later than the verification time plus CLOCK_SKEW_MS.
+// Ordering 40%, regularity 30%, records not future-dated 20% and parseable timestamps 10% make a
+// score averaged into consistency with the format's own. A "freshness" check scores the age of
+// the newest timestamp that isn't in the future, halving every FRESHNESS_HALF_LIFE_DAYS after
+// the first day, scaled by the share of records not future-dated. Only counts and the bounds of
+// the series are reported, under "time_series".
+
+const CLOCK_SKEW_MS: i64 = 5 * 60 * 1000;
+const DAY_MS: i64 = 24 * 60 * 60 * 1000;
+const FRESHNESS_HALF_LIFE_DAYS: f64 = 180.0;
+// Weight in every category unless NAUTILUS_CHECK_WEIGHTS overrides it.
+const FRESHNESS_WEIGHT: u32 = 10;
+const GAP_BUCKETS: usize = 64;
+const LARGE_GAP_BUCKETS: usize = 4;
+// A JSON Lines record longer than this isn't parsed; its timestamp counts as missing.
+const MAX_RECORD_BYTES: usize = 1024 * 1024;
+
+// Milliseconds since the Unix epoch of a timestamp value, None when it isn't one.
+pub fn parse_timestamp(value: &str) -> Option<i64> {
+    let value = value.trim().trim_matches('"');
+    if !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit() || b == b'.') {
+        let number: f64 = value.parse().ok()?;
+        // Scaled by magnitude: seconds until the year 5138, then milli-, micro- and nanoseconds.
+        let ms = match number {
+            n if n < 1e11 => n * 1e3,
+            n if n < 1e14 => n,
+            n if n < 1e17 => n / 1e3,
+            n => n / 1e6,
+        };
+        return Some(ms as i64);
+    }
+    let b = value.as_bytes();
+    let digits = |range: std::ops::Range<usize>| -> Option<i64> {
+        let part = b.get(range)?;
+        part.iter().all(u8::is_ascii_digit).then(|| std::str::from_utf8(part).ok()?.parse().ok())?
+    };
+    if b.len() < 10 || b[4] != b'-' || b[7] != b'-' {
+        return None;
+    }
+    let (year, month, day) = (digits(0..4)?, digits(5..7)?, digits(8..10)?);
+    if !(1..=12).contains(&month) || !(1..=days_in_month(year, month)).contains(&day) {
+        return None;
+    }
+    let mut ms = days_from_civil(year, month, day) * DAY_MS;
+    let rest = &b[10..];
+    if rest.is_empty() {
+        return Some(ms);
+    }
+    if !matches!(rest[0], b'T' | b't' | b' ') || rest.len() < 6 || rest[3] != b':' {
+        return None;
+    }
+    let (hour, minute) = (digits(11..13)?, digits(14..16)?);
+    let mut i = 16;
+    let mut second = 0;
+    if b.get(i) == Some(&b':') {
+        second = digits(17..19)?;
+        i = 19;
+    }
+    if hour > 23 || minute > 59 || second > 60 {
+        return None;
+    }
+    ms += ((hour * 60 + minute) * 60 + second) * 1000;
+    if b.get(i) == Some(&b'.') {
+        let end = i + 1 + b[i + 1..].iter().take_while(|c| c.is_ascii_digit()).count();
+        if end == i + 1 {
+            return None;
+        }
+        let fraction = &value[i + 1..end.min(i + 4)];
+        ms += fraction.parse::<i64>().ok()? * 10i64.pow(3 - fraction.len() as u32);
+        i = end;
+    }
+    match b.get(i..)? {
+        [] | [b'Z'] | [b'z'] => Some(ms),
+        [sign @ (b'+' | b'-'), ..] => {
+            let offset = &value[i + 1..];
+            let (h, m) = offset.split_once(':').unwrap_or((offset.get(..2)?, offset.get(2..)?));
+            let (h, m): (i64, i64) = (h.parse().ok()?, m.parse().ok()?);
+            if h > 23 || m > 59 {
+                return None;
+            }
+            let offset_ms = (h * 60 + m) * 60 * 1000;
+            Some(if *sign == b'+' { ms - offset_ms } else { ms + offset_ms })
+        }
+        _ => None,
+    }
+}
+
+fn days_in_month(year: i64, month: i64) -> i64 {
+    match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+// Days since 1970-01-01 of a proleptic Gregorian date (Howard Hinnant's algorithm).
+fn days_from_civil(year: i64, month: i64, day: i64) -> i64 {
+    let y = if month <= 2 { year - 1 } else { year };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let mp = (month + 9) % 12;
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146_097 + doe - 719_468
+}
+
+pub fn now_ms() -> i64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64
+}
+
+// The timestamps of a series in record order, summarized as they arrive.
+#[derive(Debug, Clone)]
+pub struct TimeSeries {
+    now_ms: i64,
+    records: u64,
+    parsed: u64,
+    previous: Option<i64>,
+    out_of_order: u64,
+    repeated: u64,
+    future: u64,
+    earliest: Option<i64>,
+    // Latest timestamp that isn't in the future.
+    newest: Option<i64>,
+    // Positive gaps by floor(log2(ms)), and their sums.
+    gaps: [u64; GAP_BUCKETS],
+    gap_sums: [f64; GAP_BUCKETS],
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TimeSeriesReport {
+    pub column: String,
+    // Whether the dataset has the column.
+    pub found: bool,
+    pub records: u64,
+    pub unparsed: u64,
+    pub out_of_order: u64,
+    pub duplicate_timestamps: u64,
+    pub future_dated: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub earliest_ms: Option<i64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub newest_ms: Option<i64>,
+    // Mean gap within the median log2 bucket, the share of gaps within a bucket of it, and gaps
+    // far longer than it.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub typical_gap_ms: Option<u64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub regularity: Option<f64>,
+    pub large_gaps: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub consistency: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub freshness: Option<u32>,
+}
+
+impl TimeSeries {
+    pub fn new(now_ms: i64) -> Self {
+        Self {
+            now_ms,
+            records: 0,
+            parsed: 0,
+            previous: None,
+            out_of_order: 0,
+            repeated: 0,
+            future: 0,
+            earliest: None,
+            newest: None,
+            gaps: [0; GAP_BUCKETS],
+            gap_sums: [0.0; GAP_BUCKETS],
+        }
+    }
+
+    // None for a record without a parseable timestamp.
+    pub fn add(&mut self, timestamp: Option<i64>) {
+        self.records += 1;
+        let Some(ts) = timestamp else { return };
+        self.parsed += 1;
+        if let Some(previous) = self.previous {
+            match ts.saturating_sub(previous) {
+                gap if gap < 0 => self.out_of_order += 1,
+                0 => self.repeated += 1,
+                gap => {
+                    let bucket = 63 - (gap as u64).leading_zeros() as usize;
+                    self.gaps[bucket] += 1;
+                    self.gap_sums[bucket] += gap as f64;
+                }
+            }
+        }
+        self.previous = Some(ts);
+        self.earliest = Some(self.earliest.map_or(ts, |e| e.min(ts)));
+        if ts > self.now_ms + CLOCK_SKEW_MS {
+            self.future += 1;
+        } else {
+            self.newest = Some(self.newest.map_or(ts, |n| n.max(ts)));
+        }
+    }
+
+    fn median_bucket(&self) -> Option<usize> {
+        let total: u64 = self.gaps.iter().sum();
+        let mut seen = 0;
+        (total > 0).then(|| {
+            self.gaps
+                .iter()
+                .position(|&n| {
+                    seen += n;
+                    seen * 2 >= total
+                })
+                .unwrap_or(0)
+        })
+    }
+
+    fn regularity(&self) -> Option<f64> {
+        let median = self.median_bucket()?;
+        let total: u64 = self.gaps.iter().sum();
+        let near: u64 = self.gaps[median.saturating_sub(1)..(median + 2).min(GAP_BUCKETS)].iter().sum();
+        Some(near as f64 / total as f64)
+    }
+
+    fn future_share(&self) -> f64 {
+        if self.parsed == 0 {
+            0.0
+        } else {
+            self.future as f64 / self.parsed as f64
+        }
+    }
+
+    // None with fewer than two timestamps.
+    pub fn consistency(&self) -> Option<u32> {
+        if self.parsed < 2 {
+            return None;
+        }
+        let ordered = 1.0 - self.out_of_order as f64 / (self.parsed - 1) as f64;
+        // Identical timestamps throughout are as regular as can be.
+        let regularity = self.regularity().unwrap_or(1.0);
+        let parsed = self.parsed as f64 / self.records as f64;
+        let score = 0.4 * ordered + 0.3 * regularity + 0.2 * (1.0 - self.future_share()) + 0.1 * parsed;
+        Some((score * 100.0).round() as u32)
+    }
+
+    // 0 when no timestamp up to now could be read.
+    pub fn freshness(&self) -> u32 {
+        let Some(newest) = self.newest else { return 0 };
+        let age_days = ((self.now_ms - newest - DAY_MS).max(0) as f64) / DAY_MS as f64;
+        let score = 100.0 * (-age_days / FRESHNESS_HALF_LIFE_DAYS).exp2() * (1.0 - self.future_share());
+        score.round() as u32
+    }
+
+    fn report(&self, column: &str, found: bool) -> TimeSeriesReport {
+        let median = self.median_bucket();
+        TimeSeriesReport {
+            column: column.to_string(),
+            found,
+            records: self.records,
+            unparsed: self.records - self.parsed,
+            out_of_order: self.out_of_order,
+            duplicate_timestamps: self.repeated,
+            future_dated: self.future,
+            earliest_ms: self.earliest,
+            newest_ms: self.newest,
+            typical_gap_ms: median.map(|m| (self.gap_sums[m] / self.gaps[m] as f64).round() as u64),
+            regularity: self.regularity().map(|r| (r * 10_000.0).round() / 10_000.0),
+            large_gaps: median.map_or(0, |m| self.gaps[(m + LARGE_GAP_BUCKETS).min(GAP_BUCKETS)..].iter().sum()),
+            consistency: self.consistency(),
+            freshness: (self.parsed > 0).then(|| self.freshness()),
+        }
+    }
+
+    fn state_bytes(&self) -> u64 {
+        std::mem::size_of::<Self>() as u64
+    }
+}
+
+enum Source {
+    Csv(Box<CsvStats>),
+    Jsonl { line: Vec<u8>, overflow: bool, found: bool, series: Box<TimeSeries> },
+}
+
+// The timestamp column of a CSV or JSON Lines dataset, read as the records stream past.
+pub struct TimestampReader {
+    column: String,
+    source: Source,
+}
+
+impl TimestampReader {
+    // None for formats without named columns.
+    pub fn new(format: DetectedType, column: &str, now_ms: i64) -> Option<Self> {
+        let source = match format {
+            DetectedType::Csv => Source::Csv(Box::new(CsvStats::new(false).with_timestamps(column, now_ms))),
+            DetectedType::Jsonl => {
+                Source::Jsonl { line: Vec::new(), overflow: false, found: false, series: Box::new(TimeSeries::new(now_ms)) }
+            }
+            _ => return None,
+        };
+        Some(Self { column: column.to_string(), source })
+    }
+
+    // The series, when the dataset has the column.
+    pub fn series(&self) -> Option<&TimeSeries> {
+        match &self.source {
+            Source::Csv(stats) => stats.timestamps(),
+            Source::Jsonl { found, series, .. } => found.then_some(&**series),
+        }
+    }
+
+    pub fn update(&mut self, data: &[u8]) {
+        if let Source::Csv(stats) = &mut self.source {
+            stats.update(data);
+            return;
+        }
+        for piece in data.split_inclusive(|&b| b == b'\n') {
+            if let Source::Jsonl { line, overflow, .. } = &mut self.source {
+                if line.len() + piece.len() > MAX_RECORD_BYTES {
+                    *overflow = true;
+                    line.clear();
+                } else if !*overflow {
+                    line.extend_from_slice(piece);
+                }
+            }
+            if piece.ends_with(b"\n") {
+                self.end_record();
+            }
+        }
+    }
+
+    pub fn finish(&mut self) {
+        match &mut self.source {
+            Source::Csv(stats) => stats.finish(),
+            Source::Jsonl { .. } => self.end_record(),
+        }
+    }
+
+    pub fn report(&self) -> TimeSeriesReport {
+        match self.series() {
+            Some(series) => series.report(&self.column, true),
+            None => TimeSeries::new(0).report(&self.column, false),
+        }
+    }
+
+    pub fn state_bytes(&self) -> u64 {
+        match &self.source {
+            Source::Csv(stats) => stats.state_bytes(),
+            Source::Jsonl { line, series, .. } => line.capacity() as u64 + series.state_bytes(),
+        }
+    }
+
+    fn end_record(&mut self) {
+        let Source::Jsonl { line, overflow, found, series } = &mut self.source else { return };
+        if line.iter().all(u8::is_ascii_whitespace) && !*overflow {
+            line.clear();
+            return;
+        }
+        let mut record = (!*overflow).then(|| serde_json::from_slice::<serde_json::Value>(line).ok()).flatten();
+        let value = record.as_mut().and_then(|r| r.as_object_mut()?.remove(&self.column));
+        *found |= value.is_some();
+        series.add(match value {
+            Some(serde_json::Value::String(s)) => parse_timestamp(&s),
+            Some(serde_json::Value::Number(n)) => parse_timestamp(&n.to_string()),
+            _ => None,
+        });
+        line.clear();
+        *overflow = false;
+    }
+}
+
+// Consistency from the format's own check (`fallback`) and the ordering and regularity of the
+// timestamp column.
+pub struct TimeSeriesConsistency {
+    timestamps: TimestampReader,
+    fallback: Box<dyn QualityCheck>,
+}
+
+impl TimeSeriesConsistency {
+    pub fn new(timestamps: TimestampReader, fallback: Box<dyn QualityCheck>) -> Self {
+        Self { timestamps, fallback }
+    }
+
+    fn series_score(&self) -> Option<u32> {
+        self.timestamps.series()?.consistency()
+    }
+}
+
+impl QualityCheck for TimeSeriesConsistency {
+    fn name(&self) -> &'static str {
+        self.fallback.name()
+    }
+
+    fn weight(&self, category: Category) -> u32 {
+        self.fallback.weight(category)
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.fallback.update(data);
+        self.timestamps.update(data);
+    }
+
+    fn finish(&mut self) {
+        self.fallback.finish();
+        self.timestamps.finish();
+    }
+
+    fn score(&self, total_len: u64) -> u32 {
+        let records = self.fallback.score(total_len);
+        match self.series_score() {
+            Some(series) => (records + series) / 2,
+            None => records,
+        }
+    }
+
+    fn exclude(&mut self, freq: &[u64; 256]) {
+        self.fallback.exclude(freq);
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.timestamps.state_bytes() + self.fallback.state_bytes()
+    }
+
+    // The format's findings, with the series' under "time_series".
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        let report = serde_json::to_value(self.timestamps.report()).ok()?;
+        match self.fallback.details() {
+            Some((key, mut value)) => {
+                value["time_series"] = report;
+                Some((key, value))
+            }
+            None => Some(("time_series", report)),
+        }
+    }
+
+    fn measures(&self, total_len: u64) -> Vec<(&'static str, f64)> {
+        let mut out = self.fallback.measures(total_len);
+        out.extend(self.series_score().map(|s| ("time_series_consistency", s as f64)));
+        out
+    }
+
+    fn evidence(&self) -> Option<serde_json::Value> {
+        self.fallback.evidence()
+    }
+}
+
+// How recent the newest record is.
+pub struct Freshness {
+    timestamps: TimestampReader,
+}
+
+impl Freshness {
+    pub fn new(timestamps: TimestampReader) -> Self {
+        Self { timestamps }
+    }
+}
+
+impl QualityCheck for Freshness {
+    fn name(&self) -> &'static str {
+        "freshness"
+    }
+
+    fn weight(&self, _category: Category) -> u32 {
+        FRESHNESS_WEIGHT
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.timestamps.update(data);
+    }
+
+    fn finish(&mut self) {
+        self.timestamps.finish();
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        self.timestamps.series().map_or(0, TimeSeries::freshness)
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.timestamps.state_bytes()
+    }
+
+    fn measures(&self, _total_len: u64) -> Vec<(&'static str, f64)> {
+        let Some(series) = self.timestamps.series() else { return Vec::new() };
+        let mut out = vec![("future_dated", series.future as f64)];
+        out.extend(series.newest.map(|n| ("newest_age_days", ((series.now_ms - n) / DAY_MS) as f64)));
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_series_ordering_gaps_and_freshness() {
+        assert_eq!(parse_timestamp("1970-01-02"), Some(DAY_MS));
+        assert_eq!(parse_timestamp("2024-02-29T12:30:00Z"), Some(1_709_209_800_000));
+        assert_eq!(parse_timestamp("2024-02-29 14:30:00.25+02:00"), Some(1_709_209_800_250));
+        assert_eq!(parse_timestamp("1709209800"), parse_timestamp("1709209800000"));
+        assert_eq!(parse_timestamp("1709209800.5"), Some(1_709_209_800_500));
+        assert!(parse_timestamp("2023-02-29").is_none() && parse_timestamp("yesterday").is_none());
+
+        // Hourly readings over ten days up to half a day ago, with two swapped, a twelve-hour
+        // outage, a garbled value and one a year ahead.
+        let now = parse_timestamp("2025-06-01T00:00:00Z").unwrap();
+        let start = now - 11 * DAY_MS;
+        let mut csv = String::from("reading,at\n");
+        for i in 0..240 {
+            let hour = match i {
+                100 => 101,
+                101 => 100,
+                i if i >= 150 => i + 12,
+                i => i,
+            };
+            let at = start + hour * 3_600_000;
+            let (date, time) = (at.div_euclid(DAY_MS), at.rem_euclid(DAY_MS) / 1000);
+            let ymd = |days: i64| parse_date(days);
+            csv.push_str(&format!("{},{}T{:02}:{:02}:00Z\n", i, ymd(date), time / 3600, time % 3600 / 60));
+        }
+        csv.push_str("240,n/a\n241,2026-06-01T00:00:00Z\n");
+
+        let run = |data: &[u8]| {
+            let mut reader = TimestampReader::new(DetectedType::Csv, "at", now).unwrap();
+            data.chunks(13).for_each(|c| reader.update(c));
+            reader.finish();
+            reader
+        };
+        let reader = run(csv.as_bytes());
+        let report = reader.report();
+        assert!(report.found);
+        assert_eq!((report.records, report.unparsed, report.out_of_order, report.future_dated), (242, 1, 1, 1));
+        // The outage and the jump to next year.
+        assert_eq!((report.typical_gap_ms, report.large_gaps), (Some(3_600_000), 2));
+        // The newest reading is under a day old.
+        assert_eq!(Freshness::new(run(csv.as_bytes())).score(0), 100);
+        assert!(report.consistency.unwrap() > 95, "{:?}", report);
+
+        // Shuffled records are out of order and irregular; a stale series isn't fresh.
+        let mut lines: Vec<&str> = csv.lines().skip(1).collect();
+        lines.sort_by_key(|l| l.bytes().fold(0u64, |h, b| h.wrapping_mul(31).wrapping_add(b as u64)) % 1009);
+        let shuffled = run(format!("reading,at\n{}\n", lines.join("\n")).as_bytes());
+        assert!(shuffled.series().unwrap().consistency().unwrap() < 70);
+        let mut stale = TimeSeries::new(now + 360 * DAY_MS);
+        stale.add(Some(now));
+        assert_eq!(stale.freshness(), 25);
+        assert!(!run(b"a,b\n1,2\n").report().found);
+
+        // JSON Lines with epoch seconds.
+        let mut jsonl = TimestampReader::new(DetectedType::Jsonl, "ts", now).unwrap();
+        jsonl.update(format!("{{\"ts\": {}}}\n{{\"ts\": \"{}\"}}\n{{}}\n", now / 1000 - 60, now / 1000 - 30).as_bytes());
+        jsonl.finish();
+        let report = jsonl.report();
+        assert_eq!((report.records, report.unparsed, report.typical_gap_ms), (3, 1, Some(30_000)));
+    }
+
+    // YYYY-MM-DD of days since the epoch, inverting days_from_civil.
+    fn parse_date(days: i64) -> String {
+        let mut year = 1970;
+        while days_from_civil(year + 1, 1, 1) <= days {
+            year += 1;
+        }
+        let mut month = 1;
+        while month < 12 && days_from_civil(year, month + 1, 1) <= days {
+            month += 1;
+        }
+        format!("{:04}-{:02}-{:02}", year, month, days - days_from_civil(year, month, 1) + 1)
+    }
+}
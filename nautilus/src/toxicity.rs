@@ -0,0 +1,271 @@
+use anyhow::{bail, Context, Result};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+
+use crate::category::Category;
+use crate::line_records::LineRecords;
+use crate::quality_validator::QualityCheck;
+
+// Content safety for text corpora, for buyers training user-facing models. Off unless the
+// operator sets NAUTILUS_CONTENT_SAFETY: "builtin" scores against the short English lexicon
+// below, a path against a wordlist of the operator's own (one word or phrase of up to MAX_WORDS
+// words per line, `#` comments; a leading `!` marks a term abusive rather than profane). Each
+// record (line) is lowercased, de-leeted (`sh1t`, `@ss`) and matched word by word, so
+// substrings of longer words don't count. The "content_safety" score takes 100 points per
+// record with an abusive term and 50 per record with only profanity off the share of records;
+// only counts are reported, never the matched terms. The lexicon's SHA-256 is reported with the
+// counts and is part of the config hash. A model-based estimator fits the plugin interface
+// instead (see plugins).
+
+// Weight in every category unless NAUTILUS_CHECK_WEIGHTS overrides it.
+const CONTENT_SAFETY_WEIGHT: u32 = 10;
+const MAX_WORDS: usize = 4;
+const MAX_LEXICON_BYTES: u64 = 4 * 1024 * 1024;
+
+const PROFANITY: &[&str] = &[
+    "fuck", "fucking", "fucked", "fucker", "motherfucker", "shit", "shitty", "bullshit", "bitch", "bastard", "asshole",
+    "dickhead", "piss off", "cunt", "wanker", "twat", "prick", "douchebag", "slut", "whore", "wtf", "stfu",
+];
+const ABUSE: &[&str] = &[
+    "kill yourself", "kys", "go die", "i will kill you", "ill kill you", "die in a fire", "you should die", "retard",
+    "retarded", "subhuman", "worthless piece of shit",
+];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Severity {
+    Profanity,
+    Abuse,
+}
+
+#[derive(Debug)]
+pub struct Lexicon {
+    // Normalized term (words joined by single spaces) to its severity.
+    terms: HashMap<String, Severity>,
+    // Longest term, in words.
+    max_words: usize,
+    pub sha256: String,
+}
+
+impl Lexicon {
+    pub fn builtin() -> Self {
+        let terms = PROFANITY.iter().map(|t| (*t, Severity::Profanity)).chain(ABUSE.iter().map(|t| (*t, Severity::Abuse)));
+        Self::new(terms.map(|(t, s)| (t.to_string(), s)))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let mut terms = Vec::new();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (term, severity) = match line.strip_prefix('!') {
+                Some(term) => (term, Severity::Abuse),
+                None => (line, Severity::Profanity),
+            };
+            let words = normalize(term);
+            if words.is_empty() || words.len() > MAX_WORDS {
+                bail!("wordlist line {}: a term has 1 to {} words", number + 1, MAX_WORDS);
+            }
+            terms.push((words.join(" "), severity));
+        }
+        if terms.is_empty() {
+            bail!("wordlist has no terms");
+        }
+        Ok(Self::new(terms.into_iter()))
+    }
+
+    fn new(terms: impl Iterator<Item = (String, Severity)>) -> Self {
+        let mut map = HashMap::new();
+        for (term, severity) in terms {
+            let term = normalize(&term).join(" ");
+            // A term listed as both is abusive.
+            let entry = map.entry(term).or_insert(severity);
+            if severity == Severity::Abuse {
+                *entry = Severity::Abuse;
+            }
+        }
+        let mut sorted: Vec<_> = map.iter().collect();
+        sorted.sort();
+        let mut hasher = Sha256::new();
+        for (term, severity) in sorted {
+            hasher.update(format!("{}{}\n", if *severity == Severity::Abuse { "!" } else { "" }, term));
+        }
+        let max_words = map.keys().map(|t| t.split(' ').count()).max().unwrap_or(1);
+        Self { terms: map, max_words, sha256: hex::encode(hasher.finalize()) }
+    }
+
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+}
+
+// The lexicon NAUTILUS_CONTENT_SAFETY selects, if any.
+pub fn from_env() -> Result<Option<Arc<Lexicon>>> {
+    let Some(value) = env::var("NAUTILUS_CONTENT_SAFETY").ok().filter(|v| !v.trim().is_empty()) else {
+        return Ok(None);
+    };
+    if value.trim() == "builtin" {
+        return Ok(Some(Arc::new(Lexicon::builtin())));
+    }
+    let len = std::fs::metadata(&value).with_context(|| format!("NAUTILUS_CONTENT_SAFETY wordlist {}", value))?.len();
+    if len > MAX_LEXICON_BYTES {
+        bail!("NAUTILUS_CONTENT_SAFETY wordlist {} exceeds {} bytes", value, MAX_LEXICON_BYTES);
+    }
+    let text = std::fs::read_to_string(&value).with_context(|| format!("NAUTILUS_CONTENT_SAFETY wordlist {}", value))?;
+    let lexicon = Lexicon::parse(&text).with_context(|| format!("NAUTILUS_CONTENT_SAFETY wordlist {}", value))?;
+    Ok(Some(Arc::new(lexicon)))
+}
+
+// Lowercased words with common character substitutions undone and apostrophes dropped. `!`,
+// `@` and `$` stand for letters only inside a word.
+fn normalize(text: &str) -> Vec<String> {
+    let chars: Vec<char> = text.chars().filter(|&c| c != '\'' && c != '\u{2019}').collect();
+    let in_word = |i: usize| chars.get(i).is_some_and(|c| c.is_alphanumeric());
+    let mapped: String = chars
+        .iter()
+        .enumerate()
+        .map(|(i, &c)| match c {
+            '0' => 'o',
+            '1' => 'i',
+            '3' => 'e',
+            '4' => 'a',
+            '5' => 's',
+            '7' => 't',
+            '!' if i > 0 && in_word(i - 1) && in_word(i + 1) => 'i',
+            '@' if in_word(i + 1) => 'a',
+            '$' if in_word(i + 1) => 's',
+            c => c,
+        })
+        .flat_map(char::to_lowercase)
+        .collect();
+    mapped.split(|c: char| !c.is_alphanumeric()).filter(|w| !w.is_empty()).map(str::to_string).collect()
+}
+
+pub struct ToxicityCheck {
+    lexicon: Arc<Lexicon>,
+    // Flagged with the record's severity.
+    lines: LineRecords<&'static str>,
+    tally: Tally,
+}
+
+#[derive(Default)]
+struct Tally {
+    profane: u64,
+    abusive: u64,
+    profanity_matches: u64,
+    abuse_matches: u64,
+}
+
+impl Tally {
+    // Matches lexicon terms of up to `max_words` words at every word of the record.
+    fn record(&mut self, lexicon: &Lexicon, line: &[u8]) -> Option<&'static str> {
+        let words = normalize(&String::from_utf8_lossy(line));
+        let (mut profanity, mut abuse) = (0, 0);
+        for start in 0..words.len() {
+            for len in 1..=lexicon.max_words.min(words.len() - start) {
+                match lexicon.terms.get(&words[start..start + len].join(" ")) {
+                    Some(Severity::Profanity) => profanity += 1,
+                    Some(Severity::Abuse) => abuse += 1,
+                    None => {}
+                }
+            }
+        }
+        self.profanity_matches += profanity;
+        self.abuse_matches += abuse;
+        match (profanity, abuse) {
+            (_, 1..) => {
+                self.abusive += 1;
+                Some("abuse")
+            }
+            (1.., _) => {
+                self.profane += 1;
+                Some("profanity")
+            }
+            _ => None,
+        }
+    }
+}
+
+impl ToxicityCheck {
+    pub fn new(lexicon: Arc<Lexicon>) -> Self {
+        Self { lexicon, lines: LineRecords::default(), tally: Tally::default() }
+    }
+}
+
+impl QualityCheck for ToxicityCheck {
+    fn name(&self) -> &'static str {
+        "content_safety"
+    }
+
+    fn weight(&self, _category: Category) -> u32 {
+        CONTENT_SAFETY_WEIGHT
+    }
+
+    fn update(&mut self, data: &[u8]) {
+        self.lines.update(data, |line| self.tally.record(&self.lexicon, line));
+    }
+
+    fn finish(&mut self) {
+        self.lines.finish(|line| self.tally.record(&self.lexicon, line));
+    }
+
+    fn score(&self, _total_len: u64) -> u32 {
+        if self.lines.records == 0 {
+            return 100;
+        }
+        let penalty = 100 * self.tally.abusive + 50 * self.tally.profane;
+        100 - (penalty / self.lines.records) as u32
+    }
+
+    fn state_bytes(&self) -> u64 {
+        self.lines.state_bytes()
+    }
+
+    fn details(&self) -> Option<(&'static str, serde_json::Value)> {
+        Some((
+            "content_safety",
+            serde_json::json!({
+                "records_scanned": self.lines.records,
+                "records_with_profanity": self.tally.profane,
+                "records_with_abuse": self.tally.abusive,
+                "matches": { "profanity": self.tally.profanity_matches, "abuse": self.tally.abuse_matches },
+                "lexicon": { "terms": self.lexicon.len(), "sha256": self.lexicon.sha256 },
+            }),
+        ))
+    }
+
+    fn evidence(&self) -> Option<serde_json::Value> {
+        Some(self.lines.evidence("kind"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_scored_by_severity() {
+        let mut check = ToxicityCheck::new(Arc::new(Lexicon::builtin()));
+        check.update(b"What a lovely morning\nthis is SH1T, total bullsh!t\n\n");
+        check.update(b"nobody asked, just go die\nScunthorpe and shitake are fine\n");
+        check.finish();
+        assert_eq!((check.lines.records, check.tally.profane, check.tally.abusive), (4, 1, 1));
+        assert_eq!((check.tally.profanity_matches, check.tally.abuse_matches), (2, 1));
+        // 100 for the abusive record and 50 for the profane one, over 4 records.
+        assert_eq!(check.score(0), 63);
+        assert_eq!(normalize("Go die! @ss $5 it's"), ["go", "die", "ass", "ss", "its"]);
+
+        let lexicon = Lexicon::parse("# house list\nfrobnicate\n!Total   Idiot\n").unwrap();
+        assert_eq!(lexicon.len(), 2);
+        assert_ne!(lexicon.sha256, Lexicon::builtin().sha256);
+        let mut check = ToxicityCheck::new(Arc::new(lexicon));
+        check.update(b"you total idiot\nfrobnicate it\nfuck");
+        check.finish();
+        assert_eq!((check.tally.profane, check.tally.abusive), (1, 1));
+        assert!(Lexicon::parse("# nothing\n").is_err());
+        assert!(Lexicon::parse("one two three four five\n").is_err());
+    }
+}
@@ -0,0 +1,285 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Keypair, Signer};
+use rusqlite::{params, Connection, OptionalExtension};
+use serde::Serialize;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::integrity::sha256_hex;
+use crate::key_usage::KeyUsageMonitor;
+use crate::metrics;
+use crate::sealed;
+use crate::tee_attestation::key_id;
+
+// Append-only transcript of every attestation the service issued, in transcript.sqlite in the
+// sealed dir. Entries are numbered from 1 and hash-chained: each entry's hash covers its fields
+// and the previous entry's hash (GENESIS for the first), and the service key signs the hash.
+// Auditors page through GET /attestations?since=<seq> and recompute the chain; an attestation
+// that was dropped or altered after the fact breaks it, and one missing from the transcript
+// altogether is one they hold but cannot find. Entries are never deleted, and their hashed
+// fields never change. An escrowed result's attestation carries its score, so only its hash is
+// chained while it is held; the bytes are filled in once escrow releases it (see disclose).
+
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+const DEFAULT_LIMIT: usize = 100;
+const MAX_LIMIT: usize = 1000;
+
+// The hashed fields of an entry, as canonical JSON.
+#[derive(Serialize)]
+struct Chained<'a> {
+    seq: u64,
+    issued_ms: u64,
+    job_id: &'a str,
+    blob_id: &'a str,
+    attestation_sha256: &'a str,
+    prev_hash: &'a str,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct TranscriptEntry {
+    pub seq: u64,
+    pub issued_ms: u64,
+    pub job_id: String,
+    pub blob_id: String,
+    // SHA-256 of the attestation bytes as returned to the caller (before base64).
+    pub attestation_sha256: String,
+    // None while the attestation is held in escrow.
+    pub attestation_b64: Option<String>,
+    pub prev_hash: String,
+    // SHA-256 of the canonical JSON of seq, issued_ms, job_id, blob_id, attestation_sha256 and
+    // prev_hash.
+    pub entry_hash: String,
+    // ed25519 signature over the 32 bytes of entry_hash by the then-current service key.
+    pub key_id: String,
+    pub public_key_b64: String,
+    pub signature_b64: String,
+}
+
+impl TranscriptEntry {
+    // entry_hash as recomputed from the other fields.
+    pub fn expected_hash(&self) -> Result<String> {
+        let chained = Chained {
+            seq: self.seq,
+            issued_ms: self.issued_ms,
+            job_id: &self.job_id,
+            blob_id: &self.blob_id,
+            attestation_sha256: &self.attestation_sha256,
+            prev_hash: &self.prev_hash,
+        };
+        Ok(sha256_hex(&nautilus_canonical::canonical_bytes(&chained).context("serialize transcript entry")?))
+    }
+}
+
+// A page of GET /attestations. The head (last entry) lets auditors tell the last page from a
+// truncated one and pin the chain between audits.
+#[derive(Debug, Serialize)]
+pub struct TranscriptPage {
+    pub entries: Vec<TranscriptEntry>,
+    pub head_seq: u64,
+    pub head_hash: String,
+}
+
+pub struct Transcript {
+    conn: Mutex<Connection>,
+}
+
+impl Transcript {
+    pub fn open(sealed_dir: Option<&Path>) -> Result<Self> {
+        let conn = sealed::open_db(sealed_dir, "transcript.sqlite")?;
+        conn.execute_batch(
+            "PRAGMA journal_mode = WAL;
+             CREATE TABLE IF NOT EXISTS transcript (
+                 seq INTEGER PRIMARY KEY,
+                 issued_ms INTEGER NOT NULL,
+                 job_id TEXT NOT NULL,
+                 blob_id TEXT NOT NULL,
+                 attestation_sha256 TEXT NOT NULL,
+                 attestation_b64 TEXT NOT NULL,
+                 prev_hash TEXT NOT NULL,
+                 entry_hash TEXT NOT NULL,
+                 key_id TEXT NOT NULL,
+                 public_key_b64 TEXT NOT NULL,
+                 signature_b64 TEXT NOT NULL
+             );",
+        )
+        .context("initialize attestation transcript")?;
+        Ok(Self { conn: Mutex::new(conn) })
+    }
+
+    // Records an issued attestation. Callers hand the attestation out only once this succeeded.
+    // Escrowed attestations are recorded by hash alone until they are disclosed.
+    pub fn append(
+        &self,
+        job_id: &str,
+        blob_id: &str,
+        attestation: &[u8],
+        escrowed: bool,
+        signing_key: &Keypair,
+        usage: &KeyUsageMonitor,
+    ) -> Result<TranscriptEntry> {
+        let mut conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let tx = conn.transaction()?;
+        let (prev_seq, prev_hash) = head(&tx)?;
+        let kid = key_id(&signing_key.public);
+        let mut entry = TranscriptEntry {
+            seq: prev_seq + 1,
+            issued_ms: SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64,
+            job_id: job_id.to_string(),
+            blob_id: blob_id.to_string(),
+            attestation_sha256: sha256_hex(attestation),
+            attestation_b64: (!escrowed).then(|| STANDARD.encode(attestation)),
+            prev_hash,
+            entry_hash: String::new(),
+            key_id: kid.clone(),
+            public_key_b64: STANDARD.encode(signing_key.public.to_bytes()),
+            signature_b64: String::new(),
+        };
+        entry.entry_hash = entry.expected_hash()?;
+        usage.authorize(&kid, "transcript")?;
+        entry.signature_b64 = STANDARD.encode(signing_key.sign(&hex::decode(&entry.entry_hash)?).to_bytes());
+        tx.execute(
+            "INSERT INTO transcript (seq, issued_ms, job_id, blob_id, attestation_sha256, attestation_b64,
+                 prev_hash, entry_hash, key_id, public_key_b64, signature_b64)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+            params![
+                entry.seq as i64,
+                entry.issued_ms as i64,
+                entry.job_id,
+                entry.blob_id,
+                entry.attestation_sha256,
+                entry.attestation_b64.as_deref().unwrap_or_default(),
+                entry.prev_hash,
+                entry.entry_hash,
+                entry.key_id,
+                entry.public_key_b64,
+                entry.signature_b64
+            ],
+        )?;
+        tx.commit()?;
+        metrics::inc_counter("nautilus_transcript_entries_total", "Attestations appended to the transcript", &[]);
+        Ok(entry)
+    }
+
+    // Adds the bytes of an escrowed attestation once escrow released it. Only bytes matching the
+    // chained hash are accepted; whether any entry was filled in is returned.
+    pub fn disclose(&self, job_id: &str, attestation: &[u8]) -> Result<bool> {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let updated = conn.execute(
+            "UPDATE transcript SET attestation_b64 = ?3
+             WHERE job_id = ?1 AND attestation_sha256 = ?2 AND attestation_b64 = ''",
+            params![job_id, sha256_hex(attestation), STANDARD.encode(attestation)],
+        )?;
+        Ok(updated > 0)
+    }
+
+    // Entries after sequence number `since`, oldest first, at most `limit` (DEFAULT_LIMIT when
+    // absent, capped at MAX_LIMIT).
+    pub fn since(&self, since: u64, limit: Option<usize>) -> Result<TranscriptPage> {
+        let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT);
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stmt = conn.prepare(
+            "SELECT seq, issued_ms, job_id, blob_id, attestation_sha256, attestation_b64, prev_hash,
+                 entry_hash, key_id, public_key_b64, signature_b64
+             FROM transcript WHERE seq > ?1 ORDER BY seq LIMIT ?2",
+        )?;
+        let rows = stmt.query_map(params![since as i64, limit as i64], entry_from_row)?;
+        let entries = rows.collect::<rusqlite::Result<Vec<_>>>()?;
+        let (head_seq, head_hash) = head(&conn)?;
+        Ok(TranscriptPage { entries, head_seq, head_hash })
+    }
+}
+
+// Sequence number and hash of the last entry; 0 and GENESIS while empty.
+fn head(conn: &Connection) -> Result<(u64, String)> {
+    let head = conn
+        .query_row("SELECT seq, entry_hash FROM transcript ORDER BY seq DESC LIMIT 1", [], |r| {
+            Ok((r.get::<_, i64>(0)? as u64, r.get::<_, String>(1)?))
+        })
+        .optional()?;
+    Ok(head.unwrap_or((0, GENESIS.to_string())))
+}
+
+fn entry_from_row(r: &rusqlite::Row) -> rusqlite::Result<TranscriptEntry> {
+    Ok(TranscriptEntry {
+        seq: r.get::<_, i64>(0)? as u64,
+        issued_ms: r.get::<_, i64>(1)? as u64,
+        job_id: r.get(2)?,
+        blob_id: r.get(3)?,
+        attestation_sha256: r.get(4)?,
+        attestation_b64: Some(r.get::<_, String>(5)?).filter(|b64| !b64.is_empty()),
+        prev_hash: r.get(6)?,
+        entry_hash: r.get(7)?,
+        key_id: r.get(8)?,
+        public_key_b64: r.get(9)?,
+        signature_b64: r.get(10)?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Verifier;
+
+    #[test]
+    fn test_transcript_chains_and_survives_reopen() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[5u8; 32]).unwrap();
+        let kp = Keypair { public: (&secret).into(), secret };
+        let usage = KeyUsageMonitor::new(crate::key_usage::KeyUsagePolicy::from_env());
+        let dir = std::env::temp_dir().join(format!("nautilus-transcript-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let transcript = Transcript::open(Some(&dir)).unwrap();
+        assert_eq!(transcript.since(0, None).unwrap().head_hash, GENESIS);
+        transcript.append("job-1", "blob-1", b"first", false, &kp, &usage).unwrap();
+        transcript.append("job-2", "blob-2", b"second", false, &kp, &usage).unwrap();
+        drop(transcript);
+        let transcript = Transcript::open(Some(&dir)).unwrap();
+        let third = transcript.append("job-3", "blob-3", b"third", false, &kp, &usage).unwrap();
+
+        let page = transcript.since(0, None).unwrap();
+        assert_eq!((page.head_seq, page.head_hash.as_str()), (3, third.entry_hash.as_str()));
+        let mut prev = GENESIS.to_string();
+        for (entry, seq) in page.entries.iter().zip(1..) {
+            assert_eq!((entry.seq, &entry.prev_hash), (seq, &prev));
+            assert_eq!(entry.expected_hash().unwrap(), entry.entry_hash);
+            let signature = STANDARD.decode(&entry.signature_b64).unwrap();
+            let signature = ed25519_dalek::Signature::from_bytes(&signature).unwrap();
+            assert!(kp.public.verify(&hex::decode(&entry.entry_hash).unwrap(), &signature).is_ok());
+            prev = entry.entry_hash.clone();
+        }
+        assert_eq!(page.entries[1].attestation_sha256, sha256_hex(b"second"));
+
+        let later = transcript.since(1, Some(1)).unwrap();
+        assert_eq!(later.entries.iter().map(|e| e.seq).collect::<Vec<_>>(), [2]);
+        assert_eq!(later.head_seq, 3);
+
+        // An altered entry no longer hashes to what the next one chains to.
+        let mut altered = page.entries[0].clone();
+        altered.blob_id = "other".into();
+        assert_ne!(altered.expected_hash().unwrap(), page.entries[1].prev_hash);
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+
+    #[test]
+    fn test_escrowed_attestation_disclosed_on_release() {
+        let secret = ed25519_dalek::SecretKey::from_bytes(&[6u8; 32]).unwrap();
+        let kp = Keypair { public: (&secret).into(), secret };
+        let usage = KeyUsageMonitor::new(crate::key_usage::KeyUsagePolicy::from_env());
+        let transcript = Transcript::open(None).unwrap();
+        let held = transcript.append("job-1", "blob-1", b"held score", true, &kp, &usage).unwrap();
+        assert_eq!(held.attestation_b64, None);
+        let entry = transcript.since(0, None).unwrap().entries.remove(0);
+        assert_eq!((entry.attestation_b64, entry.attestation_sha256), (None, sha256_hex(b"held score")));
+
+        // Only the chained bytes are accepted, and only once.
+        assert!(!transcript.disclose("job-1", b"other score").unwrap());
+        assert!(transcript.disclose("job-1", b"held score").unwrap());
+        assert!(!transcript.disclose("job-1", b"held score").unwrap());
+        let entry = transcript.since(0, None).unwrap().entries.remove(0);
+        assert_eq!(entry.attestation_b64, Some(STANDARD.encode(b"held score")));
+        assert_eq!(entry.expected_hash().unwrap(), held.entry_hash);
+    }
+}
@@ -1,72 +1,783 @@
 use anyhow::{Context, Result};
-use reqwest::{Client, StatusCode};
+use bytes::Bytes;
+use futures_util::stream::{self, BoxStream};
+use futures_util::{Stream, StreamExt};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, CONTENT_TYPE, ETAG, RANGE};
+use reqwest::{Client, Method, Response, StatusCode};
 use std::env;
-use std::time::Duration;
-use tokio::time::sleep;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::time::{sleep, timeout};
 use tracing::{info, warn};
 
-const DEFAULT_AGGREGATOR: &str = "https://aggregator.walrus-testnet.walrus.space";
+use crate::chaos;
+
+mod aggregators;
+mod disk_cache;
+mod memory_cache;
+mod object_cache;
+mod profile;
+mod retry;
+mod status;
+mod sui;
+mod throttle;
+mod vsock;
+
+pub(crate) use aggregators::AggregatorPool;
+pub use aggregators::Selection;
+use disk_cache::{DiskCache, PendingEntry};
+use memory_cache::MemoryCache;
+use object_cache::ObjectCache;
+pub use profile::Profile;
+pub use retry::RetryPolicy;
+use status::StatusClient;
+use sui::SuiReader;
+pub use sui::{MoveCall, SuiRandomness};
+
+// 2 GiB default ceiling for a single blob; override with WALRUS_MAX_BLOB_BYTES.
+const DEFAULT_MAX_BLOB_BYTES: u64 = 2 * 1024 * 1024 * 1024;
+// 10 GiB default disk cache budget; override with WALRUS_DISK_CACHE_MAX_BYTES.
+const DEFAULT_DISK_CACHE_MAX_BYTES: u64 = 10 * 1024 * 1024 * 1024;
+// 256 MiB memory cache holding blobs of at most 8 MiB each by default.
+const DEFAULT_MEMORY_CACHE_MAX_BYTES: u64 = 256 * 1024 * 1024;
+const DEFAULT_MEMORY_CACHE_MAX_BLOB_BYTES: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WalrusError {
+    #[error("blob exceeds maximum size of {limit} bytes")]
+    TooLarge { limit: u64 },
+    #[error("blob not found")]
+    NotFound,
+    #[error("blob storage expired at epoch {end_epoch} (current epoch {current_epoch})")]
+    Expired { end_epoch: u32, current_epoch: u32 },
+    // Registered on chain but never certified, so storage nodes don't serve it.
+    #[error("blob is not certified{}", end_epoch.map(|e| format!(" (storage paid until epoch {})", e)).unwrap_or_default())]
+    NotCertified { end_epoch: Option<u32> },
+    // Every aggregator failed (unreachable, timed out or erroring) on every attempt.
+    #[error("Walrus fetch failed after {attempts} attempts: {last}")]
+    Unavailable { attempts: u32, last: String },
+}
+
+// What the aggregator reports about a blob without transferring its body.
+#[derive(Debug, Clone, Default)]
+pub struct BlobMetadata {
+    pub size: Option<u64>,
+    pub content_type: Option<String>,
+    pub etag: Option<String>,
+}
+
+// Walrus' `Blob` object for a blob ID, as registered and certified on Sui. Unlike anything an
+// aggregator says, its size is committed to by the blob ID itself.
+#[derive(Debug, Clone)]
+pub struct RegisteredBlob {
+    pub object_id: String,
+    // Unencoded length in bytes.
+    pub size: u64,
+    pub certified_epoch: u32,
+}
+
+// A slice of a blob plus the full blob length when the aggregator reports it.
+#[derive(Debug)]
+pub struct BlobRange {
+    pub data: Vec<u8>,
+    pub total_len: Option<u64>,
+}
+
+// Walrus settings, read once at startup.
+#[derive(Debug, Clone)]
+pub struct WalrusConfig {
+    // Network profile the endpoint defaults came from.
+    pub profile: Profile,
+    pub aggregator_urls: Vec<String>,
+    pub selection: Selection,
+    pub aggregator_cooldown: Duration,
+    pub max_blob_bytes: u64,
+    // Time allowed for an aggregator to start answering before failing over.
+    pub request_timeout: Duration,
+    pub retry: RetryPolicy,
+    // Connection pool tuning for the shared HTTP client.
+    pub pool_max_idle_per_host: usize,
+    pub pool_idle_timeout: Duration,
+    pub connect_timeout: Duration,
+    pub tcp_keepalive: Duration,
+    // Egress for enclaves: an explicit HTTP CONNECT proxy, or a vsock (cid, port) on the parent
+    // that the built-in forwarder tunnels to.
+    pub proxy_url: Option<String>,
+    pub vsock_proxy: Option<(u32, u32)>,
+    // Per-download bandwidth cap in bytes/sec for blob bodies; 0 means unlimited.
+    pub download_rate_limit: u64,
+    // Sui fullnode used to resolve dataset objects to blob IDs, plus where the ID lives on them.
+    pub sui_rpc_url: String,
+    // Publisher used for the service's own uploads (audit archives) and their storage epochs;
+    // uploads fail when the profile has none and WALRUS_PUBLISHER_URL is unset.
+    pub publisher_url: Option<String>,
+    pub store_epochs: u32,
+    pub sui_dataset_type: Option<String>,
+    pub sui_blob_field: String,
+    // Sui objects cached between checkpoint polls; 0 reads every object from the fullnode.
+    pub sui_object_cache_entries: usize,
+    pub sui_checkpoint_poll: Duration,
+    // Storage node queried for blob status when an aggregator reports a blob missing, and to find
+    // a blob's registration on Sui; unset keeps missing blobs as a plain "not found" and leaves
+    // blob IDs unchecked.
+    pub status_node_url: Option<String>,
+    // Seal key servers for decrypting Seal-encrypted blobs.
+    pub seal_key_servers: Vec<String>,
+    pub disk_cache_dir: Option<PathBuf>,
+    pub disk_cache_max_bytes: u64,
+    // 0 disables the in-memory cache.
+    pub memory_cache_max_bytes: u64,
+    pub memory_cache_max_blob_bytes: u64,
+    // Local dev shortcut: synthesize bytes for `test_*` / `mock` blob ids.
+    pub allow_mock: bool,
+}
+
+impl WalrusConfig {
+    pub fn from_env() -> Result<Self> {
+        let profile = Profile::parse(&env::var("WALRUS_PROFILE").unwrap_or_else(|_| "testnet".into()))?;
+        let defaults = profile.endpoints();
+        // WALRUS_AGGREGATOR_URLS (comma-separated) takes precedence over the legacy single
+        // WALRUS_AGGREGATOR_URL; the profile's public aggregators are the last resort.
+        let aggregator_urls = env_list("WALRUS_AGGREGATOR_URLS").or_else(|| env_list("WALRUS_AGGREGATOR_URL"));
+        let aggregator_urls = match (aggregator_urls, defaults) {
+            (Some(urls), _) => urls,
+            (None, Some(d)) => d.aggregators.iter().map(|u| u.to_string()).collect(),
+            (None, None) => anyhow::bail!("WALRUS_PROFILE=custom needs WALRUS_AGGREGATOR_URLS"),
+        };
+        let sui_rpc_url = match (env::var("SUI_RPC_URL").ok().filter(|u| !u.is_empty()), defaults) {
+            (Some(url), _) => url,
+            (None, Some(d)) => d.sui_rpc.to_string(),
+            (None, None) => anyhow::bail!("WALRUS_PROFILE=custom needs SUI_RPC_URL"),
+        };
+        let publisher_url = env::var("WALRUS_PUBLISHER_URL")
+            .ok()
+            .filter(|u| !u.is_empty())
+            .or_else(|| defaults.and_then(|d| d.publisher).map(str::to_string))
+            .map(|u| u.trim_end_matches('/').to_string());
+        let seal_key_servers = env_list("SEAL_KEY_SERVERS")
+            .unwrap_or_else(|| defaults.map_or(&[][..], |d| d.seal_key_servers).iter().map(|u| u.to_string()).collect());
+        let selection = match env::var("WALRUS_AGGREGATOR_SELECTION").as_deref() {
+            Ok("latency") => Selection::LatencyAware,
+            _ => Selection::RoundRobin,
+        };
+        Ok(Self {
+            profile,
+            aggregator_urls,
+            selection,
+            aggregator_cooldown: Duration::from_secs(env_parse("WALRUS_AGGREGATOR_COOLDOWN_SECS", 30)),
+            max_blob_bytes: env_parse("WALRUS_MAX_BLOB_BYTES", DEFAULT_MAX_BLOB_BYTES),
+            request_timeout: Duration::from_secs(env_parse("WALRUS_REQUEST_TIMEOUT_SECS", 30)),
+            retry: RetryPolicy::from_env(),
+            pool_max_idle_per_host: env_parse("WALRUS_POOL_MAX_IDLE_PER_HOST", 32),
+            pool_idle_timeout: Duration::from_secs(env_parse("WALRUS_POOL_IDLE_TIMEOUT_SECS", 90)),
+            connect_timeout: Duration::from_secs(env_parse("WALRUS_CONNECT_TIMEOUT_SECS", 10)),
+            tcp_keepalive: Duration::from_secs(env_parse("WALRUS_TCP_KEEPALIVE_SECS", 60)),
+            proxy_url: env::var("WALRUS_PROXY_URL").ok().filter(|u| !u.is_empty()),
+            vsock_proxy: env::var("WALRUS_VSOCK_PROXY").ok().and_then(|v| vsock::parse_vsock_addr(&v)),
+            download_rate_limit: env_parse("WALRUS_DOWNLOAD_RATE_BYTES_PER_SEC", 0),
+            publisher_url,
+            store_epochs: env_parse("WALRUS_STORE_EPOCHS", 5),
+            sui_rpc_url,
+            sui_dataset_type: env::var("SUI_DATASET_TYPE").ok().filter(|t| !t.is_empty()),
+            sui_blob_field: env::var("SUI_DATASET_BLOB_FIELD").unwrap_or_else(|_| "blob_id".to_string()),
+            sui_object_cache_entries: env_parse("SUI_OBJECT_CACHE_ENTRIES", 1024),
+            sui_checkpoint_poll: Duration::from_millis(env_parse("SUI_CHECKPOINT_POLL_MS", 2000)),
+            status_node_url: env::var("WALRUS_STATUS_NODE_URL")
+                .ok()
+                .filter(|u| !u.is_empty())
+                .map(|u| u.trim_end_matches('/').to_string()),
+            seal_key_servers,
+            disk_cache_dir: env::var("WALRUS_DISK_CACHE_DIR").ok().filter(|d| !d.is_empty()).map(PathBuf::from),
+            disk_cache_max_bytes: env_parse("WALRUS_DISK_CACHE_MAX_BYTES", DEFAULT_DISK_CACHE_MAX_BYTES),
+            memory_cache_max_bytes: env_parse("WALRUS_MEMORY_CACHE_MAX_BYTES", DEFAULT_MEMORY_CACHE_MAX_BYTES),
+            memory_cache_max_blob_bytes: env_parse(
+                "WALRUS_MEMORY_CACHE_MAX_BLOB_BYTES",
+                DEFAULT_MEMORY_CACHE_MAX_BLOB_BYTES,
+            ),
+            allow_mock: env::var("WALRUS_ALLOW_MOCK")
+                .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
+                .unwrap_or(false),
+        })
+    }
+}
+
+// Comma-separated, trimmed, non-empty entries; None when the variable is unset or lists nothing.
+fn env_list(key: &str) -> Option<Vec<String>> {
+    let items: Vec<String> = env::var(key)
+        .ok()?
+        .split(',')
+        .map(|u| u.trim().to_string())
+        .filter(|u| !u.is_empty())
+        .collect();
+    (!items.is_empty()).then_some(items)
+}
 
 pub struct WalrusClient {
     http: Client,
-    aggregator_url: String,
+    aggregators: AggregatorPool,
+    max_blob_bytes: u64,
+    request_timeout: Duration,
+    retry: RetryPolicy,
+    download_rate_limit: u64,
+    publisher_url: Option<String>,
+    store_epochs: u32,
+    sui: SuiReader,
+    status: Option<StatusClient>,
+    disk_cache: Option<Arc<DiskCache>>,
+    memory_cache: Option<Arc<MemoryCache>>,
+    allow_mock: bool,
+    // Egress proxy in use (vsock forwarder or WALRUS_PROXY_URL), shared with other outbound sources.
+    proxy_url: Option<String>,
 }
 
 impl WalrusClient {
-    pub fn new() -> Result<Self> {
-        let aggregator_url = env::var("WALRUS_AGGREGATOR_URL").unwrap_or_else(|_| DEFAULT_AGGREGATOR.to_string());
-        let http = Client::builder()
+    // Build the long-lived client; pooled connections, aggregator health and the caches live as
+    // long as it does, so it is created once at startup and shared by every request.
+    pub fn new(config: &WalrusConfig) -> Result<Self> {
+        let mut builder = Client::builder()
             .use_rustls_tls()
+            .pool_max_idle_per_host(config.pool_max_idle_per_host)
+            .pool_idle_timeout(config.pool_idle_timeout)
+            .connect_timeout(config.connect_timeout)
+            .tcp_keepalive(config.tcp_keepalive);
+        let proxy_url = match (config.vsock_proxy, &config.proxy_url) {
+            (Some(_), Some(_)) => anyhow::bail!("Set only one of WALRUS_PROXY_URL and WALRUS_VSOCK_PROXY"),
+            (Some((cid, port)), None) => Some(format!("http://{}", vsock::spawn_forwarder(cid, port)?)),
+            (None, url) => url.clone(),
+        };
+        if let Some(url) = &proxy_url {
+            info!(proxy = %url, "Walrus requests go through a proxy");
+            builder = builder.proxy(reqwest::Proxy::all(url).context("Invalid Walrus proxy URL")?);
+        }
+        if config.download_rate_limit > 0 {
+            info!(bytes_per_sec = config.download_rate_limit, "Walrus downloads are rate limited");
+        }
+        let http = builder
             .build()
             .context("Failed building reqwest client")?;
-        Ok(Self { http, aggregator_url })
+        let disk_cache = config.disk_cache_dir.as_ref().and_then(|dir| {
+            match DiskCache::new(dir.clone(), config.disk_cache_max_bytes) {
+                Ok(cache) => {
+                    info!(dir = %dir.display(), max_bytes = config.disk_cache_max_bytes, "Walrus disk cache enabled");
+                    Some(Arc::new(cache))
+                }
+                Err(err) => {
+                    warn!(dir = %dir.display(), %err, "Walrus disk cache disabled");
+                    None
+                }
+            }
+        });
+        let sui = SuiReader::new(
+            http.clone(),
+            config.sui_rpc_url.clone(),
+            config.request_timeout,
+            config.sui_dataset_type.clone(),
+            config.sui_blob_field.clone(),
+            (config.sui_object_cache_entries > 0)
+                .then(|| ObjectCache::new(config.sui_object_cache_entries, config.sui_checkpoint_poll)),
+        );
+        let status = config
+            .status_node_url
+            .clone()
+            .map(|url| StatusClient::new(http.clone(), url, config.request_timeout));
+        Ok(Self {
+            http,
+            aggregators: AggregatorPool::new(
+                config.aggregator_urls.clone(),
+                config.selection,
+                config.aggregator_cooldown,
+            ),
+            max_blob_bytes: config.max_blob_bytes,
+            request_timeout: config.request_timeout,
+            retry: config.retry.clone(),
+            download_rate_limit: config.download_rate_limit,
+            publisher_url: config.publisher_url.clone(),
+            store_epochs: config.store_epochs,
+            sui,
+            status,
+            disk_cache,
+            memory_cache: (config.memory_cache_max_bytes > 0).then(|| {
+                Arc::new(MemoryCache::new(config.memory_cache_max_bytes, config.memory_cache_max_blob_bytes))
+            }),
+            allow_mock: config.allow_mock,
+            proxy_url,
+        })
+    }
+
+    pub fn egress_proxy(&self) -> Option<&str> {
+        self.proxy_url.as_deref()
+    }
+
+    pub fn aggregator_count(&self) -> usize {
+        self.aggregators.len()
+    }
+
+    pub fn disk_cache_enabled(&self) -> bool {
+        self.disk_cache.is_some()
+    }
+
+    // Upload `data` through the publisher and return its (content-derived) blob ID.
+    pub async fn store_blob(&self, data: Vec<u8>) -> Result<String> {
+        let publisher = self.publisher_url.as_deref().context("No Walrus publisher configured (WALRUS_PUBLISHER_URL)")?;
+        let url = format!("{}/v1/blobs?epochs={}", publisher, self.store_epochs);
+        let resp: serde_json::Value = self
+            .http
+            .put(&url)
+            .timeout(self.request_timeout)
+            .body(data)
+            .send()
+            .await
+            .with_context(|| format!("Walrus publisher {} unreachable", publisher))?
+            .error_for_status()
+            .context("Walrus store failed")?
+            .json()
+            .await
+            .context("Invalid Walrus publisher response")?;
+        // Fresh uploads report the new Blob object; re-uploads of known content only its ID.
+        resp.pointer("/newlyCreated/blobObject/blobId")
+            .or_else(|| resp.pointer("/alreadyCertified/blobId"))
+            .and_then(|v| v.as_str())
+            .map(str::to_string)
+            .ok_or_else(|| anyhow::anyhow!("Walrus publisher response has no blob ID: {}", resp))
+    }
+
+    // Blob ID recorded on a registered Sui dataset object.
+    pub async fn resolve_sui_object(&self, object_id: &str) -> Result<String> {
+        self.sui.resolve_blob_id(object_id).await
+    }
+
+    pub async fn sui_randomness(&self) -> Result<SuiRandomness> {
+        self.sui.randomness().await
+    }
+
+    pub async fn sui_object_exists(&self, object_id: &str) -> Result<bool> {
+        self.sui.object_exists(object_id).await
+    }
+
+    pub async fn sui_build_move_call(&self, call: &MoveCall) -> Result<Vec<u8>> {
+        self.sui.build_move_call(call).await
     }
 
-    pub async fn fetch_blob(&self, blob_id: &str) -> Result<Vec<u8>> {
-        // Optional local dev shortcut: if WALRUS_ALLOW_MOCK is enabled and the blob_id
-        // looks like a test id, return synthetic bytes so the service can be exercised
-        // without requiring a real Walrus blob.
-        let allow_mock = env::var("WALRUS_ALLOW_MOCK")
-            .map(|v| v == "1" || v.eq_ignore_ascii_case("true"))
-            .unwrap_or(false);
-        if allow_mock && (blob_id.starts_with("test_") || blob_id == "mock") {
+    pub async fn sui_execute(&self, tx_bytes: &[u8], signature: &str) -> Result<String> {
+        self.sui.execute(tx_bytes, signature).await
+    }
+
+    // Buffer the blob, aborting with `WalrusError::TooLarge` past `limit` (itself capped by WALRUS_MAX_BLOB_BYTES).
+    pub async fn fetch_blob(&self, blob_id: &str, limit: u64) -> Result<Vec<u8>> {
+        let mut body = self.fetch_blob_stream(blob_id, limit).await?;
+        let mut out = Vec::new();
+        while let Some(chunk) = body.next().await {
+            out.extend_from_slice(&chunk?);
+        }
+        Ok(out)
+    }
+
+    // Stream the blob body chunk by chunk instead of buffering it.
+    // The stream yields an error (and should be dropped) once more than `max_bytes` (itself capped
+    // by WALRUS_MAX_BLOB_BYTES) have arrived; a Content-Length above the cap is rejected before any
+    // body bytes are read. Network bodies are copied into the caches as they pass.
+    pub async fn fetch_blob_stream(&self, blob_id: &str, max_bytes: u64) -> Result<BoxStream<'static, Result<Bytes>>> {
+        let max_bytes = max_bytes.min(self.max_blob_bytes);
+        if self.mock_blob_allowed(blob_id) {
             info!(%blob_id, "WALRUS_ALLOW_MOCK=1 and test blob id detected; returning synthetic blob bytes");
-            return Ok(generate_mock_blob(blob_id));
+            let mock = Bytes::from(generate_mock_blob(blob_id));
+            return Ok(capped(stream::iter(vec![Ok(mock)]).boxed(), max_bytes).boxed());
+        }
+
+        if let Some(data) = self.memory_cache.as_ref().and_then(|mem| mem.get(blob_id)) {
+            info!(%blob_id, size = data.len(), "Serving Walrus blob from memory cache");
+            return Ok(capped(stream::iter(vec![Ok(data)]).boxed(), max_bytes).boxed());
+        }
+        if let Some(cache) = self.disk_cache.as_ref() {
+            if let Some(body) = cache.open(blob_id).await {
+                info!(%blob_id, "Streaming Walrus blob from disk cache");
+                // Disk hits are promoted into the memory cache when small enough.
+                return Ok(self.tee_into_caches(blob_id, capped(body, max_bytes).boxed(), None));
+            }
+        }
+
+        let resp = self.request_blob(Method::GET, blob_id, None).await?;
+        if let Some(len) = resp.content_length() {
+            if len > max_bytes {
+                return Err(WalrusError::TooLarge { limit: max_bytes }.into());
+            }
+        }
+        let mut body = resp
+            .bytes_stream()
+            .map(|chunk| chunk.context("Read Walrus body failed"))
+            .boxed();
+        // Only network transfers are paced; cache hits and mock blobs never touch the proxy.
+        if self.download_rate_limit > 0 {
+            body = throttle::throttled(body, self.download_rate_limit);
+        }
+        let disk = match &self.disk_cache {
+            Some(cache) => cache.begin(blob_id).await,
+            None => None,
+        };
+        Ok(self.tee_into_caches(blob_id, capped(body, max_bytes).boxed(), disk))
+    }
+
+    // Copy a body into the caches as it streams past. Entries are published only once the body
+    // ended cleanly, so failed, abandoned or oversized downloads never leave partial blobs behind.
+    fn tee_into_caches(
+        &self,
+        blob_id: &str,
+        body: BoxStream<'static, Result<Bytes>>,
+        disk: Option<PendingEntry>,
+    ) -> BoxStream<'static, Result<Bytes>> {
+        let memory = self.memory_cache.clone().map(|mem| (mem, Vec::new()));
+        if disk.is_none() && memory.is_none() {
+            return body;
         }
+        let tee = CacheTee { blob_id: blob_id.to_string(), disk, memory };
+        stream::unfold((body, Some(tee)), |(mut body, mut tee)| async move {
+            match body.next().await {
+                Some(Ok(chunk)) => {
+                    if let Some(tee) = tee.as_mut() {
+                        tee.write(&chunk).await;
+                    }
+                    Some((Ok(chunk), (body, tee)))
+                }
+                // Dropping the tee discards whatever was copied so far.
+                Some(Err(err)) => Some((Err(err), (body, None))),
+                None => {
+                    if let Some(tee) = tee.take() {
+                        tee.publish().await;
+                    }
+                    None
+                }
+            }
+        })
+        .boxed()
+    }
+
+    // HEAD the blob so callers can fail fast (missing blob, oversized blob) before downloading.
+    pub async fn blob_metadata(&self, blob_id: &str) -> Result<BlobMetadata> {
+        if self.mock_blob_allowed(blob_id) {
+            let size = generate_mock_blob(blob_id).len() as u64;
+            return Ok(BlobMetadata { size: Some(size), ..Default::default() });
+        }
+        if let Some(cached) = self.cached(blob_id).await {
+            return Ok(BlobMetadata { size: Some(cached.len() as u64), ..Default::default() });
+        }
+
+        let resp = self.request_blob(Method::HEAD, blob_id, None).await?;
+        let header = |name| resp.headers().get(name).and_then(|v| v.to_str().ok()).map(str::to_string);
+        Ok(BlobMetadata {
+            size: header(CONTENT_LENGTH).and_then(|v| v.parse().ok()),
+            content_type: header(CONTENT_TYPE),
+            etag: header(ETAG),
+        })
+    }
+
+    // Read `len` bytes starting at `offset` using an HTTP Range request.
+    // Aggregators that ignore Range and answer 200 are handled by slicing the full body locally.
+    pub async fn fetch_blob_range(&self, blob_id: &str, offset: u64, len: u64) -> Result<BlobRange> {
+        if len == 0 {
+            anyhow::bail!("range length must be non-zero");
+        }
+        if self.mock_blob_allowed(blob_id) {
+            let mock = generate_mock_blob(blob_id);
+            return Ok(slice_range(&mock, offset, len));
+        }
+        if let Some(cached) = self.cached(blob_id).await {
+            return Ok(slice_range(&cached, offset, len));
+        }
+
+        let end = offset.checked_add(len - 1).context("range end overflows")?;
+        let range = format!("bytes={}-{}", offset, end);
+        let resp = self.request_blob(Method::GET, blob_id, Some(&range)).await?;
+        match resp.status() {
+            StatusCode::PARTIAL_CONTENT => {
+                let total_len = resp
+                    .headers()
+                    .get(CONTENT_RANGE)
+                    .and_then(|v| v.to_str().ok())
+                    .and_then(parse_content_range_total);
+                // A conforming aggregator never sends more than the range; stop reading if one does.
+                let data = read_capped(resp, len.min(self.max_blob_bytes)).await.map_err(|err| {
+                    match err.downcast_ref::<WalrusError>() {
+                        Some(WalrusError::TooLarge { .. }) if len <= self.max_blob_bytes => {
+                            anyhow::anyhow!("Walrus returned more than {} bytes for a {}-byte range", len, len)
+                        }
+                        _ => err,
+                    }
+                })?;
+                Ok(BlobRange { data, total_len })
+            }
+            _ => {
+                warn!(%blob_id, "Aggregator ignored Range header; slicing full blob");
+                let full = read_capped(resp, self.max_blob_bytes).await?;
+                Ok(slice_range(&full, offset, len))
+            }
+        }
+    }
+
+    // The blob's registration on Sui, reached through the certification event the storage node's
+    // status names. None for mock blobs, quilt patches (their blob is the whole quilt), deletable
+    // blobs (their status names no event) and without a status node.
+    pub async fn registered_blob(&self, blob_id: &str) -> Result<Option<RegisteredBlob>> {
+        if self.mock_blob_allowed(blob_id) || status_blob_id(blob_id) != Some(blob_id) {
+            return Ok(None);
+        }
+        let Some(status) = &self.status else {
+            return Ok(None);
+        };
+        let Some((tx_digest, event_seq)) = status.status_event(blob_id).await? else {
+            return Ok(None);
+        };
+        self.sui.registered_blob(blob_id, &tx_digest, event_seq).await.map(Some)
+    }
+
+    // Optional local dev shortcut: if WALRUS_ALLOW_MOCK is enabled and the blob_id
+    // looks like a test id, return synthetic bytes so the service can be exercised
+    // without requiring a real Walrus blob.
+    fn mock_blob_allowed(&self, blob_id: &str) -> bool {
+        self.allow_mock && (blob_id.starts_with("test_") || blob_id == "mock")
+    }
 
-        // Exponential backoff: 250ms, 500ms, 1000ms
+    // Memory first, then disk; disk hits are promoted into the memory cache.
+    async fn cached(&self, blob_id: &str) -> Option<Bytes> {
+        if self.mock_blob_allowed(blob_id) {
+            return None;
+        }
+        if let Some(data) = self.memory_cache.as_ref().and_then(|mem| mem.get(blob_id)) {
+            info!(%blob_id, size = data.len(), "Serving Walrus blob from memory cache");
+            return Some(data);
+        }
+        let data = Bytes::from(self.disk_cache.as_ref()?.get(blob_id).await?);
+        info!(%blob_id, size = data.len(), "Serving Walrus blob from disk cache");
+        if let Some(mem) = &self.memory_cache {
+            mem.insert(blob_id, data.clone());
+        }
+        Some(data)
+    }
+
+    // Request a blob from the aggregators, upgrading a 404 to `Expired`/`NotCertified` when the
+    // storage node's status for the blob says so.
+    async fn request_blob(&self, method: Method, blob_id: &str, range: Option<&str>) -> Result<Response> {
+        let err = match self.request_with_retry(method, &blob_path(blob_id), range).await {
+            Ok(resp) => return Ok(resp),
+            Err(err) => err,
+        };
+        let (Some(status), Some(status_id)) = (&self.status, status_blob_id(blob_id)) else {
+            return Err(err);
+        };
+        if !matches!(err.downcast_ref::<WalrusError>(), Some(WalrusError::NotFound)) {
+            return Err(err);
+        }
+        match status.explain_missing(status_id).await {
+            Ok(Some(explained)) => Err(explained.into()),
+            Ok(None) => Err(err),
+            Err(status_err) => {
+                warn!(%blob_id, err = %status_err, "Walrus blob status lookup failed");
+                Err(err)
+            }
+        }
+    }
+
+    // Send `method` for `path` to the aggregator pool. Within each attempt, endpoints that fail with a
+    // transport error, timeout or 5xx are marked unhealthy and the next one is tried.
+    async fn request_with_retry(&self, method: Method, path: &str, range: Option<&str>) -> Result<Response> {
+        // Each round tries every aggregator once; rounds are separated by the policy's backoff.
         let mut attempt: u32 = 0;
-        let max_attempts: u32 = 3;
         loop {
             attempt += 1;
-            // Walrus aggregator exposes blobs under /v1/blobs/{blob_id}
-            let url = format!("{}/v1/{}", self.aggregator_url, format!("blobs/{}", blob_id));
-            info!(%url, attempt, "Fetching Walrus blob");
-            let resp = self.http.get(&url).send().await.context("Walrus GET failed")?;
-            match resp.status() {
-                StatusCode::OK => {
-                    let bytes = resp.bytes().await.context("Read Walrus body failed")?;
-                    return Ok(bytes.to_vec());
+            let mut last_err = String::new();
+            for idx in self.aggregators.candidates() {
+                let url = format!("{}{}", self.aggregators.url(idx), path);
+                info!(%url, attempt, "Fetching Walrus blob");
+                let mut req = self.http.request(method.clone(), &url);
+                if let Some(range) = range {
+                    req = req.header(RANGE, range);
                 }
-                status if attempt < max_attempts => {
-                    warn!(%status, attempt, "Walrus fetch failed, retrying with backoff");
-                    let backoff_ms = 250u64 << (attempt - 1);
-                    sleep(Duration::from_millis(backoff_ms)).await;
+                if let Err(err) = chaos::inject(chaos::FaultPoint::WalrusFetch).await {
+                    self.aggregators.record_failure(idx);
+                    last_err = format!("{}: {}", url, err);
                     continue;
                 }
-                status => {
-                    let txt = resp.text().await.unwrap_or_default();
-                    anyhow::bail!("Walrus returned {} after {} attempts: {}", status, attempt, txt);
+                let started = Instant::now();
+                let resp = match timeout(self.request_timeout, req.send()).await {
+                    Ok(Ok(resp)) => resp,
+                    Ok(Err(err)) => {
+                        warn!(%url, %err, "Walrus aggregator unreachable, failing over");
+                        self.aggregators.record_failure(idx);
+                        last_err = format!("{}: {}", url, err);
+                        continue;
+                    }
+                    Err(_) => {
+                        warn!(%url, "Walrus aggregator timed out, failing over");
+                        self.aggregators.record_failure(idx);
+                        last_err = format!("{}: timed out after {:?}", url, self.request_timeout);
+                        continue;
+                    }
+                };
+                match resp.status() {
+                    StatusCode::OK | StatusCode::PARTIAL_CONTENT => {
+                        self.aggregators.record_success(idx, started.elapsed());
+                        return Ok(resp);
+                    }
+                    status if self.retry.is_retryable(status) => {
+                        warn!(%url, %status, "Walrus aggregator error, failing over");
+                        self.aggregators.record_failure(idx);
+                        last_err = format!("{} returned {}", url, status);
+                    }
+                    StatusCode::NOT_FOUND => {
+                        self.aggregators.record_success(idx, started.elapsed());
+                        return Err(WalrusError::NotFound.into());
+                    }
+                    status => {
+                        // The aggregator answered; the problem is the request, not the endpoint,
+                        // so neither failover nor another round would help.
+                        self.aggregators.record_success(idx, started.elapsed());
+                        let txt = resp.text().await.unwrap_or_default();
+                        anyhow::bail!("Walrus fetch failed: {} returned {}: {}", url, status, txt);
+                    }
                 }
             }
+            if attempt >= self.retry.max_attempts {
+                return Err(WalrusError::Unavailable { attempts: attempt, last: last_err }.into());
+            }
+            let backoff = self.retry.backoff(attempt);
+            warn!(attempt, ?backoff, error = %last_err, "Walrus fetch failed, retrying with backoff");
+            sleep(backoff).await;
         }
     }
 }
 
-// Convenience function to preserve existing call sites.
-pub async fn fetch_blob(blob_id: &str) -> Result<Vec<u8>> {
-    let client = WalrusClient::new()?;
-    client.fetch_blob(blob_id).await
+struct CacheTee {
+    blob_id: String,
+    disk: Option<PendingEntry>,
+    // Buffered copy for the memory cache; dropped once the blob outgrows the per-entry limit.
+    memory: Option<(Arc<MemoryCache>, Vec<u8>)>,
+}
+
+impl CacheTee {
+    async fn write(&mut self, chunk: &[u8]) {
+        if let Some(disk) = self.disk.as_mut() {
+            disk.write(chunk).await;
+        }
+        let fits = self
+            .memory
+            .as_ref()
+            .is_some_and(|(mem, buf)| (buf.len() + chunk.len()) as u64 <= mem.max_entry_bytes());
+        match self.memory.as_mut() {
+            Some((_, buf)) if fits => buf.extend_from_slice(chunk),
+            _ => self.memory = None,
+        }
+    }
+
+    async fn publish(self) {
+        if let Some(disk) = self.disk {
+            if let Err(err) = disk.publish().await {
+                warn!(blob_id = %self.blob_id, %err, "Failed to write Walrus disk cache entry");
+            }
+        }
+        if let Some((mem, buf)) = self.memory {
+            mem.insert(&self.blob_id, Bytes::from(buf));
+        }
+    }
+}
+
+// Buffer a response body of at most `max_bytes`, rejecting a larger Content-Length before reading
+// and aborting mid-body when the aggregator sends more than it announced.
+async fn read_capped(resp: Response, max_bytes: u64) -> Result<Vec<u8>> {
+    if resp.content_length().is_some_and(|n| n > max_bytes) {
+        return Err(WalrusError::TooLarge { limit: max_bytes }.into());
+    }
+    let body = resp.bytes_stream().map(|chunk| chunk.context("Read Walrus body failed")).boxed();
+    let mut body = capped(body, max_bytes).boxed();
+    let mut out = Vec::new();
+    while let Some(chunk) = body.next().await {
+        out.extend_from_slice(&chunk?);
+    }
+    Ok(out)
+}
+
+// Enforce a running byte budget over a body stream, ending it after the first overflow error.
+pub(crate) fn capped(
+    body: stream::BoxStream<'static, Result<Bytes>>,
+    max_bytes: u64,
+) -> impl Stream<Item = Result<Bytes>> + Send + 'static {
+    body.scan((0u64, false), move |(seen, aborted), chunk| {
+        let item = if *aborted {
+            None
+        } else {
+            match chunk {
+                Ok(bytes) => {
+                    *seen += bytes.len() as u64;
+                    if *seen > max_bytes {
+                        warn!(limit = max_bytes, "Walrus blob exceeded size cap, aborting download");
+                        *aborted = true;
+                        Some(Err(WalrusError::TooLarge { limit: max_bytes }.into()))
+                    } else {
+                        Some(Ok(bytes))
+                    }
+                }
+                Err(e) => {
+                    *aborted = true;
+                    Some(Err(e))
+                }
+            }
+        };
+        futures_util::future::ready(item)
+    })
+}
+
+fn env_parse<T: std::str::FromStr>(key: &str, default: T) -> T {
+    env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default)
+}
+
+// Aggregator path for a blob reference. Besides plain blob IDs, patches inside a quilt (a bundle
+// of small blobs) can be addressed directly:
+//   quilt-patch:<quilt_patch_id>      -> /v1/blobs/by-quilt-patch-id/<quilt_patch_id>
+//   quilt:<quilt_id>/<identifier>     -> /v1/blobs/by-quilt-id/<quilt_id>/<identifier>
+// Every segment is percent-encoded so caller input cannot escape the intended route.
+fn blob_path(blob_id: &str) -> String {
+    if let Some(patch_id) = blob_id.strip_prefix("quilt-patch:") {
+        return format!("/v1/blobs/by-quilt-patch-id/{}", encode_segment(patch_id));
+    }
+    if let Some((quilt_id, identifier)) = blob_id.strip_prefix("quilt:").and_then(|r| r.split_once('/')) {
+        return format!("/v1/blobs/by-quilt-id/{}/{}", encode_segment(quilt_id), encode_segment(identifier));
+    }
+    // Walrus aggregator exposes blobs under /v1/blobs/{blob_id}
+    format!("/v1/blobs/{}", encode_segment(blob_id))
+}
+
+// The blob whose status governs `blob_id`: quilt patches live in their quilt's blob. Patch IDs
+// don't name their quilt, so those have none.
+fn status_blob_id(blob_id: &str) -> Option<&str> {
+    if blob_id.starts_with("quilt-patch:") {
+        return None;
+    }
+    match blob_id.strip_prefix("quilt:") {
+        Some(rest) => rest.split_once('/').map(|(quilt_id, _)| quilt_id),
+        None => Some(blob_id),
+    }
+}
+
+fn encode_segment(raw: &str) -> String {
+    let mut out = String::with_capacity(raw.len());
+    for b in raw.bytes() {
+        if b.is_ascii_alphanumeric() || matches!(b, b'-' | b'_' | b'.' | b'~') {
+            out.push(b as char);
+        } else {
+            out.push_str(&format!("%{:02X}", b));
+        }
+    }
+    out
+}
+
+pub(crate) fn slice_range(full: &[u8], offset: u64, len: u64) -> BlobRange {
+    let start = (offset as usize).min(full.len());
+    let end = (offset.saturating_add(len) as usize).min(full.len());
+    BlobRange { data: full[start..end].to_vec(), total_len: Some(full.len() as u64) }
+}
+
+// "bytes 0-4095/123456" -> 123456 ("*" means unknown).
+pub(crate) fn parse_content_range_total(value: &str) -> Option<u64> {
+    value.rsplit('/').next().and_then(|t| t.trim().parse().ok())
 }
 
 fn generate_mock_blob(blob_id: &str) -> Vec<u8> {
@@ -86,4 +797,177 @@ fn generate_mock_blob(blob_id: &str) -> Vec<u8> {
     out
 }
 
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use http_body_util::combinators::BoxBody;
+    use http_body_util::{BodyExt, Full, StreamBody};
+    use hyper::body::{Frame, Incoming};
+    // hyper's http types, not reqwest's.
+    use hyper::header::{CONTENT_RANGE, CONTENT_TYPE, ETAG, RANGE};
+    use hyper::server::conn::http1;
+    use hyper::service::service_fn;
+    use hyper::{Request, Response};
+    use hyper_util::rt::TokioIo;
+    use std::convert::Infallible;
+
+    type Body = BoxBody<Bytes, Infallible>;
+
+    // The 32-byte blob the local aggregator serves.
+    const BLOB: &[u8; 32] = b"0123456789abcdefghijklmnopqrstuv";
+
+    fn full(status: u16, data: &'static [u8]) -> Response<Body> {
+        let mut resp = Response::new(Full::new(Bytes::from_static(data)).boxed());
+        *resp.status_mut() = hyper::StatusCode::from_u16(status).unwrap();
+        resp
+    }
+
+    // Chunked, so no Content-Length announces the size up front.
+    fn chunked(status: u16, chunks: usize) -> Response<Body> {
+        let frames = (0..chunks).map(|_| Ok(Frame::data(Bytes::from_static(BLOB))));
+        let mut resp = Response::new(BodyExt::boxed(StreamBody::new(stream::iter(frames))));
+        *resp.status_mut() = hyper::StatusCode::from_u16(status).unwrap();
+        resp
+    }
+
+    fn aggregator_response(req: &Request<Incoming>) -> Response<Body> {
+        let range = req.headers().get(RANGE).and_then(|v| v.to_str().ok());
+        match req.uri().path() {
+            "/v1/blobs/blob" => match range.and_then(|r| r.strip_prefix("bytes=")?.split_once('-')) {
+                Some((start, end)) => {
+                    let (start, end) = (start.parse::<usize>().unwrap(), end.parse::<usize>().unwrap());
+                    let mut resp = full(206, &BLOB[start..=end.min(BLOB.len() - 1)]);
+                    let value = format!("bytes {}-{}/{}", start, end, BLOB.len());
+                    resp.headers_mut().insert(CONTENT_RANGE, value.parse().unwrap());
+                    resp
+                }
+                None => {
+                    let mut resp = full(200, BLOB);
+                    resp.headers_mut().insert(CONTENT_TYPE, "text/csv".parse().unwrap());
+                    resp.headers_mut().insert(ETAG, "\"blob-v1\"".parse().unwrap());
+                    resp
+                }
+            },
+            "/v1/blobs/ignores-range" => full(200, BLOB),
+            "/v1/blobs/overlong-range" => chunked(206, 2),
+            "/v1/blobs/huge" => full(200, &[0u8; 100]),
+            "/v1/blobs/huge-chunked" => chunked(200, 4),
+            _ => full(404, b""),
+        }
+    }
+
+    // A client whose only aggregator is a local server, with a 64-byte size cap, one attempt and
+    // no caches.
+    async fn local_client() -> WalrusClient {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move {
+            while let Ok((stream, _)) = listener.accept().await {
+                tokio::spawn(async move {
+                    let svc = service_fn(|req: Request<Incoming>| async move {
+                        Ok::<_, Infallible>(aggregator_response(&req))
+                    });
+                    let _ = http1::Builder::new().serve_connection(TokioIo::new(stream), svc).await;
+                });
+            }
+        });
+        let config = WalrusConfig {
+            aggregator_urls: vec![url],
+            max_blob_bytes: 64,
+            retry: RetryPolicy { max_attempts: 1, ..Default::default() },
+            proxy_url: None,
+            vsock_proxy: None,
+            download_rate_limit: 0,
+            status_node_url: None,
+            disk_cache_dir: None,
+            memory_cache_max_bytes: 0,
+            allow_mock: false,
+            ..WalrusConfig::from_env().unwrap()
+        };
+        WalrusClient::new(&config).unwrap()
+    }
+
+    fn too_large(err: &anyhow::Error) -> bool {
+        matches!(err.downcast_ref::<WalrusError>(), Some(WalrusError::TooLarge { limit: 64 }))
+    }
+
+    #[test]
+    fn test_blob_paths() {
+        assert_eq!(blob_path("AbC-_123"), "/v1/blobs/AbC-_123");
+        assert_eq!(blob_path("../admin"), "/v1/blobs/..%2Fadmin");
+        assert_eq!(blob_path("quilt-patch:PaTcH"), "/v1/blobs/by-quilt-patch-id/PaTcH");
+        assert_eq!(blob_path("quilt:QuIlT/train set.csv"), "/v1/blobs/by-quilt-id/QuIlT/train%20set.csv");
+        assert_eq!(blob_path("quilt:QuIlT/a/b"), "/v1/blobs/by-quilt-id/QuIlT/a%2Fb");
+    }
+
+    #[tokio::test]
+    async fn test_range_reads_are_bounded() {
+        let client = local_client().await;
+        let range = client.fetch_blob_range("blob", 4, 6).await.unwrap();
+        assert_eq!((range.data.as_slice(), range.total_len), (&b"456789"[..], Some(32)));
+        // An aggregator answering 200 is sliced locally.
+        let range = client.fetch_blob_range("ignores-range", 30, 10).await.unwrap();
+        assert_eq!((range.data.as_slice(), range.total_len), (&b"uv"[..], Some(32)));
 
+        // More bytes than the range asked for, or a full body over the cap, are not buffered.
+        let err = client.fetch_blob_range("overlong-range", 0, 8).await.unwrap_err();
+        assert!(err.to_string().contains("more than 8 bytes"), "{}", err);
+        assert!(too_large(&client.fetch_blob_range("huge", 0, 8).await.unwrap_err()));
+        assert!(too_large(&client.fetch_blob_range("huge-chunked", 0, 8).await.unwrap_err()));
+
+        let err = client.fetch_blob_range("blob", u64::MAX, 2).await.unwrap_err();
+        assert!(err.to_string().contains("overflows"), "{}", err);
+        assert!(client.fetch_blob_range("blob", 0, 0).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_stream_aborts_past_cap() {
+        let client = local_client().await;
+        assert_eq!(client.fetch_blob("blob", 64).await.unwrap(), BLOB);
+        // An announced size over the cap fails before the body is read.
+        assert!(too_large(&client.fetch_blob_stream("huge", 1024).await.err().unwrap()));
+        // Without one, the stream ends with an error once the cap is passed.
+        let mut body = client.fetch_blob_stream("huge-chunked", 1024).await.unwrap();
+        let mut received = 0;
+        let err = loop {
+            match body.next().await.unwrap() {
+                Ok(chunk) => received += chunk.len(),
+                Err(err) => break err,
+            }
+        };
+        assert!(too_large(&err) && received <= 64, "{} after {} bytes", err, received);
+        assert!(body.next().await.is_none());
+        let err = client.fetch_blob("missing", 64).await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<WalrusError>(), Some(WalrusError::NotFound)));
+    }
+
+    #[tokio::test]
+    async fn test_blob_metadata_and_existence() {
+        let client = local_client().await;
+        let meta = client.blob_metadata("blob").await.unwrap();
+        assert_eq!(meta.size, Some(32));
+        assert_eq!(meta.content_type.as_deref(), Some("text/csv"));
+        assert_eq!(meta.etag.as_deref(), Some("\"blob-v1\""));
+        let err = client.blob_metadata("missing").await.unwrap_err();
+        assert!(matches!(err.downcast_ref::<WalrusError>(), Some(WalrusError::NotFound)));
+
+        // blob_exists answers through the HEAD probe: present, missing, or a real failure.
+        let source: &dyn crate::blob_source::BlobSource = &client;
+        assert!(source.blob_exists("blob").await.unwrap());
+        assert!(!source.blob_exists("missing").await.unwrap());
+        let unreachable = WalrusClient::new(&WalrusConfig {
+            aggregator_urls: vec!["http://127.0.0.1:1".into()],
+            retry: RetryPolicy { max_attempts: 1, ..Default::default() },
+            proxy_url: None,
+            vsock_proxy: None,
+            status_node_url: None,
+            disk_cache_dir: None,
+            memory_cache_max_bytes: 0,
+            allow_mock: false,
+            ..WalrusConfig::from_env().unwrap()
+        })
+        .unwrap();
+        let source: &dyn crate::blob_source::BlobSource = &unreachable;
+        assert!(source.blob_exists("blob").await.is_err());
+    }
+}
@@ -0,0 +1,167 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::metrics;
+
+// Health-scored pool of Walrus aggregators.
+// Endpoints that return 5xx or time out are put in a cooldown and tried last; healthy
+// endpoints are picked round-robin or by observed latency (WALRUS_AGGREGATOR_SELECTION).
+
+// Consecutive failures before an endpoint is considered unhealthy.
+const UNHEALTHY_AFTER: u32 = 3;
+// Weight of the newest sample in the latency moving average.
+const EWMA_ALPHA: f64 = 0.3;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Selection {
+    RoundRobin,
+    LatencyAware,
+}
+
+#[derive(Debug, Default)]
+struct Health {
+    consecutive_failures: u32,
+    ewma_latency_ms: Option<f64>,
+    last_failure: Option<Instant>,
+}
+
+struct Endpoint {
+    url: String,
+    health: Mutex<Health>,
+}
+
+pub struct AggregatorPool {
+    endpoints: Vec<Endpoint>,
+    cursor: AtomicUsize,
+    selection: Selection,
+    cooldown: Duration,
+}
+
+impl AggregatorPool {
+    pub fn new(urls: Vec<String>, selection: Selection, cooldown: Duration) -> Self {
+        let endpoints = urls
+            .into_iter()
+            .map(|url| Endpoint { url: url.trim_end_matches('/').to_string(), health: Mutex::new(Health::default()) })
+            .collect();
+        Self { endpoints, cursor: AtomicUsize::new(0), selection, cooldown }
+    }
+
+    pub fn len(&self) -> usize {
+        self.endpoints.len()
+    }
+
+    pub fn url(&self, idx: usize) -> &str {
+        &self.endpoints[idx].url
+    }
+
+    // Order in which endpoints should be tried for the next request:
+    // healthy endpoints first (by selection strategy), endpoints in cooldown last.
+    pub fn candidates(&self) -> Vec<usize> {
+        let n = self.endpoints.len();
+        let start = self.cursor.fetch_add(1, Ordering::Relaxed) % n.max(1);
+        let mut order: Vec<usize> = (0..n).map(|i| (start + i) % n).collect();
+        if self.selection == Selection::LatencyAware {
+            order.sort_by(|&a, &b| self.latency(a).total_cmp(&self.latency(b)));
+        }
+        let (mut healthy, cooling): (Vec<usize>, Vec<usize>) = order.into_iter().partition(|&i| self.is_healthy(i));
+        healthy.extend(cooling);
+        healthy
+    }
+
+    pub fn record_success(&self, idx: usize, latency: Duration) {
+        let mut h = self.lock(idx);
+        h.consecutive_failures = 0;
+        h.last_failure = None;
+        let sample = latency.as_secs_f64() * 1000.0;
+        h.ewma_latency_ms = Some(match h.ewma_latency_ms {
+            Some(prev) => prev * (1.0 - EWMA_ALPHA) + sample * EWMA_ALPHA,
+            None => sample,
+        });
+        drop(h);
+        self.publish(idx, "success");
+    }
+
+    pub fn record_failure(&self, idx: usize) {
+        let mut h = self.lock(idx);
+        h.consecutive_failures += 1;
+        h.last_failure = Some(Instant::now());
+        drop(h);
+        self.publish(idx, "failure");
+    }
+
+    // 0..=1: 1 for a healthy endpoint, decaying with consecutive failures.
+    pub fn health_score(&self, idx: usize) -> f64 {
+        let h = self.lock(idx);
+        1.0 / (1.0 + h.consecutive_failures as f64)
+    }
+
+    fn is_healthy(&self, idx: usize) -> bool {
+        let h = self.lock(idx);
+        match h.last_failure {
+            Some(at) if h.consecutive_failures >= UNHEALTHY_AFTER => at.elapsed() >= self.cooldown,
+            _ => true,
+        }
+    }
+
+    fn latency(&self, idx: usize) -> f64 {
+        // Unmeasured endpoints sort first so they get probed.
+        self.lock(idx).ewma_latency_ms.unwrap_or(0.0)
+    }
+
+    fn publish(&self, idx: usize, outcome: &str) {
+        let url = self.url(idx);
+        metrics::inc_counter(
+            "nautilus_walrus_aggregator_requests_total",
+            "Walrus aggregator requests by endpoint and outcome",
+            &[("aggregator", url), ("outcome", outcome)],
+        );
+        metrics::set_gauge(
+            "nautilus_walrus_aggregator_health",
+            "Aggregator health score (1 = healthy)",
+            &[("aggregator", url)],
+            self.health_score(idx),
+        );
+    }
+
+    fn lock(&self, idx: usize) -> std::sync::MutexGuard<'_, Health> {
+        self.endpoints[idx].health.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pool(selection: Selection) -> AggregatorPool {
+        let urls = vec!["http://a".to_string(), "http://b".to_string(), "http://c".to_string()];
+        AggregatorPool::new(urls, selection, Duration::from_secs(60))
+    }
+
+    #[test]
+    fn test_round_robin_rotates() {
+        let p = pool(Selection::RoundRobin);
+        assert_eq!(p.candidates()[0], 0);
+        assert_eq!(p.candidates()[0], 1);
+        assert_eq!(p.candidates()[0], 2);
+    }
+
+    #[test]
+    fn test_failing_endpoint_moves_last() {
+        let p = pool(Selection::RoundRobin);
+        for _ in 0..UNHEALTHY_AFTER {
+            p.record_failure(0);
+        }
+        assert_eq!(*p.candidates().last().unwrap(), 0);
+        assert!(p.health_score(0) < p.health_score(1));
+    }
+
+    #[test]
+    fn test_latency_aware_prefers_fast() {
+        let p = pool(Selection::LatencyAware);
+        p.record_success(0, Duration::from_millis(500));
+        p.record_success(1, Duration::from_millis(20));
+        p.record_success(2, Duration::from_millis(100));
+        assert_eq!(p.candidates(), vec![1, 2, 0]);
+    }
+}
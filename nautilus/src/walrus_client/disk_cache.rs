@@ -0,0 +1,207 @@
+use anyhow::{Context, Result};
+use bytes::Bytes;
+use futures_util::stream::{self, BoxStream, StreamExt};
+use sha2::{Digest, Sha256};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
+use std::time::SystemTime;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::sync::Mutex;
+use tracing::warn;
+
+use crate::metrics;
+
+// Optional on-disk blob cache. Walrus blob IDs are content-derived, so an entry never goes
+// stale and can be kept until size-bounded eviction removes the least recently used files.
+// Enabled by WALRUS_DISK_CACHE_DIR; bounded by WALRUS_DISK_CACHE_MAX_BYTES.
+// Entries are written and read as streams, so caching never needs a blob in memory.
+
+const READ_CHUNK: usize = 64 * 1024;
+// Distinguishes temp files of concurrent writes within this process.
+static NEXT_TMP: AtomicU64 = AtomicU64::new(0);
+
+pub struct DiskCache {
+    dir: PathBuf,
+    max_bytes: u64,
+    // Serializes eviction so concurrent writers don't race over the same files.
+    evict_lock: Mutex<()>,
+}
+
+impl DiskCache {
+    pub fn new(dir: PathBuf, max_bytes: u64) -> Result<Self> {
+        std::fs::create_dir_all(&dir).with_context(|| format!("create cache dir {}", dir.display()))?;
+        Ok(Self { dir, max_bytes, evict_lock: Mutex::new(()) })
+    }
+
+    pub async fn get(&self, blob_id: &str) -> Option<Vec<u8>> {
+        let path = self.path_for(blob_id);
+        let data = tokio::fs::read(&path).await.ok();
+        record_lookup(&path, data.is_some());
+        data
+    }
+
+    // A cached blob as a stream of chunks, without loading it into memory.
+    pub async fn open(&self, blob_id: &str) -> Option<BoxStream<'static, Result<Bytes>>> {
+        let path = self.path_for(blob_id);
+        let file = tokio::fs::File::open(&path).await.ok();
+        record_lookup(&path, file.is_some());
+        Some(read_chunks(file?))
+    }
+
+    // Start writing `blob_id` as it streams in; None when it is already cached or the temp file
+    // can't be created. The entry only becomes visible once `PendingEntry::publish` runs.
+    pub async fn begin(self: &Arc<Self>, blob_id: &str) -> Option<PendingEntry> {
+        let path = self.path_for(blob_id);
+        if tokio::fs::try_exists(&path).await.unwrap_or(false) {
+            // Content-addressed: an existing entry already holds these bytes.
+            return None;
+        }
+        // Write to a temp file and rename so readers never observe a partial blob.
+        let seq = NEXT_TMP.fetch_add(1, Ordering::Relaxed);
+        let tmp = path.with_extension(format!("tmp-{}-{}", std::process::id(), seq));
+        let file = tokio::fs::File::create(&tmp).await.ok()?;
+        Some(PendingEntry { cache: self.clone(), tmp, path, file: Some(file), written: 0, published: false })
+    }
+
+    // Drop least recently used entries until the cache fits its budget.
+    async fn evict(&self) -> Result<()> {
+        let _guard = self.evict_lock.lock().await;
+        let mut entries = Vec::new();
+        let mut total = 0u64;
+        let mut dir = tokio::fs::read_dir(&self.dir).await.context("read cache dir")?;
+        while let Some(entry) = dir.next_entry().await? {
+            let meta = match entry.metadata().await {
+                Ok(m) if m.is_file() => m,
+                _ => continue,
+            };
+            total += meta.len();
+            let mtime = meta.modified().unwrap_or(SystemTime::UNIX_EPOCH);
+            entries.push((mtime, meta.len(), entry.path()));
+        }
+        metrics::set_gauge("nautilus_walrus_disk_cache_bytes", "Bytes stored in the disk cache", &[], total as f64);
+        if total <= self.max_bytes {
+            return Ok(());
+        }
+        entries.sort_by_key(|(mtime, _, _)| *mtime);
+        for (_, len, path) in entries {
+            if total <= self.max_bytes {
+                break;
+            }
+            if tokio::fs::remove_file(&path).await.is_ok() {
+                total -= len;
+                metrics::inc_counter("nautilus_walrus_disk_cache_evictions_total", "Disk cache entries evicted", &[]);
+            }
+        }
+        metrics::set_gauge("nautilus_walrus_disk_cache_bytes", "Bytes stored in the disk cache", &[], total as f64);
+        Ok(())
+    }
+
+    // Hash the blob ID so arbitrary caller input can never escape the cache directory.
+    fn path_for(&self, blob_id: &str) -> PathBuf {
+        self.dir.join(hex::encode(Sha256::digest(blob_id.as_bytes())))
+    }
+}
+
+// A cache entry being written. Dropped without `publish` (download failed, aborted or outgrew
+// the cache), its temp file is removed.
+pub struct PendingEntry {
+    cache: Arc<DiskCache>,
+    tmp: PathBuf,
+    path: PathBuf,
+    // None once the entry has been abandoned.
+    file: Option<tokio::fs::File>,
+    written: u64,
+    published: bool,
+}
+
+impl PendingEntry {
+    pub async fn write(&mut self, chunk: &[u8]) {
+        let Some(file) = self.file.as_mut() else {
+            return;
+        };
+        self.written += chunk.len() as u64;
+        if self.written > self.cache.max_bytes {
+            self.file = None;
+            return;
+        }
+        if let Err(err) = file.write_all(chunk).await {
+            warn!(%err, "Failed to write Walrus disk cache entry");
+            self.file = None;
+        }
+    }
+
+    pub async fn publish(mut self) -> Result<()> {
+        let Some(mut file) = self.file.take() else {
+            return Ok(());
+        };
+        file.flush().await.context("flush cache entry")?;
+        drop(file);
+        tokio::fs::rename(&self.tmp, &self.path).await.context("publish cache entry")?;
+        self.published = true;
+        self.cache.evict().await
+    }
+}
+
+impl Drop for PendingEntry {
+    fn drop(&mut self) {
+        if !self.published {
+            let _ = std::fs::remove_file(&self.tmp);
+        }
+    }
+}
+
+fn record_lookup(path: &Path, hit: bool) {
+    if hit {
+        // Bump mtime so eviction treats the entry as recently used.
+        if let Ok(f) = std::fs::File::options().append(true).open(path) {
+            let _ = f.set_modified(SystemTime::now());
+        }
+    }
+    let result = if hit { "hit" } else { "miss" };
+    metrics::inc_counter("nautilus_walrus_disk_cache_total", "Disk cache lookups by result", &[("result", result)]);
+}
+
+fn read_chunks(file: tokio::fs::File) -> BoxStream<'static, Result<Bytes>> {
+    stream::unfold(Some(file), |file| async move {
+        let mut file = file?;
+        let mut buf = vec![0u8; READ_CHUNK];
+        match file.read(&mut buf).await {
+            Ok(0) => None,
+            Ok(n) => {
+                buf.truncate(n);
+                Some((Ok(Bytes::from(buf)), Some(file)))
+            }
+            Err(err) => Some((Err(anyhow::Error::new(err).context("read cache entry")), None)),
+        }
+    })
+    .boxed()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_streamed_entries_publish_only_when_complete() {
+        let dir = std::env::temp_dir().join(format!("nautilus-disk-cache-{}", std::process::id()));
+        let cache = Arc::new(DiskCache::new(dir.clone(), 1 << 20).unwrap());
+
+        let mut abandoned = cache.begin("a").await.unwrap();
+        abandoned.write(b"partial").await;
+        drop(abandoned);
+        assert!(cache.get("a").await.is_none());
+
+        let mut entry = cache.begin("a").await.unwrap();
+        entry.write(&[7u8; 100_000]).await;
+        entry.write(b"tail").await;
+        entry.publish().await.unwrap();
+        assert!(cache.begin("a").await.is_none());
+        let chunks: Vec<Bytes> = cache.open("a").await.unwrap().map(|c| c.unwrap()).collect().await;
+        assert_eq!(chunks.len(), 2);
+        assert_eq!(chunks.concat().len(), 100_004);
+        // Only the published entry is left behind.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
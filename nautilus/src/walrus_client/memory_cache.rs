@@ -0,0 +1,113 @@
+use bytes::Bytes;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::metrics;
+
+// In-memory LRU for small, hot blobs (marketplace browsing bursts re-verify the same datasets).
+// Bounded by total bytes; blobs above the per-entry threshold are never admitted.
+
+struct Entry {
+    data: Bytes,
+    tick: u64,
+}
+
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<String, Entry>,
+    // tick -> key, oldest first
+    order: BTreeMap<u64, String>,
+    total_bytes: u64,
+    next_tick: u64,
+}
+
+pub struct MemoryCache {
+    inner: Mutex<Lru>,
+    max_bytes: u64,
+    max_entry_bytes: u64,
+}
+
+impl MemoryCache {
+    pub fn new(max_bytes: u64, max_entry_bytes: u64) -> Self {
+        Self { inner: Mutex::new(Lru::default()), max_bytes, max_entry_bytes }
+    }
+
+    // Largest blob that can be admitted.
+    pub fn max_entry_bytes(&self) -> u64 {
+        self.max_entry_bytes.min(self.max_bytes)
+    }
+
+    pub fn get(&self, blob_id: &str) -> Option<Bytes> {
+        let mut lru = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        let tick = lru.next_tick;
+        let hit = match lru.entries.get_mut(blob_id) {
+            Some(entry) => {
+                let old = std::mem::replace(&mut entry.tick, tick);
+                Some((old, entry.data.clone()))
+            }
+            None => None,
+        };
+        match hit {
+            Some((old, data)) => {
+                lru.next_tick += 1;
+                lru.order.remove(&old);
+                lru.order.insert(tick, blob_id.to_string());
+                metrics::inc_counter("nautilus_walrus_memory_cache_total", "Memory cache lookups by result", &[("result", "hit")]);
+                Some(data)
+            }
+            None => {
+                metrics::inc_counter("nautilus_walrus_memory_cache_total", "Memory cache lookups by result", &[("result", "miss")]);
+                None
+            }
+        }
+    }
+
+    pub fn insert(&self, blob_id: &str, data: Bytes) {
+        let size = data.len() as u64;
+        if size > self.max_entry_bytes || size > self.max_bytes {
+            return;
+        }
+        let mut lru = self.inner.lock().unwrap_or_else(|e| e.into_inner());
+        if let Some(old) = lru.entries.remove(blob_id) {
+            lru.order.remove(&old.tick);
+            lru.total_bytes -= old.data.len() as u64;
+        }
+        while lru.total_bytes + size > self.max_bytes {
+            let Some((_, key)) = lru.order.pop_first() else { break };
+            if let Some(evicted) = lru.entries.remove(&key) {
+                lru.total_bytes -= evicted.data.len() as u64;
+                metrics::inc_counter("nautilus_walrus_memory_cache_evictions_total", "Memory cache entries evicted", &[]);
+            }
+        }
+        let tick = lru.next_tick;
+        lru.next_tick += 1;
+        lru.order.insert(tick, blob_id.to_string());
+        lru.entries.insert(blob_id.to_string(), Entry { data, tick });
+        lru.total_bytes += size;
+        metrics::set_gauge("nautilus_walrus_memory_cache_bytes", "Bytes held in the memory cache", &[], lru.total_bytes as f64);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_evicts_least_recently_used() {
+        let cache = MemoryCache::new(10, 8);
+        cache.insert("a", Bytes::from_static(b"aaaa"));
+        cache.insert("b", Bytes::from_static(b"bbbb"));
+        assert!(cache.get("a").is_some());
+        cache.insert("c", Bytes::from_static(b"cccc"));
+        assert!(cache.get("b").is_none(), "b was least recently used");
+        assert!(cache.get("a").is_some());
+        assert!(cache.get("c").is_some());
+    }
+
+    #[test]
+    fn test_rejects_large_entries() {
+        let cache = MemoryCache::new(100, 8);
+        cache.insert("big", Bytes::from(vec![0u8; 9]));
+        assert!(cache.get("big").is_none());
+    }
+}
@@ -0,0 +1,180 @@
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use crate::metrics;
+
+// Cache of Sui object reads (dataset and registry objects), keyed by object ID and holding the
+// version each was read at. Entries are valid as of a checkpoint: once the chain has moved past
+// it, every cached version is rechecked in one batched read and objects whose version was bumped
+// (or that were deleted) are dropped. Lookups in between cost nothing, and a recheck costs one
+// checkpoint poll per interval rather than a full object read per request.
+
+struct Entry {
+    version: u64,
+    object: Arc<Value>,
+    tick: u64,
+}
+
+#[derive(Default)]
+struct State {
+    entries: HashMap<String, Entry>,
+    // tick -> object ID, least recently used first
+    order: BTreeMap<u64, String>,
+    next_tick: u64,
+    // Checkpoint the entries were last validated at, and when the chain was last polled.
+    checkpoint: u64,
+    polled: Option<Instant>,
+    hits: u64,
+    lookups: u64,
+}
+
+pub struct ObjectCache {
+    state: Mutex<State>,
+    capacity: usize,
+    poll_interval: Duration,
+}
+
+impl ObjectCache {
+    pub fn new(capacity: usize, poll_interval: Duration) -> Self {
+        Self { state: Mutex::new(State::default()), capacity, poll_interval }
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    // Whether the chain has to be polled before the entries can be trusted again.
+    pub fn poll_due(&self, now: Instant) -> bool {
+        self.lock().polled.is_none_or(|at| now.saturating_duration_since(at) >= self.poll_interval)
+    }
+
+    // Records a poll that saw `checkpoint`. Returns the cached (ID, version) pairs to recheck
+    // when the chain has moved on, None when nothing can have changed.
+    pub fn observe(&self, checkpoint: u64, now: Instant) -> Option<Vec<(String, u64)>> {
+        let mut state = self.lock();
+        state.polled = Some(now);
+        (checkpoint > state.checkpoint)
+            .then(|| state.entries.iter().map(|(id, e)| (id.clone(), e.version)).collect())
+    }
+
+    // Applies a recheck done at `checkpoint`: objects still at their cached version stay, bumped
+    // or deleted ones go. Entries added since `observe` were read after the poll and are kept.
+    pub fn revalidate(&self, checkpoint: u64, current: &HashMap<String, Option<u64>>) {
+        let mut state = self.lock();
+        let stale: Vec<String> = state
+            .entries
+            .iter()
+            .filter(|(id, e)| current.get(*id).is_some_and(|v| *v != Some(e.version)))
+            .map(|(id, _)| id.clone())
+            .collect();
+        for id in &stale {
+            if let Some(entry) = state.entries.remove(id) {
+                state.order.remove(&entry.tick);
+            }
+        }
+        state.checkpoint = state.checkpoint.max(checkpoint);
+        if !stale.is_empty() {
+            metrics::add_counter(
+                "nautilus_sui_object_cache_invalidations_total",
+                "Cached Sui objects dropped after a version bump",
+                &[],
+                stale.len() as f64,
+            );
+        }
+        metrics::set_gauge("nautilus_sui_checkpoint", "Latest Sui checkpoint seen", &[], state.checkpoint as f64);
+        metrics::set_gauge("nautilus_sui_object_cache_entries", "Sui objects cached", &[], state.entries.len() as f64);
+    }
+
+    pub fn get(&self, object_id: &str) -> Option<Arc<Value>> {
+        let mut state = self.lock();
+        let tick = state.next_tick;
+        let hit = state
+            .entries
+            .get_mut(object_id)
+            .map(|entry| (std::mem::replace(&mut entry.tick, tick), entry.object.clone()));
+        state.lookups += 1;
+        if let Some((old, _)) = &hit {
+            state.hits += 1;
+            state.next_tick += 1;
+            state.order.remove(old);
+            state.order.insert(tick, object_id.to_string());
+        }
+        let result = if hit.is_some() { "hit" } else { "miss" };
+        metrics::inc_counter("nautilus_sui_object_cache_total", "Sui object cache lookups by result", &[("result", result)]);
+        metrics::set_gauge(
+            "nautilus_sui_object_cache_hit_ratio",
+            "Share of Sui object lookups served from cache",
+            &[],
+            state.hits as f64 / state.lookups as f64,
+        );
+        hit.map(|(_, object)| object)
+    }
+
+    pub fn insert(&self, object_id: &str, version: u64, object: Arc<Value>) {
+        if self.capacity == 0 {
+            return;
+        }
+        let mut state = self.lock();
+        if let Some(old) = state.entries.remove(object_id) {
+            state.order.remove(&old.tick);
+        }
+        while state.entries.len() >= self.capacity {
+            let Some((_, id)) = state.order.pop_first() else { break };
+            state.entries.remove(&id);
+        }
+        let tick = state.next_tick;
+        state.next_tick += 1;
+        state.order.insert(tick, object_id.to_string());
+        state.entries.insert(object_id.to_string(), Entry { version, object, tick });
+        metrics::set_gauge("nautilus_sui_object_cache_entries", "Sui objects cached", &[], state.entries.len() as f64);
+    }
+
+    // Drops everything, e.g. when the chain can't be polled and freshness is unknown.
+    pub fn clear(&self) {
+        let mut state = self.lock();
+        state.entries.clear();
+        state.order.clear();
+        state.polled = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_invalidated_by_version_bump() {
+        let cache = ObjectCache::new(3, Duration::from_secs(2));
+        let t0 = Instant::now();
+        assert!(cache.poll_due(t0));
+        assert_eq!(cache.observe(100, t0), Some(vec![]));
+        cache.revalidate(100, &HashMap::new());
+        cache.insert("0xa", 7, Arc::new(json!({ "v": 7 })));
+        cache.insert("0xb", 3, Arc::new(json!({ "v": 3 })));
+        assert!(!cache.poll_due(t0 + Duration::from_secs(1)));
+        assert_eq!(cache.get("0xa").unwrap()["v"], 7);
+
+        // Same checkpoint: nothing to recheck. Later one: 0xa was bumped, 0xb wasn't.
+        let t1 = t0 + Duration::from_secs(3);
+        assert!(cache.poll_due(t1));
+        assert_eq!(cache.observe(100, t1), None);
+        let mut stale = cache.observe(105, t1).unwrap();
+        stale.sort();
+        assert_eq!(stale, vec![("0xa".to_string(), 7), ("0xb".to_string(), 3)]);
+        cache.insert("0xc", 1, Arc::new(json!({})));
+        cache.revalidate(105, &HashMap::from([("0xa".to_string(), Some(8)), ("0xb".to_string(), Some(3))]));
+        assert!(cache.get("0xa").is_none());
+        assert!(cache.get("0xb").is_some() && cache.get("0xc").is_some());
+
+        // At capacity the least recently used entry makes room.
+        cache.get("0xb");
+        cache.insert("0xd", 1, Arc::new(json!({})));
+        cache.insert("0xe", 1, Arc::new(json!({})));
+        assert!(cache.get("0xc").is_none() && cache.get("0xb").is_some());
+        cache.clear();
+        assert!(cache.get("0xb").is_none() && cache.poll_due(t1));
+    }
+}
@@ -0,0 +1,83 @@
+use anyhow::{bail, Result};
+
+// Named Walrus/Sui network profiles. One key (WALRUS_PROFILE) picks the aggregator, publisher,
+// Sui fullnode and Seal key server defaults for a network, so moving a deployment between testnet
+// and mainnet doesn't mean keeping several endpoint variables in step. Individual endpoint variables still override
+// the profile's defaults; "custom" has no defaults at all and needs them set explicitly. The
+// profile name is attested alongside the score: a testnet verification must not pass for a
+// mainnet one.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Profile {
+    Testnet,
+    Mainnet,
+    Custom,
+}
+
+// Public endpoints a profile starts from.
+pub struct Endpoints {
+    pub aggregators: &'static [&'static str],
+    // Mainnet has no public publisher; uploads there need one of the operator's own.
+    pub publisher: Option<&'static str>,
+    pub sui_rpc: &'static str,
+    // Seal key servers are permissioned per deployment, so neither public profile ships any.
+    pub seal_key_servers: &'static [&'static str],
+}
+
+const TESTNET: Endpoints = Endpoints {
+    aggregators: &["https://aggregator.walrus-testnet.walrus.space"],
+    publisher: Some("https://publisher.walrus-testnet.walrus.space"),
+    sui_rpc: "https://fullnode.testnet.sui.io:443",
+    seal_key_servers: &[],
+};
+
+const MAINNET: Endpoints = Endpoints {
+    aggregators: &["https://aggregator.walrus-mainnet.walrus.space"],
+    publisher: None,
+    sui_rpc: "https://fullnode.mainnet.sui.io:443",
+    seal_key_servers: &[],
+};
+
+impl Profile {
+    pub fn parse(name: &str) -> Result<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "testnet" => Ok(Self::Testnet),
+            "mainnet" => Ok(Self::Mainnet),
+            "custom" => Ok(Self::Custom),
+            other => bail!("Unknown WALRUS_PROFILE '{}' (expected testnet, mainnet or custom)", other),
+        }
+    }
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Self::Testnet => "testnet",
+            Self::Mainnet => "mainnet",
+            Self::Custom => "custom",
+        }
+    }
+
+    // None for "custom": every endpoint comes from its own variable.
+    pub fn endpoints(self) -> Option<&'static Endpoints> {
+        match self {
+            Self::Testnet => Some(&TESTNET),
+            Self::Mainnet => Some(&MAINNET),
+            Self::Custom => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_profiles() {
+        assert_eq!(Profile::parse(" Mainnet ").unwrap(), Profile::Mainnet);
+        assert!(Profile::parse("devnet").is_err());
+        let mainnet = Profile::Mainnet.endpoints().unwrap();
+        assert!(mainnet.sui_rpc.contains("mainnet") && mainnet.aggregators[0].contains("mainnet"));
+        assert!(mainnet.publisher.is_none());
+        assert!(Profile::Testnet.endpoints().unwrap().publisher.is_some());
+        assert!(Profile::Custom.endpoints().is_none());
+    }
+}
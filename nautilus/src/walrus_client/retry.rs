@@ -0,0 +1,123 @@
+use reqwest::StatusCode;
+use ring::rand::{SecureRandom, SystemRandom};
+use std::env;
+use std::time::Duration;
+
+use super::env_parse;
+
+// Retry/backoff policy for Walrus requests.
+// Transport errors and timeouts are always retried; HTTP statuses only when they match one of the
+// configured rules, so a 404 or 400 fails the fetch immediately instead of burning every attempt.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusRule {
+    // Every status in a hundreds class, e.g. `5xx` -> Class(5).
+    Class(u16),
+    Exact(u16),
+}
+
+impl StatusRule {
+    fn parse(raw: &str) -> Option<Self> {
+        let raw = raw.trim().to_ascii_lowercase();
+        if let Some(class) = raw.strip_suffix("xx") {
+            return class.parse().ok().filter(|c| (1..=5).contains(c)).map(StatusRule::Class);
+        }
+        raw.parse().ok().filter(|s| (100..=599).contains(s)).map(StatusRule::Exact)
+    }
+
+    fn matches(&self, status: StatusCode) -> bool {
+        match *self {
+            StatusRule::Class(class) => status.as_u16() / 100 == class,
+            StatusRule::Exact(code) => status.as_u16() == code,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    // Rounds over the aggregator pool, including the first.
+    pub max_attempts: u32,
+    pub base_backoff: Duration,
+    pub max_backoff: Duration,
+    // Fraction (0..=1) of each backoff that is randomized away so concurrent jobs don't retry in lockstep.
+    pub jitter: f64,
+    pub retryable: Vec<StatusRule>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_backoff: Duration::from_millis(250),
+            max_backoff: Duration::from_secs(5),
+            jitter: 0.2,
+            retryable: vec![StatusRule::Class(5), StatusRule::Exact(408), StatusRule::Exact(429)],
+        }
+    }
+}
+
+impl RetryPolicy {
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        let retryable = match env::var("WALRUS_RETRY_STATUSES") {
+            // Comma-separated codes or classes, e.g. "5xx,429"; empty means only transport errors retry.
+            Ok(raw) => raw.split(',').filter(|s| !s.trim().is_empty()).filter_map(StatusRule::parse).collect(),
+            Err(_) => defaults.retryable,
+        };
+        Self {
+            max_attempts: env_parse("WALRUS_RETRY_MAX_ATTEMPTS", defaults.max_attempts).max(1),
+            base_backoff: Duration::from_millis(env_parse("WALRUS_RETRY_BASE_MS", 250)),
+            max_backoff: Duration::from_millis(env_parse("WALRUS_RETRY_MAX_BACKOFF_MS", 5000)),
+            jitter: env_parse("WALRUS_RETRY_JITTER", defaults.jitter).clamp(0.0, 1.0),
+            retryable,
+        }
+    }
+
+    pub fn is_retryable(&self, status: StatusCode) -> bool {
+        self.retryable.iter().any(|rule| rule.matches(status))
+    }
+
+    // Delay before round `attempt + 1`: base * 2^(attempt-1), capped, minus up to `jitter` of it.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        let exp = self.base_backoff.saturating_mul(1u32 << attempt.saturating_sub(1).min(16));
+        let capped = exp.min(self.max_backoff);
+        if self.jitter <= 0.0 {
+            return capped;
+        }
+        let mut buf = [0u8; 4];
+        let unit = match SystemRandom::new().fill(&mut buf) {
+            Ok(()) => u32::from_le_bytes(buf) as f64 / u32::MAX as f64,
+            Err(_) => 0.0,
+        };
+        capped.mul_f64(1.0 - self.jitter * unit)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_rules() {
+        let policy = RetryPolicy::default();
+        assert!(policy.is_retryable(StatusCode::BAD_GATEWAY));
+        assert!(policy.is_retryable(StatusCode::TOO_MANY_REQUESTS));
+        assert!(!policy.is_retryable(StatusCode::NOT_FOUND));
+        assert!(!policy.is_retryable(StatusCode::BAD_REQUEST));
+        assert_eq!(StatusRule::parse("5XX"), Some(StatusRule::Class(5)));
+        assert_eq!(StatusRule::parse("429"), Some(StatusRule::Exact(429)));
+        assert_eq!(StatusRule::parse("9xx"), None);
+    }
+
+    #[test]
+    fn test_backoff_is_capped_and_jittered() {
+        let policy = RetryPolicy { jitter: 0.0, ..RetryPolicy::default() };
+        assert_eq!(policy.backoff(1), Duration::from_millis(250));
+        assert_eq!(policy.backoff(3), Duration::from_millis(1000));
+        assert_eq!(policy.backoff(30), Duration::from_secs(5));
+
+        let jittered = RetryPolicy { jitter: 0.5, ..RetryPolicy::default() };
+        let d = jittered.backoff(2);
+        assert!(d >= Duration::from_millis(250) && d <= Duration::from_millis(500));
+    }
+}
@@ -0,0 +1,123 @@
+use anyhow::{Context, Result};
+use reqwest::Client;
+use serde_json::Value;
+use std::time::Duration;
+use tracing::info;
+
+use super::{encode_segment, WalrusError};
+
+// Blob lifecycle lookups against a Walrus storage node. Aggregators answer 404 alike for a blob
+// that never existed, one whose storage epochs ran out and one that was registered but never
+// certified; the node's status API tells these apart, so a missing blob can be reported as
+// "expired at epoch N" and the provider prompted to re-store it.
+
+pub struct StatusClient {
+    http: Client,
+    node_url: String,
+    timeout: Duration,
+}
+
+impl StatusClient {
+    pub fn new(http: Client, node_url: String, timeout: Duration) -> Self {
+        Self { http, node_url, timeout }
+    }
+
+    // Why `blob_id` is unavailable, if its status explains it; None leaves the plain "not found".
+    pub async fn explain_missing(&self, blob_id: &str) -> Result<Option<WalrusError>> {
+        let status = self.get(&format!("/v1/blobs/{}/status", encode_segment(blob_id))).await?;
+        let health = self.get("/v1/health").await?;
+        let current_epoch = health.get("epoch").and_then(Value::as_u64).map(|e| e as u32);
+        let explained = classify(&status, current_epoch);
+        if let Some(err) = &explained {
+            info!(%blob_id, %err, "Walrus blob status explains missing blob");
+        }
+        Ok(explained)
+    }
+
+    // The Sui event (transaction digest, sequence number) behind the blob's current status, for
+    // blobs whose status names one; only deletable blobs don't.
+    pub async fn status_event(&self, blob_id: &str) -> Result<Option<(String, u64)>> {
+        let status = self.get(&format!("/v1/blobs/{}/status", encode_segment(blob_id))).await?;
+        if status.as_str() == Some("nonexistent") {
+            return Err(WalrusError::NotFound.into());
+        }
+        Ok(status_event(&status))
+    }
+
+    // Storage node responses wrap the payload as {"success": {"code": .., "data": ..}}.
+    async fn get(&self, path: &str) -> Result<Value> {
+        let url = format!("{}{}", self.node_url, path);
+        let resp: Value = self
+            .http
+            .get(&url)
+            .timeout(self.timeout)
+            .send()
+            .await
+            .with_context(|| format!("Walrus storage node {} unreachable", self.node_url))?
+            .error_for_status()
+            .with_context(|| format!("Walrus status request {} failed", url))?
+            .json()
+            .await
+            .context("Invalid Walrus status response")?;
+        Ok(resp.pointer("/success/data").cloned().unwrap_or(resp))
+    }
+}
+
+// Blob status is an externally tagged enum: "nonexistent", or {"permanent": {..}},
+// {"deletable": {..}} and {"invalid": {..}}. Field names are camelCase.
+fn classify(status: &Value, current_epoch: Option<u32>) -> Option<WalrusError> {
+    let (kind, info) = status.as_object()?.iter().next()?;
+    if kind == "invalid" {
+        return None;
+    }
+    let epoch = |key: &str| info.get(key).and_then(Value::as_u64).map(|e| e as u32);
+    let end_epoch = epoch("endEpoch");
+    let certified = info.get("isCertified").and_then(Value::as_bool).unwrap_or(epoch("initialCertifiedEpoch").is_some());
+    if let (Some(end_epoch), Some(current_epoch)) = (end_epoch, current_epoch) {
+        // Storage covers epochs up to, but not including, the end epoch.
+        if current_epoch >= end_epoch {
+            return Some(WalrusError::Expired { end_epoch, current_epoch });
+        }
+    }
+    (!certified).then_some(WalrusError::NotCertified { end_epoch })
+}
+
+fn status_event(status: &Value) -> Option<(String, u64)> {
+    let (_, info) = status.as_object()?.iter().next()?;
+    let event = info.get("statusEvent")?;
+    let tx_digest = event.get("txDigest").and_then(Value::as_str)?;
+    // Sui renders u64s as decimal strings.
+    let seq = event.get("eventSeq")?;
+    let seq = seq.as_u64().or_else(|| seq.as_str().and_then(|s| s.parse().ok()))?;
+    Some((tx_digest.to_string(), seq))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_classify_blob_status() {
+        let permanent = |end: u64, certified: bool| json!({ "permanent": { "endEpoch": end, "isCertified": certified } });
+        assert!(matches!(
+            classify(&permanent(40, true), Some(41)),
+            Some(WalrusError::Expired { end_epoch: 40, current_epoch: 41 })
+        ));
+        assert!(matches!(classify(&permanent(40, false), Some(12)), Some(WalrusError::NotCertified { end_epoch: Some(40) })));
+        // Live and certified: the aggregator's 404 stays a plain not-found.
+        assert!(classify(&permanent(40, true), Some(12)).is_none());
+        assert!(classify(&json!("nonexistent"), Some(12)).is_none());
+        assert!(matches!(
+            classify(&json!({ "deletable": { "initialCertifiedEpoch": null } }), None),
+            Some(WalrusError::NotCertified { end_epoch: None })
+        ));
+
+        let event = json!({ "txDigest": "9Ujp", "eventSeq": "2" });
+        assert_eq!(
+            status_event(&json!({ "permanent": { "endEpoch": 40, "statusEvent": event } })),
+            Some(("9Ujp".to_string(), 2))
+        );
+        assert_eq!(status_event(&json!({ "deletable": { "initialCertifiedEpoch": 3 } })), None);
+    }
+}
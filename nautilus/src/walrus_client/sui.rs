@@ -0,0 +1,407 @@
+use anyhow::{anyhow, bail, Context, Result};
+use base64::engine::general_purpose::{STANDARD, URL_SAFE_NO_PAD};
+use base64::Engine;
+use reqwest::Client;
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use tracing::{info, warn};
+
+use super::object_cache::ObjectCache;
+use super::{RegisteredBlob, WalrusError};
+
+// Sui JSON-RPC client used to resolve a registered dataset object to its Walrus blob.
+// Verifying by object ID means the bytes scored are the ones the marketplace listing points at,
+// rather than whatever blob ID the caller claims belongs to it. Object reads go through an
+// optional checkpoint-validated cache (see object_cache). The only writes are verification
+// results submitted as Move calls (see submission); the node builds the transaction and the
+// caller signs it.
+
+// Most object IDs `sui_multiGetObjects` accepts per call.
+const MULTI_GET_LIMIT: usize = 50;
+// The shared `0x2::random::Random` object; its state lives in a versioned dynamic field.
+const RANDOM_OBJECT_ID: &str = "0x8";
+
+// The chain's current randomness, as stored on the Random object for one beacon round.
+pub struct SuiRandomness {
+    pub round: u64,
+    pub epoch: u64,
+    pub bytes: Vec<u8>,
+    // The dynamic field object holding it, and its version, so the read can be looked up again.
+    pub object_id: String,
+    pub object_version: u64,
+}
+
+pub struct SuiReader {
+    http: Client,
+    rpc_url: String,
+    timeout: Duration,
+    // Expected Move type of the dataset object (e.g. `0x..::marketplace::Dataset`); any type when unset.
+    dataset_type: Option<String>,
+    // Dotted path to the blob ID inside the object's fields, e.g. `blob_id` or `blob.blob_id`.
+    blob_field: String,
+    cache: Option<ObjectCache>,
+    // Held while the chain is polled, so concurrent lookups don't all poll it.
+    refreshing: Mutex<()>,
+}
+
+// A Move entry function call, built into an unsigned transaction by the node (`unsafe_moveCall`).
+#[derive(Debug, Clone)]
+pub struct MoveCall {
+    pub sender: String,
+    pub package: String,
+    pub module: String,
+    pub function: String,
+    // JSON-encoded pure arguments, in the order the function declares them.
+    pub arguments: Vec<Value>,
+    pub gas_budget: u64,
+}
+
+impl SuiReader {
+    pub fn new(
+        http: Client,
+        rpc_url: String,
+        timeout: Duration,
+        dataset_type: Option<String>,
+        blob_field: String,
+        cache: Option<ObjectCache>,
+    ) -> Self {
+        Self { http, rpc_url, timeout, dataset_type, blob_field, cache, refreshing: Mutex::new(()) }
+    }
+
+    pub async fn resolve_blob_id(&self, object_id: &str) -> Result<String> {
+        let result = self.object(object_id).await?;
+        let blob_id = extract_blob_id(&result, self.dataset_type.as_deref(), &self.blob_field)
+            .with_context(|| format!("Sui object {}", object_id))?;
+        info!(%object_id, %blob_id, "Resolved Sui dataset object to Walrus blob");
+        Ok(blob_id)
+    }
+
+    // Whether the object exists on chain (deleted or never-created objects don't).
+    pub async fn object_exists(&self, object_id: &str) -> Result<bool> {
+        let result = self.object(object_id).await?;
+        Ok(result.get("data").is_some_and(|data| !data.is_null()))
+    }
+
+    // The Walrus `Blob` object an event of the blob's (registered, certified...) names, checked to
+    // be a certified blob with this ID. The storage node is trusted to name the right event, not
+    // the aggregator serving the bytes.
+    pub async fn registered_blob(&self, blob_id: &str, tx_digest: &str, event_seq: u64) -> Result<RegisteredBlob> {
+        let events = self.rpc("sui_getEvents", json!([tx_digest])).await?;
+        let object_id = event_object_id(&events, event_seq)
+            .ok_or_else(|| anyhow!("Sui transaction {} has no Walrus blob event {}", tx_digest, event_seq))?;
+        let result = self.object(&object_id).await?;
+        parse_registered_blob(&result, blob_id).with_context(|| format!("Walrus blob object {}", object_id))
+    }
+
+    // Latest round of on-chain randomness. Never cached: it changes with every round.
+    pub async fn randomness(&self) -> Result<SuiRandomness> {
+        let random = self.rpc("sui_getObject", json!([RANDOM_OBJECT_ID, { "showContent": true }])).await?;
+        let inner = random
+            .pointer("/data/content/fields/inner/fields")
+            .ok_or_else(|| anyhow!("Sui Random object has no inner state"))?;
+        let parent = inner.pointer("/id/id").and_then(Value::as_str).ok_or_else(|| anyhow!("Random state has no ID"))?;
+        let version = inner.get("version").and_then(as_u64).ok_or_else(|| anyhow!("Random state has no version"))?;
+        let field = self
+            .rpc("suix_getDynamicFieldObject", json!([parent, { "type": "u64", "value": version.to_string() }]))
+            .await?;
+        parse_randomness(&field).context("Sui randomness state")
+    }
+
+    // BCS transaction bytes for `call`, with a gas coin of the sender's picked by the node.
+    pub async fn build_move_call(&self, call: &MoveCall) -> Result<Vec<u8>> {
+        let params = json!([
+            call.sender,
+            call.package,
+            call.module,
+            call.function,
+            [],
+            call.arguments,
+            null,
+            call.gas_budget.to_string(),
+        ]);
+        let result = self.rpc("unsafe_moveCall", params).await?;
+        let tx_bytes = result.get("txBytes").and_then(Value::as_str).ok_or_else(|| anyhow!("Move call has no txBytes"))?;
+        STANDARD.decode(tx_bytes).context("Move call txBytes are not base64")
+    }
+
+    // Executes a signed transaction and returns its digest. A transaction that was included but
+    // aborted (out of gas, a Move abort, a version conflict) fails with the node's reason.
+    pub async fn execute(&self, tx_bytes: &[u8], signature: &str) -> Result<String> {
+        let params = json!([STANDARD.encode(tx_bytes), [signature], { "showEffects": true }, "WaitForLocalExecution"]);
+        let result = self.rpc("sui_executeTransactionBlock", params).await?;
+        let digest = result.get("digest").and_then(Value::as_str).ok_or_else(|| anyhow!("executed transaction has no digest"))?;
+        match result.pointer("/effects/status/status").and_then(Value::as_str) {
+            Some("success") => Ok(digest.to_string()),
+            status => {
+                let reason = result.pointer("/effects/status/error").and_then(Value::as_str).or(status).unwrap_or("no effects");
+                bail!("transaction {} failed: {}", digest, reason)
+            }
+        }
+    }
+
+    // `sui_getObject` result with type and content, from the cache when it is still current.
+    async fn object(&self, object_id: &str) -> Result<Arc<Value>> {
+        if !is_object_id(object_id) {
+            bail!("Invalid Sui object ID '{}'", object_id);
+        }
+        if let Some(cache) = &self.cache {
+            if let Err(err) = self.refresh(cache).await {
+                warn!(%err, "Sui checkpoint poll failed; dropping cached objects");
+                cache.clear();
+            }
+            if let Some(hit) = cache.get(object_id) {
+                return Ok(hit);
+            }
+        }
+        let result = Arc::new(self.rpc("sui_getObject", json!([object_id, { "showType": true, "showContent": true }])).await?);
+        if let (Some(cache), Some(version)) = (&self.cache, object_version(&result)) {
+            cache.insert(object_id, version, result.clone());
+        }
+        Ok(result)
+    }
+
+    // Polls the latest checkpoint when due and, if the chain moved, rechecks cached versions.
+    async fn refresh(&self, cache: &ObjectCache) -> Result<()> {
+        if !cache.poll_due(Instant::now()) {
+            return Ok(());
+        }
+        let _guard = self.refreshing.lock().await;
+        if !cache.poll_due(Instant::now()) {
+            return Ok(());
+        }
+        let latest = self.rpc("sui_getLatestCheckpointSequenceNumber", json!([])).await?;
+        let checkpoint = as_u64(&latest).ok_or_else(|| anyhow!("Invalid checkpoint sequence number: {}", latest))?;
+        let Some(cached) = cache.observe(checkpoint, Instant::now()) else {
+            return Ok(());
+        };
+        let mut current = HashMap::new();
+        for batch in cached.chunks(MULTI_GET_LIMIT) {
+            let ids: Vec<&str> = batch.iter().map(|(id, _)| id.as_str()).collect();
+            let results = self.rpc("sui_multiGetObjects", json!([ids, {}])).await?;
+            let results = results.as_array().ok_or_else(|| anyhow!("Sui multi-get returned no list"))?;
+            for (id, result) in ids.iter().zip(results) {
+                current.insert(id.to_string(), object_version(result));
+            }
+        }
+        cache.revalidate(checkpoint, &current);
+        Ok(())
+    }
+
+    async fn rpc(&self, method: &str, params: Value) -> Result<Value> {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params });
+        let mut resp: Value = self
+            .http
+            .post(&self.rpc_url)
+            .timeout(self.timeout)
+            .json(&body)
+            .send()
+            .await
+            .with_context(|| format!("Sui RPC {} unreachable", self.rpc_url))?
+            .error_for_status()
+            .context("Sui RPC request failed")?
+            .json()
+            .await
+            .context("Invalid Sui RPC response")?;
+        if let Some(err) = resp.get("error") {
+            bail!("Sui RPC error: {}", err);
+        }
+        resp.get_mut("result").map(Value::take).ok_or_else(|| anyhow!("Sui RPC response has no result"))
+    }
+}
+
+// `RandomInner` out of the dynamic field object that stores it.
+fn parse_randomness(field: &Value) -> Result<SuiRandomness> {
+    let data = field.get("data").ok_or_else(|| anyhow!("no object data"))?;
+    let state = data.pointer("/content/fields/value/fields").ok_or_else(|| anyhow!("no RandomInner fields"))?;
+    let number = |name: &str| state.get(name).and_then(as_u64).ok_or_else(|| anyhow!("no {}", name));
+    let bytes: Option<Vec<u8>> = state
+        .get("random_bytes")
+        .and_then(Value::as_array)
+        .map(|items| items.iter().map(|v| v.as_u64().and_then(|b| u8::try_from(b).ok())).collect())
+        .unwrap_or(None);
+    let bytes = bytes.filter(|b| !b.is_empty()).ok_or_else(|| anyhow!("no random bytes"))?;
+    Ok(SuiRandomness {
+        round: number("randomness_round")?,
+        epoch: number("epoch")?,
+        bytes,
+        object_id: data.get("objectId").and_then(Value::as_str).unwrap_or_default().to_string(),
+        object_version: data.get("version").and_then(as_u64).unwrap_or_default(),
+    })
+}
+
+// Version of the object in a `sui_getObject` result; None for missing or deleted objects.
+fn object_version(result: &Value) -> Option<u64> {
+    result.pointer("/data/version").and_then(as_u64)
+}
+
+fn event_object_id(events: &Value, event_seq: u64) -> Option<String> {
+    let event = events.as_array()?.iter().find(|e| e.pointer("/id/eventSeq").and_then(as_u64) == Some(event_seq))?;
+    event.pointer("/parsedJson/object_id").and_then(Value::as_str).map(str::to_string)
+}
+
+fn parse_registered_blob(result: &Value, blob_id: &str) -> Result<RegisteredBlob> {
+    let data = result.get("data").filter(|d| !d.is_null()).ok_or_else(|| anyhow!("not found on chain"))?;
+    let object_type = data.get("type").and_then(Value::as_str).unwrap_or_default();
+    if !object_type.ends_with("::blob::Blob") {
+        bail!("has type {}, not a Walrus Blob", object_type);
+    }
+    let fields = data.pointer("/content/fields").ok_or_else(|| anyhow!("is not a Move object"))?;
+    let registered_id = fields.get("blob_id").and_then(blob_id_from_field);
+    if registered_id.as_deref() != Some(blob_id) {
+        bail!("is blob {}, not {}", registered_id.unwrap_or_default(), blob_id);
+    }
+    let size = fields.get("size").and_then(as_u64).ok_or_else(|| anyhow!("has no size"))?;
+    let certified_epoch = fields.get("certified_epoch").and_then(as_u64).and_then(|e| u32::try_from(e).ok());
+    let certified_epoch = certified_epoch.ok_or(WalrusError::NotCertified { end_epoch: None })?;
+    let object_id = data.get("objectId").and_then(Value::as_str).unwrap_or_default().to_string();
+    Ok(RegisteredBlob { object_id, size, certified_epoch })
+}
+
+// Sui renders u64s as decimal strings.
+fn as_u64(value: &Value) -> Option<u64> {
+    value.as_u64().or_else(|| value.as_str().and_then(|s| s.parse().ok()))
+}
+
+// Pull the blob ID out of a `sui_getObject` result, checking the object type when one is configured.
+fn extract_blob_id(result: &Value, dataset_type: Option<&str>, blob_field: &str) -> Result<String> {
+    if let Some(err) = result.get("error") {
+        bail!("not found on chain: {}", err);
+    }
+    let data = result.get("data").ok_or_else(|| anyhow!("no object data"))?;
+    let object_type = data.get("type").and_then(Value::as_str).unwrap_or_default();
+    if let Some(expected) = dataset_type {
+        if !types_match(object_type, expected) {
+            bail!("has type {} but {} is required", object_type, expected);
+        }
+    }
+    let mut value = data.pointer("/content/fields").ok_or_else(|| anyhow!("is not a Move object"))?;
+    for segment in blob_field.split('.') {
+        // Nested structs render as {"type": ..., "fields": {...}}.
+        let fields = value.get("fields").unwrap_or(value);
+        value = fields.get(segment).ok_or_else(|| anyhow!("has no field '{}'", blob_field))?;
+    }
+    blob_id_from_field(value).ok_or_else(|| anyhow!("field '{}' is not a blob ID: {}", blob_field, value))
+}
+
+// A blob ID field is either Walrus' own `u256` (rendered as a decimal string), a string that
+// already holds the base64url blob ID, or a `vector<u8>` of that string's bytes.
+fn blob_id_from_field(value: &Value) -> Option<String> {
+    match value {
+        Value::String(s) if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) => {
+            u256_le_bytes(s).map(|bytes| URL_SAFE_NO_PAD.encode(bytes))
+        }
+        Value::String(s) if !s.is_empty() => Some(s.clone()),
+        Value::Array(items) => {
+            let bytes: Option<Vec<u8>> = items.iter().map(|v| v.as_u64().and_then(|b| u8::try_from(b).ok())).collect();
+            String::from_utf8(bytes?).ok().filter(|s| !s.is_empty())
+        }
+        _ => None,
+    }
+}
+
+// Decimal u256 -> 32 little-endian bytes, the layout Walrus encodes blob IDs from.
+fn u256_le_bytes(decimal: &str) -> Option<[u8; 32]> {
+    let mut out = [0u8; 32];
+    for digit in decimal.bytes() {
+        let mut carry = (digit - b'0') as u32;
+        for byte in out.iter_mut() {
+            let v = *byte as u32 * 10 + carry;
+            *byte = v as u8;
+            carry = v >> 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+    Some(out)
+}
+
+// Package addresses may be rendered with or without leading zeros.
+fn types_match(actual: &str, expected: &str) -> bool {
+    let normalize = |t: &str| {
+        let t = t.trim().to_ascii_lowercase();
+        match t.strip_prefix("0x").and_then(|rest| rest.split_once("::")) {
+            Some((addr, rest)) => format!("0x{}::{}", addr.trim_start_matches('0'), rest),
+            None => t,
+        }
+    };
+    normalize(actual) == normalize(expected)
+}
+
+fn is_object_id(raw: &str) -> bool {
+    raw.strip_prefix("0x")
+        .is_some_and(|hex| !hex.is_empty() && hex.len() <= 64 && hex.bytes().all(|b| b.is_ascii_hexdigit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_blob_id() {
+        let result = json!({ "data": {
+            "objectId": "0x5",
+            "type": "0x00ab::marketplace::Dataset",
+            "content": { "dataType": "moveObject", "fields": {
+                "name": "weather",
+                "blob_id": "M4hsZGQ1oCktdzegB6HnI1fRUNGEnTeZtm8oD9Bq4Do",
+                "blob": { "type": "0x2::blob::Blob", "fields": { "blob_id": "1" } },
+            } },
+        } });
+        assert_eq!(
+            extract_blob_id(&result, Some("0xab::marketplace::Dataset"), "blob_id").unwrap(),
+            "M4hsZGQ1oCktdzegB6HnI1fRUNGEnTeZtm8oD9Bq4Do"
+        );
+        // u256 1 -> 0x01 followed by 31 zero bytes.
+        assert_eq!(
+            extract_blob_id(&result, None, "blob.blob_id").unwrap(),
+            URL_SAFE_NO_PAD.encode([&[1u8][..], &[0u8; 31]].concat())
+        );
+        assert!(extract_blob_id(&result, Some("0xab::marketplace::Other"), "blob_id").is_err());
+        assert!(extract_blob_id(&result, None, "missing").is_err());
+        assert!(extract_blob_id(&json!({ "error": { "code": "notExists" } }), None, "blob_id").is_err());
+        assert!(is_object_id("0x5") && !is_object_id("5") && !is_object_id("0xzz"));
+        assert_eq!(object_version(&json!({ "data": { "version": "42" } })), Some(42));
+        assert_eq!(object_version(&json!({ "error": { "code": "deleted" } })), None);
+
+        let field = json!({ "data": {
+            "objectId": "0x9f", "version": "811",
+            "content": { "fields": { "name": "1", "value": { "fields": {
+                "version": "1", "epoch": "512", "randomness_round": "90210", "random_bytes": [7, 255, 0],
+            } } } },
+        } });
+        let r = parse_randomness(&field).unwrap();
+        assert_eq!((r.round, r.epoch, r.bytes, r.object_version), (90210, 512, vec![7, 255, 0], 811));
+        assert!(parse_randomness(&json!({ "data": { "content": { "fields": {} } } })).is_err());
+    }
+
+    #[test]
+    fn test_parse_registered_blob() {
+        let events = json!([
+            { "id": { "txDigest": "9Ujp", "eventSeq": "0" }, "parsedJson": { "object_id": "0x1" } },
+            { "id": { "txDigest": "9Ujp", "eventSeq": "1" }, "parsedJson": { "object_id": "0xb10b" } },
+        ]);
+        assert_eq!(event_object_id(&events, 1).as_deref(), Some("0xb10b"));
+        assert_eq!(event_object_id(&events, 2), None);
+
+        let blob_id = URL_SAFE_NO_PAD.encode([&[1u8][..], &[0u8; 31]].concat());
+        let object = |object_type: &str, certified_epoch: Value| {
+            json!({ "data": {
+                "objectId": "0xb10b",
+                "type": object_type,
+                "content": { "fields": { "blob_id": "1", "size": "4096", "certified_epoch": certified_epoch } },
+            } })
+        };
+        let registered = parse_registered_blob(&object("0xfd::blob::Blob", json!(7)), &blob_id).unwrap();
+        assert_eq!((registered.object_id.as_str(), registered.size, registered.certified_epoch), ("0xb10b", 4096, 7));
+        // Another blob's object, an uncertified blob or a lookalike type prove nothing.
+        let other = URL_SAFE_NO_PAD.encode([2u8; 32]);
+        assert!(parse_registered_blob(&object("0xfd::blob::Blob", json!(7)), &other).is_err());
+        let err = parse_registered_blob(&object("0xfd::blob::Blob", Value::Null), &blob_id).unwrap_err();
+        assert!(matches!(err.downcast_ref::<WalrusError>(), Some(WalrusError::NotCertified { .. })));
+        assert!(parse_registered_blob(&object("0xfd::dataset::Dataset", json!(7)), &blob_id).is_err());
+    }
+}
@@ -0,0 +1,102 @@
+use anyhow::{Context, Result};
+use std::net::SocketAddr;
+use tokio::net::TcpListener;
+use tracing::{info, warn};
+
+// Enclave egress. A Nitro Enclave has no network interface, only vsock to the parent instance,
+// so reqwest cannot reach aggregators directly. This forwarder listens on loopback inside the
+// enclave and pipes every connection to a vsock port on the parent, where an HTTP CONNECT proxy
+// (e.g. vsock-proxy in front of a forward proxy) is expected. The client then uses the loopback
+// address as its proxy, so TLS to each aggregator stays end-to-end.
+
+// Start the loopback -> vsock forwarder and return the address to use as an HTTP proxy.
+pub fn spawn_forwarder(cid: u32, port: u32) -> Result<SocketAddr> {
+    let std_listener = std::net::TcpListener::bind("127.0.0.1:0").context("bind vsock forwarder")?;
+    std_listener.set_nonblocking(true)?;
+    let listener = TcpListener::from_std(std_listener).context("register vsock forwarder")?;
+    let addr = listener.local_addr()?;
+    info!(%addr, cid, port, "Walrus egress via vsock forwarder");
+    tokio::spawn(async move {
+        loop {
+            let (mut tcp, _) = match listener.accept().await {
+                Ok(conn) => conn,
+                Err(err) => {
+                    warn!(%err, "vsock forwarder accept failed");
+                    continue;
+                }
+            };
+            tokio::spawn(async move {
+                if let Err(err) = forward(&mut tcp, cid, port).await {
+                    warn!(%err, cid, port, "vsock forwarding failed");
+                }
+            });
+        }
+    });
+    Ok(addr)
+}
+
+async fn forward(tcp: &mut tokio::net::TcpStream, cid: u32, port: u32) -> Result<()> {
+    let vsock = tokio::task::spawn_blocking(move || connect_vsock(cid, port))
+        .await?
+        .context("vsock connect")?;
+    let mut vsock = tokio::net::UnixStream::from_std(vsock).context("register vsock stream")?;
+    tokio::io::copy_bidirectional(tcp, &mut vsock).await?;
+    Ok(())
+}
+
+// Connect an AF_VSOCK stream socket. The fd is wrapped as a UnixStream purely for its
+// read/write/poll plumbing; address accessors on it are meaningless.
+#[cfg(target_os = "linux")]
+fn connect_vsock(cid: u32, port: u32) -> std::io::Result<std::os::unix::net::UnixStream> {
+    use std::os::fd::FromRawFd;
+
+    // SAFETY: plain socket syscalls on a freshly created fd that we own until it is either
+    // closed on error or handed to UnixStream, which takes ownership.
+    unsafe {
+        let fd = libc::socket(libc::AF_VSOCK, libc::SOCK_STREAM | libc::SOCK_CLOEXEC, 0);
+        if fd < 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut addr: libc::sockaddr_vm = std::mem::zeroed();
+        addr.svm_family = libc::AF_VSOCK as libc::sa_family_t;
+        addr.svm_cid = cid;
+        addr.svm_port = port;
+        let rc = libc::connect(
+            fd,
+            &addr as *const libc::sockaddr_vm as *const libc::sockaddr,
+            std::mem::size_of::<libc::sockaddr_vm>() as libc::socklen_t,
+        );
+        if rc < 0 {
+            let err = std::io::Error::last_os_error();
+            libc::close(fd);
+            return Err(err);
+        }
+        let stream = std::os::unix::net::UnixStream::from_raw_fd(fd);
+        stream.set_nonblocking(true)?;
+        Ok(stream)
+    }
+}
+
+#[cfg(not(target_os = "linux"))]
+fn connect_vsock(_cid: u32, _port: u32) -> std::io::Result<std::os::unix::net::UnixStream> {
+    Err(std::io::Error::new(std::io::ErrorKind::Unsupported, "vsock is only available on Linux"))
+}
+
+// "3:8000" -> (cid 3, port 8000)
+pub fn parse_vsock_addr(raw: &str) -> Option<(u32, u32)> {
+    let (cid, port) = raw.trim().split_once(':')?;
+    Some((cid.parse().ok()?, port.parse().ok()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_vsock_addr() {
+        assert_eq!(parse_vsock_addr("3:8000"), Some((3, 8000)));
+        assert_eq!(parse_vsock_addr(" 3:8000 "), Some((3, 8000)));
+        assert_eq!(parse_vsock_addr("3"), None);
+        assert_eq!(parse_vsock_addr("x:1"), None);
+    }
+}
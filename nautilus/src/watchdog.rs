@@ -0,0 +1,239 @@
+use anyhow::{Context, Result};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use ed25519_dalek::{Keypair, Signer};
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::io::Write;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tracing::{info, warn};
+
+use crate::key_usage::KeyUsageMonitor;
+use crate::tee_attestation::key_id;
+
+// Resource watchdog. Third-party parsers (image codecs, Parquet) occasionally leak, and a
+// long-running enclave only gets bigger. Past NAUTILUS_WATCHDOG_MAX_RSS_BYTES of resident memory
+// or NAUTILUS_WATCHDOG_MAX_FDS open descriptors the instance drains: new verifications are
+// refused as retryable, /health reports 503 so traffic moves elsewhere, and running jobs get up
+// to NAUTILUS_WATCHDOG_DRAIN_SECS to finish. It then appends a signed shutdown record to
+// shutdown-records.jsonl in the sealed dir, logs it, and exits with RESTART_EXIT_CODE for the
+// supervisor to restart it. The next boot logs the last record, so every restart is accounted for.
+
+// EX_TEMPFAIL; supervisors should restart on it.
+pub const RESTART_EXIT_CODE: i32 = 75;
+const FORMAT: &str = "nautilus-shutdown-v1";
+const RECORDS_FILE: &str = "shutdown-records.jsonl";
+
+#[derive(Debug, Clone)]
+pub struct WatchdogConfig {
+    // Zero disables the limit.
+    pub max_rss_bytes: u64,
+    pub max_fds: u64,
+    pub interval: Duration,
+    pub drain_timeout: Duration,
+}
+
+impl WatchdogConfig {
+    pub fn from_env() -> Self {
+        let parse = |key: &str, default: u64| env::var(key).ok().and_then(|v| v.parse().ok()).unwrap_or(default);
+        Self {
+            max_rss_bytes: parse("NAUTILUS_WATCHDOG_MAX_RSS_BYTES", 0),
+            max_fds: parse("NAUTILUS_WATCHDOG_MAX_FDS", 0),
+            interval: Duration::from_secs(parse("NAUTILUS_WATCHDOG_INTERVAL_SECS", 15).max(1)),
+            drain_timeout: Duration::from_secs(parse("NAUTILUS_WATCHDOG_DRAIN_SECS", 120)),
+        }
+    }
+
+    pub fn enabled(&self) -> bool {
+        self.max_rss_bytes > 0 || self.max_fds > 0
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error("QUALITY_OVERLOADED: instance is draining for a restart, retry later")]
+pub struct Draining;
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub struct ProcessUsage {
+    pub rss_bytes: u64,
+    pub fds: u64,
+}
+
+// Resident set and open descriptors of this process, from procfs; zero where unreadable.
+pub fn usage() -> ProcessUsage {
+    let rss_kb = std::fs::read_to_string("/proc/self/status").ok().and_then(|status| {
+        status.lines().find(|l| l.starts_with("VmRSS:")).and_then(|l| l.split_whitespace().nth(1)?.parse::<u64>().ok())
+    });
+    let fds = std::fs::read_dir("/proc/self/fd").map(|dir| dir.count() as u64).unwrap_or(0);
+    ProcessUsage { rss_bytes: rss_kb.unwrap_or(0) * 1024, fds }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ShutdownRecord {
+    pub format: String,
+    // "rss" or "fds": the limit that was crossed.
+    pub reason: String,
+    pub usage: ProcessUsage,
+    pub max_rss_bytes: u64,
+    pub max_fds: u64,
+    pub started_ms: u64,
+    pub shutdown_ms: u64,
+    // Jobs still running when the drain timed out.
+    pub jobs_abandoned: usize,
+    pub config_hash: String,
+}
+
+// One line of shutdown-records.jsonl. `record` is the exact JSON text that was signed.
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SignedShutdown {
+    pub record: String,
+    pub key_id: String,
+    pub public_key_b64: String,
+    pub signature_b64: String,
+}
+
+pub struct Watchdog {
+    config: WatchdogConfig,
+    draining: AtomicBool,
+    started_ms: u64,
+}
+
+impl Watchdog {
+    pub fn new(config: WatchdogConfig) -> Self {
+        Self { config, draining: AtomicBool::new(false), started_ms: now_ms() }
+    }
+
+    pub fn config(&self) -> &WatchdogConfig {
+        &self.config
+    }
+
+    pub fn draining(&self) -> bool {
+        self.draining.load(Ordering::Relaxed)
+    }
+
+    // The limit `usage` crosses, if any.
+    pub fn breach(&self, usage: &ProcessUsage) -> Option<&'static str> {
+        let over = |value: u64, max: u64| max > 0 && value > max;
+        if over(usage.rss_bytes, self.config.max_rss_bytes) {
+            Some("rss")
+        } else if over(usage.fds, self.config.max_fds) {
+            Some("fds")
+        } else {
+            None
+        }
+    }
+
+    // Stop admitting work; returns false when already draining.
+    pub fn start_drain(&self, reason: &str, usage: &ProcessUsage) -> bool {
+        let first = !self.draining.swap(true, Ordering::Relaxed);
+        if first {
+            warn!(reason, rss_bytes = usage.rss_bytes, fds = usage.fds, "Watchdog limit crossed, draining for restart");
+        }
+        first
+    }
+
+    pub fn shutdown_record(&self, reason: &str, usage: ProcessUsage, jobs_abandoned: usize, config_hash: &str) -> ShutdownRecord {
+        ShutdownRecord {
+            format: FORMAT.to_string(),
+            reason: reason.to_string(),
+            usage,
+            max_rss_bytes: self.config.max_rss_bytes,
+            max_fds: self.config.max_fds,
+            started_ms: self.started_ms,
+            shutdown_ms: now_ms(),
+            jobs_abandoned,
+            config_hash: config_hash.to_string(),
+        }
+    }
+}
+
+pub fn sign(record: &ShutdownRecord, key: &Keypair, usage: &KeyUsageMonitor) -> Result<SignedShutdown> {
+    let record = serde_json::to_string(record).context("serialize shutdown record")?;
+    let kid = key_id(&key.public);
+    usage.authorize(&kid, "shutdown")?;
+    Ok(SignedShutdown {
+        key_id: kid,
+        public_key_b64: STANDARD.encode(key.public.to_bytes()),
+        signature_b64: STANDARD.encode(key.sign(record.as_bytes()).to_bytes()),
+        record,
+    })
+}
+
+// Append to the sealed dir's record file; without a sealed dir the log line is the only copy.
+pub fn persist(sealed_dir: Option<&Path>, signed: &SignedShutdown) -> Result<()> {
+    let line = serde_json::to_string(signed)?;
+    warn!(record = %line, "Signed shutdown record");
+    let Some(dir) = sealed_dir else {
+        return Ok(());
+    };
+    std::fs::create_dir_all(dir).with_context(|| format!("create {}", dir.display()))?;
+    let path = dir.join(RECORDS_FILE);
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(&path)
+        .with_context(|| format!("open {}", path.display()))?;
+    writeln!(file, "{}", line).with_context(|| format!("append to {}", path.display()))?;
+    file.sync_all()?;
+    Ok(())
+}
+
+// The most recent shutdown record, logged at startup.
+pub fn last_shutdown(sealed_dir: Option<&Path>) -> Option<SignedShutdown> {
+    let raw = std::fs::read_to_string(sealed_dir?.join(RECORDS_FILE)).ok()?;
+    raw.lines().rev().find_map(|line| serde_json::from_str(line).ok())
+}
+
+pub fn log_last_shutdown(sealed_dir: Option<&Path>) {
+    let Some(signed) = last_shutdown(sealed_dir) else {
+        return;
+    };
+    match serde_json::from_str::<ShutdownRecord>(&signed.record) {
+        Ok(rec) => info!(
+            reason = %rec.reason,
+            rss_bytes = rec.usage.rss_bytes,
+            fds = rec.usage.fds,
+            shutdown_ms = rec.shutdown_ms,
+            key_id = %signed.key_id,
+            "Restarted after a watchdog shutdown"
+        ),
+        Err(err) => warn!(%err, "Unreadable shutdown record"),
+    }
+}
+
+fn now_ms() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::key_usage::KeyUsagePolicy;
+    use ed25519_dalek::{PublicKey, SecretKey, Signature, Verifier};
+
+    #[test]
+    fn test_breach_and_signed_record() {
+        let config = WatchdogConfig { max_rss_bytes: 0, max_fds: 3, ..WatchdogConfig::from_env() };
+        let dog = Watchdog::new(config);
+        let now = usage();
+        assert!(now.rss_bytes > 0 && now.fds > 3);
+        assert_eq!(dog.breach(&now), Some("fds"));
+        assert_eq!(dog.breach(&ProcessUsage { rss_bytes: u64::MAX, fds: 1 }), None);
+        assert!(dog.start_drain("fds", &now) && dog.draining() && !dog.start_drain("fds", &now));
+
+        let secret = SecretKey::from_bytes(&[5; 32]).unwrap();
+        let key = Keypair { public: (&secret).into(), secret };
+        let signed = sign(&dog.shutdown_record("fds", now, 1, "cfg"), &key, &KeyUsageMonitor::new(KeyUsagePolicy::from_env())).unwrap();
+        let dir = std::env::temp_dir().join(format!("nautilus-watchdog-{}", std::process::id()));
+        persist(Some(&dir), &signed).unwrap();
+        let last = last_shutdown(Some(&dir)).unwrap();
+        let public = PublicKey::from_bytes(&STANDARD.decode(&last.public_key_b64).unwrap()).unwrap();
+        let signature = Signature::from_bytes(&STANDARD.decode(&last.signature_b64).unwrap()).unwrap();
+        assert!(public.verify(last.record.as_bytes(), &signature).is_ok());
+        let rec: ShutdownRecord = serde_json::from_str(&last.record).unwrap();
+        assert_eq!((rec.reason.as_str(), rec.jobs_abandoned, rec.max_fds), ("fds", 1, 3));
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -49,9 +49,15 @@ fn parse_g2_arr(arr: &Vec<Value>) -> Result<G2Affine> {
 
 fn main() -> Result<()> {
     let args: Vec<String> = env::args().collect();
+    if args.len() == 3 && args[1] == "hash" {
+        // SHA-256 of a JSON file in the canonical form Nautilus signs (e.g. an envelope's `data`)
+        let v: Value = serde_json::from_str(&fs::read_to_string(&args[2])?)?;
+        println!("{}", nautilus_canonical::canonical_sha256_hex(&v));
+        return Ok(());
+    }
     if args.len() != 3 {
         return Err(anyhow!(
-            "Usage: sui-vktool <verification_key.json> <out.bin>"
+            "Usage: sui-vktool <verification_key.json> <out.bin>\n       sui-vktool hash <file.json>"
         ));
     }
     let json = fs::read_to_string(&args[1])?;